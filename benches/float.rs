@@ -0,0 +1,84 @@
+//! Times the OBJ float parsing against `str::parse` on a million vertex
+//! lines, run with `cargo bench --bench float`
+
+use std::time::{Duration, Instant};
+
+use threedobs::importer::float;
+
+const LINES: usize = 1_000_000;
+const ROUNDS: usize = 10;
+
+/// Best time of all rounds, the others are slowed down by whatever else
+/// the machine is doing
+fn best_of(mut run: impl FnMut() -> f32) -> (Duration, f32) {
+    let mut best = Duration::MAX;
+    let mut sum = 0.0;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        sum = std::hint::black_box(run());
+        best = best.min(start.elapsed());
+    }
+    (best, sum)
+}
+
+fn main() {
+    // xorshift, so every run parses the same lines
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut coordinate = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % 200_000_000) as f64 / 1e6 - 100.0
+    };
+    let lines: Vec<String> = (0..LINES)
+        .map(|_| {
+            let [x, y, z] = [coordinate(), coordinate(), coordinate()];
+            format!("{:.6} {:.6} {:.6}", x, y, z)
+        })
+        .collect();
+
+    let (std_time, std_sum) = best_of(|| {
+        lines
+            .iter()
+            .map(|line| {
+                let mut words = line.split_ascii_whitespace();
+                let mut floats = [0.0f32; 3];
+                for float in &mut floats {
+                    *float = words.next().unwrap().parse().unwrap();
+                }
+                floats.iter().sum::<f32>()
+            })
+            .sum()
+    });
+    let (words_time, words_sum) = best_of(|| {
+        lines
+            .iter()
+            .map(|line| {
+                let floats: [f32; 3] = float::parse_floats(line.split_ascii_whitespace()).unwrap();
+                floats.iter().sum::<f32>()
+            })
+            .sum()
+    });
+    let (leading_time, leading_sum) = best_of(|| {
+        lines
+            .iter()
+            .map(|line| float::parse_leading::<3>(line).unwrap().iter().sum::<f32>())
+            .sum()
+    });
+    assert_eq!(std_sum.to_bits(), words_sum.to_bits());
+    assert_eq!(std_sum.to_bits(), leading_sum.to_bits());
+
+    let speedup = |time: Duration| std_time.as_secs_f64() / time.as_secs_f64();
+    println!("{} lines of 3 floats, best of {} rounds", LINES, ROUNDS);
+    println!("str::parse          {:?}", std_time);
+    println!(
+        "parse_floats        {:?} ({:.2}x)",
+        words_time,
+        speedup(words_time)
+    );
+    println!(
+        "parse_leading       {:?} ({:.2}x)",
+        leading_time,
+        speedup(leading_time)
+    );
+}
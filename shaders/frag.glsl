@@ -2,14 +2,30 @@
 in vec3 fragNormals;
 in vec3 fragPos;
 in vec2 texCoords;
+in vec3 vertexColor;
 
 out vec4 FragColor;
 
+// same as importer::TextureOptions
+struct TextureOptions {
+  vec2 offset;
+  vec2 scale;
+  bool clampToEdge;
+  float base;
+  float gain;
+  bool blendU;
+  bool blendV;
+};
+
 struct Material {
   sampler2D texture_diffuse;
   sampler2D texture_specular;
   sampler2D texture_ambient;
   sampler2D texture_emission;
+  TextureOptions diffuse_options;
+  TextureOptions specular_options;
+  TextureOptions ambient_options;
+  TextureOptions emission_options;
 
   vec3 ambient;
   vec3 diffuse;
@@ -56,6 +72,11 @@ struct SpotLight {
 #define NR_POINT_LIGHTS 4
 #define blinn true
 
+// same as ui::DisplayMode
+#define DISPLAY_SHADED 0
+#define DISPLAY_DEPTH 1
+#define DISPLAY_NORMALS 2
+
 uniform vec3 viewPos;
 uniform Material material;
 uniform DirLight dirLight;
@@ -63,6 +84,52 @@ uniform PointLight pointLights[NR_POINT_LIGHTS];
 uniform SpotLight spotLight;
 uniform bool useTextures;
 uniform bool hasEmissionTexture;
+uniform int displayMode;
+uniform bool highlighted;
+uniform float near;
+uniform float far;
+uniform bool reversedZ;
+uniform bool sliceEnabled;
+uniform float sliceHeight;
+// the inside of a sliced model, seen through the cut
+uniform vec3 sectionColor;
+// drawn see-through, for the pose a model had before it was transformed
+uniform bool ghost;
+// the material's colors without any lighting
+uniform bool unlit;
+
+const float GHOST_ALPHA = 0.3;
+
+// distance from the camera of a depth buffer value
+float LinearizeDepth(float depth) {
+  if (reversedZ) {
+    return (near * far) / (depth * (far - near) + near);
+  }
+  float ndc = depth * 2.0 - 1.0;
+  return (2.0 * near * far) / (far + near - ndc * (far - near));
+}
+
+// samples a texture with the options from its MTL statement. The texture
+// coordinates are stored with V flipped, so it's flipped back around them.
+vec4 SampleTexture(sampler2D tex, TextureOptions options) {
+  vec2 uv = vec2(texCoords.x, 1.0 - texCoords.y) * options.scale + options.offset;
+  if (options.clampToEdge) {
+    uv = clamp(uv, 0.0, 1.0);
+  }
+  uv.y = 1.0 - uv.y;
+
+  // sampling the middle of a texel doesn't blend it with its neighbors
+  vec2 size = vec2(textureSize(tex, 0));
+  if (!options.blendU) {
+    uv.x = (floor(uv.x * size.x) + 0.5) / size.x;
+  }
+  if (!options.blendV) {
+    uv.y = (floor(uv.y * size.y) + 0.5) / size.y;
+  }
+
+  vec4 color = texture(tex, uv);
+  return vec4(options.base + options.gain * color.rgb, color.a);
+}
 
 vec3 CalculateDirLight(DirLight light, vec3 normal, vec3 viewDir) {
   vec3 lightDir = normalize(-light.direction);
@@ -83,9 +150,9 @@ vec3 CalculateDirLight(DirLight light, vec3 normal, vec3 viewDir) {
   vec3 specular = vec3(0.0, 0.0, 0.0);
 
   if (useTextures) {
-    ambient = light.ambient * vec3(texture(material.texture_ambient, texCoords)) * material.ambient;
-    diffuse = light.diffuse * diff * vec3(texture(material.texture_diffuse, texCoords)) * material.diffuse;
-    specular = light.specular * spec * vec3(texture(material.texture_specular, texCoords)) * material.specular;
+    ambient = light.ambient * vec3(SampleTexture(material.texture_ambient, material.ambient_options)) * material.ambient;
+    diffuse = light.diffuse * diff * vec3(SampleTexture(material.texture_diffuse, material.diffuse_options)) * material.diffuse;
+    specular = light.specular * spec * vec3(SampleTexture(material.texture_specular, material.specular_options)) * material.specular;
   } else {
     ambient = light.ambient * material.ambient;
     diffuse = light.diffuse * (diff * material.diffuse);
@@ -118,9 +185,9 @@ vec3 CalculatePointLight(PointLight light, vec3 normal, vec3 fragPos, vec3 viewD
   vec3 specular = vec3(0.0, 0.0, 0.0);
 
   if (useTextures) {
-    ambient = light.ambient * vec3(texture(material.texture_ambient, texCoords)) * material.ambient;
-    diffuse = light.diffuse * diff * vec3(texture(material.texture_diffuse, texCoords)) * material.diffuse;
-    specular = light.specular * spec * vec3(texture(material.texture_specular, texCoords)) * material.specular;
+    ambient = light.ambient * vec3(SampleTexture(material.texture_ambient, material.ambient_options)) * material.ambient;
+    diffuse = light.diffuse * diff * vec3(SampleTexture(material.texture_diffuse, material.diffuse_options)) * material.diffuse;
+    specular = light.specular * spec * vec3(SampleTexture(material.texture_specular, material.specular_options)) * material.specular;
   } else {
     ambient = light.ambient * material.ambient;
     diffuse = light.diffuse * (diff * material.diffuse);
@@ -162,9 +229,9 @@ vec3 CalculateSpotLight(SpotLight light, vec3 normal, vec3 fragPos, vec3 viewDir
   vec3 specular = vec3(0.0, 0.0, 0.0);
 
   if (useTextures) {
-    ambient = light.ambient * vec3(texture(material.texture_ambient, texCoords)) * material.ambient;
-    diffuse = light.diffuse * diff * vec3(texture(material.texture_diffuse, texCoords)) * material.diffuse;
-    specular = light.specular * spec * vec3(texture(material.texture_specular, texCoords)) * material.specular;
+    ambient = light.ambient * vec3(SampleTexture(material.texture_ambient, material.ambient_options)) * material.ambient;
+    diffuse = light.diffuse * diff * vec3(SampleTexture(material.texture_diffuse, material.diffuse_options)) * material.diffuse;
+    specular = light.specular * spec * vec3(SampleTexture(material.texture_specular, material.specular_options)) * material.specular;
   } else {
     ambient = light.ambient * material.ambient;
     diffuse = light.diffuse * (diff * material.diffuse);
@@ -185,27 +252,70 @@ void main()
 {
   // discard completely transparent fragments, such as the ones used in
   // leaves or grass textures
-  if (texture(material.texture_diffuse, texCoords).a == 0.0) {
+  if (SampleTexture(material.texture_diffuse, material.diffuse_options).a == 0.0) {
     discard;
   }
 
-  vec3 norm = normalize(fragNormals);
-  vec3 viewDir = normalize(viewPos - fragPos);
+  // everything above the slicing plane is cut away, the back faces showing
+  // through the cut fill in the cross-section
+  if (sliceEnabled) {
+    if (fragPos.y > sliceHeight) {
+      discard;
+    }
+    if (!gl_FrontFacing) {
+      FragColor = vec4(sectionColor, 1.0);
+      return;
+    }
+  }
+
+  if (displayMode == DISPLAY_DEPTH) {
+    FragColor = vec4(vec3(LinearizeDepth(gl_FragCoord.z) / far), 1.0);
+    return;
+  }
+  if (displayMode == DISPLAY_NORMALS) {
+    // the face's normal from how the position changes between neighboring
+    // fragments, which is what a normal buffer rebuilt from depth would have
+    vec3 faceNormal = normalize(cross(dFdx(fragPos), dFdy(fragPos)));
+    FragColor = vec4(faceNormal * 0.5 + 0.5, 1.0);
+    return;
+  }
 
-  // direction light
-  vec3 result = CalculateDirLight(dirLight, norm, viewDir);
+  vec3 result;
+  if (unlit) {
+    result = material.diffuse;
+    if (useTextures) {
+      result *= SampleTexture(material.texture_diffuse, material.diffuse_options).rgb;
+    }
+  } else {
+    vec3 norm = normalize(fragNormals);
+    vec3 viewDir = normalize(viewPos - fragPos);
 
-  // point lights
-  for (int i = 0; i < NR_POINT_LIGHTS; i++) {
-    result += CalculatePointLight(pointLights[i], norm, fragPos, viewDir);
+    // direction light
+    result = CalculateDirLight(dirLight, norm, viewDir);
+
+    // point lights
+    for (int i = 0; i < NR_POINT_LIGHTS; i++) {
+      result += CalculatePointLight(pointLights[i], norm, fragPos, viewDir);
+    }
+
+    result += CalculateSpotLight(spotLight, norm, fragPos, viewDir);
   }
 
-  result += CalculateSpotLight(spotLight, norm, fragPos, viewDir);
+  // per-vertex or per-face colors from the file, white for everything else
+  result *= vertexColor;
 
   if (useTextures && hasEmissionTexture) {
-    result += texture(material.texture_emission, texCoords).rgb;
+    result += SampleTexture(material.texture_emission, material.emission_options).rgb;
+  }
+
+  // meshes with the material picked in the Materials window
+  if (highlighted) {
+    result = mix(result, vec3(1.0, 0.3, 1.0), 0.5);
   }
 
   FragColor = vec4(result, material.opacity);
+  if (ghost) {
+    FragColor.a *= GHOST_ALPHA;
+  }
 }
 
@@ -8,6 +8,7 @@ uniform mat4 projection;
 uniform mat4 view;
 uniform float near;
 uniform float far;
+uniform bool reversedZ;
 
 vec4 grid(vec3 fragPos3D, float scale, float lineWidth, float lineOpacity) {
     vec2 coord = fragPos3D.xz * scale; // use the scale variable to set the distance between the lines
@@ -33,6 +34,11 @@ float computeDepth(vec3 pos) {
 
 float computeLinearDepth(vec3 pos) {
     vec4 clip_space_pos = projection * view * vec4(pos.xyz, 1.0);
+    if (reversedZ) {
+        // the depth goes from 1 at the near plane to 0 at the far plane
+        float depth = clip_space_pos.z / clip_space_pos.w;
+        return (near * far) / (depth * (far - near) + near) / far;
+    }
     float clip_space_depth = (clip_space_pos.z / clip_space_pos.w) * 2.0 - 1.0; // put back between -1 and 1
     float linearDepth = (2.0 * near * far) / (far + near - clip_space_depth * (far - near)); // get linear value between 0.01 and 100
     return linearDepth / far; // normalize
@@ -46,8 +52,13 @@ void main()
     // above depth calculation is buggy
     // see https://stackoverflow.com/questions/72791713/issues-with-infinite-grid-in-opengl-4-5-with-glsl
     // for solution
-    gl_FragDepth = ((gl_DepthRange.diff * computeDepth(fragPos3D)) +
-                gl_DepthRange.near + gl_DepthRange.far) / 2.0;
+    if (reversedZ) {
+        // clip control already maps the depth to 0 to 1
+        gl_FragDepth = computeDepth(fragPos3D);
+    } else {
+        gl_FragDepth = ((gl_DepthRange.diff * computeDepth(fragPos3D)) +
+                    gl_DepthRange.near + gl_DepthRange.far) / 2.0;
+    }
 
     float linearDepth = computeLinearDepth(fragPos3D);
     float fading = max(0, (0.5 - linearDepth));
@@ -4,6 +4,7 @@ out vec3 farPoint;
 
 uniform mat4 projection;
 uniform mat4 view;
+uniform bool reversedZ;
 
 // Grid position are in xy clipped space
 vec3 gridPlane[6] = vec3[](
@@ -20,8 +21,10 @@ vec3 UnprojectPoint(float x, float y, float z, mat4 v, mat4 proj) {
 
 void main() {
     vec3 p = gridPlane[gl_VertexID].xyz;
-    nearPoint = UnprojectPoint(p.x, p.y, 0.0, view, projection).xyz; // unprojecting on the near plane
-    farPoint = UnprojectPoint(p.x, p.y, 1.0, view, projection).xyz; // unprojecting on the far plane
+    // reversed-Z has the near plane at a depth of 1 and the far plane at 0
+    float nearDepth = reversedZ ? 1.0 : 0.0;
+    nearPoint = UnprojectPoint(p.x, p.y, nearDepth, view, projection).xyz; // unprojecting on the near plane
+    farPoint = UnprojectPoint(p.x, p.y, 1.0 - nearDepth, view, projection).xyz; // unprojecting on the far plane
     gl_Position = vec4(p, 1.0); // using directly the clipped coordinates
 }
 
@@ -2,9 +2,11 @@
 layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec3 aNormal;
 layout (location = 2) in vec2 aTexCoords;
+layout (location = 3) in vec3 aColor;
 out vec3 fragNormals;
 out vec3 fragPos;
 out vec2 texCoords;
+out vec3 vertexColor;
 
 uniform mat4 model;
 uniform mat4 view;
@@ -21,5 +23,6 @@ void main()
     fragNormals = mat3(transpose(inverse(model))) * aNormal;
   fragPos = vec3(model * vec4(aPos, 1.0));
   texCoords = aTexCoords;
+  vertexColor = aColor;
 }
 
@@ -1,177 +1,66 @@
-use glad_gl::gl;
-
-use crate::{mesh::Vertex, shader::Shader};
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct AABB {
     pub min: glm::Vec3,
     pub max: glm::Vec3,
-    indices_len: u32,
-    vao: u32,
-    vbo: u32,
-    ebo: u32,
 }
 
 impl AABB {
     pub fn new(min: glm::Vec3, max: glm::Vec3) -> AABB {
-        let mut vao = 0;
-        let mut vbo = 0;
-        let mut ebo = 0;
-
-        let vertices = vec![
-            Vertex {
-                position: glm::vec3(min.x, min.y, min.z),
-                tex_coords: glm::vec2(0.0, 0.0),
-                normal: glm::vec3(0.0, 0.0, 0.0),
-            },
-            Vertex {
-                position: glm::vec3(max.x, min.y, min.z),
-                tex_coords: glm::vec2(1.0, 0.0),
-                normal: glm::vec3(0.0, 0.0, 0.0),
-            },
-            Vertex {
-                position: glm::vec3(max.x, max.y, min.z),
-                tex_coords: glm::vec2(1.0, 1.0),
-                normal: glm::vec3(0.0, 0.0, 0.0),
-            },
-            Vertex {
-                position: glm::vec3(min.x, max.y, min.z),
-                tex_coords: glm::vec2(0.0, 1.0),
-                normal: glm::vec3(0.0, 0.0, 0.0),
-            },
-            Vertex {
-                position: glm::vec3(min.x, min.y, max.z),
-                tex_coords: glm::vec2(0.0, 0.0),
-                normal: glm::vec3(0.0, 0.0, 0.0),
-            },
-            Vertex {
-                position: glm::vec3(max.x, min.y, max.z),
-                tex_coords: glm::vec2(1.0, 0.0),
-                normal: glm::vec3(0.0, 0.0, 0.0),
-            },
-            Vertex {
-                position: glm::vec3(max.x, max.y, max.z),
-                tex_coords: glm::vec2(1.0, 1.0),
-                normal: glm::vec3(0.0, 0.0, 0.0),
-            },
-            Vertex {
-                position: glm::vec3(min.x, max.y, max.z),
-                tex_coords: glm::vec2(0.0, 1.0),
-                normal: glm::vec3(0.0, 0.0, 0.0),
-            },
-        ];
-
-        let indices = [
-            0, 1, 2, 2, 3, 0, // front
-            1, 5, 6, 6, 2, 1, // right
-            5, 4, 7, 7, 6, 5, // back
-            4, 0, 3, 3, 7, 4, // left
-            3, 2, 6, 6, 7, 3, // top
-            4, 5, 1, 1, 0, 4, // bottom
-        ];
-
-        unsafe {
-            gl::GenVertexArrays(1, &mut vao);
-            gl::GenBuffers(1, &mut vbo);
-            gl::GenBuffers(1, &mut ebo);
-
-            gl::BindVertexArray(vao);
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (std::mem::size_of::<Vertex>() * vertices.len() as usize) as isize,
-                vertices.as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW,
-            );
-
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (std::mem::size_of::<u32>() * indices.len()) as isize,
-                indices.as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW,
-            );
-
-            // vertex positions
-            gl::EnableVertexAttribArray(0);
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                std::mem::size_of::<Vertex>() as i32,
-                std::ptr::null(),
-            );
-
-            // vertex normals
-            gl::EnableVertexAttribArray(1);
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                std::mem::size_of::<Vertex>() as i32,
-                (3 * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
-            );
-
-            // vertex texture coords
-            gl::EnableVertexAttribArray(2);
-            gl::VertexAttribPointer(
-                2,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                std::mem::size_of::<Vertex>() as i32,
-                (6 * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
-            );
-
-            gl::BindVertexArray(0);
-        }
-
-        AABB {
-            min,
-            max,
-            indices_len: indices.len() as u32,
-            vao,
-            vbo,
-            ebo,
-        }
+        AABB { min, max }
     }
 
-    pub fn draw(&self, shader: &Shader, model_mat: &glm::Mat4) {
-        shader.use_shader();
+    /// The 4 corners of the front face followed by the ones of the back face,
+    /// both counter-clockwise
+    pub fn corners(&self) -> [glm::Vec3; 8] {
+        let (min, max) = (self.min, self.max);
+
+        [
+            glm::vec3(min.x, min.y, min.z),
+            glm::vec3(max.x, min.y, min.z),
+            glm::vec3(max.x, max.y, min.z),
+            glm::vec3(min.x, max.y, min.z),
+            glm::vec3(min.x, min.y, max.z),
+            glm::vec3(max.x, min.y, max.z),
+            glm::vec3(max.x, max.y, max.z),
+            glm::vec3(min.x, max.y, max.z),
+        ]
+    }
 
-        shader.set_mat4fv("model", model_mat);
-        shader.set_3fv("material.ambient", glm::vec3(1.0, 0.627, 0.157));
-        shader.set_3fv("material.diffuse", glm::vec3(1.0, 0.627, 0.157));
+    pub fn center(&self) -> glm::Vec3 {
+        (self.min + self.max) * 0.5
+    }
 
-        unsafe {
-            // draw Mesh
-            gl::BindVertexArray(self.vao);
-            gl::LineWidth(5.0);
-            gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
-            gl::DrawElements(
-                gl::TRIANGLES,
-                self.indices_len as i32,
-                gl::UNSIGNED_INT,
-                std::ptr::null(),
-            );
+    /// The box around both boxes
+    pub fn union(&self, other: &AABB) -> AABB {
+        AABB::new(glm::min(self.min, other.min), glm::max(self.max, other.max))
+    }
 
-            // reset stuff to default
-            gl::BindVertexArray(0);
-            gl::LineWidth(1.0);
+    /// The axis aligned box around this one after `transform`, which is
+    /// bigger than the box itself when it's rotated
+    pub fn transformed(&self, transform: &glm::Mat4) -> AABB {
+        let mut min = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
+        for corner in self.corners() {
+            let p = *transform * glm::vec4(corner.x, corner.y, corner.z, 1.0);
+            let p = glm::vec3(p.x / p.w, p.y / p.w, p.z / p.w);
+            min = glm::min(min, p);
+            max = glm::max(max, p);
         }
+
+        AABB::new(min, max)
     }
-}
 
-impl Drop for AABB {
-    fn drop(&mut self) {
-        unsafe {
-            gl::BindVertexArray(0);
-            gl::DeleteBuffers(1, &self.vbo);
-            gl::DeleteBuffers(1, &self.ebo);
-            gl::DeleteVertexArrays(1, &self.vao);
-        }
+    /// Whether the box is entirely off one side of the view, `view_projection`
+    /// taking it from its coordinates into clip space. Only the sides are
+    /// checked, the near and far planes move with the models.
+    pub fn outside_view(&self, view_projection: &glm::Mat4) -> bool {
+        let clip = self
+            .corners()
+            .map(|c| *view_projection * glm::vec4(c.x, c.y, c.z, 1.0));
+
+        clip.iter().all(|p| p.x > p.w)
+            || clip.iter().all(|p| p.x < -p.w)
+            || clip.iter().all(|p| p.y > p.w)
+            || clip.iter().all(|p| p.y < -p.w)
     }
 }
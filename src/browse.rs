@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    importer::{registry, ImportJob},
+    ui::ui::Settings,
+};
+
+/// Steps through the models of a folder one at a time. The files on either
+/// side of the current one are loaded in the background ahead of time, so
+/// switching to them doesn't wait on the importer.
+pub struct FolderBrowser {
+    files: Vec<PathBuf>,
+    current: usize,
+    /// Imports of the neighbours of the current file, handed over once they're
+    /// stepped onto
+    preloads: Vec<ImportJob>,
+}
+
+impl FolderBrowser {
+    /// Starts at `path`. None if its folder can't be read. The files are
+    /// imported with the options `settings` has for them.
+    pub fn new(path: &Path, settings: &Settings) -> Option<Self> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(path.parent()?)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|file| file.is_file() && registry::is_supported(file))
+            .collect();
+        files.sort();
+        let current = files
+            .iter()
+            .position(|file| file.file_name() == path.file_name())?;
+
+        let mut browser = FolderBrowser {
+            files,
+            current,
+            preloads: Vec::new(),
+        };
+        browser.preload(settings);
+        Some(browser)
+    }
+
+    pub fn current(&self) -> &Path {
+        &self.files[self.current]
+    }
+
+    /// 1-based position of the current file and how many there are
+    pub fn position(&self) -> (usize, usize) {
+        (self.current + 1, self.files.len())
+    }
+
+    /// Moves `step` files forward or back, wrapping around at the ends, and
+    /// returns the import of the file it lands on
+    pub fn step(&mut self, step: isize, settings: &Settings) -> ImportJob {
+        let len = self.files.len() as isize;
+        self.current = (self.current as isize + step).rem_euclid(len) as usize;
+
+        let path = self.files[self.current].clone();
+        let job = match self.preloads.iter().position(|job| job.path == path) {
+            Some(i) => self.preloads.swap_remove(i),
+            None => {
+                let options = settings.import_options_for(&path);
+                ImportJob::spawn(path, true, options)
+            }
+        };
+        self.preload(settings);
+        job
+    }
+
+    /// Starts loading the neighbours of the current file, dropping the loads
+    /// of files that aren't next to it anymore
+    fn preload(&mut self, settings: &Settings) {
+        let len = self.files.len();
+        let neighbours: Vec<PathBuf> = [len - 1, 1]
+            .iter()
+            .map(|offset| (self.current + offset) % len)
+            .filter(|i| *i != self.current)
+            .map(|i| self.files[i].clone())
+            .collect();
+
+        self.preloads.retain(|job| neighbours.contains(&job.path));
+        for path in neighbours {
+            if !self.preloads.iter().any(|job| job.path == path) {
+                let options = settings.import_options_for(&path);
+                self.preloads.push(ImportJob::spawn(path, true, options));
+            }
+        }
+    }
+}
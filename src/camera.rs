@@ -2,6 +2,17 @@ use glm;
 
 use crate::model;
 
+pub const NEAR_PLANE: f32 = 0.01;
+pub const DEFAULT_FAR_PLANE: f32 = 200.0;
+/// Smallest near/far ratio with automatic clip planes before a 24-bit depth
+/// buffer runs out of precision at the far end
+const MIN_NEAR_RATIO: f32 = 1e-4;
+/// Reversed-Z spreads the precision of a float depth buffer evenly, so the
+/// near plane can get much closer
+const MIN_NEAR_RATIO_REVERSED_Z: f32 = 1e-6;
+/// Room left around a model that's fit into view
+const FIT_MARGIN: f32 = 1.1;
+
 pub struct Camera {
     pub position: glm::Vec3,
     pub front: glm::Vec3,
@@ -12,6 +23,8 @@ pub struct Camera {
     _speed: f32,
     pub sensitivity: f32,
     pub fov: f32,
+    pub near: f32,
+    pub far: f32,
 }
 
 impl Default for Camera {
@@ -26,6 +39,8 @@ impl Default for Camera {
             speed: 10.0,
             sensitivity: 0.05,
             fov: 45.0,
+            near: NEAR_PLANE,
+            far: DEFAULT_FAR_PLANE,
         }
     }
 }
@@ -57,29 +72,80 @@ impl Camera {
         self.position = self.position + glm::vec3(new_x, new_y, 0.0);
     }
 
+    /// Fits the clip planes tightly around a bounding sphere of the scene, so
+    /// huge models neither get clipped nor z-fight. The far plane doesn't come
+    /// closer than the default one so the grid stays visible.
+    pub fn fit_clip_planes(&mut self, center: glm::Vec3, radius: f32, reversed_z: bool) {
+        let distance = glm::length(center - self.position);
+        let min_ratio = if reversed_z {
+            MIN_NEAR_RATIO_REVERSED_Z
+        } else {
+            MIN_NEAR_RATIO
+        };
+
+        self.far = ((distance + radius) * 1.1).max(DEFAULT_FAR_PLANE);
+        // the camera might be inside the scene, in which case the ratio is all
+        // that's stopping the near plane from reaching 0
+        self.near = ((distance - radius) * 0.9).max(self.far * min_ratio);
+    }
+
+    pub fn projection(&self, aspect: f32, reversed_z: bool) -> glm::Mat4 {
+        if !reversed_z {
+            return glm::ext::perspective(glm::radians(self.fov), aspect, self.near, self.far);
+        }
+
+        // maps the near plane to a depth of 1 and the far plane to 0, expects
+        // glClipControl to be set to a 0 to 1 depth range
+        let f = 1.0 / (glm::radians(self.fov) / 2.0).tan();
+        let range = self.far - self.near;
+        glm::Mat4::new(
+            glm::vec4(f / aspect, 0.0, 0.0, 0.0),
+            glm::vec4(0.0, f, 0.0, 0.0),
+            glm::vec4(0.0, 0.0, self.near / range, -1.0),
+            glm::vec4(0.0, 0.0, self.far * self.near / range, 0.0),
+        )
+    }
+
     pub fn update_speed(&mut self, delta_time: f32) {
         self._speed = self.speed * delta_time;
     }
 
+    /// `aspect` is the viewport's width over its height
     pub fn focus_on_selected_model(
         &mut self,
         active_model: Option<u32>,
-        objects: &Vec<model::Model>,
+        objects: &[model::Model],
+        aspect: f32,
     ) {
-        if let Some(id) = active_model {
-            for obj in objects {
-                if obj.id == id {
-                    // we scale the center of the object since the model (and therefore the AABB) is scaled
-                    let center_x =
-                        ((obj.aabb.max.x / 2.0) + (obj.aabb.min.x / 2.0)) * obj.scaling_factor;
-                    let center_y =
-                        ((obj.aabb.max.y / 2.0) + (obj.aabb.min.y / 2.0)) * obj.scaling_factor;
-                    let z = obj.aabb.max.z * obj.scaling_factor + 10.0;
-                    self.position = glm::vec3(center_x, center_y, z);
-                    self.front = glm::vec3(0.0, 0.0, -1.0);
-                    break;
-                }
-            }
+        if let Some(obj) = active_model.and_then(|id| objects.iter().find(|m| m.id == id)) {
+            self.fit_to_model(obj, aspect);
         }
     }
+
+    /// Moves the camera in front of the model, far enough back that its AABB
+    /// fits in view both vertically and horizontally. Tall models are framed
+    /// by their height and wide ones by their width, depending on the
+    /// viewport's `aspect`. The far plane is pushed back if the model is too
+    /// big for the default one.
+    pub fn fit_to_model(&mut self, model: &model::Model, aspect: f32) {
+        let aabb = model.world_aabb();
+        let center = aabb.center();
+        let half_size = (aabb.max - aabb.min) / 2.0;
+        let aspect = if aspect.is_finite() && aspect > 0.0 {
+            aspect
+        } else {
+            1.0
+        };
+
+        // the front face of the AABB is the closest to the camera, so it's the
+        // one that has to fit
+        let tan_y = (self.fov.to_radians() / 2.0).tan();
+        let tan_x = tan_y * aspect;
+        let distance = (half_size.x / tan_x).max(half_size.y / tan_y) * FIT_MARGIN;
+        let z = center.z + half_size.z + distance;
+
+        self.position = glm::vec3(center.x, center.y, z);
+        self.front = glm::vec3(0.0, 0.0, -1.0);
+        self.far = ((distance + half_size.z * 2.0) * 2.0).max(DEFAULT_FAR_PLANE);
+    }
 }
@@ -0,0 +1,243 @@
+use std::path::PathBuf;
+
+use log::{error, info};
+
+use crate::{camera::Camera, ui::ui::State};
+
+pub const DEFAULT_FPS: u32 = 30;
+/// Number of segments the path preview is drawn with
+const PREVIEW_SEGMENTS: usize = 200;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    /// Seconds from the start of the path
+    pub time: f32,
+    pub position: glm::Vec3,
+    pub front: glm::Vec3,
+    pub fov: f32,
+}
+
+/// Frames of the path being saved to a directory, one per application frame
+#[derive(Debug)]
+pub struct Export {
+    pub dir: PathBuf,
+    pub frame: u32,
+    pub frame_count: u32,
+    /// Encode the frames into a video with ffmpeg once they're saved
+    pub video: bool,
+}
+
+/// Keyframes the camera flies through, sorted by time
+#[derive(Debug)]
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,
+    /// Time of the path the camera is at when previewing or scrubbing
+    pub playhead: f32,
+    pub playing: bool,
+    pub fps: u32,
+    pub export: Option<Export>,
+}
+
+impl Default for CameraPath {
+    fn default() -> Self {
+        Self {
+            keyframes: vec![],
+            playhead: 0.0,
+            playing: false,
+            fps: DEFAULT_FPS,
+            export: None,
+        }
+    }
+}
+
+/// Catmull-Rom interpolation between `p1` and `p2`, `t` goes from 0 to 1
+fn catmull_rom(p0: glm::Vec3, p1: glm::Vec3, p2: glm::Vec3, p3: glm::Vec3, t: f32) -> glm::Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+impl CameraPath {
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    pub fn sort(&mut self) {
+        self.keyframes
+            .sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+    }
+
+    /// Adds a keyframe at the playhead with the camera's current view,
+    /// replacing the one that's already there
+    pub fn add_keyframe(&mut self, camera: &Camera) {
+        let keyframe = Keyframe {
+            time: self.playhead,
+            position: camera.position,
+            front: camera.front,
+            fov: camera.fov,
+        };
+
+        match self
+            .keyframes
+            .iter_mut()
+            .find(|k| (k.time - keyframe.time).abs() < 0.001)
+        {
+            Some(existing) => *existing = keyframe,
+            None => {
+                self.keyframes.push(keyframe);
+                self.sort();
+            }
+        }
+    }
+
+    /// The camera at `time`, smoothly interpolated between the keyframes
+    pub fn sample(&self, time: f32) -> Option<Keyframe> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        if time <= first.time {
+            return Some(*first);
+        }
+        if time >= last.time {
+            return Some(*last);
+        }
+
+        let i = self.keyframes.iter().rposition(|k| k.time <= time)?;
+        let k1 = self.keyframes[i];
+        let k2 = self.keyframes[i + 1];
+        let k0 = self.keyframes[i.saturating_sub(1)];
+        let k3 = self.keyframes[(i + 2).min(self.keyframes.len() - 1)];
+        let t = (time - k1.time) / (k2.time - k1.time).max(f32::EPSILON);
+
+        let front = catmull_rom(k0.front, k1.front, k2.front, k3.front, t);
+        Some(Keyframe {
+            time,
+            position: catmull_rom(k0.position, k1.position, k2.position, k3.position, t),
+            front: if glm::length(front) > 0.0 {
+                glm::normalize(front)
+            } else {
+                k1.front
+            },
+            fov: k1.fov + (k2.fov - k1.fov) * t,
+        })
+    }
+
+    /// Moves the camera to where it is on the path at `time`
+    pub fn apply(&self, time: f32, camera: &mut Camera) {
+        if let Some(keyframe) = self.sample(time) {
+            camera.position = keyframe.position;
+            camera.front = keyframe.front;
+            camera.fov = keyframe.fov;
+        }
+    }
+
+    /// Points along the path for drawing a preview of it
+    pub fn preview_points(&self) -> Vec<glm::Vec3> {
+        let duration = self.duration();
+        if self.keyframes.len() < 2 {
+            return vec![];
+        }
+
+        (0..=PREVIEW_SEGMENTS)
+            .filter_map(|i| self.sample(duration * i as f32 / PREVIEW_SEGMENTS as f32))
+            .map(|k| k.position)
+            .collect()
+    }
+
+    pub fn start_export(&mut self, dir: PathBuf, video: bool) {
+        let frame_count = (self.duration() * self.fps as f32).ceil() as u32 + 1;
+        info!(
+            "Exporting {} frames of the camera path to {:?}",
+            frame_count, dir
+        );
+
+        self.playing = false;
+        self.export = Some(Export {
+            dir,
+            frame: 0,
+            frame_count,
+            video,
+        });
+    }
+}
+
+/// Moves the camera along the path while it's playing or being exported.
+/// Must be called before the frame is drawn so the capture of an exported
+/// frame shows the camera at the right spot.
+pub fn update(state: &mut State, delta_time: f32) {
+    let path = &mut state.camera_path;
+
+    if let Some(export) = &mut path.export {
+        // the last frame hasn't been captured yet
+        if state.capture_request.is_some() {
+            return;
+        }
+
+        if export.frame < export.frame_count {
+            let time = export.frame as f32 / path.fps as f32;
+            state.capture_request = Some(export.dir.join(format!("frame_{:05}.png", export.frame)));
+            export.frame += 1;
+            path.playhead = time;
+            path.apply(time, &mut state.camera);
+            return;
+        }
+
+        info!("Exported {} frames to {:?}", export.frame_count, export.dir);
+        if export.video {
+            encode_video(export.dir.clone(), path.fps);
+        }
+        path.export = None;
+        return;
+    }
+
+    if path.playing {
+        path.playhead += delta_time;
+        if path.playhead >= path.duration() {
+            path.playhead = path.duration();
+            path.playing = false;
+        }
+        path.apply(path.playhead, &mut state.camera);
+    }
+}
+
+/// Turns the exported frames into an MP4 with ffmpeg on a background thread
+fn encode_video(dir: PathBuf, fps: u32) {
+    let output = dir.join("flythrough.mp4");
+    info!("Encoding the frames into {:?}", output);
+
+    let _ = std::thread::Builder::new()
+        .name("video encoder".to_string())
+        .spawn(move || {
+            let status = std::process::Command::new("ffmpeg")
+                .args([
+                    "-y",
+                    "-loglevel",
+                    "error",
+                    "-framerate",
+                    &fps.to_string(),
+                    "-i",
+                ])
+                .arg(dir.join("frame_%05d.png"))
+                // x264 needs even dimensions
+                .args([
+                    "-vf",
+                    "scale=trunc(iw/2)*2:trunc(ih/2)*2",
+                    "-c:v",
+                    "libx264",
+                    "-pix_fmt",
+                    "yuv420p",
+                ])
+                .arg(&output)
+                .status();
+
+            match status {
+                Ok(status) if status.success() => info!("Saved the flythrough to {:?}", output),
+                Ok(status) => error!("ffmpeg exited with {}", status),
+                Err(e) => error!("Failed to run ffmpeg, is it installed? {}", e),
+            }
+        });
+}
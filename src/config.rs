@@ -0,0 +1,205 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock, RwLock},
+};
+
+use crate::ui::ui::Settings;
+
+const APP_NAME: &str = "3dobs";
+const SETTINGS_NAME: &str = "settings";
+/// Holds the name of the profile picked last, it's used again on the next start
+const PROFILE_FILE: &str = "profile";
+
+/// The version of the settings files this build writes. Bump it along with a
+/// new step in `MIGRATIONS` when a change to `Settings` needs more than the
+/// defaults of new fields, and keep renamed fields readable with
+/// `#[serde(alias = "...")]`.
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// The steps taking the settings from each version to the next, the first
+/// one from files written before there were versions
+const MIGRATIONS: [fn(&mut Settings); SETTINGS_VERSION as usize] = [
+    // the import options used to be looked up by the extension as it was typed
+    |settings| {
+        settings.import_options = std::mem::take(&mut settings.import_options)
+            .into_iter()
+            .map(|(ext, options)| (ext.to_lowercase(), options))
+            .collect();
+    },
+];
+
+static DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+/// None for the default settings
+static PROFILE: RwLock<Option<String>> = RwLock::new(None);
+/// What loading the settings changed, for the console once it's set up
+static NOTICES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Sets up where the settings are read from. `dir` replaces the platform's
+/// config directory, for portable installs. `profile` picks a named set of
+/// settings instead of the one used last.
+pub fn init(dir: Option<PathBuf>, profile: Option<String>) {
+    if let Some(dir) = dir {
+        let _ = DIR_OVERRIDE.set(dir);
+    }
+    let profile = profile
+        .or_else(|| {
+            std::fs::read_to_string(self::dir()?.join(PROFILE_FILE))
+                .ok()
+                .map(|name| name.trim().to_string())
+        })
+        .filter(|name| !name.is_empty());
+    *PROFILE.write().unwrap() = profile;
+}
+
+/// Where the settings are kept, along with the samples and caches. None if
+/// the platform has no config directory and none was given.
+pub fn dir() -> Option<PathBuf> {
+    if let Some(dir) = DIR_OVERRIDE.get() {
+        return Some(dir.clone());
+    }
+    confy::get_configuration_file_path(APP_NAME, SETTINGS_NAME)
+        .ok()
+        .and_then(|path| path.parent().map(|dir| dir.to_path_buf()))
+}
+
+fn profiles_dir() -> Option<PathBuf> {
+    dir().map(|dir| dir.join("profiles"))
+}
+
+fn settings_path() -> Result<PathBuf, confy::ConfyError> {
+    let missing = || confy::ConfyError::BadConfigDirectory("no config directory".to_string());
+    match active_profile() {
+        Some(name) => Ok(profiles_dir()
+            .ok_or_else(missing)?
+            .join(format!("{}.ron", name))),
+        None => Ok(dir()
+            .ok_or_else(missing)?
+            .join(format!("{}.ron", SETTINGS_NAME))),
+    }
+}
+
+pub fn active_profile() -> Option<String> {
+    PROFILE.read().unwrap().clone()
+}
+
+/// Names of the saved profiles, sorted
+pub fn profiles() -> Vec<String> {
+    let Some(entries) = profiles_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return vec![];
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == "ron"))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Loads the settings of the active profile, a missing file is created with
+/// the defaults. Files of older versions are migrated and ones that can't be
+/// read anymore are replaced with the defaults, both after a backup.
+pub fn load() -> Result<Settings, confy::ConfyError> {
+    let path = settings_path()?;
+    let mut settings: Settings = match confy::load_path(&path) {
+        Ok(settings) => settings,
+        Err(e) if path.exists() => {
+            let backup = backup(&path, "unreadable")?;
+            notice(format!(
+                "The settings in {:?} couldn't be read ({}), they were moved to {:?} and the \
+                 defaults are used instead",
+                path, e, backup
+            ));
+            let settings = Settings::default();
+            confy::store_path(&path, &settings)?;
+            return Ok(settings);
+        }
+        Err(e) => return Err(e),
+    };
+
+    if settings.version > SETTINGS_VERSION {
+        notice(format!(
+            "The settings in {:?} are from a newer version of 3dobs, the settings it added are \
+             lost when they're saved",
+            path
+        ));
+    } else if settings.version < SETTINGS_VERSION {
+        let from = settings.version;
+        let backup = backup(&path, &format!("v{}", from))?;
+        for step in &MIGRATIONS[from as usize..] {
+            step(&mut settings);
+        }
+        settings.version = SETTINGS_VERSION;
+        confy::store_path(&path, &settings)?;
+        notice(format!(
+            "The settings were updated from version {} to {}, the old ones were saved to {:?}",
+            from, SETTINGS_VERSION, backup
+        ));
+    }
+
+    Ok(settings)
+}
+
+/// Copies a settings file next to itself before it's changed, `tag` tells the
+/// copies apart
+fn backup(path: &Path, tag: &str) -> Result<PathBuf, confy::ConfyError> {
+    let backup = path.with_extension(format!("{}.ron.bak", tag));
+    std::fs::copy(path, &backup).map_err(confy::ConfyError::GeneralLoadError)?;
+
+    Ok(backup)
+}
+
+fn notice(message: String) {
+    // the settings are loaded before the logger is set up
+    if log::max_level() == log::LevelFilter::Off {
+        NOTICES.lock().unwrap().push(message);
+    } else {
+        log::warn!("{}", message);
+    }
+}
+
+/// What loading the settings changed before the logger was set up
+pub fn take_notices() -> Vec<String> {
+    std::mem::take(&mut NOTICES.lock().unwrap())
+}
+
+/// Saves the settings into the active profile
+pub fn store(settings: Settings) -> Result<(), confy::ConfyError> {
+    confy::store_path(settings_path()?, settings)
+}
+
+/// Makes `profile` the active one and loads its settings. It's also used on
+/// the next start unless `--profile` says otherwise.
+pub fn switch_profile(profile: Option<String>) -> Result<Settings, confy::ConfyError> {
+    *PROFILE.write().unwrap() = profile.clone();
+    if let Some(dir) = dir() {
+        let path = dir.join(PROFILE_FILE);
+        let result = match &profile {
+            Some(name) => std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(path, name)),
+            None => std::fs::remove_file(path).or_else(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            }),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to remember the settings profile: {}", e);
+        }
+    }
+    load()
+}
+
+/// Saves `settings` as a new profile, without switching to it
+pub fn create_profile(name: &str, settings: Settings) -> Result<(), confy::ConfyError> {
+    let dir = profiles_dir()
+        .ok_or_else(|| confy::ConfyError::BadConfigDirectory("no config directory".to_string()))?;
+    confy::store_path(dir.join(format!("{}.ron", name)), settings)
+}
+
+/// Profile names end up as file names, so only the characters that are safe
+/// in those are allowed
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == ' ')
+}
@@ -0,0 +1,180 @@
+use std::{
+    backtrace::Backtrace,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use glad_gl::gl;
+
+use crate::logger::LOG_FILE_NAME;
+
+/// Written next to the crash report so the next start knows there's a
+/// report the user hasn't seen yet
+const CRASH_MARKER_FILE_NAME: &str = "3dobs.crash";
+
+#[derive(Default)]
+struct CrashContext {
+    models: Vec<String>,
+    gpu_info: String,
+}
+
+static CRASH_CONTEXT: Mutex<Option<CrashContext>> = Mutex::new(None);
+
+fn with_context(f: impl FnOnce(&mut CrashContext)) {
+    // a poisoned lock still holds perfectly usable data for a crash report
+    let mut ctx = CRASH_CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    f(ctx.get_or_insert_with(CrashContext::default));
+}
+
+/// Directory where the log file (and therefore crash reports) are written
+fn report_dir() -> PathBuf {
+    Path::new(LOG_FILE_NAME)
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
+}
+
+pub fn set_loaded_models<'a>(names: impl Iterator<Item = &'a str>) {
+    let models = names.map(|n| n.to_string()).collect();
+    with_context(|ctx| ctx.models = models);
+}
+
+/// Queries the GL driver strings. Must be called after GL has been loaded
+/// with a current context.
+pub fn record_gpu_info() {
+    let get = |name| unsafe {
+        let s = gl::GetString(name);
+        if s.is_null() {
+            "unknown".to_string()
+        } else {
+            std::ffi::CStr::from_ptr(s as *const std::ffi::c_char)
+                .to_string_lossy()
+                .into_owned()
+        }
+    };
+
+    let info = format!(
+        "Vendor: {}\nRenderer: {}\nOpenGL: {}\nGLSL: {}",
+        get(gl::VENDOR),
+        get(gl::RENDERER),
+        get(gl::VERSION),
+        get(gl::SHADING_LANGUAGE_VERSION)
+    );
+    with_context(|ctx| ctx.gpu_info = info);
+}
+
+fn build_report(message: &str, location: &str) -> String {
+    let mut report = String::new();
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let _ = writeln!(report, "3dobs crash report");
+    let _ = writeln!(
+        report,
+        "Version: {}-{}",
+        env!("CARGO_PKG_VERSION"),
+        env!("GIT_HASH")
+    );
+    let _ = writeln!(
+        report,
+        "OS: {} ({})",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(report, "Timestamp: {}", timestamp);
+    let _ = writeln!(
+        report,
+        "Thread: {}",
+        std::thread::current().name().unwrap_or("<unnamed>")
+    );
+    let _ = writeln!(report);
+    let _ = writeln!(report, "Panic: {}", message);
+    let _ = writeln!(report, "Location: {}", location);
+    let _ = writeln!(report);
+
+    with_context(|ctx| {
+        let _ = writeln!(report, "GPU:");
+        if ctx.gpu_info.is_empty() {
+            let _ = writeln!(report, "unknown");
+        } else {
+            let _ = writeln!(report, "{}", ctx.gpu_info);
+        }
+        let _ = writeln!(report);
+        let _ = writeln!(report, "Loaded models ({}):", ctx.models.len());
+        for model in &ctx.models {
+            let _ = writeln!(report, "  {}", model);
+        }
+    });
+
+    let _ = writeln!(report);
+    let _ = writeln!(report, "Backtrace:");
+    let _ = writeln!(report, "{}", Backtrace::force_capture());
+
+    report
+}
+
+fn write_report(report: &str) -> std::io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let dir = report_dir();
+    let report_path = dir.join(format!("3dobs-crash-{}.txt", timestamp));
+
+    std::fs::write(&report_path, report)?;
+    std::fs::write(
+        dir.join(CRASH_MARKER_FILE_NAME),
+        report_path.to_string_lossy().as_bytes(),
+    )?;
+
+    Ok(report_path)
+}
+
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "Unknown panic payload".to_string()
+        };
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+            .unwrap_or_else(|| "unknown".to_string());
+
+        // we don't go through the logger here since the panic might have
+        // happened while the console log was locked
+        let report = build_report(&message, &location);
+        match write_report(&report) {
+            Ok(path) => eprintln!("3dobs crashed. Crash report written to {:?}", path),
+            Err(e) => eprintln!(
+                "3dobs crashed and the crash report could not be written: {}",
+                e
+            ),
+        }
+
+        default_hook(info);
+    }));
+}
+
+/// Returns the path of the crash report left by the previous run, if any.
+/// The marker is removed so the user is only notified once.
+pub fn take_previous_crash_report() -> Option<PathBuf> {
+    let marker = report_dir().join(CRASH_MARKER_FILE_NAME);
+    let contents = std::fs::read_to_string(&marker).ok()?;
+    let _ = std::fs::remove_file(&marker);
+
+    let report = PathBuf::from(contents.trim());
+    report.exists().then_some(report)
+}
@@ -0,0 +1,271 @@
+use glad_gl::gl;
+
+use crate::{aabb::AABB, gl_state, shader::Shader};
+
+const LINE_WIDTH: f32 = 2.0;
+/// How far surfaces are pushed back by `begin_surface_pass`, as the factor and
+/// units of `glPolygonOffset`
+const SURFACE_OFFSET: f32 = 1.0;
+/// Number of segments circles of wireframe spheres are made of
+const CIRCLE_SEGMENTS: usize = 32;
+
+pub const RED: glm::Vec3 = glm::Vec3 {
+    x: 1.0,
+    y: 0.2,
+    z: 0.2,
+};
+pub const GREEN: glm::Vec3 = glm::Vec3 {
+    x: 0.2,
+    y: 1.0,
+    z: 0.2,
+};
+pub const BLUE: glm::Vec3 = glm::Vec3 {
+    x: 0.3,
+    y: 0.4,
+    z: 1.0,
+};
+
+/// Pushes the filled polygons drawn until `end_surface_pass` back a little in
+/// depth, so the overlay lines drawn over them afterwards win the depth test
+/// instead of z-fighting with them. Lines aren't affected by polygon offset,
+/// so the overlays keep their own depth and are still hidden by what's in
+/// front of them.
+pub fn begin_surface_pass(reversed_z: bool) {
+    // with reversed-Z farther away is a smaller depth
+    let offset = if reversed_z {
+        -SURFACE_OFFSET
+    } else {
+        SURFACE_OFFSET
+    };
+    gl_state::enable(gl::POLYGON_OFFSET_FILL);
+    unsafe {
+        gl::PolygonOffset(offset, offset);
+    }
+}
+
+pub fn end_surface_pass() {
+    unsafe {
+        gl::PolygonOffset(0.0, 0.0);
+    }
+    gl_state::disable(gl::POLYGON_OFFSET_FILL);
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct DebugVertex {
+    position: glm::Vec3,
+    color: glm::Vec3,
+}
+
+/// Text drawn over the viewport at a point in world space
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub position: glm::Vec3,
+    pub text: String,
+}
+
+/// Immediate-mode lines and labels in world space, for overlays like bounding
+/// boxes and pivots. Everything added during a frame is drawn with a single
+/// draw call by `flush`, the labels are drawn by the UI over the viewport.
+#[derive(Debug, Default)]
+pub struct DebugDraw {
+    vertices: Vec<DebugVertex>,
+    pub labels: Vec<Label>,
+    vao: u32,
+    vbo: u32,
+    /// Number of vertices the vertex buffer has room for
+    capacity: usize,
+}
+
+impl DebugDraw {
+    pub fn line(&mut self, from: glm::Vec3, to: glm::Vec3, color: glm::Vec3) {
+        self.vertices.push(DebugVertex {
+            position: from,
+            color,
+        });
+        self.vertices.push(DebugVertex {
+            position: to,
+            color,
+        });
+    }
+
+    /// A small cross marking a point
+    pub fn point(&mut self, p: glm::Vec3, size: f32, color: glm::Vec3) {
+        let h = size / 2.0;
+        self.line(
+            p - glm::vec3(h, 0.0, 0.0),
+            p + glm::vec3(h, 0.0, 0.0),
+            color,
+        );
+        self.line(
+            p - glm::vec3(0.0, h, 0.0),
+            p + glm::vec3(0.0, h, 0.0),
+            color,
+        );
+        self.line(
+            p - glm::vec3(0.0, 0.0, h),
+            p + glm::vec3(0.0, 0.0, h),
+            color,
+        );
+    }
+
+    /// The X, Y and Z axes starting at `origin` in red, green and blue
+    pub fn axes(&mut self, origin: glm::Vec3, size: f32) {
+        self.line(origin, origin + glm::vec3(size, 0.0, 0.0), RED);
+        self.line(origin, origin + glm::vec3(0.0, size, 0.0), GREEN);
+        self.line(origin, origin + glm::vec3(0.0, 0.0, size), BLUE);
+    }
+
+    /// The edges of a box, with `transform` applied to its corners
+    pub fn aabb(&mut self, aabb: &AABB, transform: &glm::Mat4, color: glm::Vec3) {
+        let corners = aabb.corners().map(|c| {
+            let p = *transform * glm::vec4(c.x, c.y, c.z, 1.0);
+            glm::vec3(p.x / p.w, p.y / p.w, p.z / p.w)
+        });
+        // each pair of corners differs in exactly one axis
+        const EDGES: [(usize, usize); 12] = [
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (3, 0),
+            (4, 5),
+            (5, 6),
+            (6, 7),
+            (7, 4),
+            (0, 4),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+        ];
+
+        for (a, b) in EDGES {
+            self.line(corners[a], corners[b], color);
+        }
+    }
+
+    /// Three circles around `center`, one in each axis plane
+    pub fn sphere(&mut self, center: glm::Vec3, radius: f32, color: glm::Vec3) {
+        let step = std::f32::consts::TAU / CIRCLE_SEGMENTS as f32;
+
+        for i in 0..CIRCLE_SEGMENTS {
+            let (s0, c0) = (i as f32 * step).sin_cos();
+            let (s1, c1) = ((i + 1) as f32 * step).sin_cos();
+            let (s0, c0, s1, c1) = (s0 * radius, c0 * radius, s1 * radius, c1 * radius);
+
+            self.line(
+                center + glm::vec3(c0, s0, 0.0),
+                center + glm::vec3(c1, s1, 0.0),
+                color,
+            );
+            self.line(
+                center + glm::vec3(c0, 0.0, s0),
+                center + glm::vec3(c1, 0.0, s1),
+                color,
+            );
+            self.line(
+                center + glm::vec3(0.0, c0, s0),
+                center + glm::vec3(0.0, c1, s1),
+                color,
+            );
+        }
+    }
+
+    /// Text that always faces the camera, drawn by the UI over the viewport
+    pub fn text(&mut self, position: glm::Vec3, text: String) {
+        self.labels.push(Label { position, text });
+    }
+
+    /// Draws the lines added since the last flush in one draw call and clears
+    /// them. The labels are left for the UI to draw and clear.
+    pub fn flush(&mut self, shader: &Shader, view_mat: &glm::Mat4, projection_mat: &glm::Mat4) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        unsafe {
+            if self.vao == 0 {
+                self.create_buffers();
+            }
+
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            let size = (std::mem::size_of::<DebugVertex>() * self.vertices.len()) as isize;
+            if self.vertices.len() > self.capacity {
+                // grow to the next power of two so the buffer isn't reallocated every frame
+                self.capacity = self.vertices.len().next_power_of_two();
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (std::mem::size_of::<DebugVertex>() * self.capacity) as isize,
+                    std::ptr::null(),
+                    gl::STREAM_DRAW,
+                );
+            }
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                size,
+                self.vertices.as_ptr() as *const std::ffi::c_void,
+            );
+
+            shader.use_shader();
+            shader.set_mat4fv("view", view_mat);
+            shader.set_mat4fv("projection", projection_mat);
+
+            gl::LineWidth(LINE_WIDTH);
+            gl::DrawArrays(gl::LINES, 0, self.vertices.len() as i32);
+
+            // reset stuff to default
+            gl::LineWidth(1.0);
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+
+        self.vertices.clear();
+    }
+
+    unsafe fn create_buffers(&mut self) {
+        gl::GenVertexArrays(1, &mut self.vao);
+        gl::GenBuffers(1, &mut self.vbo);
+
+        gl::BindVertexArray(self.vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+
+        // vertex positions
+        gl::EnableVertexAttribArray(0);
+        gl::VertexAttribPointer(
+            0,
+            3,
+            gl::FLOAT,
+            gl::FALSE,
+            std::mem::size_of::<DebugVertex>() as i32,
+            std::ptr::null(),
+        );
+
+        // vertex colors
+        gl::EnableVertexAttribArray(1);
+        gl::VertexAttribPointer(
+            1,
+            3,
+            gl::FLOAT,
+            gl::FALSE,
+            std::mem::size_of::<DebugVertex>() as i32,
+            std::mem::size_of::<glm::Vec3>() as *const std::ffi::c_void,
+        );
+
+        gl::BindVertexArray(0);
+    }
+}
+
+impl Drop for DebugDraw {
+    fn drop(&mut self) {
+        // the GL context might be in a bad state if we're unwinding from a panic,
+        // and it's about to be destroyed anyway
+        if std::thread::panicking() || self.vao == 0 {
+            return;
+        }
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use crate::importer::download;
+
+/// The URL scheme 3dobs is registered for, links look like
+/// `3dobs://open?path=/models/ship.obj` or `3dobs://open?url=https://...`
+pub const SCHEME: &str = "3dobs";
+
+/// What a link asks to open
+#[derive(Debug, PartialEq)]
+pub enum Target {
+    Path(PathBuf),
+    Url(String),
+}
+
+pub fn is_deep_link(s: &str) -> bool {
+    s.get(..SCHEME.len())
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case(SCHEME))
+        && s[SCHEME.len()..].starts_with("://")
+}
+
+/// Reads the model a link points to. Only the `open` action exists, with
+/// either a `path` or a `url` parameter.
+pub fn parse(link: &str) -> Result<Target, String> {
+    if !is_deep_link(link) {
+        return Err(format!("Not a {}:// link", SCHEME));
+    }
+    let rest = &link[SCHEME.len() + 3..];
+    let rest = rest.split('#').next().unwrap_or(rest);
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if action.trim_end_matches('/') != "open" {
+        return Err(format!("Unknown action \"{}\"", action));
+    }
+
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value)?;
+        match key {
+            "path" if !value.is_empty() => return Ok(Target::Path(PathBuf::from(value))),
+            "url" if download::is_url(&value) => return Ok(Target::Url(value)),
+            "url" => return Err(format!("\"{}\" isn't an http(s) URL", value)),
+            _ => {}
+        }
+    }
+
+    Err("The link has no path or url to open".to_string())
+}
+
+/// Decodes the `%XX` escapes and `+` for spaces of a query value
+fn percent_decode(value: &str) -> Result<String, String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                // from_str_radix would take a sign as well
+                let byte = value
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("Invalid escape in \"{}\"", value))?;
+                out.push(byte);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(out).map_err(|_| format!("\"{}\" isn't valid UTF-8", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_are_two_hex_digits() {
+        assert_eq!(percent_decode("a%20b+c%2Fd").unwrap(), "a b c/d");
+        assert_eq!(percent_decode("%C3%B1").unwrap(), "ñ");
+        for invalid in ["%+1", "%-1", "%1", "%g0", "%"] {
+            assert!(percent_decode(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+}
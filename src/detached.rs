@@ -0,0 +1,194 @@
+use std::sync::mpsc::Receiver;
+
+use glad_gl::gl;
+use glfw::{Action, Context, Key, WindowEvent};
+use log::error;
+
+use crate::{camera, gl_state, model::Model, shader::Shader, ui::ui::State};
+
+/// Degrees the view turns for each pixel the cursor is dragged
+const ORBIT_SENSITIVITY: f32 = 0.3;
+const FOV: f32 = 45.0;
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 10.0;
+
+/// A model shown in an OS window of its own, so models can be compared side
+/// by side or on different monitors. Its context shares the buffers, textures
+/// and shaders of the main window's, so nothing is uploaded twice.
+pub struct DetachedView {
+    pub model_id: u32,
+    window: glfw::Window,
+    events: Receiver<(f64, WindowEvent)>,
+    title: String,
+    /// Angles in degrees the camera orbits the model at
+    yaw: f32,
+    pitch: f32,
+    /// Multiplies the distance the whole model fits in view from
+    zoom: f32,
+    last_cursor: Option<(f64, f64)>,
+}
+
+impl DetachedView {
+    /// Opens a window for the model, `main` is the current context afterwards
+    pub fn open(main: &mut glfw::Window, model: &Model) -> Option<Self> {
+        let (w, h) = main.get_size();
+        let title = format!("{} - 3dobs", model.name);
+        let (mut window, events) = main.create_shared(
+            (w as u32 / 2).max(1),
+            (h as u32 / 2).max(1),
+            &title,
+            glfw::WindowMode::Windowed,
+        )?;
+        window.set_key_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+
+        // the main window already waits for VSync, waiting again for every
+        // view would divide the frame rate
+        window.make_current();
+        window.glfw.set_swap_interval(glfw::SwapInterval::None);
+        main.make_current();
+        gl_state::invalidate();
+
+        Some(DetachedView {
+            model_id: model.id,
+            window,
+            events,
+            title,
+            yaw: 30.0,
+            pitch: 20.0,
+            zoom: 1.0,
+            last_cursor: None,
+        })
+    }
+
+    /// Orbits and zooms with the window's input, returns whether there was any
+    fn handle_events(&mut self) -> bool {
+        let mut received = false;
+        for (_, event) in glfw::flush_messages(&self.events) {
+            received = true;
+            match event {
+                WindowEvent::CursorPos(x, y) => {
+                    let dragging =
+                        self.window.get_mouse_button(glfw::MouseButtonLeft) == Action::Press;
+                    if let (true, Some((last_x, last_y))) = (dragging, self.last_cursor) {
+                        self.yaw -= (x - last_x) as f32 * ORBIT_SENSITIVITY;
+                        self.pitch = (self.pitch + (y - last_y) as f32 * ORBIT_SENSITIVITY)
+                            .clamp(-89.0, 89.0);
+                    }
+                    self.last_cursor = Some((x, y));
+                }
+                WindowEvent::Scroll(_, yoff) => {
+                    self.zoom = (self.zoom * 0.9_f32.powf(yoff as f32)).clamp(MIN_ZOOM, MAX_ZOOM);
+                }
+                WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                    self.window.set_should_close(true);
+                }
+                _ => {}
+            }
+        }
+        received
+    }
+
+    /// Draws the model with the view's own camera and shows it
+    unsafe fn draw(&mut self, model: &Model, shader: &Shader, state: &State) {
+        self.window.make_current();
+        gl_state::invalidate();
+
+        let title = format!("{} - 3dobs", model.name);
+        if title != self.title {
+            self.window.set_title(&title);
+            self.title = title;
+        }
+
+        let (w, h) = self.window.get_framebuffer_size();
+        gl::Viewport(0, 0, w, h);
+        gl_state::enable(gl::DEPTH_TEST);
+        gl_state::enable(gl::BLEND);
+        gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        gl::ClearColor(0.2, 0.2, 0.2, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+        // orbit the model's center from far enough away to fit all of it
+        let center = model.local_to_world(model.center());
+        let radius = (glm::length(model.world_size()) / 2.0).max(camera::NEAR_PLANE);
+        let distance = radius / (FOV.to_radians() / 2.0).tan() * self.zoom;
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+        let direction = glm::vec3(
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+            pitch.cos() * yaw.cos(),
+        );
+        let position = center + direction * distance;
+        let far = (distance + radius) * 2.0;
+
+        let view_mat = glm::ext::look_at(position, center, glm::vec3(0.0, 1.0, 0.0));
+        let projection_mat = glm::ext::perspective(
+            glm::radians(FOV),
+            w.max(1) as f32 / h.max(1) as f32,
+            camera::NEAR_PLANE,
+            far,
+        );
+
+        // the program is shared, so everything the main window sets is set again
+        shader.use_shader();
+        shader.set_mat4fv("view", &view_mat);
+        shader.set_mat4fv("projection", &projection_mat);
+        shader.set_3fv("spotLight.position", position);
+        shader.set_3fv("spotLight.direction", -direction);
+        shader.set_3fv("viewPos", position);
+        shader.set_int("displayMode", state.display_mode as i32);
+        shader.set_float("near", camera::NEAR_PLANE);
+        shader.set_float("far", far);
+        shader.set_bool("reversedZ", false);
+        shader.set_bool("sliceEnabled", false);
+
+        gl_state::polygon_mode(if state.wireframe { gl::LINE } else { gl::FILL });
+        model.draw_shared(shader, state.show_textures);
+        gl_state::polygon_mode(gl::FILL);
+
+        self.window.swap_buffers();
+    }
+}
+
+/// The windows models were detached into, updated after the main window's frame
+#[derive(Default)]
+pub struct DetachedWindows {
+    views: Vec<DetachedView>,
+}
+
+impl DetachedWindows {
+    /// Opens the requested windows, closes the ones whose model is gone and
+    /// draws the rest. Returns whether any of them received input. `main` is
+    /// the current context afterwards.
+    pub fn update(&mut self, main: &mut glfw::Window, state: &mut State, shader: &Shader) -> bool {
+        for id in std::mem::take(&mut state.detach_requests) {
+            let Some(model) = state.objects.iter().find(|m| m.id == id) else {
+                continue;
+            };
+            match DetachedView::open(main, model) {
+                Some(view) => self.views.push(view),
+                None => error!("Failed to open a window for {}", model.name),
+            }
+        }
+
+        self.views.retain(|view| {
+            !view.window.should_close() && state.objects.iter().any(|m| m.id == view.model_id)
+        });
+
+        let mut input = false;
+        for view in &mut self.views {
+            input |= view.handle_events();
+            if let Some(model) = state.objects.iter().find(|m| m.id == view.model_id) {
+                unsafe {
+                    view.draw(model, shader, state);
+                }
+            }
+        }
+
+        main.make_current();
+        gl_state::invalidate();
+        input
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+/// Errors of the importers and the renderer, for the UI to explain them and
+/// for library users to tell them apart
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to read {path:?}")]
+    File {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A statement of a text file that couldn't be read, lines count from 1
+    #[error("{path:?} line {line}: {message}")]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+
+    /// The file is readable but its contents don't make a model
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("{path:?} isn't a supported model, only {formats} files can be imported")]
+    Unsupported { path: PathBuf, formats: String },
+
+    /// `resumable` if what was read so far is kept and the next import of
+    /// the file picks up from there
+    #[error("The import was cancelled")]
+    Cancelled { resumable: bool },
+
+    #[error("Failed to compile the shader {name:?}: {log}")]
+    Shader { name: String, log: String },
+
+    #[error(transparent)]
+    Cache(#[from] bincode::Error),
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub fn file(path: &Path, source: std::io::Error) -> Self {
+        Error::File {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn parse(path: &Path, line: usize, message: impl Into<String>) -> Self {
+        Error::Parse {
+            path: path.to_path_buf(),
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Invalid(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::Invalid(message.to_string())
+    }
+}
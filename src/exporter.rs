@@ -0,0 +1,217 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+
+use crate::{
+    importer::{Material, Texture, TextureOptions},
+    model::Model,
+};
+
+/// Writes the models into a single Wavefront OBJ file with their current
+/// transforms applied. Every model becomes an object and each of its meshes
+/// a group inside of it. The materials go into an MTL file next to it, and
+/// their textures are copied into the same folder.
+pub fn export_obj(models: &[&Model], path: &Path) -> std::io::Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let materials = export_mtl(models, &mtl_path)?;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writeln!(writer, "# Exported by 3dobs {}", env!("CARGO_PKG_VERSION"))?;
+    if let Some(name) = mtl_path.file_name() {
+        writeln!(writer, "mtllib {}", name.to_string_lossy())?;
+    }
+
+    // OBJ indices are global and 1-based
+    let mut offset = 1;
+
+    for (m, model) in models.iter().enumerate() {
+        writeln!(writer, "o {}", model.name)?;
+
+        for (i, mesh) in model.meshes.iter().enumerate() {
+            writeln!(writer, "g {}", mesh.name)?;
+
+            let vertices = mesh.transformed_vertices(&model.mesh_transform(i));
+            for vertex in &vertices {
+                let p = vertex.position;
+                writeln!(writer, "v {} {} {}", p.x, p.y, p.z)?;
+            }
+            for vertex in &vertices {
+                // the importer flips V, flip it back
+                let t = vertex.tex_coords;
+                writeln!(writer, "vt {} {}", t.x, 1.0 - t.y)?;
+            }
+            for vertex in &vertices {
+                let n = vertex.normal;
+                writeln!(writer, "vn {} {} {}", n.x, n.y, n.z)?;
+            }
+
+            for range in &mesh.materials {
+                if let Some(name) = materials.get(&(m, range.material.name.clone())) {
+                    writeln!(writer, "usemtl {}", name)?;
+                }
+                let indices = &mesh.indices[range.start..range.start + range.count];
+                for face in indices.chunks_exact(3) {
+                    let (a, b, c) = (face[0] + offset, face[1] + offset, face[2] + offset);
+                    writeln!(writer, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+                }
+            }
+
+            offset += vertices.len() as u32;
+        }
+    }
+
+    writer.flush()
+}
+
+/// Writes the materials of the models and copies their textures next to
+/// `path`. Returns the name each material got in the file, keyed by the
+/// model's index and the material's name, since different models can have
+/// materials with the same name.
+fn export_mtl(models: &[&Model], path: &Path) -> std::io::Result<HashMap<(usize, String), String>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "# Exported by 3dobs {}", env!("CARGO_PKG_VERSION"))?;
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut names = HashMap::new();
+    let mut used_names = HashSet::new();
+    let mut textures = TextureCopies::default();
+
+    for (m, model) in models.iter().enumerate() {
+        for range in model.meshes.iter().flat_map(|mesh| &mesh.materials) {
+            let material = &range.material;
+            let key = (m, material.name.clone());
+            if names.contains_key(&key) {
+                continue;
+            }
+
+            let name = unique_name(&material.name, &mut used_names);
+            writeln!(writer)?;
+            write_material(&mut writer, &name, material)?;
+            for texture in &material.textures {
+                if let Some(file) = textures.copy(model, texture, dir) {
+                    let token = texture.typ.to_material_str();
+                    let options = texture_options(&texture.options);
+                    writeln!(writer, "{} {}{}", token, options, file)?;
+                }
+            }
+            names.insert(key, name);
+        }
+    }
+
+    writer.flush()?;
+    Ok(names)
+}
+
+fn write_material(writer: &mut impl Write, name: &str, material: &Material) -> std::io::Result<()> {
+    let (ka, kd, ks) = (
+        material.ambient_color,
+        material.diffuse_color,
+        material.specular_color,
+    );
+    writeln!(writer, "newmtl {}", name)?;
+    writeln!(writer, "Ka {} {} {}", ka.x, ka.y, ka.z)?;
+    writeln!(writer, "Kd {} {} {}", kd.x, kd.y, kd.z)?;
+    writeln!(writer, "Ks {} {} {}", ks.x, ks.y, ks.z)?;
+    writeln!(writer, "Ns {}", material.specular_exponent)?;
+    writeln!(writer, "d {}", material.opacity)
+}
+
+/// The options that differ from the defaults, followed by a space
+fn texture_options(options: &TextureOptions) -> String {
+    let defaults = TextureOptions::default();
+    let mut out = String::new();
+    if options.offset != defaults.offset {
+        out += &format!("-o {} {} ", options.offset.x, options.offset.y);
+    }
+    if options.scale != defaults.scale {
+        out += &format!("-s {} {} ", options.scale.x, options.scale.y);
+    }
+    if options.base != defaults.base || options.gain != defaults.gain {
+        out += &format!("-mm {} {} ", options.base, options.gain);
+    }
+    if options.clamp {
+        out += "-clamp on ";
+    }
+    if !options.blend_u {
+        out += "-blendu off ";
+    }
+    if !options.blend_v {
+        out += "-blendv off ";
+    }
+    out
+}
+
+/// `name`, or with a number appended if it's already taken
+fn unique_name(name: &str, used: &mut HashSet<String>) -> String {
+    let mut unique = name.to_string();
+    let mut n = 1;
+    while !used.insert(unique.clone()) {
+        unique = format!("{}_{}", name, n);
+        n += 1;
+    }
+    unique
+}
+
+/// Texture files copied into the export folder, each source is only copied
+/// once and files from different folders with the same name get renamed
+#[derive(Default)]
+struct TextureCopies {
+    copied: HashMap<PathBuf, String>,
+    used_names: HashSet<String>,
+}
+
+impl TextureCopies {
+    /// Copies the texture's file into `dir`, returns its name there
+    fn copy(&mut self, model: &Model, texture: &Texture, dir: &Path) -> Option<String> {
+        // embedded textures have no file to copy
+        if texture.name.starts_with("data:") {
+            warn!(
+                "Skipping the embedded texture of {}, only texture files are exported",
+                model.name
+            );
+            return None;
+        }
+
+        // OBJ textures are relative to their MTL file, not the model
+        let Some(source) = texture.path.clone() else {
+            warn!(
+                "The file of the texture {} isn't known, it's not exported",
+                texture.name
+            );
+            return None;
+        };
+        if let Some(name) = self.copied.get(&source) {
+            return Some(name.clone());
+        }
+
+        let file_name = source.file_name()?.to_string_lossy().into_owned();
+        let (stem, extension) = match file_name.rsplit_once('.') {
+            Some((stem, extension)) => (stem.to_string(), format!(".{}", extension)),
+            None => (file_name.clone(), String::new()),
+        };
+        let mut name = file_name.clone();
+        let mut n = 1;
+        while !self.used_names.insert(name.clone()) {
+            name = format!("{}_{}{}", stem, n, extension);
+            n += 1;
+        }
+
+        let destination = dir.join(&name);
+        // exporting into the model's own folder keeps the textures where they are
+        if destination != source {
+            if let Err(e) = std::fs::copy(&source, &destination) {
+                warn!("Failed to copy the texture {:?}: {}", source, e);
+                return None;
+            }
+        }
+
+        self.copied.insert(source, name.clone());
+        Some(name)
+    }
+}
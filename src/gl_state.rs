@@ -0,0 +1,159 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use glad_gl::gl;
+
+/// The parts of the GL state that change the most, as they were last set
+/// through this module. Missing entries are unknown and get set on the next
+/// request.
+#[derive(Debug, Default)]
+struct Cache {
+    capabilities: HashMap<u32, bool>,
+    program: Option<u32>,
+    /// Index of the active texture unit, not the `TEXTURE0 + i` enum
+    active_texture: Option<u32>,
+    /// The texture bound to `TEXTURE_2D` of each unit
+    textures: HashMap<u32, u32>,
+    polygon_mode: Option<u32>,
+}
+
+thread_local! {
+    // the state belongs to the current context, which is only ever the main
+    // thread's
+    static CACHE: RefCell<Cache> = RefCell::new(Cache::default());
+}
+
+/// Forgets the cached state, for when another context becomes the current one
+pub fn invalidate() {
+    CACHE.with(|cache| *cache.borrow_mut() = Cache::default());
+}
+
+pub fn set_capability(capability: u32, enabled: bool) {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.capabilities.get(&capability) == Some(&enabled) {
+            return;
+        }
+        unsafe {
+            if enabled {
+                gl::Enable(capability);
+            } else {
+                gl::Disable(capability);
+            }
+        }
+        cache.capabilities.insert(capability, enabled);
+    });
+}
+
+/// Whether the capability is enabled, asking GL when it isn't cached
+pub fn is_enabled(capability: u32) -> bool {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        *cache
+            .capabilities
+            .entry(capability)
+            .or_insert_with(|| unsafe { gl::IsEnabled(capability) == gl::TRUE })
+    })
+}
+
+pub fn enable(capability: u32) {
+    set_capability(capability, true);
+}
+
+pub fn disable(capability: u32) {
+    set_capability(capability, false);
+}
+
+pub fn use_program(program: u32) {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.program != Some(program) {
+            unsafe {
+                gl::UseProgram(program);
+            }
+            cache.program = Some(program);
+        }
+    });
+}
+
+/// Deletes the program, which stops being the one in use if it was
+pub fn delete_program(program: u32) {
+    unsafe {
+        gl::DeleteProgram(program);
+    }
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.program == Some(program) {
+            cache.program = None;
+        }
+    });
+}
+
+/// Makes texture unit `unit` the one texture calls affect
+pub fn active_texture(unit: u32) {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.active_texture != Some(unit) {
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + unit);
+            }
+            cache.active_texture = Some(unit);
+        }
+    });
+}
+
+/// Binds `texture` to `TEXTURE_2D` of texture unit `unit`, which becomes the
+/// active one
+pub fn bind_texture(unit: u32, texture: u32) {
+    active_texture(unit);
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.textures.get(&unit) != Some(&texture) {
+            unsafe {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+            }
+            cache.textures.insert(unit, texture);
+        }
+    });
+}
+
+/// Deletes the textures, the units they were bound to fall back to 0 like
+/// they do in GL
+pub fn delete_textures(textures: &[u32]) {
+    unsafe {
+        gl::DeleteTextures(textures.len() as i32, textures.as_ptr());
+    }
+    CACHE.with(|cache| {
+        for bound in cache.borrow_mut().textures.values_mut() {
+            if textures.contains(bound) {
+                *bound = 0;
+            }
+        }
+    });
+}
+
+/// Sets the polygon mode of both faces
+pub fn polygon_mode(mode: u32) {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.polygon_mode != Some(mode) {
+            unsafe {
+                gl::PolygonMode(gl::FRONT_AND_BACK, mode);
+            }
+            cache.polygon_mode = Some(mode);
+        }
+    });
+}
+
+/// The polygon mode of the front faces, only asked from GL when it's unknown
+pub fn current_polygon_mode() -> u32 {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        *cache.polygon_mode.get_or_insert_with(|| {
+            let mut mode = [0; 2];
+            unsafe {
+                gl::GetIntegerv(gl::POLYGON_MODE, mode.as_mut_ptr());
+            }
+            mode[0] as u32
+        })
+    })
+}
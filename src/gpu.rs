@@ -0,0 +1,269 @@
+use glad_gl::gl;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ui::{i18n::tr, ui::Settings},
+    utils,
+};
+
+/// OpenGL versions offered in the settings, None for the 3.3 the viewer
+/// needs at least
+pub const GL_VERSIONS: [Option<(u32, u32)>; 5] =
+    [None, Some((4, 1)), Some((4, 3)), Some((4, 5)), Some((4, 6))];
+const DEFAULT_GL_VERSION: (u32, u32) = (3, 3);
+/// Parts of the renderer strings of drivers that draw on the CPU
+const SOFTWARE_RENDERERS: [&str; 5] = [
+    "llvmpipe",
+    "softpipe",
+    "swiftshader",
+    "gdi generic",
+    "basic render driver",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GlProfile {
+    #[default]
+    Core,
+    Compatibility,
+}
+
+impl GlProfile {
+    pub const ALL: [GlProfile; 2] = [GlProfile::Core, GlProfile::Compatibility];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GlProfile::Core => tr("gl_profile.core"),
+            GlProfile::Compatibility => tr("gl_profile.compatibility"),
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "core" => Some(GlProfile::Core),
+            "compat" | "compatibility" => Some(GlProfile::Compatibility),
+            _ => None,
+        }
+    }
+}
+
+/// The backend options given on the command line, they win over the
+/// environment variables and the settings
+#[derive(Debug, Clone, Default)]
+pub struct Flags {
+    pub gl_version: Option<String>,
+    pub gl_profile: Option<String>,
+    pub no_vsync: bool,
+    pub software: bool,
+    pub discrete_gpu: bool,
+}
+
+/// How the GL context is created. Every option can come from the settings,
+/// a `THREEDOBS_*` environment variable or a command line flag.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backend {
+    pub gl_version: (u32, u32),
+    pub profile: GlProfile,
+    /// Overrides the VSync setting for this session
+    pub no_vsync: bool,
+    /// Asks Mesa to draw on the CPU, for broken GPU drivers
+    pub software: bool,
+    /// Asks hybrid graphics drivers to run the viewer on the discrete GPU
+    pub discrete_gpu: bool,
+}
+
+impl Backend {
+    pub fn new(settings: &Settings, flags: Flags) -> Self {
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let env_flag = |name: &str| env(name).is_some_and(|v| v != "0");
+
+        let gl_version = flags
+            .gl_version
+            .or_else(|| env("THREEDOBS_GL_VERSION"))
+            .and_then(|v| {
+                let parsed = parse_version(&v);
+                if parsed.is_none() {
+                    warn!(
+                        "Ignoring the OpenGL version \"{}\", it should look like 4.1",
+                        v
+                    );
+                }
+                parsed
+            })
+            .or(settings.gl_version)
+            .unwrap_or(DEFAULT_GL_VERSION);
+        let profile = flags
+            .gl_profile
+            .or_else(|| env("THREEDOBS_GL_PROFILE"))
+            .and_then(|p| {
+                let parsed = GlProfile::parse(&p);
+                if parsed.is_none() {
+                    warn!(
+                        "Ignoring the OpenGL profile \"{}\", it should be core or compat",
+                        p
+                    );
+                }
+                parsed
+            })
+            .unwrap_or(settings.gl_profile);
+
+        Backend {
+            gl_version,
+            profile,
+            no_vsync: flags.no_vsync || env_flag("THREEDOBS_NO_VSYNC"),
+            software: flags.software
+                || env_flag("THREEDOBS_SOFTWARE")
+                || settings.software_rendering,
+            discrete_gpu: flags.discrete_gpu
+                || env_flag("THREEDOBS_DISCRETE_GPU")
+                || settings.discrete_gpu,
+        }
+    }
+
+    /// Sets the variables the GL drivers read when they're loaded, so it has
+    /// to be called before GLFW is initialized. Variables that are already
+    /// set are left alone.
+    pub fn export_driver_hints(&self) {
+        let set = |name: &str, value: &str| {
+            if std::env::var_os(name).is_none() {
+                std::env::set_var(name, value);
+            }
+        };
+
+        if self.software {
+            info!("Asking for software rendering");
+            set("LIBGL_ALWAYS_SOFTWARE", "1");
+            set("GALLIUM_DRIVER", "llvmpipe");
+        } else if self.discrete_gpu {
+            // Mesa's PRIME offloading, and NVIDIA's for its own driver
+            info!("Asking for the discrete GPU");
+            set("DRI_PRIME", "1");
+            set("__NV_PRIME_RENDER_OFFLOAD", "1");
+            set("__GLX_VENDOR_LIBRARY_NAME", "nvidia");
+        }
+    }
+
+    /// Hints for the context of the next window GLFW creates. Contexts are
+    /// asked to report driver resets, GLFW leaves that out where it isn't
+    /// supported.
+    pub fn window_hints(&self, glfw: &mut glfw::Glfw) {
+        let (major, minor) = self.gl_version;
+        glfw.window_hint(glfw::WindowHint::ContextVersion(major, minor));
+        glfw.window_hint(glfw::WindowHint::ContextRobustness(
+            glfw::ContextRobustnessHint::LoseContextOnReset,
+        ));
+        match self.profile {
+            GlProfile::Core => {
+                glfw.window_hint(glfw::WindowHint::OpenGlProfile(
+                    glfw::OpenGlProfileHint::Core,
+                ));
+                glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+            }
+            GlProfile::Compatibility => {
+                glfw.window_hint(glfw::WindowHint::OpenGlProfile(
+                    glfw::OpenGlProfileHint::Compat,
+                ));
+            }
+        }
+    }
+
+    /// Warns if the current context draws on the CPU without software
+    /// rendering having been asked for, or the other way round
+    pub fn check_renderer(&self) {
+        let renderer = unsafe {
+            let s = gl::GetString(gl::RENDERER);
+            if s.is_null() {
+                return;
+            }
+            std::ffi::CStr::from_ptr(s as *const std::ffi::c_char)
+                .to_string_lossy()
+                .into_owned()
+        };
+        let lowercase = renderer.to_lowercase();
+        let on_cpu = SOFTWARE_RENDERERS.iter().any(|r| lowercase.contains(r));
+
+        match (on_cpu, self.software) {
+            (true, false) => warn!(
+                "OpenGL is drawn on the CPU by \"{}\", which is slow. The GPU drivers might \
+                 be missing or broken.",
+                renderer
+            ),
+            (false, true) => warn!(
+                "Software rendering was asked for, but \"{}\" doesn't look like a software \
+                 renderer",
+                renderer
+            ),
+            _ => info!("Rendering with \"{}\"", renderer),
+        }
+    }
+}
+
+/// Which function tells whether the context was lost
+#[derive(Debug, Clone, Copy)]
+enum ResetQuery {
+    Core,
+    Arb,
+}
+
+/// Notices when the driver resets the current context, which loses every
+/// object in it. That happens when the GPU hangs, its driver is updated or
+/// it's removed.
+#[derive(Debug)]
+pub struct ResetWatch {
+    query: Option<ResetQuery>,
+}
+
+impl ResetWatch {
+    /// Watches the current context, if it was made with reset notifications
+    pub fn current() -> Self {
+        let query = if utils::gl_version() >= (4, 5) || utils::has_gl_extension("GL_KHR_robustness")
+        {
+            Some(ResetQuery::Core)
+        } else if utils::has_gl_extension("GL_ARB_robustness") {
+            Some(ResetQuery::Arb)
+        } else {
+            None
+        };
+
+        let mut strategy = 0;
+        if query.is_some() {
+            unsafe {
+                gl::GetIntegerv(gl::RESET_NOTIFICATION_STRATEGY, &mut strategy);
+            }
+        }
+        if strategy as u32 != gl::LOSE_CONTEXT_ON_RESET {
+            info!("The OpenGL context doesn't report driver resets");
+            return ResetWatch { query: None };
+        }
+
+        ResetWatch { query }
+    }
+
+    /// Whether the context has been lost since it was made, logging why
+    pub fn lost(&self) -> bool {
+        let status = match self.query {
+            Some(ResetQuery::Core) => unsafe { gl::GetGraphicsResetStatus() },
+            Some(ResetQuery::Arb) => unsafe { gl::GetGraphicsResetStatusARB() },
+            None => return false,
+        };
+
+        match status {
+            gl::NO_ERROR => return false,
+            gl::GUILTY_CONTEXT_RESET => {
+                error!("The OpenGL context was lost, the GPU was reset while drawing the viewer")
+            }
+            gl::INNOCENT_CONTEXT_RESET => {
+                error!("The OpenGL context was lost, the GPU was reset by another program")
+            }
+            _ => error!("The OpenGL context was lost, the GPU driver was reset"),
+        }
+        true
+    }
+}
+
+/// Parses versions like "4.1"
+fn parse_version(s: &str) -> Option<(u32, u32)> {
+    let (major, minor) = s.trim().split_once('.')?;
+    let version = (major.parse().ok()?, minor.parse().ok()?);
+    (version >= DEFAULT_GL_VERSION).then_some(version)
+}
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+use crate::{
+    aabb::AABB,
+    mesh::{MaterialRange, MeshGeometry},
+    model::Model,
+};
+
+const MAX_HISTORY: usize = 100;
+
+/// The parts of a model that can be changed by an undoable action
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformSnapshot {
+    /// The model's own transform
+    transform: [glm::Vec3; 3],
+    /// The transforms of the meshes within the model
+    positions: Vec<glm::Vec3>,
+    rotations: Vec<glm::Vec3>,
+    scales: Vec<glm::Vec3>,
+    custom_pivot: Option<glm::Vec3>,
+}
+
+impl TransformSnapshot {
+    pub fn capture(model: &Model) -> Self {
+        Self {
+            transform: [model.position, model.rotation, model.scale],
+            positions: model.meshes.iter().map(|m| m.position).collect(),
+            rotations: model.meshes.iter().map(|m| m.rotation).collect(),
+            scales: model.meshes.iter().map(|m| m.scale).collect(),
+            custom_pivot: model.custom_pivot,
+        }
+    }
+
+    pub fn restore(&self, model: &mut Model) {
+        [model.position, model.rotation, model.scale] = self.transform;
+        for (i, mesh) in model.meshes.iter_mut().enumerate() {
+            if let Some(position) = self.positions.get(i) {
+                mesh.position = *position;
+            }
+            if let Some(rotation) = self.rotations.get(i) {
+                mesh.rotation = *rotation;
+            }
+            if let Some(scale) = self.scales.get(i) {
+                mesh.scale = *scale;
+            }
+        }
+        model.custom_pivot = self.custom_pivot;
+    }
+}
+
+/// The vertex data of a model, for undoing edits of the geometry
+#[derive(Debug, Clone)]
+pub struct GeometrySnapshot {
+    meshes: Vec<MeshGeometry>,
+    /// Material ranges of each mesh, with the textures they had
+    materials: Vec<Vec<MaterialRange>>,
+    aabb: AABB,
+}
+
+impl GeometrySnapshot {
+    pub fn capture(model: &Model) -> Self {
+        Self {
+            meshes: model
+                .meshes
+                .iter()
+                .map(|m| MeshGeometry::new(m.vertices.clone(), m.indices.clone(), &m.materials))
+                .collect(),
+            materials: model.meshes.iter().map(|m| m.materials.clone()).collect(),
+            aabb: model.aabb,
+        }
+    }
+
+    pub fn meshes(&self) -> &[MeshGeometry] {
+        &self.meshes
+    }
+
+    pub fn triangles(&self) -> usize {
+        self.meshes.iter().map(|mesh| mesh.indices.len() / 3).sum()
+    }
+
+    /// Puts the geometry back, unless the model was unloaded or reloaded
+    /// with different meshes since
+    pub fn restore(self, model: &mut Model) -> bool {
+        if model.evicted || model.meshes.len() != self.meshes.len() {
+            return false;
+        }
+
+        // the textures were uploaded again if the model was reloaded since
+        let texture_ids: HashMap<String, u32> = model
+            .meshes
+            .iter()
+            .flat_map(|m| m.textures())
+            .map(|t| (t.name.clone(), t.id))
+            .collect();
+        for (mesh, mut materials) in model.meshes.iter_mut().zip(self.materials) {
+            for texture in materials
+                .iter_mut()
+                .flat_map(|r| r.material.textures.iter_mut())
+            {
+                if let Some(id) = texture_ids.get(&texture.name) {
+                    texture.id = *id;
+                }
+            }
+            mesh.materials = materials;
+        }
+        model.set_geometry(self.meshes);
+        model.aabb = self.aabb;
+        true
+    }
+}
+
+#[derive(Debug)]
+enum Entry {
+    Transform {
+        model_id: u32,
+        snapshot: TransformSnapshot,
+    },
+    Geometry {
+        model_id: u32,
+        snapshot: GeometrySnapshot,
+    },
+    /// The model was removed from `index` in the object list. It holds no GPU
+    /// resources while it's here, see `Model::release_gpu`.
+    Removed { index: usize, model: Box<Model> },
+    /// The model was put back into the object list, undoing a removal
+    Restored { model_id: u32 },
+    /// Several changes that are undone and redone together
+    Batch(Vec<Entry>),
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+    batch: Option<Vec<Entry>>,
+}
+
+impl History {
+    /// Records the current state of a model. Must be called right before the model is changed.
+    pub fn record(&mut self, model: &Model) {
+        self.push(Entry::Transform {
+            model_id: model.id,
+            snapshot: TransformSnapshot::capture(model),
+        });
+    }
+
+    /// Records the current geometry of a model. Must be called right before
+    /// its vertices are edited.
+    pub fn record_geometry(&mut self, model: &Model) {
+        self.record_geometry_snapshot(model.id, GeometrySnapshot::capture(model));
+    }
+
+    /// Records geometry that was captured earlier, for edits that are only
+    /// kept after being previewed
+    pub fn record_geometry_snapshot(&mut self, model_id: u32, snapshot: GeometrySnapshot) {
+        self.push(Entry::Geometry { model_id, snapshot });
+    }
+
+    /// Keeps a removed model around so the removal can be undone. Models with
+    /// a file are unloaded until then, the others only keep their geometry.
+    pub fn record_removal(&mut self, index: usize, mut model: Model) {
+        model.release_gpu();
+        self.push(Entry::Removed {
+            index,
+            model: Box::new(model),
+        });
+    }
+
+    /// Groups everything recorded until `end_batch` into a single undo step
+    pub fn begin_batch(&mut self) {
+        self.batch.get_or_insert_with(Vec::new);
+    }
+
+    pub fn end_batch(&mut self) {
+        if let Some(entries) = self.batch.take() {
+            if !entries.is_empty() {
+                self.push(Entry::Batch(entries));
+            }
+        }
+    }
+
+    fn push(&mut self, entry: Entry) {
+        if let Some(batch) = &mut self.batch {
+            batch.push(entry);
+            return;
+        }
+
+        self.undo.push(entry);
+        self.redo.clear();
+
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo(&mut self, objects: &mut Vec<Model>) -> bool {
+        Self::step(&mut self.undo, &mut self.redo, objects)
+    }
+
+    pub fn redo(&mut self, objects: &mut Vec<Model>) -> bool {
+        Self::step(&mut self.redo, &mut self.undo, objects)
+    }
+
+    /// Drops every transform and geometry entry belonging to a model, used
+    /// when they can't be applied to it anymore
+    pub fn forget(&mut self, model_id: u32) {
+        Self::forget_in(&mut self.undo, model_id);
+        Self::forget_in(&mut self.redo, model_id);
+    }
+
+    fn forget_in(entries: &mut Vec<Entry>, model_id: u32) {
+        entries.retain_mut(|e| match e {
+            Entry::Transform { model_id: id, .. } | Entry::Geometry { model_id: id, .. } => {
+                *id != model_id
+            }
+            Entry::Batch(inner) => {
+                Self::forget_in(inner, model_id);
+                !inner.is_empty()
+            }
+            _ => true,
+        });
+    }
+
+    fn step(from: &mut Vec<Entry>, to: &mut Vec<Entry>, objects: &mut Vec<Model>) -> bool {
+        while let Some(entry) = from.pop() {
+            if let Some(inverse) = Self::apply(entry, objects) {
+                to.push(inverse);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Applies an entry and returns the entry that reverts it, or `None` if
+    /// the model it belongs to is gone
+    fn apply(entry: Entry, objects: &mut Vec<Model>) -> Option<Entry> {
+        match entry {
+            Entry::Transform { model_id, snapshot } => {
+                let model = objects.iter_mut().find(|m| m.id == model_id)?;
+                let inverse = Entry::Transform {
+                    model_id,
+                    snapshot: TransformSnapshot::capture(model),
+                };
+                snapshot.restore(model);
+
+                Some(inverse)
+            }
+            Entry::Geometry { model_id, snapshot } => {
+                let model = objects.iter_mut().find(|m| m.id == model_id)?;
+                let inverse = Entry::Geometry {
+                    model_id,
+                    snapshot: GeometrySnapshot::capture(model),
+                };
+
+                snapshot.restore(model).then_some(inverse)
+            }
+            Entry::Removed { index, mut model } => {
+                let inverse = Entry::Restored { model_id: model.id };
+                // unloaded models are read again once they're selected
+                model.restore_gpu();
+                objects.insert(index.min(objects.len()), *model);
+
+                Some(inverse)
+            }
+            Entry::Restored { model_id } => {
+                let index = objects.iter().position(|m| m.id == model_id)?;
+                let mut model = objects.remove(index);
+                model.release_gpu();
+
+                Some(Entry::Removed {
+                    index,
+                    model: Box::new(model),
+                })
+            }
+            Entry::Batch(entries) => {
+                // entries are applied last to first, so removals are put back at the
+                // right indices. The inverses come out in the order that reverts this.
+                let inverse: Vec<Entry> = entries
+                    .into_iter()
+                    .rev()
+                    .filter_map(|e| Self::apply(e, objects))
+                    .collect();
+
+                (!inverse.is_empty()).then_some(Entry::Batch(inverse))
+            }
+        }
+    }
+}
@@ -0,0 +1,494 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::mpsc::{self, Receiver},
+};
+
+use crate::{
+    debug_draw::DebugDraw,
+    mesh::Mesh,
+    model::Model,
+    pacing,
+    topology::{self, face_normal},
+};
+
+pub const HULL_COLOR: glm::Vec3 = glm::Vec3 {
+    x: 0.3,
+    y: 0.9,
+    z: 0.6,
+};
+pub const OBB_COLOR: glm::Vec3 = glm::Vec3 {
+    x: 1.0,
+    y: 0.55,
+    z: 0.1,
+};
+/// Face normals closer than this in each component are treated as the same
+/// direction when trying boxes
+const NORMAL_TOLERANCE: f32 = 1e-4;
+
+/// A box rotated to fit the geometry, in the model's own coordinates
+#[derive(Debug, Clone, Copy)]
+pub struct OrientedBox {
+    pub center: glm::Vec3,
+    /// Unit directions of the box's sides
+    pub axes: [glm::Vec3; 3],
+    /// Half the size of the box along each axis
+    pub half_extents: [f32; 3],
+}
+
+impl OrientedBox {
+    pub fn corners(&self) -> [glm::Vec3; 8] {
+        let [x, y, z] = [0, 1, 2].map(|i| self.axes[i] * self.half_extents[i]);
+        // same order as `AABB::corners`, bottom face first
+        [
+            self.center - x - y - z,
+            self.center + x - y - z,
+            self.center + x - y + z,
+            self.center - x - y + z,
+            self.center - x + y - z,
+            self.center + x + y - z,
+            self.center + x + y + z,
+            self.center - x + y + z,
+        ]
+    }
+
+    /// The length of each side once `transform` is applied, largest first
+    pub fn dimensions(&self, transform: &glm::Mat4) -> [f32; 3] {
+        let mut dimensions = [0, 1, 2].map(|i| {
+            let side = self.axes[i] * self.half_extents[i] * 2.0;
+            let side = *transform * glm::vec4(side.x, side.y, side.z, 0.0);
+            glm::length(glm::vec3(side.x, side.y, side.z))
+        });
+        dimensions.sort_by(|a, b| b.total_cmp(a));
+        dimensions
+    }
+}
+
+/// The convex hull of a model and the smallest box around it, in the model's
+/// own coordinates
+#[derive(Debug)]
+pub struct Bounds {
+    pub points: Vec<glm::Vec3>,
+    /// Outward facing triangles of the hull, as indices into `points`
+    pub triangles: Vec<[usize; 3]>,
+    pub obb: OrientedBox,
+}
+
+/// The bounds of a model being computed on a worker thread
+#[derive(Debug)]
+pub struct Job {
+    rx: Receiver<Option<Bounds>>,
+}
+
+impl Job {
+    /// Copies the positions so the meshes can keep changing in the meantime
+    pub fn spawn(meshes: &[Mesh]) -> Self {
+        let positions: Vec<glm::Vec3> = meshes
+            .iter()
+            .flat_map(|mesh| mesh.vertices.iter().map(|v| v.position))
+            .collect();
+        let (tx, rx) = mpsc::channel();
+
+        let _ = std::thread::Builder::new()
+            .name("convex hull".to_string())
+            .spawn(move || {
+                // the model was removed or its geometry changed before this was done
+                if tx.send(Bounds::new(&positions)).is_ok() {
+                    pacing::request_redraw();
+                }
+            });
+
+        Job { rx }
+    }
+
+    /// The bounds once they're done, `Some(None)` if the model is flat
+    pub fn poll(&self) -> Option<Option<Bounds>> {
+        self.rx.try_recv().ok()
+    }
+}
+
+#[derive(Debug)]
+struct HullFace {
+    vertices: [usize; 3],
+    normal: glm::Vec3,
+    offset: f32,
+    /// Points above the face that haven't been added to the hull yet
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+impl HullFace {
+    fn new(points: &[glm::Vec3], vertices: [usize; 3]) -> Self {
+        let normal = glm::normalize(face_normal(vertices.map(|v| points[v])));
+        Self {
+            vertices,
+            normal,
+            offset: glm::dot(normal, points[vertices[0]]),
+            outside: vec![],
+            alive: true,
+        }
+    }
+
+    fn distance(&self, p: glm::Vec3) -> f32 {
+        glm::dot(self.normal, p) - self.offset
+    }
+}
+
+/// Quickhull, returns the outward facing triangles or `None` if the points
+/// are all on a plane
+fn convex_hull(points: &[glm::Vec3]) -> Option<Vec<[usize; 3]>> {
+    if points.len() < 4 {
+        return None;
+    }
+
+    let mut min = points[0];
+    let mut max = points[0];
+    for p in points {
+        min = glm::vec3(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+        max = glm::vec3(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+    }
+    let epsilon = glm::length(max - min) * 1e-6;
+
+    // start with a tetrahedron that's as big as possible
+    let farthest = |score: &dyn Fn(glm::Vec3) -> f32| {
+        (0..points.len()).max_by(|a, b| score(points[*a]).total_cmp(&score(points[*b])))
+    };
+    let a = farthest(&|p| -p.x)?;
+    let b = farthest(&|p| glm::length(p - points[a]))?;
+    if glm::length(points[b] - points[a]) <= epsilon {
+        return None;
+    }
+    let line = glm::normalize(points[b] - points[a]);
+    let off_line = |p: glm::Vec3| {
+        let v = p - points[a];
+        glm::length(v - line * glm::dot(v, line))
+    };
+    let c = farthest(&off_line)?;
+    if off_line(points[c]) <= epsilon {
+        return None;
+    }
+    let plane = glm::normalize(face_normal([points[a], points[b], points[c]]));
+    let d = farthest(&|p| glm::dot(p - points[a], plane).abs())?;
+    if glm::dot(points[d] - points[a], plane).abs() <= epsilon {
+        return None;
+    }
+
+    let mut faces = vec![];
+    // wind the faces so they point away from the opposite corner
+    for ([i, j, k], opposite) in [
+        ([a, b, c], d),
+        ([a, d, b], c),
+        ([a, c, d], b),
+        ([b, d, c], a),
+    ] {
+        let face = HullFace::new(points, [i, j, k]);
+        faces.push(if face.distance(points[opposite]) > 0.0 {
+            HullFace::new(points, [i, k, j])
+        } else {
+            face
+        });
+    }
+
+    let assign = |faces: &mut [HullFace], candidates: &[usize], point: usize| {
+        if let Some(face) = candidates
+            .iter()
+            .find(|f| faces[**f].distance(points[point]) > epsilon)
+        {
+            faces[*face].outside.push(point);
+        }
+    };
+    let initial: Vec<usize> = (0..faces.len()).collect();
+    for point in 0..points.len() {
+        assign(&mut faces, &initial, point);
+    }
+
+    // the live face of each directed edge, the face across the edge (i, j)
+    // is the one with (j, i)
+    let mut edge_faces: HashMap<(usize, usize), usize> = HashMap::new();
+    let face_edges = |face: &HullFace| {
+        let [i, j, k] = face.vertices;
+        [(i, j), (j, k), (k, i)]
+    };
+    for (f, face) in faces.iter().enumerate() {
+        for edge in face_edges(face) {
+            edge_faces.insert(edge, f);
+        }
+    }
+    let mut pending = initial;
+
+    while let Some(current) = pending.pop() {
+        if !faces[current].alive || faces[current].outside.is_empty() {
+            continue;
+        }
+        let eye = *faces[current]
+            .outside
+            .iter()
+            .max_by(|a, b| {
+                let face = &faces[current];
+                face.distance(points[**a])
+                    .total_cmp(&face.distance(points[**b]))
+            })
+            .unwrap();
+
+        // the faces the eye point sees are connected, so they're found by
+        // walking across the edges starting from the current one
+        let mut visible = vec![current];
+        let mut checked: HashSet<usize> = HashSet::from([current]);
+        let mut next = 0;
+        while next < visible.len() {
+            for (i, j) in face_edges(&faces[visible[next]]) {
+                let Some(&neighbor) = edge_faces.get(&(j, i)) else {
+                    continue;
+                };
+                if checked.insert(neighbor) && faces[neighbor].distance(points[eye]) > epsilon {
+                    visible.push(neighbor);
+                }
+            }
+            next += 1;
+        }
+        let edges: HashSet<(usize, usize)> = visible
+            .iter()
+            .flat_map(|f| face_edges(&faces[*f]))
+            .collect();
+
+        let mut orphans = vec![];
+        for &f in &visible {
+            faces[f].alive = false;
+            orphans.append(&mut faces[f].outside);
+        }
+        for edge in &edges {
+            edge_faces.remove(edge);
+        }
+
+        // the edges only one visible face has are on the horizon, the new
+        // faces connect them to the eye point
+        let first_new = faces.len();
+        for &(i, j) in &edges {
+            if !edges.contains(&(j, i)) {
+                let face = HullFace::new(points, [i, j, eye]);
+                for edge in face_edges(&face) {
+                    edge_faces.insert(edge, faces.len());
+                }
+                faces.push(face);
+            }
+        }
+        let new_faces: Vec<usize> = (first_new..faces.len()).collect();
+        for point in orphans {
+            if point != eye {
+                assign(&mut faces, &new_faces, point);
+            }
+        }
+        pending.extend(new_faces);
+    }
+
+    Some(
+        faces
+            .into_iter()
+            .filter(|f| f.alive)
+            .map(|f| f.vertices)
+            .collect(),
+    )
+}
+
+/// Andrew's monotone chain, returns the hull in counter-clockwise order
+fn convex_hull_2d(mut points: Vec<[f32; 2]>) -> Vec<[f32; 2]> {
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let cross = |o: [f32; 2], a: [f32; 2], b: [f32; 2]| {
+        (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+    };
+    let mut hull: Vec<[f32; 2]> = vec![];
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let start = hull.len();
+        for p in pass {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // the last point is the first of the other half
+        hull.pop();
+    }
+
+    hull
+}
+
+/// The smallest box with one side flush with a face of the hull. For each
+/// face the rest of the box is the smallest rectangle around the hull seen
+/// from above the face, which has a side along one of the edges of the
+/// outline. `points` are only the ones on the hull.
+fn minimum_box(points: &[glm::Vec3], triangles: &[[usize; 3]]) -> Option<OrientedBox> {
+    let mut seen: HashSet<[i32; 3]> = HashSet::new();
+    let mut normals: Vec<glm::Vec3> = vec![];
+    for triangle in triangles {
+        let mut normal = glm::normalize(face_normal(triangle.map(|v| points[v])));
+        // faces in the same plane or opposite ones give the same box
+        if [normal.x, normal.y, normal.z]
+            .into_iter()
+            .find(|c| c.abs() > NORMAL_TOLERANCE)
+            .is_some_and(|c| c < 0.0)
+        {
+            normal = -normal;
+        }
+        let key = [normal.x, normal.y, normal.z].map(|c| (c / NORMAL_TOLERANCE).round() as i32);
+        if seen.insert(key) {
+            normals.push(normal);
+        }
+    }
+
+    let mut best: Option<(f32, OrientedBox)> = None;
+    for normal in normals {
+        let helper = if normal.x.abs() < 0.9 {
+            glm::vec3(1.0, 0.0, 0.0)
+        } else {
+            glm::vec3(0.0, 1.0, 0.0)
+        };
+        let u = glm::normalize(glm::cross(normal, helper));
+        let w = glm::cross(normal, u);
+
+        let (mut low, mut high) = (f32::MAX, f32::MIN);
+        for p in points {
+            let h = glm::dot(*p, normal);
+            low = low.min(h);
+            high = high.max(h);
+        }
+        let outline = convex_hull_2d(
+            points
+                .iter()
+                .map(|p| [glm::dot(*p, u), glm::dot(*p, w)])
+                .collect(),
+        );
+
+        for i in 0..outline.len() {
+            let [x0, y0] = outline[i];
+            let [x1, y1] = outline[(i + 1) % outline.len()];
+            let length = (x1 - x0).hypot(y1 - y0);
+            if length == 0.0 {
+                continue;
+            }
+            let (dx, dy) = ((x1 - x0) / length, (y1 - y0) / length);
+
+            let (mut min_a, mut max_a, mut min_b, mut max_b) =
+                (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+            for [x, y] in &outline {
+                let a = x * dx + y * dy;
+                let b = -x * dy + y * dx;
+                min_a = min_a.min(a);
+                max_a = max_a.max(a);
+                min_b = min_b.min(b);
+                max_b = max_b.max(b);
+            }
+
+            let volume = (max_a - min_a) * (max_b - min_b) * (high - low);
+            if best.as_ref().is_some_and(|(v, _)| *v <= volume) {
+                continue;
+            }
+
+            let axis_a = u * dx + w * dy;
+            let axis_b = u * -dy + w * dx;
+            let center = axis_a * ((min_a + max_a) / 2.0)
+                + axis_b * ((min_b + max_b) / 2.0)
+                + normal * ((low + high) / 2.0);
+            best = Some((
+                volume,
+                OrientedBox {
+                    center,
+                    axes: [axis_a, normal, axis_b],
+                    half_extents: [
+                        (max_a - min_a) / 2.0,
+                        (high - low) / 2.0,
+                        (max_b - min_b) / 2.0,
+                    ],
+                },
+            ));
+        }
+    }
+
+    best.map(|(_, obb)| obb)
+}
+
+impl Bounds {
+    /// Computes the hull of the positions of every mesh together, `None` if
+    /// they're all on a plane
+    pub fn new(positions: &[glm::Vec3]) -> Option<Self> {
+        let mut seen: HashSet<[u32; 3]> = HashSet::new();
+        let points: Vec<glm::Vec3> = positions
+            .iter()
+            .copied()
+            .filter(|p| seen.insert(topology::position_key(*p)))
+            .collect();
+
+        let triangles = convex_hull(&points)?;
+
+        // only keep the points on the hull
+        let mut remap: HashMap<usize, usize> = HashMap::new();
+        let mut hull_points = vec![];
+        let triangles = triangles
+            .into_iter()
+            .map(|t| {
+                t.map(|v| {
+                    *remap.entry(v).or_insert_with(|| {
+                        hull_points.push(points[v]);
+                        hull_points.len() - 1
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        let obb = minimum_box(&hull_points, &triangles)?;
+
+        Some(Self {
+            points: hull_points,
+            triangles,
+            obb,
+        })
+    }
+
+    /// Volume of the hull once `transform` is applied
+    pub fn volume(&self, transform: &glm::Mat4) -> f32 {
+        let scale = glm::determinant(transform).abs();
+        scale
+            * self
+                .triangles
+                .iter()
+                .map(|t| {
+                    let [a, b, c] = t.map(|v| self.points[v]);
+                    glm::dot(a, glm::cross(b, c)) / 6.0
+                })
+                .sum::<f32>()
+    }
+
+    /// Overlays the edges of the hull and the box around it
+    pub fn draw(&self, model: &Model, debug: &mut DebugDraw) {
+        let transform = model.transform();
+        let to_world = |p: glm::Vec3| {
+            let p = transform * glm::vec4(p.x, p.y, p.z, 1.0);
+            glm::vec3(p.x / p.w, p.y / p.w, p.z / p.w)
+        };
+
+        // each edge is shared by two triangles, draw it once
+        let mut edges = HashSet::new();
+        for triangle in &self.triangles {
+            for (a, b) in [(0, 1), (1, 2), (2, 0)].map(|(i, j)| (triangle[i], triangle[j])) {
+                if edges.insert((a.min(b), a.max(b))) {
+                    debug.line(
+                        to_world(self.points[a]),
+                        to_world(self.points[b]),
+                        HULL_COLOR,
+                    );
+                }
+            }
+        }
+
+        let corners = self.obb.corners().map(to_world);
+        for i in 0..4 {
+            debug.line(corners[i], corners[(i + 1) % 4], OBB_COLOR);
+            debug.line(corners[i + 4], corners[(i + 1) % 4 + 4], OBB_COLOR);
+            debug.line(corners[i], corners[i + 4], OBB_COLOR);
+        }
+    }
+}
@@ -111,6 +111,7 @@ impl GlfwPlatform {
         io[Key::Enter] = GlfwKey::Enter as _;
         io[Key::Escape] = GlfwKey::Escape as _;
         io[Key::KeypadEnter] = GlfwKey::KpEnter as _;
+        io[Key::F2] = GlfwKey::F2 as _;
         io[Key::A] = GlfwKey::A as _;
         io[Key::C] = GlfwKey::C as _;
         io[Key::V] = GlfwKey::V as _;
@@ -5,6 +5,8 @@ use std::mem;
 
 use glad_gl::gl;
 
+use crate::gl_state;
+
 pub struct Renderer {
     program: gl::GLuint,
     locs: Locs,
@@ -106,11 +108,8 @@ impl Renderer {
             let vbo = return_param(|x| gl::GenBuffers(1, x));
             let ebo = return_param(|x| gl::GenBuffers(1, x));
 
-            let mut current_texture = 0;
-            gl::GetIntegerv(gl::TEXTURE_BINDING_2D, &mut current_texture);
-
             let font_texture = return_param(|x| gl::GenTextures(1, x));
-            gl::BindTexture(gl::TEXTURE_2D, font_texture);
+            gl_state::bind_texture(0, font_texture);
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
             gl::PixelStorei(gl::UNPACK_ROW_LENGTH, 0);
@@ -134,8 +133,6 @@ impl Renderer {
                 atlas.tex_id = (font_texture as usize).into();
             }
 
-            gl::BindTexture(gl::TEXTURE_2D, current_texture as _);
-
             Self {
                 program,
                 locs,
@@ -150,18 +147,16 @@ impl Renderer {
         use imgui::{DrawCmd, DrawCmdParams, DrawIdx, DrawVert};
 
         unsafe {
-            let last_active_texture = return_param(|x| gl::GetIntegerv(gl::ACTIVE_TEXTURE, x));
-            gl::ActiveTexture(gl::TEXTURE0);
-            let last_program = return_param(|x| gl::GetIntegerv(gl::CURRENT_PROGRAM, x));
-            let last_texture = return_param(|x| gl::GetIntegerv(gl::TEXTURE_BINDING_2D, x));
+            // the blend, cull and depth capabilities, program, textures and
+            // polygon mode are left as they're set here, the scene sets those
+            // through the same cache every frame. The scene never sets the
+            // scissor test so it's put back the way it was.
+            let last_scissor_test = gl_state::is_enabled(gl::SCISSOR_TEST);
             let last_sampler = return_param(|x| gl::GetIntegerv(gl::SAMPLER_BINDING, x));
             let last_array_buffer = return_param(|x| gl::GetIntegerv(gl::ARRAY_BUFFER_BINDING, x));
             let last_element_array_buffer =
                 return_param(|x| gl::GetIntegerv(gl::ELEMENT_ARRAY_BUFFER_BINDING, x));
             let last_vertex_array = return_param(|x| gl::GetIntegerv(gl::VERTEX_ARRAY_BINDING, x));
-            let last_polygon_mode = return_param(|x: &mut [gl::GLint; 2]| {
-                gl::GetIntegerv(gl::POLYGON_MODE, x.as_mut_ptr())
-            });
             let last_viewport = return_param(|x: &mut [gl::GLint; 4]| {
                 gl::GetIntegerv(gl::VIEWPORT, x.as_mut_ptr())
             });
@@ -176,18 +171,14 @@ impl Renderer {
                 return_param(|x| gl::GetIntegerv(gl::BLEND_EQUATION_RGB, x));
             let last_blend_equation_alpha =
                 return_param(|x| gl::GetIntegerv(gl::BLEND_EQUATION_ALPHA, x));
-            let last_enable_blend = gl::IsEnabled(gl::BLEND) == gl::TRUE;
-            let last_enable_cull_face = gl::IsEnabled(gl::CULL_FACE) == gl::TRUE;
-            let last_enable_depth_test = gl::IsEnabled(gl::DEPTH_TEST) == gl::TRUE;
-            let last_enable_scissor_test = gl::IsEnabled(gl::SCISSOR_TEST) == gl::TRUE;
 
-            gl::Enable(gl::BLEND);
+            gl_state::enable(gl::BLEND);
             gl::BlendEquation(gl::FUNC_ADD);
             gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-            gl::Disable(gl::CULL_FACE);
-            gl::Disable(gl::DEPTH_TEST);
-            gl::Enable(gl::SCISSOR_TEST);
-            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            gl_state::disable(gl::CULL_FACE);
+            gl_state::disable(gl::DEPTH_TEST);
+            gl_state::enable(gl::SCISSOR_TEST);
+            gl_state::polygon_mode(gl::FILL);
 
             let [width, height] = ctx.io().display_size;
             let [scale_w, scale_h] = ctx.io().display_framebuffer_scale;
@@ -202,7 +193,7 @@ impl Renderer {
                 [0.0, 0.0, -1.0, 0.0],
                 [-1.0, 1.0, 0.0, 1.0],
             ];
-            gl::UseProgram(self.program);
+            gl_state::use_program(self.program);
             gl::Uniform1i(self.locs.texture, 0);
             gl::UniformMatrix4fv(self.locs.proj_mtx, 1, gl::FALSE, matrix.as_ptr() as _);
             gl::BindSampler(0, 0);
@@ -272,7 +263,7 @@ impl Renderer {
                                     ..
                                 },
                         } => {
-                            gl::BindTexture(gl::TEXTURE_2D, texture_id.id() as _);
+                            gl_state::bind_texture(0, texture_id.id() as _);
 
                             gl::Scissor(
                                 (x * scale_w) as gl::GLint,
@@ -306,10 +297,7 @@ impl Renderer {
 
             gl::DeleteVertexArrays(1, &vao);
 
-            gl::UseProgram(last_program as _);
-            gl::BindTexture(gl::TEXTURE_2D, last_texture as _);
             gl::BindSampler(0, last_sampler as _);
-            gl::ActiveTexture(last_active_texture as _);
             gl::BindVertexArray(last_vertex_array as _);
             gl::BindBuffer(gl::ARRAY_BUFFER, last_array_buffer as _);
             gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, last_element_array_buffer as _);
@@ -320,27 +308,6 @@ impl Renderer {
                 last_blend_src_alpha as _,
                 last_blend_dst_alpha as _,
             );
-            if last_enable_blend {
-                gl::Enable(gl::BLEND)
-            } else {
-                gl::Disable(gl::BLEND)
-            };
-            if last_enable_cull_face {
-                gl::Enable(gl::CULL_FACE)
-            } else {
-                gl::Disable(gl::CULL_FACE)
-            };
-            if last_enable_depth_test {
-                gl::Enable(gl::DEPTH_TEST)
-            } else {
-                gl::Disable(gl::DEPTH_TEST)
-            };
-            if last_enable_scissor_test {
-                gl::Enable(gl::SCISSOR_TEST)
-            } else {
-                gl::Disable(gl::SCISSOR_TEST)
-            };
-            gl::PolygonMode(gl::FRONT_AND_BACK, last_polygon_mode[0] as _);
             gl::Viewport(
                 last_viewport[0] as _,
                 last_viewport[1] as _,
@@ -353,6 +320,7 @@ impl Renderer {
                 last_scissor_box[2] as _,
                 last_scissor_box[3] as _,
             );
+            gl_state::set_capability(gl::SCISSOR_TEST, last_scissor_test);
         }
     }
 }
@@ -363,9 +331,9 @@ impl Drop for Renderer {
             gl::DeleteBuffers(1, &self.vbo);
             gl::DeleteBuffers(1, &self.ebo);
 
-            gl::DeleteProgram(self.program);
+            gl_state::delete_program(self.program);
 
-            gl::DeleteTextures(1, &self.font_texture);
+            gl_state::delete_textures(&[self.font_texture]);
         }
     }
 }
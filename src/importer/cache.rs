@@ -0,0 +1,731 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs::{File, OpenOptions},
+    hash::{Hash, Hasher},
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Take, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use log::{info, trace, warn};
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aabb::AABB,
+    config,
+    error::Error,
+    importer::{
+        download, stl::Units, Material, ObjMesh, Object, Texture, TextureOptions, TextureType,
+    },
+    mesh::Vertex,
+};
+
+const MAGIC: &[u8; 8] = b"3DOBSGEO";
+const CHECKPOINT_MAGIC: &[u8; 8] = b"3DOBSCKP";
+/// Bumped whenever the layout of the file changes
+const FORMAT_VERSION: u32 = 1;
+
+/// Size and modification time of a file, the cache is thrown away once
+/// either changes
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Stamp {
+    len: u64,
+    modified: u64,
+}
+
+impl Stamp {
+    /// None if the file doesn't exist, so a file showing up later is noticed too
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Stamp {
+            len: metadata.len(),
+            modified: modified.as_nanos() as u64,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    app_version: String,
+    vertex_size: u32,
+    source: Option<Stamp>,
+    /// The MTL and texture files the model was loaded with
+    dependencies: Vec<(PathBuf, Option<Stamp>)>,
+    name: String,
+    aabb: [[f32; 3]; 2],
+    units: Option<Units>,
+    material_libs: Vec<PathBuf>,
+    texture_files: HashMap<String, PathBuf>,
+    meshes: Vec<CachedMesh>,
+}
+
+/// Start of a checkpoint file, the parsed geometry follows it in chunks
+#[derive(Serialize, Deserialize)]
+struct CheckpointHeader {
+    app_version: String,
+    vertex_size: u32,
+    source: Stamp,
+}
+
+/// The vertices and indices aren't part of it, they follow the header as raw
+/// bytes in the same order as the meshes
+#[derive(Serialize, Deserialize)]
+struct CachedMesh {
+    name: String,
+    vertex_count: u64,
+    index_count: u64,
+    materials: Vec<(usize, Option<CachedMaterial>)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedMaterial {
+    name: String,
+    ambient: [f32; 3],
+    diffuse: [f32; 3],
+    specular: [f32; 3],
+    specular_exponent: f32,
+    opacity: f32,
+    textures: Vec<CachedTexture>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedTexture {
+    name: String,
+    /// The MTL token of the texture type
+    typ: String,
+    offset: [f32; 2],
+    scale: [f32; 2],
+    clamp: bool,
+    base: f32,
+    gain: f32,
+    blend_u: bool,
+    blend_v: bool,
+}
+
+impl From<&Material> for CachedMaterial {
+    fn from(material: &Material) -> Self {
+        let (ka, kd, ks) = (
+            material.ambient_color,
+            material.diffuse_color,
+            material.specular_color,
+        );
+        CachedMaterial {
+            name: material.name.clone(),
+            ambient: [ka.x, ka.y, ka.z],
+            diffuse: [kd.x, kd.y, kd.z],
+            specular: [ks.x, ks.y, ks.z],
+            specular_exponent: material.specular_exponent,
+            opacity: material.opacity,
+            textures: material
+                .textures
+                .iter()
+                .map(|texture| {
+                    let options = &texture.options;
+                    CachedTexture {
+                        name: texture.name.clone(),
+                        typ: texture.typ.to_material_str().to_string(),
+                        offset: [options.offset.x, options.offset.y],
+                        scale: [options.scale.x, options.scale.y],
+                        clamp: options.clamp,
+                        base: options.base,
+                        gain: options.gain,
+                        blend_u: options.blend_u,
+                        blend_v: options.blend_v,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<CachedMaterial> for Material {
+    fn from(material: CachedMaterial) -> Self {
+        let [ka, kd, ks] = [material.ambient, material.diffuse, material.specular];
+        Material {
+            name: material.name,
+            ambient_color: glm::vec3(ka[0], ka[1], ka[2]),
+            diffuse_color: glm::vec3(kd[0], kd[1], kd[2]),
+            specular_color: glm::vec3(ks[0], ks[1], ks[2]),
+            specular_exponent: material.specular_exponent,
+            opacity: material.opacity,
+            textures: material
+                .textures
+                .into_iter()
+                .filter_map(|texture| {
+                    let typ = TextureType::from_material_str(&texture.typ)?;
+                    let mut cached = Texture::new(texture.name, typ);
+                    cached.options = TextureOptions {
+                        offset: glm::vec2(texture.offset[0], texture.offset[1]),
+                        scale: glm::vec2(texture.scale[0], texture.scale[1]),
+                        clamp: texture.clamp,
+                        base: texture.base,
+                        gain: texture.gain,
+                        blend_u: texture.blend_u,
+                        blend_v: texture.blend_v,
+                    };
+                    Some(cached)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Where the cached geometry is kept, next to the settings
+pub fn cache_dir() -> PathBuf {
+    config::dir()
+        .map(|dir| dir.join("geometry-cache"))
+        .unwrap_or_else(|| download::cache_dir().join("geometry-cache"))
+}
+
+/// Deletes every cached model
+pub fn clear() -> std::io::Result<()> {
+    match std::fs::remove_dir_all(cache_dir()) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn cache_path(source: &Path) -> PathBuf {
+    let source = source
+        .canonicalize()
+        .unwrap_or_else(|_| source.to_path_buf());
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+
+    cache_dir().join(format!("{:016x}.3dcache", hasher.finish()))
+}
+
+/// Merges vertices that are exactly the same. The OBJ importer gives every
+/// corner of every face its own vertex, most of them are shared.
+pub fn deduplicate(mesh: &mut ObjMesh) {
+    let mut ids: HashMap<[u32; 11], u32> = HashMap::with_capacity(mesh.vertices.len());
+    let mut vertices = Vec::new();
+    let remap: Vec<u32> = mesh
+        .vertices
+        .iter()
+        .map(|vertex| {
+            *ids.entry(vertex_bits(vertex)).or_insert_with(|| {
+                vertices.push(vertex.clone());
+                (vertices.len() - 1) as u32
+            })
+        })
+        .collect();
+
+    for index in &mut mesh.indices {
+        *index = remap[*index as usize];
+    }
+    mesh.vertices = vertices;
+}
+
+fn vertex_bits(vertex: &Vertex) -> [u32; 11] {
+    let (p, n, t, c) = (
+        vertex.position,
+        vertex.normal,
+        vertex.tex_coords,
+        vertex.color,
+    );
+    [p.x, p.y, p.z, n.x, n.y, n.z, t.x, t.y, c.x, c.y, c.z].map(f32::to_bits)
+}
+
+/// Writes the geometry and materials of `obj` to the cache. The textures
+/// aren't part of it, only the files they're read from.
+pub fn store(source: &Path, obj: &Object) {
+    let Some(stamp) = Stamp::of(source) else {
+        return;
+    };
+    let path = cache_path(source);
+    if let Err(e) = write(&path, stamp, obj) {
+        warn!("Failed to cache the geometry of {:?}: {}", source, e);
+        let _ = std::fs::remove_file(path.with_extension("partial"));
+    }
+}
+
+fn write(path: &Path, stamp: Stamp, obj: &Object) -> std::io::Result<()> {
+    let dependencies = obj
+        .material_libs
+        .iter()
+        .chain(obj.texture_files.values())
+        .map(|path| (path.clone(), Stamp::of(path)))
+        .collect();
+    let header = Header {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        vertex_size: std::mem::size_of::<Vertex>() as u32,
+        source: Some(stamp),
+        dependencies,
+        name: obj.name.clone(),
+        aabb: [
+            [obj.aabb.min.x, obj.aabb.min.y, obj.aabb.min.z],
+            [obj.aabb.max.x, obj.aabb.max.y, obj.aabb.max.z],
+        ],
+        units: obj.units,
+        material_libs: obj.material_libs.clone(),
+        texture_files: obj.texture_files.clone(),
+        meshes: obj
+            .meshes
+            .iter()
+            .map(|mesh| CachedMesh {
+                name: mesh.name.clone(),
+                vertex_count: mesh.vertices.len() as u64,
+                index_count: mesh.indices.len() as u64,
+                materials: mesh
+                    .materials
+                    .iter()
+                    .map(|(start, material)| (*start, material.as_ref().map(Into::into)))
+                    .collect(),
+            })
+            .collect(),
+    };
+    let header = bincode::serialize(&header).map_err(std::io::Error::other)?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // written under another name first so a crash never leaves half a cache behind
+    let partial = path.with_extension("partial");
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    writer.write_all(&(header.len() as u64).to_le_bytes())?;
+    writer.write_all(&header)?;
+    for mesh in &obj.meshes {
+        writer.write_all(as_bytes(&mesh.vertices))?;
+        writer.write_all(as_bytes(&mesh.indices))?;
+    }
+    writer.flush()?;
+    drop(writer);
+
+    std::fs::rename(partial, path)
+}
+
+/// The cached model, if there is one and neither the file nor anything it
+/// references changed since it was cached
+pub fn load(source: &Path) -> Option<Object> {
+    let path = cache_path(source);
+    let file = File::open(&path).ok()?;
+    let now = std::time::Instant::now();
+
+    // SAFETY: caches are only ever replaced by renaming a new file over them,
+    // the mapped one isn't changed while the meshes are copied out of it
+    let map = unsafe { Mmap::map(&file) }.ok()?;
+    let mut bytes = &map[..];
+    let header = match read_header(&mut bytes) {
+        Ok(header) => header,
+        Err(e) => {
+            trace!("Ignoring the geometry cache {:?}: {}", path, e);
+            return None;
+        }
+    };
+
+    let stale = header.app_version != env!("CARGO_PKG_VERSION")
+        || header.vertex_size != std::mem::size_of::<Vertex>() as u32
+        || header.source.is_none()
+        || header.source != Stamp::of(source)
+        || header
+            .dependencies
+            .iter()
+            .any(|(path, stamp)| *stamp != Stamp::of(path));
+    if stale {
+        trace!("The geometry cache of {:?} is out of date", source);
+        return None;
+    }
+
+    let meshes = match read_meshes(&mut bytes, header.meshes) {
+        Ok(meshes) => meshes,
+        Err(e) => {
+            warn!("The geometry cache {:?} is corrupt: {}", path, e);
+            return None;
+        }
+    };
+    trace!(
+        "Loaded {:?} from the geometry cache in {}ms",
+        source,
+        now.elapsed().as_millis()
+    );
+
+    let [min, max] = header.aabb;
+
+    Some(Object {
+        name: header.name,
+        meshes,
+        aabb: AABB::new(
+            glm::vec3(min[0], min[1], min[2]),
+            glm::vec3(max[0], max[1], max[2]),
+        ),
+        units: header.units,
+        colors: None,
+        material_libs: header.material_libs,
+        texture_files: header.texture_files,
+    })
+}
+
+fn read_header(bytes: &mut &[u8]) -> Result<Header, Error> {
+    let magic = take(bytes, MAGIC.len())?;
+    let version = take(bytes, 4)?;
+    if magic != MAGIC || version != FORMAT_VERSION.to_le_bytes() {
+        return Err("not a geometry cache of this version".into());
+    }
+
+    let len = read_len(&mut take(bytes, 8)?)?;
+    Ok(bincode::deserialize(take(bytes, len)?)?)
+}
+
+/// The vertices and indices that follow the header. They're checked against
+/// each other since the GPU would read past the buffers otherwise.
+fn read_meshes(bytes: &mut &[u8], meshes: Vec<CachedMesh>) -> Result<Vec<ObjMesh>, Error> {
+    meshes
+        .into_iter()
+        .map(|mesh| {
+            let vertices: Vec<Vertex> = copy_slice(bytes, mesh.vertex_count)?;
+            let indices: Vec<u32> = copy_slice(bytes, mesh.index_count)?;
+            if let Some(index) = indices.iter().find(|&&i| i as usize >= vertices.len()) {
+                return Err(format!(
+                    "the mesh {:?} uses vertex {} of {}",
+                    mesh.name,
+                    index,
+                    vertices.len()
+                )
+                .into());
+            }
+            // each material goes up to where the next one starts
+            let starts = mesh.materials.iter().map(|(start, _)| *start);
+            let ends = starts.clone().skip(1).chain([indices.len()]);
+            if starts.zip(ends).any(|(start, end)| start > end) {
+                return Err(format!(
+                    "the materials of the mesh {:?} don't fit in its {} indices",
+                    mesh.name,
+                    indices.len()
+                )
+                .into());
+            }
+
+            Ok(ObjMesh {
+                name: mesh.name,
+                vertices,
+                indices,
+                materials: mesh
+                    .materials
+                    .into_iter()
+                    .map(|(start, material)| (start, material.map(Into::into)))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Splits the first `len` bytes off
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    let (taken, rest) = bytes.split_at_checked(len).ok_or("the file ends early")?;
+    *bytes = rest;
+    Ok(taken)
+}
+
+/// Reads a length, like the size of a header or the number of vertices
+fn read_len(reader: &mut impl Read) -> Result<usize, Error> {
+    let mut len = [0; 8];
+    reader.read_exact(&mut len)?;
+
+    usize::try_from(u64::from_le_bytes(len)).map_err(|_| "length out of range".into())
+}
+
+/// Types made only of numbers without any padding, so their bytes can be
+/// written as they are and any bytes of the right length are a valid value
+///
+/// # Safety
+///
+/// Implementors must have no padding, pointers or invalid bit patterns.
+unsafe trait Plain: Clone {}
+
+unsafe impl Plain for u8 {}
+unsafe impl Plain for u16 {}
+unsafe impl Plain for u32 {}
+// packed floats
+unsafe impl Plain for Vertex {}
+const _: () = assert!(std::mem::size_of::<Vertex>() == 11 * std::mem::size_of::<f32>());
+
+fn as_bytes<T: Plain>(items: &[T]) -> &[u8] {
+    // SAFETY: `Plain` types have no padding, every byte is initialized
+    unsafe { std::slice::from_raw_parts(items.as_ptr() as *const u8, std::mem::size_of_val(items)) }
+}
+
+/// Copies `count` items off the front of `bytes`, which don't have to be
+/// aligned for `T`. Fails without allocating anything if there aren't that
+/// many.
+fn copy_slice<T: Plain>(bytes: &mut &[u8], count: u64) -> Result<Vec<T>, Error> {
+    let len = usize::try_from(count)
+        .ok()
+        .and_then(|count| count.checked_mul(std::mem::size_of::<T>()));
+    let Some(len) = len.filter(|len| *len <= bytes.len()) else {
+        return Err(format!("{} items don't fit in what's left of the file", count).into());
+    };
+
+    let count = count as usize;
+    let mut items = Vec::<T>::with_capacity(count);
+    // SAFETY: the bytes fill the capacity exactly and any bytes are a valid
+    // value of a `Plain` type
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), items.as_mut_ptr() as *mut u8, len);
+        items.set_len(count);
+    }
+    *bytes = &bytes[len..];
+    Ok(items)
+}
+
+/// Reads `count` items straight into their final buffer. Fails without
+/// allocating anything if there aren't that many left in `reader`.
+fn read_slice<T: Plain>(
+    reader: &mut Take<impl Read>,
+    count: usize,
+    zero: T,
+) -> Result<Vec<T>, Error> {
+    let fits = (count as u64)
+        .checked_mul(std::mem::size_of::<T>() as u64)
+        .is_some_and(|bytes| bytes <= reader.limit());
+    if !fits {
+        return Err(format!("{} items don't fit in what's left of the file", count).into());
+    }
+
+    let mut items = vec![zero; count];
+    // SAFETY: any bytes are a valid value of a `Plain` type
+    let bytes = unsafe {
+        std::slice::from_raw_parts_mut(
+            items.as_mut_ptr() as *mut u8,
+            std::mem::size_of_val(items.as_slice()),
+        )
+    };
+    reader.read_exact(bytes)?;
+    Ok(items)
+}
+
+/// Geometry parsed before an import was cancelled or crashed, read back from
+/// its checkpoint. The attributes are the STL ones of each vertex.
+#[derive(Debug, Default)]
+pub struct Resumed {
+    pub triangles: u64,
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub attributes: Vec<u16>,
+}
+
+/// The progress of a big file being parsed, saved every so often so an
+/// import that was cancelled or crashed picks up where it stopped instead of
+/// starting over. Every save appends the geometry parsed since the last one
+/// as a chunk, a chunk cut short by a crash is dropped when it's resumed.
+pub struct Checkpoint {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    triangles: u64,
+    vertices: usize,
+    indices: usize,
+}
+
+impl Checkpoint {
+    /// Opens the checkpoint of `source` along with the geometry saved in it,
+    /// if it was saved for the file as it is now. None if it can't be written.
+    pub fn open(source: &Path) -> Option<(Self, Resumed)> {
+        let stamp = Stamp::of(source)?;
+        let path = cache_path(source).with_extension("checkpoint");
+        let (resumed, len) = File::open(&path)
+            .ok()
+            .and_then(|file| {
+                let len = file.metadata().ok()?.len();
+                read_checkpoint(&mut BufReader::new(file).take(len), &stamp)
+            })
+            .unwrap_or_default();
+
+        std::fs::create_dir_all(path.parent()?).ok()?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .ok()?;
+        file.set_len(len).ok()?;
+        file.seek(SeekFrom::End(0)).ok()?;
+        let mut writer = BufWriter::new(file);
+
+        if len == 0 {
+            let header = CheckpointHeader {
+                app_version: env!("CARGO_PKG_VERSION").to_string(),
+                vertex_size: std::mem::size_of::<Vertex>() as u32,
+                source: stamp,
+            };
+            let header = bincode::serialize(&header).ok()?;
+            writer.write_all(CHECKPOINT_MAGIC).ok()?;
+            writer.write_all(&FORMAT_VERSION.to_le_bytes()).ok()?;
+            writer
+                .write_all(&(header.len() as u64).to_le_bytes())
+                .ok()?;
+            writer.write_all(&header).ok()?;
+            writer.flush().ok()?;
+        } else {
+            info!(
+                "Resuming the import of {:?} after {} triangles",
+                source, resumed.triangles
+            );
+        }
+
+        let checkpoint = Checkpoint {
+            path,
+            writer,
+            triangles: resumed.triangles,
+            vertices: resumed.vertices.len(),
+            indices: resumed.indices.len(),
+        };
+        Some((checkpoint, resumed))
+    }
+
+    /// Saves what was parsed since the last save. The vertices, indices and
+    /// attributes are all of them so far, `triangles` is how many were read.
+    pub fn save(
+        &mut self,
+        triangles: u64,
+        vertices: &[Vertex],
+        indices: &[u32],
+        attributes: &[u16],
+    ) -> std::io::Result<()> {
+        let (vertices, attributes) = (&vertices[self.vertices..], &attributes[self.vertices..]);
+        let indices = &indices[self.indices..];
+
+        for count in [
+            triangles - self.triangles,
+            vertices.len() as u64,
+            indices.len() as u64,
+        ] {
+            self.writer.write_all(&count.to_le_bytes())?;
+        }
+        self.writer.write_all(as_bytes(vertices))?;
+        self.writer.write_all(as_bytes(indices))?;
+        self.writer.write_all(as_bytes(attributes))?;
+        self.writer.flush()?;
+
+        self.triangles = triangles;
+        self.vertices += vertices.len();
+        self.indices += indices.len();
+        Ok(())
+    }
+
+    /// Deletes the checkpoint once the file is parsed
+    pub fn finish(self) {
+        drop(self.writer);
+        let _ = std::fs::remove_file(self.path);
+    }
+}
+
+/// The geometry of a checkpoint and the length of the file up to its last
+/// whole chunk. None if it's for another version of the file or the app.
+fn read_checkpoint(reader: &mut Take<impl Read>, stamp: &Stamp) -> Option<(Resumed, u64)> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic).ok()?;
+    let mut version = [0; 4];
+    reader.read_exact(&mut version).ok()?;
+    if &magic != CHECKPOINT_MAGIC || u32::from_le_bytes(version) != FORMAT_VERSION {
+        return None;
+    }
+    let size = read_len(reader).ok()?;
+    let header = read_slice(reader, size, 0u8).ok()?;
+    let header_len = header.len() as u64;
+    let header: CheckpointHeader = bincode::deserialize(&header).ok()?;
+    if header.app_version != env!("CARGO_PKG_VERSION")
+        || header.vertex_size != std::mem::size_of::<Vertex>() as u32
+        || header.source != *stamp
+    {
+        return None;
+    }
+
+    let mut resumed = Resumed::default();
+    let mut len = 8 + 4 + 8 + header_len;
+    let zero = glm::vec3(0.0, 0.0, 0.0);
+    let empty = Vertex {
+        position: zero,
+        normal: zero,
+        tex_coords: glm::vec2(0.0, 0.0),
+        color: zero,
+    };
+    loop {
+        let mut counts = [0usize; 3];
+        for count in &mut counts {
+            match read_len(reader) {
+                Ok(read) => *count = read,
+                Err(_) => return Some((resumed, len)),
+            }
+        }
+        let [triangles, vertex_count, index_count] = counts;
+        let vertices = read_slice(reader, vertex_count, empty.clone());
+        let indices = read_slice(reader, index_count, 0u32);
+        let attributes = read_slice(reader, vertex_count, 0u16);
+        let (Ok(vertices), Ok(indices), Ok(attributes)) = (vertices, indices, attributes) else {
+            return Some((resumed, len));
+        };
+
+        len += 3 * 8
+            + std::mem::size_of_val(vertices.as_slice()) as u64
+            + std::mem::size_of_val(indices.as_slice()) as u64
+            + std::mem::size_of_val(attributes.as_slice()) as u64;
+        resumed.triangles += triangles as u64;
+        resumed.vertices.extend(vertices);
+        resumed.indices.extend(indices);
+        resumed.attributes.extend(attributes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads back a mesh of three vertices with `indices` and materials
+    /// starting at `starts`
+    fn read_mesh(indices: &[u32], starts: &[usize]) -> Result<Vec<ObjMesh>, Error> {
+        let zero = glm::vec3(0.0, 0.0, 0.0);
+        let vertex = Vertex {
+            position: zero,
+            normal: zero,
+            tex_coords: glm::vec2(0.0, 0.0),
+            color: zero,
+        };
+        let vertices = vec![vertex; 3];
+        // off by one byte so nothing is aligned
+        let mut bytes = vec![0];
+        bytes.extend_from_slice(as_bytes(&vertices));
+        bytes.extend_from_slice(as_bytes(indices));
+
+        let mesh = CachedMesh {
+            name: "mesh".to_string(),
+            vertex_count: vertices.len() as u64,
+            index_count: indices.len() as u64,
+            materials: starts.iter().map(|start| (*start, None)).collect(),
+        };
+        read_meshes(&mut &bytes[1..], vec![mesh])
+    }
+
+    #[test]
+    fn meshes_are_read_back() {
+        let meshes = read_mesh(&[0, 1, 2, 2, 1, 0], &[0, 3]).unwrap();
+        assert_eq!(meshes[0].vertices.len(), 3);
+        assert_eq!(meshes[0].indices, [0, 1, 2, 2, 1, 0]);
+    }
+
+    #[test]
+    fn indices_past_the_vertices_are_rejected() {
+        assert!(read_mesh(&[0, 1, 3], &[0]).is_err());
+    }
+
+    #[test]
+    fn materials_past_the_indices_are_rejected() {
+        assert!(read_mesh(&[0, 1, 2], &[0, 4]).is_err());
+        assert!(read_mesh(&[0, 1, 2], &[2, 1]).is_err());
+    }
+
+    #[test]
+    fn counts_past_the_end_are_rejected() {
+        let mesh = CachedMesh {
+            name: "mesh".to_string(),
+            vertex_count: u64::MAX,
+            index_count: 0,
+            materials: Vec::new(),
+        };
+        assert!(read_meshes(&mut &[0u8; 64][..], vec![mesh]).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aabb::AABB,
+    importer::{registry, ObjMesh, Object},
+    ui::i18n::tr,
+};
+
+/// The axis that points up in a file, models are turned so it's +Y
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UpAxis {
+    X,
+    #[default]
+    Y,
+    Z,
+}
+
+impl UpAxis {
+    pub const ALL: [UpAxis; 3] = [UpAxis::X, UpAxis::Y, UpAxis::Z];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UpAxis::X => tr("import_options.x_up"),
+            UpAxis::Y => tr("import_options.y_up"),
+            UpAxis::Z => tr("import_options.z_up"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Handedness {
+    #[default]
+    Right,
+    Left,
+}
+
+/// How the coordinates of a file are laid out. Models are converted to the
+/// viewer's own right-handed, Y-up system when they're imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CoordinateSystem {
+    pub up: UpAxis,
+    pub handedness: Handedness,
+}
+
+impl CoordinateSystem {
+    /// The viewer's own system, converting from it does nothing
+    pub const NATIVE: CoordinateSystem = CoordinateSystem {
+        up: UpAxis::Y,
+        handedness: Handedness::Right,
+    };
+    /// What CAD programs and slicers use
+    pub const Z_UP: CoordinateSystem = CoordinateSystem {
+        up: UpAxis::Z,
+        handedness: Handedness::Right,
+    };
+
+    /// The system files of a format are usually in, heightmaps are Y-up
+    pub fn of_file(path: &Path) -> Self {
+        match registry::detect(path) {
+            Ok(Some(importer)) => importer.coordinate_system(),
+            _ => Self::NATIVE,
+        }
+    }
+
+    /// Whether faces have to be wound the other way round after converting
+    fn mirrors(&self) -> bool {
+        self.handedness == Handedness::Left
+    }
+
+    /// Turns a position or direction in this system into the native one
+    pub fn to_native(&self, v: glm::Vec3) -> glm::Vec3 {
+        let v = match self.up {
+            UpAxis::X => glm::vec3(-v.y, v.x, v.z),
+            UpAxis::Y => v,
+            UpAxis::Z => glm::vec3(v.x, v.z, -v.y),
+        };
+        if self.mirrors() {
+            glm::vec3(v.x, v.y, -v.z)
+        } else {
+            v
+        }
+    }
+
+    /// Converts the positions and normals of every mesh and fixes the winding
+    /// of the faces if the system was left-handed. The bounding box is
+    /// recomputed.
+    pub fn convert(&self, obj: &mut Object) {
+        if *self == Self::NATIVE {
+            return;
+        }
+
+        for mesh in &mut obj.meshes {
+            for vertex in &mut mesh.vertices {
+                vertex.position = self.to_native(vertex.position);
+                vertex.normal = self.to_native(vertex.normal);
+            }
+            if self.mirrors() {
+                for triangle in mesh.indices.chunks_exact_mut(3) {
+                    triangle.swap(1, 2);
+                }
+            }
+        }
+        if let Some(aabb) = bounds(&obj.meshes) {
+            obj.aabb = aabb;
+        }
+    }
+}
+
+/// The box around every vertex of the meshes, `None` if they have none
+pub fn bounds(meshes: &[ObjMesh]) -> Option<AABB> {
+    let mut positions = meshes
+        .iter()
+        .flat_map(|mesh| mesh.vertices.iter().map(|v| v.position));
+    let first = positions.next()?;
+    let (min, max) = positions.fold((first, first), |(min, max), p| {
+        (
+            glm::vec3(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+            glm::vec3(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+        )
+    });
+    Some(AABB::new(min, max))
+}
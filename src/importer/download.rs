@@ -0,0 +1,291 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    ffi::OsStr,
+    hash::{Hash, Hasher},
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::atomic::Ordering,
+    time::Duration,
+};
+
+use log::warn;
+
+use super::{obj, JobProgress};
+
+/// Texture statements in MTL files, the file name follows the options
+const MTL_TEXTURE_STATEMENTS: [&str; 12] = [
+    "map_Ka", "map_Kd", "map_Ks", "map_Ns", "map_d", "map_bump", "bump", "map_Ke", "disp", "decal",
+    "refl", "norm",
+];
+
+pub fn is_url(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://")
+}
+
+/// Splits a URL into the part up to the last `/` and the file name, without
+/// the query or fragment
+fn split_url(url: &str) -> (&str, &str) {
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+    url.rsplit_once('/').unwrap_or((url, ""))
+}
+
+pub fn file_name(url: &str) -> &str {
+    split_url(url).1
+}
+
+/// Where a downloaded model is kept. Files from the same directory on the
+/// server end up in the same directory locally, so relative references
+/// between them keep working.
+pub fn cache_path(url: &str) -> PathBuf {
+    let (base, name) = split_url(url);
+    let mut hasher = DefaultHasher::new();
+    base.hash(&mut hasher);
+
+    cache_dir()
+        .join(format!("{:016x}", hasher.finish()))
+        .join(name)
+}
+
+/// Where downloaded models are kept
+pub fn cache_dir() -> PathBuf {
+    std::env::temp_dir().join("3dobs-downloads")
+}
+
+/// Runs curl with `args`. The token is handed over through stdin so it
+/// doesn't show up in the process list.
+fn spawn_curl<I, S>(args: I, token: Option<&str>) -> std::io::Result<Child>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new("curl");
+    command
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if token.is_some() {
+        command.args(["-H", "@-"]).stdin(Stdio::piped());
+    }
+
+    let mut child = command.spawn()?;
+    if let (Some(token), Some(mut stdin)) = (token, child.stdin.take()) {
+        // curl reads the headers until stdin is closed when it's dropped here
+        writeln!(stdin, "Authorization: Bearer {}", token)?;
+    }
+
+    Ok(child)
+}
+
+/// Downloads `url` into memory, for small things like API responses and thumbnails
+pub fn get(url: &str, token: Option<&str>) -> Result<Vec<u8>, String> {
+    let output = spawn_curl(["-sSL", "--fail", url], token)
+        .and_then(|child| child.wait_with_output())
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Request failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Asks the server for the size of the file, 0 if it doesn't say
+fn content_length(url: &str, token: Option<&str>) -> u64 {
+    let Ok(output) =
+        spawn_curl(["-sIL", "--fail", url], token).and_then(|child| child.wait_with_output())
+    else {
+        return 0;
+    };
+
+    // with redirects every response has its own headers, the last one counts
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .filter_map(|(_, value)| value.trim().parse().ok())
+        .next_back()
+        .unwrap_or(0)
+}
+
+/// Downloads `url` to `dest` with curl, updating `progress` while it runs.
+/// The download is stopped as soon as the job is cancelled.
+fn fetch(
+    url: &str,
+    dest: &Path,
+    token: Option<&str>,
+    progress: &JobProgress,
+) -> Result<(), String> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    progress.downloaded.store(0, Ordering::Relaxed);
+    progress
+        .total
+        .store(content_length(url, token), Ordering::Relaxed);
+
+    let args = [OsStr::new("-sSL"), OsStr::new("--fail"), OsStr::new("-o")];
+    let mut child = spawn_curl(
+        args.into_iter().chain([dest.as_os_str(), OsStr::new(url)]),
+        token,
+    )
+    .map_err(|e| format!("Failed to run curl: {}", e))?;
+
+    let status = loop {
+        if progress.cancelled.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = std::fs::remove_file(dest);
+            return Err("Download cancelled".to_string());
+        }
+
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+
+        if let Ok(metadata) = std::fs::metadata(dest) {
+            progress.downloaded.store(metadata.len(), Ordering::Relaxed);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+
+    if !status.success() {
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr);
+        }
+        let _ = std::fs::remove_file(dest);
+        return Err(format!("Failed to download {}: {}", url, stderr.trim()));
+    }
+
+    Ok(())
+}
+
+/// The lines of an OBJ or MTL file that start with one of `statements`
+fn statement_lines(path: &Path, statements: &[&str]) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return vec![];
+    };
+
+    content
+        .lines()
+        .filter(|line| {
+            let statement = line.split_whitespace().next();
+            statement.is_some_and(|statement| statements.contains(&statement))
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// A file name `referrer` uses, as a path relative to it. None for files
+/// that don't need to be or mustn't be downloaded.
+fn reference(name: &str, referrer: &Path) -> Option<String> {
+    let name = name.replace('\\', "/");
+    // embedded textures don't need to be downloaded
+    if name.is_empty() || name.starts_with("data:") {
+        return None;
+    }
+    // the server decides these names, don't let them point outside the cache
+    let stays_inside = Path::new(&name)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !stays_inside {
+        warn!(
+            "Skipping download of \"{}\" referenced by {:?}",
+            name, referrer
+        );
+        return None;
+    }
+
+    Some(name)
+}
+
+/// Downloads the model at `url` to `dest`. For OBJ files the MTL files and
+/// textures next to it on the server are downloaded too, as far as they exist.
+pub fn download_model(
+    url: &str,
+    dest: &Path,
+    token: Option<&str>,
+    progress: &JobProgress,
+) -> Result<(), String> {
+    progress.downloading.store(true, Ordering::Relaxed);
+    let result = download_with_siblings(url, dest, token, progress);
+    progress.downloading.store(false, Ordering::Relaxed);
+
+    result
+}
+
+fn download_with_siblings(
+    url: &str,
+    dest: &Path,
+    token: Option<&str>,
+    progress: &JobProgress,
+) -> Result<(), String> {
+    fetch(url, dest, token, progress)?;
+
+    let is_obj = dest
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("obj"));
+    if !is_obj {
+        return Ok(());
+    }
+
+    let (base_url, _) = split_url(url);
+    let dir = dest.parent().unwrap_or(Path::new("."));
+
+    for line in statement_lines(dest, &["mtllib"]) {
+        // the importer reads the whole rest of a line with spaces as one name
+        // if there's such a file, here that's if the server has it
+        let mut whole = None;
+        let mtls = obj::material_lib_names(&line, |name| {
+            whole = reference(name, dest).filter(|mtl| {
+                let url = format!("{}/{}", base_url, mtl);
+                fetch(&url, &dir.join(mtl), token, progress).is_ok()
+            });
+            whole.is_some()
+        });
+        if progress.cancelled.load(Ordering::Relaxed) {
+            return Err("Download cancelled".to_string());
+        }
+
+        for mtl in mtls.into_iter().filter_map(|mtl| reference(mtl, dest)) {
+            let mtl_path = dir.join(&mtl);
+            if whole.as_ref() != Some(&mtl) {
+                let url = format!("{}/{}", base_url, mtl);
+                if let Err(e) = fetch(&url, &mtl_path, token, progress) {
+                    if progress.cancelled.load(Ordering::Relaxed) {
+                        return Err(e);
+                    }
+                    warn!("{}", e);
+                    continue;
+                }
+            }
+
+            // textures are relative to the MTL file
+            let mtl_dir = mtl_path.parent().unwrap_or(dir);
+            let mtl_dir_url = match mtl.rsplit_once('/') {
+                Some((mtl_dir, _)) => format!("{}/{}", base_url, mtl_dir),
+                None => base_url.to_string(),
+            };
+
+            let textures = statement_lines(&mtl_path, &MTL_TEXTURE_STATEMENTS);
+            let textures = (textures.iter())
+                .filter_map(|line| reference(obj::texture_file_name(line), &mtl_path));
+            for texture in textures {
+                let texture_url = format!("{}/{}", mtl_dir_url, texture);
+                if let Err(e) = fetch(&texture_url, &mtl_dir.join(&texture), token, progress) {
+                    if progress.cancelled.load(Ordering::Relaxed) {
+                        return Err(e);
+                    }
+                    warn!("{}", e);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
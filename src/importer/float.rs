@@ -0,0 +1,184 @@
+/// Parses a decimal float like `-1.25e-3` with fast-float, which reads
+/// eight digits at a time and is exact like `str::parse`. Most of the time
+/// spent on big text files goes here.
+pub fn parse_f32(s: &str) -> Option<f32> {
+    fast_float2::parse(s).ok()
+}
+
+/// Parses the first `N` floats of `text` straight from its bytes, skipping
+/// the whitespace between them instead of splitting the line into words
+/// first. None if there are fewer or one of them isn't a number, anything
+/// after the `N`th is ignored.
+pub fn parse_leading<const N: usize>(text: &str) -> Option<[f32; N]> {
+    let mut bytes = text.as_bytes();
+    let mut floats = [0.0; N];
+    for float in &mut floats {
+        let start = bytes.iter().position(|b| !b.is_ascii_whitespace())?;
+        bytes = &bytes[start..];
+        let (value, len) = fast_float2::parse_partial(bytes).ok()?;
+        // `1.5x` or `1,5` isn't a number
+        if bytes.get(len).is_some_and(|b| !b.is_ascii_whitespace()) {
+            return None;
+        }
+        *float = value;
+        bytes = &bytes[len..];
+    }
+    Some(floats)
+}
+
+/// Parses the first `N` whitespace separated floats of `tokens`, none if
+/// there are fewer or one of them isn't a number
+pub fn parse_floats<'a, const N: usize>(
+    tokens: impl IntoIterator<Item = &'a str>,
+) -> Option<[f32; N]> {
+    let mut tokens = tokens.into_iter();
+    let mut floats = [0.0; N];
+    for float in &mut floats {
+        *float = parse_f32(tokens.next()?)?;
+    }
+    Some(floats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses like the standard library, down to the bits and the sign of zero
+    fn check(s: &str) {
+        let expected = s.parse::<f32>().ok();
+        let parsed = parse_f32(s);
+        assert_eq!(
+            parsed.map(f32::to_bits),
+            expected.map(f32::to_bits),
+            "{:?} parsed as {:?} instead of {:?}",
+            s,
+            parsed,
+            expected
+        );
+    }
+
+    #[test]
+    fn simple_numbers() {
+        let numbers = "0 -0 +0 0.0 -0.0 1 -1 +1.5 0.1 -1.25e-3 3. .5 -.5 1E5 1e+5 00012.5000 \
+                       0.000001 123456.789";
+        numbers.split_whitespace().for_each(check);
+    }
+
+    #[test]
+    fn halfway_between_two_f32s() {
+        for s in [
+            // 2^24 + 1 and 2^24 + 3, ties to even go down and up
+            "16777217",
+            "16777219",
+            // 1 + 2^-24, right between 1 and the next f32
+            "1.000000059604644775390625",
+            "1.0000000596046448",
+            "1.0000000596046447",
+            // 2^-126 * (1 + 2^-24)
+            "1.1754944608521041e-38",
+            "0.50000002980232238769531",
+        ] {
+            check(s);
+        }
+    }
+
+    #[test]
+    fn long_mantissas() {
+        for s in [
+            "1234567890123456789",
+            "12345678901234567890",
+            "0.1234567890123456789",
+            "0.12345678901234567890123",
+            "9007199254740992",
+            "9007199254740993",
+            "000000000000000000000000001.5",
+            "3.14159265358979323846264338327950288",
+        ] {
+            check(s);
+        }
+    }
+
+    #[test]
+    fn exponents_around_the_exact_powers_of_ten() {
+        for exponent in 18..=26 {
+            for mantissa in ["1", "7", "9.5", "123456789"] {
+                check(&format!("{}e{}", mantissa, exponent));
+                check(&format!("{}e-{}", mantissa, exponent));
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_the_normal_range() {
+        for s in [
+            "3.4028235e38",
+            "3.4028236e38",
+            "1e39",
+            "1.17549435e-38",
+            "1e-40",
+            "1e-46",
+            "-1e-46",
+            "1e-400",
+            "1e400",
+        ] {
+            check(s);
+        }
+    }
+
+    #[test]
+    fn special_and_malformed() {
+        let tokens = "inf -inf nan NaN infinity - + . -. e5 1e 1e+ 1.2.3 1-2 0x10 1,5 --1";
+        tokens.split_whitespace().for_each(check);
+        for s in ["", "1 ", " 1"] {
+            check(s);
+        }
+    }
+
+    #[test]
+    fn random_coordinates() {
+        // xorshift, so the test is the same every run
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..200_000 {
+            let bits = next();
+            let digits = (bits % 10) as usize;
+            let s = format!(
+                "{}{}.{:0width$}e{}",
+                if bits & 1 == 0 { "-" } else { "" },
+                (bits >> 8) % 100_000,
+                (bits >> 32) % 10u64.pow(digits as u32),
+                ((bits >> 24) % 60) as i32 - 30,
+                width = digits
+            );
+            check(&s);
+            // and the bits of an f32 printed the shortest way
+            check(&f32::from_bits(next() as u32).to_string());
+        }
+    }
+
+    #[test]
+    fn leading_floats_of_a_line() {
+        assert_eq!(parse_leading("1 -2 3.5"), Some([1.0, -2.0, 3.5]));
+        assert_eq!(parse_leading("  1\t-2   3.5  "), Some([1.0, -2.0, 3.5]));
+        // the w of a vertex is left over
+        assert_eq!(parse_leading("1 2 3 1"), Some([1.0, 2.0, 3.0]));
+        assert_eq!(parse_leading::<3>("1 2"), None);
+        assert_eq!(parse_leading::<3>("1 2 3x"), None);
+        assert_eq!(parse_leading::<2>("1,5 2"), None);
+        assert_eq!(parse_leading::<1>(""), None);
+    }
+
+    #[test]
+    fn several_floats() {
+        assert_eq!(parse_floats("1 -2 3.5".split(' ')), Some([1.0, -2.0, 3.5]));
+        assert_eq!(parse_floats::<3>("1 2".split(' ')), None);
+        assert_eq!(parse_floats::<2>("1 x".split(' ')), None);
+        // the rest is left alone
+        assert_eq!(parse_floats("1 2 3 4".split(' ')), Some([1.0, 2.0]));
+    }
+}
@@ -0,0 +1,116 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    aabb::AABB,
+    error::Result,
+    importer::{Material, ObjMesh, Object},
+    mesh::Vertex,
+    utils,
+};
+
+/// Image formats that can be opened as heightmaps
+pub const EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "tif", "tiff", "bmp"];
+
+/// How a heightmap is turned into a mesh
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    /// Most vertices along a side, bigger images are downscaled to this
+    pub resolution: u32,
+    /// Height of white pixels, relative to the longer side of the terrain
+    pub height_scale: f32,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            resolution: 256,
+            height_scale: 0.2,
+        }
+    }
+}
+
+/// Builds a terrain from a grayscale image, one vertex per pixel with
+/// brighter pixels being higher. The terrain lies on the XZ plane with the
+/// image's top at -Z.
+pub fn load_heightmap(path: &Path, options: Options) -> Result<Object> {
+    let image = utils::decode_texture(path.to_path_buf())?;
+    let resolution = options.resolution.max(2);
+    let image = if image.width() > resolution || image.height() > resolution {
+        image.resize(
+            resolution,
+            resolution,
+            image::imageops::FilterType::Triangle,
+        )
+    } else {
+        image
+    };
+    let heights = image.to_luma32f();
+    let (width, depth) = heights.dimensions();
+    if width < 2 || depth < 2 {
+        return Err("The heightmap must be at least 2 pixels wide and tall".into());
+    }
+
+    // the longer side spans 1 unit, the model is normalized when loaded anyway
+    let spacing = 1.0 / (width.max(depth) - 1) as f32;
+    let height = |x: u32, z: u32| heights.get_pixel(x, z).0[0] * options.height_scale;
+    let position = |x: u32, z: u32| {
+        glm::vec3(
+            (x as f32 - (width - 1) as f32 / 2.0) * spacing,
+            height(x, z),
+            (z as f32 - (depth - 1) as f32 / 2.0) * spacing,
+        )
+    };
+
+    let mut vertices = Vec::with_capacity((width * depth) as usize);
+    let mut min_aabb = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
+    let mut max_aabb = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
+    for z in 0..depth {
+        for x in 0..width {
+            let p = position(x, z);
+            // slopes from the neighboring pixels, one sided at the edges
+            let dx = position((x + 1).min(width - 1), z) - position(x.saturating_sub(1), z);
+            let dz = position(x, (z + 1).min(depth - 1)) - position(x, z.saturating_sub(1));
+            let normal = glm::normalize(glm::cross(dz, dx));
+            let tex_coords = glm::vec2(
+                x as f32 / (width - 1) as f32,
+                1.0 - z as f32 / (depth - 1) as f32,
+            );
+
+            vertices.push(Vertex::new(p, normal, tex_coords));
+            min_aabb = glm::min(min_aabb, p);
+            max_aabb = glm::max(max_aabb, p);
+        }
+    }
+
+    let mut indices = Vec::with_capacity(((width - 1) * (depth - 1) * 6) as usize);
+    for z in 0..depth - 1 {
+        for x in 0..width - 1 {
+            let a = z * width + x;
+            let b = a + 1;
+            let c = a + width;
+            let d = c + 1;
+            // wound counter-clockwise seen from above
+            indices.extend_from_slice(&[a, c, b, b, c, d]);
+        }
+    }
+
+    let name = path
+        .file_stem()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "heightmap".to_string());
+
+    Ok(Object {
+        name: name.clone(),
+        meshes: vec![ObjMesh {
+            name,
+            vertices,
+            indices,
+            materials: vec![(0, Some(Material::default()))],
+        }],
+        aabb: AABB::new(min_aabb, max_aabb),
+        units: None,
+        colors: None,
+        material_libs: Vec::new(),
+        texture_files: HashMap::new(),
+    })
+}
@@ -1,14 +1,34 @@
+pub mod cache;
+pub mod coords;
+pub mod download;
+pub mod float;
+pub mod heightmap;
 mod obj;
-mod stl;
+pub mod options;
+pub mod registry;
+pub mod stl;
 
-use std::{path::Path, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    time::Instant,
+};
 
 use crate::{
     aabb::AABB,
+    error::{Error, Result},
     mesh::Vertex,
-    utils::{self, SupportedFileExtensions},
+    pacing,
+    ui::i18n::tr,
 };
 
+use self::{options::ImportOptions, registry::LoadContext};
+
 #[derive(Debug, Clone)]
 pub enum TextureType {
     Ambient,
@@ -37,6 +57,20 @@ impl TextureType {
             _ => None,
         }
     }
+
+    pub fn to_material_str(&self) -> &'static str {
+        match self {
+            TextureType::Ambient => "map_Ka",
+            TextureType::Diffuse => "map_Kd",
+            TextureType::Specular => "map_Ks",
+            TextureType::SpecularHighlight => "map_Ns",
+            TextureType::Bump => "map_bump",
+            TextureType::Displacement => "map_d",
+            TextureType::Decal => "decal",
+            TextureType::Reflection => "refl",
+            TextureType::Emissive => "map_Ke",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -103,27 +137,82 @@ impl std::fmt::Display for Material {
 
 #[derive(Clone, Debug)]
 pub struct Texture {
+    /// GL texture id, 0 until the image is uploaded on the main thread
     pub id: u32,
     pub typ: TextureType,
+    /// Key of the decoded image in `Object::textures`
+    pub name: String,
+    /// The file the image was decoded from, set once the model is added.
+    /// `None` for embedded textures and files that weren't found.
+    pub path: Option<PathBuf>,
+    pub options: TextureOptions,
 }
 
 impl Texture {
-    pub fn new(
-        path: std::path::PathBuf,
-        typ: TextureType,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let id = utils::load_texture(path)?;
+    pub fn new(name: String, typ: TextureType) -> Self {
+        Texture {
+            id: 0,
+            typ,
+            name,
+            path: None,
+            options: TextureOptions::default(),
+        }
+    }
+}
 
-        Ok(Texture { id, typ })
+/// Options of a texture statement in an MTL file, applied to the texture
+/// coordinates and values in the shader
+#[derive(Clone, Debug)]
+pub struct TextureOptions {
+    /// `-o`, added to the texture coordinates after scaling them
+    pub offset: glm::Vec2,
+    /// `-s`
+    pub scale: glm::Vec2,
+    /// `-clamp`, coordinates outside of 0-1 stick to the texture's edges
+    /// instead of repeating it
+    pub clamp: bool,
+    /// `-mm`, the texture's values are remapped to `base + gain * value`
+    pub base: f32,
+    pub gain: f32,
+    /// `-blendu` and `-blendv`, whether neighboring texels are blended along
+    /// each direction
+    pub blend_u: bool,
+    pub blend_v: bool,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            offset: glm::vec2(0.0, 0.0),
+            scale: glm::vec2(1.0, 1.0),
+            clamp: false,
+            base: 0.0,
+            gain: 1.0,
+            blend_u: true,
+            blend_v: true,
+        }
     }
 }
 
+/// An object of a file that can be imported without the rest of the file
+#[derive(Debug, Clone, Default)]
+pub struct Part {
+    /// Which object of the file it is, counting the ones without faces
+    pub index: usize,
+    pub name: String,
+    pub triangles: usize,
+    /// Roughly how many bytes the part takes once it's imported
+    pub size: usize,
+}
+
 #[derive(Debug)]
 pub struct ObjMesh {
     pub name: String,
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
-    pub material: Option<Material>,
+    /// The index each material starts at, a group that switches materials
+    /// stays a single mesh
+    pub materials: Vec<(usize, Option<Material>)>,
 }
 
 #[derive(Debug)]
@@ -131,23 +220,416 @@ pub struct Object {
     pub name: String,
     pub meshes: Vec<ObjMesh>,
     pub aabb: AABB,
+    /// Units the file says it's in, most formats don't say
+    pub units: Option<stl::Units>,
+    /// Colors of the faces of a binary STL, if it has any
+    pub colors: Option<stl::FaceColors>,
+    /// MTL files the OBJ pointed to in the order it lists them, including the
+    /// ones that couldn't be read
+    pub material_libs: Vec<PathBuf>,
+    /// The file of each texture the materials use, by the texture's name.
+    /// Embedded textures carry their image in their name instead.
+    pub texture_files: HashMap<String, PathBuf>,
+}
+
+/// Textures are skipped entirely when `load_textures` is false, which makes
+/// loading a lot faster when only the geometry is needed. The model is
+/// converted from the usual coordinate system of its format.
+pub fn load_from_file(path: &Path, load_textures: bool) -> Result<Object> {
+    let mut obj = read_file(path, load_textures, None)?;
+    coords::CoordinateSystem::of_file(path).convert(&mut obj);
+    Ok(obj)
 }
 
-pub fn load_from_file(path: &Path) -> Result<Object, Box<dyn std::error::Error>> {
-    let path_str = match path.to_str() {
-        Some(s) => s,
-        None => return Err("Failed to convert path to string".into()),
+/// The model the way the file has it, before any post-processing. Setting
+/// `cancelled` stops big binary STL files at their next checkpoint.
+fn read_file(
+    path: &Path,
+    load_textures: bool,
+    cancelled: Option<&AtomicBool>,
+) -> Result<Object> {
+    let file = std::fs::File::open(path).map_err(|e| Error::file(path, e))?;
+    // files without a known extension are told apart by their contents
+    let importer = registry::detect(path)
+        .map_err(|e| Error::file(path, e))?
+        .ok_or_else(|| Error::Unsupported {
+            path: path.to_path_buf(),
+            formats: registry::format_names(),
+        })?;
+
+    // the cached materials keep their textures, so it's only used for full loads
+    if load_textures {
+        if let Some(obj) = cache::load(path) {
+            return Ok(obj);
+        }
+    }
+
+    let ctx = LoadContext {
+        path,
+        load_textures,
+        parts: None,
+        cancelled,
     };
+    let mut obj = importer.load(file, &ctx)?;
 
-    let file = std::fs::File::open(path_str)?;
-    // TODO: if no extension, then test for binary STL magic bytes
-    // if no magic bytes, then try to guess based on the first line of text in the file
+    // which face color format to use can be changed afterwards, those files
+    // are parsed every time
+    if load_textures && obj.colors.is_none() {
+        for mesh in &mut obj.meshes {
+            cache::deduplicate(mesh);
+        }
+        cache::store(path, &obj);
+    }
 
-    let obj = match SupportedFileExtensions::from_str(path.extension().unwrap().to_str().unwrap())?
-    {
-        SupportedFileExtensions::STL => stl::load_stl(file)?,
-        SupportedFileExtensions::OBJ => obj::load_obj(path, file)?,
+    Ok(obj)
+}
+
+/// Lists the objects of a file that can be imported on their own, none for
+/// formats that only ever have one
+pub fn scan_parts(path: &Path) -> Result<Vec<Part>> {
+    let file = std::fs::File::open(path).map_err(|e| Error::file(path, e))?;
+    match registry::detect(path).map_err(|e| Error::file(path, e))? {
+        Some(importer) => importer.scan_parts(file),
+        None => Ok(vec![]),
+    }
+}
+
+/// Reads only the objects of a file in `parts`, by their index like
+/// `scan_parts` gives them. The bounding box only covers those objects, which
+/// are left in the file's coordinate system.
+pub fn load_parts(
+    path: &Path,
+    load_textures: bool,
+    parts: &HashSet<usize>,
+) -> Result<Object> {
+    let file = std::fs::File::open(path).map_err(|e| Error::file(path, e))?;
+    let importer = registry::detect(path)
+        .map_err(|e| Error::file(path, e))?
+        .filter(|importer| importer.has_parts())
+        .ok_or("The file doesn't have objects that can be imported on their own")?;
+    let ctx = LoadContext {
+        path,
+        load_textures,
+        parts: Some(parts),
+        cancelled: None,
     };
+    let mut obj = importer.load(file, &ctx)?;
+
+    obj.aabb = coords::bounds(&obj.meshes).ok_or("None of the picked objects have any faces")?;
 
     Ok(obj)
 }
+
+/// Reads the textures of the materials in `material_libs` for a model that
+/// was loaded without them, by material name. `texture_files` gets the file
+/// of each texture like `Object::texture_files`.
+pub fn load_material_textures(
+    material_libs: &[PathBuf],
+    texture_files: &mut HashMap<String, PathBuf>,
+) -> HashMap<String, Vec<Texture>> {
+    obj::load_material_textures(material_libs, texture_files)
+}
+
+/// Why a file couldn't be imported, made on the importer's thread since the
+/// errors themselves can't be sent to the main thread
+#[derive(Debug, Clone)]
+pub struct ImportError {
+    /// A short explanation for the user
+    pub reason: String,
+    /// Every error of the chain, the outermost first
+    pub details: String,
+}
+
+impl ImportError {
+    pub fn new(error: &(dyn std::error::Error + 'static)) -> Self {
+        let chain: Vec<&(dyn std::error::Error + 'static)> =
+            std::iter::successors(Some(error), |e| e.source()).collect();
+
+        // file errors are common and their messages aren't, so they're
+        // explained in the UI's language
+        let io_reason = chain.iter().find_map(|e| {
+            let kind = e.downcast_ref::<std::io::Error>()?.kind();
+            match kind {
+                std::io::ErrorKind::NotFound => Some(tr("import_error.not_found")),
+                std::io::ErrorKind::PermissionDenied => Some(tr("import_error.permission_denied")),
+                std::io::ErrorKind::UnexpectedEof => Some(tr("import_error.truncated")),
+                std::io::ErrorKind::InvalidData => Some(tr("import_error.invalid_data")),
+                _ => None,
+            }
+        });
+
+        // the importers' own errors say what's wrong and where
+        let reason = match error.downcast_ref::<Error>() {
+            Some(Error::Parse { line, message, .. }) => {
+                Some(format!("{} {}: {}", tr("import_error.line"), line, message))
+            }
+            Some(Error::Unsupported { .. }) => Some(tr("import_error.unsupported").to_string()),
+            Some(Error::Cancelled { resumable: true }) => {
+                Some(tr("import_error.cancelled_resumable").to_string())
+            }
+            Some(Error::Cancelled { resumable: false }) => {
+                Some(tr("import_error.cancelled").to_string())
+            }
+            _ => io_reason.map(str::to_string),
+        };
+
+        ImportError {
+            reason: reason.unwrap_or_else(|| error.to_string()),
+            details: chain
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+impl From<String> for ImportError {
+    fn from(reason: String) -> Self {
+        ImportError {
+            details: reason.clone(),
+            reason,
+        }
+    }
+}
+
+impl From<&str> for ImportError {
+    fn from(reason: &str) -> Self {
+        reason.to_string().into()
+    }
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.details.replace('\n', ": "))
+    }
+}
+
+/// State shared between an import job and its thread
+#[derive(Debug, Default)]
+pub struct JobProgress {
+    pub cancelled: AtomicBool,
+    pub downloading: AtomicBool,
+    pub downloaded: AtomicU64,
+    /// Size of the file being downloaded, 0 if the server didn't say
+    pub total: AtomicU64,
+}
+
+/// A model being loaded on a background thread
+pub struct ImportJob {
+    pub path: PathBuf,
+    pub name: String,
+    pub started: Instant,
+    /// Model that gets its geometry replaced once the job is done, None for new imports
+    pub reload: Option<u32>,
+    /// The file is an image that's turned into a terrain with these options
+    pub heightmap: Option<heightmap::Options>,
+    /// False for quick previews that skip the textures
+    pub load_textures: bool,
+    /// The objects of the file being imported, all of them if `None`
+    pub parts: Option<HashSet<usize>>,
+    /// Applied to the model once it's read, whichever importer reads it
+    pub options: ImportOptions,
+    progress: Arc<JobProgress>,
+    rx: Receiver<Result<Object, ImportError>>,
+}
+
+impl ImportJob {
+    /// Without textures only the geometry and the material colors are
+    /// imported, which is a lot faster for models with big textures
+    pub fn spawn(path: PathBuf, load_textures: bool, options: ImportOptions) -> Self {
+        Self::start(path, None, None, load_textures, None, options)
+    }
+
+    /// Imports only some of the objects of the file, see `load_parts`
+    pub fn spawn_parts(
+        path: PathBuf,
+        load_textures: bool,
+        parts: HashSet<usize>,
+        options: ImportOptions,
+    ) -> Self {
+        Self::start(path, None, None, load_textures, Some(parts), options)
+    }
+
+    /// Builds a terrain from a grayscale image
+    pub fn spawn_heightmap(path: PathBuf, options: heightmap::Options) -> Self {
+        Self::start(path, None, Some(options), true, None, ImportOptions::default())
+    }
+
+    /// Downloads the model into the download cache before importing it
+    pub fn spawn_url(url: String, load_textures: bool, options: ImportOptions) -> Self {
+        let path = download::cache_path(&url);
+        Self::start(path, Some((url, None)), None, load_textures, None, options)
+    }
+
+    /// Downloads the model to `path` before importing it, authenticating with
+    /// `token` if there is one
+    pub fn spawn_download(
+        url: String,
+        path: PathBuf,
+        token: Option<String>,
+        options: ImportOptions,
+    ) -> Self {
+        Self::start(path, Some((url, token)), None, true, None, options)
+    }
+
+    fn start(
+        path: PathBuf,
+        download: Option<(String, Option<String>)>,
+        heightmap: Option<heightmap::Options>,
+        load_textures: bool,
+        parts: Option<HashSet<usize>>,
+        options: ImportOptions,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let progress = Arc::new(JobProgress::default());
+
+        let thread_path = path.clone();
+        let thread_progress = progress.clone();
+        let thread_parts = parts.clone();
+        let _ = std::thread::Builder::new()
+            .name(format!("importer: {}", name))
+            .spawn(move || {
+                if let Some((url, token)) = download {
+                    if let Err(e) = download::download_model(
+                        &url,
+                        &thread_path,
+                        token.as_deref(),
+                        &thread_progress,
+                    ) {
+                        let _ = tx.send(Err(e.into()));
+                        pacing::request_redraw();
+                        return;
+                    }
+                }
+
+                let result = match (heightmap, thread_parts) {
+                    (Some(options), _) => heightmap::load_heightmap(&thread_path, options),
+                    (None, Some(parts)) => load_parts(&thread_path, load_textures, &parts),
+                    (None, None) => read_file(
+                        &thread_path,
+                        load_textures,
+                        Some(&thread_progress.cancelled),
+                    ),
+                };
+                let result = result.map(|mut obj| {
+                    options.apply(&mut obj, &thread_path);
+                    obj
+                });
+                let _ = tx.send(result.map_err(|e| ImportError::new(&e)));
+                pacing::request_redraw();
+            });
+
+        ImportJob {
+            path,
+            name,
+            started: Instant::now(),
+            reload: None,
+            heightmap,
+            load_textures,
+            parts,
+            options,
+            progress,
+            rx,
+        }
+    }
+
+    /// Stops a running download. Loading a file can't be interrupted, its
+    /// result is thrown away instead, except for big binary STL files which
+    /// stop at their next checkpoint and resume from it when imported again.
+    pub fn cancel(&self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.progress.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// How far along the download is, from 0 to 1. None when nothing is being
+    /// downloaded or the size of the download isn't known.
+    pub fn download_progress(&self) -> Option<f32> {
+        let total = self.progress.total.load(Ordering::Relaxed);
+        if !self.progress.downloading.load(Ordering::Relaxed) || total == 0 {
+            return None;
+        }
+
+        Some(self.progress.downloaded.load(Ordering::Relaxed) as f32 / total as f32)
+    }
+
+    pub fn is_downloading(&self) -> bool {
+        self.progress.downloading.load(Ordering::Relaxed)
+    }
+
+    /// Heightmaps are reloaded with the options they were first loaded with,
+    /// models previewed without textures stay without them and models made
+    /// of some of a file's objects only get those again. The import options
+    /// are the ones the model was first imported with.
+    pub fn spawn_reload(
+        path: PathBuf,
+        model_id: u32,
+        heightmap: Option<heightmap::Options>,
+        load_textures: bool,
+        parts: Option<HashSet<usize>>,
+        options: ImportOptions,
+    ) -> Self {
+        ImportJob {
+            reload: Some(model_id),
+            ..Self::start(path, None, heightmap, load_textures, parts, options)
+        }
+    }
+
+    /// Returns the result of the import once it's done, None while it's still running
+    pub fn poll(&self) -> Option<Result<Object, ImportError>> {
+        match self.rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err("Importer thread exited unexpectedly".into()))
+            }
+        }
+    }
+}
+
+/// A file being scanned for its objects on a background thread, so the ones
+/// to import can be picked before importing any of them
+pub struct PartScan {
+    pub path: PathBuf,
+    pub load_textures: bool,
+    pub options: ImportOptions,
+    rx: Receiver<Result<Vec<Part>, ImportError>>,
+}
+
+impl PartScan {
+    pub fn spawn(path: PathBuf, load_textures: bool, options: ImportOptions) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let thread_path = path.clone();
+        let _ = std::thread::Builder::new()
+            .name("part scan".to_string())
+            .spawn(move || {
+                let _ = tx.send(scan_parts(&thread_path).map_err(|e| ImportError::new(&e)));
+                pacing::request_redraw();
+            });
+
+        PartScan {
+            path,
+            load_textures,
+            options,
+            rx,
+        }
+    }
+
+    /// The objects of the file once they're listed, None while they're not
+    pub fn poll(&self) -> Option<Result<Vec<Part>, ImportError>> {
+        match self.rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err("Part scan thread exited unexpectedly".into()))
+            }
+        }
+    }
+}
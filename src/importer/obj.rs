@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{hash_map::Entry, HashMap, HashSet},
     io::{BufRead, BufReader},
     path::{Path, PathBuf},
 };
@@ -8,7 +8,8 @@ use log::{error, trace, warn};
 
 use crate::{
     aabb::AABB,
-    importer::{Material, ObjMesh, Object, Texture, TextureType},
+    error::{Error, Result},
+    importer::{float, Material, ObjMesh, Object, Part, Texture, TextureOptions, TextureType},
     mesh::Vertex,
 };
 
@@ -94,11 +95,100 @@ impl MtlToken {
     }
 }
 
+/// Everything after the statement's keyword, for names that can have spaces
+fn rest_of_line(line: &str) -> &str {
+    line.trim()
+        .split_once(char::is_whitespace)
+        .map_or("", |(_, rest)| rest.trim())
+}
+
+/// Splits the arguments of a texture statement into its options and the
+/// file name, which is everything after the options
+fn parse_texture_options(args: &str) -> (TextureOptions, &str) {
+    let mut options = TextureOptions::default();
+    let mut tokens = args.split_ascii_whitespace().peekable();
+    let on = |value: Option<&str>| value != Some("off");
+    let number = |value: Option<&str>| value.and_then(|v| v.parse::<f32>().ok());
+
+    while let Some(option) = tokens.next_if(|t| t.starts_with('-')) {
+        match option {
+            "-o" | "-s" | "-t" => {
+                // u and the optional v and w
+                let mut values = vec![];
+                while values.len() < 3 {
+                    match tokens.peek().and_then(|t| t.parse::<f32>().ok()) {
+                        Some(value) => {
+                            values.push(value);
+                            tokens.next();
+                        }
+                        None => break,
+                    }
+                }
+                let default = if option == "-s" { 1.0 } else { 0.0 };
+                let uv = glm::vec2(
+                    values.first().copied().unwrap_or(default),
+                    values.get(1).copied().unwrap_or(default),
+                );
+                match option {
+                    "-o" => options.offset = uv,
+                    "-s" => options.scale = uv,
+                    _ => {} // turbulence isn't supported
+                }
+            }
+            "-mm" => {
+                options.base = number(tokens.next()).unwrap_or(0.0);
+                options.gain = number(tokens.next()).unwrap_or(1.0);
+            }
+            "-clamp" => options.clamp = on(tokens.next()),
+            "-blendu" => options.blend_u = on(tokens.next()),
+            "-blendv" => options.blend_v = on(tokens.next()),
+            "-bm" | "-boost" | "-cc" | "-imfchan" | "-texres" | "-type" => {
+                tokens.next();
+            }
+            _ => warn!("Unknown texture option: {}", option),
+        }
+    }
+
+    let name = match tokens.next() {
+        // keep the spaces in the rest of the line
+        Some(first) => &args[first.as_ptr() as usize - args.as_ptr() as usize..],
+        None => "",
+    };
+
+    (options, name)
+}
+
+/// The names of the files an `mtllib` statement points to. They're separated
+/// by spaces, unless `is_file` says the whole rest of the line names a file
+/// with spaces in its name.
+pub fn material_lib_names(line: &str, is_file: impl FnOnce(&str) -> bool) -> Vec<&str> {
+    let rest = rest_of_line(line);
+    if rest.contains(char::is_whitespace) && is_file(rest) {
+        return vec![rest];
+    }
+
+    rest.split_ascii_whitespace().collect()
+}
+
+fn material_libs(obj_path: &Path, line: &str) -> Vec<PathBuf> {
+    let dir = obj_path.parent().unwrap();
+    material_lib_names(line, |whole| dir.join(whole).is_file())
+        .into_iter()
+        .map(|name| dir.join(name))
+        .collect()
+}
+
+/// The file a texture statement of an MTL file points to, after its options
+pub fn texture_file_name(line: &str) -> &str {
+    parse_texture_options(rest_of_line(line)).1
+}
+
 fn parse_mtl(
     path: &PathBuf,
-    obj_textures: &mut HashMap<String, Texture>,
-) -> Result<HashMap<String, Material>, Box<dyn std::error::Error>> {
-    let file = std::fs::File::open(path)?;
+    texture_files: &mut HashMap<String, PathBuf>,
+    load_textures: bool,
+) -> Result<HashMap<String, Material>> {
+    let file = std::fs::File::open(path).map_err(|e| Error::file(path, e))?;
     let reader = BufReader::with_capacity(BUF_CAP, file);
     let mut material_name = String::new();
     let mut materials: HashMap<String, Material> = HashMap::new();
@@ -110,8 +200,18 @@ fn parse_mtl(
     let mut opacity = 1.0;
     let mut mat_textures: Vec<Texture> = Vec::new();
 
-    for line in reader.lines() {
+    for (number, line) in reader.lines().enumerate() {
         let line = line?;
+        let color = |iter| {
+            float::parse_floats(iter)
+                .map(|[r, g, b]| glm::vec3(r, g, b))
+                .ok_or_else(|| Error::parse(path, number + 1, "Incomplete or invalid color"))
+        };
+        let scalar = |iter| {
+            float::parse_floats(iter)
+                .map(|[n]| n)
+                .ok_or_else(|| Error::parse(path, number + 1, "Missing or invalid number"))
+        };
         // skip empty lines and comments
         if line.is_empty() || line.chars().next().is_some_and(|c| c == '#') {
             continue;
@@ -132,68 +232,63 @@ fn parse_mtl(
                             opacity,
                             mat_textures.clone(),
                         );
-                        materials.insert(material_name, material);
+                        if materials.insert(material_name.clone(), material).is_some() {
+                            warn!(
+                                "Material \"{}\" is defined more than once in {:?}",
+                                material_name, path
+                            );
+                        }
 
                         mat_textures.clear();
                     }
 
-                    material_name = iter.next().unwrap().to_string();
+                    material_name = rest_of_line(&line).to_string();
                 }
                 Some(MtlToken::AmbientColor) => {
-                    let r = iter.next().unwrap().parse::<f32>().unwrap();
-                    let g = iter.next().unwrap().parse::<f32>().unwrap();
-                    let b = iter.next().unwrap().parse::<f32>().unwrap();
-                    ambient = glm::vec3(r, g, b);
+                    ambient = color(iter)?;
                 }
                 Some(MtlToken::DiffuseColor) => {
-                    let r = iter.next().unwrap().parse::<f32>().unwrap();
-                    let g = iter.next().unwrap().parse::<f32>().unwrap();
-                    let b = iter.next().unwrap().parse::<f32>().unwrap();
-                    diffuse = glm::vec3(r, g, b);
+                    diffuse = color(iter)?;
                 }
                 Some(MtlToken::SpecularColor) => {
-                    let r = iter.next().unwrap().parse::<f32>().unwrap();
-                    let g = iter.next().unwrap().parse::<f32>().unwrap();
-                    let b = iter.next().unwrap().parse::<f32>().unwrap();
-                    specular = glm::vec3(r, g, b);
+                    specular = color(iter)?;
                 }
                 Some(MtlToken::SpecularExponent) => {
-                    shininess = iter.next().unwrap().parse::<f32>().unwrap();
+                    shininess = scalar(iter)?;
                 }
                 Some(MtlToken::Opacity) => {
-                    opacity = iter.next().unwrap().parse::<f32>().unwrap();
+                    opacity = scalar(iter)?;
                 }
                 Some(MtlToken::Transparency) => {
                     // it's just opposite of opacity so we subtract it from 1.0
-                    opacity = 1.0 - iter.next().unwrap().parse::<f32>().unwrap();
+                    opacity = 1.0 - scalar(iter)?;
                 }
                 Some(MtlToken::DiffuseTexture)
                 | Some(MtlToken::AmbientTexture)
                 | Some(MtlToken::SpecularTexture)
                 | Some(MtlToken::EmissiveTexture) => {
+                    if !load_textures {
+                        continue;
+                    }
                     let tex_type = TextureType::from_material_str(token).unwrap();
 
-                    let name = iter.next().unwrap().to_string();
-                    let tex = if let std::collections::hash_map::Entry::Vacant(e) =
-                        obj_textures.entry(name.clone())
-                    {
-                        let path = path.parent().unwrap().join(&name);
-                        let tex = match Texture::new(path, tex_type) {
-                            Ok(v) => v,
-                            Err(e) => {
-                                error!("Failed to load texture: {}", e);
-                                continue;
-                            }
-                        };
-                        e.insert(tex.clone());
-                        tex
-                    } else {
-                        let mut tex = obj_textures.get(&name).unwrap().clone();
-                        tex.typ = tex_type;
-                        tex
-                    };
+                    let (options, name) = parse_texture_options(rest_of_line(&line));
+                    if name.is_empty() {
+                        error!("Texture statement has no file name: {}", line);
+                        continue;
+                    }
+                    let name = name.to_string();
+                    // the images are decoded once the model is added, embedded
+                    // ones straight from their name
+                    if !name.starts_with("data:") {
+                        texture_files
+                            .entry(name.clone())
+                            .or_insert_with(|| path.parent().unwrap().join(&name));
+                    }
 
-                    mat_textures.push(tex);
+                    let mut texture = Texture::new(name, tex_type);
+                    texture.options = options;
+                    mat_textures.push(texture);
                 }
                 _ => {
                     warn!("Unhandled material token: {}", token)
@@ -217,10 +312,81 @@ fn parse_mtl(
     Ok(materials)
 }
 
+/// Reads just the textures of the materials in `material_libs`, for models
+/// that were loaded without them. Materials defined again in a later file
+/// replace the earlier ones like they do when loading the OBJ.
+pub fn load_material_textures(
+    material_libs: &[PathBuf],
+    texture_files: &mut HashMap<String, PathBuf>,
+) -> HashMap<String, Vec<Texture>> {
+    let mut textures = HashMap::new();
+    for path in material_libs {
+        match parse_mtl(path, texture_files, true) {
+            Ok(materials) => {
+                for (name, material) in materials {
+                    textures.insert(name, material.textures);
+                }
+            }
+            Err(e) => error!("Failed to read the materials in {:?}: {}", path, e),
+        }
+    }
+
+    textures
+}
+
+/// Lists the objects of the file and how big they are, without parsing
+/// anything else. Objects without faces are left out.
+pub fn scan_parts(file: std::fs::File) -> Result<Vec<Part>> {
+    let reader = BufReader::with_capacity(BUF_CAP, file);
+    // faces before the first object statement make up a part of their own
+    let mut parts = vec![Part::default()];
+
+    for line in reader.lines() {
+        let line = line?;
+        let mut iter = line.split_ascii_whitespace();
+        match iter.next() {
+            Some("o") => parts.push(Part {
+                index: parts.len(),
+                name: rest_of_line(&line).to_string(),
+                ..Part::default()
+            }),
+            Some("f") => {
+                let corners = iter.count();
+                let triangles = corners.saturating_sub(2);
+                if let Some(part) = parts.last_mut() {
+                    part.triangles += triangles;
+                    part.size += corners * std::mem::size_of::<Vertex>()
+                        + triangles * 3 * std::mem::size_of::<u32>();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    parts.retain(|part| part.triangles > 0);
+    Ok(parts)
+}
+
+/// The element a face refers to by its index, which counts from 1 or back
+/// from the last one when it's negative
+fn face_element<T: Copy>(index: &str, elements: &[T]) -> Option<T> {
+    let index = index.parse::<i64>().ok()?;
+    let index = if index < 0 {
+        elements.len() as i64 + index
+    } else {
+        index - 1
+    };
+    elements.get(usize::try_from(index).ok()?).copied()
+}
+
+/// Only the faces of the objects in `parts` are imported if it's given, by
+/// their index like `scan_parts` gives them
 pub fn load_obj(
     obj_path: &Path,
     file: std::fs::File,
-) -> Result<Object, Box<dyn std::error::Error>> {
+    load_textures: bool,
+    parts: Option<&HashSet<usize>>,
+) -> Result<Object> {
     let now = std::time::Instant::now();
     let reader = BufReader::with_capacity(BUF_CAP, file);
     let mut object_name = String::new();
@@ -233,13 +399,24 @@ pub fn load_obj(
     let mut tex_coords = Vec::new();
     let mut meshes = Vec::new();
     let mut materials: HashMap<String, Material> = HashMap::new();
-    let mut current_material: Option<Material> = None;
+    // materials are looked up by name once the whole file is read since
+    // usemtl can come before the mtllib that defines the material
+    let mut current_material: Option<String> = None;
+    // the index each material of the current mesh starts at
+    let mut current_ranges: Vec<(usize, Option<String>)> = vec![(0, None)];
+    let mut mesh_materials: Vec<Vec<(usize, Option<String>)>> = Vec::new();
+    // in the order they're listed so redefined materials resolve the same
+    // way when the textures are read again later
+    let mut material_libs_read = Vec::new();
+    let mut material_libs_seen = HashSet::new();
     let mut min_aabb = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
     let mut max_aabb = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
-    let mut textures = HashMap::new();
+    let mut texture_files = HashMap::new();
+    let mut part = 0;
 
-    for line in reader.lines() {
+    for (number, line) in reader.lines().enumerate() {
         let line = line?;
+        let parse_error = |message: &str| Error::parse(obj_path, number + 1, message);
         // skip empty lines and comments
         if line.is_empty() || line.chars().next().is_some_and(|c| c == '#') {
             continue;
@@ -262,149 +439,85 @@ pub fn load_obj(
                             name,
                             vertices: vertices.clone(),
                             indices: indices.clone(),
-                            material: current_material.clone(),
+                            materials: vec![],
                         });
+                        mesh_materials.push(current_ranges.clone());
                     }
                     vertices.clear();
                     indices.clear();
                     indices_counter = 0;
+                    current_ranges = vec![(0, current_material.clone())];
 
-                    object_name = iter.next().unwrap_or("").to_string();
+                    object_name = rest_of_line(&line).to_string();
+                    part += 1;
                 }
                 Some(ObjToken::Vertex) => {
-                    let vec = iter.collect::<Vec<_>>();
-                    if vec.len() < 3 {
-                        return Err(Box::new(std::io::Error::new(
-                            std::io::ErrorKind::InvalidData,
-                            "Incomplete vertex data",
-                        )));
-                    }
-
-                    let mut iter = vec.iter().take(3).map(|i| i.parse::<f32>().unwrap());
-                    let x = iter.next().unwrap();
-                    let y = iter.next().unwrap();
-                    let z = iter.next().unwrap();
+                    let Some([x, y, z]) = float::parse_leading(rest_of_line(&line)) else {
+                        return Err(parse_error("Incomplete or invalid vertex data"));
+                    };
                     temp_vertices.push(glm::vec3(x, y, z));
 
                     min_aabb = glm::vec3(min_aabb.x.min(x), min_aabb.y.min(y), min_aabb.z.min(z));
                     max_aabb = glm::vec3(max_aabb.x.max(x), max_aabb.y.max(y), max_aabb.z.max(z));
                 }
                 Some(ObjToken::Normal) => {
-                    let vec = iter.collect::<Vec<_>>();
-                    if vec.len() < 3 {
-                        return Err(Box::new(std::io::Error::new(
-                            std::io::ErrorKind::InvalidData,
-                            "Incomplete vertex normal data",
-                        )));
-                    }
-
-                    let mut iter = vec.iter().take(3).map(|i| i.parse::<f32>().unwrap());
-                    let x = iter.next().unwrap();
-                    let y = iter.next().unwrap();
-                    let z = iter.next().unwrap();
+                    let Some([x, y, z]) = float::parse_leading(rest_of_line(&line)) else {
+                        return Err(parse_error("Incomplete or invalid vertex normal data"));
+                    };
                     normals.push(glm::vec3(x, y, z));
                 }
                 Some(ObjToken::TexCoord) => {
-                    let mut iter = iter.take(2).map(|i| i.parse::<f32>().unwrap());
-                    let u = iter.next().unwrap();
-                    let v = iter.next().unwrap();
+                    let Some([u, v]) = float::parse_leading(rest_of_line(&line)) else {
+                        return Err(parse_error("Incomplete or invalid texture coordinate data"));
+                    };
                     // vertically flip the texcoords because flipping the texture is expensive
                     tex_coords.push(glm::vec2(u, 1.0 - v));
                 }
                 Some(ObjToken::Face) => {
+                    if parts.is_some_and(|parts| !parts.contains(&part)) {
+                        continue;
+                    }
                     let face = iter.collect::<Vec<_>>();
+                    if face.len() < 3 {
+                        return Err(parse_error("Face with fewer than 3 corners"));
+                    }
                     let mut calculated_normal = glm::vec3(0.0, 0.0, 0.0);
 
                     if normals.is_empty() {
-                        let part0 = face[0].split('/').next().unwrap();
-                        let part1 = face[1].split('/').next().unwrap();
-                        let part2 = face[2].split('/').next().unwrap();
-
-                        calculated_normal = glm::normalize(glm::cross(
-                            temp_vertices[part1.parse::<i32>().unwrap() as usize - 1]
-                                - temp_vertices[part0.parse::<i32>().unwrap() as usize - 1],
-                            temp_vertices[part2.parse::<i32>().unwrap() as usize - 1]
-                                - temp_vertices[part0.parse::<i32>().unwrap() as usize - 1],
-                        ));
+                        let corner = |i: usize| {
+                            let vert = face[i].split('/').next().unwrap_or("");
+                            face_element(vert, &temp_vertices)
+                                .ok_or_else(|| parse_error("Face vertex index out of range"))
+                        };
+                        let (a, b, c) = (corner(0)?, corner(1)?, corner(2)?);
+
+                        calculated_normal = glm::normalize(glm::cross(b - a, c - a));
                     }
 
-                    for (i, vert) in face.iter().enumerate() {
-                        if vert.contains("//") {
-                            let mut it = vert.split("//");
-                            let mut vert = it.next().unwrap().parse::<i32>().unwrap();
-                            if vert < 0 {
-                                vert += temp_vertices.len() as i32;
-                            } else {
-                                vert -= 1;
-                            }
-                            let mut normal = it.next().unwrap().parse::<i32>().unwrap();
-                            if normal < 0 {
-                                normal += normals.len() as i32;
-                            } else {
-                                normal -= 1;
-                            }
-                            vertices.push(Vertex {
-                                position: *temp_vertices.get(vert as usize).unwrap(),
-                                normal: *normals.get(normal as usize).unwrap(),
-                                tex_coords: glm::vec2(0.0, 0.0),
-                            });
-                        } else if vert.matches('/').count() == 2 {
-                            let mut it = vert.split('/');
-                            let mut vertex = it.next().unwrap().parse::<i32>().unwrap();
-                            if vertex < 0 {
-                                vertex += temp_vertices.len() as i32;
-                            } else {
-                                vertex -= 1;
-                            }
-                            let mut t_coords = it.next().unwrap().parse::<i32>().unwrap();
-                            if t_coords < 0 {
-                                t_coords += tex_coords.len() as i32;
-                            } else {
-                                t_coords -= 1;
-                            }
-                            let mut normal = it.next().unwrap().parse::<i32>().unwrap();
-                            if normal < 0 {
-                                normal += normals.len() as i32;
-                            } else {
-                                normal -= 1;
-                            }
-                            vertices.push(Vertex {
-                                position: *temp_vertices.get(vertex as usize).unwrap(),
-                                normal: *normals.get(normal as usize).unwrap(),
-                                tex_coords: *tex_coords.get(t_coords as usize).unwrap(),
-                            });
-                        } else if vert.matches('/').count() == 1 {
-                            let mut it = vert.split('/');
-                            let mut vertex = it.next().unwrap().parse::<i32>().unwrap();
-                            if vertex < 0 {
-                                vertex += temp_vertices.len() as i32;
-                            } else {
-                                vertex -= 1;
-                            }
-                            let mut t_coords = it.next().unwrap().parse::<i32>().unwrap();
-                            if t_coords < 0 {
-                                t_coords += tex_coords.len() as i32;
-                            } else {
-                                t_coords -= 1;
-                            }
-                            vertices.push(Vertex {
-                                position: *temp_vertices.get(vertex as usize).unwrap(),
-                                normal: calculated_normal,
-                                tex_coords: *tex_coords.get(t_coords as usize).unwrap(),
-                            });
-                        } else {
-                            let mut vert = vert.parse::<i32>().unwrap();
-                            if vert < 0 {
-                                vert += temp_vertices.len() as i32;
-                            } else {
-                                vert -= 1;
-                            }
-                            vertices.push(Vertex {
-                                position: *temp_vertices.get(vert as usize).unwrap(),
-                                normal: calculated_normal,
-                                tex_coords: glm::vec2(0.0, 0.0),
-                            });
-                        }
+                    for (i, corner) in face.iter().enumerate() {
+                        // v, v/vt, v//vn or v/vt/vn
+                        let mut it = corner.split('/');
+                        let position = it
+                            .next()
+                            .and_then(|vert| face_element(vert, &temp_vertices))
+                            .ok_or_else(|| parse_error("Face vertex index out of range"))?;
+                        let tex_coords = match it.next().filter(|t| !t.is_empty()) {
+                            Some(t) => face_element(t, &tex_coords).ok_or_else(|| {
+                                parse_error("Face texture coordinate index out of range")
+                            })?,
+                            None => glm::vec2(0.0, 0.0),
+                        };
+                        let normal = match it.next() {
+                            Some(n) => face_element(n, &normals)
+                                .ok_or_else(|| parse_error("Face normal index out of range"))?,
+                            None => calculated_normal,
+                        };
+                        vertices.push(Vertex {
+                            position,
+                            normal,
+                            tex_coords,
+                            color: Vertex::WHITE,
+                        });
 
                         // Triangulate faces. 2 triangles per face
                         if i < face.len() - 2 {
@@ -417,12 +530,34 @@ pub fn load_obj(
                     indices_counter += face.len() as u32;
                 }
                 Some(ObjToken::MaterialLib) => {
-                    for matlib in iter {
-                        let material_path = obj_path.parent().unwrap().join(matlib);
-                        let new_materials = parse_mtl(&material_path, &mut textures);
+                    for material_path in material_libs(obj_path, &line) {
+                        // the same library is sometimes listed again for each object
+                        if !material_libs_seen.insert(material_path.clone()) {
+                            continue;
+                        }
+                        material_libs_read.push(material_path.clone());
+                        let new_materials = parse_mtl(
+                            &material_path,
+                            &mut texture_files,
+                            load_textures,
+                        );
                         match new_materials {
                             Ok(m) => {
-                                materials.extend(m);
+                                for (name, material) in m {
+                                    match materials.entry(name) {
+                                        Entry::Occupied(mut e) => {
+                                            warn!(
+                                                "Material \"{}\" is redefined in {:?}, using the new one",
+                                                e.key(),
+                                                material_path
+                                            );
+                                            e.insert(material);
+                                        }
+                                        Entry::Vacant(e) => {
+                                            e.insert(material);
+                                        }
+                                    }
+                                }
                             }
                             Err(e) => {
                                 error!("Failed to parse mtl file {:?}: {}", material_path, e);
@@ -431,40 +566,20 @@ pub fn load_obj(
                     }
                 }
                 Some(ObjToken::MaterialUsage) => {
-                    // Split into meshes by material usage
-                    let name = {
-                        if current_mesh_name.is_empty() && !object_name.is_empty() {
-                            object_name.clone()
-                        } else if !current_mesh_name.is_empty() {
-                            current_mesh_name.clone()
-                        } else {
-                            "default_mesh".to_string()
-                        }
-                    };
-                    if !vertices.is_empty() {
-                        meshes.push(ObjMesh {
-                            name,
-                            vertices: vertices.clone(),
-                            indices: indices.clone(),
-                            material: current_material.clone(),
-                        });
-                    }
-                    vertices.clear();
-                    indices.clear();
-                    indices_counter = 0;
-
-                    let mat_name = iter.next();
-                    if mat_name.is_none() {
+                    let mat_name = rest_of_line(&line);
+                    if mat_name.is_empty() {
                         error!("Material usage statement has no material name");
                         continue;
                     }
-                    let mat = materials.get(&mat_name.unwrap().to_owned()).cloned();
 
-                    if mat.is_none() {
-                        warn!("Usage of material \"{}\" which is not defined", mat_name.unwrap());
+                    // the mesh keeps going with a new material range instead of being split
+                    current_material = Some(mat_name.to_string());
+                    match current_ranges.last_mut() {
+                        Some((start, material)) if *start == indices.len() => {
+                            *material = current_material.clone();
+                        }
+                        _ => current_ranges.push((indices.len(), current_material.clone())),
                     }
-
-                    current_material = mat;
                 }
                 // Things we ignore have a statement to not clutter the log
                 Some(ObjToken::Line) |
@@ -490,12 +605,41 @@ pub fn load_obj(
         }
     };
 
-    meshes.push(ObjMesh {
-        name: mesh_name,
-        vertices: vertices.clone(),
-        indices: indices.clone(),
-        material: current_material,
-    });
+    // the file can end with a usemtl or o that has no faces after it
+    if !vertices.is_empty() || meshes.is_empty() {
+        meshes.push(ObjMesh {
+            name: mesh_name,
+            vertices: vertices.clone(),
+            indices: indices.clone(),
+            materials: vec![],
+        });
+        mesh_materials.push(current_ranges);
+    }
+
+    let mut missing = HashSet::new();
+    for (mesh, ranges) in meshes.iter_mut().zip(mesh_materials) {
+        mesh.materials = ranges
+            .into_iter()
+            .map(|(start, name)| {
+                let material = name.map(|name| match materials.get(&name) {
+                    Some(material) => material.clone(),
+                    None => {
+                        if missing.insert(name.clone()) {
+                            warn!(
+                                "Usage of material \"{}\" which is not defined, using the default material",
+                                name
+                            );
+                        }
+                        Material {
+                            name,
+                            ..Material::default()
+                        }
+                    }
+                });
+                (start, material)
+            })
+            .collect();
+    }
 
     let aabb = AABB::new(min_aabb, max_aabb);
 
@@ -503,5 +647,194 @@ pub fn load_obj(
         name: object_name,
         meshes,
         aabb,
+        units: None,
+        colors: None,
+        material_libs: material_libs_read,
+        texture_files,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the files into a directory of their own and loads the first one
+    fn load(test: &str, files: &[(&str, &str)]) -> Result<Object> {
+        let dir = std::env::temp_dir().join(format!("3dobs-obj-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+
+        let path = dir.join(files[0].0);
+        let result = load_obj(&path, std::fs::File::open(&path).unwrap(), false, None);
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+
+    fn material_names(mesh: &ObjMesh) -> Vec<(usize, Option<String>)> {
+        mesh.materials
+            .iter()
+            .map(|(start, m)| (*start, m.as_ref().map(|m| m.name.clone())))
+            .collect()
+    }
+
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    #[test]
+    fn face_element_counts_from_one_and_back_from_the_end() {
+        let elements = [10, 20, 30];
+        assert_eq!(face_element("1", &elements), Some(10));
+        assert_eq!(face_element("3", &elements), Some(30));
+        assert_eq!(face_element("-1", &elements), Some(30));
+        assert_eq!(face_element("-3", &elements), Some(10));
+        assert_eq!(face_element("0", &elements), None);
+        assert_eq!(face_element("4", &elements), None);
+        assert_eq!(face_element("-4", &elements), None);
+        assert_eq!(face_element("x", &elements), None);
+    }
+
+    #[test]
+    fn negative_indices_are_relative_to_the_vertices_so_far() {
+        let obj = format!(
+            "{}f -4 -3 -2 -1\nv 5 5 5\nv 6 5 5\nv 5 6 5\nf -3/ -2 -1\n",
+            SQUARE
+        );
+        let object = load("negative", &[("model.obj", &obj)]).unwrap();
+
+        let mesh = &object.meshes[0];
+        let positions: Vec<glm::Vec3> = mesh.vertices.iter().map(|v| v.position).collect();
+        assert_eq!(positions[0], glm::vec3(0.0, 0.0, 0.0));
+        assert_eq!(positions[3], glm::vec3(0.0, 1.0, 0.0));
+        assert_eq!(positions[4], glm::vec3(5.0, 5.0, 5.0));
+        assert_eq!(positions[6], glm::vec3(5.0, 6.0, 5.0));
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn out_of_range_indices_are_errors() {
+        for face in [
+            "f 1 2 5",
+            "f -5 1 2",
+            "f 0 1 2",
+            "f 1/2 2/2 3/2",
+            "f 1//1 2//1 3//1",
+        ] {
+            let obj = format!("{}{}\n", SQUARE, face);
+            assert!(load("range", &[("model.obj", &obj)]).is_err(), "{}", face);
+        }
+    }
+
+    #[test]
+    fn usemtl_before_geometry_and_mtllib() {
+        let obj = format!("usemtl red\n{}mtllib colors.mtl\nf 1 2 3\n", SQUARE);
+        let mtl = "newmtl red\nKd 1 0 0\n";
+        let object = load("early", &[("model.obj", &obj), ("colors.mtl", mtl)]).unwrap();
+
+        let (start, material) = &object.meshes[0].materials[0];
+        let material = material.as_ref().unwrap();
+        assert_eq!(*start, 0);
+        assert_eq!(material.name, "red");
+        assert_eq!(material.diffuse_color, glm::vec3(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn materials_switch_within_a_mesh() {
+        let obj = format!(
+            "mtllib colors.mtl\n{}usemtl red\nf 1 2 3\nusemtl blue\nf 1 3 4\nusemtl red\nf 1 2 4\n",
+            SQUARE
+        );
+        let mtl = "newmtl red\nKd 1 0 0\nnewmtl blue\nKd 0 0 1\n";
+        let object = load("switch", &[("model.obj", &obj), ("colors.mtl", mtl)]).unwrap();
+
+        assert_eq!(object.meshes.len(), 1);
+        assert_eq!(
+            material_names(&object.meshes[0]),
+            [
+                (0, Some("red".to_string())),
+                (3, Some("blue".to_string())),
+                (6, Some("red".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_mtllib_and_redefined_materials() {
+        let obj = format!(
+            "mtllib a.mtl\nmtllib a.mtl b.mtl\n{}usemtl shared\nf 1 2 3\n",
+            SQUARE
+        );
+        let a = "newmtl shared\nKd 1 0 0\n";
+        let b = "newmtl shared\nKd 0 1 0\n";
+        let object = load(
+            "repeated",
+            &[("model.obj", &obj), ("a.mtl", a), ("b.mtl", b)],
+        )
+        .unwrap();
+
+        let dir = object.material_libs[0].parent().unwrap();
+        assert_eq!(object.material_libs, [dir.join("a.mtl"), dir.join("b.mtl")]);
+        let material = object.meshes[0].materials[0].1.as_ref().unwrap();
+        // the library read last wins
+        assert_eq!(material.diffuse_color, glm::vec3(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn object_names_keep_their_spaces() {
+        let obj = format!(
+            "{}o first part\nf 1 2 3\no  second  part \nf 1 3 4\n",
+            SQUARE
+        );
+        let object = load("object-names", &[("model.obj", &obj)]).unwrap();
+        let names: Vec<_> = object
+            .meshes
+            .iter()
+            .map(|mesh| mesh.name.as_str())
+            .collect();
+        assert_eq!(names, ["first part", "second  part"]);
+
+        let path = std::env::temp_dir().join(format!("3dobs-parts-{}.obj", std::process::id()));
+        std::fs::write(&path, &obj).unwrap();
+        let parts = scan_parts(std::fs::File::open(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_file(path);
+        let names: Vec<_> = parts.iter().map(|part| part.name.as_str()).collect();
+        assert_eq!(names, ["first part", "second  part"]);
+    }
+
+    #[test]
+    fn names_of_referenced_files() {
+        let line = "mtllib a.mtl  b.mtl";
+        assert_eq!(material_lib_names(line, |_| false), ["a.mtl", "b.mtl"]);
+        assert_eq!(material_lib_names(line, |_| true), ["a.mtl  b.mtl"]);
+        assert_eq!(material_lib_names("mtllib a.mtl", |_| true), ["a.mtl"]);
+        assert_eq!(
+            texture_file_name("map_Kd -s 2 2 -clamp on my texture.png"),
+            "my texture.png"
+        );
+    }
+
+    #[test]
+    fn missing_materials_fall_back_to_the_default() {
+        let obj = format!("mtllib missing.mtl\n{}usemtl nowhere\nf 1 2 3\n", SQUARE);
+        let object = load("missing", &[("model.obj", &obj)]).unwrap();
+
+        let material = object.meshes[0].materials[0].1.as_ref().unwrap();
+        assert_eq!(material.name, "nowhere");
+        assert_eq!(material.diffuse_color, Material::default().diffuse_color);
+    }
+
+    #[test]
+    fn spaces_in_names() {
+        let obj = format!(
+            "mtllib my colors.mtl\n{}o my part\nusemtl dark red\nf 1 2 3\n",
+            SQUARE
+        );
+        let mtl = "newmtl dark red\nKd 0.5 0 0\n";
+        let object = load("spaces", &[("model.obj", &obj), ("my colors.mtl", mtl)]).unwrap();
+
+        let material = object.meshes[0].materials[0].1.as_ref().unwrap();
+        assert_eq!(material.name, "dark red");
+        assert_eq!(material.diffuse_color, glm::vec3(0.5, 0.0, 0.0));
+    }
+}
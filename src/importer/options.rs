@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    importer::{
+        coords::{self, CoordinateSystem},
+        ObjMesh, Object,
+    },
+    topology,
+};
+
+/// Scales the import options window's scale slider can be dragged between
+pub const SCALE_RANGE: (f32, f32) = (0.001, 1000.0);
+
+/// What is done to a model after any of the importers read it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// The system the file is in, the usual one for its format if `None`
+    pub coordinates: Option<CoordinateSystem>,
+    /// Multiplies the positions, after they're converted
+    pub scale: f32,
+    /// Flips the V texture coordinate, for files made with the origin of the
+    /// textures at their top
+    pub flip_v: bool,
+    /// Replaces the normals with smooth ones, keeping edges sharper than this
+    /// many degrees
+    pub smoothing_angle: Option<f32>,
+    /// Puts every mesh of the file into a single one
+    pub merge_meshes: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            coordinates: None,
+            scale: 1.0,
+            flip_v: false,
+            smoothing_angle: None,
+            merge_meshes: false,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// The options remembered for the extension of `path`, if there are any
+    pub fn remembered(
+        remembered: &HashMap<String, ImportOptions>,
+        path: &std::path::Path,
+    ) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        remembered.get(&ext).copied()
+    }
+
+    /// Runs the post-import steps that are turned on, in the order the
+    /// options are listed in. `path` is the file `obj` was read from.
+    pub fn apply(&self, obj: &mut Object, path: &std::path::Path) {
+        self.coordinates
+            .unwrap_or_else(|| CoordinateSystem::of_file(path))
+            .convert(obj);
+
+        for mesh in &mut obj.meshes {
+            for vertex in &mut mesh.vertices {
+                vertex.position = vertex.position * self.scale;
+                if self.flip_v {
+                    vertex.tex_coords.y = 1.0 - vertex.tex_coords.y;
+                }
+            }
+        }
+        if let Some(angle) = self.smoothing_angle {
+            smooth_normals(&mut obj.meshes, angle);
+        }
+        if self.merge_meshes && obj.meshes.len() > 1 {
+            let name = obj.name.clone();
+            obj.meshes = vec![merge(std::mem::take(&mut obj.meshes), name)];
+        }
+
+        if self.scale != 1.0 {
+            if let Some(aabb) = coords::bounds(&obj.meshes) {
+                obj.aabb = aabb;
+            }
+        }
+    }
+}
+
+/// Appends the meshes one after the other, their materials keep covering the
+/// same faces
+fn merge(meshes: Vec<ObjMesh>, name: String) -> ObjMesh {
+    let mut merged = ObjMesh {
+        name,
+        vertices: vec![],
+        indices: vec![],
+        materials: vec![],
+    };
+    for mesh in meshes {
+        let base_vertex = merged.vertices.len() as u32;
+        let first_index = merged.indices.len();
+        merged.vertices.extend(mesh.vertices);
+        merged
+            .indices
+            .extend(mesh.indices.iter().map(|i| i + base_vertex));
+        merged.materials.extend(
+            mesh.materials
+                .into_iter()
+                .map(|(start, material)| (start + first_index, material)),
+        );
+    }
+    merged
+}
+
+fn smooth_normals(meshes: &mut [ObjMesh], angle: f32) {
+    let geometry: Vec<_> = meshes
+        .iter()
+        .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
+        .collect();
+    let smoothed = topology::smooth_normals(&geometry, angle);
+    for (mesh, (vertices, indices)) in meshes.iter_mut().zip(smoothed) {
+        mesh.vertices = vertices;
+        mesh.indices = indices;
+    }
+}
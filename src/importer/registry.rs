@@ -0,0 +1,235 @@
+use std::{collections::HashSet, fs::File, io::Read, path::Path, sync::atomic::AtomicBool};
+
+use super::{coords::CoordinateSystem, obj, stl, Object, Part};
+use crate::{error::Result, ui::i18n::tr};
+
+/// How much of a file is read to tell its format when the extension doesn't
+const SNIFF_BYTES: u64 = 512;
+const MAX_COMMENT_LINES: usize = 20;
+
+/// What a model is being loaded for
+#[derive(Debug, Clone, Copy)]
+pub struct LoadContext<'a> {
+    pub path: &'a Path,
+    pub load_textures: bool,
+    /// Only these objects of the file, by their index like `scan_parts`
+    /// gives them
+    pub parts: Option<&'a HashSet<usize>>,
+    /// Setting it stops the formats that check it
+    pub cancelled: Option<&'a AtomicBool>,
+}
+
+/// A model format 3dobs can import. The file dialogs, drag and drop, the
+/// folder scans and the registered file types all go through `IMPORTERS`, so
+/// a new format only has to be added there.
+pub trait Importer: Sync {
+    /// Shown in the file dialogs, like "Wavefront OBJ"
+    fn name(&self) -> &'static str;
+
+    /// Lowercase and without the dot, the usual one first
+    fn extensions(&self) -> &'static [&'static str];
+
+    fn mime_type(&self) -> &'static str;
+
+    /// Whether a file of `len` bytes starting with `start` is in this format,
+    /// for files without a known extension
+    fn sniff(&self, start: &[u8], len: u64) -> bool;
+
+    fn load(&self, file: File, ctx: &LoadContext) -> Result<Object>;
+
+    /// The system files of the format are usually in
+    fn coordinate_system(&self) -> CoordinateSystem {
+        CoordinateSystem::NATIVE
+    }
+
+    /// Whether objects of a file can be imported on their own
+    fn has_parts(&self) -> bool {
+        false
+    }
+
+    fn scan_parts(&self, _file: File) -> Result<Vec<Part>> {
+        Ok(vec![])
+    }
+
+    /// Text the exporting tool left at the `start` of the file
+    fn metadata(&self, _start: &[u8]) -> Vec<String> {
+        vec![]
+    }
+}
+
+pub struct Obj;
+
+impl Importer for Obj {
+    fn name(&self) -> &'static str {
+        "Wavefront OBJ"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["obj"]
+    }
+
+    fn mime_type(&self) -> &'static str {
+        "model/obj"
+    }
+
+    /// Text made of comments and the usual statements
+    fn sniff(&self, start: &[u8], _len: u64) -> bool {
+        let text = String::from_utf8_lossy(start);
+        let mut statements = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_ascii_whitespace().next())
+            .peekable();
+        statements.peek().is_some()
+            && statements.take(8).all(|s| {
+                matches!(
+                    s,
+                    "v" | "vn" | "vt" | "f" | "o" | "g" | "s" | "mtllib" | "usemtl"
+                )
+            })
+    }
+
+    fn load(&self, file: File, ctx: &LoadContext) -> Result<Object> {
+        obj::load_obj(ctx.path, file, ctx.load_textures, ctx.parts)
+    }
+
+    fn has_parts(&self) -> bool {
+        true
+    }
+
+    fn scan_parts(&self, file: File) -> Result<Vec<Part>> {
+        obj::scan_parts(file)
+    }
+
+    /// The comments before the first statement, where exporters put their
+    /// name and version
+    fn metadata(&self, start: &[u8]) -> Vec<String> {
+        String::from_utf8_lossy(start)
+            .lines()
+            .map(str::trim)
+            .take_while(|line| line.is_empty() || line.starts_with('#'))
+            .map(|line| line.trim_start_matches('#').trim())
+            .filter(|line| !line.is_empty())
+            .take(MAX_COMMENT_LINES)
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+pub struct Stl;
+
+impl Importer for Stl {
+    fn name(&self) -> &'static str {
+        "STL"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["stl"]
+    }
+
+    fn mime_type(&self) -> &'static str {
+        "model/stl"
+    }
+
+    fn sniff(&self, start: &[u8], len: u64) -> bool {
+        stl::sniff(start, len)
+    }
+
+    fn load(&self, file: File, ctx: &LoadContext) -> Result<Object> {
+        stl::load_stl(file, ctx.path, ctx.cancelled)
+    }
+
+    /// STL comes from CAD programs and slicers
+    fn coordinate_system(&self) -> CoordinateSystem {
+        CoordinateSystem::Z_UP
+    }
+
+    /// The `solid` line of an ASCII file or the header of a binary one
+    fn metadata(&self, start: &[u8]) -> Vec<String> {
+        stl::header_text(start).into_iter().collect()
+    }
+}
+
+/// Every format that can be imported
+pub static IMPORTERS: [&dyn Importer; 2] = [&Obj, &Stl];
+
+pub fn by_extension(extension: &str) -> Option<&'static dyn Importer> {
+    IMPORTERS.iter().copied().find(|importer| {
+        importer
+            .extensions()
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(extension))
+    })
+}
+
+/// The importer of a file by its extension
+pub fn for_path(path: &Path) -> Option<&'static dyn Importer> {
+    by_extension(path.extension()?.to_str()?)
+}
+
+/// The importer of a file by its extension, or by its contents when it has
+/// none or one no importer knows
+pub fn detect(path: &Path) -> std::io::Result<Option<&'static dyn Importer>> {
+    if let Some(importer) = for_path(path) {
+        return Ok(Some(importer));
+    }
+
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut start = Vec::with_capacity(SNIFF_BYTES as usize);
+    file.take(SNIFF_BYTES).read_to_end(&mut start)?;
+
+    Ok(IMPORTERS
+        .iter()
+        .copied()
+        .find(|importer| importer.sniff(&start, len)))
+}
+
+/// Whether the extension of a file is one of a format that can be imported
+pub fn is_supported(path: &Path) -> bool {
+    for_path(path).is_some()
+}
+
+/// The filters of the import dialog by their name, all the formats first.
+/// The extensions are in lower and upper case since some platforms match
+/// them case sensitively.
+pub fn dialog_filters() -> Vec<(String, Vec<String>)> {
+    let extensions = |importer: &dyn Importer| -> Vec<String> {
+        importer
+            .extensions()
+            .iter()
+            .flat_map(|e| [e.to_string(), e.to_uppercase()])
+            .collect()
+    };
+
+    let mut filters = vec![(
+        tr("dialog.import.all").to_string(),
+        IMPORTERS.iter().flat_map(|i| extensions(*i)).collect(),
+    )];
+    for importer in IMPORTERS {
+        let dotted: Vec<String> = importer
+            .extensions()
+            .iter()
+            .map(|e| format!(".{}", e))
+            .collect();
+        filters.push((
+            format!("{} ({})", importer.name(), dotted.join(", ")),
+            extensions(importer),
+        ));
+    }
+    filters
+}
+
+/// The names of the formats like "OBJ or STL", for messages about files that
+/// can't be imported
+pub fn format_names() -> String {
+    let names: Vec<String> = IMPORTERS
+        .iter()
+        .map(|importer| importer.extensions()[0].to_uppercase())
+        .collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => names.concat(),
+    }
+}
@@ -1,11 +1,200 @@
-use std::io::{BufRead, BufReader, Read, Seek};
-
-use log::trace;
-
-use crate::{aabb::AABB, importer::Material, importer::ObjMesh, importer::Object, mesh::Vertex};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Seek},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use log::{trace, warn};
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aabb::AABB,
+    error::{Error, Result},
+    importer::cache::{Checkpoint, Resumed},
+    importer::float,
+    importer::Material,
+    importer::ObjMesh,
+    importer::Object,
+    mesh::{Mesh, MeshGeometry, Vertex},
+    topology,
+    ui::i18n::tr,
+};
 
 const STL_HEADER_SIZE: u64 = 80;
 const STL_TRIANGLE_SIZE: usize = 50;
+/// Binary files bigger than this save checkpoints while they're parsed, so
+/// a cancelled or crashed import doesn't start over
+const CHECKPOINT_MIN_SIZE: u64 = 256 * 1024 * 1024;
+/// Triangles parsed between two checkpoints
+const CHECKPOINT_TRIANGLES: u64 = 4 * 1024 * 1024;
+
+/// Units the coordinates of a file are in. STL doesn't store them, but some
+/// exporters mention them in the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Units {
+    Millimeters,
+    Inches,
+}
+
+impl Units {
+    pub const ALL: [Units; 2] = [Units::Millimeters, Units::Inches];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Units::Millimeters => tr("units.millimeters"),
+            Units::Inches => tr("units.inches"),
+        }
+    }
+
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            Units::Millimeters => "mm",
+            Units::Inches => "in",
+        }
+    }
+
+    fn from_word(word: &str) -> Option<Units> {
+        match word {
+            "mm" | "millimeter" | "millimeters" | "millimetre" | "millimetres" => {
+                Some(Units::Millimeters)
+            }
+            "in" | "inch" | "inches" => Some(Units::Inches),
+            _ => None,
+        }
+    }
+}
+
+/// Looks for the units in the header of a binary STL or the `solid` line of
+/// an ASCII one, e.g. `UNITS=mm` or `Units: inches`
+fn unit_hint(header: &[u8]) -> Option<Units> {
+    let header = String::from_utf8_lossy(header).to_lowercase();
+    let words: Vec<&str> = header
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    for (i, word) in words.iter().enumerate() {
+        if *word == "unit" || *word == "units" {
+            if let Some(units) = words.get(i + 1).and_then(|w| Units::from_word(w)) {
+                return Some(units);
+            }
+        }
+    }
+
+    // "in" and "mm" are too likely to mean something else on their own
+    words
+        .iter()
+        .filter(|w| **w != "in" && **w != "mm")
+        .find_map(|w| Units::from_word(w))
+}
+
+/// How a binary STL stores the color of each face in its attribute bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorFormat {
+    /// VisCAM and SolidView: 5 bits each of blue, green and red from the low
+    /// bits, the top bit is set on the faces that have a color
+    VisCam,
+    /// Materialise Magics: 5 bits each of red, green and blue from the low
+    /// bits, the top bit is set on the faces that use the object's color
+    Materialise,
+    /// The attribute bytes aren't colors, every face is white
+    Ignored,
+}
+
+impl ColorFormat {
+    pub const ALL: [ColorFormat; 3] = [
+        ColorFormat::VisCam,
+        ColorFormat::Materialise,
+        ColorFormat::Ignored,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorFormat::VisCam => tr("stl_colors.viscam"),
+            ColorFormat::Materialise => tr("stl_colors.materialise"),
+            ColorFormat::Ignored => tr("stl_colors.ignored"),
+        }
+    }
+}
+
+/// Colors of the faces of a binary STL
+#[derive(Debug, Clone)]
+pub struct FaceColors {
+    pub format: ColorFormat,
+    /// Whether the file could be using another format than the detected one
+    pub ambiguous: bool,
+    /// Color of the faces without their own from the `COLOR=` in the header
+    object_color: Option<glm::Vec3>,
+    /// Attribute bytes of the face each vertex belongs to, to recolor the
+    /// mesh when the format changes
+    attributes: Vec<u16>,
+}
+
+impl FaceColors {
+    /// Materialise files have `COLOR=` in their header, VisCAM ones only set
+    /// the top bit of the attributes. When neither tells them apart, the
+    /// attributes might not be colors at all.
+    fn detect(header: &[u8], attributes: Vec<u16>) -> Option<FaceColors> {
+        let object_color = header
+            .windows(6)
+            .position(|w| w == b"COLOR=")
+            .and_then(|i| header.get(i + 6..i + 9))
+            .map(|c| glm::vec3(c[0] as f32, c[1] as f32, c[2] as f32) / 255.0);
+        let flagged = attributes.iter().any(|a| a & 0x8000 != 0);
+        let unflagged = attributes.iter().any(|a| *a != 0 && a & 0x8000 == 0);
+
+        let (format, ambiguous) = match (object_color.is_some(), flagged, unflagged) {
+            (_, false, false) => return None,
+            (true, _, _) => (ColorFormat::Materialise, false),
+            (false, true, false) => (ColorFormat::VisCam, false),
+            (false, true, true) => (ColorFormat::VisCam, true),
+            (false, false, true) => (ColorFormat::Materialise, true),
+        };
+
+        Some(FaceColors {
+            format,
+            ambiguous,
+            object_color,
+            attributes,
+        })
+    }
+
+    fn color(&self, attribute: u16) -> glm::Vec3 {
+        let channel = |shift: u16| ((attribute >> shift) & 0x1f) as f32 / 31.0;
+        let has_color = attribute & 0x8000 != 0;
+
+        match self.format {
+            ColorFormat::VisCam if has_color => glm::vec3(channel(10), channel(5), channel(0)),
+            ColorFormat::Materialise if !has_color => {
+                glm::vec3(channel(0), channel(5), channel(10))
+            }
+            ColorFormat::Materialise => self.object_color.unwrap_or(Vertex::WHITE),
+            _ => Vertex::WHITE,
+        }
+    }
+
+    fn apply(&self, vertices: &mut [Vertex]) {
+        for (vertex, attribute) in vertices.iter_mut().zip(&self.attributes) {
+            vertex.color = self.color(*attribute);
+        }
+    }
+
+    /// Recolors the mesh with `format`, does nothing if the mesh's vertices
+    /// changed since it was imported
+    pub fn set_format(&mut self, format: ColorFormat, mesh: &mut Mesh) {
+        if mesh.vertices.len() != self.attributes.len() {
+            return;
+        }
+
+        self.format = format;
+        let mut vertices = std::mem::take(&mut mesh.vertices);
+        let indices = std::mem::take(&mut mesh.indices);
+        self.apply(&mut vertices);
+        mesh.set_geometry(MeshGeometry::new(vertices, indices, &mesh.materials));
+    }
+}
 
 #[repr(packed(2))]
 #[derive(Debug)]
@@ -15,19 +204,33 @@ struct STLTriangle {
     attribute_byte_count: u16,
 }
 
-struct FacetIterator<R: Read> {
+struct FacetIterator<'a, R: Read> {
     reader: BufReader<R>,
+    path: &'a Path,
+    /// The line read last, counting from 1
+    line: usize,
+    /// Why the facets stopped before the end of the file
+    error: Option<Error>,
 }
 
-impl<R: Read> FacetIterator<R> {
-    fn new(reader: R) -> Self {
+impl<'a, R: Read> FacetIterator<'a, R> {
+    /// `reader` starts after the `solid` line
+    fn new(reader: BufReader<R>, path: &'a Path) -> Self {
         FacetIterator {
-            reader: BufReader::new(reader),
+            reader,
+            path,
+            line: 1,
+            error: None,
         }
     }
+
+    fn fail(&mut self, message: &str) -> Option<STLTriangle> {
+        self.error = Some(Error::parse(self.path, self.line, message));
+        None
+    }
 }
 
-impl<R: Read> Iterator for FacetIterator<R> {
+impl<R: Read> Iterator for FacetIterator<'_, R> {
     type Item = STLTriangle;
 
     fn next(&mut self) -> Option<STLTriangle> {
@@ -39,187 +242,279 @@ impl<R: Read> Iterator for FacetIterator<R> {
             match self.reader.read_line(&mut line) {
                 Ok(0) => return None, // Reached EOF
                 Ok(_) => {
+                    self.line += 1;
                     if line.contains("normal") {
-                        let mut norm_iter = line
-                            .split_whitespace()
-                            .skip(2)
-                            .map(|s| s.parse::<f32>().unwrap());
-                        normal.x = norm_iter.next().unwrap();
-                        normal.y = norm_iter.next().unwrap();
-                        normal.z = norm_iter.next().unwrap();
+                        match float::parse_floats(line.split_whitespace().skip(2)) {
+                            Some([x, y, z]) => normal = glm::vec3(x, y, z),
+                            None => return self.fail("Incomplete or invalid facet normal"),
+                        }
                     } else if line.contains("vertex") {
-                        let mut vertex_iter = line
-                            .split_whitespace()
-                            .skip(1)
-                            .map(|s| s.parse::<f32>().unwrap());
-                        vertices.push(glm::vec3(
-                            vertex_iter.next().unwrap(),
-                            vertex_iter.next().unwrap(),
-                            vertex_iter.next().unwrap(),
-                        ));
+                        match float::parse_floats(line.split_whitespace().skip(1)) {
+                            Some([x, y, z]) => vertices.push(glm::vec3(x, y, z)),
+                            None => return self.fail("Incomplete or invalid vertex"),
+                        }
                     } else if line.contains("endfacet") {
                         break;
                     }
                 }
-                // TODO: might need to panic and log here in the future
-                Err(_) => return None, // Error reading
+                Err(e) => {
+                    self.error = Some(e.into());
+                    return None;
+                }
             }
             line.clear();
         }
 
-        let mut verts_iter = vertices.iter();
+        let &[a, b, c] = vertices.as_slice() else {
+            return self.fail("Facet doesn't have 3 vertices");
+        };
 
         Some(STLTriangle {
             normal,
-            verts: [
-                *verts_iter.next().unwrap(),
-                *verts_iter.next().unwrap(),
-                *verts_iter.next().unwrap(),
-            ],
+            verts: [a, b, c],
             attribute_byte_count: 0,
         })
     }
 }
 
-fn parse_ascii_stl(file: std::fs::File) -> Result<Object, Box<dyn std::error::Error>> {
+fn parse_ascii_stl(file: std::fs::File, source: &Path) -> Result<Object> {
     let mut reader = BufReader::new(file);
-    let mut vertices = Vec::new();
-    let mut indices = Vec::new();
-    let mut min_aabb = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
-    let mut max_aabb = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
-
-    reader.read_line(&mut String::new())?; // Skip the first line (solid name)
-    let facet_iter = FacetIterator::new(reader);
-
-    let tex_coords = glm::vec2(0.0, 0.0);
-
-    for (i, triangle) in facet_iter.enumerate() {
-        for vert in triangle.verts {
-            vertices.push(Vertex {
-                position: glm::vec3(vert.x, vert.y, vert.z),
-                normal: glm::vec3(triangle.normal.x, triangle.normal.y, triangle.normal.z),
-                tex_coords,
-            });
 
-            min_aabb = glm::min(min_aabb, vert);
-            max_aabb = glm::max(max_aabb, vert);
+    let mut solid = String::new();
+    reader.read_line(&mut solid)?; // the first line is "solid <name>"
+    let mut facets = FacetIterator::new(reader, source);
+
+    let obj = build_object(&mut facets, solid.as_bytes());
+    match facets.error {
+        Some(e) => Err(e),
+        None => Ok(obj),
+    }
+}
+
+/// Builds the mesh out of the triangles, merging the vertices they share.
+/// Only vertices with the same normal and color are merged so the hard edges
+/// stay hard.
+struct Builder {
+    min_aabb: glm::Vec3,
+    max_aabb: glm::Vec3,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    /// The attribute of the triangle each vertex was made for
+    attributes: Vec<u16>,
+    ids: HashMap<([u32; 3], [u32; 3], u16), u32>,
+}
+
+impl Builder {
+    /// Carries on from the geometry of a checkpoint, empty for a new one
+    fn new(resumed: Resumed) -> Self {
+        let mut min_aabb = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
+        let mut max_aabb = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
+        let mut ids = HashMap::with_capacity(resumed.vertices.len());
+        for (i, vertex) in resumed.vertices.iter().enumerate() {
+            let (position, normal) = (vertex.position, vertex.normal);
+            let key = (
+                topology::position_key(position),
+                topology::position_key(normal),
+                resumed.attributes[i],
+            );
+            ids.insert(key, i as u32);
+            min_aabb = glm::min(min_aabb, position);
+            max_aabb = glm::max(max_aabb, position);
         }
 
-        indices.push(i as u32 * 3);
-        indices.push(i as u32 * 3 + 1);
-        indices.push(i as u32 * 3 + 2);
+        Builder {
+            min_aabb,
+            max_aabb,
+            vertices: resumed.vertices,
+            indices: resumed.indices,
+            attributes: resumed.attributes,
+            ids,
+        }
     }
 
-    let aabb = AABB::new(min_aabb, max_aabb);
+    fn add(&mut self, triangle: STLTriangle) {
+        let tex_coords = glm::vec2(0.0, 0.0);
+        let normal = triangle.normal;
+        let verts = triangle.verts;
+        let attribute = triangle.attribute_byte_count;
+        for vert in verts {
+            let key = (
+                topology::position_key(vert),
+                topology::position_key(normal),
+                attribute,
+            );
+            let id = *self.ids.entry(key).or_insert_with(|| {
+                self.vertices.push(Vertex::new(vert, normal, tex_coords));
+                self.attributes.push(attribute);
+                self.vertices.len() as u32 - 1
+            });
+            self.indices.push(id);
 
-    Ok(Object {
-        name: "default_object".to_string(),
-        meshes: vec![ObjMesh {
-            name: "default_mesh".to_string(),
-            vertices,
+            self.min_aabb = glm::min(self.min_aabb, vert);
+            self.max_aabb = glm::max(self.max_aabb, vert);
+        }
+    }
+
+    fn finish(self, header: &[u8]) -> Object {
+        let Builder {
+            mut vertices,
             indices,
-            material: Some(Material::default()),
-        }],
-        aabb,
-    })
-}
+            attributes,
+            ..
+        } = self;
+        trace!(
+            "Merged {} STL vertices into {}",
+            indices.len(),
+            vertices.len()
+        );
+
+        let colors = FaceColors::detect(header, attributes);
+        if let Some(colors) = &colors {
+            trace!("Detected {:?} face colors", colors.format);
+            colors.apply(&mut vertices);
+        }
 
-#[derive(Debug)]
-struct TrianglesIter<R: Read> {
-    reader: R,
-    buf: Vec<u8>,
-    triangles_to_read: usize,
-    triangles_read: usize,
+        let aabb = AABB::new(self.min_aabb, self.max_aabb);
+
+        Object {
+            name: "default_object".to_string(),
+            meshes: vec![ObjMesh {
+                name: "default_mesh".to_string(),
+                vertices,
+                indices,
+                materials: vec![(0, Some(Material::default()))],
+            }],
+            aabb,
+            units: unit_hint(header),
+            colors,
+            material_libs: Vec::new(),
+            texture_files: HashMap::new(),
+        }
+    }
 }
 
-impl<R: Read> TrianglesIter<R> {
-    fn new(reader: R, triangles_to_read: usize) -> Self {
-        TrianglesIter {
-            reader,
-            buf: vec![0u8; STL_TRIANGLE_SIZE],
-            triangles_to_read,
-            triangles_read: 0,
-        }
+fn build_object(triangles: impl Iterator<Item = STLTriangle>, header: &[u8]) -> Object {
+    let mut builder = Builder::new(Resumed::default());
+    // binary files say how many triangles they have, which saves growing the
+    // indices over and over. Only the ones that fit in the file count, so a
+    // corrupt count can't make it reserve more than the file holds.
+    builder.indices.reserve(triangles.size_hint().0 * 3);
+    for triangle in triangles {
+        builder.add(triangle);
     }
+    builder.finish(header)
 }
 
-impl<R: Read> Iterator for TrianglesIter<R> {
-    type Item = STLTriangle;
+/// Decodes the little endian floats at the start of `bytes`
+fn read_vec3(bytes: &[u8]) -> glm::Vec3 {
+    let float = |i: usize| f32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+    glm::vec3(float(0), float(4), float(8))
+}
 
-    fn next(&mut self) -> Option<STLTriangle> {
-        if self.triangles_read >= self.triangles_to_read {
-            None
-        } else {
-            let _ = self.reader.read_exact(&mut self.buf);
-
-            self.triangles_read += 1;
-
-            unsafe {
-                let normal = (self.buf[0..12].as_ptr() as *const glm::Vec3).read();
-                let verts = [
-                    (self.buf[12..24].as_ptr() as *const glm::Vec3).read(),
-                    (self.buf[24..36].as_ptr() as *const glm::Vec3).read(),
-                    (self.buf[36..48].as_ptr() as *const glm::Vec3).read(),
-                ];
-                let abc = u16::from_le_bytes([self.buf[48], self.buf[49]]);
-
-                Some(STLTriangle {
-                    verts,
-                    normal,
-                    attribute_byte_count: abc,
-                })
-            }
-        }
+fn decode_triangle(bytes: &[u8]) -> STLTriangle {
+    STLTriangle {
+        normal: read_vec3(&bytes[0..12]),
+        verts: [
+            read_vec3(&bytes[12..24]),
+            read_vec3(&bytes[24..36]),
+            read_vec3(&bytes[36..48]),
+        ],
+        attribute_byte_count: u16::from_le_bytes([bytes[48], bytes[49]]),
     }
 }
 
-fn parse_binary_stl(mut file: std::fs::File) -> Result<Object, Box<dyn std::error::Error>> {
-    // skip header for now
-    file.seek(std::io::SeekFrom::Start(STL_HEADER_SIZE))?;
+/// Decodes `count` triangles straight from the mapped bytes of a binary
+/// file, without copying them out first. The header's count can't be
+/// trusted, a file that ends early gives the complete triangles it has.
+fn triangles(bytes: &[u8], count: usize) -> impl ExactSizeIterator<Item = STLTriangle> + '_ {
+    let in_file = bytes.len() / STL_TRIANGLE_SIZE;
+    if in_file < count {
+        warn!("The STL ends after {} of its {} triangles", in_file, count);
+    }
+    bytes
+        .chunks_exact(STL_TRIANGLE_SIZE)
+        .take(count)
+        .map(decode_triangle)
+}
 
-    let mut buf: [u8; 4] = [0; 4];
-    file.read_exact(&mut buf)?;
-    let tri_count: u32 = u32::from_le_bytes(buf);
+/// Maps the file into memory, the triangles are decoded from the pages the
+/// OS reads in rather than from a copy in a buffer
+fn map(file: &std::fs::File) -> Result<Mmap> {
+    // SAFETY: the map is only read while the file is parsed. A file changed
+    // by another program meanwhile gives wrong geometry, not memory errors,
+    // since every triangle is decoded from bytes of a fixed size.
+    let map = unsafe { Mmap::map(file)? };
+    if map.len() < STL_HEADER_SIZE as usize + 4 {
+        return Err(Error::Invalid(
+            "The STL is too short for its header".to_string(),
+        ));
+    }
+    Ok(map)
+}
 
-    let mut min_aabb = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
-    let mut max_aabb = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
-    let mut vertices = Vec::new();
-    let mut indices = Vec::new();
+fn parse_binary_stl(
+    file: std::fs::File,
+    source: &Path,
+    cancelled: Option<&AtomicBool>,
+) -> Result<Object> {
+    let map = map(&file)?;
+    let header = &map[..STL_HEADER_SIZE as usize];
+    let count = &map[STL_HEADER_SIZE as usize..STL_HEADER_SIZE as usize + 4];
+    let tri_count = u32::from_le_bytes(count.try_into().unwrap());
+
+    if map.len() as u64 >= CHECKPOINT_MIN_SIZE {
+        if let Some((checkpoint, resumed)) = Checkpoint::open(source) {
+            return parse_with_checkpoints(&map, tri_count, checkpoint, resumed, cancelled);
+        }
+    }
 
-    let reader = BufReader::new(file);
+    let data = &map[STL_HEADER_SIZE as usize + 4..];
+    Ok(build_object(triangles(data, tri_count as usize), header))
+}
 
-    let triangles_reader = TrianglesIter::new(reader, tri_count as usize);
+/// Parses a big binary file from where its checkpoint left off, saving a new
+/// one every `CHECKPOINT_TRIANGLES`. Cancelling stops at the next one.
+fn parse_with_checkpoints(
+    map: &[u8],
+    tri_count: u32,
+    mut checkpoint: Checkpoint,
+    resumed: Resumed,
+    cancelled: Option<&AtomicBool>,
+) -> Result<Object> {
+    let header = &map[..STL_HEADER_SIZE as usize];
+    let data = &map[STL_HEADER_SIZE as usize + 4..];
+    let in_file = (data.len() / STL_TRIANGLE_SIZE) as u64;
+    let mut read = resumed.triangles.min(tri_count as u64).min(in_file);
+    let start = read as usize * STL_TRIANGLE_SIZE;
+    let triangles = triangles(&data[start..], (tri_count as u64 - read) as usize);
+    let mut builder = Builder::new(resumed);
+    builder.indices.reserve(triangles.len() * 3);
+
+    for triangle in triangles {
+        builder.add(triangle);
+        read += 1;
+        if !read.is_multiple_of(CHECKPOINT_TRIANGLES) {
+            continue;
+        }
 
-    let tex_coords = glm::vec2(0.0, 0.0);
-    for (i, triangle) in triangles_reader.enumerate() {
-        for vert in triangle.verts {
-            vertices.push(Vertex {
-                position: glm::vec3(vert.x, vert.y, vert.z),
-                normal: glm::vec3(triangle.normal.x, triangle.normal.y, triangle.normal.z),
-                tex_coords,
+        let saved = checkpoint.save(
+            read,
+            &builder.vertices,
+            &builder.indices,
+            &builder.attributes,
+        );
+        if let Err(e) = &saved {
+            warn!("Failed to save the import checkpoint: {}", e);
+        }
+        if cancelled.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            return Err(Error::Cancelled {
+                resumable: saved.is_ok(),
             });
-
-            min_aabb = glm::min(min_aabb, vert);
-            max_aabb = glm::max(max_aabb, vert);
         }
-
-        indices.push(i as u32 * 3);
-        indices.push(i as u32 * 3 + 1);
-        indices.push(i as u32 * 3 + 2);
     }
 
-    let aabb = AABB::new(min_aabb, max_aabb);
-
-    Ok(Object {
-        name: "default_object".to_string(),
-        meshes: vec![ObjMesh {
-            name: "default_mesh".to_string(),
-            vertices,
-            indices,
-            material: Some(Material::default()),
-        }],
-        aabb,
-    })
+    checkpoint.finish();
+    Ok(builder.finish(header))
 }
 
 fn is_ascii(buf: &[u8]) -> bool {
@@ -232,7 +527,51 @@ fn is_ascii(buf: &[u8]) -> bool {
     true
 }
 
-pub fn load_stl(mut file: std::fs::File) -> Result<Object, Box<dyn std::error::Error>> {
+/// The text exporters leave in the `solid` line of an ASCII STL or the header
+/// of a binary one, often the name of the tool. `start` is the beginning of
+/// the file.
+pub fn header_text(start: &[u8]) -> Option<String> {
+    let text = if is_ascii(&start[..start.len().min(512)]) {
+        let line = start.split(|b| *b == b'\n').next()?;
+        String::from_utf8_lossy(line)
+            .trim()
+            .strip_prefix("solid")?
+            .to_string()
+    } else {
+        let header = start.get(..STL_HEADER_SIZE as usize)?;
+        String::from_utf8_lossy(header)
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect()
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Whether a file of `len` bytes starting with `start` is an STL: the
+/// triangle count of a binary one matches its size, an ASCII one starts with
+/// a `solid` line followed by facets
+pub fn sniff(start: &[u8], len: u64) -> bool {
+    let header = STL_HEADER_SIZE as usize;
+    if let Some(count) = start.get(header..header + 4) {
+        let count = u32::from_le_bytes(count.try_into().unwrap()) as u64;
+        if len == STL_HEADER_SIZE + 4 + count * STL_TRIANGLE_SIZE as u64 {
+            return true;
+        }
+    }
+
+    let text = String::from_utf8_lossy(&start[..start.len().min(512)]);
+    text.trim_start().starts_with("solid") && text.contains("facet")
+}
+
+/// Reads the STL at `source` from `file`. Big binary files can be cancelled
+/// through `cancelled`, and pick up where they stopped when they're loaded
+/// again.
+pub fn load_stl(
+    mut file: std::fs::File,
+    source: &Path,
+    cancelled: Option<&AtomicBool>,
+) -> Result<Object> {
     let mut buf: [u8; 512] = [0; 512];
     let _ = file.read_exact(&mut buf);
     _ = file.seek(std::io::SeekFrom::Start(0));
@@ -240,12 +579,46 @@ pub fn load_stl(mut file: std::fs::File) -> Result<Object, Box<dyn std::error::E
 
     let now = std::time::Instant::now();
     let obj = if is_ascii {
-        parse_ascii_stl(file)?
+        parse_ascii_stl(file, source)?
     } else {
-        parse_binary_stl(file)?
+        parse_binary_stl(file, source, cancelled)?
     };
     let elapsed = now.elapsed();
     trace!("Loaded in {} ms", elapsed.as_millis());
 
     Ok(obj)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Binary triangles whose first corner's x is their index
+    fn triangle_bytes(count: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for i in 0..count {
+            let mut triangle = [0u8; STL_TRIANGLE_SIZE];
+            triangle[12..16].copy_from_slice(&(i as f32).to_le_bytes());
+            bytes.extend_from_slice(&triangle);
+        }
+        bytes
+    }
+
+    fn first_corners(bytes: &[u8], count: usize) -> Vec<f32> {
+        triangles(bytes, count).map(|t| t.verts[0].x).collect()
+    }
+
+    #[test]
+    fn header_count_is_followed() {
+        assert_eq!(first_corners(&triangle_bytes(5), 3), [0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn file_ending_early_keeps_its_complete_triangles() {
+        let mut bytes = triangle_bytes(3);
+        // half of a fourth triangle
+        bytes.extend_from_slice(&[0; STL_TRIANGLE_SIZE / 2]);
+        assert_eq!(first_corners(&bytes, 200_000), [0.0, 1.0, 2.0]);
+        assert_eq!(triangles(&bytes, u32::MAX as usize).len(), 3);
+    }
+}
@@ -0,0 +1,203 @@
+use std::time::{Duration, Instant};
+
+use crate::{debug_draw::DebugDraw, mesh::Vertex, model::Model, ui::ui::State};
+
+/// How long the cursor has to rest on a mesh before its name shows up
+const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+const HIGHLIGHT_COLOR: glm::Vec3 = glm::Vec3 {
+    x: 1.0,
+    y: 0.3,
+    z: 1.0,
+};
+
+/// The triangle under the cursor while inspecting
+#[derive(Debug, Clone)]
+pub struct Face {
+    pub model_id: u32,
+    pub mesh: usize,
+    /// Index of the triangle in the mesh, the first of its indices is at 3 times this
+    pub triangle: usize,
+    /// The vertices as they're stored in the mesh
+    pub vertices: [Vertex; 3],
+    /// Where the vertices are drawn, for highlighting the triangle
+    pub world_positions: [glm::Vec3; 3],
+}
+
+impl Face {
+    /// Outlines the triangle and marks its vertices
+    pub fn draw(&self, debug: &mut DebugDraw) {
+        let [a, b, c] = self.world_positions;
+        let size = glm::length(b - a)
+            .max(glm::length(c - b))
+            .max(glm::length(a - c));
+
+        debug.line(a, b, HIGHLIGHT_COLOR);
+        debug.line(b, c, HIGHLIGHT_COLOR);
+        debug.line(c, a, HIGHLIGHT_COLOR);
+        for p in [a, b, c] {
+            debug.point(p, size * 0.1, HIGHLIGHT_COLOR);
+        }
+    }
+}
+
+/// Distance along the ray where it hits the triangle, using Möller–Trumbore
+fn intersect(origin: glm::Vec3, direction: glm::Vec3, [a, b, c]: [glm::Vec3; 3]) -> Option<f32> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = glm::cross(direction, edge2);
+    let det = glm::dot(edge1, p);
+    // the ray is parallel to the triangle, both sides are hit since models
+    // aren't drawn with backface culling
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let s = origin - a;
+    let u = glm::dot(s, p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = glm::cross(s, edge1);
+    let v = glm::dot(direction, q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = glm::dot(edge2, q) * inv_det;
+    (t > 0.0).then_some(t)
+}
+
+/// Finds the closest triangle of the model the ray hits, along with the
+/// distance to it. The ray is in world space.
+fn pick_face(model: &Model, origin: glm::Vec3, direction: glm::Vec3) -> Option<(f32, Face)> {
+    let mut closest: Option<(f32, usize, usize)> = None;
+
+    for (mesh_idx, mesh) in model.meshes.iter().enumerate() {
+        if !mesh.visible {
+            continue;
+        }
+        let transform = model.mesh_model_matrix(mesh_idx);
+        if !glm::ext::is_invertible(&transform) {
+            continue;
+        }
+        // the ray is moved into the mesh's space instead of transforming every
+        // vertex, distances along it stay the same
+        let inverse = glm::inverse(&transform);
+        let o = inverse * glm::vec4(origin.x, origin.y, origin.z, 1.0);
+        let d = inverse * glm::vec4(direction.x, direction.y, direction.z, 0.0);
+        let o = glm::vec3(o.x / o.w, o.y / o.w, o.z / o.w);
+        let d = glm::vec3(d.x, d.y, d.z);
+
+        for (triangle, indices) in mesh.indices.chunks_exact(3).enumerate() {
+            let corners = [0, 1, 2].map(|i| mesh.vertices[indices[i] as usize].position);
+            if let Some(t) = intersect(o, d, corners) {
+                if closest.is_none_or(|(closest_t, ..)| t < closest_t) {
+                    closest = Some((t, mesh_idx, triangle));
+                }
+            }
+        }
+    }
+
+    let (t, mesh_idx, triangle) = closest?;
+    let mesh = &model.meshes[mesh_idx];
+    let transform = model.mesh_model_matrix(mesh_idx);
+    let vertices =
+        [0, 1, 2].map(|i| mesh.vertices[mesh.indices[triangle * 3 + i] as usize].clone());
+    let world_positions = vertices.clone().map(|v| {
+        let p = transform * glm::vec4(v.position.x, v.position.y, v.position.z, 1.0);
+        glm::vec3(p.x / p.w, p.y / p.w, p.z / p.w)
+    });
+
+    Some((
+        t,
+        Face {
+            model_id: model.id,
+            mesh: mesh_idx,
+            triangle,
+            vertices,
+            world_positions,
+        },
+    ))
+}
+
+/// Picks the closest triangle of the displayed models under `cursor`, a
+/// point in the viewport from 0 to 1 on both axes
+pub fn pick(state: &State, cursor: [f32; 2]) -> Option<Face> {
+    // the cursor's point at two depths in front of the camera gives the ray's
+    // direction, that works for both depth conventions
+    let inverse = glm::inverse(&state.view_projection);
+    let [near, far] = [0.0, 0.5].map(|depth| {
+        let p = inverse * glm::vec4(cursor[0] * 2.0 - 1.0, 1.0 - cursor[1] * 2.0, depth, 1.0);
+        glm::vec3(p.x / p.w, p.y / p.w, p.z / p.w)
+    });
+    let origin = state.camera.position;
+    let direction = glm::normalize(far - near);
+
+    state
+        .objects
+        .iter()
+        .filter(|m| m.visible && !m.evicted && state.selection.contains(&m.id))
+        .filter_map(|m| pick_face(m, origin, direction))
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, face)| face)
+}
+
+/// Picks the triangle under the cursor of the displayed models while inspecting
+pub fn update(state: &mut State) {
+    state.inspected_face = None;
+    if !state.inspecting {
+        return;
+    }
+    let Some(cursor) = state.viewport_cursor else {
+        return;
+    };
+
+    state.inspected_face = pick(state, cursor);
+}
+
+/// The cursor resting in the viewport. It's only picked under once it rested
+/// for `HOVER_DELAY`, so moving it over big models stays cheap.
+#[derive(Debug)]
+pub struct Hover {
+    cursor: [f32; 2],
+    since: Instant,
+    picked: bool,
+    /// Id of the model and index of the mesh under the cursor, once picked
+    pub mesh: Option<(u32, usize)>,
+}
+
+/// Finds the mesh under the resting cursor for the viewport's tooltip
+pub fn update_hover(state: &mut State) {
+    let cursor = state
+        .viewport_cursor
+        .filter(|_| !state.inspecting && !state.is_cursor_captured);
+    let Some(cursor) = cursor else {
+        state.hover = None;
+        return;
+    };
+
+    match &state.hover {
+        Some(hover) if hover.cursor == cursor => {
+            if hover.picked || hover.since.elapsed() < HOVER_DELAY {
+                return;
+            }
+        }
+        _ => {
+            state.hover = Some(Hover {
+                cursor,
+                since: Instant::now(),
+                picked: false,
+                mesh: None,
+            });
+            return;
+        }
+    }
+
+    let mesh = pick(state, cursor).map(|face| (face.model_id, face.mesh));
+    if let Some(hover) = &mut state.hover {
+        hover.picked = true;
+        hover.mesh = mesh;
+    }
+}
@@ -1,70 +1,306 @@
 use std::{
-    fs::File,
-    io::{Read, Write},
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Seek, Write},
+    net::Shutdown,
     os::unix::net::{UnixListener, UnixStream},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::exit,
     sync::mpsc::Receiver,
     thread,
+    time::{Duration, Instant},
 };
 
 use fs4::FileExt;
-use log::error;
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
 
-fn create_named_pipe(pipe_path: PathBuf) -> UnixListener {
+use crate::pacing;
+
+const PIPE_NAME: &str = "3dobs_pipe";
+/// How long a second instance waits for the running one to say what it did
+/// with the files
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a second instance keeps trying to reach one that holds the lock
+/// but may have only just started and not be listening yet
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What a second instance passes on to the running one before exiting
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Message {
+    pub paths: Vec<PathBuf>,
+    /// Models to download, from the command line or from deep links
+    pub urls: Vec<String>,
+}
+
+/// What the running instance did with one of the files or URLs of a message
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileResult {
+    pub name: String,
+    /// Why it isn't being imported, None if it is
+    pub error: Option<String>,
+}
+
+/// The running instance's answer to a `Message`, one result for each path
+/// followed by one for each URL
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Reply {
+    pub files: Vec<FileResult>,
+}
+
+/// A message from another instance that's waiting for the `Reply`
+pub struct Incoming {
+    pub message: Message,
+    stream: UnixStream,
+}
+
+impl Incoming {
+    /// Sends the results back, the other instance prints them and exits
+    pub fn reply(mut self, reply: &Reply) {
+        let sent = bincode::serialize(reply)
+            .map_err(std::io::Error::other)
+            .and_then(|data| self.stream.write_all(&data));
+        if let Err(e) = sent {
+            warn!(
+                "Failed to send the import results to the other instance: {}",
+                e
+            );
+        }
+    }
+}
+
+/// The running instance's end of the pipe. The socket is removed when it's
+/// dropped, and the lock is released with the file.
+pub struct Server {
+    pub rx: Receiver<Incoming>,
+    pipe_path: PathBuf,
+    _lock_file: File,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.pipe_path) {
+            warn!("Failed to remove the pipe {:?}: {}", self.pipe_path, e);
+        }
+    }
+}
+
+fn create_named_pipe(pipe_path: &Path) -> std::io::Result<UnixListener> {
+    // left behind by an instance that crashed
     if pipe_path.exists() {
-        // Remove the existing pipe if it exists.
-        std::fs::remove_file(&pipe_path).expect("Failed to remove existing pipe");
+        std::fs::remove_file(pipe_path)?;
     }
 
-    UnixListener::bind(&pipe_path).expect("Failed to create named pipe")
+    UnixListener::bind(pipe_path)
 }
 
-fn send_args_to_existing_instance(pipe_path: PathBuf, args_paths: Vec<PathBuf>) {
-    let mut stream = UnixStream::connect(pipe_path).expect("Failed to connect to named pipe");
-    let data = bincode::serialize(&args_paths).expect("Failed to serialize arguments");
+/// The lock file and whether this process holds the lock on it
+fn open_lock(lock_path: &Path) -> std::io::Result<(File, bool)> {
+    // not truncated, the pid in it is the running instance's until the lock is ours
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lock_path)?;
+    let locked = file.try_lock_exclusive().is_ok();
+
+    Ok((file, locked))
+}
+
+/// The pid the instance holding the lock wrote into it
+fn lock_owner(lock_file: &mut File) -> Option<u32> {
+    let mut content = String::new();
+    lock_file.read_to_string(&mut content).ok()?;
+
+    content.trim().parse().ok()
+}
+
+fn write_pid(lock_file: &mut File) -> std::io::Result<()> {
+    lock_file.set_len(0)?;
+    lock_file.rewind()?;
+    write!(lock_file, "{}", std::process::id())?;
+    lock_file.flush()
+}
+
+/// Whether a process is still running, None where that can't be told
+fn is_running(pid: u32) -> Option<bool> {
+    let proc = Path::new("/proc");
+    if !proc.is_dir() {
+        return None;
+    }
+
+    Some(proc.join(pid.to_string()).exists())
+}
+
+/// Sends the arguments to the first instance and waits for what it did with
+/// them. The reply is None if it didn't send one in time.
+fn send_args_to_existing_instance(
+    pipe_path: &Path,
+    message: &Message,
+) -> std::io::Result<Option<Reply>> {
+    let started = Instant::now();
+    let mut stream = loop {
+        match UnixStream::connect(pipe_path) {
+            Err(e)
+                if started.elapsed() < CONNECT_TIMEOUT
+                    && matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) =>
+            {
+                thread::sleep(Duration::from_millis(50));
+            }
+            stream => break stream?,
+        }
+    };
+    let data = bincode::serialize(message).map_err(std::io::Error::other)?;
+
+    stream.write_all(&data)?;
+    // the other end reads until the message ends
+    stream.shutdown(Shutdown::Write)?;
+
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+    let mut reply = Vec::new();
+    if let Err(e) = stream.read_to_end(&mut reply) {
+        warn!("No answer from the running instance: {}", e);
+        return Ok(None);
+    }
+
+    Ok(bincode::deserialize(&reply).ok())
+}
+
+/// Prints what the running instance did with each file for shell users, and
+/// whether all of them are being imported
+fn print_reply(reply: &Reply) -> bool {
+    for file in &reply.files {
+        match &file.error {
+            None => println!("Opening {} in the running instance", file.name),
+            Some(error) => println!("Not opening {} because {}", file.name, error),
+        }
+    }
 
-    // Send the arguments to the first instance.
-    stream.write_all(&data).expect("Failed to send arguments");
+    reply.files.iter().all(|f| f.error.is_none())
 }
 
-pub fn init(
-    lock_file: &File,
-    args_paths: Vec<PathBuf>,
-    one_instance: bool,
-) -> Option<Receiver<Vec<PathBuf>>> {
+/// Brings the window forward after another instance passed files to it.
+/// Window managers often don't let a program focus itself, so when it isn't
+/// focused already it also asks for attention: the urgent hint on X11 and
+/// XWayland, a taskbar flash on Windows and a dock bounce on macOS.
+pub fn raise(window: &mut glfw::Window) {
+    let was_focused = window.is_focused();
+    if window.is_iconified() {
+        window.restore();
+    }
+    if !window.is_visible() {
+        window.show();
+    }
+    window.focus();
+
+    // focusing takes effect later, if it's allowed at all
+    if !was_focused {
+        window.request_attention();
+    }
+}
+
+/// Passes `message` on to the running instance and exits, or becomes the
+/// running instance when there's none. The lock is only taken over when the
+/// process whose pid is in it is gone, like when a child the crashed instance
+/// left behind still holds it. The socket goes next to the lock.
+pub fn init(lock_path: &Path, message: Message, one_instance: bool) -> Option<Server> {
     if !one_instance {
         return None;
     }
 
-    let pipe_name = "3dobs_pipe";
+    // exiting skips the destructors, the lock file was only a handle on the
+    // running instance's lock and no socket was made
+    start(lock_path, message).unwrap_or_else(|code| exit(code))
+}
+
+/// What `init` does before exiting, Err holds the exit code when the message
+/// went to the running instance or couldn't be passed on
+fn start(lock_path: &Path, message: Message) -> std::result::Result<Option<Server>, i32> {
+    let pipe_path = lock_path.with_file_name(PIPE_NAME);
 
-    let pipe_path = std::env::temp_dir().join(pipe_name);
+    let (mut lock_file, locked) = match open_lock(lock_path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            error!("Failed to open the lock file {:?}: {}", lock_path, e);
+            return Ok(None);
+        }
+    };
 
-    match lock_file.try_lock_exclusive() {
-        Ok(_) => {}
-        Err(_) => {
-            println!("An instance of the program is already running");
-            send_args_to_existing_instance(pipe_path, args_paths);
-            exit(0);
+    if !locked {
+        let owner = lock_owner(&mut lock_file);
+        if owner.and_then(is_running) != Some(false) {
+            // the socket is there before the pid is written, a missing pid is
+            // an instance that's still starting and gets waited for
+            return match send_args_to_existing_instance(&pipe_path, &message) {
+                Ok(reply) => {
+                    println!("An instance of the program is already running");
+                    let all_opened = reply.as_ref().is_none_or(print_reply);
+                    Err(if all_opened { 0 } else { 1 })
+                }
+                Err(e) => {
+                    error!(
+                        "An instance of the program is already running but it didn't answer: {}",
+                        e
+                    );
+                    Err(1)
+                }
+            };
         }
+        warn!("The instance {:?} that held the lock is gone", owner);
+
+        // the old file stays locked by whoever holds it, but nobody looks at it anymore
+        let lock = std::fs::remove_file(lock_path).and_then(|_| open_lock(lock_path));
+        lock_file = match lock {
+            Ok((file, true)) => file,
+            Ok((_, false)) => {
+                error!("Another instance took the lock first, not listening for files");
+                return Ok(None);
+            }
+            Err(e) => {
+                error!(
+                    "Failed to replace the stale lock file {:?}: {}",
+                    lock_path, e
+                );
+                return Ok(None);
+            }
+        };
+    }
+
+    // listening before the pid is written, so whoever finds the pid can connect
+    let pipe = match create_named_pipe(&pipe_path) {
+        Ok(pipe) => pipe,
+        Err(e) => {
+            error!("Failed to create the pipe {:?}: {}", pipe_path, e);
+            return Ok(None);
+        }
+    };
+    if let Err(e) = write_pid(&mut lock_file) {
+        warn!("Failed to write the pid into {:?}: {}", lock_path, e);
     }
 
-    let pipe = create_named_pipe(pipe_path);
-    let (ipc_tx, ipc_rx) = std::sync::mpsc::channel::<Vec<PathBuf>>();
+    let (ipc_tx, ipc_rx) = std::sync::mpsc::channel::<Incoming>();
 
-    // thread is not joined because it blocks anyway
-    // and there's no cleanup to do or anything
+    // thread is not joined because it blocks anyway, the socket is removed
+    // by the server
     let _ = thread::spawn(move || {
         for stream in pipe.incoming() {
             match stream {
                 Ok(mut stream) => {
-                    let mut serialized_paths = Vec::new();
-                    let _ = stream.read_to_end(&mut serialized_paths);
+                    let mut serialized_message = Vec::new();
+                    let _ = stream.read_to_end(&mut serialized_message);
 
-                    let paths: Vec<PathBuf> = bincode::deserialize(&serialized_paths).unwrap();
+                    let message: Message = match bincode::deserialize(&serialized_message) {
+                        Ok(message) => message,
+                        Err(e) => {
+                            warn!("Ignoring a malformed message from another instance: {}", e);
+                            continue;
+                        }
+                    };
 
-                    ipc_tx.send(paths).unwrap();
+                    if ipc_tx.send(Incoming { message, stream }).is_err() {
+                        break;
+                    }
+                    pacing::request_redraw();
                 }
                 Err(err) => {
                     error!("Error: {:?}", err);
@@ -74,5 +310,124 @@ pub fn init(
         }
     });
 
-    Some(ipc_rx)
+    Ok(Some(Server {
+        rx: ipc_rx,
+        pipe_path,
+        _lock_file: lock_file,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own for each test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("3dobs-ipc-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// The pid of a process that already exited
+    fn dead_pid() -> u32 {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        pid
+    }
+
+    #[test]
+    fn live_owner_keeps_the_lock() {
+        let dir = test_dir("live");
+        let lock_path = dir.join("3dobs.lock");
+
+        let (mut owner, locked) = open_lock(&lock_path).unwrap();
+        assert!(locked);
+        write_pid(&mut owner).unwrap();
+
+        let (mut other, locked) = open_lock(&lock_path).unwrap();
+        assert!(!locked);
+        let pid = lock_owner(&mut other);
+        assert_eq!(pid, Some(std::process::id()));
+        assert_eq!(pid.and_then(is_running), Some(true));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn stale_lock_of_a_dead_pid_is_taken_over() {
+        let dir = test_dir("stale");
+        let lock_path = dir.join("3dobs.lock");
+        let pid = dead_pid();
+        assert_eq!(is_running(pid), Some(false));
+
+        // still locked, like by a child the crashed instance left behind
+        let (mut stale, locked) = open_lock(&lock_path).unwrap();
+        assert!(locked);
+        write!(stale, "{}", pid).unwrap();
+
+        let server = init(&lock_path, Message::default(), true);
+        assert!(server.is_some());
+        let (mut lock, _) = open_lock(&lock_path).unwrap();
+        assert_eq!(lock_owner(&mut lock), Some(std::process::id()));
+        drop(server);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn live_owner_that_does_not_answer_is_not_taken_over() {
+        let dir = test_dir("silent");
+        let lock_path = dir.join("3dobs.lock");
+
+        // alive but without a socket
+        let (mut owner, _) = open_lock(&lock_path).unwrap();
+        write_pid(&mut owner).unwrap();
+
+        assert_eq!(start(&lock_path, Message::default()).err(), Some(1));
+        assert!(!dir.join(PIPE_NAME).exists());
+        let (mut lock, locked) = open_lock(&lock_path).unwrap();
+        assert!(!locked);
+        assert_eq!(lock_owner(&mut lock), Some(std::process::id()));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn message_goes_to_the_running_instance() {
+        let dir = test_dir("forward");
+        let lock_path = dir.join("3dobs.lock");
+        let server = start(&lock_path, Message::default()).unwrap().unwrap();
+
+        let running = thread::spawn(move || {
+            let incoming = server.rx.recv().unwrap();
+            let paths = incoming.message.paths.clone();
+            let files = (paths.iter())
+                .map(|path| FileResult {
+                    name: path.display().to_string(),
+                    error: None,
+                })
+                .collect();
+            incoming.reply(&Reply { files });
+            paths
+        });
+
+        let message = Message {
+            paths: vec!["model.obj".into()],
+            urls: Vec::new(),
+        };
+        assert_eq!(start(&lock_path, message).err(), Some(0));
+        assert_eq!(running.join().unwrap(), [PathBuf::from("model.obj")]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn socket_is_removed_with_the_server() {
+        let dir = test_dir("socket");
+        let pipe_path = dir.join(PIPE_NAME);
+
+        let server = init(&dir.join("3dobs.lock"), Message::default(), true).unwrap();
+        assert!(pipe_path.exists());
+        drop(server);
+        assert!(!pipe_path.exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
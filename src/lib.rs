@@ -1,14 +1,51 @@
 pub mod aabb;
+pub mod browse;
 pub mod camera;
+pub mod camera_path;
+pub mod config;
+pub mod crash;
+pub mod debug_draw;
+pub mod deep_link;
+pub mod detached;
+pub mod error;
+pub mod exporter;
+pub mod file_info;
+pub mod gl_state;
+pub mod gpu;
+pub mod history;
+pub mod hull;
 #[path = "imgui-glfw-support/mod.rs"]
 pub mod imgui_glfw_support;
 #[path = "imgui-opengl-renderer/mod.rs"]
 pub mod imgui_opengl_renderer;
 pub mod importer;
+pub mod inspect;
 pub mod ipc;
+pub mod library;
 pub mod logger;
+pub mod mass_properties;
+pub mod measure;
 pub mod mesh;
 pub mod model;
+pub mod notes;
+pub mod online;
+pub mod pacing;
+pub mod primitive;
+pub mod recording;
+pub mod render_queue;
+pub mod repair;
+pub mod report;
+pub mod samples;
+pub mod scale;
+pub mod scene;
+pub mod script;
 pub mod shader;
+pub mod shell;
+pub mod simplify;
+pub mod slicing;
+pub mod texture;
+pub mod thumbnail;
+pub mod topology;
 pub mod ui;
 pub mod utils;
+pub mod watch;
@@ -0,0 +1,607 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+};
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config, gl_state,
+    importer::{self, cache::Stamp, registry},
+    mass_properties::MeshProperties,
+    texture::{self, TextureFilter},
+    ui::{i18n::tr, notifications::Notification, ui::State},
+    utils,
+};
+
+/// The database the index is kept in, in the config directory
+const INDEX_DB: &str = "library.db";
+/// Where the index was kept before, carried over into the database once
+const OLD_INDEX_FILE: &str = "library.bin";
+const VERSION_KEY: &str = "version";
+const FOLDERS_KEY: &str = "folders";
+/// Bumped whenever the layout of the index changes, older ones are rebuilt
+const INDEX_VERSION: u32 = 2;
+const THUMBNAIL_SIZE: u32 = 96;
+/// Positions are rounded to this fraction of the size of the model before
+/// they're hashed, so the float noise of exporting again doesn't matter
+const HASH_PRECISION: f32 = 1e-5;
+/// How far apart the triangle count, size and area of two models can be for
+/// them to still count as the same model
+const NEAR_TOLERANCE: f32 = 0.01;
+
+/// What the geometry of files is compared by to find duplicates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Hash of the triangles that doesn't depend on the order of the meshes,
+    /// the triangles or their corners
+    hash: u64,
+    triangles: usize,
+    /// Size of the bounds from smallest to largest, the same for a model
+    /// that's turned by 90 degrees
+    extents: [f32; 3],
+    area: f32,
+}
+
+impl Fingerprint {
+    fn new(obj: &importer::Object) -> Self {
+        let size = obj.aabb.max - obj.aabb.min;
+        let mut extents = [size.x, size.y, size.z];
+        extents.sort_by(|a, b| a.total_cmp(b));
+        let step = (glm::length(size) * HASH_PRECISION).max(f32::MIN_POSITIVE);
+        let quantize = |p: glm::Vec3| {
+            let p = (p - obj.aabb.min) / step;
+            [p.x.round() as i64, p.y.round() as i64, p.z.round() as i64]
+        };
+
+        let mut fingerprint = Fingerprint {
+            hash: 0,
+            triangles: 0,
+            extents,
+            area: 0.0,
+        };
+        for mesh in &obj.meshes {
+            let positions: Vec<glm::Vec3> = mesh.vertices.iter().map(|v| v.position).collect();
+            fingerprint.area += MeshProperties::new(&positions, &mesh.indices).area;
+            for triangle in mesh.indices.chunks_exact(3) {
+                let Some(mut corners) = triangle
+                    .iter()
+                    .map(|i| positions.get(*i as usize).map(|p| quantize(*p)))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    continue;
+                };
+                // starts at the smallest corner, keeping the winding
+                let first = (0..3).min_by_key(|i| corners[*i]).unwrap_or(0);
+                corners.rotate_left(first);
+                // summed up so the order of the triangles doesn't matter
+                fingerprint.hash = fingerprint.hash.wrapping_add(fnv1a(&corners.concat()));
+                fingerprint.triangles += 1;
+            }
+        }
+        fingerprint
+    }
+
+    pub fn is_identical(&self, other: &Fingerprint) -> bool {
+        self.hash == other.hash && self.triangles == other.triangles
+    }
+
+    /// Whether two models are most likely the same one, exported again or
+    /// slightly changed
+    pub fn is_near(&self, other: &Fingerprint) -> bool {
+        let close = |a: f32, b: f32| (a - b).abs() <= a.abs().max(b.abs()) * NEAR_TOLERANCE;
+        close(self.triangles as f32, other.triangles as f32)
+            && close(self.area, other.area)
+            && (0..3).all(|i| close(self.extents[i], other.extents[i]))
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the std hasher gives the same hashes in every
+/// build so they can be kept in the index
+fn fnv1a(values: &[i64]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in values.iter().flat_map(|v| v.to_le_bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Another file with the same or nearly the same geometry
+#[derive(Debug)]
+pub struct Duplicate {
+    pub path: PathBuf,
+    /// The geometry is exactly the same, not just close
+    pub identical: bool,
+}
+
+/// A model file found in one of the library's folders
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub path: PathBuf,
+    /// Lowercase extension of the file
+    pub format: String,
+    /// Size of the file in bytes
+    pub size: u64,
+    stamp: Option<Stamp>,
+    pub vertices: usize,
+    pub triangles: usize,
+    pub fingerprint: Option<Fingerprint>,
+    /// Why the file couldn't be read, it's listed without its stats then
+    pub error: Option<String>,
+    /// Preview rendered by the thumbnailer, None if it failed
+    pub thumbnail: Option<PathBuf>,
+}
+
+impl Entry {
+    /// Reads the file for its stats and renders its preview
+    fn index(path: PathBuf) -> Self {
+        let mut entry = Entry {
+            format: path
+                .extension()
+                .map_or(String::new(), |e| e.to_string_lossy().to_lowercase()),
+            size: std::fs::metadata(&path).map_or(0, |m| m.len()),
+            stamp: Stamp::of(&path),
+            vertices: 0,
+            triangles: 0,
+            fingerprint: None,
+            error: None,
+            thumbnail: None,
+            path,
+        };
+
+        match importer::load_from_file(&entry.path, false) {
+            Ok(obj) => {
+                entry.vertices = obj.meshes.iter().map(|m| m.vertices.len()).sum();
+                entry.triangles = obj.meshes.iter().map(|m| m.indices.len() / 3).sum();
+                entry.fingerprint = Some(Fingerprint::new(&obj));
+            }
+            Err(e) => {
+                error!("Failed to index {:?}: {}", entry.path, e);
+                entry.error = Some(e.to_string());
+                return entry;
+            }
+        }
+
+        entry.thumbnail = render_thumbnail(&entry.path);
+        entry
+    }
+
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map_or(String::new(), |n| n.to_string_lossy().into_owned())
+    }
+
+    pub fn size_mb(&self) -> f32 {
+        self.size as f32 / (1024.0 * 1024.0)
+    }
+}
+
+/// Renders the preview with the thumbnailer of this same executable, GLFW
+/// can only make windows on the main thread
+fn render_thumbnail(path: &Path) -> Option<PathBuf> {
+    let dir = config::dir()?.join("library");
+    std::fs::create_dir_all(&dir).ok()?;
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    let output = dir.join(format!("{:016x}.png", hasher.finish()));
+
+    let status = std::process::Command::new(std::env::current_exe().ok()?)
+        .arg("--thumbnail")
+        .arg(path)
+        .arg(&output)
+        .arg(THUMBNAIL_SIZE.to_string())
+        .arg("--no-textures")
+        .status();
+    match status {
+        Ok(status) if status.success() => Some(output),
+        Ok(status) => {
+            error!("The thumbnailer exited with {} for {:?}", status, path);
+            None
+        }
+        Err(e) => {
+            error!("Failed to run the thumbnailer for {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// The index as it was kept in `OLD_INDEX_FILE`
+#[derive(Deserialize)]
+struct OldIndex {
+    version: u32,
+    folders: Vec<PathBuf>,
+    entries: Vec<Entry>,
+}
+
+/// The index on disk. The version and folders are in the default tree, the
+/// entries are in a tree of their own by path so each one can be written as
+/// soon as it's indexed. The filters and textures are left out.
+struct IndexDb {
+    db: sled::Db,
+    entries: sled::Tree,
+}
+
+impl IndexDb {
+    /// None if there's no config directory to keep it in
+    fn open() -> anyhow::Result<Option<Self>> {
+        let Some(dir) = config::dir() else {
+            return Ok(None);
+        };
+        let db = sled::open(dir.join(INDEX_DB))?;
+        let index = IndexDb {
+            entries: db.open_tree("entries")?,
+            db,
+        };
+
+        let old = dir.join(OLD_INDEX_FILE);
+        if let Ok(bytes) = std::fs::read(&old) {
+            match bincode::deserialize::<OldIndex>(&bytes) {
+                Ok(old) if old.version == INDEX_VERSION => {
+                    index.write(&old.folders, &old.entries)?
+                }
+                _ => info!("The old library index can't be carried over, it has to be rebuilt"),
+            }
+            std::fs::remove_file(old)?;
+        }
+
+        Ok(Some(index))
+    }
+
+    /// The folders and entries, None if the index is from an older version
+    fn read(&self) -> anyhow::Result<Option<(Vec<PathBuf>, Vec<Entry>)>> {
+        match self.db.get(VERSION_KEY)? {
+            Some(version) if *version != INDEX_VERSION.to_le_bytes() => return Ok(None),
+            Some(_) => {}
+            None => return Ok(Some(Default::default())),
+        }
+
+        let folders = match self.db.get(FOLDERS_KEY)? {
+            Some(bytes) => bincode::deserialize(&bytes)?,
+            None => Vec::new(),
+        };
+        let entries = self
+            .entries
+            .iter()
+            .values()
+            .map(|bytes| Ok(bincode::deserialize(&bytes?)?))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Some((folders, entries)))
+    }
+
+    fn write_entry(&self, entry: &Entry) -> anyhow::Result<()> {
+        self.entries
+            .insert(path_key(&entry.path), bincode::serialize(entry)?)?;
+        Ok(())
+    }
+
+    /// Replaces the whole index
+    fn write(&self, folders: &[PathBuf], entries: &[Entry]) -> anyhow::Result<()> {
+        let keys: HashSet<Vec<u8>> = entries.iter().map(|e| path_key(&e.path)).collect();
+        let mut batch = sled::Batch::default();
+        for key in self.entries.iter().keys() {
+            let key = key?;
+            if !keys.contains(&*key) {
+                batch.remove(key);
+            }
+        }
+        for entry in entries {
+            batch.insert(path_key(&entry.path), bincode::serialize(entry)?);
+        }
+        self.entries.apply_batch(batch)?;
+
+        self.db.insert(FOLDERS_KEY, bincode::serialize(folders)?)?;
+        self.db.insert(VERSION_KEY, &INDEX_VERSION.to_le_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+fn path_key(path: &Path) -> Vec<u8> {
+    path.as_os_str().as_encoded_bytes().to_vec()
+}
+
+enum Progress {
+    Total(usize),
+    Entry(Entry),
+}
+
+/// Indexing running in the background
+struct Indexing {
+    receiver: Receiver<Progress>,
+    done: usize,
+    total: usize,
+}
+
+/// The models in the folders picked for the library, with their stats and
+/// previews, searchable by name, notes, tags, format and size
+#[derive(Default)]
+pub struct Library {
+    pub folders: Vec<PathBuf>,
+    pub entries: Vec<Entry>,
+    /// Words the name, notes or tags have to have
+    pub query: String,
+    /// Format the entries have to be in, any if None
+    pub format: Option<String>,
+    /// Limits on the size of the files in MB, 0 for none
+    pub min_size: f32,
+    pub max_size: f32,
+    /// Only shows the files that have duplicates
+    pub only_duplicates: bool,
+    /// Files with the same or nearly the same geometry as each file that has
+    /// any
+    pub duplicates: HashMap<PathBuf, Vec<Duplicate>>,
+    pub selected: HashSet<PathBuf>,
+    indexing: Option<Indexing>,
+    /// GL textures of the thumbnails, made once they're first shown
+    thumbnails: HashMap<PathBuf, u32>,
+    /// None if it couldn't be opened, the library isn't kept then
+    db: Option<IndexDb>,
+}
+
+impl Library {
+    /// Reads the index from the config directory, an empty library if it
+    /// can't be read
+    pub fn load() -> Self {
+        let db = match IndexDb::open() {
+            Ok(Some(db)) => db,
+            Ok(None) => return Self::default(),
+            Err(e) => {
+                // like when another instance has it open
+                error!("Failed to open the library index: {}", e);
+                return Self::default();
+            }
+        };
+
+        let mut library = match db.read() {
+            Ok(Some((folders, entries))) => Library {
+                folders,
+                entries,
+                ..Default::default()
+            },
+            Ok(None) => {
+                info!("The library index is from an older version, it has to be rebuilt");
+                Self::default()
+            }
+            Err(e) => {
+                error!("Failed to read the library index: {}", e);
+                Self::default()
+            }
+        };
+        library.find_duplicates();
+        library.db = Some(db);
+        library
+    }
+
+    fn store(&self) {
+        let Some(db) = &self.db else {
+            return;
+        };
+        if let Err(e) = db.write(&self.folders, &self.entries) {
+            error!("Failed to save the library index: {}", e);
+        }
+    }
+
+    pub fn is_indexing(&self) -> bool {
+        self.indexing.is_some()
+    }
+
+    /// How many files were indexed so far and how many there are
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        self.indexing.as_ref().map(|i| (i.done, i.total))
+    }
+
+    pub fn add_folder(&mut self, folder: PathBuf, extra: Vec<PathBuf>) {
+        if !self.folders.contains(&folder) {
+            self.folders.push(folder);
+            self.index(extra);
+        }
+    }
+
+    pub fn remove_folder(&mut self, idx: usize, extra: Vec<PathBuf>) {
+        self.folders.remove(idx);
+        self.index(extra);
+    }
+
+    /// Looks through the folders again on a background thread, along with
+    /// the `extra` files outside of them. Files that didn't change since
+    /// they were last indexed aren't read again.
+    pub fn index(&mut self, extra: Vec<PathBuf>) {
+        if self.is_indexing() {
+            return;
+        }
+
+        let folders = self.folders.clone();
+        let mut known: HashMap<PathBuf, Entry> = self
+            .entries
+            .drain(..)
+            .map(|entry| (entry.path.clone(), entry))
+            .collect();
+        self.selected.clear();
+        let (tx, rx) = mpsc::channel();
+
+        let _ = std::thread::Builder::new()
+            .name("library indexer".to_string())
+            .spawn(move || {
+                let mut files = vec![];
+                for folder in &folders {
+                    find_models(folder, &mut files);
+                }
+                for file in extra {
+                    if file.is_file() && !files.contains(&file) {
+                        files.push(file);
+                    }
+                }
+                files.sort();
+                if tx.send(Progress::Total(files.len())).is_err() {
+                    return;
+                }
+
+                for file in files {
+                    let entry = match known.remove(&file) {
+                        Some(entry) if entry.stamp.is_some() && entry.stamp == Stamp::of(&file) => {
+                            entry
+                        }
+                        _ => Entry::index(file),
+                    };
+                    if tx.send(Progress::Entry(entry)).is_err() {
+                        return;
+                    }
+                }
+            });
+
+        self.indexing = Some(Indexing {
+            receiver: rx,
+            done: 0,
+            total: 0,
+        });
+    }
+
+    /// Indices of the entries that pass the filters, the name, notes and
+    /// tags are looked through with `matches`
+    pub fn filtered(&self, matches: impl Fn(&Path, &str) -> bool) -> Vec<usize> {
+        (0..self.entries.len())
+            .filter(|i| {
+                let entry = &self.entries[*i];
+                self.format.as_ref().is_none_or(|f| *f == entry.format)
+                    && (self.min_size <= 0.0 || entry.size_mb() >= self.min_size)
+                    && (self.max_size <= 0.0 || entry.size_mb() <= self.max_size)
+                    && (!self.only_duplicates || self.duplicates.contains_key(&entry.path))
+                    && matches(&entry.path, &self.query)
+            })
+            .collect()
+    }
+
+    /// Groups the entries with the same or nearly the same geometry. They're
+    /// sorted by their triangle count so only the ones close to each other
+    /// have to be compared.
+    fn find_duplicates(&mut self) {
+        self.duplicates.clear();
+        let mut fingerprints: Vec<(&Path, &Fingerprint)> = self
+            .entries
+            .iter()
+            .filter_map(|e| Some((e.path.as_path(), e.fingerprint.as_ref()?)))
+            .collect();
+        fingerprints.sort_by_key(|(_, f)| f.triangles);
+
+        for (i, (path, fingerprint)) in fingerprints.iter().enumerate() {
+            let max_triangles = fingerprint.triangles as f32 * (1.0 + NEAR_TOLERANCE);
+            for (other_path, other) in fingerprints[i + 1..]
+                .iter()
+                .take_while(|(_, f)| f.triangles as f32 <= max_triangles)
+            {
+                let identical = fingerprint.is_identical(other);
+                if !identical && !fingerprint.is_near(other) {
+                    continue;
+                }
+                for (a, b) in [(path, other_path), (other_path, path)] {
+                    self.duplicates
+                        .entry(a.to_path_buf())
+                        .or_default()
+                        .push(Duplicate {
+                            path: b.to_path_buf(),
+                            identical,
+                        });
+                }
+            }
+        }
+    }
+
+    /// The formats the entries are in, for filtering by them
+    pub fn formats(&self) -> Vec<String> {
+        let mut formats: Vec<String> = self.entries.iter().map(|e| e.format.clone()).collect();
+        formats.sort();
+        formats.dedup();
+        formats
+    }
+
+    /// GL texture of an entry's preview, 0 if it has none. Loaded the first
+    /// time it's asked for.
+    pub fn thumbnail(&mut self, entry_idx: usize) -> u32 {
+        let entry = &self.entries[entry_idx];
+        let Some(path) = &entry.thumbnail else {
+            return 0;
+        };
+        if let Some(id) = self.thumbnails.get(path) {
+            return *id;
+        }
+
+        let sampling = texture::Sampling {
+            filter: TextureFilter::Bilinear,
+            anisotropy: 1.0,
+        };
+        let uploaded = image::open(path)
+            .map_err(|e| e.to_string())
+            .and_then(|image| utils::upload_texture(&image, sampling).map_err(|e| e.to_string()));
+        let id = match uploaded {
+            Ok(id) => id,
+            Err(e) => {
+                error!("Failed to load the preview {:?}: {}", path, e);
+                0
+            }
+        };
+        self.thumbnails.insert(path.clone(), id);
+        id
+    }
+
+    /// Deletes the thumbnails' textures, they're made again once they're
+    /// shown
+    pub fn release_thumbnails(&mut self) {
+        let ids: Vec<u32> = self.thumbnails.drain().map(|(_, id)| id).collect();
+        gl_state::delete_textures(&ids);
+    }
+}
+
+/// Adds the supported model files in `dir` and its subfolders to `files`
+fn find_models(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        error!("Failed to read the library folder {:?}", dir);
+        return;
+    };
+
+    for path in read_dir.filter_map(|entry| entry.ok().map(|e| e.path())) {
+        if path.is_dir() {
+            find_models(&path, files);
+        } else if registry::is_supported(&path) {
+            files.push(path);
+        }
+    }
+}
+
+/// Picks up the files the indexer is done with
+pub fn poll(state: &mut State) {
+    let library = &mut state.library;
+    let Some(indexing) = &mut library.indexing else {
+        return;
+    };
+
+    loop {
+        match indexing.receiver.try_recv() {
+            Ok(Progress::Total(total)) => indexing.total = total,
+            Ok(Progress::Entry(entry)) => {
+                indexing.done += 1;
+                // kept right away so what's indexed isn't lost if the app is closed
+                if let Some(Err(e)) = library.db.as_ref().map(|db| db.write_entry(&entry)) {
+                    error!(
+                        "Failed to save {:?} to the library index: {}",
+                        entry.path, e
+                    );
+                }
+                library.entries.push(entry);
+            }
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => break,
+        }
+    }
+
+    library.indexing = None;
+    library.store();
+    library.find_duplicates();
+    info!("Indexed {} models for the library", library.entries.len());
+    let text = format!("{} {}", library.entries.len(), tr("library.models"));
+    state.notify(Notification::info(tr("notification.library_indexed")).with_text(text));
+}
@@ -1,9 +1,15 @@
 use std::{
-    io::Write,
+    collections::VecDeque,
     sync::{Arc, RwLock},
 };
 
-#[derive(Copy, Clone)]
+use log::{LevelFilter, Metadata, Record};
+use time::{macros::format_description, OffsetDateTime, UtcOffset};
+
+pub const LOG_FILE_NAME: &str = "3dobs.log";
+pub const DEFAULT_MAX_HISTORY: usize = 1000;
+
+#[derive(Copy, Clone, PartialEq)]
 pub enum LogLevel {
     Error,
     Warn,
@@ -11,6 +17,17 @@ pub enum LogLevel {
     Debug,
 }
 
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+        }
+    }
+}
+
 impl From<LogLevel> for mint::Vector4<f32> {
     fn from(level: LogLevel) -> Self {
         match level {
@@ -34,23 +51,56 @@ impl From<log::LevelFilter> for LogLevel {
     }
 }
 
-pub struct LogMessage {
+impl From<log::Level> for LogLevel {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => LogLevel::Error,
+            log::Level::Warn => LogLevel::Warn,
+            log::Level::Info => LogLevel::Info,
+            log::Level::Debug | log::Level::Trace => LogLevel::Debug,
+        }
+    }
+}
+
+pub struct LogRecord {
     pub level: LogLevel,
+    pub target: String,
+    pub timestamp: OffsetDateTime,
     pub message: String,
 }
 
-impl LogMessage {
-    pub fn new(level: LogLevel, message: &str) -> Self {
-        Self {
-            level,
-            message: message.to_string(),
-        }
+impl LogRecord {
+    pub fn formatted_time(&self) -> String {
+        self.timestamp
+            .format(format_description!("[hour]:[minute]:[second]"))
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}",
+            self.formatted_time(),
+            self.level.as_str(),
+            self.message
+        )
     }
 }
 
-#[derive(Default)]
 pub struct Log {
-    pub history: Vec<LogMessage>,
+    pub history: VecDeque<LogRecord>,
+    max_history: usize,
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Self {
+            history: VecDeque::new(),
+            max_history: DEFAULT_MAX_HISTORY,
+        }
+    }
 }
 
 impl Log {
@@ -58,49 +108,86 @@ impl Log {
         self.history.clear();
     }
 
-    pub fn log(&mut self, message: &str, level: LogLevel) {
-        self.history.push(LogMessage::new(level, message));
+    pub fn push(&mut self, record: LogRecord) {
+        self.history.push_back(record);
+        self.evict();
+    }
+
+    pub fn set_max_history(&mut self, max_history: usize) {
+        self.max_history = max_history.max(1);
+        self.evict();
+    }
+
+    // drop the oldest messages once we're over the limit
+    fn evict(&mut self) {
+        while self.history.len() > self.max_history {
+            self.history.pop_front();
+        }
     }
 }
 
+/// Logger backing the in-program console. Keeps the records structured
+/// so the console can filter and format them however it wants.
 #[derive(Clone)]
-pub struct WritableLog {
+pub struct ConsoleLogger {
     pub arc: Arc<RwLock<Log>>,
-    buf: String,
+    level: LevelFilter,
+    // the local offset can only be reliably queried while the program is
+    // still single threaded, so we grab it once on creation
+    utc_offset: UtcOffset,
 }
 
-impl Default for WritableLog {
-    fn default() -> Self {
+impl ConsoleLogger {
+    pub fn new(level: LevelFilter) -> Self {
         Self {
             arc: Arc::new(RwLock::new(Log::default())),
-            buf: String::with_capacity(1024),
+            level,
+            utc_offset: UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
         }
     }
 }
 
-impl Write for WritableLog {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.buf.push_str(std::str::from_utf8(buf).unwrap());
-        if buf.contains(&b'\n') {
-            self.flush()?;
-        }
-        Ok(buf.len())
+impl Default for ConsoleLogger {
+    fn default() -> Self {
+        Self::new(LevelFilter::Trace)
+    }
+}
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
-        // match the log level found at index 0 till space to LogLevel
-        let level = match &self.buf[0..self.buf.find(' ').unwrap()] {
-            "[ERROR]" => LogLevel::Error,
-            "[WARN]" => LogLevel::Warn,
-            "[INFO]" => LogLevel::Info,
-            "[DEBUG]" => LogLevel::Debug,
-            _ => LogLevel::Info,
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let record = LogRecord {
+            level: record.level().into(),
+            target: record.target().to_string(),
+            timestamp: OffsetDateTime::now_utc().to_offset(self.utc_offset),
+            message: record.args().to_string(),
         };
 
-        let mut logger = self.arc.write().unwrap();
-        logger.log(&self.buf, level);
-        self.buf.clear();
+        if let Ok(mut log) = self.arc.write() {
+            log.push(record);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl simplelog::SharedLogger for ConsoleLogger {
+    fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    fn config(&self) -> Option<&simplelog::Config> {
+        None
+    }
 
-        Ok(())
+    fn as_log(self: Box<Self>) -> Box<dyn log::Log> {
+        Box::new(*self)
     }
 }
@@ -7,10 +7,39 @@ use glad_gl::gl;
 use glfw::{Action, Context, Key, Modifiers};
 use simplelog::*;
 
-use threedobs::{ipc, shader, ui::ui, utils};
+use threedobs::{
+    camera, camera_path, config, crash, debug_draw, deep_link, detached, error, gl_state, gpu,
+    importer, inspect, ipc, library, measure, notes, online, pacing, recording,
+    render_queue::RenderQueue, report, script, shader, shell, slicing, thumbnail, ui::ui, utils,
+    watch,
+};
+
+// hybrid graphics drivers on Windows run programs exporting these on the
+// discrete GPU, they're read before anything in main runs
+#[cfg(windows)]
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static NvOptimusEnablement: u32 = 1;
+#[cfg(windows)]
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static AmdPowerXpressRequestHighPerformance: i32 = 1;
 
 fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
-    let logger = threedobs::logger::WritableLog::default();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // --thumbnail <in> <out> <size> renders a preview for file manager thumbnailers
+    // and exits, before anything else like the log file gets set up
+    if let Some(i) = args.iter().position(|arg| arg == "--thumbnail") {
+        return thumbnail::run(&args[i + 1..]).map_err(Into::into);
+    }
+
+    // --config-dir <dir> keeps the settings somewhere else, like next to a portable
+    // install, and --profile <name> picks a named set of them
+    let config_dir = take_arg_value(&mut args, "--config-dir").map(PathBuf::from);
+    let profile = take_arg_value(&mut args, "--profile");
+    config::init(config_dir, profile);
+    let settings: ui::Settings = config::load()?;
+    threedobs::ui::i18n::set_language(settings.language);
 
     let log_conf = ConfigBuilder::default()
         .set_target_level(LevelFilter::Error)
@@ -20,15 +49,6 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
         .set_level_color(Level::Warn, Some(Color::Rgb(255, 163, 0)))
         .set_level_color(Level::Info, Some(Color::Rgb(128, 128, 128)))
         .build();
-    let in_program_log_conf = ConfigBuilder::default()
-        .set_target_level(LevelFilter::Off)
-        .set_time_level(LevelFilter::Off)
-        .set_thread_level(LevelFilter::Off)
-        .set_level_color(Level::Error, Some(Color::Red))
-        .set_level_color(Level::Debug, Some(Color::Rgb(128, 128, 255)))
-        .set_level_color(Level::Warn, Some(Color::Rgb(255, 255, 0)))
-        .set_level_color(Level::Info, Some(Color::Rgb(128, 128, 128)))
-        .build();
 
     let log_level = if cfg!(debug_assertions) {
         LevelFilter::Trace
@@ -36,39 +56,103 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
         LevelFilter::Info
     };
 
-    CombinedLogger::init(vec![
+    let logger = threedobs::logger::ConsoleLogger::new(log_level);
+    logger
+        .arc
+        .write()
+        .unwrap()
+        .set_max_history(settings.console_max_history);
+
+    let mut loggers: Vec<Box<dyn SharedLogger>> = vec![
         TermLogger::new(
             log_level,
             log_conf.clone(),
             TerminalMode::Mixed,
             ColorChoice::Auto,
         ),
-        WriteLogger::new(log_level, log_conf, File::create("3dobs.log")?),
-        WriteLogger::new(log_level, in_program_log_conf, logger.clone()),
-    ])
-    .unwrap();
+        Box::new(logger.clone()),
+    ];
+    if settings.log_to_file {
+        loggers.push(WriteLogger::new(
+            log_level,
+            log_conf,
+            File::create(threedobs::logger::LOG_FILE_NAME)?,
+        ));
+    }
+    CombinedLogger::init(loggers).unwrap();
+    for notice in config::take_notices() {
+        log::warn!("{}", notice);
+    }
 
-    let settings: ui::Settings = confy::load("3dobs", "settings")?;
+    crash::install_panic_hook();
 
-    let args: Vec<String> = env::args().collect();
-    let args_paths: Vec<PathBuf> = args
+    // --register makes 3dobs the handler of model files and exits
+    if args.iter().any(|arg| arg == "--register") {
+        return shell::register_file_types().map_err(Into::into);
+    }
+    // --report <folder> <output> writes the stats of the folder's models to
+    // an HTML or CSV file and exits
+    if let Some(i) = args.iter().position(|arg| arg == "--report") {
+        return report::run(&args[i + 1..]).map_err(Into::into);
+    }
+    // watch <model> --output <image> renders the model again whenever it changes,
+    // for keeping a preview up to date while working on it
+    if args.first().is_some_and(|arg| arg == "watch") {
+        return watch::run(&args[1..]).map_err(Into::into);
+    }
+    // --script <file> runs the commands in the file once the window is up
+    let script_path = take_arg_value(&mut args, "--script").map(PathBuf::from);
+    // --no-textures imports the models without their textures for a quick look
+    let skip_textures = take_flag(&mut args, "--no-textures");
+    // --gl-version <x.y>, --gl-profile <core|compat>, --no-vsync, --software and
+    // --discrete-gpu pick how the GL context is made, over the settings and the
+    // THREEDOBS_* environment variables
+    let backend = gpu::Backend::new(
+        &settings,
+        gpu::Flags {
+            gl_version: take_arg_value(&mut args, "--gl-version"),
+            gl_profile: take_arg_value(&mut args, "--gl-profile"),
+            no_vsync: take_flag(&mut args, "--no-vsync"),
+            software: take_flag(&mut args, "--software"),
+            discrete_gpu: take_flag(&mut args, "--discrete-gpu"),
+        },
+    );
+    let (links, args): (Vec<String>, Vec<String>) = args
+        .into_iter()
+        .partition(|arg| deep_link::is_deep_link(arg));
+    let (mut urls, args): (Vec<String>, Vec<String>) = args
+        .into_iter()
+        .partition(|arg| importer::download::is_url(arg));
+    let mut args_paths: Vec<PathBuf> = args
         .iter()
-        .skip(1)
         .map(|arg| std::fs::canonicalize(PathBuf::from(arg)).unwrap())
         .collect();
+    // 3dobs:// links come from web pages and other tools, so a bad one is only logged
+    for link in &links {
+        match deep_link::parse(link) {
+            Ok(deep_link::Target::Path(path)) => match std::fs::canonicalize(&path) {
+                Ok(path) => args_paths.push(path),
+                Err(e) => log::error!("Failed to open {:?} from {}: {}", path, link, e),
+            },
+            Ok(deep_link::Target::Url(url)) => urls.push(url),
+            Err(e) => log::error!("Ignoring the link {}: {}", link, e),
+        }
+    }
 
     let lock_file_name = "3dobs.lock";
     let lock_file_path = std::env::temp_dir().join(lock_file_name);
-    let lock_file = File::create(&lock_file_path)?;
-    let ipc_rx = ipc::init(&lock_file, args_paths.clone(), settings.one_instance);
+    let ipc_message = ipc::Message {
+        paths: args_paths.clone(),
+        urls: urls.clone(),
+    };
+    // dropped at the end of main, which removes the socket
+    let ipc_server = ipc::init(&lock_file_path, ipc_message, settings.one_instance);
 
+    backend.export_driver_hints();
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS)?;
+    backend.window_hints(&mut glfw);
 
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-
-    let (mut window, events) = glfw
+    let (mut window, mut events) = glfw
         .create_window(1200, 800, "3dobs", glfw::WindowMode::Windowed)
         .expect("Failed to create GLFW window");
 
@@ -79,40 +163,19 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
     let mut state = ui::State {
         settings,
         logger,
+        skip_textures,
+        notes: notes::Notes::load(),
+        library: library::Library::load(),
         ..Default::default()
     };
 
-    glfw.set_swap_interval(glfw::SwapInterval::Sync(1));
-
-    let (mut imgui, glfw_platform, renderer) = ui::init_imgui(&mut window);
-
-    let mesh_shader = shader::Shader::new(
-        &mut shader::ShaderSource {
-            name: "vertex.glsl".to_string(),
-            source: include_str!("../shaders/vertex.glsl").to_string(),
-        },
-        &mut shader::ShaderSource {
-            name: "frag.glsl".to_string(),
-            source: include_str!("../shaders/frag.glsl").to_string(),
-        },
-    )?;
-    let grid_shader = shader::Shader::new(
-        &mut shader::ShaderSource {
-            name: "grid_v.glsl".to_string(),
-            source: include_str!("../shaders/grid_v.glsl").to_string(),
-        },
-        &mut shader::ShaderSource {
-            name: "grid_f.glsl".to_string(),
-            source: include_str!("../shaders/grid_f.glsl").to_string(),
-        },
-    )?;
+    let (mut imgui, mut glfw_platform, mut renderer) = ui::init_imgui(&mut window);
+    crash::record_gpu_info();
+    backend.check_renderer();
+    state.supports_reversed_z = utils::supports_clip_control();
+    state.crash_report = crash::take_previous_crash_report();
 
-    let points_lights: [glm::Vec3; 4] = [
-        glm::vec3(0.7, 0.2, 2.0),
-        glm::vec3(2.3, -3.3, -4.0),
-        glm::vec3(-4.0, 2.0, -12.0),
-        glm::vec3(0.0, 0.0, -3.0),
-    ];
+    let (mut mesh_shader, mut grid_shader, mut line_shader) = create_shaders()?;
 
     let mut delta_time;
     let mut last_frame: f32 = 0.0;
@@ -124,57 +187,33 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
     let mut first_mouse: bool = true;
 
     unsafe {
-        grid_shader.use_shader();
-        grid_shader.set_float("near", 0.01);
-        grid_shader.set_float("far", 200.0);
-
         mesh_shader.use_shader();
 
-        // set light uniforms
-        for (i, point_light) in points_lights.iter().enumerate() {
-            mesh_shader.set_3fv(&format!("pointLights[{}].position", i), *point_light);
+        utils::set_light_uniforms(&mesh_shader);
 
-            mesh_shader.set_float(&format!("pointLights[{}].constant", i), 1.0);
-            mesh_shader.set_float(&format!("pointLights[{}].linear", i), 0.09);
-            mesh_shader.set_float(&format!("pointLights[{}].quadratic", i), 0.032);
+        let mut scene_fb = create_scene_framebuffer();
 
-            mesh_shader.set_3fv(
-                &format!("pointLights[{}].ambient", i),
-                glm::vec3(0.1, 0.1, 0.1),
-            );
-            mesh_shader.set_3fv(
-                &format!("pointLights[{}].diffuse", i),
-                glm::vec3(0.7, 0.7, 0.7),
-            );
-            mesh_shader.set_3fv(
-                &format!("pointLights[{}].specular", i),
-                glm::vec3(1.0, 1.0, 1.0),
-            );
-        }
-        mesh_shader.set_float("spotLight.cutOff", glm::cos(glm::radians(12.5)));
-        mesh_shader.set_float("spotLight.outerCutOff", glm::cos(glm::radians(15.0)));
-        mesh_shader.set_3fv("spotLight.ambient", glm::vec3(0.2, 0.2, 0.2));
-        mesh_shader.set_3fv("spotLight.diffuse", glm::vec3(0.5, 0.5, 0.5));
-        mesh_shader.set_3fv("spotLight.specular", glm::vec3(1.0, 1.0, 1.0));
-        mesh_shader.set_float("spotLight.constant", 1.0);
-        mesh_shader.set_float("spotLight.linear", 0.09);
-        mesh_shader.set_float("spotLight.quadratic", 0.032);
-
-        mesh_shader.set_3fv("dirLight.direction", glm::vec3(-0.2, -1.0, -0.3));
-        mesh_shader.set_3fv("dirLight.ambient", glm::vec3(0.3, 0.3, 0.3));
-        mesh_shader.set_3fv("dirLight.diffuse", glm::vec3(1.0, 1.0, 1.0));
-        mesh_shader.set_3fv("dirLight.specular", glm::vec3(1.0, 1.0, 1.0));
-
-        let scene_fb = create_scene_framebuffer();
-
-        if args.len() > 1 {
+        if !args_paths.is_empty() {
             utils::import_models_from_paths(&args_paths, &mut state);
         }
+        for url in &urls {
+            utils::import_model_from_url(url, &mut state);
+        }
+        if let Some(path) = &script_path {
+            script::queue_file(path, &mut state);
+        }
 
         let mut time_since_last_frame_acc = 0.0;
+        let mut pacer = pacing::FramePacer::new();
+        if backend.no_vsync {
+            pacer.disable_vsync();
+        }
+        let mut detached_windows = detached::DetachedWindows::default();
+        let mut reset_watch = gpu::ResetWatch::current();
 
         // main loop
         while !window.should_close() {
+            pacer.begin_frame(&mut glfw, &state.settings);
             let current_frame = glfw.get_time() as f32;
             delta_time = current_frame - last_frame;
             last_frame = current_frame;
@@ -192,24 +231,38 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
                 time_since_last_frame_acc = 0.0;
             }
 
+            script::run_queued(&mut state);
+            camera_path::update(&mut state, delta_time);
+            recording::update(&mut state);
+            slicing::update(&mut state, delta_time);
+            utils::update_clip_planes(&mut state);
+            let reversed_z = state.reversed_z();
+
             // camera matrices
             let view_mat = glm::ext::look_at(
                 state.camera.position,
                 state.camera.position + state.camera.front,
                 state.camera.up,
             );
-            let projection_mat = glm::ext::perspective(
-                glm::radians(state.camera.fov),
-                state.viewport_size[0] / state.viewport_size[1],
-                0.01,
-                200.0,
-            );
-
-            if let Some(rx) = &ipc_rx {
-                match rx.try_recv() {
-                    Ok(paths) => {
-                        window.focus();
-                        utils::import_models_from_paths(&paths, &mut state);
+            let projection_mat = state
+                .camera
+                .projection(state.viewport_size[0] / state.viewport_size[1], reversed_z);
+
+            state.view_projection = projection_mat * view_mat;
+
+            utils::poll_import_jobs(&mut state);
+            utils::update_texture_residency(&mut state);
+            utils::update_model_residency(&mut state);
+            online::poll(&mut state);
+            library::poll(&mut state);
+
+            if let Some(server) = &ipc_server {
+                match server.rx.try_recv() {
+                    Ok(incoming) => {
+                        pacer.input_received(&glfw);
+                        ipc::raise(&mut window);
+                        let reply = utils::import_forwarded(&incoming.message, &mut state);
+                        incoming.reply(&reply);
                     }
                     Err(e) => match e {
                         std::sync::mpsc::TryRecvError::Empty => {}
@@ -220,7 +273,22 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
                 }
             }
 
+            state.tool_mode = if state.picking_pivot {
+                ui::ToolMode::PickPivot
+            } else if state.annotating {
+                ui::ToolMode::Annotate
+            } else if state.measuring.is_some() {
+                ui::ToolMode::Measure
+            } else if state.inspecting {
+                ui::ToolMode::Inspect
+            } else if window.get_key(glfw::Key::LeftShift) == Action::Press {
+                ui::ToolMode::Pan
+            } else {
+                ui::ToolMode::Rotate
+            };
+
             for (_, event) in glfw::flush_messages(&events) {
+                pacer.input_received(&glfw);
                 // order of handling events is important here
                 // we need to handle window events first to have an updated
                 // is_cursor_captured
@@ -244,20 +312,22 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
                     if state.can_capture_cursor
                         && window.get_mouse_button(glfw::MouseButtonLeft) == Action::Press
                     {
+                        if xoffset != 0.0 || yoffset != 0.0 {
+                            state.viewport_click = None;
+                        }
                         if window.get_key(glfw::Key::LeftShift) == Action::Press {
                             state.camera.move_camera(-xoffset, -yoffset);
-                        } else if let Some(active_model) = state.active_model {
+                        } else {
                             let x_rotation =
                                 xoffset * state.camera.sensitivity * state.rotation_speed;
                             let y_rotation =
                                 yoffset * state.camera.sensitivity * state.rotation_speed;
-                            let model = state
-                                .objects
-                                .iter_mut()
-                                .find(|m| m.id == active_model)
-                                .unwrap();
                             // let x_rotation = glm::quat_angle_axis(xoffset * state.camera.sensitivity, &state.camera.up);
-                            model.rotate(x_rotation, y_rotation);
+                            for model in state.objects.iter_mut() {
+                                if state.selection.contains(&model.id) {
+                                    model.rotate(x_rotation, y_rotation);
+                                }
+                            }
                         }
                     }
                 }
@@ -266,11 +336,15 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
             //
             // draw scene to framebuffer
             //
-            let (scene_texture, rbo) = create_scene_texture_and_renderbuffer(&window, scene_fb);
+            let (scene_texture, rbo) =
+                create_scene_texture_and_renderbuffer(&window, scene_fb, reversed_z);
 
             gl::BindFramebuffer(gl::FRAMEBUFFER, scene_fb);
-            gl::Enable(gl::DEPTH_TEST);
-            gl::Enable(gl::BLEND);
+            if state.supports_reversed_z {
+                utils::set_reversed_z(reversed_z);
+            }
+            gl_state::enable(gl::DEPTH_TEST);
+            gl_state::enable(gl::BLEND);
             gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
             gl::ClearColor(0.2, 0.2, 0.2, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
@@ -283,27 +357,88 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
             mesh_shader.set_3fv("spotLight.position", state.camera.position);
             mesh_shader.set_3fv("spotLight.direction", state.camera.front);
             mesh_shader.set_3fv("viewPos", state.camera.position);
+            mesh_shader.set_int("displayMode", state.display_mode as i32);
+            mesh_shader.set_float("near", state.camera.near);
+            mesh_shader.set_bool("reversedZ", reversed_z);
+            mesh_shader.set_float("far", state.camera.far);
+            mesh_shader.set_bool("sliceEnabled", state.slicing.plane.is_some());
+            mesh_shader.set_float("sliceHeight", state.slicing.plane.unwrap_or_default());
+            mesh_shader.set_3fv("sectionColor", state.slicing.section_color);
 
             // BUG: for objects with semi-transparent materials/textures, the order of drawing is important.
             // We must draw all opaque objects/meshes first, then perform a depth/distance sort
             // on all semi-transparent objects/meshes and draw them in order from farthest to closest.
             // Alternatively. We could implement a dual-depth peeling algorithm
             // which seems to be a good one and done solution and is order independent.
+            debug_draw::begin_surface_pass(reversed_z);
+            gl_state::polygon_mode(if state.wireframe { gl::LINE } else { gl::FILL });
+            let mut queue = RenderQueue::culled(state.view_projection);
             for obj in &state.objects {
-                if state.wireframe {
-                    gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
-                } else {
-                    gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+                if state.is_selected(obj.id) && obj.visible {
+                    queue.push(obj, state.highlighted_material(obj.id));
                 }
-                if Some(obj.id) == state.active_model {
-                    obj.draw(&mesh_shader, state.draw_aabb, state.show_textures);
+            }
+            state.render_stats = queue.draw(&mesh_shader, state.show_textures);
+            gl_state::polygon_mode(gl::FILL);
+            if !state.wireframe {
+                slicing::draw_cap(
+                    &mut state,
+                    &mesh_shader,
+                    &line_shader,
+                    &view_mat,
+                    &projection_mat,
+                );
+            }
+
+            // ghosts go on top without hiding anything behind them or getting picked
+            if state.show_ghosts {
+                gl::DepthMask(gl::FALSE);
+                for obj in state.objects.iter_mut() {
+                    if state.selection.contains(&obj.id) && obj.visible {
+                        obj.draw_ghost(&mesh_shader, state.show_textures);
+                    }
                 }
+                gl::DepthMask(gl::TRUE);
             }
-            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            debug_draw::end_surface_pass();
+
+            // pick before drawing the grid so only the model's depth is read back
+            state.cursor_model_pos = match (state.viewport_cursor, state.active_model()) {
+                (Some(cursor), Some(id)) => {
+                    pick_position(&window, cursor, &view_mat, &projection_mat, reversed_z).and_then(
+                        |pos| {
+                            state
+                                .objects
+                                .iter()
+                                .find(|m| m.id == id)
+                                .map(|m| m.world_to_local(pos))
+                        },
+                    )
+                }
+                _ => None,
+            };
+
+            inspect::update(&mut state);
+            inspect::update_hover(&mut state);
+            measure::update(&mut state);
+            utils::queue_debug_geometry(&mut state);
+            state
+                .debug_draw
+                .flush(&line_shader, &view_mat, &projection_mat);
 
             // draw grid
             if state.draw_grid {
-                draw_grid(&grid_shader, &view_mat, &projection_mat);
+                draw_grid(
+                    &grid_shader,
+                    &view_mat,
+                    &projection_mat,
+                    &state.camera,
+                    reversed_z,
+                );
+            }
+            // the UI expects the default depth range
+            if reversed_z {
+                utils::set_reversed_z(false);
             }
 
             //
@@ -312,8 +447,8 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
             gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
             gl::ClearColor(0.1, 0.1, 0.1, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
-            gl::Disable(gl::DEPTH_TEST);
-            gl::Disable(gl::BLEND);
+            gl_state::disable(gl::DEPTH_TEST);
+            gl_state::disable(gl::BLEND);
             ui::draw_ui(
                 &mut imgui,
                 &renderer,
@@ -327,25 +462,192 @@ fn main() -> anyhow::Result<(), Box<dyn std::error::Error>> {
             glfw.poll_events();
             window.swap_buffers();
 
-            gl::DeleteTextures(1, &scene_texture);
+            gl_state::delete_textures(&[scene_texture]);
             gl::DeleteRenderbuffers(1, &rbo);
+
+            // everything in a lost context is gone, so it's replaced by a new
+            // window and what was on the GPU is made again from the CPU side
+            // copies or the files
+            if reset_watch.lost() {
+                state.release_gpu_resources();
+                detached_windows = detached::DetachedWindows::default();
+
+                (window, events) = recreate_window(&mut glfw, &window);
+                window.make_current();
+                let new_renderer = ui::reattach_imgui(&mut imgui, &mut glfw_platform, &mut window);
+                // the old renderer's objects went with the old context
+                std::mem::forget(std::mem::replace(&mut renderer, new_renderer));
+                gl_state::invalidate();
+
+                (mesh_shader, grid_shader, line_shader) = create_shaders()?;
+                mesh_shader.use_shader();
+                utils::set_light_uniforms(&mesh_shader);
+                scene_fb = create_scene_framebuffer();
+                state.supports_reversed_z = utils::supports_clip_control();
+                crash::record_gpu_info();
+                backend.check_renderer();
+                pacer.forget_swap_interval();
+
+                state.restore_gpu_resources();
+                reset_watch = gpu::ResetWatch::current();
+                log::info!("Made a new OpenGL context after the reset");
+            }
+
+            if detached_windows.update(&mut window, &mut state, &mesh_shader) {
+                pacer.input_received(&glfw);
+            }
+
+            pacer.end_frame(&mut glfw, &state.settings, state.is_animating());
         }
 
+        // free the models' GPU resources while the context is still alive
+        state.objects.clear();
         gl::DeleteFramebuffers(1, &scene_fb);
     }
 
     Ok(())
 }
 
-fn draw_grid(shader: &threedobs::shader::Shader, view_mat: &glm::Mat4, projection_mat: &glm::Mat4) {
+fn create_shaders() -> error::Result<(shader::Shader, shader::Shader, shader::Shader)> {
+    let mesh_shader = shader::Shader::new(
+        &mut shader::ShaderSource {
+            name: "vertex.glsl".to_string(),
+            source: include_str!("../shaders/vertex.glsl").to_string(),
+        },
+        &mut shader::ShaderSource {
+            name: "frag.glsl".to_string(),
+            source: include_str!("../shaders/frag.glsl").to_string(),
+        },
+    )?;
+    let grid_shader = shader::Shader::new(
+        &mut shader::ShaderSource {
+            name: "grid_v.glsl".to_string(),
+            source: include_str!("../shaders/grid_v.glsl").to_string(),
+        },
+        &mut shader::ShaderSource {
+            name: "grid_f.glsl".to_string(),
+            source: include_str!("../shaders/grid_f.glsl").to_string(),
+        },
+    )?;
+    let line_shader = shader::Shader::new(
+        &mut shader::ShaderSource {
+            name: "line_v.glsl".to_string(),
+            source: include_str!("../shaders/line_v.glsl").to_string(),
+        },
+        &mut shader::ShaderSource {
+            name: "line_f.glsl".to_string(),
+            source: include_str!("../shaders/line_f.glsl").to_string(),
+        },
+    )?;
+
+    Ok((mesh_shader, grid_shader, line_shader))
+}
+
+/// Opens a window like `old` with a new context, from the hints the first
+/// one was made with. `old` is closed when it's dropped.
+fn recreate_window(
+    glfw: &mut glfw::Glfw,
+    old: &glfw::Window,
+) -> (
+    glfw::Window,
+    std::sync::mpsc::Receiver<(f64, glfw::WindowEvent)>,
+) {
+    let (w, h) = old.get_size();
+    let (mut window, events) = glfw
+        .create_window(w as u32, h as u32, "3dobs", glfw::WindowMode::Windowed)
+        .expect("Failed to create GLFW window");
+    let (x, y) = old.get_pos();
+    window.set_pos(x, y);
+    window.set_all_polling(true);
+    window.set_cursor_mode(old.get_cursor_mode());
+
+    (window, events)
+}
+
+/// Removes `flag` and the value after it from the arguments, returning the value
+fn take_arg_value(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let i = args.iter().position(|arg| arg == flag)?;
+    args.remove(i);
+    (i < args.len()).then(|| args.remove(i))
+}
+
+/// Removes `flag` from the arguments, returns whether it was there
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != len
+}
+
+fn draw_grid(
+    shader: &threedobs::shader::Shader,
+    view_mat: &glm::Mat4,
+    projection_mat: &glm::Mat4,
+    camera: &camera::Camera,
+    reversed_z: bool,
+) {
     shader.use_shader();
     shader.set_mat4fv("view", view_mat);
     shader.set_mat4fv("projection", projection_mat);
+    shader.set_float("near", camera.near);
+    shader.set_float("far", camera.far);
+    shader.set_bool("reversedZ", reversed_z);
     unsafe {
         gl::DrawArrays(gl::TRIANGLES, 0, 6);
     }
 }
 
+/// Reads back the depth under the cursor from the scene framebuffer and
+/// unprojects it into world space. Expects the scene framebuffer to be bound.
+fn pick_position(
+    window: &glfw::Window,
+    cursor: [f32; 2],
+    view_mat: &glm::Mat4,
+    projection_mat: &glm::Mat4,
+    reversed_z: bool,
+) -> Option<glm::Vec3> {
+    let (w, h) = window.get_size();
+    let x = (cursor[0] * w as f32) as i32;
+    // the viewport image is flipped vertically
+    let y = ((1.0 - cursor[1]) * h as f32) as i32;
+    if x < 0 || y < 0 || x >= w || y >= h {
+        return None;
+    }
+
+    let mut depth: f32 = 1.0;
+    unsafe {
+        gl::ReadPixels(
+            x,
+            y,
+            1,
+            1,
+            gl::DEPTH_COMPONENT,
+            gl::FLOAT,
+            &mut depth as *mut f32 as *mut std::ffi::c_void,
+        );
+    }
+
+    // nothing but background under the cursor
+    if (reversed_z && depth <= 0.0) || (!reversed_z && depth >= 1.0) {
+        return None;
+    }
+
+    // reversed-Z uses the 0 to 1 depth range as is
+    let ndc_depth = if reversed_z { depth } else { depth * 2.0 - 1.0 };
+    let ndc = glm::vec4(
+        cursor[0] * 2.0 - 1.0,
+        (1.0 - cursor[1]) * 2.0 - 1.0,
+        ndc_depth,
+        1.0,
+    );
+    let world = glm::inverse(&(*projection_mat * *view_mat)) * ndc;
+
+    Some(glm::vec3(
+        world.x / world.w,
+        world.y / world.w,
+        world.z / world.w,
+    ))
+}
+
 fn handle_window_event(
     window: &mut glfw::Window,
     event: &glfw::WindowEvent,
@@ -358,6 +660,24 @@ fn handle_window_event(
         glfw::WindowEvent::Key(Key::Q, _, Action::Press, Modifiers::Control) => {
             window.set_should_close(true);
         }
+        glfw::WindowEvent::Key(Key::Z, _, Action::Press, Modifiers::Control) => {
+            state.undo();
+        }
+        glfw::WindowEvent::Key(Key::Y, _, Action::Press, Modifiers::Control) => {
+            state.redo();
+        }
+        glfw::WindowEvent::Key(Key::PageDown, _, Action::Press | Action::Repeat, _) => {
+            state.step_folder(1);
+        }
+        glfw::WindowEvent::Key(Key::PageUp, _, Action::Press | Action::Repeat, _) => {
+            state.step_folder(-1);
+        }
+        glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+            state.picking_pivot = false;
+            state.annotating = false;
+            state.inspecting = false;
+            state.measuring = None;
+        }
         glfw::WindowEvent::Key(Key::LeftControl, _, Action::Press, _) => {
             state.camera.speed *= 5.0;
         }
@@ -368,7 +688,20 @@ fn handle_window_event(
             if !state.can_capture_cursor {
                 return;
             }
+            if state.picking_pivot {
+                state.pick_pivot();
+                return;
+            }
+            if state.annotating {
+                state.add_annotation();
+                return;
+            }
+            if state.measuring.is_some() {
+                measure::add_point(state);
+                return;
+            }
             state.is_cursor_captured = true;
+            state.viewport_click = state.viewport_cursor;
             window.set_cursor_mode(glfw::CursorMode::Disabled);
         }
         glfw::WindowEvent::MouseButton(glfw::MouseButtonLeft, Action::Release, _) => {
@@ -377,6 +710,10 @@ fn handle_window_event(
             }
             state.is_cursor_captured = false;
             window.set_cursor_mode(glfw::CursorMode::Normal);
+            // a click that didn't rotate or pan picks the mesh under it
+            if let Some(cursor) = state.viewport_click.take() {
+                state.select_clicked_mesh(cursor);
+            }
         }
         glfw::WindowEvent::Scroll(_, yoff) => {
             state.camera.handle_mouse_scroll(
@@ -386,7 +723,7 @@ fn handle_window_event(
             );
         }
         glfw::WindowEvent::FileDrop(paths) => {
-            utils::import_models_from_paths(paths, state);
+            utils::ask_import_options(paths, state);
         }
         glfw::WindowEvent::FramebufferSize(w, h) => unsafe {
             gl::Viewport(0, 0, *w, *h);
@@ -406,7 +743,11 @@ fn create_scene_framebuffer() -> u32 {
     fb
 }
 
-fn create_scene_texture_and_renderbuffer(window: &glfw::Window, fbo: u32) -> (u32, u32) {
+fn create_scene_texture_and_renderbuffer(
+    window: &glfw::Window,
+    fbo: u32,
+    reversed_z: bool,
+) -> (u32, u32) {
     let mut fb_texture: u32 = 0;
     let mut rbo: u32 = 0;
 
@@ -416,7 +757,7 @@ fn create_scene_texture_and_renderbuffer(window: &glfw::Window, fbo: u32) -> (u3
         gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
         // texture
         gl::GenTextures(1, &mut fb_texture);
-        gl::BindTexture(gl::TEXTURE_2D, fb_texture);
+        gl_state::bind_texture(0, fb_texture);
 
         gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
         gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
@@ -443,7 +784,13 @@ fn create_scene_texture_and_renderbuffer(window: &glfw::Window, fbo: u32) -> (u3
         // renderbuffer for depth
         gl::GenRenderbuffers(1, &mut rbo);
         gl::BindRenderbuffer(gl::RENDERBUFFER, rbo);
-        gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, w, h);
+        // reversed-Z only pays off with a float depth buffer
+        let depth_format = if reversed_z {
+            gl::DEPTH32F_STENCIL8
+        } else {
+            gl::DEPTH24_STENCIL8
+        };
+        gl::RenderbufferStorage(gl::RENDERBUFFER, depth_format, w, h);
         gl::FramebufferRenderbuffer(
             gl::FRAMEBUFFER,
             gl::DEPTH_STENCIL_ATTACHMENT,
@@ -0,0 +1,300 @@
+use std::sync::mpsc::{self, Receiver};
+
+use crate::{
+    debug_draw::{self, DebugDraw},
+    importer::stl::Units,
+    mesh::Mesh,
+    model::Model,
+    pacing,
+};
+
+/// How long the principal axes are drawn, relative to the size of the model
+const AXIS_LENGTH: f32 = 0.4;
+/// Sweeps of the Jacobi eigenvalue algorithm, 3x3 matrices converge in a few
+const JACOBI_SWEEPS: usize = 16;
+
+/// Surface area and enclosed volume of a mesh, in the units of the model's file
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MeshProperties {
+    pub area: f32,
+    /// Negative if most of the faces point inwards, and only meaningful for
+    /// closed meshes
+    pub volume: f32,
+}
+
+impl MeshProperties {
+    /// Sums up the triangles, the volume being that of the tetrahedra they
+    /// form with the origin
+    pub fn new(positions: &[glm::Vec3], indices: &[u32]) -> Self {
+        let mut properties = MeshProperties::default();
+        for triangle in indices.chunks_exact(3) {
+            let [Some(a), Some(b), Some(c)] =
+                [0, 1, 2].map(|i| positions.get(triangle[i] as usize))
+            else {
+                continue;
+            };
+            let (a, b, c) = (*a, *b, *c);
+            properties.area += glm::length(glm::cross(b - a, c - a)) / 2.0;
+            properties.volume += glm::dot(a, glm::cross(b, c)) / 6.0;
+        }
+        properties
+    }
+
+    /// Adds up the properties of several meshes
+    pub fn total(properties: &[MeshProperties]) -> Self {
+        properties
+            .iter()
+            .fold(MeshProperties::default(), |total, p| MeshProperties {
+                area: total.area + p.area,
+                volume: total.volume + p.volume,
+            })
+    }
+
+    pub fn area_label(&self, units: Option<Units>) -> String {
+        match units {
+            Some(units) => format!("{:.3} {}²", self.area, units.abbreviation()),
+            None => format!("{:.3}", self.area),
+        }
+    }
+
+    pub fn volume_label(&self, units: Option<Units>) -> String {
+        match units {
+            Some(units) => format!("{:.3} {}³", self.volume, units.abbreviation()),
+            None => format!("{:.3}", self.volume),
+        }
+    }
+}
+
+/// The properties of every mesh of a model being computed on a worker thread
+#[derive(Debug)]
+pub struct Job {
+    rx: Receiver<Vec<MeshProperties>>,
+}
+
+impl Job {
+    /// Copies the geometry so the meshes can keep changing in the meantime
+    pub fn spawn(meshes: &[Mesh]) -> Self {
+        let geometry: Vec<(Vec<glm::Vec3>, Vec<u32>)> = meshes
+            .iter()
+            .map(|mesh| {
+                (
+                    mesh.vertices.iter().map(|v| v.position).collect(),
+                    mesh.indices.clone(),
+                )
+            })
+            .collect();
+        let (tx, rx) = mpsc::channel();
+
+        let _ = std::thread::Builder::new()
+            .name("mass properties".to_string())
+            .spawn(move || {
+                let properties = geometry
+                    .iter()
+                    .map(|(positions, indices)| MeshProperties::new(positions, indices))
+                    .collect();
+                // the model was removed or its geometry changed before this was done
+                if tx.send(properties).is_ok() {
+                    pacing::request_redraw();
+                }
+            });
+
+        Job { rx }
+    }
+
+    /// The properties of each mesh in order, once they're done
+    pub fn poll(&self) -> Option<Vec<MeshProperties>> {
+        self.rx.try_recv().ok()
+    }
+}
+
+type Mat3 = [[f64; 3]; 3];
+
+/// Where a solid of uniform density shaped like the model balances, and the
+/// axes it turns around most steadily
+#[derive(Debug, Clone)]
+pub struct Balance {
+    /// Center of mass in the model's own coordinates
+    pub center: glm::Vec3,
+    /// Principal axes of inertia in the model's own coordinates, the one with
+    /// the smallest moment first
+    pub axes: [glm::Vec3; 3],
+    /// Moments of inertia around `axes` for a density of 1
+    pub moments: [f32; 3],
+    /// Whether the meshes enclose no volume, so the surface was weighed
+    /// instead of the solid
+    pub surface_only: bool,
+}
+
+impl Balance {
+    /// `None` for models without any triangles
+    pub fn new(model: &Model) -> Option<Self> {
+        // the solid is split into tetrahedra between the origin and each
+        // triangle, the surface into the triangles themselves
+        let mut solid = Moments::default();
+        let mut surface = Moments::default();
+        for mesh in &model.meshes {
+            for triangle in mesh.indices.chunks_exact(3) {
+                let [Some(a), Some(b), Some(c)] =
+                    [0, 1, 2].map(|i| mesh.vertices.get(triangle[i] as usize))
+                else {
+                    continue;
+                };
+                let [a, b, c] = [a, b, c].map(|v| {
+                    let p = v.position;
+                    [p.x as f64, p.y as f64, p.z as f64]
+                });
+
+                let det = dot(a, cross(b, c));
+                solid.add(det / 6.0, det / 120.0, [a, b, c], 4.0);
+                let area = length(cross(sub(b, a), sub(c, a))) / 2.0;
+                surface.add(area, area / 12.0, [a, b, c], 3.0);
+            }
+        }
+
+        let surface_only = solid.mass.abs() <= surface.mass.powf(1.5) * 1e-6;
+        let moments = if surface_only { surface } else { solid };
+        if moments.mass.abs() <= f64::EPSILON {
+            return None;
+        }
+
+        // faces pointing inwards give negative volumes, the shape is the same
+        let sign = moments.mass.signum();
+        let mass = moments.mass * sign;
+        let center = moments.first.map(|x| x * sign / mass);
+        let mut covariance = moments.second;
+        for (i, row) in covariance.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = *value * sign - mass * center[i] * center[j];
+            }
+        }
+
+        // the inertia tensor is the trace minus the covariance, so they share
+        // their eigenvectors
+        let trace = covariance[0][0] + covariance[1][1] + covariance[2][2];
+        let (values, vectors) = symmetric_eigen(covariance);
+        let mut order = [0, 1, 2].map(|i| (trace - values[i], i));
+        order.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Some(Balance {
+            center: glm::vec3(center[0] as f32, center[1] as f32, center[2] as f32),
+            axes: order.map(|(_, i)| {
+                glm::vec3(
+                    vectors[0][i] as f32,
+                    vectors[1][i] as f32,
+                    vectors[2][i] as f32,
+                )
+            }),
+            moments: order.map(|(moment, _)| moment as f32),
+            surface_only,
+        })
+    }
+
+    /// Marks the center of mass and draws the principal axes through it in
+    /// red, green and blue
+    pub fn draw(&self, model: &Model, debug: &mut DebugDraw) {
+        let length = glm::length(model.aabb.max - model.aabb.min) * AXIS_LENGTH;
+        let center = model.local_to_world(self.center);
+        for (axis, color) in
+            self.axes
+                .iter()
+                .zip([debug_draw::RED, debug_draw::GREEN, debug_draw::BLUE])
+        {
+            debug.line(
+                model.local_to_world(self.center - *axis * length),
+                model.local_to_world(self.center + *axis * length),
+                color,
+            );
+        }
+        debug.sphere(
+            center,
+            glm::length(model.world_size()) * 0.01,
+            glm::vec3(1.0, 1.0, 1.0),
+        );
+    }
+}
+
+/// Mass, first and second moments summed up over simplices
+#[derive(Debug, Clone, Copy, Default)]
+struct Moments {
+    mass: f64,
+    first: [f64; 3],
+    second: Mat3,
+}
+
+impl Moments {
+    /// Adds a simplex of the given mass whose second moment is `scale` times
+    /// the sum of the outer products of its vertices and of their sum. Its
+    /// centroid is the sum of the vertices divided by `vertex_count`, which
+    /// includes the origin for tetrahedra.
+    fn add(&mut self, mass: f64, scale: f64, vertices: [[f64; 3]; 3], vertex_count: f64) {
+        let sum = [0, 1, 2].map(|i| vertices.iter().map(|v| v[i]).sum::<f64>());
+        self.mass += mass;
+        for i in 0..3 {
+            self.first[i] += mass * sum[i] / vertex_count;
+            for j in 0..3 {
+                let outer: f64 = vertices.iter().map(|v| v[i] * v[j]).sum();
+                self.second[i][j] += scale * (outer + sum[i] * sum[j]);
+            }
+        }
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn length(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Eigenvalues and eigenvectors (the columns) of a symmetric matrix with the
+/// Jacobi eigenvalue algorithm
+fn symmetric_eigen(mut m: Mat3) -> ([f64; 3], Mat3) {
+    let mut vectors = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    for _ in 0..JACOBI_SWEEPS {
+        let off_diagonal = m[0][1].abs() + m[0][2].abs() + m[1][2].abs();
+        if off_diagonal <= f64::EPSILON * (m[0][0].abs() + m[1][1].abs() + m[2][2].abs()) {
+            break;
+        }
+
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if m[p][q] == 0.0 {
+                continue;
+            }
+            // the rotation that zeroes m[p][q]
+            let theta = (m[q][q] - m[p][p]) / (2.0 * m[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+
+            for row in &mut m {
+                let (mkp, mkq) = (row[p], row[q]);
+                row[p] = c * mkp - s * mkq;
+                row[q] = s * mkp + c * mkq;
+            }
+            let (row_p, row_q) = (m[p], m[q]);
+            m[p] = [0, 1, 2].map(|k| c * row_p[k] - s * row_q[k]);
+            m[q] = [0, 1, 2].map(|k| s * row_p[k] + c * row_q[k]);
+            for row in &mut vectors {
+                let (vp, vq) = (row[p], row[q]);
+                row[p] = c * vp - s * vq;
+                row[q] = s * vp + c * vq;
+            }
+        }
+    }
+
+    ([m[0][0], m[1][1], m[2][2]], vectors)
+}
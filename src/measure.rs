@@ -0,0 +1,258 @@
+use crate::{
+    debug_draw::DebugDraw,
+    inspect,
+    model::Model,
+    ui::{i18n::tr, ui::State},
+};
+
+const COLOR: glm::Vec3 = glm::Vec3 {
+    x: 0.3,
+    y: 0.9,
+    z: 1.0,
+};
+/// Size of the crosses marking the points, relative to the size of the model
+const POINT_SIZE: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MeasureKind {
+    Distance,
+    Angle,
+}
+
+impl MeasureKind {
+    pub const ALL: [MeasureKind; 2] = [MeasureKind::Distance, MeasureKind::Angle];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MeasureKind::Distance => tr("measure.distance"),
+            MeasureKind::Angle => tr("measure.angle"),
+        }
+    }
+
+    /// How many points a measurement of this kind is made of
+    pub fn point_count(&self) -> usize {
+        match self {
+            MeasureKind::Distance => 2,
+            MeasureKind::Angle => 3,
+        }
+    }
+}
+
+/// What the clicked points are moved to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Snap {
+    /// Wherever the cursor is on the surface
+    Off,
+    /// The closest corner of the triangle under the cursor
+    Vertex,
+    /// The closest point on an edge of the triangle under the cursor
+    Edge,
+}
+
+impl Snap {
+    pub const ALL: [Snap; 3] = [Snap::Off, Snap::Vertex, Snap::Edge];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Snap::Off => tr("measure.snap_off"),
+            Snap::Vertex => tr("measure.snap_vertex"),
+            Snap::Edge => tr("measure.snap_edge"),
+        }
+    }
+}
+
+/// A distance between two points on a model, or the angle at the middle one
+/// of three
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub kind: MeasureKind,
+    /// In the model's own coordinates, so the measurement moves along with it
+    pub points: Vec<glm::Vec3>,
+    pub name: String,
+}
+
+impl Measurement {
+    /// The length in the units of the model's file or the angle in degrees
+    pub fn value(&self) -> f32 {
+        match (self.kind, self.points.as_slice()) {
+            (MeasureKind::Distance, [a, b]) => glm::length(*b - *a),
+            (MeasureKind::Angle, [a, vertex, b]) => {
+                let (u, v) = (*a - *vertex, *b - *vertex);
+                let cos = glm::dot(u, v) / (glm::length(u) * glm::length(v));
+                cos.clamp(-1.0, 1.0).acos().to_degrees()
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn label(&self, model: &Model) -> String {
+        match self.kind {
+            MeasureKind::Distance => match model.units {
+                Some(units) => format!("{:.3} {}", self.value(), units.abbreviation()),
+                None => format!("{:.3}", self.value()),
+            },
+            MeasureKind::Angle => format!("{:.1}°", self.value()),
+        }
+    }
+
+    /// Adds the lines between the points and a label with the value, at the
+    /// middle of a distance or at the corner of an angle
+    pub fn draw(&self, model: &Model, debug: &mut DebugDraw) {
+        draw_points(model, &self.points, debug);
+
+        let world: Vec<glm::Vec3> = self
+            .points
+            .iter()
+            .map(|p| model.local_to_world(*p))
+            .collect();
+        let anchor = match (self.kind, world.as_slice()) {
+            (MeasureKind::Distance, [a, b]) => (*a + *b) / 2.0,
+            (MeasureKind::Angle, [_, vertex, _]) => *vertex,
+            _ => return,
+        };
+        debug.text(anchor, format!("{}: {}", self.name, self.label(model)));
+    }
+}
+
+/// A measurement being placed by clicking points on the active model
+#[derive(Debug)]
+pub struct Placement {
+    pub model_id: u32,
+    pub kind: MeasureKind,
+    /// The points clicked so far, in the model's own coordinates
+    pub points: Vec<glm::Vec3>,
+    /// Where the next click would place a point, `None` while the cursor
+    /// isn't over the model
+    pub cursor: Option<glm::Vec3>,
+}
+
+impl Placement {
+    pub fn new(model_id: u32, kind: MeasureKind) -> Self {
+        Placement {
+            model_id,
+            kind,
+            points: vec![],
+            cursor: None,
+        }
+    }
+
+    /// The points placed so far and a line to the one under the cursor
+    pub fn draw(&self, model: &Model, debug: &mut DebugDraw) {
+        let mut points = self.points.clone();
+        points.extend(self.cursor);
+        draw_points(model, &points, debug);
+    }
+}
+
+/// Marks the points and connects them in order
+fn draw_points(model: &Model, points: &[glm::Vec3], debug: &mut DebugDraw) {
+    let size = glm::length(model.world_size()) * POINT_SIZE;
+    let world: Vec<glm::Vec3> = points.iter().map(|p| model.local_to_world(*p)).collect();
+
+    for pair in world.windows(2) {
+        debug.line(pair[0], pair[1], COLOR);
+    }
+    for p in &world {
+        debug.point(*p, size, COLOR);
+    }
+}
+
+/// The point under the cursor on `model`, snapped to the triangle it's on
+fn snapped_cursor(state: &State, model: &Model) -> Option<glm::Vec3> {
+    let pos = state.cursor_model_pos?;
+    if state.measure_snap == Snap::Off {
+        return Some(pos);
+    }
+    let Some(face) = state
+        .viewport_cursor
+        .and_then(|cursor| inspect::pick(state, cursor))
+        .filter(|face| face.model_id == model.id)
+    else {
+        return Some(pos);
+    };
+
+    let world = model.local_to_world(pos);
+    let [a, b, c] = face.world_positions;
+    let candidates = match state.measure_snap {
+        Snap::Vertex => vec![a, b, c],
+        _ => vec![
+            closest_on_segment(world, a, b),
+            closest_on_segment(world, b, c),
+            closest_on_segment(world, c, a),
+        ],
+    };
+    candidates
+        .into_iter()
+        .min_by(|p, q| glm::length(*p - world).total_cmp(&glm::length(*q - world)))
+        .map(|p| model.world_to_local(p))
+}
+
+fn closest_on_segment(p: glm::Vec3, a: glm::Vec3, b: glm::Vec3) -> glm::Vec3 {
+    let ab = b - a;
+    let length_sq = glm::dot(ab, ab);
+    if length_sq <= f32::EPSILON {
+        return a;
+    }
+    a + ab * (glm::dot(p - a, ab) / length_sq).clamp(0.0, 1.0)
+}
+
+/// Follows the cursor with the point the next click places. Placing stops
+/// once another model becomes the active one.
+pub fn update(state: &mut State) {
+    let Some(placement) = &state.measuring else {
+        return;
+    };
+    if state.active_model() != Some(placement.model_id) {
+        state.measuring = None;
+        return;
+    }
+
+    let cursor = state
+        .objects
+        .iter()
+        .find(|m| m.id == placement.model_id)
+        .and_then(|model| snapped_cursor(state, model));
+    if let Some(placement) = &mut state.measuring {
+        placement.cursor = cursor;
+    }
+}
+
+/// Places the point under the cursor, the measurement is added to the model
+/// once it has all of its points. Clicking on empty space keeps measuring.
+pub fn add_point(state: &mut State) {
+    let Some(placement) = &mut state.measuring else {
+        return;
+    };
+    let Some(point) = placement.cursor else {
+        return;
+    };
+    placement.points.push(point);
+    if placement.points.len() < placement.kind.point_count() {
+        return;
+    }
+
+    let Some(placement) = state.measuring.take() else {
+        return;
+    };
+    if let Some(model) = state
+        .objects
+        .iter_mut()
+        .find(|m| m.id == placement.model_id)
+    {
+        let name = format!(
+            "{} {}",
+            placement.kind.name(),
+            model
+                .measurements
+                .iter()
+                .filter(|m| m.kind == placement.kind)
+                .count()
+                + 1
+        );
+        model.measurements.push(Measurement {
+            kind: placement.kind,
+            points: placement.points,
+            name,
+        });
+    }
+}
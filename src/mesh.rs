@@ -1,11 +1,146 @@
+use std::{
+    cell::{Cell, OnceCell},
+    rc::Rc,
+};
+
 use glad_gl::gl;
 
 use crate::{
-    importer::{Material, TextureType},
+    aabb::AABB,
+    gl_state,
+    importer::{Material, ObjMesh, Texture, TextureOptions, TextureType},
     shader::Shader,
     utils,
 };
 
+/// Meshes with fewer vertices than this are packed into buffers shared with
+/// other small meshes of the same model
+const SMALL_MESH_VERTICES: usize = 4096;
+/// Most vertices packed into one set of shared buffers
+const ARENA_BLOCK_VERTICES: usize = 1 << 18;
+
+/// Sets the uniforms of the material and binds its textures
+fn set_material(shader: &Shader, material: &Material, is_wireframe: bool, show_textures: bool) {
+    if !is_wireframe {
+        // TODO: these can be missing in the (.obj) material, maybe we should set them
+        // to 1.0 as fallback. shininess too
+        shader.set_3fv("material.ambient", material.ambient_color);
+        shader.set_3fv("material.diffuse", material.diffuse_color);
+        shader.set_3fv("material.specular", material.specular_color);
+        shader.set_float("material.shininess", material.specular_exponent);
+        shader.set_float("material.opacity", material.opacity);
+    } else {
+        shader.set_3fv("material.ambient", glm::vec3(0.0, 0.0, 0.0));
+        shader.set_3fv("material.diffuse", glm::vec3(0.0, 0.0, 0.0));
+    }
+
+    if !show_textures {
+        shader.set_bool("useTextures", false);
+        return;
+    }
+
+    shader.set_bool("useTextures", !material.textures.is_empty());
+    shader.set_bool("hasEmissionTexture", false);
+    // the maps this material doesn't have shouldn't keep the previous one's options
+    for map in ["ambient", "diffuse", "specular", "emission"] {
+        set_texture_options(shader, map, &TextureOptions::default());
+    }
+    for (i, tex) in material.textures.iter().enumerate() {
+        match tex.typ {
+            TextureType::Ambient => {
+                shader.set_int("material.texture_ambient", i as i32);
+                set_texture_options(shader, "ambient", &tex.options);
+            }
+            TextureType::Diffuse => {
+                shader.set_int("material.texture_diffuse", i as i32);
+                set_texture_options(shader, "diffuse", &tex.options);
+            }
+            TextureType::Specular => {
+                shader.set_int("material.texture_specular", i as i32);
+                set_texture_options(shader, "specular", &tex.options);
+            }
+            TextureType::Emissive => {
+                shader.set_int("material.texture_emission", i as i32);
+                shader.set_bool("hasEmissionTexture", true);
+                set_texture_options(shader, "emission", &tex.options);
+            }
+            _ => {}
+        }
+
+        gl_state::bind_texture(i as u32, tex.id);
+    }
+}
+
+/// Sets the uniforms of `material.<map>_options`
+fn set_texture_options(shader: &Shader, map: &str, options: &TextureOptions) {
+    let uniform = |field: &str| format!("material.{}_options.{}", map, field);
+    shader.set_2fv(&uniform("offset"), options.offset);
+    shader.set_2fv(&uniform("scale"), options.scale);
+    shader.set_bool(&uniform("clampToEdge"), options.clamp);
+    shader.set_float(&uniform("base"), options.base);
+    shader.set_float(&uniform("gain"), options.gain);
+    shader.set_bool(&uniform("blendU"), options.blend_u);
+    shader.set_bool(&uniform("blendV"), options.blend_v);
+}
+
+/// Creates a VAO for the current context that reads the vertices from `vbo`
+/// and the indices from `ebo`
+fn create_vao(vbo: u32, ebo: u32, format: VertexFormat) -> u32 {
+    let mut vao = 0;
+
+    unsafe {
+        gl::GenVertexArrays(1, &mut vao);
+        gl::BindVertexArray(vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+
+        match format {
+            VertexFormat::Full => {
+                let stride = std::mem::size_of::<Vertex>() as i32;
+                // positions, normals, texture coords and colors
+                attribute(0, 3, gl::FLOAT, false, stride, 0);
+                attribute(1, 3, gl::FLOAT, false, stride, 3 * 4);
+                attribute(2, 2, gl::FLOAT, false, stride, 6 * 4);
+                attribute(3, 3, gl::FLOAT, false, stride, 8 * 4);
+            }
+            VertexFormat::Compact => {
+                let stride = std::mem::size_of::<CompactVertex>() as i32;
+                attribute(0, 3, gl::FLOAT, false, stride, 0);
+                // the shaders take the first 3 components of the 4 packed ones
+                attribute(1, 4, gl::INT_2_10_10_10_REV, true, stride, 12);
+                attribute(2, 2, gl::HALF_FLOAT, false, stride, 16);
+                attribute(3, 4, gl::UNSIGNED_BYTE, true, stride, 20);
+            }
+        }
+
+        gl::BindVertexArray(0);
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+    }
+
+    vao
+}
+
+/// Enables the vertex attribute at `index` of the bound VAO, `offset` bytes
+/// into each vertex
+unsafe fn attribute(
+    index: u32,
+    size: i32,
+    kind: u32,
+    normalized: bool,
+    stride: i32,
+    offset: usize,
+) {
+    gl::EnableVertexAttribArray(index);
+    gl::VertexAttribPointer(
+        index,
+        size,
+        kind,
+        if normalized { gl::TRUE } else { gl::FALSE },
+        stride,
+        offset as *const std::ffi::c_void,
+    );
+}
+
 fn create_rotation_matrix(pitch: f32, yaw: f32, roll: f32, pivot: glm::Vec3) -> glm::Mat4 {
     let pitch = pitch.to_radians();
     let yaw = yaw.to_radians();
@@ -28,6 +163,92 @@ pub fn apply_rotation(matrix: &glm::Mat4, rot: glm::Vec3, pivot: glm::Vec3) -> g
     rot * *matrix
 }
 
+/// Ways a mesh can be drawn differently from the rest of the scene
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderOverrides {
+    pub wireframe: bool,
+    /// Drawn in the colors of its materials and textures, without lighting
+    pub unlit: bool,
+    /// Color replacing the ones of the materials and textures
+    pub tint: Option<glm::Vec3>,
+}
+
+/// The runs of a mesh's triangles that use the same material, drawn with a
+/// single call
+#[derive(Debug, Clone)]
+pub struct MaterialBatch<'a> {
+    pub material: &'a Material,
+    counts: Vec<i32>,
+    /// Byte offsets of the runs into the element buffer
+    offsets: Vec<*const std::ffi::c_void>,
+}
+
+/// A run of a mesh's triangles drawn with the same material
+#[derive(Debug, Clone)]
+pub struct MaterialRange {
+    pub material: Material,
+    /// First index of the run in `Mesh::indices`
+    pub start: usize,
+    /// Number of indices in the run
+    pub count: usize,
+}
+
+/// New vertices and indices for a mesh, from edits that add or remove
+/// triangles, and how many of them each of its material ranges gets
+#[derive(Debug, Clone, Default)]
+pub struct MeshGeometry {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    /// The number of indices of each of the mesh's material ranges, in
+    /// order. The triangles of a range follow the ones of the range before.
+    pub material_counts: Vec<usize>,
+}
+
+impl MeshGeometry {
+    /// Geometry drawn with the materials of `ranges` as they are
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, ranges: &[MaterialRange]) -> Self {
+        Self {
+            vertices,
+            indices,
+            material_counts: ranges.iter().map(|r| r.count).collect(),
+        }
+    }
+
+    /// Geometry made of `triangles`, each with the index of the material
+    /// range it's drawn with, for a mesh with `ranges` of them. They're
+    /// grouped by their range and otherwise keep their order.
+    pub fn from_triangles(
+        vertices: Vec<Vertex>,
+        mut triangles: Vec<(usize, [u32; 3])>,
+        ranges: usize,
+    ) -> Self {
+        triangles.sort_by_key(|(range, _)| *range);
+        let mut material_counts = vec![0; ranges];
+        for (range, _) in &triangles {
+            material_counts[*range] += 3;
+        }
+
+        Self {
+            vertices,
+            indices: triangles.into_iter().flat_map(|(_, t)| t).collect(),
+            material_counts,
+        }
+    }
+
+    /// The material range the triangle is in
+    pub fn material_index(&self, triangle: usize) -> usize {
+        let index = triangle * 3;
+        let mut end = 0;
+        self.material_counts
+            .iter()
+            .position(|count| {
+                end += count;
+                index < end
+            })
+            .unwrap_or(0)
+    }
+}
+
 #[derive(Debug)]
 pub struct Mesh {
     pub name: String,
@@ -35,114 +256,197 @@ pub struct Mesh {
     pub rotation: glm::Vec3,
     pub scale: glm::Vec3,
     pub pivot: glm::Vec3,
+    /// Hidden meshes are skipped when drawing and inspecting the model
+    pub visible: bool,
+    pub overrides: RenderOverrides,
 
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
-    pub material: Material,
+    /// The materials of consecutive runs of triangles, there's always at
+    /// least one and together they cover all of the indices
+    pub materials: Vec<MaterialRange>,
 
-    vao: u32,
-    vbo: u32,
-    ebo: u32,
+    buffers: Buffers,
+    /// The box around the vertices, computed when first asked for
+    bounds: OnceCell<Option<AABB>>,
+    /// The world space box for the model matrix it was last asked for with
+    world_bounds: Cell<Option<(glm::Mat4, Option<AABB>)>>,
 }
 
 impl Mesh {
+    /// `materials` has the index each material starts at, in order
     pub fn new(
         name: &str,
         vertices: Vec<Vertex>,
         indices: Vec<u32>,
-        material: Option<Material>,
+        materials: Vec<(usize, Option<Material>)>,
+        format: VertexFormat,
     ) -> Mesh {
-        let mut vao = 0;
-        let mut vbo = 0;
-        let mut ebo = 0;
-
-        unsafe {
-            gl::GenVertexArrays(1, &mut vao);
-            gl::GenBuffers(1, &mut vbo);
-            gl::GenBuffers(1, &mut ebo);
-
-            gl::BindVertexArray(vao);
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (std::mem::size_of::<Vertex>() * vertices.len()) as isize,
-                vertices.as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW,
-            );
-
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (std::mem::size_of::<u32>() * indices.len()) as isize,
-                indices.as_ptr() as *const std::ffi::c_void,
-                gl::STATIC_DRAW,
-            );
+        let buffers = Buffers::new(&vertices, &indices, format);
+        Mesh::with_buffers(name, vertices, indices, materials, buffers)
+    }
 
-            // vertex positions
-            gl::EnableVertexAttribArray(0);
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                std::mem::size_of::<Vertex>() as i32,
-                std::ptr::null(),
-            );
+    /// Creates the meshes of a model. The small ones are packed together into
+    /// shared buffers and drawn from their part of them, scenes made of
+    /// thousands of tiny meshes would otherwise need as many sets of GL objects.
+    pub fn new_batch(meshes: Vec<ObjMesh>, format: VertexFormat) -> Vec<Mesh> {
+        // consecutive small meshes go into the same block until it's full
+        let mut blocks: Vec<Vec<usize>> = vec![];
+        let mut block_vertices = ARENA_BLOCK_VERTICES;
+        for (i, mesh) in meshes.iter().enumerate() {
+            let count = mesh.vertices.len();
+            if count >= SMALL_MESH_VERTICES {
+                continue;
+            }
+            if block_vertices + count > ARENA_BLOCK_VERTICES {
+                blocks.push(vec![]);
+                block_vertices = 0;
+            }
+            block_vertices += count;
+            if let Some(block) = blocks.last_mut() {
+                block.push(i);
+            }
+        }
 
-            // vertex normals
-            gl::EnableVertexAttribArray(1);
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                std::mem::size_of::<Vertex>() as i32,
-                (3 * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
-            );
+        let mut shared: Vec<Option<Buffers>> = meshes.iter().map(|_| None).collect();
+        // a mesh alone in its block saves nothing by sharing it
+        for block in blocks.iter().filter(|block| block.len() > 1) {
+            let members: Vec<&ObjMesh> = block.iter().map(|i| &meshes[*i]).collect();
+            for (i, buffers) in block.iter().zip(ArenaBlock::pack(&members, format)) {
+                shared[*i] = Some(buffers);
+            }
+        }
 
-            // vertex texture coords
-            gl::EnableVertexAttribArray(2);
-            gl::VertexAttribPointer(
-                2,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                std::mem::size_of::<Vertex>() as i32,
-                (6 * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
-            );
+        meshes
+            .into_iter()
+            .zip(shared)
+            .map(|(mesh, buffers)| {
+                let buffers =
+                    buffers.unwrap_or_else(|| Buffers::new(&mesh.vertices, &mesh.indices, format));
+                Mesh::with_buffers(
+                    &mesh.name,
+                    mesh.vertices,
+                    mesh.indices,
+                    mesh.materials,
+                    buffers,
+                )
+            })
+            .collect()
+    }
 
-            gl::BindVertexArray(0);
+    fn with_buffers(
+        name: &str,
+        vertices: Vec<Vertex>,
+        indices: Vec<u32>,
+        materials: Vec<(usize, Option<Material>)>,
+        buffers: Buffers,
+    ) -> Mesh {
+        let mut ranges: Vec<MaterialRange> = vec![];
+        for (i, (start, material)) in materials.iter().enumerate() {
+            let end = materials
+                .get(i + 1)
+                .map_or(indices.len(), |(next, _)| *next);
+            ranges.push(MaterialRange {
+                material: material.clone().unwrap_or_default(),
+                start: *start,
+                count: end.saturating_sub(*start),
+            });
+        }
+        if ranges.is_empty() {
+            ranges.push(MaterialRange {
+                material: Material::default(),
+                start: 0,
+                count: indices.len(),
+            });
         }
 
         Mesh {
             name: name.to_string(),
             vertices,
             indices,
-            material: material.unwrap_or_default(),
-            vbo,
-            vao,
-            ebo,
+            materials: ranges,
+            buffers,
+            bounds: OnceCell::new(),
+            world_bounds: Cell::new(None),
             position: glm::vec3(0.0, 0.0, 0.0),
             rotation: glm::vec3(0.0, 0.0, 0.0),
             scale: glm::vec3(1.0, 1.0, 1.0),
             pivot: glm::vec3(0.0, 0.0, 0.0),
+            visible: true,
+            overrides: RenderOverrides::default(),
+        }
+    }
+
+    /// Draws the mesh with `model_mat`, see `Model::mesh_model_matrix`,
+    /// tinting the triangles with `highlighted_material`
+    pub fn draw(
+        &self,
+        shader: &Shader,
+        model_mat: &glm::Mat4,
+        show_textures: bool,
+        highlighted_material: Option<&str>,
+    ) {
+        self.draw_with_vao(
+            self.buffers.vao,
+            shader,
+            model_mat,
+            show_textures,
+            highlighted_material,
+        );
+    }
+
+    /// Draws the mesh in a context that shares its buffers with the one it was
+    /// created in. VAOs aren't shared between contexts, so a temporary one is
+    /// made over the same buffers.
+    pub fn draw_shared(&self, shader: &Shader, model_mat: &glm::Mat4, show_textures: bool) {
+        let vao = create_vao(self.buffers.vbo, self.buffers.ebo, self.buffers.format);
+        self.draw_with_vao(vao, shader, model_mat, show_textures, None);
+        unsafe {
+            gl::DeleteVertexArrays(1, &vao);
         }
     }
 
-    pub fn draw(&self, shader: &Shader, scale: f32, pivot: glm::Vec3, show_textures: bool) {
+    fn draw_with_vao(
+        &self,
+        vao: u32,
+        shader: &Shader,
+        model_mat: &glm::Mat4,
+        show_textures: bool,
+        highlighted_material: Option<&str>,
+    ) {
         shader.use_shader();
+        self.set_transform(shader, model_mat);
 
-        let model_mat = glm::ext::scale(&utils::mat_ident(), glm::vec3(scale, scale, scale));
-        let model_mat = apply_rotation(&model_mat, self.rotation, pivot);
-        let model_mat = glm::ext::translate(
-            &model_mat,
-            glm::vec3(self.position.x, self.position.y, self.position.z),
-        );
-        shader.set_mat4fv("model", &model_mat);
+        let polygon_mode = gl_state::current_polygon_mode();
+        unsafe {
+            gl::BindVertexArray(vao);
+        }
+        let is_wireframe = self.set_overrides(shader, polygon_mode);
+
+        for batch in self.material_batches() {
+            shader.set_bool(
+                "highlighted",
+                highlighted_material == Some(batch.material.name.as_str()),
+            );
+            self.set_material(shader, batch.material, is_wireframe, show_textures);
+            self.draw_batch(&batch);
+        }
+
+        shader.set_bool("unlit", false);
+        unsafe {
+            // reset stuff to default
+            gl::BindVertexArray(0);
+        }
+        gl_state::active_texture(0);
+        gl_state::polygon_mode(polygon_mode);
+    }
+
+    /// Sets the model and normal matrices the mesh is drawn with
+    pub fn set_transform(&self, shader: &Shader, model_mat: &glm::Mat4) {
+        shader.set_mat4fv("model", model_mat);
 
-        if glm::ext::is_invertible(&model_mat) {
-            let normal_mat = glm::transpose(&glm::inverse(&model_mat));
+        if glm::ext::is_invertible(model_mat) {
+            let normal_mat = glm::transpose(&glm::inverse(model_mat));
             let normal_mat = glm::mat3(
                 normal_mat[0][0],
                 normal_mat[0][1],
@@ -159,72 +463,128 @@ impl Mesh {
         } else {
             shader.set_bool("useNormalMatrix", false);
         }
+    }
 
-        let mut polygon_mode = 0;
-        unsafe {
-            gl::GetIntegerv(gl::POLYGON_MODE, &mut polygon_mode);
-        }
-        let is_wireframe = polygon_mode as u32 == gl::LINE;
-
-        if !is_wireframe {
-            // TODO: these can be missing in the (.obj) material, maybe we should set them
-            // to 1.0 as fallback. shininess too
-            shader.set_3fv("material.ambient", self.material.ambient_color);
-            shader.set_3fv("material.diffuse", self.material.diffuse_color);
-            shader.set_3fv("material.specular", self.material.specular_color);
-            shader.set_float("material.shininess", self.material.specular_exponent);
-            shader.set_float("material.opacity", self.material.opacity);
-        } else {
-            shader.set_3fv("material.ambient", glm::vec3(0.0, 0.0, 0.0));
-            shader.set_3fv("material.diffuse", glm::vec3(0.0, 0.0, 0.0));
-        }
-
-        if show_textures {
-            shader.set_bool("useTextures", !self.material.textures.is_empty());
-            for (i, tex) in self.material.textures.iter().enumerate() {
-                shader.set_bool("hasEmissionTexture", false);
-                unsafe {
-                    gl::ActiveTexture(gl::TEXTURE0 + i as u32);
-                    match tex.typ {
-                        TextureType::Ambient => {
-                            shader.set_int("material.texture_ambient", i as i32);
-                        }
-                        TextureType::Diffuse => {
-                            shader.set_int("material.texture_diffuse", i as i32);
-                        }
-                        TextureType::Specular => {
-                            shader.set_int("material.texture_specular", i as i32);
-                        }
-                        TextureType::Emissive => {
-                            shader.set_int("material.texture_emission", i as i32);
-                            shader.set_bool("hasEmissionTexture", true);
-                        }
-                        _ => {}
-                    }
+    /// Applies the mesh's render overrides on top of `polygon_mode`, returns
+    /// whether it's drawn as a wireframe
+    pub fn set_overrides(&self, shader: &Shader, polygon_mode: u32) -> bool {
+        let is_wireframe = self.overrides.wireframe || polygon_mode == gl::LINE;
+        gl_state::polygon_mode(if is_wireframe { gl::LINE } else { polygon_mode });
+        shader.set_bool("unlit", self.overrides.unlit);
+        is_wireframe
+    }
+
+    /// Sets the uniforms of `material` and binds its textures, unless the
+    /// mesh is tinted
+    pub fn set_material(
+        &self,
+        shader: &Shader,
+        material: &Material,
+        is_wireframe: bool,
+        show_textures: bool,
+    ) {
+        set_material(shader, material, is_wireframe, show_textures);
+        if let (Some(tint), false) = (self.overrides.tint, is_wireframe) {
+            shader.set_3fv("material.ambient", tint * 0.5);
+            shader.set_3fv("material.diffuse", tint);
+            shader.set_bool("useTextures", false);
+            shader.set_bool("hasEmissionTexture", false);
+        }
+    }
 
-                    gl::BindTexture(gl::TEXTURE_2D, tex.id);
+    /// The runs of triangles grouped by their material, in the order the
+    /// materials first appear
+    pub fn material_batches(&self) -> Vec<MaterialBatch<'_>> {
+        let mut batches = vec![];
+        // ranges that share a material are drawn together
+        let mut drawn = vec![false; self.materials.len()];
+        for (i, range) in self.materials.iter().enumerate() {
+            if drawn[i] {
+                continue;
+            }
+
+            let mut counts = vec![];
+            let mut offsets = vec![];
+            for (j, other) in self.materials.iter().enumerate().skip(i) {
+                if other.material.name == range.material.name && other.count > 0 {
+                    drawn[j] = true;
+                    counts.push(other.count as i32);
+                    let first = self.buffers.first_index + other.start;
+                    offsets.push((first * std::mem::size_of::<u32>()) as *const std::ffi::c_void);
                 }
             }
-        } else {
-            shader.set_bool("useTextures", false);
+            if !counts.is_empty() {
+                batches.push(MaterialBatch {
+                    material: &range.material,
+                    counts,
+                    offsets,
+                });
+            }
+        }
+        batches
+    }
+
+    /// Binds the VAO the mesh is drawn from in the main window
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindVertexArray(self.buffers.vao);
         }
+    }
 
+    /// Draws the triangles of one of the mesh's batches from the bound VAO
+    pub fn draw_batch(&self, batch: &MaterialBatch) {
+        let base_vertices = vec![self.buffers.base_vertex as i32; batch.counts.len()];
         unsafe {
-            // draw Mesh
-            gl::BindVertexArray(self.vao);
-            gl::DrawElements(
+            gl::MultiDrawElementsBaseVertex(
                 gl::TRIANGLES,
-                self.indices.len() as i32,
+                batch.counts.as_ptr(),
                 gl::UNSIGNED_INT,
-                std::ptr::null(),
+                batch.offsets.as_ptr(),
+                batch.counts.len() as i32,
+                base_vertices.as_ptr(),
             );
-
-            // reset stuff to default
-            gl::ActiveTexture(gl::TEXTURE0);
-            gl::BindVertexArray(0);
         }
     }
 
+    /// The material the triangle is drawn with
+    pub fn material_at(&self, triangle: usize) -> &Material {
+        &self.materials[self.material_index(triangle)].material
+    }
+
+    /// The index of the material range the triangle is in
+    pub fn material_index(&self, triangle: usize) -> usize {
+        let index = triangle * 3;
+        self.materials
+            .iter()
+            .position(|r| (r.start..r.start + r.count).contains(&index))
+            .unwrap_or(0)
+    }
+
+    /// The triangles of the mesh, each with the index of its material range
+    pub fn triangles_by_material(&self) -> Vec<(usize, [u32; 3])> {
+        self.materials
+            .iter()
+            .enumerate()
+            .flat_map(|(i, range)| {
+                self.indices[range.start..range.start + range.count]
+                    .chunks_exact(3)
+                    .map(move |t| (i, [t[0], t[1], t[2]]))
+            })
+            .collect()
+    }
+
+    pub fn textures(&self) -> impl Iterator<Item = &Texture> {
+        self.materials
+            .iter()
+            .flat_map(|r| r.material.textures.iter())
+    }
+
+    pub fn textures_mut(&mut self) -> impl Iterator<Item = &mut Texture> {
+        self.materials
+            .iter_mut()
+            .flat_map(|r| r.material.textures.iter_mut())
+    }
+
     pub fn rotate(&mut self, rotation: glm::Vec3) {
         self.rotation = self.rotation + rotation;
     }
@@ -232,17 +592,316 @@ impl Mesh {
     pub fn reset_rotation(&mut self) {
         self.rotation = glm::vec3(0.0, 0.0, 0.0);
     }
+
+    pub fn reset_position(&mut self) {
+        self.position = glm::vec3(0.0, 0.0, 0.0);
+    }
+
+    pub fn reset_scale(&mut self) {
+        self.scale = glm::vec3(1.0, 1.0, 1.0);
+    }
+
+    /// Whether the mesh is moved, rotated or scaled within its model
+    pub fn has_local_transform(&self) -> bool {
+        self.position != glm::vec3(0.0, 0.0, 0.0)
+            || self.rotation != glm::vec3(0.0, 0.0, 0.0)
+            || self.scale != glm::vec3(1.0, 1.0, 1.0)
+    }
+
+    /// The box around the vertices, None without any
+    pub fn bounds(&self) -> Option<AABB> {
+        *self.bounds.get_or_init(|| {
+            let mut vertices = self.vertices.iter().map(|v| v.position);
+            let first = vertices.next()?;
+            let (min, max) = vertices.fold((first, first), |(min, max), p| {
+                (glm::min(min, p), glm::max(max, p))
+            });
+            Some(AABB::new(min, max))
+        })
+    }
+
+    /// The box around the mesh once it's drawn with `model_mat`, only
+    /// computed again when the matrix or the geometry changed
+    pub fn world_bounds(&self, model_mat: &glm::Mat4) -> Option<AABB> {
+        match self.world_bounds.get() {
+            Some((mat, bounds)) if mat == *model_mat => bounds,
+            _ => {
+                let bounds = self.bounds().map(|b| b.transformed(model_mat));
+                self.world_bounds.set(Some((*model_mat, bounds)));
+                bounds
+            }
+        }
+    }
+
+    fn invalidate_bounds(&mut self) {
+        self.bounds = OnceCell::new();
+        self.world_bounds.set(None);
+    }
+
+    /// The mesh's transform within its model, `pivot` is in the model's own
+    /// coordinates with the mesh's position and scale applied
+    pub fn local_transform(&self, pivot: glm::Vec3) -> glm::Mat4 {
+        let mat = glm::ext::scale(&utils::mat_ident(), self.scale);
+        let mat = glm::ext::translate(&mat, self.position);
+
+        apply_rotation(&mat, self.rotation, pivot)
+    }
+
+    /// Returns the vertices transformed by `transform`, with the normals
+    /// transformed accordingly
+    pub fn transformed_vertices(&self, transform: &glm::Mat4) -> Vec<Vertex> {
+        let normal_mat = if glm::ext::is_invertible(transform) {
+            Some(glm::transpose(&glm::inverse(transform)))
+        } else {
+            None
+        };
+
+        self.vertices
+            .iter()
+            .map(|vertex| {
+                let p = vertex.position;
+                let p = *transform * glm::vec4(p.x, p.y, p.z, 1.0);
+
+                let n = vertex.normal;
+                let normal = match normal_mat {
+                    Some(normal_mat) => {
+                        let n = normal_mat * glm::vec4(n.x, n.y, n.z, 0.0);
+                        glm::normalize(glm::vec3(n.x, n.y, n.z))
+                    }
+                    None => n,
+                };
+
+                Vertex {
+                    position: glm::vec3(p.x / p.w, p.y / p.w, p.z / p.w),
+                    normal,
+                    ..vertex.clone()
+                }
+            })
+            .collect()
+    }
+
+    /// Transforms the vertex data by `transform`, re-uploads it and resets
+    /// the mesh's transform so it's drawn the same as before.
+    pub fn bake_transform(&mut self, transform: &glm::Mat4) {
+        self.vertices = self.transformed_vertices(transform);
+        self.invalidate_bounds();
+
+        self.reset_position();
+        self.reset_rotation();
+        self.reset_scale();
+
+        let buffers = &self.buffers;
+        write_vertices(
+            buffers.vbo,
+            buffers.base_vertex,
+            &self.vertices,
+            buffers.format,
+        );
+    }
+}
+
+impl Mesh {
+    /// Replaces the vertex data and re-uploads it, for edits that change the
+    /// number of vertices or triangles
+    pub fn set_geometry(&mut self, geometry: MeshGeometry) {
+        let MeshGeometry {
+            vertices,
+            indices,
+            material_counts,
+        } = geometry;
+        debug_assert_eq!(material_counts.len(), self.materials.len());
+        debug_assert_eq!(material_counts.iter().sum::<usize>(), indices.len());
+
+        let mut start = 0;
+        for (range, count) in self.materials.iter_mut().zip(material_counts) {
+            range.start = start;
+            range.count = count;
+            start += count;
+        }
+        self.vertices = vertices;
+        self.indices = indices;
+        self.invalidate_bounds();
+
+        if self.buffers.block.is_some() {
+            // its part of the shared buffers can't grow, so it gets its own
+            let format = self.buffers.format;
+            self.buffers.release();
+            self.buffers = Buffers::new(&self.vertices, &self.indices, format);
+        } else {
+            // the VAO keeps pointing at the same buffers, only their storage changes
+            upload_vertices(self.buffers.vbo, &self.vertices, self.buffers.format);
+            upload_indices(self.buffers.vao, &self.indices);
+        }
+    }
+
+    /// Frees the GPU buffers and the geometry, the transform and material stay
+    pub fn unload(&mut self) {
+        self.buffers.release();
+        self.vertices = Vec::new();
+        self.indices = Vec::new();
+        self.invalidate_bounds();
+    }
+
+    /// Frees the GPU buffers but keeps the geometry, for when the context
+    /// they were made in is lost
+    pub fn release_buffers(&mut self) {
+        self.buffers.release();
+    }
+
+    /// Uploads the geometry into buffers of the mesh's own after
+    /// `release_buffers`
+    pub fn restore_buffers(&mut self) {
+        self.buffers = Buffers::new(&self.vertices, &self.indices, self.buffers.format);
+    }
 }
 
 impl Drop for Mesh {
     fn drop(&mut self) {
         // TODO: should we impl a Drop on material to delete the textures from gpu??
+        // the GL context might be in a bad state if we're unwinding from a panic,
+        // and it's about to be destroyed anyway
+        if std::thread::panicking() {
+            return;
+        }
+        self.buffers.release();
+    }
+}
+
+/// The GL objects a mesh is drawn from
+#[derive(Debug)]
+struct Buffers {
+    vao: u32,
+    vbo: u32,
+    ebo: u32,
+    format: VertexFormat,
+    /// Keeps the objects alive while they're shared with other small meshes,
+    /// `None` if they're the mesh's own
+    block: Option<Rc<ArenaBlock>>,
+    /// Where the mesh's vertices start in `vbo`
+    base_vertex: usize,
+    /// Where the mesh's indices start in `ebo`
+    first_index: usize,
+}
+
+impl Buffers {
+    /// Buffers of the mesh's own, filled with its geometry
+    fn new(vertices: &[Vertex], indices: &[u32], format: VertexFormat) -> Self {
+        let (vao, vbo, ebo) = create_buffers(format);
+        upload_vertices(vbo, vertices, format);
+        upload_indices(vao, indices);
+
+        Buffers {
+            vao,
+            vbo,
+            ebo,
+            format,
+            block: None,
+            base_vertex: 0,
+            first_index: 0,
+        }
+    }
+
+    /// Deletes the GL objects, shared ones only once the last mesh using them
+    /// lets go of them
+    fn release(&mut self) {
+        if self.block.take().is_none() {
+            delete_buffers(self.vao, self.vbo, self.ebo);
+        }
+        self.vao = 0;
+        self.vbo = 0;
+        self.ebo = 0;
+        self.base_vertex = 0;
+        self.first_index = 0;
+    }
+}
+
+/// Buffers several small meshes are packed into one after the other
+#[derive(Debug)]
+struct ArenaBlock {
+    vao: u32,
+    vbo: u32,
+    ebo: u32,
+}
+
+impl ArenaBlock {
+    /// Uploads all of `meshes` into a new block, returning the part of it
+    /// each one is drawn from
+    fn pack(meshes: &[&ObjMesh], format: VertexFormat) -> Vec<Buffers> {
+        let vertex_count: usize = meshes.iter().map(|m| m.vertices.len()).sum();
+        let index_count: usize = meshes.iter().map(|m| m.indices.len()).sum();
+        let (vao, vbo, ebo) = create_buffers(format);
         unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (format.vertex_size() * vertex_count) as isize,
+                std::ptr::null(),
+                gl::STATIC_DRAW,
+            );
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(vao);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                (std::mem::size_of::<u32>() * index_count) as isize,
+                std::ptr::null(),
+                gl::STATIC_DRAW,
+            );
             gl::BindVertexArray(0);
-            gl::DeleteBuffers(1, &self.vbo);
-            gl::DeleteBuffers(1, &self.ebo);
-            gl::DeleteVertexArrays(1, &self.vao);
         }
+
+        let block = Rc::new(ArenaBlock { vao, vbo, ebo });
+        let (mut base_vertex, mut first_index) = (0, 0);
+        meshes
+            .iter()
+            .map(|mesh| {
+                write_vertices(vbo, base_vertex, &mesh.vertices, format);
+                write_indices(vao, first_index, &mesh.indices);
+                let buffers = Buffers {
+                    vao,
+                    vbo,
+                    ebo,
+                    format,
+                    block: Some(block.clone()),
+                    base_vertex,
+                    first_index,
+                };
+                base_vertex += mesh.vertices.len();
+                first_index += mesh.indices.len();
+                buffers
+            })
+            .collect()
+    }
+}
+
+impl Drop for ArenaBlock {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            return;
+        }
+        delete_buffers(self.vao, self.vbo, self.ebo);
+    }
+}
+
+/// Generates a vertex and an element buffer and a VAO reading from them
+fn create_buffers(format: VertexFormat) -> (u32, u32, u32) {
+    let mut vbo = 0;
+    let mut ebo = 0;
+
+    unsafe {
+        gl::GenBuffers(1, &mut vbo);
+        gl::GenBuffers(1, &mut ebo);
+    }
+    let vao = create_vao(vbo, ebo, format);
+
+    (vao, vbo, ebo)
+}
+
+fn delete_buffers(vao: u32, vbo: u32, ebo: u32) {
+    unsafe {
+        gl::BindVertexArray(0);
+        gl::DeleteBuffers(1, &vbo);
+        gl::DeleteBuffers(1, &ebo);
+        gl::DeleteVertexArrays(1, &vao);
     }
 }
 
@@ -252,14 +911,167 @@ pub struct Vertex {
     pub position: glm::Vec3,
     pub normal: glm::Vec3,
     pub tex_coords: glm::Vec2,
+    /// Multiplied with the lit color, white unless the file has vertex or face colors
+    pub color: glm::Vec3,
 }
 
 impl Vertex {
+    pub const WHITE: glm::Vec3 = glm::Vec3 {
+        x: 1.0,
+        y: 1.0,
+        z: 1.0,
+    };
+
     pub fn new(position: glm::Vec3, normal: glm::Vec3, tex_coords: glm::Vec2) -> Self {
         Vertex {
             position,
             normal,
             tex_coords,
+            color: Vertex::WHITE,
+        }
+    }
+}
+
+/// How the vertices are laid out in the GPU buffers
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum VertexFormat {
+    /// Every attribute as 32 bit floats, 44 bytes a vertex
+    #[default]
+    Full,
+    /// Float positions, normals in 10 bits per axis, half float texture
+    /// coords and 8 bit colors, 24 bytes a vertex
+    Compact,
+}
+
+impl VertexFormat {
+    pub fn vertex_size(&self) -> usize {
+        match self {
+            VertexFormat::Full => std::mem::size_of::<Vertex>(),
+            VertexFormat::Compact => std::mem::size_of::<CompactVertex>(),
+        }
+    }
+}
+
+/// A `Vertex` packed for `VertexFormat::Compact`
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+struct CompactVertex {
+    position: [f32; 3],
+    /// x, y and z as signed normalized 10 bit integers from the lowest bits
+    normal: u32,
+    tex_coords: [u16; 2],
+    color: [u8; 4],
+}
+
+impl CompactVertex {
+    fn new(vertex: &Vertex) -> Self {
+        let (position, normal) = (vertex.position, vertex.normal);
+        let (tex_coords, color) = (vertex.tex_coords, vertex.color);
+        let snorm = |x: f32| ((x.clamp(-1.0, 1.0) * 511.0).round() as i32 as u32) & 0x3ff;
+        let unorm = |x: f32| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        CompactVertex {
+            position: [position.x, position.y, position.z],
+            normal: snorm(normal.x) | snorm(normal.y) << 10 | snorm(normal.z) << 20,
+            tex_coords: [half_float(tex_coords.x), half_float(tex_coords.y)],
+            color: [unorm(color.x), unorm(color.y), unorm(color.z), 255],
+        }
+    }
+}
+
+/// The bits of the closest half float to `x`, out of range values become
+/// infinite and the ones too small for it become 0
+fn half_float(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
         }
+        // subnormal, with the implicit leading bit shifted in
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let rounded = (mantissa + (1 << (shift - 1))) >> shift;
+        return sign | rounded as u16;
+    }
+
+    // rounding can carry into the exponent, which is still the right result
+    let rounded = ((exponent as u32) << 10) + ((mantissa + 0x1000) >> 13);
+    sign | rounded.min(0x7c00) as u16
+}
+
+/// Calls `f` with the vertices laid out in `format` and their size in bytes
+fn with_vertex_data(
+    vertices: &[Vertex],
+    format: VertexFormat,
+    f: impl FnOnce(*const std::ffi::c_void, isize),
+) {
+    let size = (format.vertex_size() * vertices.len()) as isize;
+    match format {
+        VertexFormat::Full => f(vertices.as_ptr() as *const std::ffi::c_void, size),
+        VertexFormat::Compact => {
+            let packed: Vec<CompactVertex> = vertices.iter().map(CompactVertex::new).collect();
+            f(packed.as_ptr() as *const std::ffi::c_void, size);
+        }
+    }
+}
+
+/// Replaces the storage of `vbo` with `vertices` in the given format
+fn upload_vertices(vbo: u32, vertices: &[Vertex], format: VertexFormat) {
+    with_vertex_data(vertices, format, |data, size| unsafe {
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferData(gl::ARRAY_BUFFER, size, data, gl::STATIC_DRAW);
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+    });
+}
+
+/// Overwrites the vertices of `vbo` starting at `first`, which has to leave
+/// room for all of them
+fn write_vertices(vbo: u32, first: usize, vertices: &[Vertex], format: VertexFormat) {
+    with_vertex_data(vertices, format, |data, size| unsafe {
+        let offset = (first * format.vertex_size()) as isize;
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferSubData(gl::ARRAY_BUFFER, offset, size, data);
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+    });
+}
+
+/// Replaces the storage of the element buffer `vao` reads from with `indices`
+fn upload_indices(vao: u32, indices: &[u32]) {
+    unsafe {
+        gl::BindVertexArray(vao);
+        gl::BufferData(
+            gl::ELEMENT_ARRAY_BUFFER,
+            std::mem::size_of_val(indices) as isize,
+            indices.as_ptr() as *const std::ffi::c_void,
+            gl::STATIC_DRAW,
+        );
+        gl::BindVertexArray(0);
+    }
+}
+
+/// Overwrites the indices of the element buffer `vao` reads from starting at
+/// `first`, which has to leave room for all of them
+fn write_indices(vao: u32, first: usize, indices: &[u32]) {
+    unsafe {
+        gl::BindVertexArray(vao);
+        gl::BufferSubData(
+            gl::ELEMENT_ARRAY_BUFFER,
+            (first * std::mem::size_of::<u32>()) as isize,
+            std::mem::size_of_val(indices) as isize,
+            indices.as_ptr() as *const std::ffi::c_void,
+        );
+        gl::BindVertexArray(0);
     }
 }
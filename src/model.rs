@@ -1,27 +1,137 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Instant,
+};
+
+use log::{error, info, warn};
+
 use crate::{
-    aabb, importer,
-    mesh::{apply_rotation, Mesh},
+    aabb,
+    debug_draw::DebugDraw,
+    file_info, gl_state,
+    history::TransformSnapshot,
+    hull, importer, mass_properties, measure,
+    mesh::{apply_rotation, Mesh, MeshGeometry},
     shader::Shader,
+    texture, topology,
     ui::ui,
     utils,
 };
 
 const SCALING_FACTOR: f32 = 8.0;
+/// Normals are only drawn for every n-th vertex on models with more vertices than this
+const MAX_NORMAL_LINES: usize = 100_000;
+
+/// Memory budget options for the loaded models, in megabytes
+pub const MEMORY_BUDGETS: [Option<u32>; 6] = [
+    None,
+    Some(512),
+    Some(1024),
+    Some(2048),
+    Some(4096),
+    Some(8192),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScaleMode {
+    /// Scaled so the largest side of the AABB is `SCALING_FACTOR` units long
+    Normalized,
+    /// Drawn with the units from the file
+    Original,
+    Custom(f32),
+}
+
+/// A note pinned to a point on the model's surface
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    /// Point the note is attached to, in the model's own coordinates
+    pub position: glm::Vec3,
+    pub text: String,
+}
+
+/// The meshes of a model that share a material
+#[derive(Debug)]
+pub struct MaterialUsage {
+    pub name: String,
+    pub meshes: Vec<usize>,
+    pub triangles: usize,
+}
 
 #[derive(Debug)]
 pub struct Model {
     pub id: u32,
     pub name: String,
+    /// Each mesh's own transform comes on top of the model's
     pub meshes: Vec<Mesh>,
     pub aabb: aabb::AABB,
+    pub position: glm::Vec3,
+    /// Degrees around each axis, turning around the pivot
+    pub rotation: glm::Vec3,
+    pub scale: glm::Vec3,
+    /// The scale the model is drawn with, depends on `scale_mode`
     pub scaling_factor: f32,
+    /// The scale that fits the model into the viewer, calculated on import
+    pub normalization_factor: f32,
+    pub scale_mode: ScaleMode,
     pub mem_usage: usize,
+    /// Point the model rotates around, in the model's own coordinates.
+    /// Defaults to the center of the AABB when not set.
+    pub custom_pivot: Option<glm::Vec3>,
+    pub visible: bool,
+    /// File the model was imported from, used for reloading it
+    pub source_path: Option<PathBuf>,
+    /// Options the model was built from `source_path` with if it's a heightmap
+    pub heightmap: Option<importer::heightmap::Options>,
+    /// Indices of the objects of `source_path` the model was built from, all
+    /// of them if `None`
+    pub parts: Option<HashSet<usize>>,
+    /// What was done to the model after reading it, done again on reloads
+    pub import_options: importer::options::ImportOptions,
+    /// Whether the textures are currently restricted to their lower mip levels
+    pub low_res_textures: bool,
+    pub annotations: Vec<Annotation>,
+    pub measurements: Vec<measure::Measurement>,
+    /// Whether the geometry and textures were freed to stay within the memory
+    /// budget, they're loaded back in from `source_path` once it's selected
+    pub evicted: bool,
+    /// Last time the model was selected, the least recently used ones are evicted first
+    pub last_used: Instant,
+    /// Result of the last topology analysis, overlaid on the model until the
+    /// geometry changes
+    pub topology: Option<topology::Analysis>,
+    /// Convex hull and oriented box of the geometry, overlaid on the model
+    /// until the geometry changes
+    pub bounds: Option<hull::Bounds>,
+    bounds_job: Option<hull::Job>,
+    /// Area and volume of each mesh, computed in the background when they're
+    /// first shown and again once the geometry changes
+    pub mass_properties: Option<Vec<mass_properties::MeshProperties>>,
+    mass_properties_job: Option<mass_properties::Job>,
+    /// Center of mass and principal axes, overlaid on the model until the
+    /// geometry changes
+    pub balance: Option<mass_properties::Balance>,
+    /// Units the coordinates are in, `None` when they're unknown
+    pub units: Option<importer::stl::Units>,
+    /// Face colors of a binary STL, they can't be reinterpreted anymore once
+    /// the geometry changes
+    pub face_colors: Option<importer::stl::FaceColors>,
+    /// Textures still being decoded, they're drawn as a checkerboard until
+    /// they arrive
+    pub pending_textures: Option<texture::DecodeJob>,
+    /// Id of the group the model is listed under in the Objects window
+    pub group: Option<u32>,
+    /// Size, date and checksum of `source_path`, read when asked for
+    pub file_info: Option<file_info::FileInfo>,
+    /// MTL files whose textures were skipped for a quick preview, they're read
+    /// once the textures are asked for
+    pub skipped_textures: Option<Vec<PathBuf>>,
+    /// Pose the ghost is drawn at while ghosting is on
+    pub ghost: Option<TransformSnapshot>,
 }
 
 impl Model {
-    pub fn new(obj: importer::Object, state: &mut ui::State) -> Model {
-        let mut meshes = Vec::new();
-
+    pub fn new(mut obj: importer::Object, state: &mut ui::State) -> Model {
         let scale_factor_x = SCALING_FACTOR / (obj.aabb.max.x - obj.aabb.min.x);
         let scale_factor_y = SCALING_FACTOR / (obj.aabb.max.y - obj.aabb.min.y);
         let scale_factor_z = SCALING_FACTOR / (obj.aabb.max.z - obj.aabb.min.z);
@@ -29,22 +139,53 @@ impl Model {
         // Use the minimum scaling factor to maintain proportions
         let scale = scale_factor_x.min(scale_factor_y).min(scale_factor_z);
 
-        for mesh in obj.meshes.into_iter() {
-            meshes.push(Mesh::new(
-                &mesh.name,
-                mesh.vertices,
-                mesh.indices,
-                mesh.material,
-            ));
-        }
+        let textures = obj
+            .meshes
+            .iter_mut()
+            .flat_map(|mesh| &mut mesh.materials)
+            .filter_map(|(_, material)| material.as_mut())
+            .flat_map(|material| &mut material.textures);
+        let pending_textures =
+            start_texture_decoding(textures, &obj.texture_files, &state.settings);
+
+        let meshes = Mesh::new_batch(obj.meshes, state.settings.vertex_format());
 
         let mut model = Model {
             id: state.get_next_id(),
             name: obj.name.to_owned(),
             aabb: obj.aabb,
+            position: glm::vec3(0.0, 0.0, 0.0),
+            rotation: glm::vec3(0.0, 0.0, 0.0),
+            scale: glm::vec3(1.0, 1.0, 1.0),
             scaling_factor: scale,
+            normalization_factor: scale,
+            scale_mode: ScaleMode::Normalized,
             meshes,
             mem_usage: 0,
+            custom_pivot: None,
+            visible: true,
+            source_path: None,
+            heightmap: None,
+            parts: None,
+            import_options: importer::options::ImportOptions::default(),
+            low_res_textures: false,
+            annotations: vec![],
+            measurements: vec![],
+            evicted: false,
+            group: None,
+            file_info: None,
+            last_used: Instant::now(),
+            topology: None,
+            bounds: None,
+            bounds_job: None,
+            mass_properties: None,
+            mass_properties_job: None,
+            balance: None,
+            units: obj.units,
+            face_colors: obj.colors,
+            pending_textures,
+            skipped_textures: None,
+            ghost: None,
         };
 
         model.set_mem_usage();
@@ -52,63 +193,698 @@ impl Model {
         model
     }
 
-    pub fn draw(&self, shader: &Shader, draw_aabb: bool, show_textures: bool) {
-        let center_x = ((self.aabb.max.x / 2.0) + (self.aabb.min.x / 2.0)) * self.scaling_factor;
-        let center_y = ((self.aabb.max.y / 2.0) + (self.aabb.min.y / 2.0)) * self.scaling_factor;
-        let center_z = ((self.aabb.max.z / 2.0) + (self.aabb.min.z / 2.0)) * self.scaling_factor;
-        let pivot = glm::vec3(center_x, center_y, center_z);
+    /// Center of the AABB in the model's own coordinates
+    pub fn center(&self) -> glm::Vec3 {
+        let center_x = (self.aabb.max.x / 2.0) + (self.aabb.min.x / 2.0);
+        let center_y = (self.aabb.max.y / 2.0) + (self.aabb.min.y / 2.0);
+        let center_z = (self.aabb.max.z / 2.0) + (self.aabb.min.z / 2.0);
 
-        let model_mat = glm::ext::scale(
-            &utils::mat_ident(),
-            glm::vec3(
-                self.scaling_factor,
-                self.scaling_factor,
-                self.scaling_factor,
-            ),
-        );
-        let model_mat = apply_rotation(&model_mat, self.meshes[0].rotation, pivot);
-        let model_mat = glm::ext::translate(
-            &model_mat,
-            glm::vec3(
-                self.meshes[0].position.x,
-                self.meshes[0].position.y,
-                self.meshes[0].position.z,
+        glm::vec3(center_x, center_y, center_z)
+    }
+
+    /// The pivot with the model's transform applied, but without the normalization scale
+    fn local_pivot(&self) -> glm::Vec3 {
+        let pivot = self.custom_pivot.unwrap_or_else(|| self.center()) + self.position;
+
+        pivot * self.scale
+    }
+
+    /// The point a mesh rotates around within the model, the model's pivot
+    /// with the mesh's position and scale applied
+    fn mesh_pivot(&self, idx: usize) -> glm::Vec3 {
+        let mesh = &self.meshes[idx];
+        let pivot = self.custom_pivot.unwrap_or_else(|| self.center()) + mesh.position;
+
+        pivot * mesh.scale
+    }
+
+    /// The model's transform without the normalization scale
+    fn local_transform(&self) -> glm::Mat4 {
+        let mat = glm::ext::scale(&utils::mat_ident(), self.scale);
+        let mat = glm::ext::translate(&mat, self.position);
+
+        apply_rotation(&mat, self.rotation, self.local_pivot())
+    }
+
+    /// Transform of a mesh without the normalization scale, the model's
+    /// transform on top of the mesh's own. It's the same one
+    /// `apply_transform` bakes into the vertices.
+    pub fn mesh_transform(&self, idx: usize) -> glm::Mat4 {
+        self.local_transform() * self.meshes[idx].local_transform(self.mesh_pivot(idx))
+    }
+
+    /// The point the meshes are drawn around, in world units
+    pub fn pivot(&self) -> glm::Vec3 {
+        self.local_pivot() * self.scaling_factor
+    }
+
+    /// The model matrix a mesh is drawn, picked and exported with
+    pub fn mesh_model_matrix(&self, idx: usize) -> glm::Mat4 {
+        self.normalization() * self.mesh_transform(idx)
+    }
+
+    /// The model matrix of the whole model, from its own coordinates into the
+    /// world. The meshes' own transforms come before it.
+    pub fn transform(&self) -> glm::Mat4 {
+        self.normalization() * self.local_transform()
+    }
+
+    fn normalization(&self) -> glm::Mat4 {
+        let factor = self.scaling_factor;
+        glm::ext::scale(&utils::mat_ident(), glm::vec3(factor, factor, factor))
+    }
+
+    /// The box around the meshes in the model's own coordinates, which is
+    /// `aabb` unless meshes were moved, rotated or scaled on their own
+    pub fn local_aabb(&self) -> aabb::AABB {
+        if !self.meshes.iter().any(|m| m.has_local_transform()) {
+            return self.aabb;
+        }
+
+        let mut min = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            let Some(bounds) = mesh.bounds() else {
+                continue;
+            };
+            let transform = mesh.local_transform(self.mesh_pivot(i));
+            for corner in bounds.corners() {
+                let p = transform * glm::vec4(corner.x, corner.y, corner.z, 1.0);
+                let p = glm::vec3(p.x / p.w, p.y / p.w, p.z / p.w);
+                min = glm::min(min, p);
+                max = glm::max(max, p);
+            }
+        }
+
+        if min.x > max.x {
+            return self.aabb;
+        }
+        aabb::AABB::new(min, max)
+    }
+
+    /// The world space box around a mesh as it's drawn, None if it has no
+    /// vertices
+    pub fn mesh_world_aabb(&self, idx: usize) -> Option<aabb::AABB> {
+        self.meshes[idx].world_bounds(&self.mesh_model_matrix(idx))
+    }
+
+    /// The world space box around the model as it's drawn. The boxes of the
+    /// meshes are kept until they move, so it's cheap to ask for every frame.
+    pub fn world_aabb(&self) -> aabb::AABB {
+        (0..self.meshes.len())
+            .filter_map(|i| self.mesh_world_aabb(i))
+            .reduce(|a, b| a.union(&b))
+            // evicted models don't have their vertices around
+            .unwrap_or_else(|| self.aabb.transformed(&self.transform()))
+    }
+
+    /// Converts a point in world space into the model's own coordinate space
+    pub fn world_to_local(&self, point: glm::Vec3) -> glm::Vec3 {
+        let local = glm::inverse(&self.transform()) * glm::vec4(point.x, point.y, point.z, 1.0);
+
+        glm::vec3(local.x / local.w, local.y / local.w, local.z / local.w)
+    }
+
+    /// Converts a point in the model's own coordinate space into world space
+    pub fn local_to_world(&self, point: glm::Vec3) -> glm::Vec3 {
+        let world = self.transform() * glm::vec4(point.x, point.y, point.z, 1.0);
+
+        glm::vec3(world.x / world.w, world.y / world.w, world.z / world.w)
+    }
+
+    /// Draws the visible meshes, the ones with `highlighted_material` are tinted
+    pub fn draw(&self, shader: &Shader, show_textures: bool, highlighted_material: Option<&str>) {
+        if self.evicted {
+            return;
+        }
+        for (i, mesh) in self.meshes.iter().enumerate().filter(|(_, m)| m.visible) {
+            mesh.draw(
+                shader,
+                &self.mesh_model_matrix(i),
+                show_textures,
+                highlighted_material,
+            );
+        }
+        shader.set_bool("highlighted", false);
+    }
+
+    /// Draws the model see-through at the pose of its ghost, so it shows how
+    /// far the model was moved, rotated or scaled since. The first call pins
+    /// the ghost to the current pose.
+    pub fn draw_ghost(&mut self, shader: &Shader, show_textures: bool) {
+        let current = TransformSnapshot::capture(self);
+        let Some(ghost) = self.ghost.clone() else {
+            self.ghost = Some(current);
+            return;
+        };
+        if ghost == current {
+            return;
+        }
+
+        ghost.restore(self);
+        shader.set_bool("ghost", true);
+        self.draw(shader, show_textures, None);
+        shader.set_bool("ghost", false);
+        current.restore(self);
+    }
+
+    /// Draws the visible meshes in a context other than the main window's,
+    /// see `Mesh::draw_shared`
+    pub fn draw_shared(&self, shader: &Shader, show_textures: bool) {
+        if self.evicted {
+            return;
+        }
+        for (i, mesh) in self.meshes.iter().enumerate().filter(|(_, m)| m.visible) {
+            mesh.draw_shared(shader, &self.mesh_model_matrix(i), show_textures);
+        }
+    }
+
+    /// The materials of the meshes in the order they first appear, with the
+    /// number of triangles drawn with each one
+    pub fn material_usage(&self) -> Vec<MaterialUsage> {
+        let mut usage: Vec<MaterialUsage> = vec![];
+
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            for range in &mesh.materials {
+                let triangles = range.count / 3;
+                match usage.iter_mut().find(|u| u.name == range.material.name) {
+                    Some(u) => {
+                        if !u.meshes.contains(&i) {
+                            u.meshes.push(i);
+                        }
+                        u.triangles += triangles;
+                    }
+                    None => usage.push(MaterialUsage {
+                        name: range.material.name.clone(),
+                        meshes: vec![i],
+                        triangles,
+                    }),
+                }
+            }
+        }
+
+        usage
+    }
+
+    /// Size of the AABB in the file's units, followed by the units if known
+    pub fn size_label(&self) -> String {
+        let size = self.aabb.max - self.aabb.min;
+        let label = format!("{:.2} x {:.2} x {:.2}", size.x, size.y, size.z);
+        match self.units {
+            Some(units) => format!("{} {}", label, units.abbreviation()),
+            None => label,
+        }
+    }
+
+    /// Adds the world space bounding box with a label of its size in the
+    /// file's units, and dimmer ones around each visible mesh when there's
+    /// more than one
+    pub fn draw_aabb(&self, debug: &mut DebugDraw) {
+        let ident = utils::mat_ident();
+        if self.meshes.len() > 1 {
+            for (i, _) in self.meshes.iter().enumerate().filter(|(_, m)| m.visible) {
+                if let Some(aabb) = self.mesh_world_aabb(i) {
+                    debug.aabb(&aabb, &ident, glm::vec3(0.5, 0.314, 0.078));
+                }
+            }
+        }
+
+        let aabb = self.world_aabb();
+        debug.aabb(&aabb, &ident, glm::vec3(1.0, 0.627, 0.157));
+        debug.text(aabb.max, self.size_label());
+    }
+
+    /// Adds a short line along the normal of the vertices
+    pub fn draw_normals(&self, debug: &mut DebugDraw) {
+        let size = self.world_size();
+        let length = size.x.max(size.y).max(size.z) * 0.02;
+        let vertex_count: usize = self.meshes.iter().map(|m| m.vertices.len()).sum();
+        let step = (vertex_count / MAX_NORMAL_LINES).max(1);
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            let transform = self.mesh_model_matrix(i);
+            if !glm::ext::is_invertible(&transform) {
+                continue;
+            }
+            let normal_mat = glm::transpose(&glm::inverse(&transform));
+
+            for vertex in mesh.vertices.iter().step_by(step) {
+                let (p, n) = (vertex.position, vertex.normal);
+                let p = transform * glm::vec4(p.x, p.y, p.z, 1.0);
+                let n = normal_mat * glm::vec4(n.x, n.y, n.z, 0.0);
+                let n = glm::vec3(n.x, n.y, n.z);
+                if glm::length(n) == 0.0 {
+                    continue;
+                }
+
+                let p = glm::vec3(p.x / p.w, p.y / p.w, p.z / p.w);
+                debug.line(p, p + glm::normalize(n) * length, glm::vec3(0.2, 0.8, 1.0));
+            }
+        }
+    }
+
+    pub fn rotate(&mut self, xoffset: f32, yoffset: f32) -> &mut Self {
+        self.rotation = self.rotation + glm::vec3(-yoffset, xoffset, 0.0);
+        self
+    }
+
+    pub fn set_position(&mut self, position: glm::Vec3) -> &mut Self {
+        self.position = position;
+        self
+    }
+
+    /// Moves the model so the center of its AABB sits at the origin
+    pub fn center_at_origin(&mut self) -> &mut Self {
+        let center = self.center();
+        self.set_position(-center)
+    }
+
+    /// Moves the model vertically so the bottom of its AABB rests on the grid plane
+    pub fn drop_to_grid(&mut self) -> &mut Self {
+        self.position.y = -self.local_aabb().min.y;
+        self
+    }
+
+    /// Size of the AABB in world units, ignoring rotation
+    pub fn world_size(&self) -> glm::Vec3 {
+        let aabb = self.local_aabb();
+        (aabb.max - aabb.min) * self.scale * self.scaling_factor
+    }
+
+    /// Moves the model so the center of its AABB is above `x`, `z` (in world
+    /// units) and its bottom rests on the grid plane
+    pub fn place_on_grid(&mut self, x: f32, z: f32) -> &mut Self {
+        let scale = self.scale * self.scaling_factor;
+        let center = self.center();
+
+        self.set_position(glm::vec3(
+            x / scale.x - center.x,
+            -self.local_aabb().min.y,
+            z / scale.z - center.z,
+        ))
+    }
+
+    pub fn set_scale_mode(&mut self, mode: ScaleMode) -> &mut Self {
+        self.scale_mode = mode;
+        self.scaling_factor = match mode {
+            ScaleMode::Normalized => self.normalization_factor,
+            ScaleMode::Original => 1.0,
+            ScaleMode::Custom(factor) => factor,
+        };
+        self
+    }
+
+    /// Swaps in the geometry of a freshly imported copy of this model while
+    /// keeping its current transform. The old GPU buffers are freed once
+    /// `new` is dropped.
+    pub fn replace_geometry(&mut self, mut new: Model) -> &mut Self {
+        // the meshes the file gained stay where the model puts them
+        for (mesh, old) in new.meshes.iter_mut().zip(&self.meshes) {
+            mesh.position = old.position;
+            mesh.rotation = old.rotation;
+            mesh.scale = old.scale;
+            mesh.overrides = old.overrides;
+        }
+
+        std::mem::swap(&mut self.meshes, &mut new.meshes);
+        std::mem::swap(&mut self.aabb, &mut new.aabb);
+        // the new textures start out at full resolution
+        self.low_res_textures = false;
+        self.evicted = false;
+        self.topology = None;
+        self.clear_bounds();
+        self.balance = None;
+        self.clear_mass_properties();
+        self.file_info = None;
+        // keep the colors the way they were picked before reloading
+        if let (Some(old), Some(colors)) = (&self.face_colors, &mut new.face_colors) {
+            if old.format != colors.format {
+                colors.set_format(old.format, &mut self.meshes[0]);
+            }
+        }
+        self.face_colors = new.face_colors;
+        self.pending_textures = new.pending_textures.take();
+        self.skipped_textures = new.skipped_textures.take();
+        self.normalization_factor = new.normalization_factor;
+        self.mem_usage = new.mem_usage;
+
+        self.set_scale_mode(self.scale_mode)
+    }
+
+    /// Adds the textures that were skipped on import to the materials and
+    /// starts decoding them
+    pub fn load_skipped_textures(&mut self, settings: &ui::Settings) {
+        let Some(material_libs) = self.skipped_textures.take() else {
+            return;
+        };
+        let mut texture_files = HashMap::new();
+        let material_textures =
+            importer::load_material_textures(&material_libs, &mut texture_files);
+
+        let textures = self
+            .meshes
+            .iter_mut()
+            .flat_map(|mesh| &mut mesh.materials)
+            .filter_map(|range| {
+                let textures = material_textures.get(&range.material.name)?;
+                range.material.textures = textures.clone();
+                Some(&mut range.material.textures)
+            })
+            .flatten();
+        self.pending_textures = start_texture_decoding(textures, &texture_files, settings);
+        self.set_mem_usage();
+    }
+
+    /// Starts computing the area and volume of the meshes unless they're
+    /// already known or being computed
+    pub fn request_mass_properties(&mut self) {
+        if self.mass_properties.is_none() && self.mass_properties_job.is_none() && !self.evicted {
+            self.mass_properties_job = Some(mass_properties::Job::spawn(&self.meshes));
+        }
+    }
+
+    /// Picks up the area and volume once they're computed
+    pub fn poll_mass_properties(&mut self) {
+        if let Some(properties) = self.mass_properties_job.as_ref().and_then(|job| job.poll()) {
+            self.mass_properties = Some(properties);
+            self.mass_properties_job = None;
+        }
+    }
+
+    /// Starts computing the convex hull and oriented box in the background,
+    /// unless they're being computed already
+    pub fn request_bounds(&mut self) {
+        if self.bounds_job.is_none() && !self.evicted {
+            self.bounds_job = Some(hull::Job::spawn(&self.meshes));
+        }
+    }
+
+    pub fn computing_bounds(&self) -> bool {
+        self.bounds_job.is_some()
+    }
+
+    /// Picks up the convex hull and oriented box once they're computed
+    pub fn poll_bounds(&mut self) {
+        let Some(bounds) = self.bounds_job.as_ref().and_then(|job| job.poll()) else {
+            return;
+        };
+        match &bounds {
+            Some(bounds) => info!(
+                "Computed the convex hull of {}: {} points, {} triangles",
+                self.name,
+                bounds.points.len(),
+                bounds.triangles.len()
             ),
-        );
+            None => warn!("{} is flat, it has no convex hull", self.name),
+        }
+        self.bounds = bounds;
+        self.bounds_job = None;
+    }
 
+    /// Drops the hull and box of the old geometry along with a computation
+    /// that's still running for it
+    fn clear_bounds(&mut self) {
+        self.bounds = None;
+        self.bounds_job = None;
+    }
+
+    /// Drops the area and volume of the old geometry along with a computation
+    /// that's still running for it
+    fn clear_mass_properties(&mut self) {
+        self.mass_properties = None;
+        self.mass_properties_job = None;
+    }
+
+    /// Uploads the textures that finished decoding since the last call.
+    /// Returns the names of the ones that couldn't be loaded.
+    pub fn poll_textures(&mut self, sampling: texture::Sampling) -> Vec<String> {
+        let Some(job) = &mut self.pending_textures else {
+            return vec![];
+        };
+        let decoded = job.poll();
+        if job.is_done() {
+            self.pending_textures = None;
+        }
+        self.apply_textures(decoded, sampling)
+    }
+
+    /// Waits for the rest of the textures to be decoded and uploads them
+    pub fn finish_textures(&mut self, sampling: texture::Sampling) {
+        if let Some(mut job) = self.pending_textures.take() {
+            let decoded = job.wait();
+            self.apply_textures(decoded, sampling);
+        }
+    }
+
+    /// Replaces the placeholders with the decoded images. Textures that
+    /// couldn't be decoded are taken off their materials, their names are
+    /// returned.
+    fn apply_textures(
+        &mut self,
+        decoded: Vec<texture::Decoded>,
+        sampling: texture::Sampling,
+    ) -> Vec<String> {
+        let mut failed = vec![];
+        for (name, image) in decoded {
+            let Some(id) = self
+                .meshes
+                .iter()
+                .flat_map(|mesh| mesh.textures())
+                .find(|tex| tex.name == name)
+                .map(|tex| tex.id)
+                .filter(|id| *id != 0)
+            else {
+                continue;
+            };
+
+            let uploaded = image.and_then(|image| {
+                utils::replace_texture_image(id, &image, sampling).map_err(|e| e.to_string())
+            });
+            match uploaded {
+                Ok(()) => {
+                    if self.low_res_textures {
+                        texture::set_low_res(id, true);
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to load texture: {}", e);
+                    gl_state::delete_textures(&[id]);
+                    for range in self.meshes.iter_mut().flat_map(|m| &mut m.materials) {
+                        range.material.textures.retain(|tex| tex.name != name);
+                    }
+                    failed.push(name);
+                }
+            }
+        }
+        failed
+    }
+
+    pub fn set_texture_sampling(&self, sampling: texture::Sampling) {
         for mesh in &self.meshes {
-            mesh.draw(shader, self.scaling_factor, pivot, show_textures);
+            for tex in mesh.textures() {
+                if tex.id != 0 {
+                    texture::apply_sampling(tex.id, sampling);
+                }
+            }
         }
+    }
 
-        if draw_aabb {
-            self.aabb.draw(shader, &model_mat);
+    pub fn set_low_res_textures(&mut self, low_res: bool) -> &mut Self {
+        if self.low_res_textures == low_res {
+            return self;
         }
+
+        for mesh in &self.meshes {
+            for tex in mesh.textures() {
+                if tex.id != 0 {
+                    texture::set_low_res(tex.id, low_res);
+                }
+            }
+        }
+        self.low_res_textures = low_res;
+        self
     }
 
-    pub fn rotate(&mut self, xoffset: f32, yoffset: f32) -> &mut Self {
-        let rotation = glm::vec3(-yoffset, xoffset, 0.0);
+    /// Frees the geometry and textures while keeping the transform, notes and
+    /// everything else. `replace_geometry` brings them back after a reload.
+    pub fn evict(&mut self) -> &mut Self {
+        self.release_textures();
+        for mesh in &mut self.meshes {
+            mesh.unload();
+        }
+
+        self.evicted = true;
+        self.topology = None;
+        self.clear_bounds();
+        self.balance = None;
+        self.set_mem_usage();
+        self
+    }
+
+    /// Deletes the textures and takes them off the materials, along with the
+    /// decoding of the ones that aren't done yet
+    fn release_textures(&mut self) {
+        let mut texture_ids: Vec<u32> = self
+            .meshes
+            .iter()
+            .flat_map(|mesh| mesh.textures().map(|tex| tex.id))
+            .filter(|id| *id != 0)
+            .collect();
+        // meshes share the textures of the model
+        texture_ids.sort_unstable();
+        texture_ids.dedup();
+        gl_state::delete_textures(&texture_ids);
+
+        for mesh in &mut self.meshes {
+            for tex in mesh.textures_mut() {
+                tex.id = 0;
+            }
+        }
+        self.pending_textures = None;
+        self.low_res_textures = false;
+    }
+
+    /// Frees the GPU resources, of a context that was lost while it's still
+    /// the current one or of a model that was removed and only kept for undo.
+    /// Models with a file are evicted to be read again once they're selected,
+    /// the others keep their geometry for `restore_gpu` and lose their textures.
+    pub fn release_gpu(&mut self) {
+        if self.evicted {
+            return;
+        }
+        if self.source_path.is_some() {
+            self.evict();
+            return;
+        }
+        self.release_textures();
         for mesh in &mut self.meshes {
-            mesh.rotate(rotation);
+            mesh.release_buffers();
+        }
+    }
+
+    /// Uploads the geometry `release_gpu` kept into the current context
+    pub fn restore_gpu(&mut self) {
+        if self.evicted {
+            return;
         }
+        for mesh in &mut self.meshes {
+            mesh.restore_buffers();
+        }
+    }
+
+    pub fn set_pivot(&mut self, pivot: Option<glm::Vec3>) -> &mut Self {
+        self.custom_pivot = pivot;
         self
     }
 
+    /// Resets the rotation of the model and of each of its meshes
     pub fn reset_rotation(&mut self) -> &mut Self {
+        self.rotation = glm::vec3(0.0, 0.0, 0.0);
         for mesh in &mut self.meshes {
             mesh.reset_rotation();
         }
         self
     }
 
+    pub fn reset_position(&mut self) -> &mut Self {
+        self.position = glm::vec3(0.0, 0.0, 0.0);
+        for mesh in &mut self.meshes {
+            mesh.reset_position();
+        }
+        self
+    }
+
+    pub fn reset_scale(&mut self) -> &mut Self {
+        self.scale = glm::vec3(1.0, 1.0, 1.0);
+        for mesh in &mut self.meshes {
+            mesh.reset_scale();
+        }
+        self
+    }
+
+    /// Bakes the current transform of every mesh into its vertex data and
+    /// recalculates the AABB. The model is drawn exactly as before, but the
+    /// geometry (and therefore any export) now has the transform applied.
+    pub fn apply_transform(&mut self) -> &mut Self {
+        // there's no geometry to bake the transform into
+        if self.evicted {
+            return self;
+        }
+
+        let pivot = self.local_pivot();
+        let transforms: Vec<glm::Mat4> = (0..self.meshes.len())
+            .map(|i| self.mesh_transform(i))
+            .collect();
+        self.position = glm::vec3(0.0, 0.0, 0.0);
+        self.rotation = glm::vec3(0.0, 0.0, 0.0);
+        self.scale = glm::vec3(1.0, 1.0, 1.0);
+
+        let mut min = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
+
+        for (mesh, transform) in self.meshes.iter_mut().zip(transforms) {
+            mesh.bake_transform(&transform);
+
+            for vertex in &mesh.vertices {
+                let p = vertex.position;
+                min = glm::vec3(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+                max = glm::vec3(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+            }
+        }
+
+        // the pivot doesn't move during the rotation, so it ends up at the same spot on the geometry
+        if self.custom_pivot.is_some() {
+            self.custom_pivot = Some(pivot);
+        }
+
+        self.aabb = aabb::AABB::new(min, max);
+        // the overlay's edges are where the vertices were before
+        self.topology = None;
+        self.clear_bounds();
+        self.balance = None;
+
+        self
+    }
+
+    /// Replaces the vertices and indices of every mesh, `geometry` has an
+    /// entry for each mesh in order
+    pub fn set_geometry(&mut self, geometry: Vec<MeshGeometry>) -> &mut Self {
+        // an evicted model has no buffers to upload into
+        if self.evicted {
+            return self;
+        }
+
+        let mut min = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
+        for (mesh, geometry) in self.meshes.iter_mut().zip(geometry) {
+            for vertex in &geometry.vertices {
+                let p = vertex.position;
+                min = glm::vec3(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+                max = glm::vec3(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+            }
+            mesh.set_geometry(geometry);
+        }
+
+        if min.x <= max.x {
+            self.aabb = aabb::AABB::new(min, max);
+        }
+        self.topology = None;
+        self.clear_bounds();
+        self.balance = None;
+        self.clear_mass_properties();
+        self.face_colors = None;
+        self.set_mem_usage();
+        self
+    }
+
     fn set_mem_usage(&mut self) {
         let mut size: usize = 0;
 
         size += std::mem::size_of_val(self);
         for mesh in &self.meshes {
             size += std::mem::size_of_val(mesh);
-            size += std::mem::size_of::<importer::Material>();
+            size += std::mem::size_of::<importer::Material>() * mesh.materials.len();
 
-            for texture in &mesh.material.textures {
+            for texture in mesh.textures() {
                 size += std::mem::size_of_val(texture);
             }
             for vertex in &mesh.vertices {
@@ -122,3 +898,43 @@ impl Model {
         self.mem_usage = size;
     }
 }
+
+/// Gives each texture a checkerboard to be drawn with until the workers have
+/// decoded its image, textures with the same name share one. Textures whose
+/// file isn't known are left without an image.
+fn start_texture_decoding<'a>(
+    textures: impl Iterator<Item = &'a mut importer::Texture>,
+    texture_files: &HashMap<String, PathBuf>,
+    settings: &ui::Settings,
+) -> Option<texture::DecodeJob> {
+    let sampling = settings.texture_sampling();
+    let placeholder = texture::placeholder();
+    let mut texture_ids: HashMap<String, u32> = HashMap::new();
+    let mut sources = Vec::new();
+    for texture in textures {
+        texture.path = texture_files.get(&texture.name).cloned();
+        if let Some(id) = texture_ids.get(&texture.name) {
+            texture.id = *id;
+            continue;
+        }
+        let source = if texture.name.starts_with("data:") {
+            texture::Source::DataUri(texture.name.clone())
+        } else if let Some(path) = &texture.path {
+            texture::Source::File(path.clone())
+        } else {
+            texture.id = 0;
+            continue;
+        };
+        let id = utils::upload_texture(&placeholder, sampling).unwrap_or_else(|e| {
+            error!("Failed to upload the placeholder texture: {}", e);
+            0
+        });
+        texture.id = id;
+        texture_ids.insert(texture.name.clone(), id);
+        sources.push((texture.name.clone(), source));
+    }
+
+    (!sources.is_empty()).then(|| {
+        texture::DecodeJob::spawn(sources, settings.texture_threads, settings.max_texture_size)
+    })
+}
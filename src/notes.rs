@@ -0,0 +1,139 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+const NOTES_FILE: &str = "notes.ron";
+
+/// Free-form notes and tags kept for a model file
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Note {
+    pub text: String,
+    pub tags: Vec<String>,
+}
+
+impl Note {
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && self.tags.is_empty()
+    }
+
+    /// The tags as they're typed in, separated by commas
+    pub fn tags_label(&self) -> String {
+        self.tags.join(", ")
+    }
+}
+
+/// Splits tags typed in separated by commas, lowercased and without repeats
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+    for tag in input.split(',').map(|t| t.trim().to_lowercase()) {
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Notes for model files, stored next to the settings so local collections
+/// can be annotated and searched without touching the files themselves
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Notes {
+    entries: BTreeMap<PathBuf, Note>,
+}
+
+/// The same file is found under the same key however it was opened
+fn key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl Notes {
+    /// Reads the notes from the config directory, none if they can't be read
+    pub fn load() -> Self {
+        let Some(dir) = config::dir() else {
+            return Self::default();
+        };
+        confy::load_path(dir.join(NOTES_FILE)).unwrap_or_else(|e| {
+            error!("Failed to load the model notes: {}", e);
+            Self::default()
+        })
+    }
+
+    fn store(&self) {
+        let Some(dir) = config::dir() else {
+            return;
+        };
+        if let Err(e) = confy::store_path(dir.join(NOTES_FILE), self) {
+            error!("Failed to save the model notes: {}", e);
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&Note> {
+        self.entries.get(&key(path))
+    }
+
+    /// Replaces the note of a file and saves them all, empty notes are
+    /// removed
+    pub fn set(&mut self, path: &Path, note: Note) {
+        if note.is_empty() {
+            self.entries.remove(&key(path));
+        } else {
+            self.entries.insert(key(path), note);
+        }
+        self.store();
+    }
+
+    /// Whether every word of `query` is in the file name or the notes and
+    /// tags of the file
+    pub fn matches(&self, path: &Path, query: &str) -> bool {
+        let name = path
+            .file_name()
+            .map_or(String::new(), |n| n.to_string_lossy().to_lowercase());
+        let note = self.get(path);
+        let text = note.map_or(String::new(), |n| n.text.to_lowercase());
+
+        query.split_whitespace().all(|word| {
+            let word = word.to_lowercase();
+            name.contains(&word)
+                || text.contains(&word)
+                || note.is_some_and(|n| n.tags.iter().any(|tag| tag.contains(&word)))
+        })
+    }
+
+    /// The files that have notes
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.entries.keys().cloned().collect()
+    }
+}
+
+/// A note in the editor, the tags are kept as typed until it's saved
+#[derive(Debug)]
+pub struct NoteEdit {
+    pub path: PathBuf,
+    pub text: String,
+    pub tags: String,
+}
+
+impl NoteEdit {
+    pub fn new(path: &Path, notes: &Notes) -> Self {
+        let note = notes.get(path).cloned().unwrap_or_default();
+        NoteEdit {
+            path: path.to_path_buf(),
+            tags: note.tags_label(),
+            text: note.text,
+        }
+    }
+
+    pub fn note(&self) -> Note {
+        Note {
+            text: self.text.trim_end().to_string(),
+            tags: parse_tags(&self.tags),
+        }
+    }
+}
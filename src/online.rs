@@ -0,0 +1,267 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use log::{error, info, warn};
+use serde_json::Value;
+
+use crate::{
+    gl_state,
+    importer::{self, download, registry},
+    pacing,
+    texture::{self, TextureFilter},
+    ui::ui::State,
+    utils,
+};
+
+const API_URL: &str = "https://api.thingiverse.com";
+const RESULTS_PER_PAGE: u32 = 20;
+const THUMBNAIL_SIZE: u32 = 128;
+
+/// A model on Thingiverse
+pub struct Thing {
+    pub id: u64,
+    pub name: String,
+    pub creator: String,
+    /// Decoded on the search thread, uploaded once it reaches the main thread.
+    /// Kept to upload it again if the GL context is lost.
+    thumbnail: Option<image::DynamicImage>,
+    /// GL texture of the thumbnail, 0 if there is none
+    pub thumbnail_id: u32,
+}
+
+impl Drop for Thing {
+    fn drop(&mut self) {
+        if self.thumbnail_id != 0 {
+            gl_state::delete_textures(&[self.thumbnail_id]);
+        }
+    }
+}
+
+/// A file of a thing that can be imported
+struct ThingFile {
+    name: String,
+    download_url: String,
+}
+
+/// Search results and the requests that are still running
+#[derive(Default)]
+pub struct Browser {
+    pub query: String,
+    pub results: Vec<Thing>,
+    pub error: Option<String>,
+    search: Option<Receiver<Result<Vec<Thing>, String>>>,
+    /// Things whose file list is being fetched before their model is downloaded
+    resolving: Vec<(u64, Receiver<Result<ThingFile, String>>)>,
+}
+
+impl Browser {
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Whether a search or a file list is being fetched
+    pub fn is_busy(&self) -> bool {
+        self.search.is_some() || !self.resolving.is_empty()
+    }
+
+    pub fn is_resolving(&self, thing_id: u64) -> bool {
+        self.resolving.iter().any(|(id, _)| *id == thing_id)
+    }
+
+    /// Deletes the thumbnails' textures, for when the context they were made
+    /// in is lost
+    pub fn release_thumbnails(&mut self) {
+        for thing in &mut self.results {
+            if thing.thumbnail_id != 0 {
+                gl_state::delete_textures(&[thing.thumbnail_id]);
+                thing.thumbnail_id = 0;
+            }
+        }
+    }
+
+    /// Uploads the thumbnails that don't have a texture yet
+    pub fn upload_thumbnails(&mut self) {
+        for thing in &mut self.results {
+            if let (0, Some(thumbnail)) = (thing.thumbnail_id, &thing.thumbnail) {
+                let sampling = texture::Sampling {
+                    filter: TextureFilter::Bilinear,
+                    anisotropy: 1.0,
+                };
+                match utils::upload_texture(thumbnail, sampling) {
+                    Ok(id) => thing.thumbnail_id = id,
+                    Err(e) => {
+                        warn!("Failed to show the thumbnail of {}: {}", thing.name, e);
+                        // not tried again every frame
+                        thing.thumbnail = None;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn search(&mut self, token: &str) {
+        let (tx, rx) = mpsc::channel();
+        let query = self.query.trim().to_string();
+        let token = token.to_string();
+
+        info!("Searching Thingiverse for \"{}\"", query);
+        let _ = std::thread::Builder::new()
+            .name("thingiverse search".to_string())
+            .spawn(move || {
+                let _ = tx.send(search(&query, &token));
+                pacing::request_redraw();
+            });
+
+        self.error = None;
+        self.search = Some(rx);
+    }
+
+    /// Looks up the first file of the thing that can be imported, the
+    /// download starts once it's found
+    pub fn import(&mut self, thing_id: u64, token: &str) {
+        let (tx, rx) = mpsc::channel();
+        let token = token.to_string();
+
+        let _ = std::thread::Builder::new()
+            .name("thingiverse files".to_string())
+            .spawn(move || {
+                let _ = tx.send(model_file(thing_id, &token));
+                pacing::request_redraw();
+            });
+
+        self.resolving.push((thing_id, rx));
+    }
+}
+
+/// Percent-encodes everything but the unreserved characters
+fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// serde_json gives up on responses nested deeper than 128 levels, so a
+/// hostile one can't overflow the stack
+fn api_get(path: &str, token: &str) -> Result<Value, String> {
+    let body = download::get(&format!("{}{}", API_URL, path), Some(token))?;
+    serde_json::from_slice(&body)
+        .map_err(|e| format!("Unexpected response from Thingiverse: {}", e))
+}
+
+fn search(query: &str, token: &str) -> Result<Vec<Thing>, String> {
+    let response = api_get(
+        &format!(
+            "/search/{}/?type=things&per_page={}",
+            encode(query),
+            RESULTS_PER_PAGE
+        ),
+        token,
+    )?;
+    let hits = response
+        .get("hits")
+        .and_then(|h| h.as_array())
+        .ok_or("Unexpected response from Thingiverse")?;
+
+    let things = hits
+        .iter()
+        .filter_map(|hit| {
+            let thumbnail = hit
+                .get("thumbnail")
+                .and_then(|t| t.as_str())
+                .and_then(|url| download::get(url, None).ok())
+                .and_then(|bytes| image::load_from_memory(&bytes).ok())
+                .map(|image| image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE));
+
+            Some(Thing {
+                id: hit.get("id")?.as_u64()?,
+                name: hit.get("name")?.as_str()?.to_string(),
+                creator: hit
+                    .get("creator")
+                    .and_then(|c| c.get("name"))
+                    .and_then(|n| n.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                thumbnail,
+                thumbnail_id: 0,
+            })
+        })
+        .collect();
+
+    Ok(things)
+}
+
+fn model_file(thing_id: u64, token: &str) -> Result<ThingFile, String> {
+    let files = api_get(&format!("/things/{}/files", thing_id), token)?;
+
+    files
+        .as_array()
+        .ok_or("Unexpected response from Thingiverse")?
+        .iter()
+        .filter_map(|file| {
+            Some(ThingFile {
+                name: file.get("name")?.as_str()?.to_string(),
+                download_url: file.get("download_url")?.as_str()?.to_string(),
+            })
+        })
+        .find(|file| registry::is_supported(std::path::Path::new(&file.name)))
+        .ok_or_else(|| format!("The model has no {} files", registry::format_names()))
+}
+
+/// Picks up finished searches and starts the downloads of things whose files
+/// were found. Must be called from the main thread since it uploads the thumbnails.
+pub fn poll(state: &mut State) {
+    let browser = &mut state.online;
+
+    if let Some(rx) = &browser.search {
+        match rx.try_recv() {
+            Ok(Ok(things)) => {
+                browser.results = things;
+                browser.upload_thumbnails();
+                browser.search = None;
+            }
+            Ok(Err(e)) => {
+                error!("Thingiverse search failed: {}", e);
+                browser.error = Some(e);
+                browser.search = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => browser.search = None,
+        }
+    }
+
+    let mut i = 0;
+    while i < state.online.resolving.len() {
+        let (thing_id, rx) = &state.online.resolving[i];
+        let thing_id = *thing_id;
+        let file = match rx.try_recv() {
+            Ok(file) => file,
+            Err(TryRecvError::Empty) => {
+                i += 1;
+                continue;
+            }
+            Err(TryRecvError::Disconnected) => Err("Request thread exited".to_string()),
+        };
+        state.online.resolving.remove(i);
+
+        match file {
+            Ok(file) => {
+                let path = download::cache_dir()
+                    .join(format!("thingiverse-{}", thing_id))
+                    .join(&file.name);
+                info!("Downloading {} from Thingiverse", file.name);
+                let options = state.settings.import_options_for(&path);
+                state.import_jobs.push(importer::ImportJob::spawn_download(
+                    file.download_url,
+                    path,
+                    Some(state.settings.thingiverse_token.clone()),
+                    options,
+                ));
+            }
+            Err(e) => warn!("Can't import thing {}: {}", thing_id, e),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use crate::ui::ui::Settings;
+
+/// Frame rate caps offered in the settings, None for no cap
+pub const FPS_CAPS: [Option<u32>; 6] = [None, Some(30), Some(60), Some(120), Some(144), Some(240)];
+/// Seconds without any input before the main loop stops drawing, ImGui needs
+/// a few frames after the last input to settle
+const IDLE_DELAY: f64 = 1.0;
+
+static REDRAW_REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Whether GLFW is up to receive the empty events that wake the main loop
+static CAN_WAKE: AtomicBool = AtomicBool::new(false);
+
+/// Makes the main loop draw another frame even if it's idle. Safe to call
+/// from any thread, background work calls it once it has a result.
+pub fn request_redraw() {
+    REDRAW_REQUESTED.store(true, Ordering::Release);
+    if CAN_WAKE.load(Ordering::Acquire) {
+        // glfwPostEmptyEvent may be called from any thread while GLFW is initialized
+        unsafe {
+            glfw::ffi::glfwPostEmptyEvent();
+        }
+    }
+}
+
+/// Decides how long the main loop waits between frames. When idle it
+/// doesn't draw at all until there's input or a redraw request.
+pub struct FramePacer {
+    frame_start: f64,
+    last_input: f64,
+    /// The swap interval that was last set, None before the first frame
+    vsync: Option<bool>,
+    /// Keeps VSync off whatever the setting says, for `--no-vsync`
+    vsync_disabled: bool,
+}
+
+impl FramePacer {
+    /// Must only be created once GLFW is initialized
+    pub fn new() -> Self {
+        CAN_WAKE.store(true, Ordering::Release);
+        FramePacer {
+            frame_start: 0.0,
+            last_input: 0.0,
+            vsync: None,
+            vsync_disabled: false,
+        }
+    }
+
+    /// Sets the swap interval again on the next frame, for a new context
+    pub fn forget_swap_interval(&mut self) {
+        self.vsync = None;
+    }
+
+    /// Turns VSync off for the rest of the session
+    pub fn disable_vsync(&mut self) {
+        self.vsync_disabled = true;
+    }
+
+    /// Applies a changed VSync setting and starts timing the frame
+    pub fn begin_frame(&mut self, glfw: &mut glfw::Glfw, settings: &Settings) {
+        self.frame_start = glfw.get_time();
+
+        let vsync = settings.vsync && !self.vsync_disabled;
+        if self.vsync != Some(vsync) {
+            glfw.set_swap_interval(if vsync {
+                glfw::SwapInterval::Sync(1)
+            } else {
+                glfw::SwapInterval::None
+            });
+            self.vsync = Some(vsync);
+        }
+    }
+
+    pub fn input_received(&mut self, glfw: &glfw::Glfw) {
+        self.last_input = glfw.get_time();
+    }
+
+    /// Waits out the rest of the frame to stay under the FPS cap. In idle
+    /// mode, once there's been no input for a while and nothing is moving,
+    /// it waits for the next event instead.
+    pub fn end_frame(&mut self, glfw: &mut glfw::Glfw, settings: &Settings, animating: bool) {
+        let now = glfw.get_time();
+        let redraw = REDRAW_REQUESTED.swap(false, Ordering::AcqRel);
+        if settings.idle_mode && !animating && !redraw && now - self.last_input > IDLE_DELAY {
+            glfw.wait_events();
+            return;
+        }
+
+        let Some(fps) = settings.fps_cap else {
+            return;
+        };
+        let remaining = self.frame_start + 1.0 / fps as f64 - now;
+        if remaining > 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(remaining));
+        }
+    }
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for FramePacer {
+    fn drop(&mut self) {
+        CAN_WAKE.store(false, Ordering::Release);
+    }
+}
@@ -0,0 +1,205 @@
+use std::{collections::HashMap, f32::consts::PI, f32::consts::TAU};
+
+use log::info;
+
+use crate::{
+    aabb::AABB,
+    crash,
+    importer::{Material, ObjMesh, Object},
+    mesh::Vertex,
+    model,
+    ui::{i18n::tr, ui::State},
+};
+
+pub const DEFAULT_SEGMENTS: u32 = 32;
+pub const MAX_SEGMENTS: u32 = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    Cube,
+    Sphere,
+    Torus,
+    Plane,
+    Cylinder,
+}
+
+impl Primitive {
+    pub const ALL: [Primitive; 5] = [
+        Primitive::Cube,
+        Primitive::Sphere,
+        Primitive::Torus,
+        Primitive::Plane,
+        Primitive::Cylinder,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Primitive::Cube => tr("primitive.cube"),
+            Primitive::Sphere => tr("primitive.sphere"),
+            Primitive::Torus => tr("primitive.torus"),
+            Primitive::Plane => tr("primitive.plane"),
+            Primitive::Cylinder => tr("primitive.cylinder"),
+        }
+    }
+
+    /// Generates the mesh with `segments` subdivisions around its curves, or
+    /// along the sides of the flat ones
+    pub fn generate(&self, segments: u32) -> Object {
+        let n = segments.clamp(1, MAX_SEGMENTS);
+        let round = n.max(3);
+        let up = glm::vec3(0.0, 1.0, 0.0);
+
+        let (vertices, indices) = match self {
+            Primitive::Cube => {
+                // one face for each axis and direction
+                let faces = [
+                    glm::vec3(1.0, 0.0, 0.0),
+                    glm::vec3(-1.0, 0.0, 0.0),
+                    glm::vec3(0.0, 1.0, 0.0),
+                    glm::vec3(0.0, -1.0, 0.0),
+                    glm::vec3(0.0, 0.0, 1.0),
+                    glm::vec3(0.0, 0.0, -1.0),
+                ];
+                merge(faces.map(|normal| {
+                    let tangent = if normal.y.abs() > 0.0 {
+                        glm::vec3(1.0, 0.0, 0.0)
+                    } else {
+                        up
+                    };
+                    let bitangent = glm::cross(normal, tangent);
+                    surface(n, n, |u, v| {
+                        let p = normal * 0.5 + tangent * (u - 0.5) + bitangent * (v - 0.5);
+                        (p, normal)
+                    })
+                }))
+            }
+            Primitive::Sphere => surface(round, round / 2, |u, v| {
+                let (theta, phi) = (u * TAU, v * PI);
+                let normal = glm::vec3(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+                (normal * 0.5, normal)
+            }),
+            Primitive::Torus => surface(round, (round / 2).max(3), |u, v| {
+                let (theta, phi) = (u * TAU, v * TAU);
+                let normal = glm::vec3(phi.cos() * theta.cos(), phi.sin(), phi.cos() * theta.sin());
+                let center = glm::vec3(theta.cos(), 0.0, theta.sin()) * 0.5;
+                (center + normal * 0.2, normal)
+            }),
+            Primitive::Plane => surface(n, n, |u, v| (glm::vec3(u - 0.5, 0.0, v - 0.5), up)),
+            Primitive::Cylinder => {
+                let side = surface(round, 1, |u, v| {
+                    let normal = glm::vec3((u * TAU).cos(), 0.0, (u * TAU).sin());
+                    (normal * 0.5 + up * (v - 0.5), normal)
+                });
+                let caps = [1.0, -1.0].map(|y: f32| {
+                    surface(round, 1, move |u, v| {
+                        let (s, c) = (u * TAU).sin_cos();
+                        (glm::vec3(c * v * 0.5, y * 0.5, s * v * 0.5), up * y)
+                    })
+                });
+                let [top, bottom] = caps;
+                merge([side, top, bottom])
+            }
+        };
+
+        let mut min = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
+        for vertex in &vertices {
+            min = glm::min(min, vertex.position);
+            max = glm::max(max, vertex.position);
+        }
+
+        let name = self.name().to_string();
+        Object {
+            name: name.clone(),
+            meshes: vec![ObjMesh {
+                name,
+                vertices,
+                indices,
+                materials: vec![(0, Some(Material::default()))],
+            }],
+            aabb: AABB::new(min, max),
+            units: None,
+            colors: None,
+            material_libs: Vec::new(),
+            texture_files: HashMap::new(),
+        }
+    }
+}
+
+/// A grid of `columns` by `rows` quads bent into shape by `f`, which maps
+/// the UVs to a position and normal
+fn surface(
+    columns: u32,
+    rows: u32,
+    f: impl Fn(f32, f32) -> (glm::Vec3, glm::Vec3),
+) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = Vec::with_capacity(((columns + 1) * (rows + 1)) as usize);
+    for row in 0..=rows {
+        for column in 0..=columns {
+            let (u, v) = (column as f32 / columns as f32, row as f32 / rows as f32);
+            let (position, normal) = f(u, v);
+            vertices.push(Vertex::new(position, normal, glm::vec2(u, v)));
+        }
+    }
+
+    let mut indices = Vec::with_capacity((columns * rows * 6) as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            let a = row * (columns + 1) + column;
+            let b = a + 1;
+            let c = a + columns + 1;
+            let d = c + 1;
+            for triangle in [[a, b, c], [b, d, c]] {
+                let [p0, p1, p2] = triangle.map(|i| vertices[i as usize].position);
+                let face_normal = glm::cross(p1 - p0, p2 - p0);
+                // the quads at the poles of a sphere and the center of a
+                // disc have a corner collapsed into a point
+                if glm::length(face_normal) <= f32::EPSILON {
+                    continue;
+                }
+
+                // wind the triangle so it faces the same way as its normals
+                let normal = vertices[triangle[0] as usize].normal;
+                if glm::dot(face_normal, normal) >= 0.0 {
+                    indices.extend_from_slice(&triangle);
+                } else {
+                    indices.extend_from_slice(&[triangle[0], triangle[2], triangle[1]]);
+                }
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+fn merge<const N: usize>(parts: [(Vec<Vertex>, Vec<u32>); N]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    for (part_vertices, part_indices) in parts {
+        let offset = vertices.len() as u32;
+        vertices.extend(part_vertices);
+        indices.extend(part_indices.into_iter().map(|i| i + offset));
+    }
+
+    (vertices, indices)
+}
+
+/// Generates a primitive and adds it to the scene as the selected model
+pub fn add(state: &mut State, primitive: Primitive) {
+    let obj = primitive.generate(state.primitive_segments);
+    let mut m = model::Model::new(obj, state);
+    m.name = primitive.name().to_string();
+    info!(
+        "Added a {} with {} segments",
+        m.name, state.primitive_segments
+    );
+
+    state.selection = vec![m.id];
+    state.objects.push(m);
+    crash::set_loaded_models(state.objects.iter().map(|m| m.name.as_str()));
+    state.camera.focus_on_selected_model(
+        state.active_model(),
+        &state.objects,
+        state.viewport_aspect(),
+    );
+}
@@ -0,0 +1,247 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Command, ExitStatus, Stdio},
+    sync::mpsc::{self, SyncSender},
+    thread::JoinHandle,
+    time::Instant,
+};
+
+use log::{error, info};
+
+use crate::ui::{
+    self,
+    i18n::tr,
+    notifications::{Kind, Notification},
+    ui::State,
+};
+
+pub const DEFAULT_FPS: u32 = 30;
+/// Frames waiting to be written to ffmpeg before the viewport waits for it
+const QUEUED_FRAMES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Mp4,
+    WebM,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Mp4, Format::WebM];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Mp4 => "MP4",
+            Format::WebM => "WebM",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Mp4 => "mp4",
+            Format::WebM => "webm",
+        }
+    }
+
+    fn codec_args(&self) -> &'static [&'static str] {
+        match self {
+            Format::Mp4 => &["-c:v", "libx264", "-pix_fmt", "yuv420p"],
+            Format::WebM => &["-c:v", "libvpx-vp9", "-pix_fmt", "yuv420p", "-b:v", "0"],
+        }
+    }
+}
+
+/// Viewport frames being piped to an ffmpeg child process
+#[derive(Debug)]
+pub struct Recording {
+    pub path: PathBuf,
+    /// Size of the video, the frames are scaled to it if the viewport is
+    /// resized while recording
+    pub size: [u32; 2],
+    pub started: Instant,
+    /// Frames sent to ffmpeg so far
+    pub frames: u32,
+    sender: SyncSender<Vec<u8>>,
+    writer: JoinHandle<std::io::Result<ExitStatus>>,
+}
+
+/// Recording of the viewport and its options
+#[derive(Debug)]
+pub struct Recorder {
+    pub fps: u32,
+    /// Seconds to stop recording after, 0 records until stopped
+    pub duration: f32,
+    pub format: Format,
+    pub active: Option<Recording>,
+    /// Recordings ffmpeg is still encoding the last frames of
+    finishing: Vec<(PathBuf, JoinHandle<std::io::Result<ExitStatus>>)>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self {
+            fps: DEFAULT_FPS,
+            duration: 0.0,
+            format: Format::Mp4,
+            active: None,
+            finishing: vec![],
+        }
+    }
+}
+
+impl Recorder {
+    /// Starts ffmpeg for a video of `size` pixels, which is made even for the
+    /// encoders
+    pub fn start(&mut self, path: PathBuf, size: [f32; 2]) -> anyhow::Result<()> {
+        let size = [
+            (size[0].max(2.0) as u32) & !1,
+            (size[1].max(2.0) as u32) & !1,
+        ];
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut child = Command::new("ffmpeg")
+            .args([
+                "-y",
+                "-loglevel",
+                "error",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "rgba",
+                "-s",
+                &format!("{}x{}", size[0], size[1]),
+                "-framerate",
+                &self.fps.to_string(),
+                "-i",
+                "-",
+            ])
+            .args(self.format.codec_args())
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to run ffmpeg, is it installed? {}", e))?;
+        let mut stdin = child.stdin.take().expect("ffmpeg stdin to be piped");
+
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(QUEUED_FRAMES);
+        let writer = std::thread::Builder::new()
+            .name("video recorder".to_string())
+            .spawn(move || {
+                for frame in receiver {
+                    stdin.write_all(&frame)?;
+                }
+                // closing stdin tells ffmpeg the video is over
+                drop(stdin);
+                child.wait()
+            })?;
+
+        info!("Recording the viewport to {:?}", path);
+        self.active = Some(Recording {
+            path,
+            size,
+            started: Instant::now(),
+            frames: 0,
+            sender,
+            writer,
+        });
+        Ok(())
+    }
+
+    /// Stops the recording, ffmpeg finishes the video in the background
+    pub fn stop(&mut self) {
+        if let Some(recording) = self.active.take() {
+            info!(
+                "Stopped recording after {} frames, encoding {:?}",
+                recording.frames, recording.path
+            );
+            self.finishing.push((recording.path, recording.writer));
+        }
+    }
+
+    /// Whether the next viewport frame has to be sent, frames are repeated
+    /// or skipped to keep the video at its framerate
+    pub fn wants_frame(&self) -> bool {
+        self.active
+            .as_ref()
+            .is_some_and(|r| r.due_frames(self.fps) > 0)
+    }
+
+    /// Sends a frame of the viewport, top row first, scaling it to the size
+    /// of the video
+    pub fn push_frame(&mut self, frame: image::RgbaImage) {
+        let fps = self.fps;
+        let Some(recording) = &mut self.active else {
+            return;
+        };
+
+        let frame = if frame.dimensions() == (recording.size[0], recording.size[1]) {
+            frame
+        } else {
+            image::imageops::resize(
+                &frame,
+                recording.size[0],
+                recording.size[1],
+                image::imageops::FilterType::Triangle,
+            )
+        };
+        let frame = frame.into_raw();
+
+        // the writer is gone when ffmpeg stopped taking frames
+        let mut failed = false;
+        for _ in 0..recording.due_frames(fps) {
+            if recording.sender.send(frame.clone()).is_err() {
+                failed = true;
+                break;
+            }
+            recording.frames += 1;
+        }
+
+        let elapsed = recording.started.elapsed().as_secs_f32();
+        if failed || (self.duration > 0.0 && elapsed >= self.duration) {
+            self.stop();
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.active.is_some() || !self.finishing.is_empty()
+    }
+}
+
+impl Recording {
+    fn due_frames(&self, fps: u32) -> u32 {
+        let due = (self.started.elapsed().as_secs_f32() * fps as f32) as u32 + 1;
+        due.saturating_sub(self.frames)
+    }
+}
+
+/// Tells about the recordings ffmpeg is done with
+pub fn update(state: &mut State) {
+    let recorder = &mut state.recorder;
+    let (done, finishing) = recorder
+        .finishing
+        .drain(..)
+        .partition::<Vec<_>, _>(|(_, writer)| writer.is_finished());
+    recorder.finishing = finishing;
+
+    for (path, writer) in done {
+        let error = match writer.join() {
+            Ok(Ok(status)) if status.success() => {
+                info!("Saved the recording to {:?}", path);
+                let path = path.canonicalize().unwrap_or(path);
+                state.notify(ui::ui::saved_notification(
+                    tr("notification.recording_saved"),
+                    path,
+                ));
+                continue;
+            }
+            Ok(Ok(status)) => format!("ffmpeg exited with {}", status),
+            Ok(Err(e)) => format!("Failed to write the recording to ffmpeg: {}", e),
+            Err(_) => "The video recorder thread panicked".to_string(),
+        };
+        error!("{}", error);
+        state.notify(
+            Notification::new(Kind::Error, tr("notification.recording_failed")).with_text(error),
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use glad_gl::gl;
+
+use crate::{
+    gl_state,
+    mesh::{MaterialBatch, Mesh},
+    model::Model,
+    shader::Shader,
+};
+
+/// How much state the last frame's render queue had to change, for telling
+/// how well the meshes batch together
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderStats {
+    pub draw_calls: usize,
+    /// Times the uniforms of a material were set and its textures bound
+    pub material_changes: usize,
+    /// Times the transform of a mesh was set and its VAO bound
+    pub mesh_changes: usize,
+    /// Meshes left out for being out of view
+    pub culled: usize,
+}
+
+/// A mesh's triangles of one material
+struct Item<'a> {
+    model: &'a Model,
+    mesh: &'a Mesh,
+    /// The model matrix of the mesh
+    transform: glm::Mat4,
+    batch: MaterialBatch<'a>,
+    highlighted: bool,
+}
+
+impl<'a> Item<'a> {
+    /// Items that bind the same textures and set the same material end up
+    /// next to each other, and the ones of a mesh next to each other within
+    /// those
+    fn key(&self) -> (Vec<u32>, u32, &'a str, *const Mesh) {
+        let textures = self.batch.material.textures.iter().map(|t| t.id).collect();
+        (
+            textures,
+            self.model.id,
+            self.batch.material.name.as_str(),
+            self.mesh as *const Mesh,
+        )
+    }
+}
+
+/// The meshes drawn in a frame, sorted so the ones sharing textures and
+/// materials are drawn one after the other without setting those up again
+#[derive(Default)]
+pub struct RenderQueue<'a> {
+    items: Vec<Item<'a>>,
+    /// Meshes whose boxes are outside of it aren't queued
+    view_projection: Option<glm::Mat4>,
+    culled: usize,
+}
+
+impl<'a> RenderQueue<'a> {
+    /// A queue that skips the meshes `view_projection` doesn't see
+    pub fn culled(view_projection: glm::Mat4) -> Self {
+        Self {
+            view_projection: Some(view_projection),
+            ..Default::default()
+        }
+    }
+
+    /// Adds the visible meshes of `model`, tinting the triangles with
+    /// `highlighted_material`
+    pub fn push(&mut self, model: &'a Model, highlighted_material: Option<&str>) {
+        if model.evicted {
+            return;
+        }
+        for (i, mesh) in model.meshes.iter().enumerate().filter(|(_, m)| m.visible) {
+            let transform = model.mesh_model_matrix(i);
+            if let (Some(view_projection), Some(bounds)) =
+                (&self.view_projection, mesh.world_bounds(&transform))
+            {
+                if bounds.outside_view(view_projection) {
+                    self.culled += 1;
+                    continue;
+                }
+            }
+            for batch in mesh.material_batches() {
+                let highlighted = highlighted_material == Some(batch.material.name.as_str());
+                self.items.push(Item {
+                    model,
+                    mesh,
+                    transform,
+                    batch,
+                    highlighted,
+                });
+            }
+        }
+    }
+
+    /// Draws everything in the queue with the polygon mode that's set
+    pub fn draw(mut self, shader: &Shader, show_textures: bool) -> RenderStats {
+        self.items.sort_by_cached_key(|item| item.key());
+        let mut stats = RenderStats {
+            culled: self.culled,
+            ..Default::default()
+        };
+
+        shader.use_shader();
+        let polygon_mode = gl_state::current_polygon_mode();
+
+        let mut is_wireframe = false;
+        let mut last_mesh: Option<*const Mesh> = None;
+        let mut last_material = None;
+        for item in &self.items {
+            let mesh_ptr = item.mesh as *const Mesh;
+            if last_mesh != Some(mesh_ptr) {
+                item.mesh.set_transform(shader, &item.transform);
+                is_wireframe = item.mesh.set_overrides(shader, polygon_mode);
+                item.mesh.bind();
+                last_mesh = Some(mesh_ptr);
+                stats.mesh_changes += 1;
+            }
+
+            // tints and wireframes change what a material sets
+            let material = (
+                item.model.id,
+                item.batch.material.name.as_str(),
+                item.highlighted,
+                item.mesh.overrides.tint.map(|t| [t.x, t.y, t.z]),
+                is_wireframe,
+            );
+            if last_material != Some(material) {
+                shader.set_bool("highlighted", item.highlighted);
+                item.mesh
+                    .set_material(shader, item.batch.material, is_wireframe, show_textures);
+                last_material = Some(material);
+                stats.material_changes += 1;
+            }
+
+            item.mesh.draw_batch(&item.batch);
+            stats.draw_calls += 1;
+        }
+
+        shader.set_bool("unlit", false);
+        shader.set_bool("highlighted", false);
+        // reset stuff to default
+        unsafe {
+            gl::BindVertexArray(0);
+        }
+        gl_state::active_texture(0);
+        gl_state::polygon_mode(polygon_mode);
+
+        stats
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::{HashMap, HashSet};
+
+use log::info;
+
+use crate::{
+    mesh::{MeshGeometry, Vertex},
+    model::Model,
+    topology::{self, face_normal, Analysis},
+    ui::i18n::tr,
+};
+
+/// Faces meeting at a sharper angle than this keep separate normals along
+/// their shared edge, so hard edges stay hard
+const CREASE_ANGLE: f32 = 60.0;
+/// Holes with more edges than this are left open, those are usually
+/// openings on purpose rather than missing triangles
+pub const MAX_HOLE_EDGES: usize = 32;
+
+/// The geometry of each mesh of a model, in order
+type Geometry = Vec<MeshGeometry>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repair {
+    RecomputeNormals,
+    UnifyWinding,
+    RemoveDegenerate,
+    FillHoles,
+}
+
+impl Repair {
+    pub const ALL: [Repair; 4] = [
+        Repair::RecomputeNormals,
+        Repair::UnifyWinding,
+        Repair::RemoveDegenerate,
+        Repair::FillHoles,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Repair::RecomputeNormals => tr("repair.recompute_normals"),
+            Repair::UnifyWinding => tr("repair.unify_winding"),
+            Repair::RemoveDegenerate => tr("repair.remove_degenerate"),
+            Repair::FillHoles => tr("repair.fill_holes"),
+        }
+    }
+
+    pub fn tooltip(&self) -> &'static str {
+        match self {
+            Repair::RecomputeNormals => tr("repair.recompute_normals.tooltip"),
+            Repair::UnifyWinding => tr("repair.unify_winding.tooltip"),
+            Repair::RemoveDegenerate => tr("repair.remove_degenerate.tooltip"),
+            Repair::FillHoles => tr("repair.fill_holes.tooltip"),
+        }
+    }
+}
+
+/// The counts of an analysis, without the edges and faces themselves
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stats {
+    pub triangles: usize,
+    pub boundary_edges: usize,
+    pub non_manifold_edges: usize,
+    pub flipped_faces: usize,
+    pub degenerate_faces: usize,
+}
+
+impl From<&Analysis> for Stats {
+    fn from(analysis: &Analysis) -> Self {
+        Self {
+            triangles: analysis.triangles,
+            boundary_edges: analysis.boundary_edges.len(),
+            non_manifold_edges: analysis.non_manifold_edges.len(),
+            flipped_faces: analysis.flipped_faces.len(),
+            degenerate_faces: analysis.degenerate_faces,
+        }
+    }
+}
+
+/// What a repair did to a model
+#[derive(Debug, Clone, Copy)]
+pub struct Report {
+    pub repair: Repair,
+    /// Vertices, faces or holes the repair touched, depending on the repair
+    pub changed: usize,
+    pub before: Stats,
+    pub after: Stats,
+}
+
+fn geometry(model: &Model) -> Geometry {
+    model
+        .meshes
+        .iter()
+        .map(|m| MeshGeometry::new(m.vertices.clone(), m.indices.clone(), &m.materials))
+        .collect()
+}
+
+/// Smooth normals from the area weighted normals of the faces around each
+/// position, split along creases. Returns the number of vertices.
+fn recompute_normals(model: &Model) -> (Geometry, usize) {
+    let meshes: Vec<_> = model
+        .meshes
+        .iter()
+        .map(|m| (m.vertices.as_slice(), m.indices.as_slice()))
+        .collect();
+    let geometry: Geometry = topology::smooth_normals(&meshes, CREASE_ANGLE)
+        .into_iter()
+        .zip(&model.meshes)
+        .map(|((vertices, indices), mesh)| MeshGeometry::new(vertices, indices, &mesh.materials))
+        .collect();
+
+    let changed = geometry.iter().map(|m| m.vertices.len()).sum();
+    (geometry, changed)
+}
+
+/// Flips the faces wound against the rest of their connected part. Returns
+/// the number of flipped faces.
+fn unify_winding(model: &Model) -> (Geometry, usize) {
+    let faces = topology::welded_faces(model);
+    let flipped = topology::inconsistent_faces(&faces);
+
+    let mut geometry = geometry(model);
+    for &i in &flipped {
+        let (mesh, triangle) = faces[i].0;
+        geometry[mesh]
+            .indices
+            .swap(triangle * 3 + 1, triangle * 3 + 2);
+    }
+
+    (geometry, flipped.len())
+}
+
+/// Drops faces without an area and faces using the same positions as an
+/// earlier one. Returns the number of removed faces.
+fn remove_degenerate(model: &Model) -> (Geometry, usize) {
+    let faces = topology::welded_faces(model);
+    let mut seen: HashSet<[u32; 3]> = HashSet::new();
+    let mut kept: Vec<Vec<(usize, [u32; 3])>> = vec![vec![]; model.meshes.len()];
+    let mut removed = 0;
+
+    for ((mesh_idx, triangle), welded) in faces {
+        let mesh = &model.meshes[mesh_idx];
+        let indices = &mesh.indices[triangle * 3..triangle * 3 + 3];
+        let positions = [0, 1, 2].map(|i| mesh.vertices[indices[i] as usize].position);
+
+        // faces with distinct corners on a line have no area either
+        let Some(mut welded) = welded.filter(|_| glm::length(face_normal(positions)) > 0.0) else {
+            removed += 1;
+            continue;
+        };
+        welded.sort_unstable();
+        if !seen.insert(welded) {
+            removed += 1;
+            continue;
+        }
+
+        let material = mesh.material_index(triangle);
+        kept[mesh_idx].push((material, [indices[0], indices[1], indices[2]]));
+    }
+
+    let geometry = model
+        .meshes
+        .iter()
+        .zip(kept)
+        .map(|(mesh, triangles)| {
+            MeshGeometry::from_triangles(mesh.vertices.clone(), triangles, mesh.materials.len())
+        })
+        .collect();
+    (geometry, removed)
+}
+
+/// Closes the holes of up to `MAX_HOLE_EDGES` edges with a fan of triangles
+/// around their center. Returns the number of filled holes.
+fn fill_holes(model: &Model) -> (Geometry, usize) {
+    let faces = topology::welded_faces(model);
+    let mut positions: HashMap<u32, glm::Vec3> = HashMap::new();
+    for &((mesh_idx, triangle), welded) in &faces {
+        let Some(welded) = welded else {
+            continue;
+        };
+        let mesh = &model.meshes[mesh_idx];
+        for (i, id) in welded.into_iter().enumerate() {
+            positions
+                .entry(id)
+                .or_insert(mesh.vertices[mesh.indices[triangle * 3 + i] as usize].position);
+        }
+    }
+
+    // the hole goes around its boundary the other way than the faces next to
+    // it, so the new faces end up wound the same as their neighbors
+    let mut next: HashMap<u32, (u32, (usize, usize))> = HashMap::new();
+    let mut ambiguous: HashSet<u32> = HashSet::new();
+    for ((a, b), around) in topology::edge_faces(&faces) {
+        let [(face, ascending)] = around[..] else {
+            continue;
+        };
+        let (from, to) = if ascending { (b, a) } else { (a, b) };
+        if next.insert(from, (to, faces[face].0)).is_some() {
+            // more than one hole meets at this vertex, there's no telling
+            // which way to go around
+            ambiguous.insert(from);
+        }
+    }
+
+    let mut vertices: Vec<Vec<Vertex>> = model.meshes.iter().map(|m| m.vertices.clone()).collect();
+    let mut triangles: Vec<Vec<(usize, [u32; 3])>> = model
+        .meshes
+        .iter()
+        .map(|m| m.triangles_by_material())
+        .collect();
+    let mut visited: HashSet<u32> = HashSet::new();
+    let mut starts: Vec<u32> = next.keys().copied().collect();
+    starts.sort_unstable();
+    let mut filled = 0;
+
+    for start in starts {
+        if visited.contains(&start) {
+            continue;
+        }
+
+        let mut hole = vec![];
+        let mut current = start;
+        let mut closed = false;
+        while visited.insert(current) {
+            hole.push(current);
+            match next.get(&current) {
+                Some(&(to, _)) => current = to,
+                None => break,
+            }
+            if current == start {
+                closed = true;
+                break;
+            }
+        }
+
+        if !closed
+            || hole.len() < 3
+            || hole.len() > MAX_HOLE_EDGES
+            || hole.iter().any(|id| ambiguous.contains(id))
+        {
+            continue;
+        }
+
+        let points: Vec<glm::Vec3> = hole.iter().map(|id| positions[id]).collect();
+        let center = points
+            .iter()
+            .fold(glm::vec3(0.0, 0.0, 0.0), |sum, p| sum + *p)
+            / points.len() as f32;
+        let normal = (0..points.len())
+            .map(|i| face_normal([points[i], points[(i + 1) % points.len()], center]))
+            .fold(glm::vec3(0.0, 0.0, 0.0), |sum, n| sum + n);
+        let normal = if glm::length(normal) > 0.0 {
+            glm::normalize(normal)
+        } else {
+            glm::vec3(0.0, 1.0, 0.0)
+        };
+
+        // the fan goes into the mesh and material of the face the hole
+        // starts next to
+        let (mesh_idx, triangle) = next[&start].1;
+        let material = model.meshes[mesh_idx].material_index(triangle);
+        let (vertices, triangles) = (&mut vertices[mesh_idx], &mut triangles[mesh_idx]);
+        let base = vertices.len() as u32;
+        let uv = glm::vec2(0.0, 0.0);
+        vertices.push(Vertex::new(center, normal, uv));
+        vertices.extend(points.iter().map(|p| Vertex::new(*p, normal, uv)));
+        for i in 0..hole.len() as u32 {
+            triangles.push((
+                material,
+                [base + 1 + i, base + 1 + (i + 1) % hole.len() as u32, base],
+            ));
+        }
+        filled += 1;
+    }
+
+    let geometry = model
+        .meshes
+        .iter()
+        .zip(vertices.into_iter().zip(triangles))
+        .map(|(mesh, (vertices, triangles))| {
+            MeshGeometry::from_triangles(vertices, triangles, mesh.materials.len())
+        })
+        .collect();
+    (geometry, filled)
+}
+
+/// Runs a repair on the model's geometry and reports the analysis before
+/// and after it. The caller records the geometry for undo beforehand.
+pub fn run(model: &mut Model, repair: Repair) -> Report {
+    let before = Stats::from(&Analysis::new(model));
+    let (geometry, changed) = match repair {
+        Repair::RecomputeNormals => recompute_normals(model),
+        Repair::UnifyWinding => unify_winding(model),
+        Repair::RemoveDegenerate => remove_degenerate(model),
+        Repair::FillHoles => fill_holes(model),
+    };
+
+    let showing_analysis = model.topology.is_some();
+    model.set_geometry(geometry);
+    let analysis = Analysis::new(model);
+    let after = Stats::from(&analysis);
+    if showing_analysis {
+        model.topology = Some(analysis);
+    }
+
+    info!("Ran {:?} on {}, {} changed", repair, model.name, changed);
+
+    Report {
+        repair,
+        changed,
+        before,
+        after,
+    }
+}
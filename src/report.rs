@@ -0,0 +1,303 @@
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    importer::{self, registry},
+    model::{MaterialUsage, Model},
+    thumbnail, topology,
+    ui::{i18n::tr, ui::State},
+};
+
+/// Counts of the problems a topology analysis found
+pub struct Diagnostics {
+    pub boundary_edges: usize,
+    pub non_manifold_edges: usize,
+    pub flipped_faces: usize,
+    pub degenerate_faces: usize,
+}
+
+impl From<&topology::Analysis> for Diagnostics {
+    fn from(analysis: &topology::Analysis) -> Self {
+        Diagnostics {
+            boundary_edges: analysis.boundary_edges.len(),
+            non_manifold_edges: analysis.non_manifold_edges.len(),
+            flipped_faces: analysis.flipped_faces.len(),
+            degenerate_faces: analysis.degenerate_faces,
+        }
+    }
+}
+
+/// What the Objects window shows about a model, for copying it elsewhere
+pub struct Stats {
+    pub name: String,
+    pub file: Option<PathBuf>,
+    pub meshes: usize,
+    pub vertices: usize,
+    pub triangles: usize,
+    pub size: String,
+    pub materials: Vec<MaterialUsage>,
+    /// Only there once the topology was analyzed
+    pub diagnostics: Option<Diagnostics>,
+}
+
+impl Stats {
+    pub fn new(model: &Model) -> Self {
+        Stats {
+            name: model.name.clone(),
+            file: model.source_path.clone(),
+            meshes: model.meshes.len(),
+            vertices: model.meshes.iter().map(|m| m.vertices.len()).sum(),
+            triangles: model.meshes.iter().map(|m| m.indices.len() / 3).sum(),
+            size: model.size_label(),
+            materials: model.material_usage(),
+            diagnostics: model.topology.as_ref().map(Diagnostics::from),
+        }
+    }
+
+    /// Label and value of each line, shared by the text formats
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![];
+        if let Some(file) = &self.file {
+            rows.push((tr("report.file"), file.display().to_string()));
+        }
+        rows.push((tr("report.meshes"), self.meshes.to_string()));
+        rows.push((tr("objects.vertices"), self.vertices.to_string()));
+        rows.push((tr("objects.triangles"), self.triangles.to_string()));
+        rows.push((tr("objects.size"), self.size.clone()));
+        rows.push((tr("report.materials"), self.materials.len().to_string()));
+        if let Some(d) = &self.diagnostics {
+            rows.push((tr("topology.boundary_edges"), d.boundary_edges.to_string()));
+            rows.push((
+                tr("topology.non_manifold_edges"),
+                d.non_manifold_edges.to_string(),
+            ));
+            rows.push((tr("topology.flipped_faces"), d.flipped_faces.to_string()));
+            rows.push((
+                tr("topology.degenerate_faces"),
+                d.degenerate_faces.to_string(),
+            ));
+        }
+        rows
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{}\n", self.name);
+        for (label, value) in self.rows() {
+            let _ = writeln!(out, "{}: {}", label, value);
+        }
+        for material in &self.materials {
+            let _ = writeln!(
+                out,
+                "  {}: {} {}",
+                material.name,
+                material.triangles,
+                tr("objects.triangles").to_lowercase()
+            );
+        }
+        out
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("### {}\n\n| | |\n|---|---|\n", self.name);
+        for (label, value) in self.rows() {
+            let _ = writeln!(out, "| {} | {} |", label, markdown_escape(&value));
+        }
+        if !self.materials.is_empty() {
+            let _ = write!(
+                out,
+                "\n| {} | {} |\n|---|---|\n",
+                tr("report.material"),
+                tr("objects.triangles")
+            );
+            for material in &self.materials {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} |",
+                    markdown_escape(&material.name),
+                    material.triangles
+                );
+            }
+        }
+        out
+    }
+}
+
+fn markdown_escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Handles `--report <folder> <output>`, writing the stats of every model in
+/// the folder into an HTML or CSV file depending on the output's extension
+pub fn run(args: &[String]) -> Result<()> {
+    let [folder, output, ..] = args else {
+        bail!("Usage: --report <folder> <output.html|output.csv>");
+    };
+    let (folder, output) = (Path::new(folder), Path::new(output));
+    let csv = match output.extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("csv") => true,
+        Some(e) if e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm") => false,
+        _ => bail!("The report must be an .html or .csv file"),
+    };
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(folder)
+        .with_context(|| format!("Failed to read the folder {:?}", folder))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|file| file.is_file() && registry::is_supported(file))
+        .collect();
+    files.sort();
+
+    // the models are only analyzed, but they need a context for their buffers
+    let _context = thumbnail::offscreen_context("3dobs report")?;
+    let mut state = State::default();
+    let results: Vec<(PathBuf, Result<Stats, String>)> = files
+        .into_iter()
+        .map(|file| {
+            log::info!("Analyzing {:?}", file);
+            let stats = importer::load_from_file(&file, false)
+                .map(|obj| {
+                    let mut model = Model::new(obj, &mut state);
+                    model.topology = Some(topology::Analysis::new(&model));
+                    Stats::new(&model)
+                })
+                .map_err(|e| e.to_string());
+            (file, stats)
+        })
+        .collect();
+
+    let report = if csv {
+        csv_report(&results)
+    } else {
+        html_report(folder, &results)
+    };
+    std::fs::write(output, report)
+        .with_context(|| format!("Failed to write the report to {:?}", output))?;
+    log::info!(
+        "Wrote the report of {} file(s) to {:?}",
+        results.len(),
+        output
+    );
+
+    Ok(())
+}
+
+/// Columns of the batch reports after the file name
+fn columns() -> [&'static str; 9] {
+    [
+        tr("report.meshes"),
+        tr("objects.vertices"),
+        tr("objects.triangles"),
+        tr("objects.size"),
+        tr("report.materials"),
+        tr("topology.boundary_edges"),
+        tr("topology.non_manifold_edges"),
+        tr("topology.flipped_faces"),
+        tr("topology.degenerate_faces"),
+    ]
+}
+
+fn values(stats: &Stats) -> [String; 9] {
+    let d = stats.diagnostics.as_ref();
+    let count = |value: Option<usize>| value.map(|v| v.to_string()).unwrap_or_default();
+    [
+        stats.meshes.to_string(),
+        stats.vertices.to_string(),
+        stats.triangles.to_string(),
+        stats.size.clone(),
+        stats.materials.len().to_string(),
+        count(d.map(|d| d.boundary_edges)),
+        count(d.map(|d| d.non_manifold_edges)),
+        count(d.map(|d| d.flipped_faces)),
+        count(d.map(|d| d.degenerate_faces)),
+    ]
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn csv_report(results: &[(PathBuf, Result<Stats, String>)]) -> String {
+    let mut header = vec![tr("report.file"), tr("report.error")];
+    header.extend(columns());
+    let mut out = header
+        .iter()
+        .map(|c| csv_field(c))
+        .collect::<Vec<_>>()
+        .join(",");
+    out.push('\n');
+
+    for (file, stats) in results {
+        let mut row = vec![file_name(file)];
+        match stats {
+            Ok(stats) => {
+                row.push(String::new());
+                row.extend(values(stats));
+            }
+            Err(e) => row.push(e.clone()),
+        }
+        out += &row
+            .iter()
+            .map(|c| csv_field(c))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push('\n');
+    }
+    out
+}
+
+fn html_report(folder: &Path, results: &[(PathBuf, Result<Stats, String>)]) -> String {
+    let title = html_escape(&format!("{} {}", tr("report.title"), folder.display()));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>table {{ border-collapse: collapse; }} th, td {{ border: 1px solid #888; \
+         padding: 4px 8px; }} .error {{ color: #c00; }}</style>\n</head>\n<body>\n\
+         <h1>{title}</h1>\n<table>\n<tr><th>{}</th>",
+        html_escape(tr("report.file"))
+    );
+    for column in columns() {
+        let _ = write!(out, "<th>{}</th>", html_escape(column));
+    }
+    out += "</tr>\n";
+
+    for (file, stats) in results {
+        let _ = write!(out, "<tr><td>{}</td>", html_escape(&file_name(file)));
+        match stats {
+            Ok(stats) => {
+                for value in values(stats) {
+                    let _ = write!(out, "<td>{}</td>", html_escape(&value));
+                }
+            }
+            Err(e) => {
+                let _ = write!(
+                    out,
+                    "<td class=\"error\" colspan=\"{}\">{}</td>",
+                    columns().len(),
+                    html_escape(e)
+                );
+            }
+        }
+        out += "</tr>\n";
+    }
+    out += "</table>\n</body>\n</html>\n";
+    out
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use log::info;
+
+use crate::{config, importer, ui::ui::State};
+
+/// A freely licensed model to try the viewer with
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub name: &'static str,
+    pub url: &'static str,
+    pub license: &'static str,
+}
+
+/// At least one sample for each supported format
+pub const SAMPLES: [Sample; 3] = [
+    Sample {
+        name: "Spot (OBJ)",
+        url: "https://raw.githubusercontent.com/alecjacobson/common-3d-test-models/master/data/spot.obj",
+        license: "CC0, Keenan Crane",
+    },
+    Sample {
+        name: "Utah Teapot (OBJ)",
+        url: "https://raw.githubusercontent.com/alecjacobson/common-3d-test-models/master/data/teapot.obj",
+        license: "Public domain, Martin Newell",
+    },
+    Sample {
+        name: "Slotted Disk (STL)",
+        url: "https://raw.githubusercontent.com/mrdoob/three.js/master/examples/models/stl/ascii/slotted_disk.stl",
+        license: "MIT, three.js examples",
+    },
+];
+
+/// Samples are kept next to the settings so they're only downloaded once
+fn samples_dir() -> PathBuf {
+    config::dir()
+        .map(|dir| dir.join("samples"))
+        .unwrap_or_else(|| importer::download::cache_dir().join("samples"))
+}
+
+/// Imports every sample, downloading the ones that aren't cached yet
+pub fn load_all(state: &mut State) {
+    let dir = samples_dir();
+
+    for sample in SAMPLES {
+        let path = dir.join(importer::download::file_name(sample.url));
+        let options = state.settings.import_options_for(&path);
+        let job = if path.exists() {
+            info!("Loading the cached sample {}", sample.name);
+            importer::ImportJob::spawn(path, true, options)
+        } else {
+            info!("Downloading the sample {} from {}", sample.name, sample.url);
+            importer::ImportJob::spawn_download(sample.url.to_string(), path, None, options)
+        };
+        state.import_jobs.push(job);
+    }
+}
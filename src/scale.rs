@@ -0,0 +1,150 @@
+use crate::{debug_draw::Label, importer::stl::Units, ui::ui::State, utils};
+
+/// Longest the scale bar gets on screen in pixels, the grid labels are about
+/// as far apart
+const MAX_BAR_LENGTH: f32 = 150.0;
+/// Space between the scale bar and the bottom left corner of the viewport
+const BAR_MARGIN: f32 = 16.0;
+const TICK_HEIGHT: f32 = 6.0;
+/// Grid labels on each side of the camera along both axes
+const GRID_LABELS_PER_SIDE: i32 = 6;
+
+/// How lengths in world space read in the units of the active model's file
+pub struct UnitSystem {
+    /// File units in a world unit, undoing the scale the model is drawn at
+    units_per_world: f32,
+    pub units: Option<Units>,
+}
+
+impl UnitSystem {
+    /// Plain world units when there's no active model
+    pub fn of(state: &State) -> Self {
+        let model = state
+            .active_model()
+            .and_then(|id| state.objects.iter().find(|m| m.id == id));
+        match model {
+            Some(model) => UnitSystem {
+                units_per_world: 1.0 / (model.scale.x * model.scaling_factor),
+                units: model.units,
+            },
+            None => UnitSystem {
+                units_per_world: 1.0,
+                units: None,
+            },
+        }
+    }
+
+    /// `value` with as many decimals as `step` needs
+    pub fn format(&self, value: f32, step: f32) -> String {
+        let decimals = (-step.log10().floor()).max(0.0) as usize;
+        let value = format!("{:.*}", decimals, value);
+        match self.units {
+            Some(units) => format!("{} {}", value, units.abbreviation()),
+            None => value,
+        }
+    }
+}
+
+/// The largest 1, 2 or 5 times a power of ten that's no longer than `max`
+pub fn nice_length(max: f32) -> f32 {
+    let magnitude = 10_f32.powf(max.log10().floor());
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|length| *length <= max)
+        .unwrap_or(magnitude)
+}
+
+/// Size of a pixel in world space at the depth of the displayed models, or of
+/// the origin if there are none
+fn world_per_pixel(state: &State) -> Option<f32> {
+    let target = utils::displayed_bounds(state)
+        .map_or(glm::vec3(0.0, 0.0, 0.0), |(min, max)| (min + max) / 2.0);
+    let depth = glm::dot(target - state.camera.position, state.camera.front).max(state.camera.near);
+    let height = state.viewport_size[1];
+    if height <= 0.0 {
+        return None;
+    }
+
+    Some(2.0 * depth * (glm::radians(state.camera.fov) / 2.0).tan() / height)
+}
+
+/// The step between grid labels and the length of the scale bar, in world
+/// space and in the file's units
+fn step(state: &State, system: &UnitSystem) -> Option<(f32, f32)> {
+    let world_per_pixel = world_per_pixel(state)?;
+    let units = nice_length(MAX_BAR_LENGTH * world_per_pixel * system.units_per_world);
+    (units.is_finite() && units > 0.0).then_some((units / system.units_per_world, units))
+}
+
+/// A bar of a round length in the active model's units, for telling the size
+/// of what's shown in the viewport
+pub struct ScaleBar {
+    /// In pixels
+    pub length: f32,
+    pub label: String,
+}
+
+impl ScaleBar {
+    pub fn new(state: &State) -> Option<Self> {
+        let system = UnitSystem::of(state);
+        let (world, units) = step(state, &system)?;
+
+        Some(ScaleBar {
+            length: world / world_per_pixel(state)?,
+            label: system.format(units, units),
+        })
+    }
+
+    /// The bar and the ticks at its ends, in pixels from the top left corner
+    /// of a viewport that's `size` big
+    pub fn lines(&self, size: [f32; 2]) -> [[[f32; 2]; 2]; 3] {
+        let y = size[1] - BAR_MARGIN;
+        let (start, end) = (BAR_MARGIN, BAR_MARGIN + self.length);
+        [
+            [[start, y], [end, y]],
+            [[start, y], [start, y - TICK_HEIGHT]],
+            [[end, y], [end, y - TICK_HEIGHT]],
+        ]
+    }
+
+    /// Top left corner of the label, which sits above the middle of the bar
+    pub fn label_position(&self, size: [f32; 2], label_size: [f32; 2]) -> [f32; 2] {
+        [
+            BAR_MARGIN + (self.length - label_size[0]) / 2.0,
+            size[1] - BAR_MARGIN - TICK_HEIGHT - label_size[1],
+        ]
+    }
+}
+
+/// Distances along the X and Z axes of the grid, around the point the camera
+/// is above and as far apart as the scale bar is long
+pub fn grid_labels(state: &State) -> Vec<Label> {
+    let system = UnitSystem::of(state);
+    let Some((world, units)) = step(state, &system) else {
+        return vec![];
+    };
+
+    let mut labels = vec![Label {
+        position: glm::vec3(0.0, 0.0, 0.0),
+        text: system.format(0.0, units),
+    }];
+    let position = state.camera.position;
+    for (axis, offset) in [
+        (glm::vec3(1.0, 0.0, 0.0), position.x),
+        (glm::vec3(0.0, 0.0, 1.0), position.z),
+    ] {
+        // kept in a range the label indices can't overflow in
+        let nearest = (offset / world).round().clamp(-1e6, 1e6) as i32;
+        for i in nearest - GRID_LABELS_PER_SIDE..=nearest + GRID_LABELS_PER_SIDE {
+            if i != 0 {
+                labels.push(Label {
+                    position: axis * (i as f32 * world),
+                    text: system.format(i as f32 * units, units),
+                });
+            }
+        }
+    }
+
+    labels
+}
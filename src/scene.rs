@@ -0,0 +1,98 @@
+use crate::{
+    camera::Camera,
+    history::History,
+    model::Model,
+    ui::ui::{DisplayMode, State},
+};
+
+/// A named folder in the Objects window. Its models are kept next to each
+/// other in the object list.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub id: u32,
+    pub name: String,
+}
+
+/// A viewport tab with its own models, camera and view options. The active
+/// scene's contents live in the state like they would without tabs, the
+/// other scenes keep theirs here until they're switched to.
+pub struct Scene {
+    pub id: u32,
+    pub name: String,
+    objects: Vec<Model>,
+    groups: Vec<Group>,
+    selection: Vec<u32>,
+    camera: Camera,
+    history: History,
+    draw_grid: bool,
+    draw_aabb: bool,
+    wireframe: bool,
+    display_mode: DisplayMode,
+    show_textures: bool,
+    show_normals: bool,
+    show_annotations: bool,
+}
+
+impl Scene {
+    /// An empty scene with the same view options a new state starts with
+    pub fn new(id: u32, name: String) -> Self {
+        Scene {
+            id,
+            name,
+            objects: vec![],
+            groups: vec![],
+            selection: vec![],
+            camera: Camera::default(),
+            history: History::default(),
+            draw_grid: false,
+            draw_aabb: false,
+            wireframe: false,
+            display_mode: DisplayMode::Shaded,
+            show_textures: true,
+            show_normals: false,
+            show_annotations: true,
+        }
+    }
+
+    /// Frees the GPU resources of the scene's models, see
+    /// `Model::release_gpu`
+    pub fn release_gpu(&mut self) {
+        for model in &mut self.objects {
+            model.release_gpu();
+        }
+    }
+
+    pub fn restore_gpu(&mut self) {
+        for model in &mut self.objects {
+            model.restore_gpu();
+        }
+    }
+
+    /// Exchanges the scene's contents with the ones in the state, parking the
+    /// active scene or bringing this one in
+    pub fn swap(&mut self, state: &mut State) {
+        std::mem::swap(&mut self.objects, &mut state.objects);
+        std::mem::swap(&mut self.groups, &mut state.groups);
+        std::mem::swap(&mut self.selection, &mut state.selection);
+        std::mem::swap(&mut self.camera, &mut state.camera);
+        std::mem::swap(&mut self.history, &mut state.history);
+        std::mem::swap(&mut self.draw_grid, &mut state.draw_grid);
+        std::mem::swap(&mut self.draw_aabb, &mut state.draw_aabb);
+        std::mem::swap(&mut self.wireframe, &mut state.wireframe);
+        std::mem::swap(&mut self.display_mode, &mut state.display_mode);
+        std::mem::swap(&mut self.show_textures, &mut state.show_textures);
+        std::mem::swap(&mut self.show_normals, &mut state.show_normals);
+        std::mem::swap(&mut self.show_annotations, &mut state.show_annotations);
+
+        // these point at models of the scene that was active
+        state.selected_mesh = None;
+        state.renaming = None;
+        state.editing_annotation = None;
+        state.inspected_face = None;
+        state.hover = None;
+        state.measuring = None;
+        state.highlighted_material = None;
+        state.last_repair = None;
+        state.folder_browser = None;
+    }
+}
@@ -0,0 +1,627 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+};
+
+use anyhow::{bail, Context, Result};
+use log::{error, info};
+use rhai::{Dynamic, Engine, EvalAltResult, ImmutableString, Scope};
+
+use crate::{importer, pacing, ui::ui::State, utils};
+
+const HELP: &str = "\
+Scripts are Rhai (https://rhai.rs), with these functions for the scene:
+load(path_or_url)           import a model, the script waits until it's in
+select(\"all\"|n|name)        select all models, the n-th one or one by name
+models()                    names of the models in the Objects window
+meshes()                    the meshes of the selected models
+focus()                     move the camera to the active model
+camera(x, y, z)             move the camera
+look(x, y, z)               point the camera at a position
+fov(degrees)                set the field of view
+set(option, true|false)     toggle grid, aabb, wireframe, textures or notes
+capture() / capture(path)   save a capture of the viewport
+help()                      show this list
+e.g. for m in models() { select(m); focus(); capture(m + \".png\"); }";
+
+/// Requests from the script thread answered in one frame at most, so a
+/// script calling the scene in a tight loop doesn't hold up drawing
+const REQUESTS_PER_FRAME: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Toggle {
+    Grid,
+    Aabb,
+    Wireframe,
+    Textures,
+    Notes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selector {
+    All,
+    /// 1-based position in the Objects window
+    Index(usize),
+    Name(String),
+}
+
+/// What a script asks of the scene, run on the main thread
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Load(PathBuf),
+    Select(Selector),
+    Models,
+    Meshes,
+    Focus,
+    Camera(glm::Vec3),
+    Look(glm::Vec3),
+    Fov(f32),
+    Set(Toggle, bool),
+    Capture(Option<PathBuf>),
+}
+
+/// A mesh of a selected model as `meshes()` lists it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshInfo {
+    pub model: String,
+    pub name: String,
+    pub vertices: usize,
+    pub triangles: usize,
+}
+
+/// The answer to a command, errors are raised in the script
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reply {
+    Done,
+    Names(Vec<String>),
+    Meshes(Vec<MeshInfo>),
+}
+
+/// What the script thread sends to the main thread
+#[derive(Debug)]
+enum Message {
+    Print(String),
+    Request(Command, Sender<Result<Reply, String>>),
+    /// The script ended, with the error that stopped it if any
+    Finished(Option<String>),
+}
+
+/// The thread scripts run on, one after the other. Variables they set are
+/// kept for the ones after them, so the console can build on earlier lines.
+#[derive(Debug)]
+struct Runner {
+    scripts: Sender<String>,
+    messages: Receiver<Message>,
+    /// Ends the running script and the queued ones at their next step
+    stop: Arc<AtomicBool>,
+}
+
+impl Runner {
+    fn spawn() -> Self {
+        let (scripts_tx, scripts_rx) = mpsc::channel::<String>();
+        let (messages_tx, messages_rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_stop = stop.clone();
+        std::thread::spawn(move || {
+            let engine = engine(messages_tx.clone(), thread_stop.clone());
+            let mut scope = Scope::new();
+            for script in scripts_rx {
+                let error = if thread_stop.load(Ordering::Relaxed) {
+                    None
+                } else {
+                    match engine.eval_with_scope::<Dynamic>(&mut scope, &script) {
+                        Ok(value) if value.is_unit() => None,
+                        Ok(value) => {
+                            let _ = messages_tx.send(Message::Print(value.to_string()));
+                            None
+                        }
+                        Err(e) => Some(match *e {
+                            EvalAltResult::ErrorTerminated(..) => "The script was stopped".into(),
+                            e => e.to_string(),
+                        }),
+                    }
+                };
+                if messages_tx.send(Message::Finished(error)).is_err() {
+                    break;
+                }
+                pacing::request_redraw();
+            }
+        });
+
+        Runner {
+            scripts: scripts_tx,
+            messages: messages_rx,
+            stop,
+        }
+    }
+}
+
+/// Sends a command to the main thread and waits for its answer
+fn request(messages: &Sender<Message>, command: Command) -> Result<Reply, Box<EvalAltResult>> {
+    let (reply_tx, reply_rx) = mpsc::channel();
+    messages
+        .send(Message::Request(command, reply_tx))
+        .map_err(|_| "The app is closing")?;
+    pacing::request_redraw();
+    match reply_rx.recv() {
+        Ok(reply) => reply.map_err(Into::into),
+        Err(_) => Err("The app is closing".into()),
+    }
+}
+
+/// Rhai numbers are either integers or floats, both are taken anywhere a
+/// number is expected
+fn number(value: Dynamic) -> Result<f32, Box<EvalAltResult>> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|i| i as f64))
+        .map(|n| n as f32)
+        .map_err(|typ| format!("Expected a number, got {}", typ).into())
+}
+
+fn vec3(x: Dynamic, y: Dynamic, z: Dynamic) -> Result<glm::Vec3, Box<EvalAltResult>> {
+    Ok(glm::vec3(number(x)?, number(y)?, number(z)?))
+}
+
+/// An engine with the scene functions, they send their commands through
+/// `messages`
+fn engine(messages: Sender<Message>, stop: Arc<AtomicBool>) -> Engine {
+    let mut engine = Engine::new();
+
+    engine.on_progress(move |_| stop.load(Ordering::Relaxed).then_some(Dynamic::UNIT));
+    let tx = messages.clone();
+    engine.on_print(move |text| {
+        let _ = tx.send(Message::Print(text.to_string()));
+    });
+    let tx = messages.clone();
+    engine.on_debug(move |text, _, _| {
+        let _ = tx.send(Message::Print(text.to_string()));
+    });
+    let tx = messages.clone();
+    engine.register_fn("help", move || {
+        let _ = tx.send(Message::Print(HELP.to_string()));
+    });
+
+    // for the commands that answer with nothing
+    let runner = |messages: &Sender<Message>| {
+        let tx = messages.clone();
+        move |command: Command| request(&tx, command).map(|_| ())
+    };
+
+    let run = runner(&messages);
+    engine.register_fn("load", move |path: ImmutableString| {
+        run(Command::Load(PathBuf::from(path.as_str())))
+    });
+    let run = runner(&messages);
+    engine.register_fn("select", move |target: ImmutableString| {
+        run(Command::Select(match target.as_str() {
+            "all" => Selector::All,
+            name => Selector::Name(name.to_string()),
+        }))
+    });
+    let run = runner(&messages);
+    engine.register_fn("select", move |index: i64| {
+        let index = usize::try_from(index).map_err(|_| format!("No model {}", index))?;
+        run(Command::Select(Selector::Index(index)))
+    });
+    let run = runner(&messages);
+    engine.register_fn("focus", move || run(Command::Focus));
+    let run = runner(&messages);
+    engine.register_fn("camera", move |x: Dynamic, y: Dynamic, z: Dynamic| {
+        run(Command::Camera(vec3(x, y, z)?))
+    });
+    let run = runner(&messages);
+    engine.register_fn("look", move |x: Dynamic, y: Dynamic, z: Dynamic| {
+        run(Command::Look(vec3(x, y, z)?))
+    });
+    let run = runner(&messages);
+    engine.register_fn("fov", move |degrees: Dynamic| {
+        run(Command::Fov(number(degrees)?))
+    });
+    let run = runner(&messages);
+    engine.register_fn("set", move |option: ImmutableString, value: bool| {
+        let toggle = match option.as_str() {
+            "grid" => Toggle::Grid,
+            "aabb" => Toggle::Aabb,
+            "wireframe" => Toggle::Wireframe,
+            "textures" => Toggle::Textures,
+            "notes" => Toggle::Notes,
+            option => return Err(format!("Unknown option \"{}\"", option).into()),
+        };
+        run(Command::Set(toggle, value))
+    });
+    let run = runner(&messages);
+    engine.register_fn("capture", move || run(Command::Capture(None)));
+    let run = runner(&messages);
+    engine.register_fn("capture", move |path: ImmutableString| {
+        run(Command::Capture(Some(PathBuf::from(path.as_str()))))
+    });
+
+    let tx = messages.clone();
+    engine.register_fn(
+        "models",
+        move || -> Result<rhai::Array, Box<EvalAltResult>> {
+            let Reply::Names(names) = request(&tx, Command::Models)? else {
+                return Err("Unexpected reply to models()".into());
+            };
+            Ok(names.into_iter().map(Dynamic::from).collect())
+        },
+    );
+    let tx = messages;
+    engine.register_fn(
+        "meshes",
+        move || -> Result<rhai::Array, Box<EvalAltResult>> {
+            let Reply::Meshes(meshes) = request(&tx, Command::Meshes)? else {
+                return Err("Unexpected reply to meshes()".into());
+            };
+            Ok(meshes
+                .into_iter()
+                .map(|mesh| {
+                    let mut map = rhai::Map::new();
+                    map.insert("model".into(), mesh.model.into());
+                    map.insert("name".into(), mesh.name.into());
+                    map.insert("vertices".into(), (mesh.vertices as i64).into());
+                    map.insert("triangles".into(), (mesh.triangles as i64).into());
+                    map.into()
+                })
+                .collect())
+        },
+    );
+
+    engine
+}
+
+/// Scripts typed into the Script window or passed with `--script`, and what
+/// they printed
+#[derive(Debug, Default)]
+pub struct ScriptConsole {
+    pub input: String,
+    pub output: Vec<String>,
+    /// Started with the first script
+    runner: Option<Runner>,
+    /// Scripts sent to the runner that haven't finished
+    queued: usize,
+}
+
+impl ScriptConsole {
+    fn print(&mut self, line: String) {
+        info!("{}", line);
+        self.output.push(line);
+    }
+
+    /// Runs `script` once the ones before it are done
+    pub fn queue(&mut self, script: String) {
+        let runner = self.runner.get_or_insert_with(Runner::spawn);
+        if runner.scripts.send(script).is_ok() {
+            self.queued += 1;
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.queued > 0
+    }
+
+    /// Number of scripts running or waiting to
+    pub fn queued(&self) -> usize {
+        self.queued
+    }
+
+    /// Ends the running script and drops the queued ones
+    pub fn stop(&self) {
+        if let Some(runner) = &self.runner {
+            runner.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Reads a script file and queues it. Syntax errors are reported before any
+/// of it runs.
+pub fn queue_file(path: &PathBuf, state: &mut State) {
+    match std::fs::read_to_string(path) {
+        Ok(script) => {
+            info!("Running script {:?}", path);
+            state.script.queue(script);
+        }
+        Err(e) => error!("Failed to read script {:?}: {}", path, e),
+    }
+}
+
+/// Answers what the scripts ask of the scene. Must be called before the
+/// frame is drawn so camera changes show up in captures taken in the same
+/// frame.
+pub fn run_queued(state: &mut State) {
+    let mut answered = 0;
+    // commands after a load expect the model to be there
+    while state.import_jobs.is_empty()
+        && state.capture_request.is_none()
+        && answered < REQUESTS_PER_FRAME
+    {
+        let Some(runner) = &state.script.runner else {
+            return;
+        };
+        let message = match runner.messages.try_recv() {
+            Ok(message) => message,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                state.script.runner = None;
+                state.script.queued = 0;
+                return;
+            }
+        };
+
+        match message {
+            Message::Print(text) => state.script.print(text),
+            Message::Request(command, reply) => {
+                answered += 1;
+                let result = execute(command, state).map_err(|e| format!("{:#}", e));
+                let _ = reply.send(result);
+            }
+            Message::Finished(error) => {
+                state.script.queued -= 1;
+                if let Some(error) = error {
+                    error!("{}", error);
+                    state.script.output.push(error);
+                    // the queued scripts likely depend on the failed one
+                    state.script.stop();
+                }
+                if state.script.queued == 0 {
+                    runner.stop.store(false, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+fn execute(command: Command, state: &mut State) -> Result<Reply> {
+    match command {
+        Command::Load(path) => {
+            if let Some(url) = path.to_str().filter(|p| importer::download::is_url(p)) {
+                utils::import_model_from_url(url, state);
+                return Ok(Reply::Done);
+            }
+            let path = std::fs::canonicalize(&path)
+                .with_context(|| format!("Failed to find model {:?}", path))?;
+            utils::import_models_from_paths(&[path], state);
+        }
+        Command::Select(selector) => {
+            let ids: Vec<u32> = match &selector {
+                Selector::All => state.objects.iter().map(|m| m.id).collect(),
+                Selector::Index(index) => index
+                    .checked_sub(1)
+                    .and_then(|i| state.objects.get(i))
+                    .map(|m| m.id)
+                    .into_iter()
+                    .collect(),
+                Selector::Name(name) => state
+                    .objects
+                    .iter()
+                    .filter(|m| m.name == *name)
+                    .map(|m| m.id)
+                    .collect(),
+            };
+            if ids.is_empty() {
+                bail!("No model matches {:?}", selector);
+            }
+            state.selection = ids;
+        }
+        Command::Models => {
+            return Ok(Reply::Names(
+                state.objects.iter().map(|m| m.name.clone()).collect(),
+            ));
+        }
+        Command::Meshes => {
+            return Ok(Reply::Meshes(
+                state
+                    .selected_models()
+                    .flat_map(|model| {
+                        model.meshes.iter().map(|mesh| MeshInfo {
+                            model: model.name.clone(),
+                            name: mesh.name.clone(),
+                            vertices: mesh.vertices.len(),
+                            triangles: mesh.indices.len() / 3,
+                        })
+                    })
+                    .collect(),
+            ));
+        }
+        Command::Focus => {
+            state.camera.focus_on_selected_model(
+                state.active_model(),
+                &state.objects,
+                state.viewport_aspect(),
+            );
+        }
+        Command::Camera(position) => state.camera.position = position,
+        Command::Look(target) => {
+            state.camera.front = glm::normalize(target - state.camera.position);
+        }
+        Command::Fov(degrees) => state.camera.fov = degrees.clamp(0.5, 85.0),
+        Command::Set(toggle, value) => {
+            let option = match toggle {
+                Toggle::Grid => &mut state.draw_grid,
+                Toggle::Aabb => &mut state.draw_aabb,
+                Toggle::Wireframe => &mut state.wireframe,
+                Toggle::Textures => &mut state.show_textures,
+                Toggle::Notes => &mut state.show_annotations,
+            };
+            *option = value;
+        }
+        Command::Capture(path) => {
+            state.capture_request = Some(
+                path.unwrap_or_else(|| utils::capture_path(state, state.viewport_size, "png")),
+            );
+        }
+    }
+
+    Ok(Reply::Done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What a script asked for and printed, and the error that ended it
+    #[derive(Debug, Default)]
+    struct Run {
+        commands: Vec<Command>,
+        printed: Vec<String>,
+        error: Option<String>,
+    }
+
+    /// Runs the scripts one after the other on a runner, answering its
+    /// commands the way `answer` does instead of going to a scene
+    fn run_all(
+        scripts: &[&str],
+        mut answer: impl FnMut(&Command) -> Result<Reply, String>,
+    ) -> Vec<Run> {
+        let runner = Runner::spawn();
+        scripts
+            .iter()
+            .for_each(|s| runner.scripts.send(s.to_string()).unwrap());
+
+        let mut runs = vec![Run::default()];
+        while runs.len() <= scripts.len() {
+            let run = runs.last_mut().unwrap();
+            match runner.messages.recv().unwrap() {
+                Message::Print(text) => run.printed.push(text),
+                Message::Request(command, reply) => {
+                    reply.send(answer(&command)).unwrap();
+                    run.commands.push(command);
+                }
+                Message::Finished(error) => {
+                    run.error = error;
+                    runs.push(Run::default());
+                }
+            }
+        }
+        runs.pop();
+        runs
+    }
+
+    fn run(script: &str) -> Run {
+        run_all(&[script], |_| Ok(Reply::Done)).remove(0)
+    }
+
+    #[test]
+    fn commands_take_integers_and_floats() {
+        let run = run(r#"camera(1, 2.5, -3); fov(45); set("grid", false); select(2);"#);
+        assert_eq!(run.error, None);
+        assert_eq!(
+            run.commands,
+            [
+                Command::Camera(glm::vec3(1.0, 2.5, -3.0)),
+                Command::Fov(45.0),
+                Command::Set(Toggle::Grid, false),
+                Command::Select(Selector::Index(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn loops_go_through_the_models() {
+        let runs = run_all(
+            &[r#"for m in models() { select(m); capture(m + ".png"); }"#],
+            |command| match command {
+                Command::Models => Ok(Reply::Names(vec!["a".into(), "b c".into()])),
+                _ => Ok(Reply::Done),
+            },
+        );
+        assert_eq!(
+            runs[0].commands,
+            [
+                Command::Models,
+                Command::Select(Selector::Name("a".into())),
+                Command::Capture(Some("a.png".into())),
+                Command::Select(Selector::Name("b c".into())),
+                Command::Capture(Some("b c.png".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn variables_are_kept_for_the_next_script() {
+        let runs = run_all(
+            &["let n = 3;", "if n > 2 { print(`n is ${n}`) }", "n * 2"],
+            |_| Ok(Reply::Done),
+        );
+        assert!(runs.iter().all(|run| run.error.is_none()));
+        assert_eq!(runs[1].printed, ["n is 3"]);
+        // the value of the last expression is shown
+        assert_eq!(runs[2].printed, ["6"]);
+    }
+
+    #[test]
+    fn meshes_are_maps() {
+        let runs = run_all(
+            &["let m = meshes()[0]; print(`${m.name} ${m.triangles}`)"],
+            |_| {
+                Ok(Reply::Meshes(vec![MeshInfo {
+                    model: "model".into(),
+                    name: "mesh".into(),
+                    vertices: 4,
+                    triangles: 2,
+                }]))
+            },
+        );
+        assert_eq!(runs[0].printed, ["mesh 2"]);
+    }
+
+    #[test]
+    fn failed_commands_end_the_script() {
+        let runs = run_all(
+            &[r#"select("missing"); focus();"#],
+            |command| match command {
+                Command::Select(_) => Err("No model matches".into()),
+                _ => Ok(Reply::Done),
+            },
+        );
+        assert_eq!(runs[0].commands.len(), 1);
+        assert!(runs[0].error.as_ref().unwrap().contains("No model matches"));
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        for (script, expected) in [
+            ("camera(1, 2", "Expecting ')'"),
+            (r#"set("fog", true)"#, "Unknown option \"fog\""),
+            (r#"fov("wide")"#, "Expected a number"),
+            ("select(-1)", "No model -1"),
+            ("undefined_thing", "Variable not found"),
+        ] {
+            let run = run(script);
+            let error = run.error.unwrap_or_default();
+            assert!(
+                error.contains(expected),
+                "{:?} failed with {:?}",
+                script,
+                error
+            );
+            assert!(run.commands.is_empty(), "{:?}", script);
+        }
+    }
+
+    #[test]
+    fn stopping_ends_endless_loops() {
+        // ranges are walked lazily so a huge one doesn't use up the memory
+        for endless in ["loop {}", "for i in 0..1_000_000_000_000 { let x = i; }"] {
+            let runner = Runner::spawn();
+            runner.scripts.send(endless.to_string()).unwrap();
+            runner.scripts.send("focus()".to_string()).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            runner.stop.store(true, Ordering::Relaxed);
+
+            let finished: Vec<_> = (0..2)
+                .map(|_| match runner.messages.recv().unwrap() {
+                    Message::Finished(error) => error,
+                    message => panic!("{:?} after stopping", message),
+                })
+                .collect();
+            assert_eq!(finished, [Some("The script was stopped".to_string()), None]);
+        }
+    }
+}
@@ -1,7 +1,11 @@
-use anyhow::Result;
 use glad_gl::gl;
 use log::{debug, error};
 
+use crate::{
+    error::{Error, Result},
+    gl_state,
+};
+
 pub struct ShaderSource {
     pub name: String,
     pub source: String,
@@ -11,81 +15,55 @@ pub struct Shader {
     pub program_id: gl::GLuint,
 }
 
-impl Shader {
-    pub fn new(
-        vertex_obj: &mut ShaderSource,
-        frag_obj: &mut ShaderSource,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        vertex_obj.source.push('\0');
-        let vertex_shader_source =
-            std::ffi::CStr::from_bytes_with_nul(vertex_obj.source.as_bytes())?;
+/// Compiles one stage of a program, with the compiler's log if it fails
+unsafe fn compile(kind: gl::GLenum, source: &mut ShaderSource) -> Result<gl::GLuint> {
+    source.source.push('\0');
+    let c_source = std::ffi::CStr::from_bytes_with_nul(source.source.as_bytes()).map_err(|e| {
+        Error::Shader {
+            name: source.name.clone(),
+            log: e.to_string(),
+        }
+    })?;
+
+    let shader = gl::CreateShader(kind);
+    gl::ShaderSource(shader, 1, &c_source.as_ptr(), std::ptr::null());
+    gl::CompileShader(shader);
+    let mut success = 0;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+    #[cfg(debug_assertions)]
+    debug!("shader {:?} compiled with status: {}", source.name, success);
+    if success == 0 {
+        let mut info_buf = [0u8; 512];
+        let mut len = 0;
+        gl::GetShaderInfoLog(
+            shader,
+            info_buf.len() as i32,
+            &mut len,
+            info_buf.as_mut_ptr() as *mut i8,
+        );
+        gl::DeleteShader(shader);
+        let log = String::from_utf8_lossy(&info_buf[..len.max(0) as usize]);
+        error!("shader {:?} info: {}", source.name, log);
+        return Err(Error::Shader {
+            name: source.name.clone(),
+            log: log.trim().to_string(),
+        });
+    }
 
-        frag_obj.source.push('\0');
-        let frag_shader_source = std::ffi::CStr::from_bytes_with_nul(frag_obj.source.as_bytes())?;
+    Ok(shader)
+}
 
+impl Shader {
+    pub fn new(vertex_obj: &mut ShaderSource, frag_obj: &mut ShaderSource) -> Result<Self> {
         unsafe {
-            let vertex_shader = gl::CreateShader(gl::VERTEX_SHADER);
-            gl::CreateShader(vertex_shader);
-            gl::ShaderSource(
-                vertex_shader,
-                1,
-                &vertex_shader_source.as_ptr(),
-                std::ptr::null(),
-            );
-            gl::CompileShader(vertex_shader);
-            let mut success1 = 0;
-            gl::GetShaderiv(vertex_shader, gl::COMPILE_STATUS, &mut success1);
-            #[cfg(debug_assertions)]
-            debug!(
-                "vertex shader {:?} compiled with status: {}",
-                vertex_obj.name, success1
-            );
-            if success1 == 0 {
-                let info_buf = [0u8; 512];
-                gl::GetShaderInfoLog(
-                    vertex_shader as u32,
-                    512,
-                    std::ptr::null_mut(),
-                    info_buf.as_ptr() as *mut i8,
-                );
-                #[cfg(debug_assertions)]
-                error!(
-                    "vertex shader info: {}",
-                    std::str::from_utf8(&info_buf).unwrap()
-                );
-            }
-
-            let frag_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
-            gl::CreateShader(frag_shader);
-            gl::ShaderSource(
-                frag_shader,
-                1,
-                &frag_shader_source.as_ptr(),
-                std::ptr::null(),
-            );
-            gl::CompileShader(frag_shader);
-
-            let mut success2 = 0;
-            gl::GetShaderiv(frag_shader, gl::COMPILE_STATUS, &mut success2);
-            #[cfg(debug_assertions)]
-            debug!(
-                "frag shader {:?} compiled with status: {}",
-                frag_obj.name, success2
-            );
-            if success2 == 0 {
-                let info_buf2 = [0u8; 512];
-                gl::GetShaderInfoLog(
-                    frag_shader as u32,
-                    512,
-                    std::ptr::null_mut(),
-                    info_buf2.as_ptr() as *mut i8,
-                );
-                #[cfg(debug_assertions)]
-                error!(
-                    "frag shader info: {}",
-                    std::str::from_utf8(&info_buf2).unwrap()
-                );
-            }
+            let vertex_shader = compile(gl::VERTEX_SHADER, vertex_obj)?;
+            let frag_shader = match compile(gl::FRAGMENT_SHADER, frag_obj) {
+                Ok(shader) => shader,
+                Err(e) => {
+                    gl::DeleteShader(vertex_shader);
+                    return Err(e);
+                }
+            };
 
             let shader_program = gl::CreateProgram();
             gl::AttachShader(shader_program, vertex_shader);
@@ -95,6 +73,16 @@ impl Shader {
             gl::DeleteShader(vertex_shader);
             gl::DeleteShader(frag_shader);
 
+            let mut success = 0;
+            gl::GetProgramiv(shader_program, gl::LINK_STATUS, &mut success);
+            if success == 0 {
+                gl::DeleteProgram(shader_program);
+                return Err(Error::Shader {
+                    name: format!("{} + {}", vertex_obj.name, frag_obj.name),
+                    log: "the program failed to link".to_string(),
+                });
+            }
+
             Ok(Self {
                 program_id: shader_program,
             })
@@ -102,9 +90,7 @@ impl Shader {
     }
 
     pub fn use_shader(&self) {
-        unsafe {
-            gl::UseProgram(self.program_id);
-        }
+        gl_state::use_program(self.program_id);
     }
 
     pub fn set_bool(&self, name: &str, value: bool) {
@@ -175,6 +161,17 @@ impl Shader {
         }
     }
 
+    pub fn set_2fv(&self, name: &str, value: glm::Vec2) {
+        let c_str = std::ffi::CString::new(name).unwrap();
+        unsafe {
+            gl::Uniform2fv(
+                gl::GetUniformLocation(self.program_id, c_str.as_ptr()),
+                1,
+                value.as_array() as *const f32,
+            );
+        }
+    }
+
     pub fn set_3fv(&self, name: &str, value: glm::Vec3) {
         let c_str = std::ffi::CString::new(name).unwrap();
         unsafe {
@@ -0,0 +1,276 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::info;
+
+use crate::{deep_link::SCHEME, importer::registry};
+
+/// File extensions that get opened with 3dobs, with their MIME types
+fn file_types() -> Vec<(&'static str, &'static str)> {
+    registry::IMPORTERS
+        .iter()
+        .flat_map(|importer| {
+            importer
+                .extensions()
+                .iter()
+                .map(|ext| (*ext, importer.mime_type()))
+        })
+        .collect()
+}
+
+fn run(program: &str, args: &[&str]) -> Result<()> {
+    let status = std::process::Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run {}", program))?;
+    anyhow::ensure!(status.success(), "{} exited with {}", program, status);
+
+    Ok(())
+}
+
+/// Registers 3dobs as the handler of the supported model files and of
+/// `3dobs://` links for the current user, so double-clicking a file in the
+/// file manager or following a link opens it. With "one instance" enabled
+/// the model goes to the window that's already open.
+pub fn register_file_types() -> Result<()> {
+    let exe = std::env::current_exe().context("Failed to find the executable")?;
+
+    register(&exe.to_string_lossy())?;
+    info!(
+        "Registered 3dobs as the handler of {} files and {}:// links",
+        file_types()
+            .iter()
+            .map(|(ext, _)| format!(".{}", ext))
+            .collect::<Vec<_>>()
+            .join(", "),
+        SCHEME
+    );
+
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn register(exe: &str) -> Result<()> {
+    const PROG_ID: &str = "3dobs.Model";
+    let classes = r"HKCU\Software\Classes";
+    let reg_add = |key: &str, name: Option<&str>, value: &str| {
+        let mut args = vec!["add", key, "/f", "/d", value];
+        match name {
+            Some(name) => args.extend(["/v", name]),
+            None => args.push("/ve"),
+        }
+        run("reg", &args)
+    };
+
+    reg_add(&format!(r"{}\{}", classes, PROG_ID), None, "3D Model")?;
+    reg_add(
+        &format!(r"{}\{}\DefaultIcon", classes, PROG_ID),
+        None,
+        &format!("\"{}\",0", exe),
+    )?;
+    reg_add(
+        &format!(r"{}\{}\shell\open\command", classes, PROG_ID),
+        None,
+        &format!("\"{}\" \"%1\"", exe),
+    )?;
+
+    for (ext, mime) in file_types() {
+        let key = format!(r"{}\.{}", classes, ext);
+        reg_add(&key, None, PROG_ID)?;
+        reg_add(&key, Some("Content Type"), mime)?;
+        // keeps 3dobs in "Open With" even if another program takes over the default
+        reg_add(&format!(r"{}\OpenWithProgids", key), Some(PROG_ID), "")?;
+    }
+
+    let scheme_key = format!(r"{}\{}", classes, SCHEME);
+    reg_add(&scheme_key, None, "URL:3dobs Protocol")?;
+    reg_add(&scheme_key, Some("URL Protocol"), "")?;
+    reg_add(
+        &format!(r"{}\shell\open\command", scheme_key),
+        None,
+        &format!("\"{}\" \"%1\"", exe),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn register(_exe: &str) -> Result<()> {
+    anyhow::bail!("File types are registered through the app bundle on macOS")
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn register(exe: &str) -> Result<()> {
+    let data_dir = match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME").context("HOME isn't set")?)
+            .join(".local")
+            .join("share"),
+    };
+    let applications_dir = data_dir.join("applications");
+    let mime_dir = data_dir.join("mime");
+    let packages_dir = mime_dir.join("packages");
+    let thumbnailers_dir = data_dir.join("thumbnailers");
+    std::fs::create_dir_all(&applications_dir)?;
+    std::fs::create_dir_all(&packages_dir)?;
+    std::fs::create_dir_all(&thumbnailers_dir)?;
+
+    let mime_types: Vec<&str> = file_types().iter().map(|(_, mime)| *mime).collect();
+
+    // most distros don't know about the model types yet
+    let mime_info: String = file_types()
+        .iter()
+        .map(|(ext, mime)| {
+            format!(
+                "  <mime-type type=\"{}\">\n    <comment>{} model</comment>\n    \
+                 <glob pattern=\"*.{}\"/>\n  </mime-type>\n",
+                mime,
+                ext.to_uppercase(),
+                ext
+            )
+        })
+        .collect();
+    std::fs::write(
+        packages_dir.join("3dobs.xml"),
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <mime-info xmlns=\"http://www.freedesktop.org/standards/shared-mime-info\">\n\
+             {}</mime-info>\n",
+            mime_info
+        ),
+    )?;
+
+    std::fs::write(
+        applications_dir.join("3dobs.desktop"),
+        format!(
+            "[Desktop Entry]\n\
+             Name=3dobs\n\
+             GenericName=3D Viewer\n\
+             Comment=View STL & OBJ Files\n\
+             Type=Application\n\
+             Exec=\"{}\" %F\n\
+             Terminal=false\n\
+             PrefersNonDefaultGPU=true\n\
+             MimeType={};\n\
+             Categories=Graphics;3DGraphics;Viewer;\n",
+            exe,
+            mime_types.join(";")
+        ),
+    )?;
+
+    // a separate entry for the links, the main one takes file paths and not URLs
+    let scheme_mime = format!("x-scheme-handler/{}", SCHEME);
+    std::fs::write(
+        applications_dir.join("3dobs-url-handler.desktop"),
+        format!(
+            "[Desktop Entry]\n\
+             Name=3dobs\n\
+             Type=Application\n\
+             Exec=\"{}\" %u\n\
+             Terminal=false\n\
+             NoDisplay=true\n\
+             MimeType={};\n",
+            exe, scheme_mime
+        ),
+    )?;
+
+    // lets the file manager show previews of the models with --thumbnail
+    std::fs::write(
+        thumbnailers_dir.join("3dobs.thumbnailer"),
+        format!(
+            "[Thumbnailer Entry]\n\
+             TryExec={}\n\
+             Exec=\"{}\" --thumbnail %i %o %s\n\
+             MimeType={};\n",
+            exe,
+            exe,
+            mime_types.join(";")
+        ),
+    )?;
+
+    run("update-mime-database", &[&mime_dir.to_string_lossy()])?;
+    // only refreshes the cache, the desktop entry works without it
+    let _ = run(
+        "update-desktop-database",
+        &[&applications_dir.to_string_lossy()],
+    );
+
+    let mut args = vec!["default", "3dobs.desktop"];
+    args.extend(mime_types);
+    run("xdg-mime", &args)?;
+    run(
+        "xdg-mime",
+        &["default", "3dobs-url-handler.desktop", &scheme_mime],
+    )
+}
+
+/// `path` from the root of the file system. Symlinks aren't resolved, and
+/// the path doesn't have to exist.
+pub fn absolute_path(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Opens the folder holding `path` in the file manager, with the file
+/// selected where the file manager can do that
+pub fn reveal_in_file_manager(path: &Path) -> Result<()> {
+    let path = absolute_path(path);
+    anyhow::ensure!(path.exists(), "{:?} doesn't exist anymore", path);
+
+    reveal(&path).with_context(|| format!("Failed to show {:?} in the file manager", path))
+}
+
+#[cfg(target_os = "windows")]
+fn reveal(path: &Path) -> Result<()> {
+    // explorer exits with 1 even when it worked, so its status means nothing
+    std::process::Command::new("explorer")
+        .arg(format!("/select,{}", path.display()))
+        .spawn()?;
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn reveal(path: &Path) -> Result<()> {
+    run("open", &["-R", &path.to_string_lossy()])
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn reveal(path: &Path) -> Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    // the freedesktop file manager interface selects the file, file managers
+    // without it only get to open the folder
+    let uri: String = std::iter::once("file://".to_string())
+        .chain(path.as_os_str().as_bytes().iter().map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                (*b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        }))
+        .collect();
+    // the reply is only printed so dbus-send fails without a file manager
+    let shown = std::process::Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+            &format!("array:string:{}", uri),
+            "string:",
+        ])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    if shown.is_ok_and(|status| status.success()) {
+        return Ok(());
+    }
+
+    let dir = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(path)
+    };
+    crate::utils::open_path(dir)
+}
@@ -0,0 +1,369 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use log::info;
+
+use crate::{
+    history::{GeometrySnapshot, History},
+    mesh::{MeshGeometry, Vertex},
+    model::Model,
+    topology::{self, face_normal},
+};
+
+/// How much more moving away from a boundary edge costs than moving off the
+/// surface, keeps the outline of open meshes from shrinking
+const BOUNDARY_WEIGHT: f64 = 10.0;
+
+/// The geometry of each mesh of a model, in order
+type Geometry = Vec<MeshGeometry>;
+/// Edges to collapse, cheapest first, as (cost, vertex to remove, vertex to
+/// keep, their versions)
+type Candidates = BinaryHeap<Reverse<(u64, u32, u32, u32, u32)>>;
+
+/// Sum of squared distances to a set of planes, as the upper triangle of a
+/// symmetric 4x4 matrix
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn plane(normal: glm::Vec3, point: glm::Vec3, weight: f64) -> Self {
+        let [a, b, c] = [normal.x, normal.y, normal.z].map(f64::from);
+        let d = -(a * point.x as f64 + b * point.y as f64 + c * point.z as f64);
+        Self(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|q| q * weight),
+        )
+    }
+
+    fn error(&self, p: glm::Vec3) -> f64 {
+        let [x, y, z] = [p.x, p.y, p.z].map(f64::from);
+        let q = &self.0;
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+impl std::ops::Add for Quadric {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        for (q, o) in self.0.iter_mut().zip(other.0) {
+            *q += o;
+        }
+        self
+    }
+}
+
+/// Collapses edges until the geometry has at most `target` triangles or no
+/// edge can be collapsed without flipping a face. The cheapest edge by its
+/// quadric error goes first and collapses into one of its ends, so the
+/// remaining vertices keep their normals and UVs.
+pub fn decimate(geometry: &[MeshGeometry], target: usize) -> Geometry {
+    // weld by position, meshes are decimated together so there are no gaps
+    // between them
+    let mut ids: HashMap<[u32; 3], u32> = HashMap::new();
+    let mut positions: Vec<glm::Vec3> = vec![];
+    let mut vertex_ids: Vec<Vec<u32>> = vec![];
+    for mesh in geometry {
+        let mut mesh_ids = Vec::with_capacity(mesh.vertices.len());
+        for vertex in &mesh.vertices {
+            let p = vertex.position;
+            let id = *ids.entry(topology::position_key(p)).or_insert_with(|| {
+                positions.push(p);
+                positions.len() as u32 - 1
+            });
+            mesh_ids.push(id);
+        }
+        vertex_ids.push(mesh_ids);
+    }
+
+    // faces that are already degenerate are dropped
+    let mut faces: Vec<[u32; 3]> = vec![];
+    let mut origins: Vec<(usize, usize)> = vec![];
+    for (mesh_idx, mesh) in geometry.iter().enumerate() {
+        for (triangle, corners) in mesh.indices.chunks_exact(3).enumerate() {
+            let face = [0, 1, 2].map(|i| vertex_ids[mesh_idx][corners[i] as usize]);
+            if face[0] != face[1] && face[1] != face[2] && face[2] != face[0] {
+                faces.push(face);
+                origins.push((mesh_idx, triangle));
+            }
+        }
+    }
+
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut vertex_faces: Vec<Vec<usize>> = vec![vec![]; positions.len()];
+    let mut edges: HashMap<(u32, u32), (usize, usize)> = HashMap::new();
+    for (f, face) in faces.iter().enumerate() {
+        let normal = face_normal(face.map(|id| positions[id as usize]));
+        let length = glm::length(normal);
+        for (i, &id) in face.iter().enumerate() {
+            if length > 0.0 {
+                // weighted by area so small faces don't dominate
+                quadrics[id as usize] = quadrics[id as usize]
+                    + Quadric::plane(normal / length, positions[id as usize], length as f64 / 2.0);
+            }
+            vertex_faces[id as usize].push(f);
+
+            let next = face[(i + 1) % 3];
+            edges
+                .entry((id.min(next), id.max(next)))
+                .or_insert((0, f))
+                .0 += 1;
+        }
+    }
+    for ((a, b), (count, f)) in edges {
+        if count != 1 {
+            continue;
+        }
+        let (pa, pb) = (positions[a as usize], positions[b as usize]);
+        let normal = face_normal(faces[f].map(|id| positions[id as usize]));
+        let side = glm::cross(pb - pa, normal);
+        if glm::length(side) == 0.0 {
+            continue;
+        }
+        // a plane through the edge standing on the face
+        let weight = BOUNDARY_WEIGHT * glm::length(pb - pa) as f64;
+        let plane = Quadric::plane(glm::normalize(side), pa, weight);
+        quadrics[a as usize] = quadrics[a as usize] + plane;
+        quadrics[b as usize] = quadrics[b as usize] + plane;
+    }
+
+    let mut alive = vec![true; faces.len()];
+    let mut triangles = faces.len();
+    let mut parent: Vec<u32> = (0..positions.len() as u32).collect();
+    // bumped whenever a vertex changes, so outdated candidates are skipped
+    let mut versions = vec![0u32; positions.len()];
+    let mut heap = Candidates::new();
+
+    let push_edges = |v: u32,
+                      heap: &mut Candidates,
+                      faces: &[[u32; 3]],
+                      vertex_faces: &[Vec<usize>],
+                      quadrics: &[Quadric],
+                      versions: &[u32]| {
+        let mut neighbors: Vec<u32> = vertex_faces[v as usize]
+            .iter()
+            .flat_map(|f| faces[*f])
+            .filter(|n| *n != v)
+            .collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+
+        for n in neighbors {
+            let q = quadrics[v as usize] + quadrics[n as usize];
+            let into_n = q.error(positions[n as usize]);
+            let into_v = q.error(positions[v as usize]);
+            let (cost, remove, keep) = if into_n <= into_v {
+                (into_n, v, n)
+            } else {
+                (into_v, n, v)
+            };
+            // the bits of non-negative floats sort the same as the floats
+            heap.push(Reverse((
+                cost.max(0.0).to_bits(),
+                remove,
+                keep,
+                versions[remove as usize],
+                versions[keep as usize],
+            )));
+        }
+    };
+
+    for v in 0..positions.len() as u32 {
+        push_edges(v, &mut heap, &faces, &vertex_faces, &quadrics, &versions);
+    }
+
+    while triangles > target {
+        let Some(Reverse((_, remove, keep, remove_version, keep_version))) = heap.pop() else {
+            break;
+        };
+        let (r, k) = (remove as usize, keep as usize);
+        if parent[r] != remove
+            || parent[k] != keep
+            || versions[r] != remove_version
+            || versions[k] != keep_version
+        {
+            continue;
+        }
+
+        // moving the vertex must not turn any of the faces it keeps around
+        let flips = vertex_faces[r]
+            .iter()
+            .filter(|f| alive[**f] && !faces[**f].contains(&keep))
+            .any(|f| {
+                let before = faces[*f].map(|id| positions[id as usize]);
+                let after =
+                    faces[*f].map(|id| positions[if id == remove { k } else { id as usize }]);
+                glm::dot(face_normal(before), face_normal(after)) <= 0.0
+            });
+        if flips {
+            continue;
+        }
+
+        parent[r] = keep;
+        quadrics[k] = quadrics[k] + quadrics[r];
+        for f in std::mem::take(&mut vertex_faces[r]) {
+            if !alive[f] {
+                continue;
+            }
+            let face = &mut faces[f];
+            for id in face.iter_mut() {
+                if *id == remove {
+                    *id = keep;
+                }
+            }
+            if face[0] == face[1] || face[1] == face[2] || face[2] == face[0] {
+                alive[f] = false;
+                triangles -= 1;
+            } else {
+                vertex_faces[k].push(f);
+            }
+        }
+        vertex_faces[k].retain(|f| alive[*f]);
+        versions[k] += 1;
+
+        push_edges(keep, &mut heap, &faces, &vertex_faces, &quadrics, &versions);
+    }
+
+    let find = |mut id: u32| {
+        while parent[id as usize] != id {
+            id = parent[id as usize];
+        }
+        id
+    };
+
+    let mut new_vertices: Vec<Vec<Vertex>> = vec![vec![]; geometry.len()];
+    let mut new_triangles: Vec<Vec<(usize, [u32; 3])>> = vec![vec![]; geometry.len()];
+    let mut remaps: Vec<HashMap<u32, u32>> = vec![HashMap::new(); geometry.len()];
+    for (f, &(mesh_idx, triangle)) in origins.iter().enumerate() {
+        if !alive[f] {
+            continue;
+        }
+        let mesh = &geometry[mesh_idx];
+        let vertices = &mut new_vertices[mesh_idx];
+        let corners = [0, 1, 2].map(|i| {
+            let index = mesh.indices[triangle * 3 + i];
+            // only the vertices still in use are kept
+            *remaps[mesh_idx].entry(index).or_insert_with(|| {
+                let mut vertex = mesh.vertices[index as usize].clone();
+                vertex.position = positions[find(vertex_ids[mesh_idx][index as usize]) as usize];
+                vertices.push(vertex);
+                vertices.len() as u32 - 1
+            })
+        });
+        new_triangles[mesh_idx].push((mesh.material_index(triangle), corners));
+    }
+
+    geometry
+        .iter()
+        .zip(new_vertices.into_iter().zip(new_triangles))
+        .map(|(mesh, (vertices, triangles))| {
+            MeshGeometry::from_triangles(vertices, triangles, mesh.material_counts.len())
+        })
+        .collect()
+}
+
+/// The model's original geometry while a simplified one is shown in its place
+#[derive(Debug)]
+pub struct Preview {
+    pub model_id: u32,
+    pub original: GeometrySnapshot,
+}
+
+#[derive(Debug)]
+pub struct Simplify {
+    /// Aim for `triangles` instead of `percent` of the original triangles
+    pub by_count: bool,
+    pub percent: f32,
+    pub triangles: u32,
+    pub preview: Option<Preview>,
+}
+
+impl Default for Simplify {
+    fn default() -> Self {
+        Self {
+            by_count: false,
+            percent: 50.0,
+            triangles: 1000,
+            preview: None,
+        }
+    }
+}
+
+impl Simplify {
+    /// The number of triangles to simplify a model of `triangles` down to
+    pub fn target(&self, triangles: usize) -> usize {
+        if self.by_count {
+            self.triangles as usize
+        } else {
+            (triangles as f32 * self.percent / 100.0).round() as usize
+        }
+        .clamp(1, triangles.max(1))
+    }
+
+    /// Shows the model simplified to the current target, starting over from
+    /// the original geometry if it's already being previewed
+    pub fn preview(&mut self, model: &mut Model) {
+        let original = match self.preview.take() {
+            Some(preview) if preview.model_id == model.id => preview.original,
+            _ => GeometrySnapshot::capture(model),
+        };
+
+        let target = self.target(original.triangles());
+        let geometry = decimate(original.meshes(), target);
+        let triangles: usize = geometry.iter().map(|m| m.indices.len() / 3).sum();
+        info!(
+            "Simplified {} from {} to {} triangles",
+            model.name,
+            original.triangles(),
+            triangles
+        );
+        model.set_geometry(geometry);
+
+        self.preview = Some(Preview {
+            model_id: model.id,
+            original,
+        });
+    }
+
+    /// Keeps the previewed geometry, undoing brings back the original
+    pub fn apply(&mut self, history: &mut History) {
+        if let Some(preview) = self.preview.take() {
+            history.record_geometry_snapshot(preview.model_id, preview.original);
+        }
+    }
+
+    /// Puts the original geometry back
+    pub fn cancel(&mut self, model: &mut Model) {
+        if let Some(preview) = self.preview.take() {
+            preview.original.restore(model);
+        }
+    }
+
+    /// The number of triangles of the previewed model before simplifying
+    pub fn original_triangles(&self, model_id: u32) -> Option<usize> {
+        self.preview
+            .as_ref()
+            .filter(|p| p.model_id == model_id)
+            .map(|p| p.original.triangles())
+    }
+}
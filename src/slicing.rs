@@ -0,0 +1,237 @@
+use glad_gl::gl;
+
+use crate::{debug_draw::DebugDraw, gl_state, shader::Shader, ui::ui::State, utils};
+
+pub const DEFAULT_LAYER_HEIGHT: f32 = 0.05;
+/// Seconds each layer is shown for while sweeping
+const LAYER_INTERVAL: f32 = 0.05;
+const PLANE_COLOR: glm::Vec3 = glm::Vec3 {
+    x: 1.0,
+    y: 0.4,
+    z: 0.1,
+};
+/// The color the cross-section starts with
+const SECTION_COLOR: glm::Vec3 = glm::Vec3 {
+    x: 1.0,
+    y: 0.4,
+    z: 0.1,
+};
+/// How far the cap reaches past the bounds of the models, so it covers the
+/// whole cut even where the bounds are tight
+const CAP_MARGIN: f32 = 0.01;
+
+/// A horizontal clipping plane swept through the displayed models one layer
+/// at a time, like a slicer's print preview
+#[derive(Debug)]
+pub struct Slicing {
+    pub enabled: bool,
+    pub layer_height: f32,
+    /// The topmost layer that's shown, 0 being the bottom one
+    pub layer: u32,
+    pub playing: bool,
+    /// Time since the sweep moved to the current layer
+    elapsed: f32,
+    /// Height everything above is clipped at this frame, `None` while slicing
+    /// is off or nothing is displayed
+    pub plane: Option<f32>,
+    /// Bottom and top of the displayed models this frame
+    pub range: Option<(f32, f32)>,
+    /// Fill the cut with a solid cap instead of showing the back faces
+    /// through it
+    pub cap: bool,
+    /// Color of the cap, and of the back faces when there's none
+    pub section_color: glm::Vec3,
+    cap_vao: u32,
+    cap_vbo: u32,
+}
+
+impl Default for Slicing {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            layer_height: DEFAULT_LAYER_HEIGHT,
+            layer: 0,
+            playing: false,
+            elapsed: 0.0,
+            plane: None,
+            range: None,
+            cap: false,
+            section_color: SECTION_COLOR,
+            cap_vao: 0,
+            cap_vbo: 0,
+        }
+    }
+}
+
+impl Slicing {
+    /// Deletes the cap's buffers, for when the context they were made in is
+    /// lost. They're made again the next time the cap is drawn.
+    pub fn release_buffers(&mut self) {
+        if self.cap_vao != 0 {
+            unsafe {
+                gl::DeleteBuffers(1, &self.cap_vbo);
+                gl::DeleteVertexArrays(1, &self.cap_vao);
+            }
+        }
+        self.cap_vao = 0;
+        self.cap_vbo = 0;
+    }
+
+    pub fn layer_count(&self) -> u32 {
+        self.range.map_or(0, |(bottom, top)| {
+            ((top - bottom) / self.layer_height).ceil().max(1.0) as u32
+        })
+    }
+
+    /// Outlines the plane across the bounds of the displayed models
+    pub fn draw_plane(&self, (min, max): (glm::Vec3, glm::Vec3), debug: &mut DebugDraw) {
+        let Some(height) = self.plane else {
+            return;
+        };
+
+        let corners = [
+            glm::vec3(min.x, height, min.z),
+            glm::vec3(max.x, height, min.z),
+            glm::vec3(max.x, height, max.z),
+            glm::vec3(min.x, height, max.z),
+        ];
+        for i in 0..4 {
+            debug.line(corners[i], corners[(i + 1) % 4], PLANE_COLOR);
+        }
+    }
+
+    /// Starts the sweep over from the bottom if it already reached the top
+    pub fn toggle_playing(&mut self) {
+        if !self.playing && self.layer + 1 >= self.layer_count() {
+            self.layer = 0;
+        }
+        self.playing = !self.playing;
+        self.elapsed = 0.0;
+    }
+}
+
+/// Advances the sweep and places the plane at the top of the current layer
+pub fn update(state: &mut State, delta_time: f32) {
+    state.slicing.range = utils::displayed_bounds(state).map(|(min, max)| (min.y, max.y));
+
+    let slicing = &mut state.slicing;
+    slicing.layer_height = slicing.layer_height.max(0.001);
+    let layer_count = slicing.layer_count();
+    let Some((bottom, _)) = slicing.range.filter(|_| slicing.enabled) else {
+        slicing.plane = None;
+        slicing.playing = false;
+        return;
+    };
+
+    if slicing.playing {
+        slicing.elapsed += delta_time;
+        while slicing.elapsed >= LAYER_INTERVAL {
+            slicing.elapsed -= LAYER_INTERVAL;
+            slicing.layer += 1;
+        }
+        if slicing.layer + 1 >= layer_count {
+            slicing.playing = false;
+        }
+    }
+    slicing.layer = slicing.layer.min(layer_count.saturating_sub(1));
+
+    slicing.plane = Some(bottom + (slicing.layer + 1) as f32 * slicing.layer_height);
+}
+
+/// Fills the cut through the displayed models with the section color, so
+/// they read as solid instead of hollow shells. The models are drawn into
+/// the stencil buffer first, flipping it for every surface left below the
+/// plane, which leaves an odd count wherever the cut is inside of a model.
+/// That only holds for closed meshes, the cap leaks out of holes in open
+/// ones.
+pub fn draw_cap(
+    state: &mut State,
+    mesh_shader: &Shader,
+    line_shader: &Shader,
+    view_mat: &glm::Mat4,
+    projection_mat: &glm::Mat4,
+) {
+    let (Some(height), Some((min, max)), true) = (
+        state.slicing.plane,
+        utils::displayed_bounds(state),
+        state.slicing.cap,
+    ) else {
+        return;
+    };
+    let slicing = &mut state.slicing;
+    let margin = glm::length(max - min) * CAP_MARGIN;
+    let (min, max) = (min - margin, max + margin);
+    let corners: [glm::Vec3; 4] = [
+        glm::vec3(min.x, height, min.z),
+        glm::vec3(max.x, height, min.z),
+        glm::vec3(max.x, height, max.z),
+        glm::vec3(min.x, height, max.z),
+    ];
+
+    unsafe {
+        if slicing.cap_vao == 0 {
+            gl::GenVertexArrays(1, &mut slicing.cap_vao);
+            gl::GenBuffers(1, &mut slicing.cap_vbo);
+            gl::BindVertexArray(slicing.cap_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, slicing.cap_vbo);
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                std::mem::size_of::<glm::Vec3>() as i32,
+                std::ptr::null(),
+            );
+        }
+
+        // count the surfaces behind every pixel, without drawing anything
+        gl::Clear(gl::STENCIL_BUFFER_BIT);
+        gl_state::enable(gl::STENCIL_TEST);
+        gl::StencilFunc(gl::ALWAYS, 0, 0xFF);
+        gl::StencilOp(gl::KEEP, gl::INVERT, gl::INVERT);
+        gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+        gl::DepthMask(gl::FALSE);
+        gl_state::disable(gl::DEPTH_TEST);
+        mesh_shader.use_shader();
+        for obj in state
+            .objects
+            .iter()
+            .filter(|m| m.visible && state.selection.contains(&m.id))
+        {
+            obj.draw(mesh_shader, false, None);
+        }
+        gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+        gl::DepthMask(gl::TRUE);
+        gl_state::enable(gl::DEPTH_TEST);
+
+        // then draw the plane where the count is odd
+        gl::StencilFunc(gl::NOTEQUAL, 0, 0xFF);
+        gl::StencilOp(gl::KEEP, gl::KEEP, gl::KEEP);
+        line_shader.use_shader();
+        line_shader.set_mat4fv("view", view_mat);
+        line_shader.set_mat4fv("projection", projection_mat);
+        gl::BindVertexArray(slicing.cap_vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, slicing.cap_vbo);
+        gl::BufferData(
+            gl::ARRAY_BUFFER,
+            std::mem::size_of_val(&corners) as isize,
+            corners.as_ptr() as *const std::ffi::c_void,
+            gl::STREAM_DRAW,
+        );
+        // the color is the same for every corner
+        gl::VertexAttrib3f(
+            1,
+            slicing.section_color.x,
+            slicing.section_color.y,
+            slicing.section_color.z,
+        );
+        gl::DrawArrays(gl::TRIANGLE_FAN, 0, 4);
+
+        // reset stuff to default
+        gl_state::disable(gl::STENCIL_TEST);
+        gl::BindVertexArray(0);
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        mesh_shader.use_shader();
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+};
+
+use glad_gl::gl;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{gl_state, pacing, ui::i18n::tr, utils};
+
+pub const ANISOTROPY_LEVELS: [f32; 5] = [1.0, 2.0, 4.0, 8.0, 16.0];
+pub const MAX_TEXTURE_SIZES: [Option<u32>; 5] =
+    [None, Some(1024), Some(2048), Some(4096), Some(8192)];
+/// Mip level inactive models are drawn from when their textures are kept at
+/// a lower resolution, a quarter of the original size
+const INACTIVE_BASE_LEVEL: i32 = 2;
+/// Side of the checkerboard placeholder and of its squares, in pixels
+const PLACEHOLDER_SIZE: u32 = 64;
+const PLACEHOLDER_CELL: u32 = 8;
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+    #[default]
+    Trilinear,
+}
+
+impl TextureFilter {
+    pub const ALL: [TextureFilter; 3] = [
+        TextureFilter::Nearest,
+        TextureFilter::Bilinear,
+        TextureFilter::Trilinear,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextureFilter::Nearest => tr("texture_filter.nearest"),
+            TextureFilter::Bilinear => tr("texture_filter.bilinear"),
+            TextureFilter::Trilinear => tr("texture_filter.trilinear"),
+        }
+    }
+
+    /// The min and mag filters for this mode
+    fn gl_filters(&self) -> (u32, u32) {
+        match self {
+            TextureFilter::Nearest => (gl::NEAREST_MIPMAP_NEAREST, gl::NEAREST),
+            TextureFilter::Bilinear => (gl::LINEAR_MIPMAP_NEAREST, gl::LINEAR),
+            TextureFilter::Trilinear => (gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR),
+        }
+    }
+}
+
+/// How textures are sampled, applied to every texture on upload and
+/// re-applied to the loaded ones whenever the settings change
+#[derive(Debug, Clone, Copy)]
+pub struct Sampling {
+    pub filter: TextureFilter,
+    pub anisotropy: f32,
+}
+
+/// Highest anisotropy level the driver supports, 1.0 if it doesn't support
+/// anisotropic filtering at all
+pub fn max_anisotropy() -> f32 {
+    let mut max = 0.0;
+    unsafe {
+        gl::GetFloatv(gl::MAX_TEXTURE_MAX_ANISOTROPY, &mut max);
+        // clear the error in case the extension is missing
+        gl::GetError();
+    }
+
+    max.max(1.0)
+}
+
+/// Applies the sampling parameters to a texture
+pub fn apply_sampling(texture_id: u32, sampling: Sampling) {
+    let (min_filter, mag_filter) = sampling.filter.gl_filters();
+
+    unsafe {
+        gl_state::bind_texture(0, texture_id);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, min_filter as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, mag_filter as i32);
+        gl::TexParameterf(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MAX_ANISOTROPY,
+            sampling.anisotropy.clamp(1.0, max_anisotropy()),
+        );
+        gl_state::bind_texture(0, 0);
+    }
+}
+
+/// Downscales the image if it's bigger than `max_size` on either side,
+/// keeping its aspect ratio
+pub fn limit_size(name: &str, image: &mut image::DynamicImage, max_size: u32) {
+    if image.width() <= max_size && image.height() <= max_size {
+        return;
+    }
+
+    info!(
+        "Downscaling texture \"{}\" from {}x{} to fit into {}x{}",
+        name,
+        image.width(),
+        image.height(),
+        max_size,
+        max_size
+    );
+    *image = image.resize(max_size, max_size, image::imageops::FilterType::Triangle);
+}
+
+/// Checkerboard a texture is drawn with until its image is decoded
+pub fn placeholder() -> image::DynamicImage {
+    let image = image::RgbImage::from_fn(PLACEHOLDER_SIZE, PLACEHOLDER_SIZE, |x, y| {
+        if ((x / PLACEHOLDER_CELL) ^ (y / PLACEHOLDER_CELL)) & 1 == 0 {
+            image::Rgb([200, 200, 200])
+        } else {
+            image::Rgb([120, 120, 120])
+        }
+    });
+
+    image::DynamicImage::ImageRgb8(image)
+}
+
+/// Where the image of a texture is read from
+#[derive(Debug, Clone)]
+pub enum Source {
+    File(PathBuf),
+    /// Embedded in the model file as a `data:` URI
+    DataUri(String),
+}
+
+/// A decoded image or why it couldn't be decoded, by the texture's name
+pub type Decoded = (String, Result<image::DynamicImage, String>);
+
+/// The textures of a model being decoded on worker threads. They're handed
+/// over one at a time as they finish, in no particular order.
+#[derive(Debug)]
+pub struct DecodeJob {
+    rx: Receiver<Decoded>,
+    remaining: usize,
+}
+
+impl DecodeJob {
+    /// Decodes the textures on `threads` threads, or one per CPU core if it's
+    /// 0. Images bigger than `max_size` are downscaled on the workers.
+    pub fn spawn(sources: Vec<(String, Source)>, threads: usize, max_size: Option<u32>) -> Self {
+        let remaining = sources.len();
+        let threads = match threads {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let queue = Arc::new(Mutex::new(sources));
+        let (tx, rx) = mpsc::channel();
+
+        for i in 0..threads.min(remaining) {
+            let queue = queue.clone();
+            let tx = tx.clone();
+            let _ = std::thread::Builder::new()
+                .name(format!("texture decoder {}", i))
+                .spawn(move || loop {
+                    let Some((name, source)) = queue.lock().unwrap().pop() else {
+                        break;
+                    };
+                    let image = match source {
+                        Source::File(path) => utils::decode_texture(path),
+                        Source::DataUri(uri) => utils::decode_data_uri(&uri),
+                    };
+                    let image = image
+                        .map(|mut image| {
+                            if let Some(max_size) = max_size {
+                                limit_size(&name, &mut image, max_size);
+                            }
+                            image
+                        })
+                        .map_err(|e| format!("{:#}", e));
+                    // the model was removed before its textures were done
+                    if tx.send((name, image)).is_err() {
+                        break;
+                    }
+                    pacing::request_redraw();
+                });
+        }
+
+        DecodeJob { rx, remaining }
+    }
+
+    /// The textures that finished since the last call
+    pub fn poll(&mut self) -> Vec<Decoded> {
+        let mut decoded = Vec::new();
+        loop {
+            match self.rx.try_recv() {
+                Ok(texture) => decoded.push(texture),
+                Err(TryRecvError::Empty) => break,
+                // a worker that died takes its texture with it
+                Err(TryRecvError::Disconnected) => {
+                    self.remaining = 0;
+                    return decoded;
+                }
+            }
+        }
+        self.remaining -= decoded.len();
+        decoded
+    }
+
+    /// Blocks until every texture is decoded
+    pub fn wait(&mut self) -> Vec<Decoded> {
+        self.remaining = 0;
+        self.rx.iter().collect()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+/// Restricts a texture to its lower mip levels so the driver can page the
+/// full resolution ones out of VRAM, or lifts the restriction again
+pub fn set_low_res(texture_id: u32, low_res: bool) {
+    let base_level = if low_res { INACTIVE_BASE_LEVEL } else { 0 };
+
+    unsafe {
+        gl_state::bind_texture(0, texture_id);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_BASE_LEVEL, base_level);
+        gl_state::bind_texture(0, 0);
+    }
+}
@@ -0,0 +1,201 @@
+use std::{path::Path, sync::mpsc::Receiver};
+
+use anyhow::{bail, Context, Result};
+use glad_gl::gl;
+use glfw::Context as _;
+
+use crate::{camera, gl_state, importer, model::Model, shader, ui::ui::State, utils};
+
+/// The model is rendered at this multiple of the requested size and scaled
+/// down, which smooths its edges
+const SUPERSAMPLING: u32 = 2;
+
+/// Direction the camera looks at the model from, slightly above and to the side
+const VIEW_DIRECTION: [f32; 3] = [0.6, 0.5, 1.0];
+
+/// Handles `--thumbnail <in> <out> <size> [--no-textures]`, the arguments
+/// file manager thumbnailers pass to render a preview of a model
+pub fn run(args: &[String]) -> Result<()> {
+    let [input, output, size, flags @ ..] = args else {
+        bail!("Usage: --thumbnail <input> <output> <size> [--no-textures]");
+    };
+    let size: u32 = size
+        .parse()
+        .with_context(|| format!("Invalid thumbnail size \"{}\"", size))?;
+    if size == 0 {
+        bail!("The thumbnail size must be bigger than 0");
+    }
+    let load_textures = !flags.iter().any(|flag| flag == "--no-textures");
+
+    render(Path::new(input), Path::new(output), size, load_textures)
+}
+
+/// Renders the model at `input` with a transparent background into a square
+/// PNG at `output`, without showing a window
+pub fn render(input: &Path, output: &Path, size: u32, load_textures: bool) -> Result<()> {
+    let obj = importer::load_from_file(input, load_textures)
+        .map_err(|e| anyhow::anyhow!("Failed to load {:?}: {}", input, e))?;
+
+    let _context = offscreen_context("3dobs thumbnail")?;
+    render_object(obj, output, size)
+}
+
+/// Like [`render`] for a model that's already loaded, drawn with the context
+/// that's current so one context can be kept for many renders
+pub fn render_object(obj: importer::Object, output: &Path, size: u32) -> Result<()> {
+    let mesh_shader = shader::Shader::new(
+        &mut shader::ShaderSource {
+            name: "vertex.glsl".to_string(),
+            source: include_str!("../shaders/vertex.glsl").to_string(),
+        },
+        &mut shader::ShaderSource {
+            name: "frag.glsl".to_string(),
+            source: include_str!("../shaders/frag.glsl").to_string(),
+        },
+    )?;
+    utils::set_light_uniforms(&mesh_shader);
+
+    let mut state = State::default();
+    let mut model = Model::new(obj, &mut state);
+    model.finish_textures(state.settings.texture_sampling());
+
+    let render_size = size * SUPERSAMPLING;
+    let pixels = unsafe { draw(&model, &mesh_shader, render_size as i32) };
+    // free the GPU buffers while the context is still alive
+    drop(model);
+
+    let image = image::RgbaImage::from_raw(render_size, render_size, pixels)
+        .context("Failed to read back the thumbnail")?;
+    let image = image::DynamicImage::ImageRgba8(image).flipv().resize_exact(
+        size,
+        size,
+        image::imageops::FilterType::Triangle,
+    );
+    image
+        .save_with_format(output, image::ImageFormat::Png)
+        .with_context(|| format!("Failed to save the thumbnail to {:?}", output))?;
+
+    Ok(())
+}
+
+/// Creates a hidden window and makes its GL context current, for drawing
+/// without showing anything. The context lives as long as the window.
+pub fn offscreen_context(
+    title: &str,
+) -> Result<(glfw::Window, Receiver<(f64, glfw::WindowEvent)>)> {
+    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS)?;
+    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
+    glfw.window_hint(glfw::WindowHint::OpenGlProfile(
+        glfw::OpenGlProfileHint::Core,
+    ));
+    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+    glfw.window_hint(glfw::WindowHint::Visible(false));
+
+    let (mut window, events) = glfw
+        .create_window(1, 1, title, glfw::WindowMode::Windowed)
+        .context("Failed to create an OpenGL context")?;
+    window.make_current();
+    gl::load(|e| window.get_proc_address(e) as *const std::os::raw::c_void);
+
+    Ok((window, events))
+}
+
+/// Draws the model into an offscreen framebuffer and reads back its pixels
+unsafe fn draw(model: &Model, shader: &shader::Shader, size: i32) -> Vec<u8> {
+    let (mut fbo, mut texture, mut rbo) = (0, 0, 0);
+    gl::GenFramebuffers(1, &mut fbo);
+    gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+
+    gl::GenTextures(1, &mut texture);
+    gl_state::bind_texture(0, texture);
+    gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        gl::RGBA8 as i32,
+        size,
+        size,
+        0,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        std::ptr::null(),
+    );
+    gl::FramebufferTexture2D(
+        gl::FRAMEBUFFER,
+        gl::COLOR_ATTACHMENT0,
+        gl::TEXTURE_2D,
+        texture,
+        0,
+    );
+
+    gl::GenRenderbuffers(1, &mut rbo);
+    gl::BindRenderbuffer(gl::RENDERBUFFER, rbo);
+    gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, size, size);
+    gl::FramebufferRenderbuffer(
+        gl::FRAMEBUFFER,
+        gl::DEPTH_STENCIL_ATTACHMENT,
+        gl::RENDERBUFFER,
+        rbo,
+    );
+
+    gl::Viewport(0, 0, size, size);
+    gl_state::enable(gl::DEPTH_TEST);
+    gl_state::enable(gl::BLEND);
+    // keep the background's alpha at 0 and the model's at 1 where it's opaque
+    gl::BlendFuncSeparate(
+        gl::SRC_ALPHA,
+        gl::ONE_MINUS_SRC_ALPHA,
+        gl::ONE,
+        gl::ONE_MINUS_SRC_ALPHA,
+    );
+    gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+    gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+    // frame the whole model from the view direction
+    let aabb = model.world_aabb();
+    let center = aabb.center();
+    let radius = glm::length(aabb.max - aabb.min) / 2.0;
+    let fov: f32 = 30.0;
+    let distance = radius / (fov.to_radians() / 2.0).tan();
+    let direction = glm::normalize(glm::vec3(
+        VIEW_DIRECTION[0],
+        VIEW_DIRECTION[1],
+        VIEW_DIRECTION[2],
+    ));
+    let position = center + direction * distance;
+
+    let view_mat = glm::ext::look_at(position, center, glm::vec3(0.0, 1.0, 0.0));
+    let projection_mat = glm::ext::perspective(
+        glm::radians(fov),
+        1.0,
+        camera::NEAR_PLANE,
+        (distance + radius) * 2.0,
+    );
+
+    shader.use_shader();
+    shader.set_mat4fv("view", &view_mat);
+    shader.set_mat4fv("projection", &projection_mat);
+    shader.set_3fv("spotLight.position", position);
+    shader.set_3fv("spotLight.direction", -direction);
+    shader.set_3fv("viewPos", position);
+
+    model.draw(shader, true, None);
+
+    let mut pixels = vec![0u8; (size * size * 4) as usize];
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+    gl::ReadPixels(
+        0,
+        0,
+        size,
+        size,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        pixels.as_mut_ptr() as *mut std::ffi::c_void,
+    );
+
+    gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    gl::DeleteRenderbuffers(1, &rbo);
+    gl_state::delete_textures(&[texture]);
+    gl::DeleteFramebuffers(1, &fbo);
+
+    pixels
+}
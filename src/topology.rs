@@ -0,0 +1,293 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{debug_draw::DebugDraw, mesh::Vertex, model::Model};
+
+pub const BOUNDARY_COLOR: glm::Vec3 = glm::Vec3 {
+    x: 1.0,
+    y: 0.85,
+    z: 0.1,
+};
+pub const NON_MANIFOLD_COLOR: glm::Vec3 = glm::Vec3 {
+    x: 1.0,
+    y: 0.15,
+    z: 0.15,
+};
+pub const FLIPPED_COLOR: glm::Vec3 = glm::Vec3 {
+    x: 0.2,
+    y: 0.8,
+    z: 1.0,
+};
+
+/// A triangle of a model, as the index of its mesh and of the triangle in it
+pub type FaceRef = (usize, usize);
+
+/// Problems with how the triangles of a model connect, the edges and faces
+/// are in the model's own coordinates
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub triangles: usize,
+    /// Edges with only one face, the mesh has a hole or an open side there
+    pub boundary_edges: Vec<[glm::Vec3; 2]>,
+    /// Edges shared by more than two faces
+    pub non_manifold_edges: Vec<[glm::Vec3; 2]>,
+    /// Faces wound the other way than most of the faces connected to them
+    pub flipped_faces: Vec<FaceRef>,
+    /// Triangles with two corners on the same position
+    pub degenerate_faces: usize,
+}
+
+/// Vertices are welded by their exact position since the importers split
+/// them wherever the normals or UVs differ
+pub fn position_key(p: glm::Vec3) -> [u32; 3] {
+    // treat -0.0 and 0.0 as the same position
+    [p.x + 0.0, p.y + 0.0, p.z + 0.0].map(f32::to_bits)
+}
+
+/// Twice the area of the triangle in the direction of its normal
+pub fn face_normal([a, b, c]: [glm::Vec3; 3]) -> glm::Vec3 {
+    glm::cross(b - a, c - a)
+}
+
+/// Area weighted normals of the faces around each position, only averaging
+/// faces less than `crease_angle` degrees apart. Vertices are split where
+/// their corners end up with different normals, the triangles keep their
+/// order. The normals are shared across meshes so there are no seams
+/// between them.
+pub fn smooth_normals(
+    meshes: &[(&[Vertex], &[u32])],
+    crease_angle: f32,
+) -> Vec<(Vec<Vertex>, Vec<u32>)> {
+    let crease_cos = crease_angle.to_radians().cos();
+    let corners = |vertices: &[Vertex], triangle: &[u32]| {
+        [0, 1, 2].map(|i| vertices[triangle[i] as usize].position)
+    };
+
+    let mut around: HashMap<[u32; 3], Vec<glm::Vec3>> = HashMap::new();
+    for (vertices, indices) in meshes {
+        for triangle in indices.chunks_exact(3) {
+            let positions = corners(vertices, triangle);
+            let normal = face_normal(positions);
+            if glm::length(normal) == 0.0 {
+                continue;
+            }
+            for p in positions {
+                around.entry(position_key(p)).or_default().push(normal);
+            }
+        }
+    }
+
+    let mut smoothed = Vec::with_capacity(meshes.len());
+    for (mesh_vertices, mesh_indices) in meshes {
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(mesh_vertices.len());
+        let mut indices = Vec::with_capacity(mesh_indices.len());
+        let mut remap: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+
+        for triangle in mesh_indices.chunks_exact(3) {
+            let positions = corners(mesh_vertices, triangle);
+            let normal = face_normal(positions);
+            let unit = (glm::length(normal) > 0.0).then(|| glm::normalize(normal));
+
+            for (corner, &index) in triangle.iter().enumerate() {
+                let vertex = &mesh_vertices[index as usize];
+                let sum = around
+                    .get(&position_key(positions[corner]))
+                    .into_iter()
+                    .flatten()
+                    .filter(|n| unit.is_none_or(|u| glm::dot(glm::normalize(**n), u) >= crease_cos))
+                    .fold(glm::vec3(0.0, 0.0, 0.0), |sum, n| sum + *n);
+                let normal = if glm::length(sum) > 0.0 {
+                    glm::normalize(sum)
+                } else {
+                    vertex.normal
+                };
+
+                let new_index = *remap
+                    .entry((index, [normal.x, normal.y, normal.z].map(f32::to_bits)))
+                    .or_insert_with(|| {
+                        vertices.push(Vertex {
+                            normal,
+                            ..vertex.clone()
+                        });
+                        vertices.len() as u32 - 1
+                    });
+                indices.push(new_index);
+            }
+        }
+
+        smoothed.push((vertices, indices));
+    }
+    smoothed
+}
+
+/// The welded vertex ids of each face of the model, `None` for faces that
+/// collapse into a line or point
+pub fn welded_faces(model: &Model) -> Vec<(FaceRef, Option<[u32; 3]>)> {
+    let mut ids: HashMap<[u32; 3], u32> = HashMap::new();
+    let mut faces = vec![];
+
+    for (mesh_idx, mesh) in model.meshes.iter().enumerate() {
+        for (triangle, indices) in mesh.indices.chunks_exact(3).enumerate() {
+            let welded = [0, 1, 2].map(|i| {
+                let key = position_key(mesh.vertices[indices[i] as usize].position);
+                let next = ids.len() as u32;
+                *ids.entry(key).or_insert(next)
+            });
+            let [a, b, c] = welded;
+            let valid = a != b && b != c && c != a;
+            faces.push(((mesh_idx, triangle), valid.then_some(welded)));
+        }
+    }
+
+    faces
+}
+
+/// The faces around each edge, keyed by its vertex ids in ascending order.
+/// The flag is whether the face goes along the edge in ascending order.
+pub fn edge_faces(
+    faces: &[(FaceRef, Option<[u32; 3]>)],
+) -> HashMap<(u32, u32), Vec<(usize, bool)>> {
+    let mut edges: HashMap<(u32, u32), Vec<(usize, bool)>> = HashMap::new();
+
+    for (i, (_, welded)) in faces.iter().enumerate() {
+        let Some([a, b, c]) = *welded else {
+            continue;
+        };
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            edges
+                .entry((from.min(to), from.max(to)))
+                .or_default()
+                .push((i, from < to));
+        }
+    }
+
+    edges
+}
+
+/// Orients the faces connected through manifold edges consistently and
+/// returns the ones that disagree with the majority of their connected part
+pub fn inconsistent_faces(faces: &[(FaceRef, Option<[u32; 3]>)]) -> Vec<usize> {
+    let edges = edge_faces(faces);
+    let mut neighbors: Vec<Vec<(usize, bool)>> = vec![vec![]; faces.len()];
+    for around in edges.values() {
+        if let [(f1, dir1), (f2, dir2)] = around[..] {
+            // two faces wound the same way go along their shared edge in
+            // opposite directions
+            let same_winding = dir1 != dir2;
+            neighbors[f1].push((f2, same_winding));
+            neighbors[f2].push((f1, same_winding));
+        }
+    }
+
+    // flipped[i] is relative to the first face of its connected part
+    let mut flipped: Vec<Option<bool>> = vec![None; faces.len()];
+    let mut inconsistent = vec![];
+    for start in 0..faces.len() {
+        if flipped[start].is_some() || faces[start].1.is_none() {
+            continue;
+        }
+
+        let mut part = vec![start];
+        let mut queue = VecDeque::from([start]);
+        flipped[start] = Some(false);
+        while let Some(face) = queue.pop_front() {
+            let face_flipped = flipped[face].unwrap_or_default();
+            for &(neighbor, same_winding) in &neighbors[face] {
+                if flipped[neighbor].is_none() {
+                    flipped[neighbor] = Some(face_flipped ^ !same_winding);
+                    part.push(neighbor);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        let flipped_count = part
+            .iter()
+            .filter(|f| flipped[**f].unwrap_or_default())
+            .count();
+        // the orientation most of the part agrees on is taken as the right one
+        let minority = flipped_count * 2 < part.len();
+        inconsistent.extend(
+            part.into_iter()
+                .filter(|f| flipped[*f].unwrap_or_default() == minority),
+        );
+    }
+
+    inconsistent
+}
+
+impl Analysis {
+    pub fn new(model: &Model) -> Self {
+        let faces = welded_faces(model);
+        let position = |(mesh, triangle): FaceRef, corner: usize| {
+            let mesh = &model.meshes[mesh];
+            mesh.vertices[mesh.indices[triangle * 3 + corner] as usize].position
+        };
+
+        let mut analysis = Analysis {
+            triangles: faces.len(),
+            degenerate_faces: faces.iter().filter(|(_, w)| w.is_none()).count(),
+            ..Default::default()
+        };
+
+        for ((a, b), around) in edge_faces(&faces) {
+            if around.len() == 2 {
+                continue;
+            }
+
+            // find the positions of the edge's ends through its first face
+            let (face, welded) = &faces[around[0].0];
+            let welded = welded.unwrap_or_default();
+            let corner = |id| welded.iter().position(|w| *w == id).unwrap_or_default();
+            let edge = [position(*face, corner(a)), position(*face, corner(b))];
+
+            if around.len() == 1 {
+                analysis.boundary_edges.push(edge);
+            } else {
+                analysis.non_manifold_edges.push(edge);
+            }
+        }
+
+        analysis.flipped_faces = inconsistent_faces(&faces)
+            .into_iter()
+            .map(|i| faces[i].0)
+            .collect();
+
+        analysis
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.boundary_edges.is_empty()
+            && self.non_manifold_edges.is_empty()
+            && self.flipped_faces.is_empty()
+            && self.degenerate_faces == 0
+    }
+
+    /// Overlays the problem edges and the outlines of the flipped faces
+    pub fn draw(&self, model: &Model, debug: &mut DebugDraw) {
+        let transform = model.transform();
+        let to_world = |p: glm::Vec3| {
+            let p = transform * glm::vec4(p.x, p.y, p.z, 1.0);
+            glm::vec3(p.x / p.w, p.y / p.w, p.z / p.w)
+        };
+
+        for [a, b] in &self.boundary_edges {
+            debug.line(to_world(*a), to_world(*b), BOUNDARY_COLOR);
+        }
+        for [a, b] in &self.non_manifold_edges {
+            debug.line(to_world(*a), to_world(*b), NON_MANIFOLD_COLOR);
+        }
+        for &(mesh, triangle) in &self.flipped_faces {
+            let Some(mesh) = model.meshes.get(mesh) else {
+                continue;
+            };
+            let Some(indices) = mesh.indices.get(triangle * 3..triangle * 3 + 3) else {
+                continue;
+            };
+            let [a, b, c] =
+                [0, 1, 2].map(|i| to_world(mesh.vertices[indices[i] as usize].position));
+            debug.line(a, b, FLIPPED_COLOR);
+            debug.line(b, c, FLIPPED_COLOR);
+            debug.line(c, a, FLIPPED_COLOR);
+        }
+    }
+}
@@ -0,0 +1,1898 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        PoisonError, RwLock,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+static CURRENT_LANGUAGE: AtomicUsize = AtomicUsize::new(0);
+/// The strings of the current language by key, with the English ones filling
+/// in for those that aren't translated. Built when the language is selected
+/// so the catalogs aren't searched for every label of every frame.
+static STRINGS: RwLock<Option<HashMap<&'static str, &'static str>>> = RwLock::new(None);
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    /// The name of the language in that language, so it can be found
+    /// in the selector regardless of what the UI is currently set to
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+        }
+    }
+
+    fn catalog(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => EN,
+            Language::Spanish => ES,
+        }
+    }
+}
+
+pub fn set_language(lang: Language) {
+    let idx = Language::ALL.iter().position(|l| *l == lang).unwrap_or(0);
+    CURRENT_LANGUAGE.store(idx, Ordering::Relaxed);
+
+    // the translations come last to replace the English strings
+    let strings = EN.iter().chain(lang.catalog()).copied().collect();
+    *STRINGS.write().unwrap_or_else(PoisonError::into_inner) = Some(strings);
+}
+
+pub fn current_language() -> Language {
+    Language::ALL[CURRENT_LANGUAGE.load(Ordering::Relaxed)]
+}
+
+fn lookup(key: &str) -> Option<&'static str> {
+    let strings = STRINGS.read().unwrap_or_else(PoisonError::into_inner);
+    match strings.as_ref() {
+        Some(strings) => strings.get(key).copied(),
+        // nothing was selected yet
+        None => {
+            drop(strings);
+            set_language(current_language());
+            lookup(key)
+        }
+    }
+}
+
+/// Translates a UI string key into the currently selected language.
+/// Falls back to English if the key is missing from the catalog, and to
+/// the key itself if it's missing from the English catalog too.
+pub fn tr(key: &'static str) -> &'static str {
+    lookup(key).unwrap_or(key)
+}
+
+/// The help text of a setting or toggle in the currently selected language.
+/// It's kept in the catalogs next to the label, under the label's key with
+/// `_tooltip` appended, so `None` if there's none for `key`.
+pub fn tooltip(key: &str) -> Option<&'static str> {
+    lookup(&format!("{}_tooltip", key))
+}
+
+const EN: &[(&str, &str)] = &[
+    // menus
+    ("menu.file", "File"),
+    ("menu.file.import", "Import Model(s)"),
+    ("menu.file.open_url", "Open URL..."),
+    ("menu.file.skip_textures", "Import Without Textures"),
+    (
+        "menu.file.skip_textures_tooltip",
+        "Only load the geometry and material colors, much faster for big models",
+    ),
+    ("menu.file.import_heightmap", "Import Heightmap..."),
+    ("menu.file.export_active", "Export Active Model..."),
+    (
+        "menu.file.export_active_tooltip",
+        "Saves the transformed model as OBJ and MTL, with its textures copied next to them",
+    ),
+    ("menu.file.browse_folder", "Browse Folder"),
+    (
+        "menu.file.browse_folder_tooltip",
+        "Step through the models in the active model's folder, one at a time",
+    ),
+    ("menu.file.new_primitive", "New Primitive"),
+    ("primitive.cube", "Cube"),
+    ("primitive.sphere", "Sphere"),
+    ("primitive.torus", "Torus"),
+    ("primitive.plane", "Plane"),
+    ("primitive.cylinder", "Cylinder"),
+    ("primitive.segments", "Segments"),
+    (
+        "primitive.segments_tooltip",
+        "Subdivisions around curved primitives and along the sides of flat ones",
+    ),
+    ("menu.file.settings", "Settings"),
+    ("menu.file.quit", "Quit"),
+    ("menu.edit", "Edit"),
+    ("menu.edit.undo", "Undo"),
+    ("menu.edit.redo", "Redo"),
+    ("menu.view", "View"),
+    ("menu.view.grid", "Show Grid"),
+    (
+        "menu.view.grid_tooltip",
+        "Draws the ground plane grid under the models",
+    ),
+    ("menu.view.grid_labels", "Show Grid Labels"),
+    (
+        "menu.view.grid_labels_tooltip",
+        "Writes the distance of the grid lines from the origin next to them",
+    ),
+    ("menu.view.scale_bar", "Show Scale Bar"),
+    (
+        "menu.view.scale_bar_tooltip",
+        "A length in the active model's units for telling sizes, captures include it too",
+    ),
+    ("menu.view.aabb", "Draw Bounding Box"),
+    (
+        "menu.view.aabb_tooltip",
+        "Draws the axis aligned box around each selected model",
+    ),
+    ("menu.view.normals", "Show Normals"),
+    (
+        "menu.view.normals_tooltip",
+        "Draws a line along the normal of every vertex, to find flipped faces",
+    ),
+    ("menu.view.camera_path", "Camera Path"),
+    ("menu.view.slicing", "Slicing Preview"),
+    ("menu.view.materials", "Materials"),
+    ("menu.view.measurements", "Measurements"),
+    ("menu.view.annotations", "Show Notes"),
+    (
+        "menu.view.annotations_tooltip",
+        "Shows the notes pinned to the models in the viewport",
+    ),
+    ("menu.view.ghosts", "Show Ghosts"),
+    (
+        "menu.view.ghosts_tooltip",
+        "Shows where the selected models were when this was turned on",
+    ),
+    ("menu.view.library", "Library"),
+    (
+        "menu.view.library_tooltip",
+        "Models in the folders picked for the library, searchable by their notes and tags",
+    ),
+    ("menu.view.online", "Online Models"),
+    ("menu.help", "Help"),
+    ("menu.help.keybinds", "Keybinds"),
+    ("menu.help.about", "About"),
+    ("menu.help.load_samples", "Load Sample Models"),
+    (
+        "menu.help.load_samples_tooltip",
+        "Downloads a few small test models once and loads them:",
+    ),
+    ("menu.mem", "Mem"),
+    ("menu.draw_calls", "Draw calls"),
+    ("menu.material_changes", "Material changes"),
+    ("menu.mesh_changes", "Mesh changes"),
+    ("menu.culled_meshes", "Meshes out of view"),
+    // file dialog
+    ("dialog.import.title", "Import Model(s)"),
+    ("dialog.import.all", "All supported files"),
+    ("dialog.import_heightmap.title", "Import heightmap"),
+    ("dialog.import_heightmap.images", "Images"),
+    ("dialog.export.title", "Export Selected Models"),
+    // windows
+    ("window.about", "About"),
+    ("window.settings", "Settings"),
+    ("window.keybinds", "Keybinds"),
+    ("window.objects", "Objects"),
+    ("window.annotations", "Notes"),
+    ("window.measurements", "Measurements"),
+    ("window.markup", "Annotate Capture"),
+    ("window.script", "Script"),
+    (
+        "script.hint",
+        "Type Rhai code, \"help()\" lists the functions",
+    ),
+    ("script.queued", "Queued"),
+    ("script.stop", "Stop"),
+    ("markup.arrow", "Arrow"),
+    ("markup.rectangle", "Rectangle"),
+    ("markup.freehand", "Freehand"),
+    ("markup.text", "Text"),
+    ("markup.text_hint", "Type and press Enter"),
+    ("markup.undo", "Undo"),
+    ("markup.save", "Save"),
+    ("markup.copy", "Copy to Clipboard"),
+    ("window.console", "Console"),
+    ("window.viewer", "Viewer"),
+    ("window.crash_report", "Crash Report"),
+    // about
+    ("about.version", "Version"),
+    ("about.made_by", "Made by"),
+    // settings
+    ("settings.profile", "Profile"),
+    (
+        "settings.profile_tooltip",
+        "Named sets of settings, the one picked here is used again on the next start",
+    ),
+    ("settings.profile.default", "Default"),
+    ("settings.profile.name_hint", "New profile"),
+    ("settings.profile.create", "Create"),
+    (
+        "settings.one_instance",
+        "Only allow one program instance (Reboot required when enabling)",
+    ),
+    (
+        "settings.one_instance_tooltip",
+        "Files opened while 3dobs is running go to the open window instead of a new one",
+    ),
+    (
+        "settings.log_to_file",
+        "Write log to file (Reboot required)",
+    ),
+    (
+        "settings.log_to_file_tooltip",
+        "Also writes the console to a log file next to the program, from the next start",
+    ),
+    ("settings.console_max_history", "Console history size"),
+    (
+        "settings.console_max_history_tooltip",
+        "How many messages the console keeps before dropping the oldest",
+    ),
+    ("settings.language", "Language"),
+    ("settings.language_tooltip", "The language of the interface"),
+    ("settings.theme", "Theme"),
+    ("settings.theme_tooltip", "The colors of the interface"),
+    ("settings.accent_color", "Accent Color"),
+    (
+        "settings.accent_color_tooltip",
+        "The color of buttons, tabs and selections",
+    ),
+    ("settings.reset", "Reset"),
+    ("settings.texture_filter", "Texture Filtering"),
+    (
+        "settings.texture_filter_tooltip",
+        "How textures are smoothed when they're drawn bigger or smaller than they are",
+    ),
+    ("settings.anisotropy", "Anisotropic Filtering"),
+    (
+        "settings.anisotropy_tooltip",
+        "Keeps textures sharp on surfaces seen at a steep angle, higher levels cost more",
+    ),
+    ("settings.memory_budget", "Memory Budget"),
+    ("settings.memory_budget.unlimited", "No limit"),
+    (
+        "settings.memory_budget_tooltip",
+        "Unselected models are unloaded above this and loaded back in when selected",
+    ),
+    ("settings.max_texture_size", "Max Texture Size"),
+    ("settings.max_texture_size.unlimited", "No limit"),
+    (
+        "settings.max_texture_size_tooltip",
+        "Textures larger than this are downscaled when a model is loaded",
+    ),
+    ("settings.texture_threads", "Texture Decoding Threads"),
+    (
+        "settings.texture_threads_tooltip",
+        "Textures are drawn as a checkerboard until they're decoded. 0 uses every CPU core.",
+    ),
+    (
+        "settings.low_res_inactive_textures",
+        "Lower resolution textures for inactive models",
+    ),
+    (
+        "settings.low_res_inactive_textures_tooltip",
+        "Models that aren't selected or are hidden only keep their lower mip levels in use",
+    ),
+    ("settings.compact_vertices", "Compact vertex buffers"),
+    (
+        "settings.compact_vertices_tooltip",
+        "Halves the GPU memory of models loaded from now on, at a small loss of precision",
+    ),
+    ("settings.pick_parts", "Pick objects to import"),
+    (
+        "settings.pick_parts_tooltip",
+        "Lists the objects of OBJ files with more than one so only some get imported",
+    ),
+    ("settings.thingiverse_token", "Thingiverse Token"),
+    (
+        "settings.thingiverse_token_tooltip",
+        "App token from thingiverse.com/apps, used by the Online Models window",
+    ),
+    ("settings.register_file_types", "Register File Types"),
+    ("settings.auto_clip_planes", "Automatic Clip Planes"),
+    (
+        "settings.auto_clip_planes_tooltip",
+        "Fits the near and far planes to the displayed models so huge ones don't clip or z-fight",
+    ),
+    ("settings.reversed_z", "Reversed-Z Depth"),
+    (
+        "settings.reversed_z_tooltip",
+        "Uses a float depth buffer with reversed-Z for much better depth precision",
+    ),
+    (
+        "settings.reversed_z_unsupported",
+        "Needs OpenGL 4.5 or GL_ARB_clip_control, which this driver doesn't have",
+    ),
+    ("settings.vsync", "VSync"),
+    (
+        "settings.vsync_tooltip",
+        "Waits for the monitor before showing each frame, which stops tearing",
+    ),
+    ("settings.gl_version", "OpenGL version"),
+    ("settings.gl_version.default", "3.3 (default)"),
+    ("settings.gl_profile", "OpenGL profile"),
+    ("gl_profile.core", "Core"),
+    ("gl_profile.compatibility", "Compatibility"),
+    ("settings.restart_tooltip", "Takes effect after restarting"),
+    ("settings.software_rendering", "Software rendering"),
+    (
+        "settings.software_rendering_tooltip",
+        "Draws on the CPU for broken GPU drivers, after restarting. Slow.",
+    ),
+    ("settings.discrete_gpu", "Use the discrete GPU"),
+    (
+        "settings.discrete_gpu_tooltip",
+        "On laptops with two GPUs, after restarting. Always on for Windows.",
+    ),
+    ("settings.capture_dir", "Capture Folder"),
+    ("settings.capture_dir.working_dir", "Working directory"),
+    ("settings.capture_dir.browse", "Browse..."),
+    ("dialog.capture_dir.title", "Folder for captures"),
+    ("settings.capture_name", "Capture Name"),
+    (
+        "settings.capture_name_tooltip",
+        "Name of new captures without the extension. {model}, {date}, {time}, {timestamp}, \
+         {width} and {height} are replaced with the active model's name, the date and \
+         time, the seconds since 1970 and the size of the viewport.",
+    ),
+    ("settings.open_captures", "Open Captures After Saving"),
+    (
+        "settings.open_captures_tooltip",
+        "Opens captures in the image viewer once they're saved",
+    ),
+    ("settings.fps_cap", "FPS Cap"),
+    (
+        "settings.fps_cap_tooltip",
+        "The most frames drawn each second, lower caps use less power",
+    ),
+    ("settings.fps_cap.unlimited", "Unlimited"),
+    ("settings.idle_mode", "Power Saving When Idle"),
+    (
+        "settings.idle_mode_tooltip",
+        "Stops drawing a second after the last input until something changes again",
+    ),
+    ("settings.stl_units", "Assume STL Units"),
+    (
+        "settings.stl_units_tooltip",
+        "Units used for STL files whose header doesn't say which they're in",
+    ),
+    (
+        "settings.register_file_types_tooltip",
+        "Open OBJ and STL files with 3dobs when they're double-clicked in the file manager",
+    ),
+    ("settings.tab.general", "General"),
+    ("settings.tab.formats", "Formats"),
+    (
+        "settings.formats.description",
+        "Options each format is imported with, also where the import options window starts",
+    ),
+    ("settings.formats.reset", "Reset"),
+    (
+        "settings.formats.remembered",
+        "Options remembered in the import options window are used instead",
+    ),
+    ("settings.formats.forget", "Forget"),
+    ("settings.clear_geometry_cache", "Clear Geometry Cache"),
+    (
+        "settings.clear_geometry_cache_tooltip",
+        "Files that were opened before load from a cache until they change, this deletes it",
+    ),
+    ("texture_filter.nearest", "Nearest"),
+    ("texture_filter.bilinear", "Bilinear"),
+    ("texture_filter.trilinear", "Trilinear"),
+    ("theme.dark", "Dark"),
+    ("theme.light", "Light"),
+    ("theme.high_contrast", "High Contrast"),
+    // crash report
+    (
+        "crash_report.message",
+        "3dobs crashed the last time it was running. A crash report was saved to:",
+    ),
+    ("crash_report.open", "Open Report"),
+    ("crash_report.open_folder", "Open Folder"),
+    ("crash_report.dismiss", "Dismiss"),
+    // keybinds
+    ("keybinds.key", "Key"),
+    ("keybinds.action", "Action"),
+    ("keybinds.drag_drop", "Drag & Drop"),
+    ("keybinds.import", "Import Model(s)"),
+    ("keybinds.quit", "Quit"),
+    ("keybinds.lmb", "Left Mouse Button"),
+    ("keybinds.rotate", "Rotate object"),
+    ("keybinds.scroll", "Scroll"),
+    ("keybinds.zoom", "Zoom camera"),
+    ("keybinds.left_shift", "Left Shift"),
+    ("keybinds.pan", "Pan camera"),
+    ("keybinds.left_ctrl", "Left Ctrl"),
+    ("keybinds.camera_speed", "Increase camera movement speed"),
+    ("keybinds.undo", "Undo"),
+    ("keybinds.redo", "Redo"),
+    ("keybinds.cancel_pick", "Cancel pivot picking"),
+    ("keybinds.arrows", "Arrow Keys"),
+    ("keybinds.navigate_objects", "Navigate the Objects window"),
+    ("keybinds.remove_model", "Remove selected model"),
+    ("keybinds.rename_model", "Rename selected model"),
+    ("keybinds.space", "Space"),
+    ("keybinds.multi_select", "Ctrl/Shift + Click"),
+    ("keybinds.multi_select_action", "Select multiple models"),
+    (
+        "keybinds.browse_folder",
+        "Previous/next model in the folder being browsed",
+    ),
+    (
+        "keybinds.toggle_visibility",
+        "Toggle visibility of selected model",
+    ),
+    // status bar
+    ("status.mode", "Mode"),
+    ("status.importing", "Importing"),
+    ("status.downloading", "Downloading"),
+    ("status.cancel_import", "Cancel"),
+    ("window.open_url", "Open URL"),
+    ("open_url.paste", "Paste"),
+    ("open_url.open", "Open"),
+    ("window.import_heightmap", "Import Heightmap"),
+    ("heightmap.resolution", "Resolution"),
+    (
+        "heightmap.resolution_tooltip",
+        "Most vertices along a side, bigger images are scaled down to this",
+    ),
+    ("heightmap.height_scale", "Height scale"),
+    (
+        "heightmap.height_scale_tooltip",
+        "Height of white pixels, relative to the longer side of the terrain",
+    ),
+    ("heightmap.import", "Import"),
+    ("window.import_options", "Import Options"),
+    ("import_options.up_axis", "Up axis"),
+    (
+        "import_options.up_axis_tooltip",
+        "The axis pointing up in the file, it's turned into +Y",
+    ),
+    ("import_options.format_default", "Format default"),
+    ("import_options.left_handed", "Left-handed"),
+    (
+        "import_options.left_handed_tooltip",
+        "Mirrors the model along Z and rewinds its faces",
+    ),
+    ("import_options.x_up", "X up"),
+    ("import_options.y_up", "Y up"),
+    ("import_options.z_up", "Z up"),
+    ("import_options.scale", "Scale"),
+    ("import_options.flip_v", "Flip texture V"),
+    (
+        "import_options.flip_v_tooltip",
+        "For textures that come out upside down",
+    ),
+    ("import_options.smooth", "Smooth normals"),
+    (
+        "import_options.smooth_tooltip",
+        "Edges sharper than the angle are kept sharp",
+    ),
+    ("import_options.merge_meshes", "Merge meshes"),
+    ("import_options.remember", "Remember for this extension"),
+    ("import_options.import", "Import"),
+    ("window.pick_parts", "Pick Objects"),
+    ("parts.select_all", "All"),
+    ("parts.select_none", "None"),
+    ("parts.unnamed", "(unnamed)"),
+    ("parts.triangles", "triangles"),
+    ("parts.import", "Import"),
+    ("notification.import_failed", "Couldn't import"),
+    ("notification.details", "Details"),
+    ("notification.copy", "Copy"),
+    ("notification.dismiss", "Dismiss"),
+    ("path.reveal", "Show in Folder"),
+    ("path.copy", "Copy Path"),
+    ("notification.click_to_open", "Click to open"),
+    ("notification.loaded", "Loaded"),
+    ("notification.texture_missing", "Texture missing:"),
+    ("notification.capture_saved", "Screenshot saved"),
+    ("notes.tags", "Tags"),
+    ("notes.tags_hint", "Tags, separated by commas"),
+    ("notes.edit", "Edit Notes"),
+    (
+        "notes.edit_tooltip",
+        "Notes and tags for the model file, kept by 3dobs without changing the file",
+    ),
+    ("notes.save", "Save"),
+    ("notes.cancel", "Cancel"),
+    ("library.folders", "Folders"),
+    (
+        "library.no_folders",
+        "No folders yet, their models are found in subfolders too",
+    ),
+    ("library.add_folder", "Add Folder"),
+    ("library.reindex", "Re-index"),
+    (
+        "library.reindex_tooltip",
+        "Looks through the folders again, only new and changed files are read",
+    ),
+    ("library.indexing", "Indexing"),
+    ("library.search_hint", "Search by name, notes or tags"),
+    ("library.format", "Format"),
+    ("library.any_format", "Any"),
+    ("library.min_size", "Min"),
+    ("library.max_size", "Max"),
+    (
+        "library.max_size_tooltip",
+        "Only shows files between these sizes, 0 for no limit",
+    ),
+    ("library.only_duplicates", "Only Duplicates"),
+    (
+        "library.only_duplicates_tooltip",
+        "Only shows the files whose geometry is the same or nearly the same as another's",
+    ),
+    ("library.duplicates", "Duplicates"),
+    ("library.identical", "identical"),
+    ("library.similar", "nearly identical"),
+    ("library.import_selected", "Import Selected"),
+    (
+        "library.empty",
+        "The library is empty. Add a folder, or notes to a model with Edit Notes.",
+    ),
+    ("library.missing", "missing"),
+    ("library.open", "Open"),
+    ("library.triangles", "triangles"),
+    ("library.models", "models"),
+    ("notification.library_indexed", "Library indexed"),
+    ("dialog.library_folder.title", "Add a folder to the library"),
+    ("notification.recording_saved", "Recording saved"),
+    ("notification.recording_failed", "Recording failed"),
+    (
+        "notification.annotated_capture_saved",
+        "Annotated screenshot saved",
+    ),
+    (
+        "import_error.not_found",
+        "The file or one it refers to doesn't exist",
+    ),
+    (
+        "import_error.permission_denied",
+        "There's no permission to read the file",
+    ),
+    (
+        "import_error.truncated",
+        "The file ends too early, it might not have finished saving",
+    ),
+    (
+        "import_error.invalid_data",
+        "The file is damaged or isn't in the format its name says",
+    ),
+    ("import_error.line", "Error on line"),
+    (
+        "import_error.unsupported",
+        "The file isn't in a format 3dobs can import",
+    ),
+    ("import_error.cancelled", "The import was cancelled"),
+    (
+        "import_error.cancelled_resumable",
+        "The import was cancelled, it picks up where it stopped next time",
+    ),
+    ("window.online", "Online Models"),
+    ("window.camera_path", "Camera Path"),
+    ("window.note_editor", "Model Notes"),
+    ("window.library", "Library"),
+    ("window.slicing", "Slicing Preview"),
+    ("window.materials", "Materials"),
+    ("materials.no_model", "No model selected"),
+    (
+        "materials.help",
+        "Click a material to highlight the meshes using it",
+    ),
+    ("materials.unloaded", "The model is being loaded back in"),
+    ("materials.name", "Material"),
+    ("materials.triangles", "Triangles"),
+    ("materials.visible", "Visible"),
+    ("camera_path.add_keyframe", "Add Keyframe"),
+    (
+        "camera_path.add_keyframe_tooltip",
+        "Adds the current view as a keyframe at the playhead's time",
+    ),
+    ("camera_path.play", "Play"),
+    ("camera_path.stop", "Stop"),
+    ("camera_path.empty", "No keyframes yet"),
+    ("camera_path.go_to", "Go To"),
+    ("camera_path.fps", "FPS"),
+    ("camera_path.export_frames", "Export Frames"),
+    ("camera_path.export_video", "Export Video"),
+    (
+        "camera_path.export_video_tooltip",
+        "Exports the frames and encodes them into an MP4, needs ffmpeg",
+    ),
+    ("camera_path.exporting", "Exporting frame"),
+    ("camera_path.cancel", "Cancel"),
+    ("dialog.export_frames.title", "Export Frames To"),
+    (
+        "online.no_token",
+        "Add a Thingiverse app token in the settings to search for models",
+    ),
+    ("online.open_settings", "Open Settings"),
+    ("online.search_hint", "Search Thingiverse"),
+    ("online.search", "Search"),
+    ("online.searching", "Searching..."),
+    ("online.by", "by"),
+    ("online.import", "Import"),
+    ("tool.rotate", "Rotate"),
+    ("tool.pan", "Pan"),
+    ("tool.pick_pivot", "Pick Pivot"),
+    ("tool.annotate", "Add Note"),
+    ("tool.inspect", "Inspect"),
+    ("tool.measure", "Measure"),
+    ("topology.analyze", "Analyze Topology"),
+    (
+        "topology.analyze_tooltip",
+        "Finds holes, non-manifold edges and flipped faces and highlights them",
+    ),
+    ("topology.clear", "Clear"),
+    ("topology.clean", "No topology problems found"),
+    ("topology.boundary_edges", "Boundary edges"),
+    ("topology.non_manifold_edges", "Non-manifold edges"),
+    ("topology.flipped_faces", "Flipped faces"),
+    ("topology.degenerate_faces", "Degenerate faces"),
+    ("repair.title", "Repair"),
+    ("repair.recompute_normals", "Recompute Normals"),
+    (
+        "repair.recompute_normals.tooltip",
+        "Replaces the normals with smooth ones from the surrounding faces, keeping sharp edges",
+    ),
+    ("repair.unify_winding", "Unify Winding"),
+    (
+        "repair.unify_winding.tooltip",
+        "Flips the faces wound the other way than most of the faces connected to them",
+    ),
+    ("repair.remove_degenerate", "Remove Degenerate Faces"),
+    (
+        "repair.remove_degenerate.tooltip",
+        "Removes faces without an area and faces that duplicate another one",
+    ),
+    ("repair.fill_holes", "Fill Small Holes"),
+    (
+        "repair.fill_holes.tooltip",
+        "Closes holes with up to 32 edges. Use Undo to revert any repair.",
+    ),
+    ("repair.changed", "changed"),
+    ("repair.triangles", "Triangles"),
+    ("simplify.title", "Simplify"),
+    ("simplify.by_percent", "Percentage"),
+    ("simplify.by_count", "Triangle count"),
+    ("simplify.triangles", "Triangles"),
+    ("simplify.percent", "Target"),
+    ("simplify.preview", "Preview"),
+    (
+        "simplify.preview_tooltip",
+        "Shows the model with fewer triangles, nothing is kept until Apply is pressed",
+    ),
+    ("simplify.apply", "Apply"),
+    ("simplify.cancel", "Cancel"),
+    ("simplify.export", "Export..."),
+    ("bounds.compute", "Compute Hull & Box"),
+    (
+        "bounds.compute_tooltip",
+        "Computes the convex hull and the smallest rotated box that fits around the model",
+    ),
+    ("bounds.computing", "Computing the hull and box..."),
+    ("bounds.clear", "Clear"),
+    ("bounds.hull", "Convex hull"),
+    ("bounds.points", "points"),
+    ("bounds.triangles", "triangles"),
+    ("bounds.hull_volume", "Hull volume"),
+    ("bounds.obb", "Oriented box"),
+    ("bounds.obb_volume", "Box volume"),
+    ("balance.compute", "Center of Mass"),
+    (
+        "balance.compute_tooltip",
+        "Where the model balances if it's solid and evenly dense, and its principal axes",
+    ),
+    ("balance.clear", "Clear"),
+    ("balance.center", "Center of mass"),
+    (
+        "balance.surface_only",
+        "The meshes don't enclose a volume, so this is the balance of the surface",
+    ),
+    ("balance.axis", "Axis"),
+    ("balance.moment", "moment"),
+    ("slicing.enabled", "Slice"),
+    (
+        "slicing.enabled_tooltip",
+        "Cuts away everything above the current layer, the cross-section is highlighted",
+    ),
+    ("slicing.layer_height", "Layer height"),
+    ("slicing.nothing_displayed", "No models are displayed"),
+    ("slicing.play", "Play"),
+    ("slicing.stop", "Stop"),
+    ("slicing.height", "Height"),
+    ("slicing.cap", "Cap the cut"),
+    (
+        "slicing.cap_tooltip",
+        "Fills the cut so the models look solid instead of hollow, only for closed meshes",
+    ),
+    ("slicing.section_color", "Section color"),
+    ("annotations.add", "Add Note"),
+    (
+        "annotations.placing",
+        "Click on the active model to place the note",
+    ),
+    ("annotations.none", "No notes yet"),
+    ("annotations.edit", "Edit"),
+    ("annotations.delete", "Delete"),
+    ("annotations.edit_title", "Edit Note"),
+    ("annotations.save", "Save"),
+    ("annotations.cancel", "Cancel"),
+    ("measure.distance", "Distance"),
+    ("measure.angle", "Angle"),
+    ("measure.snap", "Snap"),
+    (
+        "measure.snap_tooltip",
+        "Moves the clicked points onto the triangle under the cursor",
+    ),
+    ("measure.snap_off", "Off"),
+    ("measure.snap_vertex", "Vertices"),
+    ("measure.snap_edge", "Edges"),
+    ("measure.start", "Measure"),
+    ("measure.placing", "Click points on the active model"),
+    ("measure.delete", "Delete"),
+    ("measure.none", "No measurements yet"),
+    // objects
+    ("objects.vertices", "Vertices"),
+    ("objects.triangles", "Triangles"),
+    ("objects.area", "Area"),
+    ("objects.volume", "Volume"),
+    (
+        "objects.volume_tooltip",
+        "Only meaningful for closed meshes, negative if the faces point inwards",
+    ),
+    (
+        "objects.computing_mass_properties",
+        "Computing area and volume...",
+    ),
+    ("objects.transformations", "Transformations"),
+    ("objects.center_origin", "Center to Origin"),
+    ("objects.drop_to_grid", "Drop to Grid"),
+    ("objects.pick_pivot", "Pick Pivot"),
+    (
+        "objects.pick_pivot_inactive",
+        "Select the model to pick a pivot on it",
+    ),
+    ("objects.reset_pivot", "Reset Pivot"),
+    ("objects.hidden", " (hidden)"),
+    ("objects.unloaded", " (unloaded)"),
+    ("objects.loading", " (loading)"),
+    ("objects.selected", "Selected"),
+    ("objects.toggle_visibility", "Hide/Show"),
+    ("objects.remove_selected", "Remove"),
+    ("objects.export_selected", "Export"),
+    ("objects.move_selected", "Move"),
+    ("objects.group", "Group"),
+    ("objects.group_selected", "Group"),
+    (
+        "objects.group_selected_tooltip",
+        "Puts the selected models into a new group, drag models onto a group to add them",
+    ),
+    ("objects.group_name", "Name"),
+    ("objects.move_group", "Move"),
+    ("objects.ungroup", "Ungroup"),
+    ("objects.remove_from_group", "Remove from Group"),
+    ("objects.rename", "Rename"),
+    ("objects.reload", "Reload"),
+    ("objects.open_in_window", "Open in New Window"),
+    (
+        "objects.open_in_window_tooltip",
+        "Shows the model in a separate window with its own camera, drag to orbit it",
+    ),
+    ("objects.rename_confirm", "OK"),
+    ("objects.rename_cancel", "Cancel"),
+    ("objects.position", "Position"),
+    ("objects.rotation", "Rotation"),
+    ("objects.scale", "Scale"),
+    ("objects.units", "Units"),
+    (
+        "objects.units_tooltip",
+        "Units the file's coordinates are in, taken from the STL header when it says",
+    ),
+    ("objects.size", "Size"),
+    ("units.unknown", "Unknown"),
+    ("units.millimeters", "Millimeters"),
+    ("units.inches", "Inches"),
+    ("stl_colors", "Face Colors"),
+    ("stl_colors.viscam", "VisCAM/SolidView"),
+    ("stl_colors.materialise", "Materialise Magics"),
+    ("stl_colors.ignored", "None"),
+    (
+        "stl_colors.ambiguous_tooltip",
+        "The file could be using either convention, or its attribute bytes aren't colors at all",
+    ),
+    ("objects.reset_position", "Reset Position"),
+    ("objects.reset_rotation", "Reset Rotation"),
+    ("objects.arrange", "Arrange"),
+    (
+        "objects.arrange_tooltip",
+        "Lays the selected models out in a grid on the ground",
+    ),
+    ("objects.reset_scale", "Reset Scale"),
+    ("objects.apply_transform", "Apply Transform"),
+    ("objects.copy_info", "Copy Info"),
+    ("file_info.read", "File Info"),
+    (
+        "file_info.read_tooltip",
+        "Reads the size, date and SHA-256 of the model's file and the comments its exporter left",
+    ),
+    ("file_info.size", "File size"),
+    ("file_info.modified", "Modified"),
+    ("file_info.copy_checksum", "Copy"),
+    ("file_info.metadata", "Embedded info"),
+    ("objects.copy_markdown", "Copy as Markdown"),
+    (
+        "objects.copy_markdown_tooltip",
+        "Copies the counts, size, materials and topology problems as Markdown tables",
+    ),
+    ("objects.mesh_wireframe", "Wireframe"),
+    ("objects.mesh_unlit", "Unlit"),
+    (
+        "objects.mesh_unlit_tooltip",
+        "Draws the mesh in the colors of its materials, without lighting",
+    ),
+    ("objects.mesh_tint", "Solid Color"),
+    ("objects.mesh_reset_overrides", "Reset"),
+    ("objects.pin_ghost", "Pin Ghost"),
+    (
+        "objects.pin_ghost_tooltip",
+        "Moves the ghost to where the model is now",
+    ),
+    ("objects.load_textures", "Load Textures"),
+    (
+        "objects.load_textures_tooltip",
+        "The model was imported without textures, this adds them to its materials",
+    ),
+    ("report.title", "Models in"),
+    ("report.file", "File"),
+    ("report.error", "Error"),
+    ("report.meshes", "Meshes"),
+    ("report.materials", "Materials"),
+    ("report.material", "Material"),
+    ("objects.normalization_factor", "Normalization factor"),
+    (
+        "objects.normalization_factor_tooltip",
+        "Scale that fits the model into the viewer. Models are drawn with it in Normalized mode.",
+    ),
+    ("objects.scale_normalized", "Normalized"),
+    ("objects.scale_original", "Original Size"),
+    ("objects.scale_custom", "Custom"),
+    ("objects.scale_factor", "Scale factor"),
+    (
+        "objects.apply_transform_tooltip",
+        "Bakes the current transform into the vertex data. This can't be undone.",
+    ),
+    // console
+    ("console.clear", "Clear"),
+    ("console.copy_all", "Copy All"),
+    ("console.copy_line", "Copy"),
+    ("console.open_log_folder", "Open Log Folder"),
+    ("console.autoscroll", "Autoscroll"),
+    ("console.search", "Search"),
+    ("console.errors", "Errors"),
+    ("console.warnings", "Warnings"),
+    ("console.info", "Info"),
+    ("console.debug", "Debug"),
+    ("console.source", "Source"),
+    // viewport
+    ("viewport.scene", "Scene"),
+    (
+        "viewport.new_scene_tooltip",
+        "Add a scene with its own models, camera and view options",
+    ),
+    ("viewport.reset_camera", "Reset Camera"),
+    (
+        "viewport.reset_camera_tooltip",
+        "Moves the camera back so the active model fits in view",
+    ),
+    ("viewport.capture", "Capture Scene"),
+    (
+        "viewport.capture_tooltip",
+        "Saves the viewport as an image, after drawing on it in the markup window",
+    ),
+    ("viewport.capture_window", "Capture Window"),
+    (
+        "viewport.capture_window_tooltip",
+        "Saves the whole window as an image, panels included, for bug reports",
+    ),
+    ("viewport.record", "Record"),
+    (
+        "viewport.record_tooltip",
+        "Records the viewport into a video, needs ffmpeg",
+    ),
+    ("viewport.stop_recording", "Stop"),
+    (
+        "viewport.stop_recording_tooltip",
+        "Stops recording, the video is saved once ffmpeg is done with it",
+    ),
+    ("recording.fps", "FPS"),
+    ("recording.duration", "Duration"),
+    (
+        "recording.duration_tooltip",
+        "Stops recording on its own after this many seconds, 0 records until stopped",
+    ),
+    ("recording.format", "Format"),
+    (
+        "recording.format_tooltip",
+        "MP4 plays almost everywhere, WebM can be embedded in web pages and issues",
+    ),
+    ("recording.start", "Start Recording"),
+    ("viewport.wireframe", "Wireframe"),
+    (
+        "viewport.wireframe_tooltip",
+        "Draws only the edges of the triangles",
+    ),
+    ("viewport.inspect", "Inspect"),
+    (
+        "viewport.inspect_tooltip",
+        "Shows the vertices and material of the triangle under the cursor",
+    ),
+    ("inspect.triangle", "Triangle"),
+    ("inspect.vertex", "Vertex"),
+    ("inspect.position", "Position"),
+    ("inspect.normal", "Normal"),
+    ("inspect.material", "Material"),
+    ("inspect.diffuse", "Diffuse"),
+    (
+        "viewport.display_mode_tooltip",
+        "Shows the depth or normal buffer instead of the shaded models",
+    ),
+    ("display_mode.shaded", "Shaded"),
+    ("display_mode.depth", "Depth"),
+    ("display_mode.normals", "Normals"),
+    ("viewport.fov_zoom", "FOV zoom"),
+    (
+        "viewport.fov_zoom_tooltip",
+        "Scrolling narrows the field of view like a lens instead of moving the camera \
+         closer (dolly). Zooming by FOV flattens the perspective, dollying keeps it.",
+    ),
+    ("viewport.show_textures", "Show Textures"),
+    (
+        "viewport.show_textures_tooltip",
+        "Draws the models with their textures, or with their material colors only",
+    ),
+    ("viewport.camera_speed", "Camera Speed"),
+    (
+        "viewport.camera_speed_tooltip",
+        "How fast the camera moves when panning and scrolling",
+    ),
+    ("viewport.rotation_speed", "Rotation Speed"),
+    (
+        "viewport.rotation_speed_tooltip",
+        "How fast models turn while dragging them",
+    ),
+];
+
+const ES: &[(&str, &str)] = &[
+    // menus
+    ("menu.file", "Archivo"),
+    ("menu.file.import", "Importar modelo(s)"),
+    ("menu.file.open_url", "Abrir URL..."),
+    ("menu.file.skip_textures", "Importar sin texturas"),
+    (
+        "menu.file.skip_textures_tooltip",
+        "Cargar solo la geometría y los colores, mucho más rápido con modelos grandes",
+    ),
+    ("menu.file.import_heightmap", "Importar mapa de alturas..."),
+    ("menu.file.export_active", "Exportar modelo activo..."),
+    (
+        "menu.file.export_active_tooltip",
+        "Guarda el modelo transformado como OBJ y MTL, con sus texturas copiadas junto a ellos",
+    ),
+    ("menu.file.browse_folder", "Recorrer carpeta"),
+    (
+        "menu.file.browse_folder_tooltip",
+        "Recorre uno a uno los modelos de la carpeta del modelo activo",
+    ),
+    ("menu.file.new_primitive", "Nueva primitiva"),
+    ("primitive.cube", "Cubo"),
+    ("primitive.sphere", "Esfera"),
+    ("primitive.torus", "Toro"),
+    ("primitive.plane", "Plano"),
+    ("primitive.cylinder", "Cilindro"),
+    ("primitive.segments", "Segmentos"),
+    (
+        "primitive.segments_tooltip",
+        "Subdivisiones alrededor de las primitivas curvas y a lo largo de los lados de las planas",
+    ),
+    ("menu.file.settings", "Ajustes"),
+    ("menu.file.quit", "Salir"),
+    ("menu.edit", "Editar"),
+    ("menu.edit.undo", "Deshacer"),
+    ("menu.edit.redo", "Rehacer"),
+    ("menu.view", "Ver"),
+    ("menu.view.grid", "Mostrar cuadrícula"),
+    (
+        "menu.view.grid_tooltip",
+        "Dibuja la cuadrícula del plano del suelo bajo los modelos",
+    ),
+    (
+        "menu.view.grid_labels",
+        "Mostrar etiquetas de la cuadrícula",
+    ),
+    (
+        "menu.view.grid_labels_tooltip",
+        "Escribe junto a las líneas de la cuadrícula su distancia al origen",
+    ),
+    ("menu.view.scale_bar", "Mostrar barra de escala"),
+    (
+        "menu.view.scale_bar_tooltip",
+        "Una longitud en las unidades del modelo activo, también aparece en las capturas",
+    ),
+    ("menu.view.aabb", "Dibujar caja delimitadora"),
+    (
+        "menu.view.aabb_tooltip",
+        "Dibuja la caja alineada a los ejes alrededor de cada modelo seleccionado",
+    ),
+    ("menu.view.normals", "Mostrar normales"),
+    (
+        "menu.view.normals_tooltip",
+        "Dibuja una línea por la normal de cada vértice, para encontrar caras invertidas",
+    ),
+    ("menu.view.camera_path", "Recorrido de cámara"),
+    ("menu.view.slicing", "Vista previa de capas"),
+    ("menu.view.materials", "Materiales"),
+    ("menu.view.measurements", "Medidas"),
+    ("menu.view.annotations", "Mostrar notas"),
+    (
+        "menu.view.annotations_tooltip",
+        "Muestra en el visor las notas fijadas a los modelos",
+    ),
+    ("menu.view.ghosts", "Mostrar fantasmas"),
+    (
+        "menu.view.ghosts_tooltip",
+        "Muestra dónde estaban los modelos seleccionados al activarlo",
+    ),
+    ("menu.view.library", "Biblioteca"),
+    (
+        "menu.view.library_tooltip",
+        "Modelos de las carpetas elegidas para la biblioteca, con búsqueda por notas y etiquetas",
+    ),
+    ("menu.view.online", "Modelos en línea"),
+    ("menu.help", "Ayuda"),
+    ("menu.help.keybinds", "Atajos de teclado"),
+    ("menu.help.about", "Acerca de"),
+    ("menu.help.load_samples", "Cargar modelos de ejemplo"),
+    (
+        "menu.help.load_samples_tooltip",
+        "Descarga una vez unos pequeños modelos de prueba y los carga:",
+    ),
+    ("menu.mem", "Mem"),
+    ("menu.draw_calls", "Llamadas de dibujo"),
+    ("menu.material_changes", "Cambios de material"),
+    ("menu.mesh_changes", "Cambios de malla"),
+    ("menu.culled_meshes", "Mallas fuera de vista"),
+    // file dialog
+    ("dialog.import.title", "Importar modelo(s)"),
+    ("dialog.import.all", "Todos los archivos compatibles"),
+    ("dialog.import_heightmap.title", "Importar mapa de alturas"),
+    ("dialog.import_heightmap.images", "Imágenes"),
+    ("dialog.export.title", "Exportar modelos seleccionados"),
+    // windows
+    ("window.about", "Acerca de"),
+    ("window.settings", "Ajustes"),
+    ("window.keybinds", "Atajos de teclado"),
+    ("window.objects", "Objetos"),
+    ("window.annotations", "Notas"),
+    ("window.measurements", "Medidas"),
+    ("window.markup", "Anotar captura"),
+    ("window.script", "Script"),
+    (
+        "script.hint",
+        "Escribe código Rhai, \"help()\" muestra las funciones",
+    ),
+    ("script.queued", "En cola"),
+    ("script.stop", "Detener"),
+    ("markup.arrow", "Flecha"),
+    ("markup.rectangle", "Rectángulo"),
+    ("markup.freehand", "Mano alzada"),
+    ("markup.text", "Texto"),
+    ("markup.text_hint", "Escribe y pulsa Enter"),
+    ("markup.undo", "Deshacer"),
+    ("markup.save", "Guardar"),
+    ("markup.copy", "Copiar al portapapeles"),
+    ("window.console", "Consola"),
+    ("window.viewer", "Visor"),
+    ("window.crash_report", "Informe de fallo"),
+    // about
+    ("about.version", "Versión"),
+    ("about.made_by", "Hecho por"),
+    // settings
+    ("settings.profile", "Perfil"),
+    (
+        "settings.profile_tooltip",
+        "Conjuntos de ajustes con nombre, el elegido aquí se usa de nuevo al iniciar",
+    ),
+    ("settings.profile.default", "Predeterminado"),
+    ("settings.profile.name_hint", "Perfil nuevo"),
+    ("settings.profile.create", "Crear"),
+    (
+        "settings.one_instance",
+        "Permitir solo una instancia del programa (requiere reiniciar al activarlo)",
+    ),
+    (
+        "settings.one_instance_tooltip",
+        "Los archivos abiertos con 3dobs en marcha van a su ventana en lugar de a una nueva",
+    ),
+    (
+        "settings.log_to_file",
+        "Guardar el registro en un archivo (requiere reiniciar)",
+    ),
+    (
+        "settings.log_to_file_tooltip",
+        "Escribe también la consola en un registro junto al programa, desde el próximo inicio",
+    ),
+    (
+        "settings.console_max_history",
+        "Tamaño del historial de la consola",
+    ),
+    (
+        "settings.console_max_history_tooltip",
+        "Cuántos mensajes guarda la consola antes de descartar los más antiguos",
+    ),
+    ("settings.language", "Idioma"),
+    ("settings.language_tooltip", "El idioma de la interfaz"),
+    ("settings.theme", "Tema"),
+    ("settings.theme_tooltip", "Los colores de la interfaz"),
+    ("settings.accent_color", "Color de acento"),
+    (
+        "settings.accent_color_tooltip",
+        "El color de botones, pestañas y selecciones",
+    ),
+    ("settings.reset", "Restablecer"),
+    ("settings.texture_filter", "Filtrado de texturas"),
+    (
+        "settings.texture_filter_tooltip",
+        "Cómo se suavizan las texturas al dibujarlas más grandes o más pequeñas de lo que son",
+    ),
+    ("settings.anisotropy", "Filtrado anisotrópico"),
+    (
+        "settings.anisotropy_tooltip",
+        "Mantiene nítidas las texturas de superficies vistas en ángulo, más nivel cuesta más",
+    ),
+    ("settings.memory_budget", "Límite de memoria"),
+    ("settings.memory_budget.unlimited", "Sin límite"),
+    (
+        "settings.memory_budget_tooltip",
+        "Por encima de esto se descargan los modelos no seleccionados hasta que se seleccionen",
+    ),
+    ("settings.max_texture_size", "Tamaño máximo de texturas"),
+    ("settings.max_texture_size.unlimited", "Sin límite"),
+    (
+        "settings.max_texture_size_tooltip",
+        "Las texturas más grandes se reducen al cargar un modelo",
+    ),
+    (
+        "settings.texture_threads",
+        "Hilos de decodificación de texturas",
+    ),
+    (
+        "settings.texture_threads_tooltip",
+        "Las texturas se dibujan como un tablero hasta decodificarse. 0 usa todos los núcleos.",
+    ),
+    (
+        "settings.low_res_inactive_textures",
+        "Texturas de menor resolución para modelos inactivos",
+    ),
+    (
+        "settings.low_res_inactive_textures_tooltip",
+        "Los modelos no seleccionados u ocultos solo usan sus niveles de mipmap inferiores",
+    ),
+    ("settings.compact_vertices", "Búferes de vértices compactos"),
+    (
+        "settings.compact_vertices_tooltip",
+        "Reduce a la mitad la memoria de GPU de los modelos nuevos a costa de precisión",
+    ),
+    ("settings.pick_parts", "Elegir objetos a importar"),
+    (
+        "settings.pick_parts_tooltip",
+        "Lista los objetos de los archivos OBJ con más de uno para importar solo algunos",
+    ),
+    ("settings.thingiverse_token", "Token de Thingiverse"),
+    (
+        "settings.thingiverse_token_tooltip",
+        "Token de aplicación de thingiverse.com/apps, usado por la ventana de modelos en línea",
+    ),
+    ("settings.register_file_types", "Registrar tipos de archivo"),
+    ("settings.auto_clip_planes", "Planos de recorte automáticos"),
+    (
+        "settings.auto_clip_planes_tooltip",
+        "Ajusta los planos de recorte a los modelos mostrados para evitar recortes y z-fighting",
+    ),
+    ("settings.reversed_z", "Profundidad Z invertida"),
+    (
+        "settings.reversed_z_tooltip",
+        "Usa un búfer de profundidad flotante con Z invertida para mucha más precisión",
+    ),
+    (
+        "settings.reversed_z_unsupported",
+        "Necesita OpenGL 4.5 o GL_ARB_clip_control, que este controlador no tiene",
+    ),
+    ("settings.vsync", "Sincronización vertical"),
+    (
+        "settings.vsync_tooltip",
+        "Espera al monitor antes de mostrar cada fotograma, lo que evita el tearing",
+    ),
+    ("settings.gl_version", "Versión de OpenGL"),
+    ("settings.gl_version.default", "3.3 (predeterminada)"),
+    ("settings.gl_profile", "Perfil de OpenGL"),
+    ("gl_profile.core", "Core"),
+    ("gl_profile.compatibility", "Compatibilidad"),
+    ("settings.restart_tooltip", "Se aplica tras reiniciar"),
+    ("settings.software_rendering", "Renderizado por software"),
+    (
+        "settings.software_rendering_tooltip",
+        "Dibuja con la CPU si los drivers de la GPU fallan, tras reiniciar. Lento.",
+    ),
+    ("settings.discrete_gpu", "Usar la GPU dedicada"),
+    (
+        "settings.discrete_gpu_tooltip",
+        "En portátiles con dos GPU, tras reiniciar. Siempre activo en Windows.",
+    ),
+    ("settings.capture_dir", "Carpeta de capturas"),
+    ("settings.capture_dir.working_dir", "Directorio de trabajo"),
+    ("settings.capture_dir.browse", "Examinar..."),
+    ("dialog.capture_dir.title", "Carpeta para las capturas"),
+    ("settings.capture_name", "Nombre de las capturas"),
+    (
+        "settings.capture_name_tooltip",
+        "Nombre de las capturas nuevas sin la extensión. {model}, {date}, {time}, \
+         {timestamp}, {width} y {height} se sustituyen por el nombre del modelo activo, la \
+         fecha y la hora, los segundos desde 1970 y el tamaño del visor.",
+    ),
+    ("settings.open_captures", "Abrir las capturas al guardarlas"),
+    (
+        "settings.open_captures_tooltip",
+        "Abre las capturas en el visor de imágenes una vez guardadas",
+    ),
+    ("settings.fps_cap", "Límite de FPS"),
+    (
+        "settings.fps_cap_tooltip",
+        "El máximo de fotogramas dibujados por segundo, los límites bajos gastan menos energía",
+    ),
+    ("settings.fps_cap.unlimited", "Sin límite"),
+    ("settings.idle_mode", "Ahorro de energía en reposo"),
+    (
+        "settings.idle_mode_tooltip",
+        "Deja de dibujar un segundo después de la última entrada hasta que algo cambie",
+    ),
+    ("settings.stl_units", "Unidades STL supuestas"),
+    (
+        "settings.stl_units_tooltip",
+        "Unidades usadas para los archivos STL cuya cabecera no indica cuáles son",
+    ),
+    (
+        "settings.register_file_types_tooltip",
+        "Abrir los archivos OBJ y STL con 3dobs al hacer doble clic en el explorador de archivos",
+    ),
+    ("settings.tab.general", "General"),
+    ("settings.tab.formats", "Formatos"),
+    (
+        "settings.formats.description",
+        "Opciones con las que se importa cada formato, también el punto de partida de la \
+         ventana de opciones de importación",
+    ),
+    ("settings.formats.reset", "Restablecer"),
+    (
+        "settings.formats.remembered",
+        "Se usan en su lugar las opciones recordadas en la ventana de opciones de importación",
+    ),
+    ("settings.formats.forget", "Olvidar"),
+    ("settings.clear_geometry_cache", "Vaciar caché de geometría"),
+    (
+        "settings.clear_geometry_cache_tooltip",
+        "Los archivos ya abiertos se cargan desde una caché hasta que cambian, esto la borra",
+    ),
+    ("texture_filter.nearest", "Vecino más cercano"),
+    ("texture_filter.bilinear", "Bilineal"),
+    ("texture_filter.trilinear", "Trilineal"),
+    ("theme.dark", "Oscuro"),
+    ("theme.light", "Claro"),
+    ("theme.high_contrast", "Alto contraste"),
+    // crash report
+    (
+        "crash_report.message",
+        "3dobs se cerró inesperadamente la última vez. Se guardó un informe de fallo en:",
+    ),
+    ("crash_report.open", "Abrir informe"),
+    ("crash_report.open_folder", "Abrir carpeta"),
+    ("crash_report.dismiss", "Descartar"),
+    // keybinds
+    ("keybinds.key", "Tecla"),
+    ("keybinds.action", "Acción"),
+    ("keybinds.drag_drop", "Arrastrar y soltar"),
+    ("keybinds.import", "Importar modelo(s)"),
+    ("keybinds.quit", "Salir"),
+    ("keybinds.lmb", "Botón izquierdo del ratón"),
+    ("keybinds.rotate", "Rotar objeto"),
+    ("keybinds.scroll", "Rueda del ratón"),
+    ("keybinds.zoom", "Acercar/alejar cámara"),
+    ("keybinds.left_shift", "Mayús izquierda"),
+    ("keybinds.pan", "Desplazar cámara"),
+    ("keybinds.left_ctrl", "Ctrl izquierdo"),
+    (
+        "keybinds.camera_speed",
+        "Aumentar la velocidad de la cámara",
+    ),
+    ("keybinds.undo", "Deshacer"),
+    ("keybinds.redo", "Rehacer"),
+    ("keybinds.cancel_pick", "Cancelar selección de pivote"),
+    ("keybinds.arrows", "Flechas"),
+    (
+        "keybinds.navigate_objects",
+        "Navegar por la ventana de objetos",
+    ),
+    ("keybinds.remove_model", "Eliminar el modelo seleccionado"),
+    ("keybinds.rename_model", "Renombrar el modelo seleccionado"),
+    ("keybinds.space", "Espacio"),
+    ("keybinds.multi_select", "Ctrl/Mayús + Clic"),
+    ("keybinds.multi_select_action", "Seleccionar varios modelos"),
+    (
+        "keybinds.browse_folder",
+        "Modelo anterior/siguiente de la carpeta que se recorre",
+    ),
+    (
+        "keybinds.toggle_visibility",
+        "Mostrar/ocultar el modelo seleccionado",
+    ),
+    // status bar
+    ("status.mode", "Modo"),
+    ("status.importing", "Importando"),
+    ("status.downloading", "Descargando"),
+    ("status.cancel_import", "Cancelar"),
+    ("window.open_url", "Abrir URL"),
+    ("open_url.paste", "Pegar"),
+    ("open_url.open", "Abrir"),
+    ("window.import_heightmap", "Importar mapa de alturas"),
+    ("heightmap.resolution", "Resolución"),
+    (
+        "heightmap.resolution_tooltip",
+        "Máximo de vértices por lado, las imágenes más grandes se reducen a esto",
+    ),
+    ("heightmap.height_scale", "Escala de altura"),
+    (
+        "heightmap.height_scale_tooltip",
+        "Altura de los píxeles blancos, relativa al lado más largo del terreno",
+    ),
+    ("heightmap.import", "Importar"),
+    ("window.import_options", "Opciones de importación"),
+    ("import_options.up_axis", "Eje vertical"),
+    (
+        "import_options.up_axis_tooltip",
+        "El eje que apunta arriba en el archivo, se gira a +Y",
+    ),
+    ("import_options.format_default", "Según el formato"),
+    ("import_options.left_handed", "Levógiro"),
+    (
+        "import_options.left_handed_tooltip",
+        "Refleja el modelo en Z y invierte el sentido de sus caras",
+    ),
+    ("import_options.x_up", "X arriba"),
+    ("import_options.y_up", "Y arriba"),
+    ("import_options.z_up", "Z arriba"),
+    ("import_options.scale", "Escala"),
+    ("import_options.flip_v", "Invertir V de texturas"),
+    (
+        "import_options.flip_v_tooltip",
+        "Para texturas que salen al revés",
+    ),
+    ("import_options.smooth", "Suavizar normales"),
+    (
+        "import_options.smooth_tooltip",
+        "Las aristas más marcadas que el ángulo se mantienen",
+    ),
+    ("import_options.merge_meshes", "Unir mallas"),
+    ("import_options.remember", "Recordar para esta extensión"),
+    ("import_options.import", "Importar"),
+    ("window.pick_parts", "Elegir objetos"),
+    ("parts.select_all", "Todos"),
+    ("parts.select_none", "Ninguno"),
+    ("parts.unnamed", "(sin nombre)"),
+    ("parts.triangles", "triángulos"),
+    ("parts.import", "Importar"),
+    ("notification.import_failed", "No se pudo importar"),
+    ("notification.details", "Detalles"),
+    ("notification.copy", "Copiar"),
+    ("notification.dismiss", "Descartar"),
+    ("path.reveal", "Mostrar en la carpeta"),
+    ("path.copy", "Copiar ruta"),
+    ("notification.click_to_open", "Haz clic para abrir"),
+    ("notification.loaded", "Cargado"),
+    ("notification.texture_missing", "Falta la textura:"),
+    ("notification.capture_saved", "Captura guardada"),
+    ("notes.tags", "Etiquetas"),
+    ("notes.tags_hint", "Etiquetas, separadas por comas"),
+    ("notes.edit", "Editar notas"),
+    (
+        "notes.edit_tooltip",
+        "Notas y etiquetas del archivo del modelo, guardadas por 3dobs sin cambiar el archivo",
+    ),
+    ("notes.save", "Guardar"),
+    ("notes.cancel", "Cancelar"),
+    ("library.folders", "Carpetas"),
+    (
+        "library.no_folders",
+        "Aún no hay carpetas, sus modelos se buscan también en las subcarpetas",
+    ),
+    ("library.add_folder", "Añadir carpeta"),
+    ("library.reindex", "Reindexar"),
+    (
+        "library.reindex_tooltip",
+        "Vuelve a mirar las carpetas, solo se leen los archivos nuevos y cambiados",
+    ),
+    ("library.indexing", "Indexando"),
+    (
+        "library.search_hint",
+        "Buscar por nombre, notas o etiquetas",
+    ),
+    ("library.format", "Formato"),
+    ("library.any_format", "Todos"),
+    ("library.min_size", "Mín"),
+    ("library.max_size", "Máx"),
+    (
+        "library.max_size_tooltip",
+        "Solo muestra archivos entre estos tamaños, 0 para sin límite",
+    ),
+    ("library.only_duplicates", "Solo duplicados"),
+    (
+        "library.only_duplicates_tooltip",
+        "Solo muestra los archivos cuya geometría es igual o casi igual a la de otro",
+    ),
+    ("library.duplicates", "Duplicados"),
+    ("library.identical", "idéntico"),
+    ("library.similar", "casi idéntico"),
+    ("library.import_selected", "Importar seleccionados"),
+    (
+        "library.empty",
+        "La biblioteca está vacía. Añade una carpeta, o notas a un modelo con Editar notas.",
+    ),
+    ("library.missing", "no encontrado"),
+    ("library.open", "Abrir"),
+    ("library.triangles", "triángulos"),
+    ("library.models", "modelos"),
+    ("notification.library_indexed", "Biblioteca indexada"),
+    (
+        "dialog.library_folder.title",
+        "Añadir una carpeta a la biblioteca",
+    ),
+    ("notification.recording_saved", "Grabación guardada"),
+    ("notification.recording_failed", "La grabación falló"),
+    (
+        "notification.annotated_capture_saved",
+        "Captura anotada guardada",
+    ),
+    (
+        "import_error.not_found",
+        "El archivo o uno al que hace referencia no existe",
+    ),
+    (
+        "import_error.permission_denied",
+        "No hay permiso para leer el archivo",
+    ),
+    (
+        "import_error.truncated",
+        "El archivo termina antes de tiempo, puede que no se terminara de guardar",
+    ),
+    (
+        "import_error.invalid_data",
+        "El archivo está dañado o no tiene el formato que indica su nombre",
+    ),
+    ("import_error.line", "Error en la línea"),
+    (
+        "import_error.unsupported",
+        "El archivo no tiene un formato que 3dobs pueda importar",
+    ),
+    ("import_error.cancelled", "La importación se canceló"),
+    (
+        "import_error.cancelled_resumable",
+        "La importación se canceló, continuará donde se detuvo la próxima vez",
+    ),
+    ("window.online", "Modelos en línea"),
+    ("window.camera_path", "Recorrido de cámara"),
+    ("window.note_editor", "Notas del modelo"),
+    ("window.library", "Biblioteca"),
+    ("window.slicing", "Vista previa de capas"),
+    ("window.materials", "Materiales"),
+    ("materials.no_model", "Ningún modelo seleccionado"),
+    (
+        "materials.help",
+        "Haz clic en un material para resaltar las mallas que lo usan",
+    ),
+    ("materials.unloaded", "El modelo se está volviendo a cargar"),
+    ("materials.name", "Material"),
+    ("materials.triangles", "Triángulos"),
+    ("materials.visible", "Visible"),
+    ("camera_path.add_keyframe", "Añadir fotograma clave"),
+    (
+        "camera_path.add_keyframe_tooltip",
+        "Añade la vista actual como fotograma clave en el tiempo del cabezal",
+    ),
+    ("camera_path.play", "Reproducir"),
+    ("camera_path.stop", "Detener"),
+    ("camera_path.empty", "Aún no hay fotogramas clave"),
+    ("camera_path.go_to", "Ir a"),
+    ("camera_path.fps", "FPS"),
+    ("camera_path.export_frames", "Exportar fotogramas"),
+    ("camera_path.export_video", "Exportar vídeo"),
+    (
+        "camera_path.export_video_tooltip",
+        "Exporta los fotogramas y los codifica en un MP4, necesita ffmpeg",
+    ),
+    ("camera_path.exporting", "Exportando fotograma"),
+    ("camera_path.cancel", "Cancelar"),
+    ("dialog.export_frames.title", "Exportar fotogramas a"),
+    (
+        "online.no_token",
+        "Añade un token de aplicación de Thingiverse en los ajustes para buscar modelos",
+    ),
+    ("online.open_settings", "Abrir ajustes"),
+    ("online.search_hint", "Buscar en Thingiverse"),
+    ("online.search", "Buscar"),
+    ("online.searching", "Buscando..."),
+    ("online.by", "de"),
+    ("online.import", "Importar"),
+    ("tool.rotate", "Rotar"),
+    ("tool.pan", "Desplazar"),
+    ("tool.pick_pivot", "Elegir pivote"),
+    ("tool.annotate", "Añadir nota"),
+    ("tool.inspect", "Inspeccionar"),
+    ("tool.measure", "Medir"),
+    ("topology.analyze", "Analizar topología"),
+    (
+        "topology.analyze_tooltip",
+        "Busca agujeros, aristas no múltiples y caras invertidas y las resalta",
+    ),
+    ("topology.clear", "Borrar"),
+    ("topology.clean", "No se encontraron problemas de topología"),
+    ("topology.boundary_edges", "Aristas de borde"),
+    ("topology.non_manifold_edges", "Aristas no múltiples"),
+    ("topology.flipped_faces", "Caras invertidas"),
+    ("topology.degenerate_faces", "Caras degeneradas"),
+    ("repair.title", "Reparar"),
+    ("repair.recompute_normals", "Recalcular normales"),
+    (
+        "repair.recompute_normals.tooltip",
+        "Suaviza las normales según las caras vecinas, manteniendo las aristas vivas",
+    ),
+    ("repair.unify_winding", "Unificar orientación"),
+    (
+        "repair.unify_winding.tooltip",
+        "Invierte las caras orientadas al revés que la mayoría de las caras conectadas a ellas",
+    ),
+    ("repair.remove_degenerate", "Eliminar caras degeneradas"),
+    (
+        "repair.remove_degenerate.tooltip",
+        "Elimina las caras sin área y las caras que duplican otra",
+    ),
+    ("repair.fill_holes", "Rellenar agujeros pequeños"),
+    (
+        "repair.fill_holes.tooltip",
+        "Cierra los agujeros de hasta 32 aristas. Usa Deshacer para revertir la reparación.",
+    ),
+    ("repair.changed", "cambiados"),
+    ("repair.triangles", "Triángulos"),
+    ("simplify.title", "Simplificar"),
+    ("simplify.by_percent", "Porcentaje"),
+    ("simplify.by_count", "Número de triángulos"),
+    ("simplify.triangles", "Triángulos"),
+    ("simplify.percent", "Objetivo"),
+    ("simplify.preview", "Vista previa"),
+    (
+        "simplify.preview_tooltip",
+        "Muestra el modelo con menos triángulos, no se guarda nada hasta pulsar Aplicar",
+    ),
+    ("simplify.apply", "Aplicar"),
+    ("simplify.cancel", "Cancelar"),
+    ("simplify.export", "Exportar..."),
+    ("bounds.compute", "Calcular envolvente y caja"),
+    (
+        "bounds.compute_tooltip",
+        "Calcula la envolvente convexa y la caja rotada más pequeña que contiene el modelo",
+    ),
+    ("bounds.computing", "Calculando la envolvente y la caja..."),
+    ("bounds.clear", "Borrar"),
+    ("bounds.hull", "Envolvente convexa"),
+    ("bounds.points", "puntos"),
+    ("bounds.triangles", "triángulos"),
+    ("bounds.hull_volume", "Volumen de la envolvente"),
+    ("bounds.obb", "Caja orientada"),
+    ("bounds.obb_volume", "Volumen de la caja"),
+    ("balance.compute", "Centro de masas"),
+    (
+        "balance.compute_tooltip",
+        "Dónde se equilibra el modelo si es macizo y de densidad uniforme, y sus ejes principales",
+    ),
+    ("balance.clear", "Borrar"),
+    ("balance.center", "Centro de masas"),
+    (
+        "balance.surface_only",
+        "Las mallas no encierran un volumen, así que es el equilibrio de la superficie",
+    ),
+    ("balance.axis", "Eje"),
+    ("balance.moment", "momento"),
+    ("slicing.enabled", "Cortar"),
+    (
+        "slicing.enabled_tooltip",
+        "Recorta todo lo que está por encima de la capa actual, la sección se resalta",
+    ),
+    ("slicing.layer_height", "Altura de capa"),
+    ("slicing.nothing_displayed", "No se muestra ningún modelo"),
+    ("slicing.play", "Reproducir"),
+    ("slicing.stop", "Detener"),
+    ("slicing.height", "Altura"),
+    ("slicing.cap", "Tapar el corte"),
+    (
+        "slicing.cap_tooltip",
+        "Rellena el corte para que los modelos se vean macizos, solo con mallas cerradas",
+    ),
+    ("slicing.section_color", "Color de sección"),
+    ("annotations.add", "Añadir nota"),
+    (
+        "annotations.placing",
+        "Haz clic en el modelo activo para colocar la nota",
+    ),
+    ("annotations.none", "Aún no hay notas"),
+    ("annotations.edit", "Editar"),
+    ("annotations.delete", "Eliminar"),
+    ("annotations.edit_title", "Editar nota"),
+    ("annotations.save", "Guardar"),
+    ("annotations.cancel", "Cancelar"),
+    ("measure.distance", "Distancia"),
+    ("measure.angle", "Ángulo"),
+    ("measure.snap", "Ajuste"),
+    (
+        "measure.snap_tooltip",
+        "Mueve los puntos pulsados al triángulo bajo el cursor",
+    ),
+    ("measure.snap_off", "Ninguno"),
+    ("measure.snap_vertex", "Vértices"),
+    ("measure.snap_edge", "Aristas"),
+    ("measure.start", "Medir"),
+    ("measure.placing", "Pulsa puntos del modelo activo"),
+    ("measure.delete", "Eliminar"),
+    ("measure.none", "Aún no hay medidas"),
+    // objects
+    ("objects.vertices", "Vértices"),
+    ("objects.triangles", "Triángulos"),
+    ("objects.area", "Área"),
+    ("objects.volume", "Volumen"),
+    (
+        "objects.volume_tooltip",
+        "Solo tiene sentido en mallas cerradas, negativo si las caras miran hacia dentro",
+    ),
+    (
+        "objects.computing_mass_properties",
+        "Calculando área y volumen...",
+    ),
+    ("objects.transformations", "Transformaciones"),
+    ("objects.center_origin", "Centrar en el origen"),
+    ("objects.drop_to_grid", "Apoyar en la cuadrícula"),
+    ("objects.pick_pivot", "Elegir pivote"),
+    (
+        "objects.pick_pivot_inactive",
+        "Selecciona el modelo para elegir un pivote en él",
+    ),
+    ("objects.reset_pivot", "Restablecer pivote"),
+    ("objects.hidden", " (oculto)"),
+    ("objects.unloaded", " (descargado)"),
+    ("objects.loading", " (cargando)"),
+    ("objects.selected", "Seleccionados"),
+    ("objects.toggle_visibility", "Ocultar/Mostrar"),
+    ("objects.remove_selected", "Eliminar"),
+    ("objects.export_selected", "Exportar"),
+    ("objects.move_selected", "Mover"),
+    ("objects.group", "Grupo"),
+    ("objects.group_selected", "Agrupar"),
+    (
+        "objects.group_selected_tooltip",
+        "Pone los modelos seleccionados en un grupo nuevo, arrastra modelos a un grupo",
+    ),
+    ("objects.group_name", "Nombre"),
+    ("objects.move_group", "Mover"),
+    ("objects.ungroup", "Desagrupar"),
+    ("objects.remove_from_group", "Quitar del grupo"),
+    ("objects.rename", "Renombrar"),
+    ("objects.reload", "Recargar"),
+    ("objects.open_in_window", "Abrir en una ventana nueva"),
+    (
+        "objects.open_in_window_tooltip",
+        "Muestra el modelo en una ventana aparte con su propia cámara, arrastra para orbitar",
+    ),
+    ("objects.rename_confirm", "Aceptar"),
+    ("objects.rename_cancel", "Cancelar"),
+    ("objects.position", "Posición"),
+    ("objects.rotation", "Rotación"),
+    ("objects.scale", "Escala"),
+    ("objects.units", "Unidades"),
+    (
+        "objects.units_tooltip",
+        "Unidades de las coordenadas del archivo, tomadas de la cabecera STL si las indica",
+    ),
+    ("objects.size", "Tamaño"),
+    ("units.unknown", "Desconocidas"),
+    ("units.millimeters", "Milímetros"),
+    ("units.inches", "Pulgadas"),
+    ("stl_colors", "Colores de caras"),
+    ("stl_colors.viscam", "VisCAM/SolidView"),
+    ("stl_colors.materialise", "Materialise Magics"),
+    ("stl_colors.ignored", "Ninguno"),
+    (
+        "stl_colors.ambiguous_tooltip",
+        "El archivo podría usar cualquier convención, o sus bytes de atributo no son colores",
+    ),
+    ("objects.reset_position", "Restablecer posición"),
+    ("objects.reset_rotation", "Restablecer rotación"),
+    ("objects.arrange", "Organizar"),
+    (
+        "objects.arrange_tooltip",
+        "Coloca los modelos seleccionados en una cuadrícula sobre el suelo",
+    ),
+    ("objects.reset_scale", "Restablecer escala"),
+    ("objects.apply_transform", "Aplicar transformación"),
+    ("objects.copy_info", "Copiar información"),
+    ("file_info.read", "Información del archivo"),
+    (
+        "file_info.read_tooltip",
+        "Lee el tamaño, la fecha y el SHA-256 del archivo y los comentarios de su exportador",
+    ),
+    ("file_info.size", "Tamaño del archivo"),
+    ("file_info.modified", "Modificado"),
+    ("file_info.copy_checksum", "Copiar"),
+    ("file_info.metadata", "Información incrustada"),
+    ("objects.copy_markdown", "Copiar como Markdown"),
+    (
+        "objects.copy_markdown_tooltip",
+        "Copia los recuentos, el tamaño, los materiales y la topología como tablas Markdown",
+    ),
+    ("objects.mesh_wireframe", "Malla de alambre"),
+    ("objects.mesh_unlit", "Sin iluminación"),
+    (
+        "objects.mesh_unlit_tooltip",
+        "Dibuja la malla con los colores de sus materiales, sin iluminación",
+    ),
+    ("objects.mesh_tint", "Color sólido"),
+    ("objects.mesh_reset_overrides", "Restablecer"),
+    ("objects.pin_ghost", "Fijar fantasma"),
+    (
+        "objects.pin_ghost_tooltip",
+        "Mueve el fantasma a donde está el modelo ahora",
+    ),
+    ("objects.load_textures", "Cargar texturas"),
+    (
+        "objects.load_textures_tooltip",
+        "El modelo se importó sin texturas, esto las añade a sus materiales",
+    ),
+    ("report.title", "Modelos en"),
+    ("report.file", "Archivo"),
+    ("report.error", "Error"),
+    ("report.meshes", "Mallas"),
+    ("report.materials", "Materiales"),
+    ("report.material", "Material"),
+    ("objects.normalization_factor", "Factor de normalización"),
+    (
+        "objects.normalization_factor_tooltip",
+        "Escala que ajusta el modelo al visor. Se usa en el modo Normalizado.",
+    ),
+    ("objects.scale_normalized", "Normalizado"),
+    ("objects.scale_original", "Tamaño original"),
+    ("objects.scale_custom", "Personalizado"),
+    ("objects.scale_factor", "Factor de escala"),
+    (
+        "objects.apply_transform_tooltip",
+        "Aplica la transformación actual a los vértices. No se puede deshacer.",
+    ),
+    // console
+    ("console.clear", "Limpiar"),
+    ("console.copy_all", "Copiar todo"),
+    ("console.copy_line", "Copiar"),
+    ("console.open_log_folder", "Abrir carpeta del registro"),
+    ("console.autoscroll", "Desplazamiento automático"),
+    ("console.search", "Buscar"),
+    ("console.errors", "Errores"),
+    ("console.warnings", "Advertencias"),
+    ("console.info", "Info"),
+    ("console.debug", "Depuración"),
+    ("console.source", "Origen"),
+    // viewport
+    ("viewport.scene", "Escena"),
+    (
+        "viewport.new_scene_tooltip",
+        "Añade una escena con sus propios modelos, cámara y opciones de vista",
+    ),
+    ("viewport.reset_camera", "Restablecer cámara"),
+    (
+        "viewport.reset_camera_tooltip",
+        "Aleja la cámara para que el modelo activo quepa en la vista",
+    ),
+    ("viewport.capture", "Capturar escena"),
+    (
+        "viewport.capture_tooltip",
+        "Guarda el visor como imagen, tras dibujar sobre ella en la ventana de anotación",
+    ),
+    ("viewport.capture_window", "Capturar ventana"),
+    (
+        "viewport.capture_window_tooltip",
+        "Guarda toda la ventana como imagen, con los paneles, para informar de errores",
+    ),
+    ("viewport.record", "Grabar"),
+    (
+        "viewport.record_tooltip",
+        "Graba el visor en un vídeo, necesita ffmpeg",
+    ),
+    ("viewport.stop_recording", "Detener"),
+    (
+        "viewport.stop_recording_tooltip",
+        "Detiene la grabación, el vídeo se guarda cuando ffmpeg termina con él",
+    ),
+    ("recording.fps", "FPS"),
+    ("recording.duration", "Duración"),
+    (
+        "recording.duration_tooltip",
+        "Detiene la grabación tras estos segundos, 0 graba hasta que se detenga",
+    ),
+    ("recording.format", "Formato"),
+    (
+        "recording.format_tooltip",
+        "MP4 se reproduce casi en cualquier sitio, WebM se puede incrustar en webs e incidencias",
+    ),
+    ("recording.start", "Iniciar grabación"),
+    ("viewport.wireframe", "Malla de alambre"),
+    (
+        "viewport.wireframe_tooltip",
+        "Dibuja solo las aristas de los triángulos",
+    ),
+    ("viewport.inspect", "Inspeccionar"),
+    (
+        "viewport.inspect_tooltip",
+        "Muestra los vértices y el material del triángulo bajo el cursor",
+    ),
+    ("inspect.triangle", "Triángulo"),
+    ("inspect.vertex", "Vértice"),
+    ("inspect.position", "Posición"),
+    ("inspect.normal", "Normal"),
+    ("inspect.material", "Material"),
+    ("inspect.diffuse", "Difuso"),
+    (
+        "viewport.display_mode_tooltip",
+        "Muestra el búfer de profundidad o de normales en lugar de los modelos sombreados",
+    ),
+    ("display_mode.shaded", "Sombreado"),
+    ("display_mode.depth", "Profundidad"),
+    ("display_mode.normals", "Normales"),
+    ("viewport.fov_zoom", "Zoom por FOV"),
+    (
+        "viewport.fov_zoom_tooltip",
+        "La rueda estrecha el campo de visión como un objetivo en lugar de acercar la \
+         cámara (dolly). El zoom por FOV aplana la perspectiva, el dolly la conserva.",
+    ),
+    ("viewport.show_textures", "Mostrar texturas"),
+    (
+        "viewport.show_textures_tooltip",
+        "Dibuja los modelos con sus texturas, o solo con los colores de sus materiales",
+    ),
+    ("viewport.camera_speed", "Velocidad de cámara"),
+    (
+        "viewport.camera_speed_tooltip",
+        "Lo rápido que se mueve la cámara al desplazar y con la rueda",
+    ),
+    ("viewport.rotation_speed", "Velocidad de rotación"),
+    (
+        "viewport.rotation_speed_tooltip",
+        "Lo rápido que giran los modelos al arrastrarlos",
+    ),
+];
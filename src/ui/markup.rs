@@ -0,0 +1,367 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    gl_state,
+    texture::{self, TextureFilter},
+    ui::i18n::tr,
+    utils,
+};
+
+const LINE_THICKNESS: f32 = 3.0;
+const ARROW_HEAD_LENGTH: f32 = 16.0;
+const ARROW_HEAD_ANGLE: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkupTool {
+    Arrow,
+    Rectangle,
+    Freehand,
+    Text,
+}
+
+impl MarkupTool {
+    pub const ALL: [MarkupTool; 4] = [
+        MarkupTool::Arrow,
+        MarkupTool::Rectangle,
+        MarkupTool::Freehand,
+        MarkupTool::Text,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarkupTool::Arrow => tr("markup.arrow"),
+            MarkupTool::Rectangle => tr("markup.rectangle"),
+            MarkupTool::Freehand => tr("markup.freehand"),
+            MarkupTool::Text => tr("markup.text"),
+        }
+    }
+}
+
+/// Positions are in pixels of the captured image
+#[derive(Debug, Clone)]
+pub enum Shape {
+    Arrow { from: [f32; 2], to: [f32; 2] },
+    Rectangle { start: [f32; 2], end: [f32; 2] },
+    Freehand { points: Vec<[f32; 2]> },
+    Text { pos: [f32; 2], text: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct Mark {
+    pub shape: Shape,
+    pub color: [f32; 4],
+}
+
+impl Mark {
+    /// Starts a new mark at `pos` that gets extended while the mouse is dragged
+    pub fn start(tool: MarkupTool, pos: [f32; 2], color: [f32; 4]) -> Self {
+        let shape = match tool {
+            MarkupTool::Arrow => Shape::Arrow { from: pos, to: pos },
+            MarkupTool::Rectangle => Shape::Rectangle {
+                start: pos,
+                end: pos,
+            },
+            MarkupTool::Freehand => Shape::Freehand { points: vec![pos] },
+            MarkupTool::Text => Shape::Text {
+                pos,
+                text: String::new(),
+            },
+        };
+
+        Mark { shape, color }
+    }
+
+    pub fn drag_to(&mut self, pos: [f32; 2]) {
+        match &mut self.shape {
+            Shape::Arrow { to, .. } => *to = pos,
+            Shape::Rectangle { end, .. } => *end = pos,
+            Shape::Freehand { points } => {
+                let last = points[points.len() - 1];
+                // skip points that are too close together to matter
+                if distance(last, pos) >= 1.0 {
+                    points.push(pos);
+                }
+            }
+            Shape::Text { .. } => {}
+        }
+    }
+
+    /// Draws the mark over the image shown at `origin` on the screen
+    pub fn draw(&self, draw_list: &imgui::DrawListMut, origin: [f32; 2]) {
+        let at = |p: [f32; 2]| [origin[0] + p[0], origin[1] + p[1]];
+        let color = self.color;
+
+        match &self.shape {
+            Shape::Arrow { from, to } => {
+                draw_list
+                    .add_line(at(*from), at(*to), color)
+                    .thickness(LINE_THICKNESS)
+                    .build();
+                for head in arrow_head(*from, *to) {
+                    draw_list
+                        .add_line(at(*to), at(head), color)
+                        .thickness(LINE_THICKNESS)
+                        .build();
+                }
+            }
+            Shape::Rectangle { start, end } => {
+                draw_list
+                    .add_rect(at(*start), at(*end), color)
+                    .thickness(LINE_THICKNESS)
+                    .build();
+            }
+            Shape::Freehand { points } => {
+                draw_list
+                    .add_polyline(points.iter().map(|p| at(*p)).collect(), color)
+                    .thickness(LINE_THICKNESS)
+                    .build();
+            }
+            Shape::Text { pos, text } => {
+                draw_list.add_text(at(*pos), color, text);
+            }
+        }
+    }
+
+    /// Draws the mark's coverage into a mask the size of the image
+    fn rasterize(&self, mask: &mut Mask) {
+        match &self.shape {
+            Shape::Arrow { from, to } => {
+                mask.segment(*from, *to);
+                for head in arrow_head(*from, *to) {
+                    mask.segment(*to, head);
+                }
+            }
+            Shape::Rectangle { start, end } => {
+                let corners = [*start, [end[0], start[1]], *end, [start[0], end[1]]];
+                for i in 0..corners.len() {
+                    mask.segment(corners[i], corners[(i + 1) % corners.len()]);
+                }
+            }
+            Shape::Freehand { points } => {
+                if points.len() == 1 {
+                    mask.dot(points[0]);
+                }
+                for pair in points.windows(2) {
+                    mask.segment(pair[0], pair[1]);
+                }
+            }
+            Shape::Text { pos, text } => mask.text(*pos, text),
+        }
+    }
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt()
+}
+
+/// End points of the two short lines that make up the tip of an arrow
+fn arrow_head(from: [f32; 2], to: [f32; 2]) -> [[f32; 2]; 2] {
+    let angle = (from[1] - to[1]).atan2(from[0] - to[0]);
+
+    [angle - ARROW_HEAD_ANGLE, angle + ARROW_HEAD_ANGLE].map(|a| {
+        [
+            to[0] + a.cos() * ARROW_HEAD_LENGTH,
+            to[1] + a.sin() * ARROW_HEAD_LENGTH,
+        ]
+    })
+}
+
+/// How much of every pixel a single mark covers. Marks are blended onto the
+/// image once each, so overlapping parts of a translucent mark don't get darker.
+struct Mask {
+    width: u32,
+    height: u32,
+    coverage: Vec<u8>,
+}
+
+impl Mask {
+    fn new(width: u32, height: u32) -> Self {
+        Mask {
+            width,
+            height,
+            coverage: vec![0; (width * height) as usize],
+        }
+    }
+
+    fn cover(&mut self, x: i64, y: i64, amount: u8) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let idx = (y * self.width as i64 + x) as usize;
+        self.coverage[idx] = self.coverage[idx].max(amount);
+    }
+
+    fn dot(&mut self, center: [f32; 2]) {
+        let radius = LINE_THICKNESS / 2.0;
+        let min_x = (center[0] - radius).floor() as i64;
+        let max_x = (center[0] + radius).ceil() as i64;
+        let min_y = (center[1] - radius).floor() as i64;
+        let max_y = (center[1] + radius).ceil() as i64;
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if distance(center, [x as f32 + 0.5, y as f32 + 0.5]) <= radius {
+                    self.cover(x, y, u8::MAX);
+                }
+            }
+        }
+    }
+
+    fn segment(&mut self, from: [f32; 2], to: [f32; 2]) {
+        let steps = (distance(from, to) * 2.0).ceil().max(1.0) as usize;
+
+        for i in 0..=steps {
+            let t = i as f32 / steps as f32;
+            self.dot([
+                from[0] + (to[0] - from[0]) * t,
+                from[1] + (to[1] - from[1]) * t,
+            ]);
+        }
+    }
+
+    /// Draws the text with the current imgui font, the same one it's shown with
+    /// in the overlay. Must be called while a frame is being built.
+    fn text(&mut self, pos: [f32; 2], text: &str) {
+        unsafe {
+            let font = imgui::sys::igGetFont();
+            let mut pixels: *mut u8 = std::ptr::null_mut();
+            let (mut atlas_w, mut atlas_h, mut bpp) = (0, 0, 0);
+            imgui::sys::ImFontAtlas_GetTexDataAsRGBA32(
+                (*font).ContainerAtlas,
+                &mut pixels,
+                &mut atlas_w,
+                &mut atlas_h,
+                &mut bpp,
+            );
+            if pixels.is_null() {
+                return;
+            }
+            let atlas = std::slice::from_raw_parts(pixels, (atlas_w * atlas_h * bpp) as usize);
+
+            let mut pen_x = pos[0];
+            for c in text.chars() {
+                let glyph = imgui::sys::ImFont_FindGlyph(font, c as imgui::sys::ImWchar);
+                if glyph.is_null() {
+                    continue;
+                }
+                let glyph = &*glyph;
+
+                let x0 = pen_x + glyph.X0;
+                let y0 = pos[1] + glyph.Y0;
+                let glyph_w = glyph.X1 - glyph.X0;
+                let glyph_h = glyph.Y1 - glyph.Y0;
+
+                for y in y0.floor() as i64..(y0 + glyph_h).ceil() as i64 {
+                    for x in x0.floor() as i64..(x0 + glyph_w).ceil() as i64 {
+                        let u = glyph.U0 + (x as f32 + 0.5 - x0) / glyph_w * (glyph.U1 - glyph.U0);
+                        let v = glyph.V0 + (y as f32 + 0.5 - y0) / glyph_h * (glyph.V1 - glyph.V0);
+                        let ax = ((u * atlas_w as f32) as i32).clamp(0, atlas_w - 1);
+                        let ay = ((v * atlas_h as f32) as i32).clamp(0, atlas_h - 1);
+
+                        // the alpha channel holds the glyph's coverage
+                        let alpha = atlas[((ay * atlas_w + ax) * bpp + bpp - 1) as usize];
+                        self.cover(x, y, alpha);
+                    }
+                }
+
+                pen_x += glyph.AdvanceX;
+            }
+        }
+    }
+}
+
+/// A capture being marked up before it's saved or copied
+pub struct Markup {
+    pub image: image::RgbaImage,
+    /// Where the capture was saved, the marked up copy is saved next to it
+    pub path: PathBuf,
+    pub texture: u32,
+    pub marks: Vec<Mark>,
+    pub tool: MarkupTool,
+    pub color: [f32; 4],
+    /// Mark that's being drawn with the mouse
+    pub current: Option<Mark>,
+    /// Where a text mark goes and the text typed for it so far
+    pub pending_text: Option<([f32; 2], String)>,
+}
+
+impl Markup {
+    pub fn new(image: image::RgbaImage, path: &Path) -> Self {
+        let texture = upload(&image);
+
+        Markup {
+            image,
+            path: path.to_path_buf(),
+            texture,
+            marks: vec![],
+            tool: MarkupTool::Arrow,
+            color: [1.0, 0.2, 0.2, 1.0],
+            current: None,
+            pending_text: None,
+        }
+    }
+
+    /// Deletes the texture, for when the context it was made in is lost
+    pub fn release_texture(&mut self) {
+        gl_state::delete_textures(&[self.texture]);
+        self.texture = 0;
+    }
+
+    /// Uploads the capture again after `release_texture`
+    pub fn restore_texture(&mut self) {
+        self.texture = upload(&self.image);
+    }
+
+    /// Path the marked up copy of the capture is saved to
+    pub fn output_path(&self) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        self.path.with_file_name(format!("{}-annotated.png", stem))
+    }
+
+    /// The capture with every mark drawn onto it
+    pub fn flatten(&self) -> image::RgbaImage {
+        let mut image = self.image.clone();
+
+        for mark in &self.marks {
+            let mut mask = Mask::new(image.width(), image.height());
+            mark.rasterize(&mut mask);
+
+            for (pixel, coverage) in image.pixels_mut().zip(&mask.coverage) {
+                if *coverage == 0 {
+                    continue;
+                }
+
+                let alpha = mark.color[3] * (*coverage as f32 / 255.0);
+                for c in 0..3 {
+                    let src = mark.color[c] * 255.0;
+                    pixel[c] = (src * alpha + pixel[c] as f32 * (1.0 - alpha)) as u8;
+                }
+            }
+        }
+
+        image
+    }
+}
+
+fn upload(image: &image::RgbaImage) -> u32 {
+    let sampling = texture::Sampling {
+        filter: TextureFilter::Bilinear,
+        anisotropy: 1.0,
+    };
+    let image = image::DynamicImage::ImageRgba8(image.clone());
+    utils::upload_texture(&image, sampling).unwrap_or_else(|e| {
+        log::error!("Failed to upload the markup: {}", e);
+        0
+    })
+}
+
+impl Drop for Markup {
+    fn drop(&mut self) {
+        gl_state::delete_textures(&[self.texture]);
+    }
+}
@@ -1,2 +1,6 @@
 pub mod docking;
+pub mod i18n;
+pub mod markup;
+pub mod notifications;
+pub mod theme;
 pub mod ui;
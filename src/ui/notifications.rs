@@ -0,0 +1,191 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use log::error;
+
+use crate::{
+    importer::ImportError,
+    ui::{self, i18n::tr, ui::State},
+    utils,
+};
+
+/// How long info and warnings stay up without being hovered, errors stay
+/// until they're dismissed
+const TIMEOUT: Duration = Duration::from_secs(5);
+const WIDTH: f32 = 360.0;
+const SPACING: f32 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Kind {
+    fn color(&self) -> [f32; 4] {
+        match self {
+            Kind::Info => [0.6, 0.8, 1.0, 1.0],
+            Kind::Warning => [1.0, 0.64, 0.0, 1.0],
+            Kind::Error => [1.0, 0.4, 0.4, 1.0],
+        }
+    }
+}
+
+/// What clicking a notification does
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Opens the file or folder with the program the OS picks for it
+    Open(PathBuf),
+}
+
+/// A toast in the bottom right corner telling about something that
+/// happened, besides it being logged in the console
+#[derive(Debug)]
+pub struct Notification {
+    pub kind: Kind,
+    pub title: String,
+    /// Shown under the title
+    pub text: Option<String>,
+    /// Shown when the toast is expanded, like a whole error chain
+    pub details: Option<String>,
+    pub action: Option<Action>,
+    /// When it was shown or last hovered
+    shown: Instant,
+}
+
+impl Notification {
+    pub fn new(kind: Kind, title: impl Into<String>) -> Self {
+        Notification {
+            kind,
+            title: title.into(),
+            text: None,
+            details: None,
+            action: None,
+            shown: Instant::now(),
+        }
+    }
+
+    pub fn info(title: impl Into<String>) -> Self {
+        Self::new(Kind::Info, title)
+    }
+
+    pub fn warning(title: impl Into<String>) -> Self {
+        Self::new(Kind::Warning, title)
+    }
+
+    /// A file that couldn't be imported, with the reason and the error chain
+    pub fn import_error(file: &str, error: ImportError) -> Self {
+        Notification {
+            text: Some(error.reason),
+            details: Some(error.details),
+            ..Self::new(
+                Kind::Error,
+                format!("{} {}", tr("notification.import_failed"), file),
+            )
+        }
+    }
+
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn with_action(mut self, action: Action) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// Whether it goes away on its own, and keeps the frames coming until it
+    /// does
+    pub fn expires(&self) -> bool {
+        self.kind != Kind::Error
+    }
+
+    fn expired(&self) -> bool {
+        self.expires() && self.shown.elapsed() >= TIMEOUT
+    }
+}
+
+/// Stacks the notifications in the bottom right corner of the window, the
+/// newest at the bottom
+pub fn draw(ui: &imgui::Ui, state: &mut State) {
+    state.notifications.retain(|n| !n.expired());
+
+    let display_size = ui.io().display_size;
+    // keeps the toasts above the status bar
+    let mut bottom = display_size[1] - ui.frame_height_with_spacing() * 2.0;
+    let mut dismissed = None;
+    let mut clicked = None;
+
+    for (i, notification) in state.notifications.iter_mut().enumerate().rev() {
+        ui.window(format!("###Notification{}", i))
+            .no_decoration()
+            .always_auto_resize(true)
+            .focus_on_appearing(false)
+            .position([display_size[0] - 10.0, bottom], imgui::Condition::Always)
+            .position_pivot([1.0, 1.0])
+            .build(|| {
+                let wrap = ui.push_text_wrap_pos_with_pos(ui.cursor_pos()[0] + WIDTH);
+                ui.text_colored(notification.kind.color(), &notification.title);
+                if let Some(text) = &notification.text {
+                    ui.text(text);
+                }
+                if let Some(Action::Open(_)) = notification.action {
+                    ui.text_disabled(tr("notification.click_to_open"));
+                }
+                if let Some(details) = &notification.details {
+                    if ui.collapsing_header(
+                        tr("notification.details"),
+                        imgui::TreeNodeFlags::empty(),
+                    ) {
+                        ui.text_disabled(details);
+                        if ui.small_button(tr("notification.copy")) {
+                            ui.set_clipboard_text(details);
+                        }
+                    }
+                }
+                wrap.end();
+                if ui.small_button(tr("notification.dismiss")) {
+                    dismissed = Some(i);
+                }
+
+                let mut menu_open = false;
+                if let Some(Action::Open(path)) = &notification.action {
+                    if ui.is_window_hovered() && ui.is_mouse_clicked(imgui::MouseButton::Right) {
+                        ui.open_popup("###NotificationContext");
+                    }
+                    ui.popup("###NotificationContext", || {
+                        menu_open = true;
+                        ui::ui::draw_path_menu_items(ui, path);
+                    });
+                }
+
+                // stays up while it's being looked at or its menu is open
+                if ui.is_window_hovered() || menu_open {
+                    notification.shown = Instant::now();
+                }
+                if ui.is_window_hovered() {
+                    // clicks on the buttons and the details aren't the toast's
+                    if ui.is_mouse_clicked(imgui::MouseButton::Left) && !ui.is_any_item_hovered() {
+                        clicked = Some(i);
+                    }
+                }
+                bottom -= ui.window_size()[1] + SPACING;
+            });
+    }
+
+    if let Some(i) = clicked {
+        if let Some(Action::Open(path)) = &state.notifications[i].action {
+            if let Err(e) = utils::open_path(path) {
+                error!("{}", e);
+            }
+            dismissed = Some(i);
+        }
+    }
+    if let Some(i) = dismissed {
+        state.notifications.remove(i);
+    }
+}
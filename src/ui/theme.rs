@@ -0,0 +1,120 @@
+use imgui::StyleColor;
+use serde::{Deserialize, Serialize};
+
+use crate::{logger::LogLevel, ui::i18n::tr};
+
+pub const DEFAULT_ACCENT_COLOR: [f32; 3] = [0.26, 0.59, 0.98];
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::HighContrast];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Dark => tr("theme.dark"),
+            Theme::Light => tr("theme.light"),
+            Theme::HighContrast => tr("theme.high_contrast"),
+        }
+    }
+
+    pub fn apply(&self, style: &mut imgui::Style, accent: [f32; 3]) {
+        match self {
+            Theme::Dark => {
+                style.use_dark_colors();
+                style.frame_border_size = 0.0;
+                style.window_border_size = 1.0;
+            }
+            Theme::Light => {
+                style.use_light_colors();
+                style.frame_border_size = 1.0;
+                style.window_border_size = 1.0;
+            }
+            Theme::HighContrast => {
+                style.use_dark_colors();
+                style.frame_border_size = 1.0;
+                style.window_border_size = 2.0;
+
+                style[StyleColor::Text] = [1.0, 1.0, 1.0, 1.0];
+                style[StyleColor::TextDisabled] = [0.7, 0.7, 0.7, 1.0];
+                style[StyleColor::WindowBg] = [0.0, 0.0, 0.0, 1.0];
+                style[StyleColor::ChildBg] = [0.0, 0.0, 0.0, 1.0];
+                style[StyleColor::PopupBg] = [0.0, 0.0, 0.0, 1.0];
+                style[StyleColor::MenuBarBg] = [0.0, 0.0, 0.0, 1.0];
+                style[StyleColor::Border] = [1.0, 1.0, 1.0, 1.0];
+                style[StyleColor::FrameBg] = [0.0, 0.0, 0.0, 1.0];
+                style[StyleColor::FrameBgHovered] = [0.25, 0.25, 0.25, 1.0];
+                style[StyleColor::TitleBg] = [0.0, 0.0, 0.0, 1.0];
+                style[StyleColor::TitleBgCollapsed] = [0.0, 0.0, 0.0, 1.0];
+                style[StyleColor::ScrollbarBg] = [0.0, 0.0, 0.0, 1.0];
+                style[StyleColor::ScrollbarGrab] = [0.8, 0.8, 0.8, 1.0];
+                style[StyleColor::ScrollbarGrabHovered] = [0.9, 0.9, 0.9, 1.0];
+                style[StyleColor::ScrollbarGrabActive] = [1.0, 1.0, 1.0, 1.0];
+                style[StyleColor::Separator] = [1.0, 1.0, 1.0, 1.0];
+                style[StyleColor::TableBorderStrong] = [1.0, 1.0, 1.0, 1.0];
+                style[StyleColor::TableBorderLight] = [0.7, 0.7, 0.7, 1.0];
+            }
+        }
+
+        let [r, g, b] = accent;
+        let with_alpha = |a: f32| [r, g, b, a];
+        // lighten the accent color a bit for hovered/active states
+        let lighter = |a: f32| {
+            [
+                r + (1.0 - r) * 0.25,
+                g + (1.0 - g) * 0.25,
+                b + (1.0 - b) * 0.25,
+                a,
+            ]
+        };
+
+        style[StyleColor::CheckMark] = with_alpha(1.0);
+        style[StyleColor::SliderGrab] = with_alpha(0.8);
+        style[StyleColor::SliderGrabActive] = lighter(1.0);
+        style[StyleColor::FrameBgActive] = with_alpha(0.67);
+        style[StyleColor::TitleBgActive] = with_alpha(0.6);
+        style[StyleColor::Button] = with_alpha(0.4);
+        style[StyleColor::ButtonHovered] = with_alpha(1.0);
+        style[StyleColor::ButtonActive] = lighter(1.0);
+        style[StyleColor::Header] = with_alpha(0.31);
+        style[StyleColor::HeaderHovered] = with_alpha(0.8);
+        style[StyleColor::HeaderActive] = with_alpha(1.0);
+        style[StyleColor::SeparatorHovered] = with_alpha(0.78);
+        style[StyleColor::SeparatorActive] = with_alpha(1.0);
+        style[StyleColor::ResizeGrip] = with_alpha(0.2);
+        style[StyleColor::ResizeGripHovered] = with_alpha(0.67);
+        style[StyleColor::ResizeGripActive] = with_alpha(0.95);
+        style[StyleColor::TabHovered] = with_alpha(0.8);
+        style[StyleColor::TabActive] = with_alpha(0.6);
+        style[StyleColor::DockingPreview] = with_alpha(0.7);
+        style[StyleColor::TextSelectedBg] = with_alpha(0.35);
+        style[StyleColor::NavHighlight] = with_alpha(1.0);
+    }
+
+    /// Console log colors, picked so that every level stays readable
+    /// against the window background of the theme
+    pub fn log_color(&self, level: LogLevel) -> [f32; 4] {
+        match (self, level) {
+            (Theme::Dark, LogLevel::Debug) => [0.5, 0.5, 1.0, 1.0],
+            (Theme::Dark, LogLevel::Info) => [0.5, 0.5, 0.5, 1.0],
+            (Theme::Dark, LogLevel::Warn) => [1.0, 0.64, 0.0, 1.0],
+            (Theme::Dark, LogLevel::Error) => [1.0, 0.0, 0.0, 1.0],
+
+            (Theme::Light, LogLevel::Debug) => [0.15, 0.15, 0.75, 1.0],
+            (Theme::Light, LogLevel::Info) => [0.3, 0.3, 0.3, 1.0],
+            (Theme::Light, LogLevel::Warn) => [0.7, 0.4, 0.0, 1.0],
+            (Theme::Light, LogLevel::Error) => [0.8, 0.0, 0.0, 1.0],
+
+            (Theme::HighContrast, LogLevel::Debug) => [0.6, 0.8, 1.0, 1.0],
+            (Theme::HighContrast, LogLevel::Info) => [1.0, 1.0, 1.0, 1.0],
+            (Theme::HighContrast, LogLevel::Warn) => [1.0, 0.85, 0.0, 1.0],
+            (Theme::HighContrast, LogLevel::Error) => [1.0, 0.35, 0.35, 1.0],
+        }
+    }
+}
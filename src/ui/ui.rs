@@ -1,20 +1,297 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
 
 use glad_gl::gl;
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    camera::Camera, imgui_glfw_support, imgui_opengl_renderer, logger, mesh, model, ui, utils,
+    browse,
+    camera::Camera,
+    camera_path, config, crash, debug_draw, exporter, file_info, gpu, history, hull,
+    imgui_glfw_support, imgui_opengl_renderer,
+    importer::{
+        self,
+        coords::{CoordinateSystem, Handedness, UpAxis},
+        options::{ImportOptions, SCALE_RANGE},
+    },
+    inspect, library, logger, mass_properties, measure, mesh, model, notes, online, pacing,
+    primitive, recording, render_queue, repair, report, samples,
+    scale::{self, ScaleBar},
+    scene, script, shell, simplify, slicing,
+    texture::{self, TextureFilter},
+    topology,
+    ui::{
+        self,
+        i18n::{tr, Language},
+        markup::{Mark, MarkupTool, Shape},
+        theme::Theme,
+    },
+    utils,
 };
 
-#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+/// Distance from a note's anchor point to the bottom left of its label, in pixels
+const ANNOTATION_LABEL_OFFSET: [f32; 2] = [30.0, -30.0];
+
+/// Gap between arranged models, relative to the size of the largest one
+const ARRANGE_SPACING: f32 = 0.25;
+/// Crease angle the smoothing starts out with in the import options window
+const DEFAULT_SMOOTHING_ANGLE: f32 = 60.0;
+const MAX_TEXTURE_THREADS: usize = 32;
+/// Drag and drop payload of a model being moved in the Objects window
+const MODEL_PAYLOAD: &str = "OBJECTS_MODEL";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Settings {
+    /// The version the file was written by, 0 for files from before there
+    /// were versions. See `config::SETTINGS_VERSION`.
+    #[serde(default)]
+    pub version: u32,
     pub one_instance: bool,
+    pub language: Language,
+    pub theme: Theme,
+    pub accent_color: [f32; 3],
+    pub log_to_file: bool,
+    pub console_max_history: usize,
+    pub texture_filter: TextureFilter,
+    pub anisotropy: f32,
+    /// Textures bigger than this on either side are downscaled on import
+    pub max_texture_size: Option<u32>,
+    /// Threads decoding the textures of a model, 0 for one per CPU core
+    pub texture_threads: usize,
+    pub low_res_inactive_textures: bool,
+    /// Pack the normals, texture coords and colors of models loaded from now
+    /// on into about half the GPU memory
+    pub compact_vertices: bool,
+    /// Ask which objects to import from files that have more than one
+    pub pick_parts: bool,
+    /// Megabytes the loaded models may take before unselected ones get evicted
+    pub memory_budget: Option<u32>,
+    /// App token for the Thingiverse API, searching is disabled without one
+    pub thingiverse_token: String,
+    /// Fit the near and far planes to the displayed models every frame
+    pub auto_clip_planes: bool,
+    pub reversed_z: bool,
+    pub vsync: bool,
+    /// OpenGL version asked for at startup, 3.3 if `None`
+    pub gl_version: Option<(u32, u32)>,
+    pub gl_profile: gpu::GlProfile,
+    /// Draw on the CPU, for when the GPU drivers are broken
+    pub software_rendering: bool,
+    /// Ask hybrid graphics drivers for the discrete GPU
+    pub discrete_gpu: bool,
+    pub fps_cap: Option<u32>,
+    /// Drop to a low frame rate while there's no input and nothing is moving
+    pub idle_mode: bool,
+    /// Units assumed for STL files that don't say which they're in
+    pub stl_units: Option<importer::stl::Units>,
+    /// Folder captures are saved in, the working directory if `None`
+    pub capture_dir: Option<std::path::PathBuf>,
+    /// Name of new captures, see `utils::expand_capture_template`
+    pub capture_name: String,
+    /// Open captures in the image viewer once they're saved
+    pub open_captures: bool,
+    /// Import options to use without asking, by lowercase file extension
+    pub import_options: HashMap<String, ImportOptions>,
+    /// Import options of each format by lowercase extension, used for files
+    /// without remembered ones and as the start of the import options window
+    pub format_defaults: HashMap<String, ImportOptions>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: config::SETTINGS_VERSION,
+            one_instance: false,
+            language: Language::default(),
+            theme: Theme::default(),
+            accent_color: ui::theme::DEFAULT_ACCENT_COLOR,
+            log_to_file: true,
+            console_max_history: logger::DEFAULT_MAX_HISTORY,
+            texture_filter: TextureFilter::default(),
+            anisotropy: 1.0,
+            max_texture_size: None,
+            texture_threads: 0,
+            low_res_inactive_textures: false,
+            compact_vertices: false,
+            pick_parts: true,
+            memory_budget: None,
+            thingiverse_token: String::new(),
+            auto_clip_planes: true,
+            reversed_z: false,
+            vsync: true,
+            gl_version: None,
+            gl_profile: gpu::GlProfile::default(),
+            software_rendering: false,
+            discrete_gpu: false,
+            fps_cap: None,
+            idle_mode: true,
+            stl_units: None,
+            capture_dir: None,
+            capture_name: "capture-{timestamp}".to_string(),
+            open_captures: false,
+            import_options: HashMap::new(),
+            format_defaults: HashMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn vertex_format(&self) -> mesh::VertexFormat {
+        if self.compact_vertices {
+            mesh::VertexFormat::Compact
+        } else {
+            mesh::VertexFormat::Full
+        }
+    }
+
+    pub fn texture_sampling(&self) -> texture::Sampling {
+        texture::Sampling {
+            filter: self.texture_filter,
+            anisotropy: self.anisotropy,
+        }
+    }
+
+    /// The options set in the Formats tab for the extension of `path`
+    pub fn default_import_options(&self, path: &std::path::Path) -> ImportOptions {
+        ImportOptions::remembered(&self.format_defaults, path).unwrap_or_default()
+    }
+
+    /// The options a file is imported with without asking, the remembered
+    /// ones over the defaults of its format
+    pub fn import_options_for(&self, path: &std::path::Path) -> ImportOptions {
+        ImportOptions::remembered(&self.import_options, path)
+            .unwrap_or_else(|| self.default_import_options(path))
+    }
+}
+
+/// Where a model dragged in the Objects window goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectDrop {
+    /// In front of another model, into the same group as it
+    Before(u32),
+    /// To the end of a group
+    IntoGroup(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToolMode {
+    Rotate,
+    Pan,
+    PickPivot,
+    Annotate,
+    Inspect,
+    Measure,
+}
+
+impl ToolMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToolMode::Rotate => tr("tool.rotate"),
+            ToolMode::Pan => tr("tool.pan"),
+            ToolMode::PickPivot => tr("tool.pick_pivot"),
+            ToolMode::Annotate => tr("tool.annotate"),
+            ToolMode::Inspect => tr("tool.inspect"),
+            ToolMode::Measure => tr("tool.measure"),
+        }
+    }
+}
+
+/// What the viewport shows, the buffers help diagnosing depth and normal issues
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayMode {
+    Shaded = 0,
+    /// Linearized depth, black at the camera and white at the far plane
+    Depth = 1,
+    /// Normals reconstructed from the depth of neighboring fragments
+    Normals = 2,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Shaded,
+        DisplayMode::Depth,
+        DisplayMode::Normals,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DisplayMode::Shaded => tr("display_mode.shaded"),
+            DisplayMode::Depth => tr("display_mode.depth"),
+            DisplayMode::Normals => tr("display_mode.normals"),
+        }
+    }
+}
+
+pub struct ConsoleState {
+    pub show_errors: bool,
+    pub show_warnings: bool,
+    pub show_info: bool,
+    pub show_debug: bool,
+    pub autoscroll: bool,
+    pub search: String,
+    context_line: Option<String>,
+}
+
+impl Default for ConsoleState {
+    fn default() -> Self {
+        Self {
+            show_errors: true,
+            show_warnings: true,
+            show_info: true,
+            show_debug: true,
+            autoscroll: true,
+            search: String::new(),
+            context_line: None,
+        }
+    }
+}
+
+impl ConsoleState {
+    fn is_visible(&self, line: &logger::LogRecord) -> bool {
+        let level_visible = match line.level {
+            logger::LogLevel::Error => self.show_errors,
+            logger::LogLevel::Warn => self.show_warnings,
+            logger::LogLevel::Info => self.show_info,
+            logger::LogLevel::Debug => self.show_debug,
+        };
+
+        level_visible
+            && (self.search.is_empty()
+                || line
+                    .message
+                    .to_lowercase()
+                    .contains(&self.search.to_lowercase())
+                || line
+                    .target
+                    .to_lowercase()
+                    .contains(&self.search.to_lowercase()))
+    }
+}
+
+/// The objects of a file waiting for the user to pick which ones to import
+#[derive(Debug)]
+pub struct PartPick {
+    pub path: std::path::PathBuf,
+    pub load_textures: bool,
+    pub options: ImportOptions,
+    pub parts: Vec<importer::Part>,
+    /// Whether each of `parts` gets imported
+    pub selected: Vec<bool>,
+}
+
+/// A file waiting for its import options to be confirmed
+#[derive(Debug)]
+pub struct ImportPrompt {
+    pub path: std::path::PathBuf,
+    pub options: ImportOptions,
+    /// Use the options for the file's extension from now on without asking
+    pub remember: bool,
 }
 
 pub struct State {
-    pub active_model: Option<u32>,
+    /// Ids of the selected models, the last one is the active model
+    pub selection: Vec<u32>,
     pub show_console: bool,
     pub show_help_menu_about: bool,
     pub show_settings: bool,
@@ -26,20 +303,132 @@ pub struct State {
     pub fov_zoom: bool,
     pub rotation_speed: f32,
     pub wireframe: bool,
+    pub display_mode: DisplayMode,
     pub first_frame_drawn: bool,
     pub camera: Camera,
     pub objects: Vec<model::Model>,
+    pub groups: Vec<scene::Group>,
+    pub next_group_id: u32,
     pub viewport_size: [f32; 2],
-    pub logger: logger::WritableLog,
+    pub logger: logger::ConsoleLogger,
     pub settings: Settings,
     pub fps: f32,
+    /// How the models batched together when the last frame was drawn
+    pub render_stats: render_queue::RenderStats,
     pub show_textures: bool,
+    pub theme_dirty: bool,
+    pub console: ConsoleState,
+    pub crash_report: Option<std::path::PathBuf>,
+    pub import_jobs: Vec<importer::ImportJob>,
+    /// Files being scanned for their objects before they're imported
+    pub part_scans: Vec<importer::PartScan>,
+    /// Scanned files waiting for their objects to be picked, the first one
+    /// is shown
+    pub part_picks: Vec<PartPick>,
+    /// Files waiting for their import options, the first one is shown
+    pub import_prompts: Vec<ImportPrompt>,
+    /// Toasts that are up, the oldest first
+    pub notifications: Vec<ui::notifications::Notification>,
+    pub tool_mode: ToolMode,
+    /// Cursor position over the viewport image, normalized to 0..1 from the top left
+    pub viewport_cursor: Option<[f32; 2]>,
+    /// Position on the active model under the cursor, in the model's own coordinates
+    pub cursor_model_pos: Option<glm::Vec3>,
+    pub history: history::History,
+    /// The next click in the viewport sets the active model's pivot
+    pub picking_pivot: bool,
+    pub next_id: u32,
+    /// Mesh highlighted in the Objects window, always part of the active model
+    pub selected_mesh: Option<(u32, usize)>,
+    /// Model being renamed and the name typed so far
+    pub renaming: Option<(u32, String)>,
+    /// Opens or closes a model's tree node in the Objects window on the next frame
+    pub objects_open_request: Option<(u32, bool)>,
+    /// Camera matrices of the current frame, used for placing labels over the viewport
+    pub view_projection: glm::Mat4,
+    /// The next click in the viewport adds a note to the active model
+    pub annotating: bool,
+    /// Note being edited, identified by its model and index, and the text typed so far
+    pub editing_annotation: Option<(u32, usize, String)>,
+    /// Opens the note editor on the next frame
+    pub annotation_popup_request: bool,
+    pub show_annotations: bool,
+    /// Draw the selected models' ghosts, see `Model::draw_ghost`
+    pub show_ghosts: bool,
+    /// Label distances along the axes of the grid
+    pub show_grid_labels: bool,
+    pub show_scale_bar: bool,
+    /// Last capture, open for marking up
+    pub markup: Option<ui::markup::Markup>,
+    pub script: script::ScriptConsole,
+    /// Path to save a capture of the viewport to on the next frame
+    pub capture_request: Option<std::path::PathBuf>,
+    /// Path to save a capture of the whole window to once the frame is drawn
+    pub window_capture_request: Option<std::path::PathBuf>,
+    /// URL typed into the Open URL window, None while it's closed
+    pub url_input: Option<String>,
+    /// New imports only load the geometry and the material colors, for quick
+    /// previews of big models. Set with `--no-textures` too.
+    pub skip_textures: bool,
+    /// Image picked to import as a heightmap, waiting for its options
+    pub heightmap_import: Option<std::path::PathBuf>,
+    pub heightmap_options: importer::heightmap::Options,
+    /// Subdivisions of the primitives made with File > New Primitive
+    pub primitive_segments: u32,
+    pub show_online: bool,
+    pub online: online::Browser,
+    pub show_normals: bool,
+    pub debug_draw: debug_draw::DebugDraw,
+    pub show_camera_path: bool,
+    pub camera_path: camera_path::CameraPath,
+    pub recorder: recording::Recorder,
+    /// Notes and tags of model files
+    pub notes: notes::Notes,
+    /// Note in the editor window, which is open while it's Some
+    pub note_edit: Option<notes::NoteEdit>,
+    pub show_library: bool,
+    pub library: library::Library,
+    /// Whether the driver has glClipControl, checked once the context exists
+    pub supports_reversed_z: bool,
+    /// Hovering a displayed model shows the triangle under the cursor
+    pub inspecting: bool,
+    pub inspected_face: Option<inspect::Face>,
+    /// Where the cursor rests in the viewport, for naming the mesh under it
+    pub hover: Option<inspect::Hover>,
+    /// Where the viewport was clicked, None once the click turns into a drag
+    pub viewport_click: Option<[f32; 2]>,
+    pub show_materials: bool,
+    pub show_measurements: bool,
+    /// Measurement whose points are being clicked in the viewport
+    pub measuring: Option<measure::Placement>,
+    pub measure_kind: measure::MeasureKind,
+    pub measure_snap: measure::Snap,
+    /// Material picked in the Materials window, the meshes using it are tinted
+    pub highlighted_material: Option<(u32, String)>,
+    /// The model the last repair ran on and what it did
+    pub last_repair: Option<(u32, repair::Report)>,
+    pub simplify: simplify::Simplify,
+    pub show_slicing: bool,
+    pub slicing: slicing::Slicing,
+    /// Stepping through the folder of a model with Page Up and Page Down
+    pub folder_browser: Option<browse::FolderBrowser>,
+    /// Models to open in a window of their own on the next frame
+    pub detach_requests: Vec<u32>,
+    /// The viewport tabs, the active one's contents are in the fields above
+    pub scenes: Vec<scene::Scene>,
+    pub active_scene: usize,
+    pub next_scene_id: u32,
+    /// Selects a scene's tab on the next frame, for switches that don't come
+    /// from clicking the tab
+    pub scene_select_request: Option<u32>,
+    /// Name typed in for a new settings profile
+    pub new_profile_name: String,
 }
 
 impl Default for State {
     fn default() -> Self {
         Self {
-            active_model: None,
+            selection: vec![],
             show_console: false,
             show_help_menu_about: false,
             show_settings: false,
@@ -52,26 +441,585 @@ impl Default for State {
             fov_zoom: true,
             rotation_speed: 1.0,
             wireframe: false,
+            display_mode: DisplayMode::Shaded,
             camera: Camera::default(),
             objects: vec![],
+            groups: vec![],
+            next_group_id: 0,
             viewport_size: [0.0, 0.0],
-            logger: logger::WritableLog::default(),
+            logger: logger::ConsoleLogger::default(),
             settings: Settings::default(),
             fps: 0.0,
+            render_stats: render_queue::RenderStats::default(),
             show_textures: true,
+            theme_dirty: true,
+            console: ConsoleState::default(),
+            crash_report: None,
+            import_jobs: vec![],
+            part_scans: vec![],
+            part_picks: vec![],
+            import_prompts: vec![],
+            notifications: vec![],
+            tool_mode: ToolMode::Rotate,
+            viewport_cursor: None,
+            cursor_model_pos: None,
+            history: history::History::default(),
+            picking_pivot: false,
+            next_id: 0,
+            selected_mesh: None,
+            renaming: None,
+            objects_open_request: None,
+            view_projection: utils::mat_ident(),
+            annotating: false,
+            editing_annotation: None,
+            annotation_popup_request: false,
+            show_annotations: true,
+            show_grid_labels: false,
+            show_scale_bar: false,
+            show_ghosts: false,
+            markup: None,
+            script: script::ScriptConsole::default(),
+            capture_request: None,
+            window_capture_request: None,
+            url_input: None,
+            skip_textures: false,
+            heightmap_import: None,
+            heightmap_options: importer::heightmap::Options::default(),
+            primitive_segments: primitive::DEFAULT_SEGMENTS,
+            show_online: false,
+            online: online::Browser::default(),
+            show_normals: false,
+            debug_draw: debug_draw::DebugDraw::default(),
+            show_camera_path: false,
+            camera_path: camera_path::CameraPath::default(),
+            recorder: recording::Recorder::default(),
+            notes: notes::Notes::default(),
+            note_edit: None,
+            show_library: false,
+            library: library::Library::default(),
+            supports_reversed_z: false,
+            inspecting: false,
+            inspected_face: None,
+            hover: None,
+            viewport_click: None,
+            show_materials: false,
+            show_measurements: false,
+            measuring: None,
+            measure_kind: measure::MeasureKind::Distance,
+            measure_snap: measure::Snap::Vertex,
+            highlighted_material: None,
+            last_repair: None,
+            simplify: simplify::Simplify::default(),
+            show_slicing: false,
+            slicing: slicing::Slicing::default(),
+            folder_browser: None,
+            detach_requests: vec![],
+            scenes: vec![scene::Scene::new(0, format!("{} 1", tr("viewport.scene")))],
+            active_scene: 0,
+            next_scene_id: 1,
+            scene_select_request: None,
+            new_profile_name: String::new(),
         }
     }
 }
 
 impl State {
-    pub fn get_next_id(&self) -> u32 {
-        let mut id = 0;
-        if let Some(last_model) = self.objects.last() {
-            id = last_model.id + 1;
-        }
+    /// Width over height of the viewport the scene is drawn into
+    pub fn viewport_aspect(&self) -> f32 {
+        self.viewport_size[0] / self.viewport_size[1]
+    }
+
+    pub fn get_next_id(&mut self) -> u32 {
+        // removed models can come back with undo, so ids are never reused
+        let id = self.next_id;
+        self.next_id += 1;
 
         id
     }
+
+    /// The model the camera focuses on and picking works with
+    pub fn active_model(&self) -> Option<u32> {
+        self.selection.last().copied()
+    }
+
+    /// The highlighted material if it belongs to the model
+    pub fn highlighted_material(&self, model_id: u32) -> Option<&str> {
+        self.highlighted_material
+            .as_ref()
+            .filter(|(id, _)| *id == model_id)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Whether the scene is drawn with reversed-Z depth
+    pub fn reversed_z(&self) -> bool {
+        self.settings.reversed_z && self.supports_reversed_z
+    }
+
+    pub fn is_selected(&self, id: u32) -> bool {
+        self.selection.contains(&id)
+    }
+
+    /// Makes a model the only selected one and focuses the camera on it
+    pub fn select_model(&mut self, id: u32) {
+        self.selected_mesh = None;
+        if self.selection == [id] {
+            return;
+        }
+
+        if let Some(model) = self.objects.iter_mut().find(|m| m.id == id) {
+            model.reset_rotation();
+        }
+        self.selection = vec![id];
+        self.camera.focus_on_selected_model(
+            self.active_model(),
+            &self.objects,
+            self.viewport_aspect(),
+        );
+    }
+
+    /// Adds or removes a model from the selection (Ctrl+Click)
+    pub fn toggle_selection(&mut self, id: u32) {
+        self.selected_mesh = None;
+        match self.selection.iter().position(|s| *s == id) {
+            Some(i) => {
+                self.selection.remove(i);
+            }
+            None => self.selection.push(id),
+        }
+    }
+
+    /// Selects every model between the active model and this one (Shift+Click).
+    /// The active model stays the same so the range can be adjusted.
+    pub fn select_range(&mut self, id: u32) {
+        let anchor = self
+            .active_model()
+            .and_then(|active| self.objects.iter().position(|m| m.id == active));
+        let target = self.objects.iter().position(|m| m.id == id);
+        let (Some(anchor), Some(target)) = (anchor, target) else {
+            self.select_model(id);
+            return;
+        };
+
+        let anchor_id = self.objects[anchor].id;
+        let (start, end) = (anchor.min(target), anchor.max(target));
+        self.selected_mesh = None;
+        self.selection = self.objects[start..=end]
+            .iter()
+            .map(|m| m.id)
+            .filter(|m| *m != anchor_id)
+            .collect();
+        self.selection.push(anchor_id);
+    }
+
+    pub fn selected_models(&self) -> impl Iterator<Item = &model::Model> {
+        self.objects.iter().filter(|m| self.is_selected(m.id))
+    }
+
+    pub fn select_mesh(&mut self, model_id: u32, mesh: usize) {
+        self.select_model(model_id);
+        self.selected_mesh = Some((model_id, mesh));
+    }
+
+    /// Selects the mesh under a click in the viewport and opens it in the
+    /// Objects window. Only selected models are drawn there, so its model
+    /// just becomes the active one and the camera stays where it is.
+    pub fn select_clicked_mesh(&mut self, cursor: [f32; 2]) {
+        let Some(face) = inspect::pick(self, cursor) else {
+            return;
+        };
+        self.selection.retain(|id| *id != face.model_id);
+        self.selection.push(face.model_id);
+        self.selected_mesh = Some((face.model_id, face.mesh));
+        self.objects_open_request = Some((face.model_id, true));
+    }
+
+    /// Removes a model, keeping it in the history so it can be brought back with undo
+    pub fn remove_model(&mut self, idx: usize) {
+        let model = self.objects.remove(idx);
+        let id = model.id;
+        info!("Removing object {}", model.name);
+
+        self.history.record_removal(idx, model);
+        crash::set_loaded_models(self.objects.iter().map(|m| m.name.as_str()));
+
+        if self.selected_mesh.map(|(model_id, _)| model_id) == Some(id) {
+            self.selected_mesh = None;
+        }
+        if self.is_selected(id) {
+            self.selection.retain(|s| *s != id);
+            if self.selection.is_empty() {
+                if let Some(model) = self.objects.last_mut() {
+                    model.reset_rotation();
+                    self.selection.push(model.id);
+                }
+                self.camera.focus_on_selected_model(
+                    self.active_model(),
+                    &self.objects,
+                    self.viewport_aspect(),
+                );
+            }
+        }
+    }
+
+    /// Puts the selected models into a new group, next to each other where
+    /// the first of them is
+    pub fn group_selected(&mut self) {
+        let Some(start) = self
+            .objects
+            .iter()
+            .position(|m| self.selection.contains(&m.id))
+        else {
+            return;
+        };
+        let id = self.next_group_id;
+        self.next_group_id += 1;
+        self.groups.push(scene::Group {
+            id,
+            name: format!("{} {}", tr("objects.group"), id + 1),
+        });
+
+        let (mut members, rest): (Vec<model::Model>, Vec<model::Model>) =
+            std::mem::take(&mut self.objects)
+                .into_iter()
+                .partition(|m| self.selection.contains(&m.id));
+        for model in &mut members {
+            model.group = Some(id);
+        }
+        self.objects = rest;
+        self.objects.splice(start..start, members);
+        self.remove_empty_groups();
+    }
+
+    /// Takes the models out of the group and removes it, they stay where they are
+    pub fn ungroup(&mut self, id: u32) {
+        for model in self.objects.iter_mut().filter(|m| m.group == Some(id)) {
+            model.group = None;
+        }
+        self.groups.retain(|g| g.id != id);
+    }
+
+    /// Moves a model to where it was dropped in the Objects window
+    pub fn move_model(&mut self, id: u32, to: ObjectDrop) {
+        if to == ObjectDrop::Before(id) {
+            return;
+        }
+        let Some(from) = self.objects.iter().position(|m| m.id == id) else {
+            return;
+        };
+        let mut model = self.objects.remove(from);
+
+        let (index, group) = match to {
+            ObjectDrop::Before(target) => match self.objects.iter().position(|m| m.id == target) {
+                Some(i) => (i, self.objects[i].group),
+                None => (from, model.group),
+            },
+            ObjectDrop::IntoGroup(group) => {
+                let end = self
+                    .objects
+                    .iter()
+                    .rposition(|m| m.group == Some(group))
+                    .map_or(self.objects.len(), |i| i + 1);
+                (end, Some(group))
+            }
+        };
+        model.group = group;
+        self.objects.insert(index, model);
+        self.remove_empty_groups();
+    }
+
+    /// Takes the model out of its group, placing it right after the group
+    pub fn remove_from_group(&mut self, id: u32) {
+        let Some(group) = self
+            .objects
+            .iter()
+            .find(|m| m.id == id)
+            .and_then(|m| m.group)
+        else {
+            return;
+        };
+        self.move_model(id, ObjectDrop::IntoGroup(group));
+        if let Some(model) = self.objects.iter_mut().find(|m| m.id == id) {
+            model.group = None;
+        }
+        self.remove_empty_groups();
+    }
+
+    fn remove_empty_groups(&mut self) {
+        let objects = &self.objects;
+        self.groups
+            .retain(|g| objects.iter().any(|m| m.group == Some(g.id)));
+    }
+
+    /// Starts or stops stepping through the folder of the active model
+    pub fn toggle_folder_browsing(&mut self) {
+        if self.folder_browser.take().is_some() {
+            return;
+        }
+        let Some(path) = self
+            .active_model()
+            .and_then(|id| self.objects.iter().find(|m| m.id == id))
+            .and_then(|m| m.source_path.as_ref())
+        else {
+            return;
+        };
+
+        self.folder_browser = browse::FolderBrowser::new(path, &self.settings);
+        if self.folder_browser.is_none() {
+            warn!("Failed to list the models next to {:?}", path);
+        }
+    }
+
+    /// Replaces the model of the browsed file with the next or previous model
+    /// in its folder
+    pub fn step_folder(&mut self, step: isize) {
+        let Some(browser) = &mut self.folder_browser else {
+            return;
+        };
+        let current = browser.current().to_path_buf();
+        let job = browser.step(step, &self.settings);
+
+        if let Some(idx) = self
+            .objects
+            .iter()
+            .position(|m| m.source_path.as_ref() == Some(&current))
+        {
+            self.remove_model(idx);
+        }
+        self.import_jobs.push(job);
+    }
+
+    /// Adds an empty scene and switches to it
+    pub fn add_scene(&mut self) {
+        let id = self.next_scene_id;
+        self.next_scene_id += 1;
+        self.scenes.push(scene::Scene::new(
+            id,
+            format!("{} {}", tr("viewport.scene"), id + 1),
+        ));
+        self.switch_scene(self.scenes.len() - 1);
+        self.scene_select_request = Some(id);
+    }
+
+    pub fn switch_scene(&mut self, idx: usize) {
+        if idx == self.active_scene || idx >= self.scenes.len() {
+            return;
+        }
+        // taken out so they can be swapped with the rest of the state
+        let mut scenes = std::mem::take(&mut self.scenes);
+        scenes[self.active_scene].swap(self);
+        scenes[idx].swap(self);
+        self.scenes = scenes;
+        self.active_scene = idx;
+    }
+
+    /// Closes the scene and frees its models, the last scene can't be closed
+    pub fn close_scene(&mut self, idx: usize) {
+        if self.scenes.len() <= 1 || idx >= self.scenes.len() {
+            return;
+        }
+        if idx == self.active_scene {
+            let next = if idx > 0 { idx - 1 } else { 1 };
+            self.switch_scene(next);
+            self.scene_select_request = Some(self.scenes[next].id);
+        }
+        self.scenes.remove(idx);
+        if self.active_scene > idx {
+            self.active_scene -= 1;
+        }
+    }
+
+    pub fn notify(&mut self, notification: ui::notifications::Notification) {
+        self.notifications.push(notification);
+    }
+
+    /// Shows a toast for a file that couldn't be imported
+    pub fn notify_import_error(&mut self, path: &std::path::Path, error: importer::ImportError) {
+        let file = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy();
+        self.notify(ui::notifications::Notification::import_error(&file, error));
+    }
+
+    /// Frees everything the state keeps on the GPU while the context it was
+    /// made in is still current after being lost, which makes GL ignore the
+    /// deletes instead of deleting objects of the next context
+    pub fn release_gpu_resources(&mut self) {
+        for model in &mut self.objects {
+            model.release_gpu();
+        }
+        for scene in &mut self.scenes {
+            scene.release_gpu();
+        }
+        self.debug_draw = debug_draw::DebugDraw::default();
+        self.slicing.release_buffers();
+        self.online.release_thumbnails();
+        self.library.release_thumbnails();
+        if let Some(markup) = &mut self.markup {
+            markup.release_texture();
+        }
+    }
+
+    /// Uploads what `release_gpu_resources` kept on the CPU into the new
+    /// context. Evicted models are read from their files again once they're
+    /// selected.
+    pub fn restore_gpu_resources(&mut self) {
+        for model in &mut self.objects {
+            model.restore_gpu();
+        }
+        for scene in &mut self.scenes {
+            scene.restore_gpu();
+        }
+        self.online.upload_thumbnails();
+        if let Some(markup) = &mut self.markup {
+            markup.restore_texture();
+        }
+    }
+
+    /// Whether anything changes on screen without input, the frame rate only
+    /// drops in idle mode while nothing does
+    pub fn is_animating(&self) -> bool {
+        !self.import_jobs.is_empty()
+            || !self.part_scans.is_empty()
+            || self.objects.iter().any(|m| m.pending_textures.is_some())
+            || self.camera_path.playing
+            || self.camera_path.export.is_some()
+            || self.recorder.is_busy()
+            || self.slicing.playing
+            || self.capture_request.is_some()
+            || self.window_capture_request.is_some()
+            || self.script.is_running()
+            || self.online.is_busy()
+            || self.library.is_indexing()
+            || self.notifications.iter().any(|n| n.expires())
+    }
+
+    /// Removes every selected model as a single undo step
+    pub fn remove_selected(&mut self) {
+        self.history.begin_batch();
+        for id in self.selection.clone() {
+            if let Some(idx) = self.objects.iter().position(|m| m.id == id) {
+                self.remove_model(idx);
+            }
+        }
+        self.history.end_batch();
+    }
+
+    /// Shows all selected models if any of them is hidden, hides them otherwise
+    pub fn toggle_selected_visibility(&mut self) {
+        let visible = self.selected_models().any(|m| !m.visible);
+        for model in self.objects.iter_mut() {
+            if self.selection.contains(&model.id) {
+                model.visible = visible;
+            }
+        }
+    }
+
+    /// Lays the displayed models out in a grid on the ground plane, centered
+    /// on the origin. Every cell fits the largest of them plus some spacing.
+    pub fn arrange_selected(&mut self) {
+        let ids: Vec<u32> = self
+            .selected_models()
+            .filter(|m| m.visible)
+            .map(|m| m.id)
+            .collect();
+        if ids.is_empty() {
+            return;
+        }
+
+        let mut cell = glm::vec3(0.0, 0.0, 0.0);
+        for model in self.selected_models().filter(|m| m.visible) {
+            let size = model.world_size();
+            cell = glm::vec3(cell.x.max(size.x), 0.0, cell.z.max(size.z));
+        }
+        let cell = cell * (1.0 + ARRANGE_SPACING);
+
+        let columns = (ids.len() as f32).sqrt().ceil() as usize;
+        let rows = ids.len().div_ceil(columns);
+
+        self.history.begin_batch();
+        for (i, id) in ids.iter().enumerate() {
+            let Some(model) = self.objects.iter_mut().find(|m| m.id == *id) else {
+                continue;
+            };
+            self.history.record(model);
+
+            let column = (i % columns) as f32 - (columns - 1) as f32 / 2.0;
+            let row = (i / columns) as f32 - (rows - 1) as f32 / 2.0;
+            model.place_on_grid(column * cell.x, row * cell.z);
+        }
+        self.history.end_batch();
+    }
+
+    pub fn undo(&mut self) {
+        let before: Vec<u32> = self.objects.iter().map(|m| m.id).collect();
+        if self.history.undo(&mut self.objects) {
+            self.after_history_step(&before);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        let before: Vec<u32> = self.objects.iter().map(|m| m.id).collect();
+        if self.history.redo(&mut self.objects) {
+            self.after_history_step(&before);
+        }
+    }
+
+    /// `before` are the ids of the models there were before the step
+    fn after_history_step(&mut self, before: &[u32]) {
+        crash::set_loaded_models(self.objects.iter().map(|m| m.name.as_str()));
+
+        // selected models might have been removed or restored
+        let objects = &self.objects;
+        self.selection
+            .retain(|id| objects.iter().any(|m| m.id == *id));
+        // restored models are selected, which also loads the ones that were
+        // unloaded while they were in the history back in
+        let restored = objects.iter().filter(|m| !before.contains(&m.id));
+        self.selection.extend(restored.map(|m| m.id));
+        if self.selection.is_empty() {
+            self.selection.extend(self.objects.last().map(|m| m.id));
+            self.selected_mesh = None;
+        }
+    }
+
+    /// Pins a new note to the point under the cursor on the active model and
+    /// opens it for editing. Clicking on empty space keeps annotating active.
+    pub fn add_annotation(&mut self) {
+        let (Some(id), Some(pos)) = (self.active_model(), self.cursor_model_pos) else {
+            return;
+        };
+        let Some(model) = self.objects.iter_mut().find(|m| m.id == id) else {
+            return;
+        };
+
+        model.annotations.push(model::Annotation {
+            position: pos,
+            text: String::new(),
+        });
+        self.editing_annotation = Some((id, model.annotations.len() - 1, String::new()));
+        self.annotation_popup_request = true;
+        self.annotating = false;
+    }
+
+    /// Sets the active model's pivot to the point under the cursor.
+    /// Clicking on empty space keeps picking active.
+    pub fn pick_pivot(&mut self) {
+        let (Some(id), Some(pos)) = (self.active_model(), self.cursor_model_pos) else {
+            return;
+        };
+        let Some(model) = self.objects.iter_mut().find(|m| m.id == id) else {
+            return;
+        };
+
+        self.history.record(model);
+        model.set_pivot(Some(pos));
+        self.picking_pivot = false;
+        info!(
+            "Set pivot of {} to ({:.3}, {:.3}, {:.3})",
+            model.name, pos.x, pos.y, pos.z
+        );
+    }
 }
 
 pub fn init_imgui(
@@ -119,76 +1067,303 @@ pub fn init_imgui(
     (imgui, glfw_platform, renderer)
 }
 
+/// Moves ImGui over to a window that replaces the one it was drawn in, with
+/// a renderer for the new window's context. That context has to be current.
+pub fn reattach_imgui(
+    imgui: &mut imgui::Context,
+    glfw_platform: &mut imgui_glfw_support::GlfwPlatform,
+    window: &mut glfw::Window,
+) -> imgui_opengl_renderer::Renderer {
+    glfw_platform.attach_window(
+        imgui.io_mut(),
+        window,
+        imgui_glfw_support::HiDpiMode::Default,
+    );
+    gl::load(|e| window.get_proc_address(e) as *const std::os::raw::c_void);
+
+    let renderer = imgui_opengl_renderer::Renderer::new(imgui);
+    glfw_platform.set_clipboard_backend(imgui, window);
+    renderer
+}
+
 pub fn import_model(state: &mut State) {
-    let models = match rfd::FileDialog::new()
-        .set_title("Import Model(s)")
-        .set_directory("./")
-        .add_filter("All supported files", &["obj", "OBJ", "stl", "STL"])
-        .add_filter("Wavefront OBJ (.obj)", &["obj", "OBJ"])
-        .add_filter("STL (.stl)", &["stl", "STL"])
-        .pick_files()
-    {
+    let mut dialog = rfd::FileDialog::new()
+        .set_title(tr("dialog.import.title"))
+        .set_directory("./");
+    for (name, extensions) in importer::registry::dialog_filters() {
+        let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+        dialog = dialog.add_filter(&name, &extensions);
+    }
+    let models = match dialog.pick_files() {
         Some(m) => m,
         None => return,
     };
-    utils::import_models_from_paths(&models, state);
+    utils::ask_import_options(&models, state);
 }
 
 pub fn draw_main_menu_bar(ui: &imgui::Ui, state: &mut State, window: &mut glfw::Window) {
     ui.main_menu_bar(|| {
-        ui.menu("File", || {
+        ui.menu(tr("menu.file"), || {
             if ui
-                .menu_item_config("Import Model(s)")
+                .menu_item_config(tr("menu.file.import"))
                 .shortcut("Ctrl+O")
                 .build()
             {
                 import_model(state);
             }
-            if ui.menu_item_config("Settings").build() {
+            if ui.menu_item_config(tr("menu.file.open_url")).build() {
+                state.url_input = Some(String::new());
+            }
+            if ui
+                .menu_item_config(tr("menu.file.skip_textures"))
+                .selected(state.skip_textures)
+                .build()
+            {
+                state.skip_textures = !state.skip_textures;
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr("menu.file.skip_textures_tooltip"));
+            }
+            ui.menu(tr("menu.file.new_primitive"), || {
+                for p in primitive::Primitive::ALL {
+                    if ui.menu_item(p.name()) {
+                        primitive::add(state, p);
+                    }
+                }
+                ui.separator();
+                ui.set_next_item_width(150.0);
+                imgui::Drag::new(tr("primitive.segments"))
+                    .range(1, primitive::MAX_SEGMENTS)
+                    .build(ui, &mut state.primitive_segments);
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr("primitive.segments_tooltip"));
+                }
+            });
+            if ui
+                .menu_item_config(tr("menu.file.import_heightmap"))
+                .build()
+            {
+                state.heightmap_import = rfd::FileDialog::new()
+                    .set_title(tr("dialog.import_heightmap.title"))
+                    .set_directory("./")
+                    .add_filter(
+                        tr("dialog.import_heightmap.images"),
+                        &importer::heightmap::EXTENSIONS,
+                    )
+                    .pick_file();
+            }
+            ui.separator();
+            if ui
+                .menu_item_config(tr("menu.file.export_active"))
+                .enabled(state.active_model().is_some())
+                .build()
+            {
+                export_active_model(state);
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr("menu.file.export_active_tooltip"));
+            }
+            let can_browse = state.folder_browser.is_some()
+                || state
+                    .active_model()
+                    .and_then(|id| state.objects.iter().find(|m| m.id == id))
+                    .is_some_and(|m| m.source_path.is_some());
+            if ui
+                .menu_item_config(tr("menu.file.browse_folder"))
+                .shortcut("PgUp/PgDn")
+                .selected(state.folder_browser.is_some())
+                .enabled(can_browse)
+                .build()
+            {
+                state.toggle_folder_browsing();
+            }
+            if ui.is_item_hovered() {
+                match &state.folder_browser {
+                    Some(browser) => {
+                        let (position, count) = browser.position();
+                        ui.tooltip_text(format!(
+                            "{} ({}/{})",
+                            browser.current().display(),
+                            position,
+                            count
+                        ));
+                    }
+                    None => ui.tooltip_text(tr("menu.file.browse_folder_tooltip")),
+                }
+            }
+            ui.separator();
+            if ui.menu_item_config(tr("menu.file.settings")).build() {
                 state.show_settings = !state.show_settings;
             }
-            if ui.menu_item_config("Quit").shortcut("Ctrl+Q").build() {
+            if ui
+                .menu_item_config(tr("menu.file.quit"))
+                .shortcut("Ctrl+Q")
+                .build()
+            {
                 window.set_should_close(true);
             }
         });
-        ui.menu("View", || {
+        ui.menu(tr("menu.edit"), || {
+            if ui
+                .menu_item_config(tr("menu.edit.undo"))
+                .shortcut("Ctrl+Z")
+                .enabled(state.history.can_undo())
+                .build()
+            {
+                state.undo();
+            }
+            if ui
+                .menu_item_config(tr("menu.edit.redo"))
+                .shortcut("Ctrl+Y")
+                .enabled(state.history.can_redo())
+                .build()
+            {
+                state.redo();
+            }
+        });
+        ui.menu(tr("menu.view"), || {
             if ui
-                .menu_item_config("Show Grid")
+                .menu_item_config(tr("menu.view.grid"))
                 .selected(state.draw_grid)
                 .build()
             {
                 state.draw_grid = !state.draw_grid;
             }
+            item_help(ui, "menu.view.grid");
+            if ui
+                .menu_item_config(tr("menu.view.grid_labels"))
+                .selected(state.show_grid_labels)
+                .enabled(state.draw_grid)
+                .build()
+            {
+                state.show_grid_labels = !state.show_grid_labels;
+            }
+            item_help(ui, "menu.view.grid_labels");
+            if ui
+                .menu_item_config(tr("menu.view.scale_bar"))
+                .selected(state.show_scale_bar)
+                .build()
+            {
+                state.show_scale_bar = !state.show_scale_bar;
+            }
+            item_help(ui, "menu.view.scale_bar");
             if ui
-                .menu_item_config("Draw Bounding Box")
+                .menu_item_config(tr("menu.view.aabb"))
                 .selected(state.draw_aabb)
                 .build()
             {
                 state.draw_aabb = !state.draw_aabb;
             }
-        });
-        ui.menu("Help", || {
+            item_help(ui, "menu.view.aabb");
             if ui
-                .menu_item_config("Keybinds")
-                .selected(state.show_keybinds)
+                .menu_item_config(tr("menu.view.normals"))
+                .selected(state.show_normals)
                 .build()
             {
-                state.show_keybinds = !state.show_keybinds;
+                state.show_normals = !state.show_normals;
             }
+            item_help(ui, "menu.view.normals");
             if ui
-                .menu_item_config("About")
-                .selected(state.show_help_menu_about)
+                .menu_item_config(tr("menu.view.annotations"))
+                .selected(state.show_annotations)
                 .build()
             {
-                state.show_help_menu_about = !state.show_help_menu_about;
+                state.show_annotations = !state.show_annotations;
             }
-        });
-        let mem = state
-            .objects
-            .iter()
-            .fold(0_usize, |acc, m| acc + m.mem_usage) as f32;
+            item_help(ui, "menu.view.annotations");
+            if ui
+                .menu_item_config(tr("menu.view.ghosts"))
+                .selected(state.show_ghosts)
+                .build()
+            {
+                state.show_ghosts = !state.show_ghosts;
+                // turning it back on starts over from the poses at that time
+                for model in &mut state.objects {
+                    model.ghost = None;
+                }
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr("menu.view.ghosts_tooltip"));
+            }
+            if ui
+                .menu_item_config(tr("menu.view.materials"))
+                .selected(state.show_materials)
+                .build()
+            {
+                state.show_materials = !state.show_materials;
+            }
+            if ui
+                .menu_item_config(tr("menu.view.measurements"))
+                .selected(state.show_measurements)
+                .build()
+            {
+                state.show_measurements = !state.show_measurements;
+            }
+            if ui
+                .menu_item_config(tr("menu.view.camera_path"))
+                .selected(state.show_camera_path)
+                .build()
+            {
+                state.show_camera_path = !state.show_camera_path;
+            }
+            if ui
+                .menu_item_config(tr("menu.view.slicing"))
+                .selected(state.show_slicing)
+                .build()
+            {
+                state.show_slicing = !state.show_slicing;
+            }
+            if ui
+                .menu_item_config(tr("menu.view.library"))
+                .selected(state.show_library)
+                .build()
+            {
+                state.show_library = !state.show_library;
+            }
+            item_help(ui, "menu.view.library");
+            if ui
+                .menu_item_config(tr("menu.view.online"))
+                .selected(state.show_online)
+                .build()
+            {
+                state.show_online = !state.show_online;
+            }
+        });
+        ui.menu(tr("menu.help"), || {
+            if ui
+                .menu_item_config(tr("menu.help.keybinds"))
+                .selected(state.show_keybinds)
+                .build()
+            {
+                state.show_keybinds = !state.show_keybinds;
+            }
+            if ui
+                .menu_item_config(tr("menu.help.about"))
+                .selected(state.show_help_menu_about)
+                .build()
+            {
+                state.show_help_menu_about = !state.show_help_menu_about;
+            }
+            ui.separator();
+            if ui.menu_item(tr("menu.help.load_samples")) {
+                samples::load_all(state);
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip(|| {
+                    ui.text(tr("menu.help.load_samples_tooltip"));
+                    for sample in samples::SAMPLES {
+                        ui.bullet_text(format!("{} - {}", sample.name, sample.license));
+                    }
+                });
+            }
+        });
+        let mem = state
+            .objects
+            .iter()
+            .fold(0_usize, |acc, m| acc + m.mem_usage) as f32;
         let mem_fps = format!(
-            "Mem: {:.1}MB | FPS: {:.1}",
+            "{}: {:.1}MB | FPS: {:.1}",
+            tr("menu.mem"),
             mem / (1024.0 * 1024.0),
             state.fps
         );
@@ -198,6 +1373,23 @@ pub fn draw_main_menu_bar(ui: &imgui::Ui, state: &mut State, window: &mut glfw::
         ];
         ui.dummy(avail_size);
         ui.text(&mem_fps);
+        if ui.is_item_hovered() {
+            let stats = state.render_stats;
+            ui.tooltip(|| {
+                ui.text(format!("{}: {}", tr("menu.draw_calls"), stats.draw_calls));
+                ui.text(format!(
+                    "{}: {}",
+                    tr("menu.material_changes"),
+                    stats.material_changes
+                ));
+                ui.text(format!(
+                    "{}: {}",
+                    tr("menu.mesh_changes"),
+                    stats.mesh_changes
+                ));
+                ui.text(format!("{}: {}", tr("menu.culled_meshes"), stats.culled));
+            });
+        }
     });
 }
 
@@ -207,7 +1399,7 @@ fn draw_about_window(ui: &imgui::Ui, state: &mut State) {
     }
     let display_size = ui.io().display_size;
 
-    ui.window("About")
+    ui.window(format!("{}###About", tr("window.about")))
         .resizable(false)
         .movable(false)
         .opened(&mut state.show_help_menu_about)
@@ -219,25 +1411,58 @@ fn draw_about_window(ui: &imgui::Ui, state: &mut State) {
         .build(|| {
             ui.text("3dobs - 3D Object Browser");
             ui.text(format!(
-                "Version: {}-{}",
+                "{}: {}-{}",
+                tr("about.version"),
                 env!("CARGO_PKG_VERSION"),
                 env!("GIT_HASH")
             ));
             ui.text(env!("CARGO_PKG_DESCRIPTION"));
             ui.spacing();
             ui.spacing();
-            ui.text(format!("Made by: {}", env!("CARGO_PKG_AUTHORS")));
+            ui.text(format!(
+                "{}: {}",
+                tr("about.made_by"),
+                env!("CARGO_PKG_AUTHORS")
+            ));
         });
 }
 
+/// Shows the help text of `key` from the string catalogs while the last item
+/// is hovered, see `i18n::tooltip`
+pub fn item_help(ui: &imgui::Ui, key: &str) {
+    if let Some(help) = ui::i18n::tooltip(key) {
+        if ui.is_item_hovered() {
+            ui.tooltip_text(help);
+        }
+    }
+}
+
+/// Puts a (?) after the last item that shows the help text of `key`, which
+/// the item itself shows too
+pub fn help_marker(ui: &imgui::Ui, key: &str) {
+    let Some(help) = ui::i18n::tooltip(key) else {
+        return;
+    };
+    if ui.is_item_hovered() {
+        ui.tooltip_text(help);
+    }
+    ui.same_line();
+    ui.text_disabled("(?)");
+    if ui.is_item_hovered() {
+        ui.tooltip_text(help);
+    }
+}
+
 pub fn draw_settings_window(ui: &imgui::Ui, state: &mut State) {
     if !state.show_settings {
         return;
     }
     let display_size = ui.io().display_size;
 
-    ui.window("Settings")
-        .opened(&mut state.show_settings)
+    // the tabs need all of the state
+    let mut opened = state.show_settings;
+    ui.window(format!("{}###Settings", tr("window.settings")))
+        .opened(&mut opened)
         .movable(false)
         .position(
             [display_size[0] / 2.0, display_size[1] / 2.0],
@@ -245,13 +1470,534 @@ pub fn draw_settings_window(ui: &imgui::Ui, state: &mut State) {
         )
         .position_pivot([0.5, 0.5])
         .build(|| {
-            if ui.checkbox(
-                "Only allow one program instance (Reboot required when enabling)",
-                &mut state.settings.one_instance,
-            ) {
-                confy::store("3dobs", "settings", state.settings.clone()).unwrap();
+            let active = config::active_profile();
+            let mut profiles: Vec<Option<String>> = std::iter::once(None)
+                .chain(config::profiles().into_iter().map(Some))
+                .collect();
+            if !profiles.contains(&active) {
+                profiles.push(active.clone());
+            }
+            let mut profile_idx = profiles.iter().position(|p| *p == active).unwrap_or(0);
+            ui.set_next_item_width(150.0);
+            let switch_to = if ui.combo(tr("settings.profile"), &mut profile_idx, &profiles, |p| {
+                p.as_deref()
+                    .unwrap_or(tr("settings.profile.default"))
+                    .into()
+            }) && profiles[profile_idx] != active
+            {
+                Some(profiles[profile_idx].clone())
+            } else {
+                None
+            };
+            help_marker(ui, "settings.profile");
+            ui.same_line();
+            ui.set_next_item_width(150.0);
+            ui.input_text("###NewProfile", &mut state.new_profile_name)
+                .hint(tr("settings.profile.name_hint"))
+                .build();
+            ui.same_line();
+            let name = state.new_profile_name.trim().to_string();
+            let mut created = None;
+            ui.disabled(!config::is_valid_profile_name(&name), || {
+                if ui.button(tr("settings.profile.create")) {
+                    // the new profile starts out with the current settings
+                    match config::create_profile(&name, state.settings.clone()) {
+                        Ok(()) => created = Some(Some(name.clone())),
+                        Err(e) => error!("Failed to create the profile {}: {}", name, e),
+                    }
+                }
+            });
+            if created.is_some() {
+                state.new_profile_name.clear();
+            }
+            if let Some(profile) = switch_to.or(created) {
+                match config::switch_profile(profile.clone()) {
+                    Ok(settings) => {
+                        info!(
+                            "Switched to the settings profile {}",
+                            profile.as_deref().unwrap_or(tr("settings.profile.default"))
+                        );
+                        state.settings = settings;
+                        ui::i18n::set_language(state.settings.language);
+                        state.theme_dirty = true;
+                    }
+                    Err(e) => error!("Failed to load the settings profile: {}", e),
+                }
+            }
+            ui.separator();
+
+            let Some(_tab_bar) = ui.tab_bar("###SettingsTabs") else {
+                return;
+            };
+            if let Some(_tab) = ui.tab_item(tr("settings.tab.general")) {
+                draw_general_settings(ui, state);
+            }
+            if let Some(_tab) = ui.tab_item(tr("settings.tab.formats")) {
+                draw_format_settings(ui, state);
+            }
+        });
+    state.show_settings = opened;
+}
+
+fn draw_general_settings(ui: &imgui::Ui, state: &mut State) {
+    if ui.checkbox(
+        tr("settings.one_instance"),
+        &mut state.settings.one_instance,
+    ) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.one_instance");
+
+    if ui.checkbox(tr("settings.log_to_file"), &mut state.settings.log_to_file) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.log_to_file");
+
+    ui.set_next_item_width(150.0);
+    if imgui::Drag::new(tr("settings.console_max_history"))
+        .range(100, 100_000)
+        .speed(10.0)
+        .build(ui, &mut state.settings.console_max_history)
+    {
+        state
+            .logger
+            .arc
+            .write()
+            .unwrap()
+            .set_max_history(state.settings.console_max_history);
+    }
+    if ui.is_item_deactivated_after_edit() {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.console_max_history");
+
+    let mut language_idx = Language::ALL
+        .iter()
+        .position(|l| *l == state.settings.language)
+        .unwrap_or(0);
+    if ui.combo(
+        tr("settings.language"),
+        &mut language_idx,
+        &Language::ALL,
+        |l| l.native_name().into(),
+    ) {
+        state.settings.language = Language::ALL[language_idx];
+        ui::i18n::set_language(state.settings.language);
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.language");
+
+    let mut theme_idx = Theme::ALL
+        .iter()
+        .position(|t| *t == state.settings.theme)
+        .unwrap_or(0);
+    if ui.combo(tr("settings.theme"), &mut theme_idx, &Theme::ALL, |t| {
+        t.name().into()
+    }) {
+        state.settings.theme = Theme::ALL[theme_idx];
+        state.theme_dirty = true;
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.theme");
+    if ui.color_edit3(
+        tr("settings.accent_color"),
+        &mut state.settings.accent_color,
+    ) {
+        state.theme_dirty = true;
+    }
+    // don't write the config on every drag of the color picker
+    if ui.is_item_deactivated_after_edit() {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.accent_color");
+    ui.same_line();
+    if ui.button(tr("settings.reset")) {
+        state.settings.accent_color = ui::theme::DEFAULT_ACCENT_COLOR;
+        state.theme_dirty = true;
+        config::store(state.settings.clone()).unwrap();
+    }
+
+    let mut sampling_changed = false;
+    let mut filter_idx = TextureFilter::ALL
+        .iter()
+        .position(|f| *f == state.settings.texture_filter)
+        .unwrap_or(0);
+    if ui.combo(
+        tr("settings.texture_filter"),
+        &mut filter_idx,
+        &TextureFilter::ALL,
+        |f| f.name().into(),
+    ) {
+        state.settings.texture_filter = TextureFilter::ALL[filter_idx];
+        sampling_changed = true;
+    }
+    help_marker(ui, "settings.texture_filter");
+
+    // only offer the levels the driver supports
+    let max_anisotropy = texture::max_anisotropy();
+    let levels: Vec<f32> = texture::ANISOTROPY_LEVELS
+        .into_iter()
+        .filter(|l| *l <= max_anisotropy)
+        .collect();
+    let mut anisotropy_idx = levels
+        .iter()
+        .rposition(|l| *l <= state.settings.anisotropy)
+        .unwrap_or(0);
+    if ui.combo(
+        tr("settings.anisotropy"),
+        &mut anisotropy_idx,
+        &levels,
+        |l| format!("{}x", l).into(),
+    ) {
+        state.settings.anisotropy = levels[anisotropy_idx];
+        sampling_changed = true;
+    }
+    help_marker(ui, "settings.anisotropy");
+
+    if sampling_changed {
+        let sampling = state.settings.texture_sampling();
+        for model in &state.objects {
+            model.set_texture_sampling(sampling);
+        }
+        config::store(state.settings.clone()).unwrap();
+    }
+
+    let mut size_idx = texture::MAX_TEXTURE_SIZES
+        .iter()
+        .position(|s| *s == state.settings.max_texture_size)
+        .unwrap_or(0);
+    if ui.combo(
+        tr("settings.max_texture_size"),
+        &mut size_idx,
+        &texture::MAX_TEXTURE_SIZES,
+        |s| match s {
+            Some(size) => format!("{}x{}", size, size).into(),
+            None => tr("settings.max_texture_size.unlimited").into(),
+        },
+    ) {
+        state.settings.max_texture_size = texture::MAX_TEXTURE_SIZES[size_idx];
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.max_texture_size");
+
+    ui.set_next_item_width(150.0);
+    imgui::Drag::new(tr("settings.texture_threads"))
+        .range(0, MAX_TEXTURE_THREADS)
+        .build(ui, &mut state.settings.texture_threads);
+    if ui.is_item_deactivated_after_edit() {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.texture_threads");
+
+    if ui.checkbox(
+        tr("settings.low_res_inactive_textures"),
+        &mut state.settings.low_res_inactive_textures,
+    ) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.low_res_inactive_textures");
+
+    if ui.checkbox(
+        tr("settings.compact_vertices"),
+        &mut state.settings.compact_vertices,
+    ) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.compact_vertices");
+
+    if ui.checkbox(tr("settings.pick_parts"), &mut state.settings.pick_parts) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.pick_parts");
+
+    let mut budget_idx = model::MEMORY_BUDGETS
+        .iter()
+        .position(|b| *b == state.settings.memory_budget)
+        .unwrap_or(0);
+    if ui.combo(
+        tr("settings.memory_budget"),
+        &mut budget_idx,
+        &model::MEMORY_BUDGETS,
+        |b| match b {
+            Some(mb) => format!("{}MB", mb).into(),
+            None => tr("settings.memory_budget.unlimited").into(),
+        },
+    ) {
+        state.settings.memory_budget = model::MEMORY_BUDGETS[budget_idx];
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.memory_budget");
+
+    if ui.checkbox(
+        tr("settings.auto_clip_planes"),
+        &mut state.settings.auto_clip_planes,
+    ) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.auto_clip_planes");
+
+    ui.disabled(!state.supports_reversed_z, || {
+        if ui.checkbox(tr("settings.reversed_z"), &mut state.settings.reversed_z) {
+            config::store(state.settings.clone()).unwrap();
+        }
+    });
+    if ui.is_item_hovered_with_flags(imgui::ItemHoveredFlags::ALLOW_WHEN_DISABLED) {
+        ui.tooltip_text(if state.supports_reversed_z {
+            tr("settings.reversed_z_tooltip")
+        } else {
+            tr("settings.reversed_z_unsupported")
+        });
+    }
+
+    if ui.checkbox(tr("settings.vsync"), &mut state.settings.vsync) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.vsync");
+
+    let mut version_idx = gpu::GL_VERSIONS
+        .iter()
+        .position(|v| *v == state.settings.gl_version)
+        .unwrap_or(0);
+    if ui.combo(
+        tr("settings.gl_version"),
+        &mut version_idx,
+        &gpu::GL_VERSIONS,
+        |v| match v {
+            Some((major, minor)) => format!("{}.{}", major, minor).into(),
+            None => tr("settings.gl_version.default").into(),
+        },
+    ) {
+        state.settings.gl_version = gpu::GL_VERSIONS[version_idx];
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.restart");
+    let mut gl_profile_idx = gpu::GlProfile::ALL
+        .iter()
+        .position(|p| *p == state.settings.gl_profile)
+        .unwrap_or(0);
+    if ui.combo(
+        tr("settings.gl_profile"),
+        &mut gl_profile_idx,
+        &gpu::GlProfile::ALL,
+        |p| p.name().into(),
+    ) {
+        state.settings.gl_profile = gpu::GlProfile::ALL[gl_profile_idx];
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.restart");
+    if ui.checkbox(
+        tr("settings.software_rendering"),
+        &mut state.settings.software_rendering,
+    ) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.software_rendering");
+    if ui.checkbox(
+        tr("settings.discrete_gpu"),
+        &mut state.settings.discrete_gpu,
+    ) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.discrete_gpu");
+
+    let mut cap_idx = pacing::FPS_CAPS
+        .iter()
+        .position(|c| *c == state.settings.fps_cap)
+        .unwrap_or(0);
+    if ui.combo(
+        tr("settings.fps_cap"),
+        &mut cap_idx,
+        &pacing::FPS_CAPS,
+        |c| match c {
+            Some(fps) => format!("{} FPS", fps).into(),
+            None => tr("settings.fps_cap.unlimited").into(),
+        },
+    ) {
+        state.settings.fps_cap = pacing::FPS_CAPS[cap_idx];
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.fps_cap");
+
+    if ui.checkbox(tr("settings.idle_mode"), &mut state.settings.idle_mode) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.idle_mode");
+
+    let options: Vec<Option<importer::stl::Units>> = std::iter::once(None)
+        .chain(importer::stl::Units::ALL.map(Some))
+        .collect();
+    let mut units_idx = options
+        .iter()
+        .position(|u| *u == state.settings.stl_units)
+        .unwrap_or(0);
+    if ui.combo(tr("settings.stl_units"), &mut units_idx, &options, |u| {
+        u.map_or(tr("units.unknown"), |u| u.name()).into()
+    }) {
+        state.settings.stl_units = options[units_idx];
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.stl_units");
+
+    let capture_dir = state
+        .settings
+        .capture_dir
+        .as_ref()
+        .map_or(tr("settings.capture_dir.working_dir").into(), |d| {
+            d.to_string_lossy()
+        });
+    ui.text(format!("{}: {}", tr("settings.capture_dir"), capture_dir));
+    ui.same_line();
+    if ui.small_button(tr("settings.capture_dir.browse")) {
+        if let Some(dir) = rfd::FileDialog::new()
+            .set_title(tr("dialog.capture_dir.title"))
+            .pick_folder()
+        {
+            state.settings.capture_dir = Some(dir);
+            config::store(state.settings.clone()).unwrap();
+        }
+    }
+    ui.same_line();
+    ui.disabled(state.settings.capture_dir.is_none(), || {
+        if ui.small_button(tr("settings.reset")) {
+            state.settings.capture_dir = None;
+            config::store(state.settings.clone()).unwrap();
+        }
+    });
+    ui.set_next_item_width(300.0);
+    ui.input_text(
+        tr("settings.capture_name"),
+        &mut state.settings.capture_name,
+    )
+    .build();
+    if ui.is_item_deactivated_after_edit() {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.capture_name");
+    if ui.checkbox(
+        tr("settings.open_captures"),
+        &mut state.settings.open_captures,
+    ) {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.open_captures");
+
+    ui.set_next_item_width(300.0);
+    ui.input_text(
+        tr("settings.thingiverse_token"),
+        &mut state.settings.thingiverse_token,
+    )
+    .password(true)
+    .build();
+    if ui.is_item_deactivated_after_edit() {
+        config::store(state.settings.clone()).unwrap();
+    }
+    help_marker(ui, "settings.thingiverse_token");
+
+    if ui.button(tr("settings.register_file_types")) {
+        if let Err(e) = shell::register_file_types() {
+            error!("Failed to register file types: {:#}", e);
+        }
+    }
+    help_marker(ui, "settings.register_file_types");
+
+    if ui.button(tr("settings.clear_geometry_cache")) {
+        match importer::cache::clear() {
+            Ok(()) => info!("Cleared the geometry cache"),
+            Err(e) => error!("Failed to clear the geometry cache: {}", e),
+        }
+    }
+    help_marker(ui, "settings.clear_geometry_cache");
+}
+
+/// Import options of each format, for the files that are imported without
+/// asking and as the start of the import options window
+fn draw_format_settings(ui: &imgui::Ui, state: &mut State) {
+    ui.text_wrapped(tr("settings.formats.description"));
+    for importer in importer::registry::IMPORTERS {
+        for ext in importer.extensions() {
+            let _id = ui.push_id(ext);
+            let header = format!("{} (.{})", importer.name(), ext);
+            if !ui.collapsing_header(header, imgui::TreeNodeFlags::empty()) {
+                continue;
+            }
+
+            let defaults = &mut state.settings.format_defaults;
+            let current = defaults.get(*ext).copied().unwrap_or_default();
+            let mut options = current;
+            let edited = draw_import_option_fields(ui, &mut options, importer.coordinate_system());
+            // drags change the options every frame but are saved once they're let go
+            if options != current {
+                defaults.insert(ext.to_string(), options);
+            }
+            if edited {
+                config::store(state.settings.clone()).unwrap();
+            }
+            if state.settings.format_defaults.contains_key(*ext)
+                && ui.small_button(tr("settings.formats.reset"))
+            {
+                state.settings.format_defaults.remove(*ext);
+                config::store(state.settings.clone()).unwrap();
+            }
+
+            // those are used instead without asking
+            if state.settings.import_options.contains_key(*ext) {
+                ui.text_disabled(tr("settings.formats.remembered"));
+                ui.same_line();
+                if ui.small_button(tr("settings.formats.forget")) {
+                    state.settings.import_options.remove(*ext);
+                    config::store(state.settings.clone()).unwrap();
+                }
+            }
+        }
+    }
+}
+
+fn draw_crash_report_window(ui: &imgui::Ui, state: &mut State) {
+    let Some(report_path) = state.crash_report.clone() else {
+        return;
+    };
+    let display_size = ui.io().display_size;
+    let mut opened = true;
+
+    ui.window(format!("{}###CrashReport", tr("window.crash_report")))
+        .opened(&mut opened)
+        .resizable(false)
+        .movable(false)
+        .collapsible(false)
+        .position(
+            [display_size[0] / 2.0, display_size[1] / 2.0],
+            imgui::Condition::Always,
+        )
+        .position_pivot([0.5, 0.5])
+        .build(|| {
+            ui.text(tr("crash_report.message"));
+            ui.text_disabled(report_path.to_string_lossy());
+            ui.spacing();
+
+            if ui.button(tr("crash_report.open")) {
+                if let Err(e) = utils::open_path(&report_path) {
+                    error!("{}", e);
+                }
+                state.crash_report = None;
+            }
+            ui.same_line();
+            if ui.button(tr("crash_report.open_folder")) {
+                if let Some(dir) = report_path.parent() {
+                    if let Err(e) = utils::open_path(dir) {
+                        error!("{}", e);
+                    }
+                }
+                state.crash_report = None;
+            }
+            ui.same_line();
+            if ui.button(tr("crash_report.dismiss")) {
+                state.crash_report = None;
             }
         });
+
+    if !opened {
+        state.crash_report = None;
+    }
 }
 
 fn draw_keybinds_window(ui: &imgui::Ui, state: &mut State) {
@@ -260,7 +2006,7 @@ fn draw_keybinds_window(ui: &imgui::Ui, state: &mut State) {
     }
     let display_size = ui.io().display_size;
 
-    ui.window("Keybinds")
+    ui.window(format!("{}###Keybinds", tr("window.keybinds")))
         .opened(&mut state.show_keybinds)
         .resizable(false)
         .movable(false)
@@ -278,174 +2024,2948 @@ fn draw_keybinds_window(ui: &imgui::Ui, state: &mut State) {
                 0.0,
             ) {
                 ui.table_next_column();
-                ui.text_colored([0.7, 0.7, 0.6, 1.0], "Key");
+                ui.text_colored([0.7, 0.7, 0.6, 1.0], tr("keybinds.key"));
                 ui.table_next_column();
-                ui.text_colored([0.7, 0.7, 0.6, 1.0], "Action");
+                ui.text_colored([0.7, 0.7, 0.6, 1.0], tr("keybinds.action"));
 
                 ui.table_next_column();
-                ui.text("Ctrl + O | Drag & Drop");
+                ui.text(format!("Ctrl + O | {}", tr("keybinds.drag_drop")));
                 ui.table_next_column();
-                ui.text("Import Model(s)");
+                ui.text(tr("keybinds.import"));
 
                 ui.table_next_column();
                 ui.text("Ctrl + Q");
                 ui.table_next_column();
-                ui.text("Quit");
+                ui.text(tr("keybinds.quit"));
+
+                ui.table_next_column();
+                ui.text(tr("keybinds.lmb"));
+                ui.table_next_column();
+                ui.text(tr("keybinds.rotate"));
+
+                ui.table_next_column();
+                ui.text(tr("keybinds.scroll"));
+                ui.table_next_column();
+                ui.text(tr("keybinds.zoom"));
+
+                ui.table_next_column();
+                ui.text(tr("keybinds.left_shift"));
+                ui.table_next_column();
+                ui.text(tr("keybinds.pan"));
+
+                ui.table_next_column();
+                ui.text(tr("keybinds.left_ctrl"));
+                ui.table_next_column();
+                ui.text(tr("keybinds.camera_speed"));
+
+                ui.table_next_column();
+                ui.text("Ctrl + Z");
+                ui.table_next_column();
+                ui.text(tr("keybinds.undo"));
+
+                ui.table_next_column();
+                ui.text("Ctrl + Y");
+                ui.table_next_column();
+                ui.text(tr("keybinds.redo"));
+
+                ui.table_next_column();
+                ui.text("Esc");
+                ui.table_next_column();
+                ui.text(tr("keybinds.cancel_pick"));
 
                 ui.table_next_column();
-                ui.text("Left Mouse Button");
+                ui.text(tr("keybinds.arrows"));
                 ui.table_next_column();
-                ui.text("Rotate object");
+                ui.text(tr("keybinds.navigate_objects"));
 
                 ui.table_next_column();
-                ui.text("Scroll");
+                ui.text("Delete");
                 ui.table_next_column();
-                ui.text("Zoom camera");
+                ui.text(tr("keybinds.remove_model"));
 
                 ui.table_next_column();
-                ui.text("Left Shift");
+                ui.text("F2");
                 ui.table_next_column();
-                ui.text("Pan camera");
+                ui.text(tr("keybinds.rename_model"));
 
                 ui.table_next_column();
-                ui.text("Left Ctrl");
+                ui.text(tr("keybinds.space"));
                 ui.table_next_column();
-                ui.text("Increase camera movement speed");
+                ui.text(tr("keybinds.toggle_visibility"));
+
+                ui.table_next_column();
+                ui.text(tr("keybinds.multi_select"));
+                ui.table_next_column();
+                ui.text(tr("keybinds.multi_select_action"));
+
+                ui.table_next_column();
+                ui.text("Page Up / Page Down");
+                ui.table_next_column();
+                ui.text(tr("keybinds.browse_folder"));
             }
         });
 }
 
-fn draw_transformations(ui: &imgui::Ui, mesh: &mut mesh::Mesh) {
+/// Drags for a transform, the model's or one of a mesh's within its model
+fn draw_transformations(
+    ui: &imgui::Ui,
+    position: &mut glm::Vec3,
+    rotation: &mut glm::Vec3,
+    scale: &mut glm::Vec3,
+) {
+    ui.text(tr("objects.position"));
     imgui::Drag::new("###XPos")
         .range(f32::NEG_INFINITY, f32::INFINITY)
         .speed(0.1)
         .display_format("X: %.3f")
-        .build(ui, &mut mesh.position.x);
+        .build(ui, &mut position.x);
     imgui::Drag::new("###YPos")
         .range(f32::NEG_INFINITY, f32::INFINITY)
         .speed(0.1)
         .display_format("Y: %.3f")
-        .build(ui, &mut mesh.position.y);
+        .build(ui, &mut position.y);
     imgui::Drag::new("###ZPos")
         .range(f32::NEG_INFINITY, f32::INFINITY)
         .speed(0.1)
         .display_format("Z: %.3f")
-        .build(ui, &mut mesh.position.z);
+        .build(ui, &mut position.z);
+
+    ui.text(tr("objects.rotation"));
+    imgui::Drag::new("###XRot")
+        .range(f32::NEG_INFINITY, f32::INFINITY)
+        .speed(0.5)
+        .display_format("X: %.1f°")
+        .build(ui, &mut rotation.x);
+    imgui::Drag::new("###YRot")
+        .range(f32::NEG_INFINITY, f32::INFINITY)
+        .speed(0.5)
+        .display_format("Y: %.1f°")
+        .build(ui, &mut rotation.y);
+    imgui::Drag::new("###ZRot")
+        .range(f32::NEG_INFINITY, f32::INFINITY)
+        .speed(0.5)
+        .display_format("Z: %.1f°")
+        .build(ui, &mut rotation.z);
+
+    ui.text(tr("objects.scale"));
+    imgui::Drag::new("###XScale")
+        .range(0.001, f32::INFINITY)
+        .speed(0.01)
+        .display_format("X: %.3f")
+        .build(ui, &mut scale.x);
+    imgui::Drag::new("###YScale")
+        .range(0.001, f32::INFINITY)
+        .speed(0.01)
+        .display_format("Y: %.3f")
+        .build(ui, &mut scale.y);
+    imgui::Drag::new("###ZScale")
+        .range(0.001, f32::INFINITY)
+        .speed(0.01)
+        .display_format("Z: %.3f")
+        .build(ui, &mut scale.z);
 }
 
-fn draw_mesh_hierarchy(ui: &imgui::Ui, mesh: &mut mesh::Mesh, i: usize) {
-    ui.tree_node_config(format!("{}###{}", mesh.name.as_str(), i))
-        .build(|| {
-            ui.text(format!("Vertices: {}", mesh.vertices.len()));
-            ui.text(format!("Triangles: {}", mesh.indices.len() / 3));
-            ui.tree_node_config(mesh.material.name.as_str()).build(|| {
-                ui.text(format!("{}", mesh.material));
+/// Area and volume of a mesh or of a whole model, or that they're still
+/// being computed
+fn draw_mass_properties(
+    ui: &imgui::Ui,
+    properties: Option<&mass_properties::MeshProperties>,
+    units: Option<importer::stl::Units>,
+) {
+    let Some(properties) = properties else {
+        ui.text_disabled(tr("objects.computing_mass_properties"));
+        return;
+    };
+    ui.text(format!(
+        "{}: {}",
+        tr("objects.area"),
+        properties.area_label(units)
+    ));
+    ui.text(format!(
+        "{}: {}",
+        tr("objects.volume"),
+        properties.volume_label(units)
+    ));
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("objects.volume_tooltip"));
+    }
+}
+
+fn draw_mesh_hierarchy(
+    ui: &imgui::Ui,
+    mesh: &mut mesh::Mesh,
+    i: usize,
+    selected: bool,
+    properties: Option<&mass_properties::MeshProperties>,
+    units: Option<importer::stl::Units>,
+) -> bool {
+    let node = ui
+        .tree_node_config(format!("{}###{}", mesh.name.as_str(), i))
+        .selected(selected)
+        .push();
+    let clicked = ui.is_item_clicked();
+    let popup = format!("###MeshContext{}", i);
+    if ui.is_item_clicked_with_button(imgui::MouseButton::Right) {
+        ui.open_popup(&popup);
+    }
+    ui.popup(&popup, || draw_mesh_overrides(ui, &mut mesh.overrides));
+
+    if node.is_some() {
+        ui.text(format!(
+            "{}: {}",
+            tr("objects.vertices"),
+            mesh.vertices.len()
+        ));
+        ui.text(format!(
+            "{}: {}",
+            tr("objects.triangles"),
+            mesh.indices.len() / 3
+        ));
+        draw_mass_properties(ui, properties, units);
+        for (j, range) in mesh.materials.iter().enumerate() {
+            ui.tree_node_config(format!("{}###Material{}", range.material.name, j))
+                .build(|| {
+                    if mesh.materials.len() > 1 {
+                        ui.text(format!("{}: {}", tr("objects.triangles"), range.count / 3));
+                    }
+                    ui.text(format!("{}", range.material));
+                });
+        }
+        ui.tree_node_config(format!(
+            "{}###Transformations",
+            tr("objects.transformations")
+        ))
+        .build(|| {
+            draw_transformations(ui, &mut mesh.position, &mut mesh.rotation, &mut mesh.scale);
+        });
+    }
+
+    clicked
+}
+
+/// Ways to draw a single mesh differently from the global view options
+fn draw_mesh_overrides(ui: &imgui::Ui, overrides: &mut mesh::RenderOverrides) {
+    if ui
+        .menu_item_config(tr("objects.mesh_wireframe"))
+        .selected(overrides.wireframe)
+        .build()
+    {
+        overrides.wireframe = !overrides.wireframe;
+    }
+    if ui
+        .menu_item_config(tr("objects.mesh_unlit"))
+        .selected(overrides.unlit)
+        .build()
+    {
+        overrides.unlit = !overrides.unlit;
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("objects.mesh_unlit_tooltip"));
+    }
+    // a checkbox keeps the menu open for picking the color
+    let mut tinted = overrides.tint.is_some();
+    if ui.checkbox(tr("objects.mesh_tint"), &mut tinted) {
+        overrides.tint = tinted.then(|| glm::vec3(1.0, 0.5, 0.2));
+    }
+    if let Some(tint) = &mut overrides.tint {
+        let mut color = [tint.x, tint.y, tint.z];
+        if ui.color_edit3("###MeshTint", &mut color) {
+            *tint = glm::vec3(color[0], color[1], color[2]);
+        }
+    }
+    ui.separator();
+    if ui
+        .menu_item_config(tr("objects.mesh_reset_overrides"))
+        .enabled(*overrides != mesh::RenderOverrides::default())
+        .build()
+    {
+        *overrides = mesh::RenderOverrides::default();
+    }
+}
+
+fn draw_model_actions(ui: &imgui::Ui, state: &mut State, idx: usize) {
+    let is_active = state.active_model() == Some(state.objects[idx].id);
+    let aspect = state.viewport_aspect();
+    let model = &mut state.objects[idx];
+
+    if ui.small_button(tr("objects.center_origin")) {
+        state.history.record(model);
+        model.center_at_origin();
+    }
+    ui.same_line();
+    if ui.small_button(tr("objects.drop_to_grid")) {
+        state.history.record(model);
+        model.drop_to_grid();
+    }
+
+    // picking only works on the active model
+    ui.disabled(!is_active, || {
+        if ui.small_button(tr("objects.pick_pivot")) {
+            state.picking_pivot = true;
+            state.annotating = false;
+        }
+    });
+    if ui.is_item_hovered_with_flags(imgui::ItemHoveredFlags::ALLOW_WHEN_DISABLED) && !is_active {
+        ui.tooltip_text(tr("objects.pick_pivot_inactive"));
+    }
+    ui.same_line();
+    ui.disabled(model.custom_pivot.is_none(), || {
+        if ui.small_button(tr("objects.reset_pivot")) {
+            state.history.record(model);
+            model.set_pivot(None);
+        }
+    });
+
+    ui.text(format!(
+        "{}: {:.4}",
+        tr("objects.normalization_factor"),
+        model.normalization_factor
+    ));
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("objects.normalization_factor_tooltip"));
+    }
+
+    let mut scale_mode = model.scale_mode;
+    if ui.radio_button_bool(
+        tr("objects.scale_normalized"),
+        scale_mode == model::ScaleMode::Normalized,
+    ) {
+        scale_mode = model::ScaleMode::Normalized;
+    }
+    ui.same_line();
+    if ui.radio_button_bool(
+        tr("objects.scale_original"),
+        scale_mode == model::ScaleMode::Original,
+    ) {
+        scale_mode = model::ScaleMode::Original;
+    }
+    ui.same_line();
+    if ui.radio_button_bool(
+        tr("objects.scale_custom"),
+        matches!(scale_mode, model::ScaleMode::Custom(_)),
+    ) && !matches!(scale_mode, model::ScaleMode::Custom(_))
+    {
+        scale_mode = model::ScaleMode::Custom(model.scaling_factor);
+    }
+    if let model::ScaleMode::Custom(factor) = &mut scale_mode {
+        let mut value = *factor;
+        if ui
+            .input_float(tr("objects.scale_factor"), &mut value)
+            .enter_returns_true(true)
+            .build()
+            && value > 0.0
+        {
+            *factor = value;
+        }
+    }
+
+    if scale_mode != model.scale_mode {
+        model.set_scale_mode(scale_mode);
+        if is_active {
+            state.camera.fit_to_model(model, aspect);
+        }
+    }
+
+    ui.tree_node_config(format!(
+        "{}###ModelTransformations",
+        tr("objects.transformations")
+    ))
+    .build(|| {
+        draw_transformations(
+            ui,
+            &mut model.position,
+            &mut model.rotation,
+            &mut model.scale,
+        );
+    });
+
+    if ui.small_button(tr("objects.reset_position")) {
+        state.history.record(model);
+        model.reset_position();
+    }
+    ui.same_line();
+    if ui.small_button(tr("objects.reset_rotation")) {
+        state.history.record(model);
+        model.reset_rotation();
+    }
+    ui.same_line();
+    if ui.small_button(tr("objects.reset_scale")) {
+        state.history.record(model);
+        model.reset_scale();
+    }
+
+    if ui.small_button(tr("objects.apply_transform")) {
+        // the recorded transforms don't make sense for the baked geometry anymore
+        state.history.forget(model.id);
+        model.apply_transform();
+        info!("Applied transform to {}", model.name);
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("objects.apply_transform_tooltip"));
+    }
+
+    if ui.small_button(tr("objects.copy_info")) {
+        ui.set_clipboard_text(report::Stats::new(model).to_text());
+    }
+    ui.same_line();
+    if ui.small_button(tr("objects.copy_markdown")) {
+        ui.set_clipboard_text(report::Stats::new(model).to_markdown());
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("objects.copy_markdown_tooltip"));
+    }
+    if state.show_ghosts {
+        if ui.small_button(tr("objects.pin_ghost")) {
+            model.ghost = None;
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr("objects.pin_ghost_tooltip"));
+        }
+    }
+    if model.skipped_textures.is_some() {
+        if ui.small_button(tr("objects.load_textures")) {
+            model.load_skipped_textures(&state.settings);
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr("objects.load_textures_tooltip"));
+        }
+    }
+
+    draw_topology(ui, model);
+    draw_repair(ui, state, idx);
+    draw_simplify(ui, state, idx);
+    draw_bounds(ui, &mut state.objects[idx]);
+    draw_balance(ui, &mut state.objects[idx]);
+    draw_units(ui, &mut state.objects[idx]);
+    draw_face_colors(ui, &mut state.objects[idx]);
+    draw_file_info(ui, &mut state.objects[idx]);
+    draw_model_notes(ui, state, idx);
+}
+
+fn draw_file_info(ui: &imgui::Ui, model: &mut model::Model) {
+    ui.disabled(model.source_path.is_none(), || {
+        if ui.small_button(tr("file_info.read")) {
+            if let Some(path) = &model.source_path {
+                match file_info::FileInfo::read(path) {
+                    Ok(info) => model.file_info = Some(info),
+                    Err(e) => error!("Failed to read {:?}: {}", path, e),
+                }
+            }
+        }
+    });
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("file_info.read_tooltip"));
+    }
+
+    let Some(info) = &model.file_info else {
+        return;
+    };
+    ui.text(format!(
+        "{}: {:.2}MB ({} bytes)",
+        tr("file_info.size"),
+        info.size as f64 / (1024.0 * 1024.0),
+        info.size
+    ));
+    ui.text(format!(
+        "{}: {}",
+        tr("file_info.modified"),
+        info.modified_label()
+    ));
+    ui.text(format!("SHA-256: {}", info.sha256));
+    ui.same_line();
+    if ui.small_button(tr("file_info.copy_checksum")) {
+        ui.set_clipboard_text(&info.sha256);
+    }
+    if !info.metadata.is_empty() {
+        ui.text(format!("{}:", tr("file_info.metadata")));
+        for line in &info.metadata {
+            ui.text_disabled(line);
+        }
+    }
+}
+
+/// The notes and tags kept for the file the model was opened from
+fn draw_model_notes(ui: &imgui::Ui, state: &mut State, idx: usize) {
+    let Some(path) = &state.objects[idx].source_path else {
+        return;
+    };
+
+    if let Some(note) = state.notes.get(path) {
+        if !note.tags.is_empty() {
+            ui.text(format!("{}: {}", tr("notes.tags"), note.tags_label()));
+        }
+        if !note.text.is_empty() {
+            ui.text_wrapped(&note.text);
+        }
+    }
+    if ui.small_button(tr("notes.edit")) {
+        state.note_edit = Some(notes::NoteEdit::new(path, &state.notes));
+    }
+    item_help(ui, "notes.edit");
+}
+
+/// How the STL face colors were read, with a choice when it's unclear
+fn draw_face_colors(ui: &imgui::Ui, model: &mut model::Model) {
+    let Some(colors) = &mut model.face_colors else {
+        return;
+    };
+
+    if !colors.ambiguous {
+        ui.text(format!("{}: {}", tr("stl_colors"), colors.format.name()));
+        return;
+    }
+
+    let formats = importer::stl::ColorFormat::ALL;
+    let mut format_idx = formats
+        .iter()
+        .position(|f| *f == colors.format)
+        .unwrap_or(0);
+    if ui.combo(tr("stl_colors"), &mut format_idx, &formats, |f| {
+        f.name().into()
+    }) {
+        colors.set_format(formats[format_idx], &mut model.meshes[0]);
+        info!(
+            "Showing the face colors of {} as {}",
+            model.name,
+            formats[format_idx].name()
+        );
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("stl_colors.ambiguous_tooltip"));
+    }
+}
+
+/// The units the model is in and its size in them
+fn draw_units(ui: &imgui::Ui, model: &mut model::Model) {
+    let options: Vec<Option<importer::stl::Units>> = std::iter::once(None)
+        .chain(importer::stl::Units::ALL.map(Some))
+        .collect();
+    let mut units_idx = options.iter().position(|u| *u == model.units).unwrap_or(0);
+    if ui.combo(tr("objects.units"), &mut units_idx, &options, |u| {
+        u.map_or(tr("units.unknown"), |u| u.name()).into()
+    }) {
+        model.units = options[units_idx];
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("objects.units_tooltip"));
+    }
+
+    ui.text(format!("{}: {}", tr("objects.size"), model.size_label()));
+    // the size in the other units too, so inch models can be checked in mm and back
+    let size = model.aabb.max - model.aabb.min;
+    let converted = match model.units {
+        Some(importer::stl::Units::Millimeters) => Some((size / 25.4, "in")),
+        Some(importer::stl::Units::Inches) => Some((size * 25.4, "mm")),
+        None => None,
+    };
+    if let Some((size, abbreviation)) = converted {
+        ui.text_disabled(format!(
+            "= {:.2} x {:.2} x {:.2} {}",
+            size.x, size.y, size.z, abbreviation
+        ));
+    }
+}
+
+/// The convex hull and oriented box of a model and their sizes
+fn draw_bounds(ui: &imgui::Ui, model: &mut model::Model) {
+    ui.disabled(model.evicted || model.computing_bounds(), || {
+        if ui.small_button(tr("bounds.compute")) {
+            model.request_bounds();
+        }
+    });
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("bounds.compute_tooltip"));
+    }
+    if model.computing_bounds() {
+        ui.text_disabled(tr("bounds.computing"));
+    }
+
+    let Some(bounds) = &model.bounds else {
+        return;
+    };
+    ui.same_line();
+    if ui.small_button(tr("bounds.clear")) {
+        model.bounds = None;
+        return;
+    }
+
+    let transform = model.transform();
+    let color = |c: glm::Vec3| [c.x, c.y, c.z, 1.0];
+    ui.text_colored(
+        color(hull::HULL_COLOR),
+        format!(
+            "{}: {} {}, {} {}",
+            tr("bounds.hull"),
+            bounds.points.len(),
+            tr("bounds.points"),
+            bounds.triangles.len(),
+            tr("bounds.triangles")
+        ),
+    );
+    ui.text(format!(
+        "{}: {:.4}",
+        tr("bounds.hull_volume"),
+        bounds.volume(&transform)
+    ));
+    let [x, y, z] = bounds.obb.dimensions(&transform);
+    ui.text_colored(
+        color(hull::OBB_COLOR),
+        format!("{}: {:.4} x {:.4} x {:.4}", tr("bounds.obb"), x, y, z),
+    );
+    ui.text(format!("{}: {:.4}", tr("bounds.obb_volume"), x * y * z));
+}
+
+/// The center of mass and principal axes, for balancing props and picking a
+/// print orientation
+fn draw_balance(ui: &imgui::Ui, model: &mut model::Model) {
+    ui.disabled(model.evicted, || {
+        if ui.small_button(tr("balance.compute")) {
+            model.balance = mass_properties::Balance::new(model);
+            if model.balance.is_none() {
+                warn!("{} has no triangles to balance", model.name);
+            }
+        }
+    });
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("balance.compute_tooltip"));
+    }
+
+    let Some(balance) = &model.balance else {
+        return;
+    };
+    ui.same_line();
+    if ui.small_button(tr("balance.clear")) {
+        model.balance = None;
+        return;
+    }
+
+    let c = balance.center;
+    ui.text(format!(
+        "{}: ({:.4}, {:.4}, {:.4})",
+        tr("balance.center"),
+        c.x,
+        c.y,
+        c.z
+    ));
+    if balance.surface_only {
+        ui.same_line();
+        ui.text_disabled("(?)");
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr("balance.surface_only"));
+        }
+    }
+    let colors = [debug_draw::RED, debug_draw::GREEN, debug_draw::BLUE];
+    for ((axis, moment), color) in balance.axes.iter().zip(balance.moments).zip(colors) {
+        ui.text_colored(
+            [color.x, color.y, color.z, 1.0],
+            format!(
+                "{}: ({:.3}, {:.3}, {:.3}) {}: {:.4}",
+                tr("balance.axis"),
+                axis.x,
+                axis.y,
+                axis.z,
+                tr("balance.moment"),
+                moment
+            ),
+        );
+    }
+}
+
+/// Target of the simplification and the buttons to preview, keep and export it
+fn draw_simplify(ui: &imgui::Ui, state: &mut State, idx: usize) {
+    // the preview can't be kept once its model is gone or was unloaded
+    if let Some(preview) = &state.simplify.preview {
+        if !state
+            .objects
+            .iter()
+            .any(|m| m.id == preview.model_id && !m.evicted)
+        {
+            state.simplify.preview = None;
+        }
+    }
+
+    let model = &mut state.objects[idx];
+    let simplify = &mut state.simplify;
+    let original_triangles = simplify.original_triangles(model.id);
+
+    ui.text_disabled(tr("simplify.title"));
+    if ui.radio_button_bool(tr("simplify.by_percent"), !simplify.by_count) {
+        simplify.by_count = false;
+    }
+    ui.same_line();
+    if ui.radio_button_bool(tr("simplify.by_count"), simplify.by_count) {
+        simplify.by_count = true;
+    }
+
+    ui.set_next_item_width(150.0);
+    if simplify.by_count {
+        imgui::Drag::new(tr("simplify.triangles"))
+            .range(1, u32::MAX)
+            .speed(10.0)
+            .build(ui, &mut simplify.triangles);
+    } else {
+        imgui::Drag::new(tr("simplify.percent"))
+            .range(1.0, 100.0)
+            .speed(0.5)
+            .display_format("%.0f%%")
+            .build(ui, &mut simplify.percent);
+    }
+
+    // only one model is previewed at a time
+    let other_previewed = simplify.preview.is_some() && original_triangles.is_none();
+    ui.disabled(model.evicted || other_previewed, || {
+        if ui.small_button(tr("simplify.preview")) {
+            simplify.preview(model);
+        }
+    });
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("simplify.preview_tooltip"));
+    }
+
+    let Some(original_triangles) = original_triangles else {
+        return;
+    };
+    ui.same_line();
+    if ui.small_button(tr("simplify.apply")) {
+        simplify.apply(&mut state.history);
+    }
+    ui.same_line();
+    if ui.small_button(tr("simplify.cancel")) {
+        simplify.cancel(model);
+        return;
+    }
+    ui.same_line();
+    if ui.small_button(tr("simplify.export")) {
+        export_simplified(model);
+    }
+
+    let triangles: usize = model.meshes.iter().map(|m| m.indices.len() / 3).sum();
+    ui.text(format!(
+        "{}: {} -> {}",
+        tr("simplify.triangles"),
+        original_triangles,
+        triangles
+    ));
+}
+
+fn export_active_model(state: &State) {
+    let Some(model) = state
+        .active_model()
+        .and_then(|id| state.objects.iter().find(|m| m.id == id))
+    else {
+        return;
+    };
+    let Some(path) = rfd::FileDialog::new()
+        .set_title(tr("dialog.export.title"))
+        .set_directory("./")
+        .add_filter("Wavefront OBJ (.obj)", &["obj"])
+        .set_file_name(&format!("{}.obj", model.name))
+        .save_file()
+    else {
+        return;
+    };
+
+    match exporter::export_obj(&[model], &path) {
+        Ok(()) => info!("Exported {} to {:?}", model.name, path),
+        Err(e) => error!("Failed to export to {:?}: {}", path, e),
+    }
+}
+
+fn export_simplified(model: &model::Model) {
+    let Some(path) = rfd::FileDialog::new()
+        .set_title(tr("dialog.export.title"))
+        .set_directory("./")
+        .add_filter("Wavefront OBJ (.obj)", &["obj"])
+        .set_file_name(&format!("{}_simplified.obj", model.name))
+        .save_file()
+    else {
+        return;
+    };
+
+    match exporter::export_obj(&[model], &path) {
+        Ok(()) => info!("Exported the simplified {} to {:?}", model.name, path),
+        Err(e) => error!("Failed to export to {:?}: {}", path, e),
+    }
+}
+
+/// Buttons for the repairs and the before and after counts of the last one
+fn draw_repair(ui: &imgui::Ui, state: &mut State, idx: usize) {
+    let model = &mut state.objects[idx];
+
+    ui.text_disabled(tr("repair.title"));
+    for (i, repair) in repair::Repair::ALL.into_iter().enumerate() {
+        if i % 2 == 1 {
+            ui.same_line();
+        }
+        ui.disabled(model.evicted, || {
+            if ui.small_button(repair.name()) {
+                state.history.record_geometry(model);
+                state.last_repair = Some((model.id, repair::run(model, repair)));
+            }
+        });
+        if ui.is_item_hovered() {
+            ui.tooltip_text(repair.tooltip());
+        }
+    }
+
+    let Some((_, report)) = state.last_repair.filter(|(id, _)| *id == model.id) else {
+        return;
+    };
+    ui.text(format!(
+        "{}: {} {}",
+        report.repair.name(),
+        report.changed,
+        tr("repair.changed")
+    ));
+    let (before, after) = (report.before, report.after);
+    for (label, before, after) in [
+        (tr("repair.triangles"), before.triangles, after.triangles),
+        (
+            tr("topology.boundary_edges"),
+            before.boundary_edges,
+            after.boundary_edges,
+        ),
+        (
+            tr("topology.non_manifold_edges"),
+            before.non_manifold_edges,
+            after.non_manifold_edges,
+        ),
+        (
+            tr("topology.flipped_faces"),
+            before.flipped_faces,
+            after.flipped_faces,
+        ),
+        (
+            tr("topology.degenerate_faces"),
+            before.degenerate_faces,
+            after.degenerate_faces,
+        ),
+    ] {
+        ui.text(format!("{}: {} -> {}", label, before, after));
+    }
+}
+
+/// The topology analysis of a model and its results
+fn draw_topology(ui: &imgui::Ui, model: &mut model::Model) {
+    ui.disabled(model.evicted, || {
+        if ui.small_button(tr("topology.analyze")) {
+            let analysis = topology::Analysis::new(model);
+            info!(
+                "Analyzed {}: {} boundary edges, {} non-manifold edges, {} flipped faces",
+                model.name,
+                analysis.boundary_edges.len(),
+                analysis.non_manifold_edges.len(),
+                analysis.flipped_faces.len()
+            );
+            model.topology = Some(analysis);
+        }
+    });
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("topology.analyze_tooltip"));
+    }
+
+    let Some(analysis) = &model.topology else {
+        return;
+    };
+    ui.same_line();
+    if ui.small_button(tr("topology.clear")) {
+        model.topology = None;
+        return;
+    }
+
+    if analysis.is_clean() {
+        ui.text_colored([0.3, 0.9, 0.3, 1.0], tr("topology.clean"));
+        return;
+    }
+    let color = |c: glm::Vec3| [c.x, c.y, c.z, 1.0];
+    ui.text_colored(
+        color(topology::BOUNDARY_COLOR),
+        format!(
+            "{}: {}",
+            tr("topology.boundary_edges"),
+            analysis.boundary_edges.len()
+        ),
+    );
+    ui.text_colored(
+        color(topology::NON_MANIFOLD_COLOR),
+        format!(
+            "{}: {}",
+            tr("topology.non_manifold_edges"),
+            analysis.non_manifold_edges.len()
+        ),
+    );
+    ui.text_colored(
+        color(topology::FLIPPED_COLOR),
+        format!(
+            "{}: {}",
+            tr("topology.flipped_faces"),
+            analysis.flipped_faces.len()
+        ),
+    );
+    ui.text(format!(
+        "{}: {}",
+        tr("topology.degenerate_faces"),
+        analysis.degenerate_faces
+    ));
+}
+
+/// Draws a group's row and, while it's open, the rows of its models
+fn draw_group(
+    ui: &imgui::Ui,
+    state: &mut State,
+    group: u32,
+    members: std::ops::Range<usize>,
+    nav: &mut Vec<(u32, Option<usize>)>,
+    dropped: &mut Option<(u32, ObjectDrop)>,
+    removed: &mut Vec<u32>,
+) {
+    let ids: Vec<u32> = state.objects[members.clone()]
+        .iter()
+        .map(|m| m.id)
+        .collect();
+
+    ui.table_next_column();
+    let mut selected = ids.iter().all(|id| state.is_selected(*id));
+    if ui.checkbox(format!("###GroupSelect{}", group), &mut selected) {
+        state.selected_mesh = None;
+        state.selection.retain(|id| !ids.contains(id));
+        if selected {
+            state.selection.extend(&ids);
+        }
+    }
+
+    ui.table_next_column();
+    let name = state
+        .groups
+        .iter()
+        .find(|g| g.id == group)
+        .map_or("", |g| g.name.as_str());
+    let node = ui
+        .tree_node_config(format!("{} ({})###Group{}", name, ids.len(), group))
+        .push();
+    if let Some(target) = ui.drag_drop_target() {
+        if let Some(Ok(payload)) =
+            target.accept_payload::<u32, _>(MODEL_PAYLOAD, imgui::DragDropFlags::empty())
+        {
+            *dropped = Some((payload.data, ObjectDrop::IntoGroup(group)));
+        }
+    }
+    let popup = format!("###GroupContext{}", group);
+    if ui.is_item_clicked_with_button(imgui::MouseButton::Right) {
+        ui.open_popup(&popup);
+    }
+    ui.popup(&popup, || {
+        if let Some(group) = state.groups.iter_mut().find(|g| g.id == group) {
+            ui.input_text(tr("objects.group_name"), &mut group.name)
+                .build();
+        }
+        draw_move_drag(ui, state, tr("objects.move_group"), &ids);
+        if ui.menu_item(tr("objects.toggle_visibility")) {
+            let visible = state.objects[members.clone()].iter().any(|m| !m.visible);
+            for model in &mut state.objects[members.clone()] {
+                model.visible = visible;
+            }
+        }
+        if ui.menu_item(tr("objects.ungroup")) {
+            state.ungroup(group);
+        }
+    });
+    ui.table_next_column();
+
+    if let Some(_node) = node {
+        for id in ids {
+            let Some(idx) = state.objects.iter().position(|m| m.id == id) else {
+                continue;
+            };
+            if draw_object_hierarchy(ui, state, idx, nav, dropped) {
+                removed.push(id);
+            }
+        }
+    }
+}
+
+fn draw_object_hierarchy(
+    ui: &imgui::Ui,
+    state: &mut State,
+    idx: usize,
+    nav: &mut Vec<(u32, Option<usize>)>,
+    dropped: &mut Option<(u32, ObjectDrop)>,
+) -> bool {
+    let id = state.objects[idx].id;
+    let residency = if !state.objects[idx].evicted {
+        ""
+    } else if state.import_jobs.iter().any(|j| j.reload == Some(id)) {
+        tr("objects.loading")
+    } else {
+        tr("objects.unloaded")
+    };
+
+    ui.table_next_column();
+    if ui.checkbox(format!("###{}", id), &mut state.is_selected(id)) {
+        if ui.io().key_ctrl {
+            state.toggle_selection(id);
+        } else if ui.io().key_shift {
+            state.select_range(id);
+        } else {
+            state.select_model(id);
+        }
+    }
+
+    ui.table_next_column();
+    let mut node = ui
+        .tree_node_config(format!(
+            "{} ({:.1}MB){}{}###{}",
+            state.objects[idx].name.as_str(),
+            state.objects[idx].mem_usage as f32 / (1024.0 * 1024.0),
+            if state.objects[idx].visible {
+                ""
+            } else {
+                tr("objects.hidden")
+            },
+            residency,
+            id
+        ))
+        .selected(state.is_selected(id) && state.selected_mesh.is_none());
+    if let Some((request_id, open)) = state.objects_open_request {
+        if request_id == id {
+            node = node.opened(open, imgui::Condition::Always);
+            state.objects_open_request = None;
+        }
+    }
+    nav.push((id, None));
+
+    let node = node.push();
+    if let Some(_tooltip) = ui.drag_drop_source_config(MODEL_PAYLOAD).begin_payload(id) {
+        ui.text(&state.objects[idx].name);
+    }
+    if let Some(target) = ui.drag_drop_target() {
+        if let Some(Ok(payload)) =
+            target.accept_payload::<u32, _>(MODEL_PAYLOAD, imgui::DragDropFlags::empty())
+        {
+            *dropped = Some((payload.data, ObjectDrop::Before(id)));
+        }
+    }
+    if ui.is_item_clicked_with_button(imgui::MouseButton::Right) {
+        ui.open_popup("###ModelContext");
+    }
+    ui.popup("###ModelContext", || {
+        let model = &state.objects[idx];
+        let reloading = state.import_jobs.iter().any(|j| j.reload == Some(id));
+        if ui
+            .menu_item_config(tr("objects.reload"))
+            .enabled(model.source_path.is_some() && !reloading)
+            .build()
+        {
+            if let Some(path) = &model.source_path {
+                info!("Reloading model {}", model.name);
+                state.import_jobs.push(importer::ImportJob::spawn_reload(
+                    path.clone(),
+                    id,
+                    model.heightmap,
+                    model.skipped_textures.is_none(),
+                    model.parts.clone(),
+                    model.import_options,
+                ));
+            }
+        }
+        if let Some(path) = &model.source_path {
+            draw_path_menu_items(ui, path);
+        }
+        if ui
+            .menu_item_config(tr("objects.remove_from_group"))
+            .enabled(model.group.is_some())
+            .build()
+        {
+            state.remove_from_group(id);
+        }
+        if ui.menu_item(tr("objects.open_in_window")) {
+            state.detach_requests.push(id);
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr("objects.open_in_window_tooltip"));
+        }
+    });
+
+    if let Some(_node) = node {
+        draw_model_actions(ui, state, idx);
+
+        let model = &mut state.objects[idx];
+        model.request_mass_properties();
+        let properties = model.mass_properties.clone();
+        let total = properties
+            .as_deref()
+            .map(mass_properties::MeshProperties::total);
+        draw_mass_properties(ui, total.as_ref(), model.units);
+
+        for j in 0..state.objects[idx].meshes.len() {
+            nav.push((id, Some(j)));
+            let selected = state.selected_mesh == Some((id, j));
+            let model = &mut state.objects[idx];
+            if draw_mesh_hierarchy(
+                ui,
+                &mut model.meshes[j],
+                j,
+                selected,
+                properties.as_ref().and_then(|p| p.get(j)),
+                model.units,
+            ) {
+                state.select_mesh(id, j);
+            }
+        }
+    }
+
+    ui.table_next_column();
+    if ui.small_button(format!("X###{}-{}", state.objects[idx].name.as_str(), idx)) {
+        return true;
+    }
+
+    false
+}
+
+fn export_selection(state: &State) {
+    let Some(path) = rfd::FileDialog::new()
+        .set_title(tr("dialog.export.title"))
+        .set_directory("./")
+        .add_filter("Wavefront OBJ (.obj)", &["obj"])
+        .set_file_name("export.obj")
+        .save_file()
+    else {
+        return;
+    };
+
+    let models: Vec<&model::Model> = state.selected_models().collect();
+    match exporter::export_obj(&models, &path) {
+        Ok(()) => info!("Exported {} model(s) to {:?}", models.len(), path),
+        Err(e) => error!("Failed to export to {:?}: {}", path, e),
+    }
+}
+
+fn draw_selection_actions(ui: &imgui::Ui, state: &mut State) {
+    ui.text(format!(
+        "{}: {}",
+        tr("objects.selected"),
+        state.selection.len()
+    ));
+    ui.same_line();
+    if ui.small_button(tr("objects.toggle_visibility")) {
+        state.toggle_selected_visibility();
+    }
+    ui.same_line();
+    if ui.small_button(tr("objects.remove_selected")) {
+        state.remove_selected();
+    }
+    ui.same_line();
+    if ui.small_button(tr("objects.export_selected")) {
+        export_selection(state);
+    }
+
+    ui.same_line();
+    if ui.small_button(tr("objects.group_selected")) {
+        state.group_selected();
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("objects.group_selected_tooltip"));
+    }
+
+    let selection = state.selection.clone();
+    draw_move_drag(ui, state, tr("objects.move_selected"), &selection);
+
+    if ui.small_button(tr("objects.reset_rotation")) {
+        state.history.begin_batch();
+        for model in state.objects.iter_mut() {
+            if state.selection.contains(&model.id) {
+                state.history.record(model);
+                model.reset_rotation();
+            }
+        }
+        state.history.end_batch();
+    }
+    ui.same_line();
+    if ui.small_button(tr("objects.arrange")) {
+        state.arrange_selected();
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("objects.arrange_tooltip"));
+    }
+}
+
+/// Moves the models by the dragged amount, the whole drag is undone at once
+fn draw_move_drag(ui: &imgui::Ui, state: &mut State, label: &str, ids: &[u32]) {
+    let mut offset = [0.0_f32; 3];
+    let moved = imgui::Drag::new(label)
+        .speed(0.1)
+        .display_format("%+.3f")
+        .build_array(ui, &mut offset);
+    if ui.is_item_activated() {
+        state.history.begin_batch();
+        for id in ids {
+            if let Some(model) = state.objects.iter().find(|m| m.id == *id) {
+                state.history.record(model);
+            }
+        }
+        state.history.end_batch();
+    }
+    if moved {
+        let offset = glm::vec3(offset[0], offset[1], offset[2]);
+        for model in state.objects.iter_mut() {
+            if ids.contains(&model.id) {
+                model.position = model.position + offset;
+            }
+        }
+    }
+}
+
+fn handle_objects_keys(ui: &imgui::Ui, state: &mut State, nav: &[(u32, Option<usize>)]) {
+    let current = nav.iter().position(|item| match state.selected_mesh {
+        Some((id, mesh)) => *item == (id, Some(mesh)),
+        None => state.active_model().map(|id| (id, None)) == Some(*item),
+    });
+
+    let step = if ui.is_key_pressed(imgui::Key::DownArrow) {
+        1
+    } else if ui.is_key_pressed(imgui::Key::UpArrow) {
+        -1
+    } else {
+        0
+    };
+    if step != 0 && !nav.is_empty() {
+        let next = match current {
+            Some(i) => (i as isize + step).clamp(0, nav.len() as isize - 1) as usize,
+            None => 0,
+        };
+        match nav[next] {
+            (id, Some(mesh)) => state.select_mesh(id, mesh),
+            (id, None) => state.select_model(id),
+        }
+    }
+
+    let Some(id) = state.active_model() else {
+        return;
+    };
+
+    if ui.is_key_pressed(imgui::Key::RightArrow) {
+        state.objects_open_request = Some((id, true));
+    }
+    if ui.is_key_pressed(imgui::Key::LeftArrow) {
+        // collapsing moves the selection back up to the model
+        state.selected_mesh = None;
+        state.objects_open_request = Some((id, false));
+    }
+    if ui.is_key_pressed(imgui::Key::Space) {
+        state.toggle_selected_visibility();
+    }
+    if ui.is_key_pressed(imgui::Key::F2) {
+        if let Some(model) = state.objects.iter().find(|m| m.id == id) {
+            state.renaming = Some((id, model.name.clone()));
+            ui.open_popup("###RenameModel");
+        }
+    }
+    if ui.is_key_pressed(imgui::Key::Delete) {
+        state.remove_selected();
+    }
+}
+
+fn draw_rename_popup(ui: &imgui::Ui, state: &mut State) {
+    ui.modal_popup_config(format!("{}###RenameModel", tr("objects.rename")))
+        .always_auto_resize(true)
+        .build(|| {
+            let Some((id, name)) = &mut state.renaming else {
+                ui.close_current_popup();
+                return;
+            };
+
+            if ui.is_window_appearing() {
+                ui.set_keyboard_focus_here();
+            }
+            let confirmed = ui
+                .input_text("###RenameInput", name)
+                .enter_returns_true(true)
+                .build();
+
+            if (confirmed || ui.button(tr("objects.rename_confirm"))) && !name.trim().is_empty() {
+                if let Some(model) = state.objects.iter_mut().find(|m| m.id == *id) {
+                    info!("Renamed {} to {}", model.name, name.trim());
+                    model.name = name.trim().to_string();
+                }
+                crash::set_loaded_models(state.objects.iter().map(|m| m.name.as_str()));
+                state.renaming = None;
+                ui.close_current_popup();
+            }
+            ui.same_line();
+            if ui.button(tr("objects.rename_cancel")) || ui.is_key_pressed(imgui::Key::Escape) {
+                state.renaming = None;
+                ui.close_current_popup();
+            }
+        });
+}
+
+fn draw_objects_window(ui: &imgui::Ui, state: &mut State) {
+    ui.window(format!("{}###Objects", tr("window.objects")))
+        .size([500.0, 200.0], imgui::Condition::FirstUseEver)
+        // arrow keys are used for navigating the tree instead
+        .flags(imgui::WindowFlags::NO_NAV_INPUTS)
+        .build(|| {
+            let mut i = 0;
+            let mut nav = Vec::new();
+
+            if !state.selection.is_empty() {
+                draw_selection_actions(ui, state);
+                ui.separator();
+            }
+
+            if let Some(..) = ui.begin_table_with_sizing(
+                "Objects Table",
+                3,
+                imgui::TableFlags::SIZING_FIXED_FIT,
+                [0.0, 0.0],
+                0.0,
+            ) {
+                ui.table_setup_column_with(imgui::TableColumnSetup {
+                    name: "",
+                    flags: imgui::TableColumnFlags::empty(),
+                    init_width_or_weight: 30.0,
+                    user_id: imgui::Id::default(),
+                });
+                ui.table_setup_column_with(imgui::TableColumnSetup {
+                    name: "",
+                    flags: imgui::TableColumnFlags::WIDTH_STRETCH,
+                    init_width_or_weight: 0.0,
+                    user_id: imgui::Id::default(),
+                });
+                ui.table_setup_column_with(imgui::TableColumnSetup {
+                    name: "",
+                    flags: imgui::TableColumnFlags::empty(),
+                    init_width_or_weight: 20.0,
+                    user_id: imgui::Id::default(),
+                });
+
+                let mut removed = vec![];
+                let mut dropped = None;
+                while i < state.objects.len() {
+                    let group = state.objects[i]
+                        .group
+                        .filter(|g| state.groups.iter().any(|group| group.id == *g));
+                    if let Some(group) = group {
+                        let count = state.objects[i..]
+                            .iter()
+                            .take_while(|m| m.group == Some(group))
+                            .count();
+                        draw_group(
+                            ui,
+                            state,
+                            group,
+                            i..i + count,
+                            &mut nav,
+                            &mut dropped,
+                            &mut removed,
+                        );
+                        i += count;
+                        continue;
+                    }
+
+                    if draw_object_hierarchy(ui, state, i, &mut nav, &mut dropped) {
+                        removed.push(state.objects[i].id);
+                    }
+                    i += 1;
+                }
+
+                for id in removed {
+                    if let Some(idx) = state.objects.iter().position(|m| m.id == id) {
+                        state.remove_model(idx);
+                    }
+                }
+                if let Some((id, to)) = dropped {
+                    state.move_model(id, to);
+                }
+            }
+
+            if ui.is_window_focused_with_flags(imgui::WindowFocusedFlags::ROOT_AND_CHILD_WINDOWS)
+                && !ui.io().want_text_input
+            {
+                handle_objects_keys(ui, state, &nav);
+            }
+
+            draw_rename_popup(ui, state);
+        });
+}
+
+/// Maps a point in world space onto the viewport image, None if it's behind the camera
+fn project_to_viewport(
+    view_projection: &glm::Mat4,
+    point: glm::Vec3,
+    image_min: [f32; 2],
+    image_size: [f32; 2],
+) -> Option<[f32; 2]> {
+    let clip = *view_projection * glm::vec4(point.x, point.y, point.z, 1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+
+    let ndc = glm::vec2(clip.x / clip.w, clip.y / clip.w);
+    Some([
+        image_min[0] + (ndc.x + 1.0) / 2.0 * image_size[0],
+        // the viewport image is flipped vertically
+        image_min[1] + (1.0 - ndc.y) / 2.0 * image_size[1],
+    ])
+}
+
+/// Draws the labels queued on the debug draw this frame over the viewport image
+fn draw_debug_labels(ui: &imgui::Ui, state: &mut State, image_min: [f32; 2], image_size: [f32; 2]) {
+    let draw_list = ui.get_window_draw_list();
+    let text_color = ui.style_color(imgui::StyleColor::Text);
+    let image_max = [image_min[0] + image_size[0], image_min[1] + image_size[1]];
+
+    draw_list.with_clip_rect_intersect(image_min, image_max, || {
+        for label in state.debug_draw.labels.drain(..) {
+            if let Some(pos) = project_to_viewport(
+                &state.view_projection,
+                label.position,
+                image_min,
+                image_size,
+            ) {
+                draw_list.add_text(pos, text_color, &label.text);
+            }
+        }
+    });
+}
+
+/// Draws the scale bar in the bottom left corner of the viewport image
+fn draw_scale_bar(ui: &imgui::Ui, state: &State, image_min: [f32; 2], image_size: [f32; 2]) {
+    let Some(bar) = ScaleBar::new(state).filter(|_| state.show_scale_bar) else {
+        return;
+    };
+    let draw_list = ui.get_window_draw_list();
+    let color = ui.style_color(imgui::StyleColor::Text);
+    let at = |p: [f32; 2]| [image_min[0] + p[0], image_min[1] + p[1]];
+
+    for [from, to] in bar.lines(image_size) {
+        draw_list
+            .add_line(at(from), at(to), color)
+            .thickness(2.0)
+            .build();
+    }
+    let label_pos = bar.label_position(image_size, ui.calc_text_size(&bar.label));
+    draw_list.add_text(at(label_pos), color, &bar.label);
+}
+
+/// The scale bar and grid labels that are shown, as marks on a capture of the
+/// viewport
+fn scale_marks(ui: &imgui::Ui, state: &State, size: [f32; 2]) -> Vec<Mark> {
+    let color = ui.style_color(imgui::StyleColor::Text);
+    let mark = |shape| Mark { shape, color };
+    let mut marks = vec![];
+
+    if let Some(bar) = ScaleBar::new(state).filter(|_| state.show_scale_bar) {
+        for [from, to] in bar.lines(size) {
+            marks.push(mark(Shape::Freehand {
+                points: vec![from, to],
+            }));
+        }
+        marks.push(mark(Shape::Text {
+            pos: bar.label_position(size, ui.calc_text_size(&bar.label)),
+            text: bar.label,
+        }));
+    }
+    if state.draw_grid && state.show_grid_labels {
+        for label in scale::grid_labels(state) {
+            if let Some(pos) =
+                project_to_viewport(&state.view_projection, label.position, [0.0, 0.0], size)
+            {
+                marks.push(mark(Shape::Text {
+                    pos,
+                    text: label.text,
+                }));
+            }
+        }
+    }
+
+    marks
+}
+
+/// Draws the notes of the displayed models over the viewport image, each one
+/// connected to the point it's pinned to with a leader line
+fn draw_annotation_labels(
+    ui: &imgui::Ui,
+    state: &State,
+    image_min: [f32; 2],
+    image_size: [f32; 2],
+) {
+    let draw_list = ui.get_window_draw_list();
+    let text_color = ui.style_color(imgui::StyleColor::Text);
+    let bg_color = ui.style_color(imgui::StyleColor::PopupBg);
+    let image_max = [image_min[0] + image_size[0], image_min[1] + image_size[1]];
+
+    draw_list.with_clip_rect_intersect(image_min, image_max, || {
+        for model in state
+            .objects
+            .iter()
+            .filter(|m| m.visible && state.is_selected(m.id))
+        {
+            for (i, annotation) in model.annotations.iter().enumerate() {
+                let Some(anchor) = project_to_viewport(
+                    &state.view_projection,
+                    model.local_to_world(annotation.position),
+                    image_min,
+                    image_size,
+                ) else {
+                    continue;
+                };
+
+                let label = format!("{}. {}", i + 1, annotation.text);
+                let label_size = ui.calc_text_size(&label);
+                let label_pos = [
+                    anchor[0] + ANNOTATION_LABEL_OFFSET[0],
+                    anchor[1] + ANNOTATION_LABEL_OFFSET[1] - label_size[1],
+                ];
+
+                draw_list
+                    .add_line(
+                        anchor,
+                        [label_pos[0], label_pos[1] + label_size[1]],
+                        text_color,
+                    )
+                    .build();
+                draw_list
+                    .add_circle(anchor, 3.0, text_color)
+                    .filled(true)
+                    .build();
+                draw_list
+                    .add_rect(
+                        [label_pos[0] - 4.0, label_pos[1] - 2.0],
+                        [
+                            label_pos[0] + label_size[0] + 4.0,
+                            label_pos[1] + label_size[1] + 2.0,
+                        ],
+                        bg_color,
+                    )
+                    .filled(true)
+                    .rounding(3.0)
+                    .build();
+                draw_list.add_text(label_pos, text_color, &label);
+            }
+        }
+    });
+}
+
+fn draw_annotation_popup(ui: &imgui::Ui, state: &mut State) {
+    if state.annotation_popup_request {
+        ui.open_popup("###EditAnnotation");
+        state.annotation_popup_request = false;
+    }
+
+    ui.modal_popup_config(format!("{}###EditAnnotation", tr("annotations.edit_title")))
+        .always_auto_resize(true)
+        .build(|| {
+            let Some((id, idx, text)) = &mut state.editing_annotation else {
+                ui.close_current_popup();
+                return;
+            };
+            let Some(model) = state.objects.iter_mut().find(|m| m.id == *id) else {
+                state.editing_annotation = None;
+                ui.close_current_popup();
+                return;
+            };
+
+            if ui.is_window_appearing() {
+                ui.set_keyboard_focus_here();
+            }
+            let confirmed = ui
+                .input_text("###AnnotationInput", text)
+                .enter_returns_true(true)
+                .build();
+
+            if (confirmed || ui.button(tr("annotations.save"))) && !text.trim().is_empty() {
+                if let Some(annotation) = model.annotations.get_mut(*idx) {
+                    annotation.text = text.trim().to_string();
+                }
+                state.editing_annotation = None;
+                ui.close_current_popup();
+                return;
+            }
+            ui.same_line();
+            if ui.button(tr("annotations.cancel")) || ui.is_key_pressed(imgui::Key::Escape) {
+                // a note that was just placed has nothing to go back to
+                if model
+                    .annotations
+                    .get(*idx)
+                    .is_some_and(|a| a.text.is_empty())
+                {
+                    model.annotations.remove(*idx);
+                }
+                state.editing_annotation = None;
+                ui.close_current_popup();
+            }
+        });
+}
+
+fn draw_annotations_window(ui: &imgui::Ui, state: &mut State) {
+    ui.window(format!("{}###Annotations", tr("window.annotations")))
+        .size([500.0, 200.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            ui.disabled(state.active_model().is_none(), || {
+                if ui.button(tr("annotations.add")) {
+                    state.annotating = true;
+                    state.picking_pivot = false;
+                }
+            });
+            if state.annotating {
+                ui.same_line();
+                ui.text_disabled(tr("annotations.placing"));
+            }
+            ui.separator();
+
+            let mut edit = None;
+            let mut delete = None;
+
+            for model in &state.objects {
+                if model.annotations.is_empty() {
+                    continue;
+                }
+
+                ui.tree_node_config(format!("{}###AnnotationsOf{}", model.name, model.id))
+                    .default_open(true)
+                    .build(|| {
+                        for (i, annotation) in model.annotations.iter().enumerate() {
+                            ui.text_wrapped(format!("{}. {}", i + 1, annotation.text));
+                            if ui.small_button(format!(
+                                "{}###EditAnnotation{}_{}",
+                                tr("annotations.edit"),
+                                model.id,
+                                i
+                            )) {
+                                edit = Some((model.id, i, annotation.text.clone()));
+                            }
+                            ui.same_line();
+                            if ui.small_button(format!(
+                                "{}###DeleteAnnotation{}_{}",
+                                tr("annotations.delete"),
+                                model.id,
+                                i
+                            )) {
+                                delete = Some((model.id, i));
+                            }
+                        }
+                    });
+            }
+
+            if state.objects.iter().all(|m| m.annotations.is_empty()) {
+                ui.text_disabled(tr("annotations.none"));
+            }
+
+            if edit.is_some() {
+                state.editing_annotation = edit;
+                state.annotation_popup_request = true;
+            }
+            if let Some((id, idx)) = delete {
+                if let Some(model) = state.objects.iter_mut().find(|m| m.id == id) {
+                    model.annotations.remove(idx);
+                }
+            }
+        });
+}
+
+fn draw_markup_window(ui: &imgui::Ui, state: &mut State) {
+    let open_captures = state.settings.open_captures;
+    let Some(markup) = &mut state.markup else {
+        return;
+    };
+
+    let mut open = true;
+    let mut notification = None;
+    ui.window(format!("{}###Markup", tr("window.markup")))
+        .opened(&mut open)
+        .size([800.0, 600.0], imgui::Condition::FirstUseEver)
+        .horizontal_scrollbar(true)
+        .build(|| {
+            for tool in MarkupTool::ALL {
+                if ui.radio_button_bool(tool.name(), markup.tool == tool) {
+                    markup.tool = tool;
+                }
+                ui.same_line();
+            }
+            ui.color_edit4_config("###MarkupColor", &mut markup.color)
+                .inputs(false)
+                .build();
+            ui.same_line();
+            ui.disabled(markup.marks.is_empty(), || {
+                if ui.button(tr("markup.undo")) {
+                    markup.marks.pop();
+                }
+            });
+            ui.same_line();
+            if ui.button(tr("markup.save")) {
+                let path = markup.output_path();
+                match markup.flatten().save(&path) {
+                    Ok(_) => {
+                        info!("Annotated capture saved to: {}", path.display());
+                        if open_captures {
+                            if let Err(e) = utils::open_path(&path) {
+                                error!("{}", e);
+                            }
+                        }
+                        notification = Some(saved_notification(
+                            tr("notification.annotated_capture_saved"),
+                            path,
+                        ));
+                    }
+                    Err(e) => error!("Failed to save annotated capture: {}", e),
+                }
+            }
+            ui.same_line();
+            if ui.button(tr("markup.copy")) {
+                // the clipboard tools read the image from a file
+                let path = markup.output_path();
+                let copied = markup
+                    .flatten()
+                    .save(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|_| utils::copy_image_to_clipboard(&path));
+                match copied {
+                    Ok(_) => info!("Copied annotated capture to the clipboard"),
+                    Err(e) => error!("Failed to copy annotated capture: {:#}", e),
+                }
+            }
+
+            if let Some((pos, text)) = &mut markup.pending_text {
+                // grab the focus as soon as the text is placed
+                if text.is_empty() {
+                    ui.set_keyboard_focus_here();
+                }
+                let confirmed = ui
+                    .input_text("###MarkupText", text)
+                    .hint(tr("markup.text_hint"))
+                    .enter_returns_true(true)
+                    .build();
+                if confirmed {
+                    if !text.trim().is_empty() {
+                        markup.marks.push(Mark {
+                            shape: Shape::Text {
+                                pos: *pos,
+                                text: text.trim().to_string(),
+                            },
+                            color: markup.color,
+                        });
+                    }
+                    markup.pending_text = None;
+                }
+            }
+            ui.separator();
+
+            // an invisible button keeps drags on the image from moving the window
+            let origin = ui.cursor_screen_pos();
+            let size = [markup.image.width() as f32, markup.image.height() as f32];
+            ui.invisible_button("###MarkupCanvas", size);
+            let mouse = ui.io().mouse_pos;
+            let pos = [mouse[0] - origin[0], mouse[1] - origin[1]];
+
+            if ui.is_item_activated() {
+                if markup.tool == MarkupTool::Text {
+                    markup.pending_text = Some((pos, String::new()));
+                } else {
+                    markup.current = Some(Mark::start(markup.tool, pos, markup.color));
+                }
+            }
+            if let Some(mark) = &mut markup.current {
+                if ui.is_item_active() {
+                    mark.drag_to(pos);
+                } else {
+                    markup.marks.push(mark.clone());
+                    markup.current = None;
+                }
+            }
+
+            let draw_list = ui.get_window_draw_list();
+            draw_list
+                .add_image(
+                    imgui::TextureId::new(markup.texture as usize),
+                    origin,
+                    [origin[0] + size[0], origin[1] + size[1]],
+                )
+                .build();
+            for mark in markup.marks.iter().chain(&markup.current) {
+                mark.draw(&draw_list, origin);
+            }
+            if let Some((pos, text)) = &markup.pending_text {
+                Mark {
+                    shape: Shape::Text {
+                        pos: *pos,
+                        text: format!("{}|", text),
+                    },
+                    color: markup.color,
+                }
+                .draw(&draw_list, origin);
+            }
+        });
+
+    if let Some(notification) = notification {
+        state.notify(notification);
+    }
+    if !open {
+        state.markup = None;
+    }
+}
+
+/// Context menu items for a file on disk
+pub fn draw_path_menu_items(ui: &imgui::Ui, path: &std::path::Path) {
+    if ui.menu_item(tr("path.reveal")) {
+        if let Err(e) = shell::reveal_in_file_manager(path) {
+            error!("{:#}", e);
+        }
+    }
+    if ui.menu_item(tr("path.copy")) {
+        ui.set_clipboard_text(shell::absolute_path(path).to_string_lossy());
+    }
+}
+
+/// A file was saved, clicking the toast opens it
+pub fn saved_notification(
+    title: &str,
+    path: std::path::PathBuf,
+) -> ui::notifications::Notification {
+    ui::notifications::Notification::info(title)
+        .with_text(path.to_string_lossy())
+        .with_action(ui::notifications::Action::Open(path))
+}
+
+fn draw_console(ui: &imgui::Ui, state: &mut State) {
+    ui.window(format!("{}###Console", tr("window.console")))
+        .size([500.0, 200.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let console = &mut state.console;
+            ui.checkbox(tr("console.errors"), &mut console.show_errors);
+            ui.same_line();
+            ui.checkbox(tr("console.warnings"), &mut console.show_warnings);
+            ui.same_line();
+            ui.checkbox(tr("console.info"), &mut console.show_info);
+            ui.same_line();
+            ui.checkbox(tr("console.debug"), &mut console.show_debug);
+            ui.same_line();
+            ui.set_next_item_width(-1.0);
+            ui.input_text("###ConsoleSearch", &mut console.search)
+                .hint(tr("console.search"))
+                .build();
+
+            ui.child_window("###ConsoleHistory")
+                .size([0.0, -35.0])
+                .build(|| {
+                    for line in state.logger.arc.read().unwrap().history.iter() {
+                        if !console.is_visible(line) {
+                            continue;
+                        }
+
+                        let style = ui.push_style_color(
+                            imgui::StyleColor::Text,
+                            state.settings.theme.log_color(line.level),
+                        );
+
+                        ui.text_wrapped(line.to_string());
+                        style.pop();
+
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(format!("{}: {}", tr("console.source"), line.target));
+                        }
+                        if ui.is_item_clicked_with_button(imgui::MouseButton::Right) {
+                            console.context_line = Some(line.to_string());
+                            ui.open_popup("###ConsoleLineContext");
+                        }
+                    }
+
+                    ui.popup("###ConsoleLineContext", || {
+                        if ui.menu_item(tr("console.copy_line")) {
+                            if let Some(line) = console.context_line.take() {
+                                ui.set_clipboard_text(line);
+                            }
+                        }
+                    });
+
+                    if console.autoscroll && ui.scroll_y() >= ui.scroll_max_y() {
+                        ui.set_scroll_here_y_with_ratio(1.0);
+                    }
+                });
+
+            ui.separator();
+            if ui.button(tr("console.clear")) {
+                let mut logger = state.logger.arc.write().unwrap();
+                logger.clear();
+            }
+            ui.same_line();
+            if ui.button(tr("console.copy_all")) {
+                let text = state
+                    .logger
+                    .arc
+                    .read()
+                    .unwrap()
+                    .history
+                    .iter()
+                    .filter(|line| console.is_visible(line))
+                    .map(|line| line.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ui.set_clipboard_text(text);
+            }
+            ui.same_line();
+            if ui.button(tr("console.open_log_folder")) {
+                let log_dir = std::path::Path::new(logger::LOG_FILE_NAME)
+                    .canonicalize()
+                    .ok()
+                    .and_then(|p| p.parent().map(|p| p.to_path_buf()));
+                match log_dir {
+                    Some(dir) => {
+                        if let Err(e) = utils::open_path(&dir) {
+                            error!("{}", e);
+                        }
+                    }
+                    None => warn!("Log file \"{}\" was not found", logger::LOG_FILE_NAME),
+                }
+            }
+            ui.same_line();
+            ui.checkbox(tr("console.autoscroll"), &mut console.autoscroll);
+        });
+}
+
+fn draw_heightmap_import_window(ui: &imgui::Ui, state: &mut State) {
+    let Some(path) = &state.heightmap_import else {
+        return;
+    };
+
+    let mut open = true;
+    let mut submitted = false;
+    let options = &mut state.heightmap_options;
+    ui.window(format!(
+        "{}###ImportHeightmap",
+        tr("window.import_heightmap")
+    ))
+    .opened(&mut open)
+    .always_auto_resize(true)
+    .collapsible(false)
+    .build(|| {
+        ui.text(path.to_string_lossy());
+        ui.set_next_item_width(150.0);
+        imgui::Drag::new(tr("heightmap.resolution"))
+            .range(2, 4096)
+            .speed(4.0)
+            .build(ui, &mut options.resolution);
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr("heightmap.resolution_tooltip"));
+        }
+        ui.set_next_item_width(150.0);
+        imgui::Drag::new(tr("heightmap.height_scale"))
+            .range(0.0, 10.0)
+            .speed(0.005)
+            .display_format("%.3f")
+            .build(ui, &mut options.height_scale);
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr("heightmap.height_scale_tooltip"));
+        }
+
+        if ui.button(tr("heightmap.import")) {
+            submitted = true;
+        }
+    });
+
+    if submitted {
+        if let Some(path) = state.heightmap_import.take() {
+            info!("Importing heightmap {:?}", path);
+            state.import_jobs.push(importer::ImportJob::spawn_heightmap(
+                path,
+                state.heightmap_options,
+            ));
+        }
+    } else if !open {
+        state.heightmap_import = None;
+    }
+}
+
+/// The fields of the import options, `format_default` is the coordinate
+/// system the format is usually in. Returns whether an edit was finished.
+fn draw_import_option_fields(
+    ui: &imgui::Ui,
+    options: &mut ImportOptions,
+    format_default: CoordinateSystem,
+) -> bool {
+    let mut edited = false;
+
+    ui.set_next_item_width(150.0);
+    let mut axes = vec![None];
+    axes.extend(UpAxis::ALL.map(Some));
+    let mut axis_idx = axes
+        .iter()
+        .position(|a| *a == options.coordinates.map(|c| c.up))
+        .unwrap_or(0);
+    if ui.combo(
+        tr("import_options.up_axis"),
+        &mut axis_idx,
+        &axes,
+        |a| match a {
+            Some(axis) => axis.name().into(),
+            None => format!(
+                "{} ({})",
+                tr("import_options.format_default"),
+                format_default.up.name()
+            )
+            .into(),
+        },
+    ) {
+        options.coordinates = axes[axis_idx].map(|up| CoordinateSystem {
+            up,
+            ..options.coordinates.unwrap_or(format_default)
+        });
+        edited = true;
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("import_options.up_axis_tooltip"));
+    }
+    if let Some(coordinates) = &mut options.coordinates {
+        let mut left_handed = coordinates.handedness == Handedness::Left;
+        if ui.checkbox(tr("import_options.left_handed"), &mut left_handed) {
+            coordinates.handedness = if left_handed {
+                Handedness::Left
+            } else {
+                Handedness::Right
+            };
+            edited = true;
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr("import_options.left_handed_tooltip"));
+        }
+    }
+
+    ui.set_next_item_width(150.0);
+    imgui::Drag::new(tr("import_options.scale"))
+        .range(SCALE_RANGE.0, SCALE_RANGE.1)
+        .speed(0.01)
+        .flags(imgui::SliderFlags::LOGARITHMIC)
+        .display_format("%.3f")
+        .build(ui, &mut options.scale);
+    edited |= ui.is_item_deactivated_after_edit();
+
+    edited |= ui.checkbox(tr("import_options.flip_v"), &mut options.flip_v);
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("import_options.flip_v_tooltip"));
+    }
+
+    let mut smooth = options.smoothing_angle.is_some();
+    if ui.checkbox(tr("import_options.smooth"), &mut smooth) {
+        options.smoothing_angle = smooth.then_some(DEFAULT_SMOOTHING_ANGLE);
+        edited = true;
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("import_options.smooth_tooltip"));
+    }
+    if let Some(angle) = &mut options.smoothing_angle {
+        ui.same_line();
+        ui.set_next_item_width(100.0);
+        imgui::Drag::new("##SmoothingAngle")
+            .range(0.0, 180.0)
+            .speed(0.5)
+            .display_format("%.0f°")
+            .build(ui, angle);
+        edited |= ui.is_item_deactivated_after_edit();
+    }
+
+    edited |= ui.checkbox(tr("import_options.merge_meshes"), &mut options.merge_meshes);
+
+    edited
+}
+
+fn draw_import_options_window(ui: &imgui::Ui, state: &mut State) {
+    let Some(prompt) = state.import_prompts.first_mut() else {
+        return;
+    };
+
+    let mut open = true;
+    let mut submitted = false;
+    let options = &mut prompt.options;
+    ui.window(format!("{}###ImportOptions", tr("window.import_options")))
+        .opened(&mut open)
+        .always_auto_resize(true)
+        .collapsible(false)
+        .build(|| {
+            ui.text(prompt.path.to_string_lossy());
+
+            draw_import_option_fields(ui, options, CoordinateSystem::of_file(&prompt.path));
+
+            ui.separator();
+            let ext = prompt
+                .path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            ui.checkbox(
+                format!("{} (.{})", tr("import_options.remember"), ext),
+                &mut prompt.remember,
+            );
+            if ui.button(tr("import_options.import")) {
+                submitted = true;
+            }
+        });
+
+    if submitted {
+        let prompt = state.import_prompts.remove(0);
+        if prompt.remember {
+            if let Some(ext) = prompt.path.extension() {
+                state
+                    .settings
+                    .import_options
+                    .insert(ext.to_string_lossy().to_lowercase(), prompt.options);
+                config::store(state.settings.clone()).unwrap();
+            }
+        }
+        utils::start_import(prompt.path, prompt.options, state);
+    } else if !open {
+        state.import_prompts.remove(0);
+    }
+}
+
+fn draw_part_picker_window(ui: &imgui::Ui, state: &mut State) {
+    let Some(pick) = state.part_picks.first_mut() else {
+        return;
+    };
+
+    let mut open = true;
+    let mut submitted = false;
+    ui.window(format!("{}###PickParts", tr("window.pick_parts")))
+        .opened(&mut open)
+        .size([420.0, 360.0], imgui::Condition::FirstUseEver)
+        .collapsible(false)
+        .build(|| {
+            ui.text(pick.path.to_string_lossy());
+            if ui.small_button(tr("parts.select_all")) {
+                pick.selected.fill(true);
+            }
+            ui.same_line();
+            if ui.small_button(tr("parts.select_none")) {
+                pick.selected.fill(false);
+            }
+            ui.separator();
+
+            let footer = ui.frame_height_with_spacing();
+            ui.child_window("##Parts").size([0.0, -footer]).build(|| {
+                for (part, selected) in pick.parts.iter().zip(pick.selected.iter_mut()) {
+                    let name = if part.name.is_empty() {
+                        tr("parts.unnamed")
+                    } else {
+                        &part.name
+                    };
+                    ui.checkbox(format!("{}##{}", name, part.index), selected);
+                    ui.same_line();
+                    ui.text_disabled(format!(
+                        "{} {}, {:.2} MB",
+                        part.triangles,
+                        tr("parts.triangles"),
+                        part.size as f32 / (1024.0 * 1024.0)
+                    ));
+                }
+            });
+
+            let any_selected = pick.selected.contains(&true);
+            ui.disabled(!any_selected, || {
+                if ui.button(tr("parts.import")) {
+                    submitted = true;
+                }
+            });
+        });
+
+    if submitted {
+        let pick = state.part_picks.remove(0);
+        let parts: std::collections::HashSet<usize> = pick
+            .parts
+            .iter()
+            .zip(&pick.selected)
+            .filter(|(_, selected)| **selected)
+            .map(|(part, _)| part.index)
+            .collect();
+        info!(
+            "Importing {} of the {} objects of {:?}",
+            parts.len(),
+            pick.parts.len(),
+            pick.path
+        );
+        state.import_jobs.push(importer::ImportJob::spawn_parts(
+            pick.path,
+            pick.load_textures,
+            parts,
+            pick.options,
+        ));
+    } else if !open {
+        state.part_picks.remove(0);
+    }
+}
+
+fn draw_open_url_window(ui: &imgui::Ui, state: &mut State) {
+    let Some(url) = &mut state.url_input else {
+        return;
+    };
+
+    let mut open = true;
+    let mut submitted = false;
+    ui.window(format!("{}###OpenUrl", tr("window.open_url")))
+        .opened(&mut open)
+        .always_auto_resize(true)
+        .collapsible(false)
+        .build(|| {
+            if ui.is_window_appearing() {
+                ui.set_keyboard_focus_here();
+            }
+            ui.set_next_item_width(400.0);
+            let entered = ui
+                .input_text("###UrlInput", url)
+                .hint("https://")
+                .enter_returns_true(true)
+                .build();
+            ui.same_line();
+            if ui.button(tr("open_url.paste")) {
+                if let Some(text) = ui.clipboard_text() {
+                    *url = text.trim().to_string();
+                }
+            }
+
+            ui.disabled(!importer::download::is_url(url.trim()), || {
+                if ui.button(tr("open_url.open")) || entered {
+                    submitted = true;
+                }
+            });
+        });
+
+    if submitted {
+        if let Some(url) = state.url_input.take() {
+            utils::import_model_from_url(url.trim(), state);
+        }
+    } else if !open {
+        state.url_input = None;
+    }
+}
+
+fn draw_note_editor(ui: &imgui::Ui, state: &mut State) {
+    let Some(edit) = &mut state.note_edit else {
+        return;
+    };
+
+    let mut open = true;
+    let mut saved = false;
+    let mut cancelled = false;
+    let name = edit
+        .path
+        .file_name()
+        .map_or(String::new(), |n| n.to_string_lossy().into_owned());
+    let title = format!("{} - {}###NoteEditor", tr("window.note_editor"), name);
+    ui.window(title)
+        .opened(&mut open)
+        .size([400.0, 260.0], imgui::Condition::FirstUseEver)
+        .collapsible(false)
+        .build(|| {
+            ui.text_disabled(edit.path.to_string_lossy());
+            ui.set_next_item_width(-1.0);
+            ui.input_text("###NoteTags", &mut edit.tags)
+                .hint(tr("notes.tags_hint"))
+                .build();
+            ui.input_text_multiline("###NoteText", &mut edit.text, [-1.0, -30.0])
+                .build();
+            if ui.button(tr("notes.save")) {
+                saved = true;
+            }
+            ui.same_line();
+            if ui.button(tr("notes.cancel")) {
+                cancelled = true;
+            }
+        });
+
+    if saved {
+        if let Some(edit) = state.note_edit.take() {
+            state.notes.set(&edit.path, edit.note());
+        }
+    } else if !open || cancelled {
+        state.note_edit = None;
+    }
+}
+
+/// The folders of the library, with the indexing progress
+fn draw_library_folders(ui: &imgui::Ui, state: &mut State) {
+    if !ui.collapsing_header(tr("library.folders"), imgui::TreeNodeFlags::DEFAULT_OPEN) {
+        return;
+    }
+
+    let library = &mut state.library;
+    let mut removed = None;
+    for (i, folder) in library.folders.iter().enumerate() {
+        let _id = ui.push_id_usize(i);
+        ui.disabled(library.is_indexing(), || {
+            if ui.small_button("x") {
+                removed = Some(i);
+            }
+        });
+        ui.same_line();
+        ui.text(folder.to_string_lossy());
+    }
+    if library.folders.is_empty() {
+        ui.text_disabled(tr("library.no_folders"));
+    }
+
+    ui.disabled(library.is_indexing(), || {
+        if ui.button(tr("library.add_folder")) {
+            if let Some(dir) = rfd::FileDialog::new()
+                .set_title(tr("dialog.library_folder.title"))
+                .pick_folder()
+            {
+                library.add_folder(dir, state.notes.paths());
+            }
+        }
+        ui.same_line();
+        if ui.button(tr("library.reindex")) {
+            library.index(state.notes.paths());
+        }
+        item_help(ui, "library.reindex");
+    });
+    if let Some((done, total)) = library.progress() {
+        ui.same_line();
+        ui.text(format!("{} {}/{}", tr("library.indexing"), done, total));
+    }
+
+    if let Some(i) = removed {
+        library.remove_folder(i, state.notes.paths());
+    }
+}
+
+/// The search and the format and size filters of the library
+fn draw_library_filters(ui: &imgui::Ui, library: &mut library::Library) {
+    ui.set_next_item_width(-1.0);
+    ui.input_text("###LibraryQuery", &mut library.query)
+        .hint(tr("library.search_hint"))
+        .build();
+
+    let formats: Vec<Option<String>> = std::iter::once(None)
+        .chain(library.formats().into_iter().map(Some))
+        .collect();
+    let mut format_idx = formats
+        .iter()
+        .position(|f| *f == library.format)
+        .unwrap_or(0);
+    ui.set_next_item_width(100.0);
+    if ui.combo(tr("library.format"), &mut format_idx, &formats, |f| {
+        f.as_ref()
+            .map_or(tr("library.any_format").to_string(), |f| f.to_uppercase())
+            .into()
+    }) {
+        library.format = formats[format_idx].clone();
+    }
+    ui.same_line();
+    ui.set_next_item_width(80.0);
+    imgui::Drag::new(tr("library.min_size"))
+        .range(0.0, 10000.0)
+        .speed(0.1)
+        .display_format("%.1f MB")
+        .build(ui, &mut library.min_size);
+    ui.same_line();
+    ui.set_next_item_width(80.0);
+    imgui::Drag::new(tr("library.max_size"))
+        .range(0.0, 10000.0)
+        .speed(0.1)
+        .display_format("%.1f MB")
+        .build(ui, &mut library.max_size);
+    help_marker(ui, "library.max_size");
+    ui.same_line();
+    ui.checkbox(tr("library.only_duplicates"), &mut library.only_duplicates);
+    help_marker(ui, "library.only_duplicates");
+}
+
+/// The models in the library's folders and the files that have notes,
+/// searchable by their name, notes, tags, format and size
+fn draw_library_window(ui: &imgui::Ui, state: &mut State) {
+    if !state.show_library {
+        return;
+    }
+
+    let mut opened = vec![];
+    let mut edited = None;
+    let mut show_library = true;
+    ui.window(format!("{}###Library", tr("window.library")))
+        .opened(&mut show_library)
+        .size([520.0, 500.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            draw_library_folders(ui, state);
+            ui.separator();
+            draw_library_filters(ui, &mut state.library);
+
+            let library = &mut state.library;
+            ui.disabled(library.selected.is_empty(), || {
+                let label = format!(
+                    "{} ({})",
+                    tr("library.import_selected"),
+                    library.selected.len()
+                );
+                if ui.button(label) {
+                    opened = library.selected.drain().collect();
+                }
+            });
+            ui.separator();
+
+            if library.entries.is_empty() {
+                ui.text_wrapped(tr("library.empty"));
+                return;
+            }
+
+            let shown = library.filtered(|path, query| state.notes.matches(path, query));
+            ui.child_window("###LibraryEntries").build(|| {
+                for idx in shown {
+                    let texture = library.thumbnail(idx);
+                    let entry = &library.entries[idx];
+                    let _id = ui.push_id(entry.path.to_string_lossy());
+                    if texture != 0 {
+                        imgui::Image::new(imgui::TextureId::new(texture as usize), [64.0, 64.0])
+                            .build(ui);
+                    } else {
+                        ui.dummy([64.0, 64.0]);
+                    }
+                    ui.same_line();
+                    ui.group(|| {
+                        let exists = entry.path.exists();
+                        let mut selected = library.selected.contains(&entry.path);
+                        if ui.checkbox(entry.name(), &mut selected) {
+                            if selected {
+                                library.selected.insert(entry.path.clone());
+                            } else {
+                                library.selected.remove(&entry.path);
+                            }
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(entry.path.to_string_lossy());
+                        }
+                        if !exists {
+                            ui.same_line();
+                            ui.text_disabled(format!("({})", tr("library.missing")));
+                        }
+
+                        match &entry.error {
+                            Some(error) => ui.text_colored([1.0, 0.4, 0.4, 1.0], error),
+                            None => ui.text_disabled(format!(
+                                "{} - {:.2} MB - {} {}",
+                                entry.format.to_uppercase(),
+                                entry.size_mb(),
+                                entry.triangles,
+                                tr("library.triangles")
+                            )),
+                        }
+                        if let Some(duplicates) = library.duplicates.get(&entry.path) {
+                            ui.text_colored(
+                                [1.0, 0.64, 0.0, 1.0],
+                                format!("{} ({})", tr("library.duplicates"), duplicates.len()),
+                            );
+                            if ui.is_item_hovered() {
+                                ui.tooltip(|| {
+                                    for duplicate in duplicates {
+                                        let kind = if duplicate.identical {
+                                            tr("library.identical")
+                                        } else {
+                                            tr("library.similar")
+                                        };
+                                        ui.text(format!(
+                                            "{} ({})",
+                                            duplicate.path.to_string_lossy(),
+                                            kind
+                                        ));
+                                    }
+                                });
+                            }
+                        }
+                        if let Some(note) = state.notes.get(&entry.path) {
+                            if !note.tags.is_empty() {
+                                ui.text_colored([0.6, 0.8, 1.0, 1.0], note.tags_label());
+                            }
+                            if let Some(line) = note.text.lines().next() {
+                                ui.text_disabled(line);
+                            }
+                        }
+
+                        ui.disabled(!exists, || {
+                            if ui.small_button(tr("library.open")) {
+                                opened.push(entry.path.clone());
+                            }
+                        });
+                        ui.same_line();
+                        if ui.small_button(tr("notes.edit")) {
+                            edited = Some(entry.path.clone());
+                        }
+                        if exists {
+                            ui.same_line();
+                            if ui.small_button("...") {
+                                ui.open_popup("###LibraryMenu");
+                            }
+                            ui.popup("###LibraryMenu", || {
+                                draw_path_menu_items(ui, &entry.path);
+                            });
+                        }
+                    });
+                    ui.separator();
+                }
+            });
+        });
+    state.show_library = show_library;
+
+    if !opened.is_empty() {
+        utils::import_models_from_paths(&opened, state);
+    }
+    if let Some(path) = edited {
+        state.note_edit = Some(notes::NoteEdit::new(&path, &state.notes));
+    }
+}
+
+fn draw_measurements_window(ui: &imgui::Ui, state: &mut State) {
+    if !state.show_measurements {
+        return;
+    }
+
+    ui.window(format!("{}###Measurements", tr("window.measurements")))
+        .opened(&mut state.show_measurements)
+        .size([450.0, 250.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            for kind in measure::MeasureKind::ALL {
+                if ui.radio_button_bool(kind.name(), state.measure_kind == kind) {
+                    state.measure_kind = kind;
+                }
+                ui.same_line();
+            }
+            let mut snap_idx = measure::Snap::ALL
+                .iter()
+                .position(|s| *s == state.measure_snap)
+                .unwrap_or(0);
+            ui.set_next_item_width(120.0);
+            if ui.combo(
+                tr("measure.snap"),
+                &mut snap_idx,
+                &measure::Snap::ALL,
+                |s| s.name().into(),
+            ) {
+                state.measure_snap = measure::Snap::ALL[snap_idx];
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr("measure.snap_tooltip"));
+            }
+
+            let active = state.selection.last().copied();
+            ui.disabled(active.is_none(), || {
+                if ui.button(tr("measure.start")) {
+                    if let Some(id) = active {
+                        state.measuring = Some(measure::Placement::new(id, state.measure_kind));
+                        state.annotating = false;
+                        state.picking_pivot = false;
+                    }
+                }
+            });
+            if let Some(placement) = &state.measuring {
+                ui.same_line();
+                ui.text_disabled(format!(
+                    "{} ({}/{})",
+                    tr("measure.placing"),
+                    placement.points.len(),
+                    placement.kind.point_count()
+                ));
+            }
+            ui.separator();
+
+            let mut delete = None;
+            for model in &mut state.objects {
+                if model.measurements.is_empty() {
+                    continue;
+                }
+
+                let labels: Vec<String> =
+                    model.measurements.iter().map(|m| m.label(model)).collect();
+                ui.tree_node_config(format!("{}###MeasurementsOf{}", model.name, model.id))
+                    .default_open(true)
+                    .build(|| {
+                        for (i, (measurement, label)) in
+                            model.measurements.iter_mut().zip(labels).enumerate()
+                        {
+                            ui.set_next_item_width(150.0);
+                            ui.input_text(
+                                format!("###MeasurementName{}_{}", model.id, i),
+                                &mut measurement.name,
+                            )
+                            .build();
+                            ui.same_line();
+                            ui.text(label);
+                            ui.same_line();
+                            if ui.small_button(format!(
+                                "{}###DeleteMeasurement{}_{}",
+                                tr("measure.delete"),
+                                model.id,
+                                i
+                            )) {
+                                delete = Some((model.id, i));
+                            }
+                        }
+                    });
+            }
+
+            if state.objects.iter().all(|m| m.measurements.is_empty()) {
+                ui.text_disabled(tr("measure.none"));
+            }
+
+            if let Some((id, idx)) = delete {
+                if let Some(model) = state.objects.iter_mut().find(|m| m.id == id) {
+                    model.measurements.remove(idx);
+                }
+            }
+        });
+}
+
+fn draw_materials_window(ui: &imgui::Ui, state: &mut State) {
+    if !state.show_materials {
+        return;
+    }
+
+    ui.window(format!("{}###Materials", tr("window.materials")))
+        .opened(&mut state.show_materials)
+        .size([400.0, 300.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let Some(model) = state
+                .selection
+                .last()
+                .and_then(|id| state.objects.iter_mut().find(|m| m.id == *id))
+            else {
+                ui.text_disabled(tr("materials.no_model"));
+                return;
+            };
+            if model.evicted {
+                ui.text_disabled(tr("materials.unloaded"));
+                return;
+            }
+
+            ui.text_disabled(tr("materials.help"));
+            let Some(_table) = ui.begin_table_with_flags(
+                "Materials Table",
+                3,
+                imgui::TableFlags::ROW_BG | imgui::TableFlags::SIZING_STRETCH_PROP,
+            ) else {
+                return;
+            };
+            ui.table_setup_column(tr("materials.name"));
+            ui.table_setup_column(tr("materials.triangles"));
+            ui.table_setup_column(tr("materials.visible"));
+            ui.table_headers_row();
+
+            for (i, usage) in model.material_usage().into_iter().enumerate() {
+                let _id = ui.push_id_usize(i);
+                let highlighted =
+                    state.highlighted_material.as_ref() == Some(&(model.id, usage.name.clone()));
+
+                ui.table_next_column();
+                if ui
+                    .selectable_config(&usage.name)
+                    .selected(highlighted)
+                    .build()
+                {
+                    state.highlighted_material =
+                        (!highlighted).then(|| (model.id, usage.name.clone()));
+                }
+
+                ui.table_next_column();
+                ui.text(usage.triangles.to_string());
+
+                ui.table_next_column();
+                let mut visible = usage.meshes.iter().all(|m| model.meshes[*m].visible);
+                if ui.checkbox("###Visible", &mut visible) {
+                    for m in &usage.meshes {
+                        model.meshes[*m].visible = visible;
+                    }
+                }
+            }
+        });
+}
+
+fn draw_online_window(ui: &imgui::Ui, state: &mut State) {
+    if !state.show_online {
+        return;
+    }
+
+    ui.window(format!("{}###Online", tr("window.online")))
+        .opened(&mut state.show_online)
+        .size([450.0, 500.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let token = state.settings.thingiverse_token.trim().to_string();
+            if token.is_empty() {
+                ui.text_wrapped(tr("online.no_token"));
+                if ui.button(tr("online.open_settings")) {
+                    state.show_settings = true;
+                }
+                return;
+            }
+
+            let browser = &mut state.online;
+            ui.set_next_item_width(-80.0);
+            let entered = ui
+                .input_text("###OnlineQuery", &mut browser.query)
+                .hint(tr("online.search_hint"))
+                .enter_returns_true(true)
+                .build();
+            ui.same_line();
+            let can_search = !browser.is_searching() && !browser.query.trim().is_empty();
+            ui.disabled(!can_search, || {
+                if ui.button(tr("online.search")) || (entered && can_search) {
+                    browser.search(&token);
+                }
+            });
+
+            if browser.is_searching() {
+                ui.text_disabled(tr("online.searching"));
+            } else if let Some(error) = &browser.error {
+                ui.text_colored([1.0, 0.4, 0.4, 1.0], error);
+            }
+            ui.separator();
+
+            let mut import = None;
+            ui.child_window("###OnlineResults").build(|| {
+                for thing in &browser.results {
+                    let _id = ui.push_id(thing.id.to_string());
+                    if thing.thumbnail_id != 0 {
+                        imgui::Image::new(
+                            imgui::TextureId::new(thing.thumbnail_id as usize),
+                            [64.0, 64.0],
+                        )
+                        .build(ui);
+                    } else {
+                        ui.dummy([64.0, 64.0]);
+                    }
+                    ui.same_line();
+                    ui.group(|| {
+                        ui.text(&thing.name);
+                        if !thing.creator.is_empty() {
+                            ui.text_disabled(format!("{} {}", tr("online.by"), thing.creator));
+                        }
+                        let resolving = browser.is_resolving(thing.id);
+                        ui.disabled(resolving, || {
+                            if ui.button(tr("online.import")) {
+                                import = Some(thing.id);
+                            }
+                        });
+                    });
+                    ui.separator();
+                }
             });
-            ui.tree_node_config("Transformations").build(|| {
-                draw_transformations(ui, mesh);
-            })
+
+            if let Some(thing_id) = import {
+                browser.import(thing_id, &token);
+            }
         });
 }
 
-fn draw_object_hierarchy(ui: &imgui::Ui, state: &mut State, idx: usize) -> bool {
-    ui.table_next_column();
-    if ui.checkbox(
-        format!("###{}", state.objects[idx].id),
-        &mut (Some(state.objects[idx].id) == state.active_model),
-    ) {
-        state.objects[idx].reset_rotation();
-        state.active_model = Some(state.objects[idx].id);
-        state
-            .camera
-            .focus_on_selected_model(state.active_model, &state.objects);
+fn draw_slicing_window(ui: &imgui::Ui, state: &mut State) {
+    if !state.show_slicing {
+        return;
     }
 
-    ui.table_next_column();
-    ui.tree_node_config(format!(
-        "{} ({:.1}MB)###{}",
-        state.objects[idx].name.as_str(),
-        state.objects[idx].mem_usage as f32 / (1024.0 * 1024.0),
-        idx
-    ))
-    .build(|| {
-        for (j, mesh) in &mut state.objects[idx].meshes.iter_mut().enumerate() {
-            draw_mesh_hierarchy(ui, mesh, j);
-        }
-    });
+    ui.window(format!("{}###Slicing", tr("window.slicing")))
+        .opened(&mut state.show_slicing)
+        .size([350.0, 200.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let slicing = &mut state.slicing;
 
-    ui.table_next_column();
-    if ui.small_button(format!("X###{}-{}", state.objects[idx].name.as_str(), idx)) {
-        info!("Removing object {}", state.objects[idx].name);
-        return true;
-    }
+            ui.checkbox(tr("slicing.enabled"), &mut slicing.enabled);
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr("slicing.enabled_tooltip"));
+            }
+            ui.set_next_item_width(150.0);
+            imgui::Drag::new(tr("slicing.layer_height"))
+                .range(0.001, 100.0)
+                .speed(0.001)
+                .display_format("%.3f")
+                .build(ui, &mut slicing.layer_height);
+            ui.checkbox(tr("slicing.cap"), &mut slicing.cap);
+            if ui.is_item_hovered() {
+                ui.tooltip_text(tr("slicing.cap_tooltip"));
+            }
+            ui.same_line();
+            let color = &mut slicing.section_color;
+            let mut rgb = [color.x, color.y, color.z];
+            if ui
+                .color_edit3_config(tr("slicing.section_color"), &mut rgb)
+                .inputs(false)
+                .build()
+            {
+                *color = glm::vec3(rgb[0], rgb[1], rgb[2]);
+            }
 
-    false
+            let layer_count = slicing.layer_count();
+            if layer_count == 0 {
+                ui.text_disabled(tr("slicing.nothing_displayed"));
+                return;
+            }
+
+            ui.disabled(!slicing.enabled, || {
+                if ui.arrow_button("###PreviousLayer", imgui::Direction::Left) {
+                    slicing.playing = false;
+                    slicing.layer = slicing.layer.saturating_sub(1);
+                }
+                ui.same_line();
+                if ui.arrow_button("###NextLayer", imgui::Direction::Right) {
+                    slicing.playing = false;
+                    slicing.layer = (slicing.layer + 1).min(layer_count - 1);
+                }
+                ui.same_line();
+                let label = if slicing.playing {
+                    tr("slicing.stop")
+                } else {
+                    tr("slicing.play")
+                };
+                if ui.button(label) {
+                    slicing.toggle_playing();
+                }
+                ui.same_line();
+                ui.set_next_item_width(-1.0);
+                let mut layer = slicing.layer + 1;
+                if imgui::Drag::new("###Layer")
+                    .range(1, layer_count)
+                    .display_format(&format!("%d / {}", layer_count))
+                    .build(ui, &mut layer)
+                {
+                    slicing.playing = false;
+                    slicing.layer = layer.clamp(1, layer_count) - 1;
+                }
+            });
+
+            if let Some(height) = slicing.plane {
+                ui.text(format!("{}: {:.3}", tr("slicing.height"), height));
+            }
+        });
 }
 
-fn draw_objects_window(ui: &imgui::Ui, state: &mut State) {
-    ui.window("Objects")
-        .size([500.0, 200.0], imgui::Condition::FirstUseEver)
+fn draw_camera_path_window(ui: &imgui::Ui, state: &mut State) {
+    if !state.show_camera_path {
+        return;
+    }
+
+    ui.window(format!("{}###CameraPath", tr("window.camera_path")))
+        .opened(&mut state.show_camera_path)
+        .size([400.0, 350.0], imgui::Condition::FirstUseEver)
         .build(|| {
-            let mut i = 0;
+            let path = &mut state.camera_path;
+            let exporting = path.export.is_some();
 
-            if let Some(..) = ui.begin_table_with_sizing(
-                "Objects Table",
-                3,
-                imgui::TableFlags::SIZING_FIXED_FIT,
-                [0.0, 0.0],
-                0.0,
-            ) {
-                ui.table_setup_column_with(imgui::TableColumnSetup {
-                    name: "",
-                    flags: imgui::TableColumnFlags::empty(),
-                    init_width_or_weight: 30.0,
-                    user_id: imgui::Id::default(),
-                });
-                ui.table_setup_column_with(imgui::TableColumnSetup {
-                    name: "",
-                    flags: imgui::TableColumnFlags::WIDTH_STRETCH,
-                    init_width_or_weight: 0.0,
-                    user_id: imgui::Id::default(),
-                });
-                ui.table_setup_column_with(imgui::TableColumnSetup {
-                    name: "",
-                    flags: imgui::TableColumnFlags::empty(),
-                    init_width_or_weight: 20.0,
-                    user_id: imgui::Id::default(),
+            ui.disabled(exporting, || {
+                if ui.button(tr("camera_path.add_keyframe")) {
+                    path.add_keyframe(&state.camera);
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr("camera_path.add_keyframe_tooltip"));
+                }
+                ui.same_line();
+                ui.disabled(path.keyframes.len() < 2, || {
+                    let label = if path.playing {
+                        tr("camera_path.stop")
+                    } else {
+                        tr("camera_path.play")
+                    };
+                    if ui.button(label) {
+                        if !path.playing && path.playhead >= path.duration() {
+                            path.playhead = 0.0;
+                        }
+                        path.playing = !path.playing;
+                    }
                 });
+                ui.same_line();
+                ui.set_next_item_width(-1.0);
+                if imgui::Drag::new("###Playhead")
+                    .range(0.0, f32::MAX)
+                    .speed(0.05)
+                    .display_format("%.2f s")
+                    .build(ui, &mut path.playhead)
+                    && path.playhead <= path.duration()
+                {
+                    path.playing = false;
+                    path.apply(path.playhead, &mut state.camera);
+                }
+            });
+            ui.separator();
 
-                while i < state.objects.len() {
-                    if draw_object_hierarchy(ui, state, i) {
-                        let selected_obj_id = state.objects[i].id;
-                        state.objects.remove(i);
-                        if state.active_model == Some(selected_obj_id) {
-                            let model = state.objects.last_mut().map(|m| m.reset_rotation());
-                            state.active_model = model.map(|o| o.id);
-                            state
-                                .camera
-                                .focus_on_selected_model(state.active_model, &state.objects);
+            let mut go_to = None;
+            let mut remove = None;
+            let mut resort = false;
+            ui.child_window("###Keyframes")
+                .size([0.0, -60.0])
+                .build(|| {
+                    if path.keyframes.is_empty() {
+                        ui.text_disabled(tr("camera_path.empty"));
+                    }
+                    for (i, keyframe) in path.keyframes.iter_mut().enumerate() {
+                        let _id = ui.push_id_usize(i);
+                        ui.text(format!("{}.", i + 1));
+                        ui.same_line();
+                        ui.set_next_item_width(100.0);
+                        resort |= imgui::Drag::new("###Time")
+                            .range(0.0, f32::MAX)
+                            .speed(0.05)
+                            .display_format("%.2f s")
+                            .build(ui, &mut keyframe.time);
+                        ui.same_line();
+                        if ui.small_button(tr("camera_path.go_to")) {
+                            go_to = Some(keyframe.time);
+                        }
+                        ui.same_line();
+                        if ui.small_button("X") {
+                            remove = Some(i);
                         }
-                        continue;
                     }
+                });
+            if let Some(i) = remove {
+                path.keyframes.remove(i);
+            }
+            if resort {
+                path.sort();
+            }
+            if let Some(time) = go_to {
+                path.playing = false;
+                path.playhead = time;
+                path.apply(time, &mut state.camera);
+            }
+            ui.separator();
 
-                    i += 1;
+            if let Some(export) = &path.export {
+                ui.text(format!(
+                    "{} {}/{}",
+                    tr("camera_path.exporting"),
+                    export.frame,
+                    export.frame_count
+                ));
+                ui.same_line();
+                if ui.button(tr("camera_path.cancel")) {
+                    path.export = None;
                 }
+                return;
             }
+
+            ui.set_next_item_width(100.0);
+            imgui::Drag::new(tr("camera_path.fps"))
+                .range(1, 120)
+                .build(ui, &mut path.fps);
+            ui.disabled(path.keyframes.len() < 2, || {
+                let frames = ui.button(tr("camera_path.export_frames"));
+                ui.same_line();
+                let video = ui.button(tr("camera_path.export_video"));
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr("camera_path.export_video_tooltip"));
+                }
+
+                if frames || video {
+                    if let Some(dir) = rfd::FileDialog::new()
+                        .set_title(tr("dialog.export_frames.title"))
+                        .pick_folder()
+                    {
+                        path.start_export(dir, video);
+                    }
+                }
+            });
         });
 }
 
-fn draw_console(ui: &imgui::Ui, state: &mut State) {
-    ui.window("Console")
+fn draw_script_window(ui: &imgui::Ui, state: &mut State) {
+    ui.window(format!("{}###Script", tr("window.script")))
         .size([500.0, 200.0], imgui::Condition::FirstUseEver)
         .build(|| {
-            ui.child_window("###ConsoleHistory")
+            ui.child_window("###ScriptOutput")
                 .size([0.0, -35.0])
                 .build(|| {
-                    for line in state.logger.arc.read().unwrap().history.iter() {
-                        let style = ui.push_style_color(imgui::StyleColor::Text, line.level);
-
-                        ui.text_wrapped(line.message.clone());
-                        style.pop();
+                    for line in &state.script.output {
+                        ui.text_wrapped(line);
                     }
                     if ui.scroll_y() >= ui.scroll_max_y() {
                         ui.set_scroll_here_y_with_ratio(1.0);
@@ -453,9 +4973,31 @@ fn draw_console(ui: &imgui::Ui, state: &mut State) {
                 });
 
             ui.separator();
-            if ui.button("Clear") {
-                let mut logger = state.logger.arc.write().unwrap();
-                logger.clear();
+            if state.script.is_running() {
+                ui.text_disabled(format!(
+                    "{}: {}",
+                    tr("script.queued"),
+                    state.script.queued()
+                ));
+                ui.same_line();
+                if ui.small_button(tr("script.stop")) {
+                    state.script.stop();
+                }
+                ui.same_line();
+            }
+            ui.set_next_item_width(-1.0);
+            let entered = ui
+                .input_text("###ScriptInput", &mut state.script.input)
+                .hint(tr("script.hint"))
+                .enter_returns_true(true)
+                .build();
+            if entered {
+                let line = std::mem::take(&mut state.script.input);
+                state.script.output.push(format!("> {}", line));
+
+                state.script.queue(line);
+                // keep typing without clicking the input again
+                ui.set_keyboard_focus_here_with_offset(imgui::FocusedWidget::Previous);
             }
         });
 }
@@ -475,10 +5017,15 @@ fn create_initial_docking(ui: &imgui::Ui, state: &mut State) {
     let padding = ui.push_style_var(imgui::StyleVar::WindowPadding([0.0, 0.0]));
     let rounding = ui.push_style_var(imgui::StyleVar::WindowRounding(0.0));
 
+    let display_size = ui.io().display_size;
+
     ui.window("Main Window")
         .flags(flags)
         .position([0.0, 0.0], imgui::Condition::Always)
-        .size(ui.io().display_size, imgui::Condition::Always)
+        .size(
+            [display_size[0], display_size[1] - status_bar_height(ui)],
+            imgui::Condition::Always,
+        )
         .build(|| {
             // Create top-level docking area, needs to be made early (before docked windows)
             let ui_d = ui::docking::UiDocking {};
@@ -495,15 +5042,18 @@ fn create_initial_docking(ui: &imgui::Ui, state: &mut State) {
                             imgui::Direction::Up,
                             0.6,
                             |up| {
-                                up.dock_window("Objects");
+                                up.dock_window("###Objects");
+                                up.dock_window("###Annotations");
+                                up.dock_window("###Materials");
                             },
                             |down| {
-                                down.dock_window("Console");
+                                down.dock_window("###Console");
+                                down.dock_window("###Script");
                             },
                         )
                     },
                     |left| {
-                        left.dock_window("Viewer");
+                        left.dock_window("###Viewer");
                     },
                 )
             }
@@ -513,99 +5063,422 @@ fn create_initial_docking(ui: &imgui::Ui, state: &mut State) {
     rounding.pop();
 }
 
+fn status_bar_height(ui: &imgui::Ui) -> f32 {
+    ui.text_line_height() + 8.0
+}
+
+fn draw_status_bar(ui: &imgui::Ui, state: &mut State) {
+    const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+    let display_size = ui.io().display_size;
+    let height = status_bar_height(ui);
+
+    let padding = ui.push_style_var(imgui::StyleVar::WindowPadding([8.0, 4.0]));
+    let rounding = ui.push_style_var(imgui::StyleVar::WindowRounding(0.0));
+
+    ui.window("###StatusBar")
+        .flags(
+            imgui::WindowFlags::NO_DECORATION
+                | imgui::WindowFlags::NO_MOVE
+                | imgui::WindowFlags::NO_DOCKING
+                | imgui::WindowFlags::NO_SAVED_SETTINGS
+                | imgui::WindowFlags::NO_NAV,
+        )
+        .position([0.0, display_size[1] - height], imgui::Condition::Always)
+        .size([display_size[0], height], imgui::Condition::Always)
+        .build(|| {
+            ui.text(format!("{}: {}", tr("status.mode"), state.tool_mode.name()));
+
+            if let Some(pos) = state.cursor_model_pos {
+                ui.same_line();
+                ui.text_disabled("|");
+                ui.same_line();
+                ui.text(format!("X: {:.3} Y: {:.3} Z: {:.3}", pos.x, pos.y, pos.z));
+            }
+
+            for (i, job) in state.import_jobs.iter().enumerate() {
+                let frame = (job.started.elapsed().as_millis() / 100) as usize % SPINNER.len();
+                ui.same_line();
+                ui.text_disabled("|");
+                ui.same_line();
+                if job.is_downloading() {
+                    ui.text(format!(
+                        "{} {} {}",
+                        SPINNER[frame],
+                        tr("status.downloading"),
+                        job.name
+                    ));
+                    if let Some(progress) = job.download_progress() {
+                        ui.same_line();
+                        imgui::ProgressBar::new(progress)
+                            .size([100.0, 0.0])
+                            .build(ui);
+                    }
+                } else {
+                    ui.text(format!(
+                        "{} {} {}",
+                        SPINNER[frame],
+                        tr("status.importing"),
+                        job.name
+                    ));
+                }
+                ui.same_line();
+                if ui.small_button(format!("x###CancelImport{}", i)) {
+                    job.cancel();
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr("status.cancel_import"));
+                }
+            }
+
+            if let Some(line) = state.logger.arc.read().unwrap().history.back() {
+                ui.same_line();
+                ui.text_disabled("|");
+                ui.same_line();
+                ui.text_colored(
+                    state.settings.theme.log_color(line.level),
+                    line.message.lines().next().unwrap_or_default(),
+                );
+            }
+        });
+
+    padding.pop();
+    rounding.pop();
+}
+
+/// Saves the scene the way it's shown in the viewport and returns the image,
+/// see `save_capture` for `open`
+fn capture_scene(
+    state: &mut State,
+    texture: u32,
+    size: [f32; 2],
+    save_path: &std::path::Path,
+    open: bool,
+) -> image::DynamicImage {
+    let now = std::time::Instant::now();
+    let capture = scene_image(texture);
+    let capture = capture.resize_exact(
+        size[0] as u32,
+        size[1] as u32,
+        image::imageops::FilterType::Gaussian,
+    );
+    save_capture(state, &capture, save_path, open);
+
+    debug!("Scene capture took: {}ms", now.elapsed().as_millis());
+
+    unsafe {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    }
+
+    capture
+}
+
+/// The scene texture read back from the GPU, top row first
+fn scene_image(texture: u32) -> image::DynamicImage {
+    let mut w = 0;
+    let mut h = 0;
+
+    unsafe {
+        gl::GetTextureLevelParameteriv(texture, 0, gl::TEXTURE_WIDTH, &mut w);
+        gl::GetTextureLevelParameteriv(texture, 0, gl::TEXTURE_HEIGHT, &mut h);
+    }
+
+    let mut pixels = vec![0u8; (w * h * 4) as usize];
+
+    unsafe {
+        gl::GetTextureImage(
+            texture,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            w * h * 4,
+            pixels.as_mut_ptr() as *mut std::ffi::c_void,
+        );
+    }
+
+    let capture =
+        image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(w as u32, h as u32, pixels).unwrap();
+    image::DynamicImage::ImageRgba8(capture).flipv()
+}
+
+/// Starts and stops recordings of the viewport, the options are in a popup
+/// that opens before recording
+fn draw_record_button(ui: &imgui::Ui, state: &mut State, size: [f32; 2]) {
+    if let Some(recording) = &state.recorder.active {
+        let elapsed = recording.started.elapsed().as_secs();
+        let style = ui.push_style_color(imgui::StyleColor::Text, [1.0, 0.4, 0.4, 1.0]);
+        let stop = ui.button(format!(
+            "{} {:02}:{:02}###Record",
+            tr("viewport.stop_recording"),
+            elapsed / 60,
+            elapsed % 60
+        ));
+        style.pop();
+        item_help(ui, "viewport.stop_recording");
+        if stop {
+            state.recorder.stop();
+        }
+        return;
+    }
+
+    if ui.button(format!("{}###Record", tr("viewport.record"))) {
+        ui.open_popup("###RecordOptions");
+    }
+    item_help(ui, "viewport.record");
+    ui.popup("###RecordOptions", || {
+        let recorder = &mut state.recorder;
+        ui.set_next_item_width(100.0);
+        imgui::Drag::new(tr("recording.fps"))
+            .range(1, 120)
+            .build(ui, &mut recorder.fps);
+        ui.set_next_item_width(100.0);
+        imgui::Drag::new(tr("recording.duration"))
+            .range(0.0, 3600.0)
+            .speed(0.1)
+            .display_format("%.1f s")
+            .build(ui, &mut recorder.duration);
+        help_marker(ui, "recording.duration");
+        let mut format_idx = recording::Format::ALL
+            .iter()
+            .position(|f| *f == recorder.format)
+            .unwrap_or(0);
+        ui.set_next_item_width(100.0);
+        if ui.combo(
+            tr("recording.format"),
+            &mut format_idx,
+            &recording::Format::ALL,
+            |f| f.name().into(),
+        ) {
+            recorder.format = recording::Format::ALL[format_idx];
+        }
+        help_marker(ui, "recording.format");
+
+        if ui.button(tr("recording.start")) {
+            let path = utils::capture_path(state, size, state.recorder.format.extension());
+            if let Err(e) = state.recorder.start(path, size) {
+                error!("Failed to start recording: {}", e);
+                state.notify(
+                    ui::notifications::Notification::new(
+                        ui::notifications::Kind::Error,
+                        tr("notification.recording_failed"),
+                    )
+                    .with_text(e.to_string()),
+                );
+            }
+            ui.close_current_popup();
+        }
+    });
+}
+
+/// Saves the whole window the way it was just drawn, panels included, for
+/// bug reports and documentation. Has to be called before the buffers are
+/// swapped.
+fn capture_window(state: &mut State, window: &glfw::Window, save_path: &std::path::Path) {
+    let (w, h) = window.get_framebuffer_size();
+    let mut pixels = vec![0u8; (w * h * 4) as usize];
+
+    unsafe {
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        gl::ReadBuffer(gl::BACK);
+        gl::ReadPixels(
+            0,
+            0,
+            w,
+            h,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut std::ffi::c_void,
+        );
+    }
+
+    let Some(capture) =
+        image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(w as u32, h as u32, pixels)
+    else {
+        return;
+    };
+    // the window has no use for its alpha, which would make the image see-through
+    let capture =
+        image::DynamicImage::ImageRgb8(image::DynamicImage::ImageRgba8(capture).to_rgb8());
+    save_capture(state, &capture.flipv(), save_path, true);
+}
+
+/// Writes a capture to disk, telling about it in a toast. With `open` the
+/// capture is opened afterwards if the settings ask for it.
+fn save_capture(
+    state: &mut State,
+    capture: &image::DynamicImage,
+    save_path: &std::path::Path,
+    open: bool,
+) {
+    if let Some(dir) = save_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            error!("Failed to create the capture folder {:?}: {}", dir, e);
+        }
+    }
+    match capture.save(save_path) {
+        Ok(_) => {
+            let path = save_path
+                .canonicalize()
+                .expect("Capture path to be canonicalized");
+            info!(
+                "Capture saved to: {} successfully",
+                path.to_str().expect("Capture path to be valid unicode")
+            );
+            if open && state.settings.open_captures {
+                if let Err(e) = utils::open_path(&path) {
+                    error!("{}", e);
+                }
+            }
+            state.notify(saved_notification(tr("notification.capture_saved"), path));
+        }
+        Err(e) => error!("Failed to save capture to {:?}: {}", save_path, e),
+    }
+}
+
+fn draw_scene_tabs(ui: &imgui::Ui, state: &mut State) {
+    let Some(_tab_bar) = ui.tab_bar_with_flags(
+        "###Scenes",
+        imgui::TabBarFlags::REORDERABLE | imgui::TabBarFlags::FITTING_POLICY_SCROLL,
+    ) else {
+        return;
+    };
+
+    let closable = state.scenes.len() > 1;
+    let mut switch_to = None;
+    let mut close = None;
+    for (i, scene) in state.scenes.iter().enumerate() {
+        let mut open = true;
+        let mut item = imgui::TabItem::new(format!("{}###Scene{}", scene.name, scene.id));
+        if closable {
+            item = item.opened(&mut open);
+        }
+        if state.scene_select_request == Some(scene.id) {
+            item = item.flags(imgui::TabItemFlags::SET_SELECTED);
+        }
+        let selected = item.begin(ui).is_some();
+        // the tab bar still shows the previous tab on the frame a switch is requested
+        if selected && i != state.active_scene && state.scene_select_request.is_none() {
+            switch_to = Some(i);
+        }
+        if !open {
+            close = Some(i);
+        }
+    }
+    state.scene_select_request = None;
+
+    let label = imgui::ImString::new("+");
+    let add = unsafe {
+        imgui::sys::igTabItemButton(
+            label.as_ptr(),
+            (imgui::TabItemFlags::TRAILING | imgui::TabItemFlags::NO_TOOLTIP).bits() as i32,
+        )
+    };
+    if ui.is_item_hovered() {
+        ui.tooltip_text(tr("viewport.new_scene_tooltip"));
+    }
+
+    if let Some(i) = switch_to {
+        state.switch_scene(i);
+    }
+    if let Some(i) = close {
+        state.close_scene(i);
+    }
+    if add {
+        state.add_scene();
+    }
+}
+
 fn draw_viewport(ui: &imgui::Ui, state: &mut State, texture: u32) {
-    ui.window("Viewer")
+    ui.window(format!("{}###Viewer", tr("window.viewer")))
         .size(ui.content_region_avail(), imgui::Condition::FirstUseEver)
         .no_decoration()
         .scrollable(!state.can_capture_cursor)
         .resizable(true)
         .build(|| {
+            draw_scene_tabs(ui, state);
+
             let mut tex_size = ui.content_region_avail();
             tex_size[1] -= 25.0;
             state.viewport_size = tex_size;
 
-            if ui.button("Reset Camera") {
-                state
-                    .camera
-                    .focus_on_selected_model(state.active_model, &state.objects);
-            }
-            ui.same_line();
-            if ui.button("Capture Scene") {
-                let now = std::time::Instant::now();
-                let mut w = 0;
-                let mut h = 0;
-
-                unsafe {
-                    gl::GetTextureLevelParameteriv(texture, 0, gl::TEXTURE_WIDTH, &mut w);
-                    gl::GetTextureLevelParameteriv(texture, 0, gl::TEXTURE_HEIGHT, &mut h);
-                }
-
-                let mut pixels = vec![0u8; (w * h * 4) as usize];
-
-                unsafe {
-                    gl::GetTextureImage(
-                        texture,
-                        0,
-                        gl::RGBA,
-                        gl::UNSIGNED_BYTE,
-                        w * h * 4,
-                        pixels.as_mut_ptr() as *mut std::ffi::c_void,
-                    );
-                }
-
-                let timestamp = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .expect("Current time to not be before the UNIX epoch");
-                let file_name = format!("capture-{}.png", timestamp.as_secs());
-                let save_path = std::path::Path::new(file_name.as_str());
-                let capture =
-                    image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(w as u32, h as u32, pixels)
-                        .unwrap();
-                let capture = image::DynamicImage::ImageRgba8(capture);
-                let capture = capture.flipv();
-                let capture = capture.resize_exact(
-                    tex_size[0] as u32,
-                    tex_size[1] as u32,
-                    image::imageops::FilterType::Gaussian,
-                );
-                let _ = capture.save(save_path);
-                let elapsed = now.elapsed();
-
-                info!(
-                    "Scene capture saved to: {} successfully",
-                    save_path
-                        .canonicalize()
-                        .expect("Capture path to be canonicalized")
-                        .to_str()
-                        .expect("Capture path to be valid unicode")
+            if ui.button(tr("viewport.reset_camera")) {
+                state.camera.focus_on_selected_model(
+                    state.active_model(),
+                    &state.objects,
+                    state.viewport_aspect(),
                 );
-
-                debug!("Scene capture took: {}ms", elapsed.as_millis());
-
-                unsafe {
-                    gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
-                }
+            }
+            item_help(ui, "viewport.reset_camera");
+            ui.same_line();
+            if ui.button(tr("viewport.capture")) {
+                let path = utils::capture_path(state, tex_size, "png");
+                let capture = capture_scene(state, texture, tex_size, &path, true);
+                let mut markup = ui::markup::Markup::new(capture.to_rgba8(), &path);
+                // start off with the scale overlays so the capture shows the size
+                markup.marks = scale_marks(ui, state, tex_size);
+                state.markup = Some(markup);
+            }
+            item_help(ui, "viewport.capture");
+            ui.same_line();
+            if ui.button(tr("viewport.capture_window")) {
+                let io = ui.io();
+                let size = [
+                    io.display_size[0] * io.display_framebuffer_scale[0],
+                    io.display_size[1] * io.display_framebuffer_scale[1],
+                ];
+                state.window_capture_request = Some(utils::capture_path(state, size, "png"));
+            }
+            item_help(ui, "viewport.capture_window");
+            ui.same_line();
+            draw_record_button(ui, state, tex_size);
+            // captures requested by scripts skip the markup window
+            if let Some(path) = state.capture_request.take() {
+                capture_scene(state, texture, tex_size, &path, false);
+            }
+            if state.recorder.wants_frame() {
+                let frame = scene_image(texture).to_rgba8();
+                state.recorder.push_frame(frame);
             }
             ui.same_line();
-            ui.checkbox("Wireframe", &mut state.wireframe);
+            ui.checkbox(tr("viewport.wireframe"), &mut state.wireframe);
+            item_help(ui, "viewport.wireframe");
             ui.same_line();
-            ui.checkbox("FOV zoom", &mut state.fov_zoom);
+            ui.checkbox(tr("viewport.inspect"), &mut state.inspecting);
+            item_help(ui, "viewport.inspect");
             ui.same_line();
-            ui.checkbox("Show Textures", &mut state.show_textures);
+            let mut mode_idx = DisplayMode::ALL
+                .iter()
+                .position(|m| *m == state.display_mode)
+                .unwrap_or(0);
+            ui.set_next_item_width(120.0);
+            if ui.combo("###DisplayMode", &mut mode_idx, &DisplayMode::ALL, |m| {
+                m.name().into()
+            }) {
+                state.display_mode = DisplayMode::ALL[mode_idx];
+            }
+            item_help(ui, "viewport.display_mode");
+            ui.same_line();
+            ui.checkbox(tr("viewport.fov_zoom"), &mut state.fov_zoom);
+            item_help(ui, "viewport.fov_zoom");
+            ui.same_line();
+            ui.checkbox(tr("viewport.show_textures"), &mut state.show_textures);
+            item_help(ui, "viewport.show_textures");
             ui.same_line();
             ui.set_next_item_width(150.0);
-            imgui::Drag::new("Camera Speed")
+            imgui::Drag::new(tr("viewport.camera_speed"))
                 .range(1.0, 10000.0)
                 .speed(1.0)
                 .display_format("%.3f")
                 .build(ui, &mut state.camera.speed);
+            item_help(ui, "viewport.camera_speed");
             ui.same_line();
             ui.set_next_item_width(150.0);
-            imgui::Drag::new("Rotation Speed")
+            imgui::Drag::new(tr("viewport.rotation_speed"))
                 .range(0.1, 100.0)
                 .speed(0.5)
                 .display_format("%.3f")
                 .build(ui, &mut state.rotation_speed);
+            item_help(ui, "viewport.rotation_speed");
             imgui::Image::new(imgui::TextureId::new(texture.try_into().unwrap()), tex_size)
                 // flip the image vertically
                 .uv0([0.0, 1.0])
@@ -614,9 +5487,100 @@ fn draw_viewport(ui: &imgui::Ui, state: &mut State, texture: u32) {
 
             // only allow capturing the cursor if the mouse is over the viewport
             state.can_capture_cursor = ui.is_item_hovered();
+            let image_min = ui.item_rect_min();
+
+            draw_debug_labels(ui, state, image_min, tex_size);
+            draw_scale_bar(ui, state, image_min, tex_size);
+            if state.show_annotations {
+                draw_annotation_labels(ui, state, image_min, tex_size);
+            }
+            draw_annotation_popup(ui, state);
+            if state.can_capture_cursor {
+                draw_inspected_face(ui, state);
+                draw_hovered_mesh(ui, state);
+            }
+
+            state.viewport_cursor = if state.can_capture_cursor {
+                let mouse_pos = ui.io().mouse_pos;
+                Some([
+                    (mouse_pos[0] - image_min[0]) / tex_size[0],
+                    (mouse_pos[1] - image_min[1]) / tex_size[1],
+                ])
+            } else {
+                None
+            };
         });
 }
 
+/// Names the mesh the cursor rests on and its model
+fn draw_hovered_mesh(ui: &imgui::Ui, state: &State) {
+    let Some((id, mesh)) = state.hover.as_ref().and_then(|hover| hover.mesh) else {
+        return;
+    };
+    let Some(model) = state.objects.iter().find(|m| m.id == id) else {
+        return;
+    };
+    let Some(mesh) = model.meshes.get(mesh) else {
+        return;
+    };
+
+    ui.tooltip(|| {
+        ui.text(&mesh.name);
+        ui.text_disabled(&model.name);
+    });
+}
+
+/// Shows the vertices and material of the triangle under the cursor
+fn draw_inspected_face(ui: &imgui::Ui, state: &State) {
+    let Some(face) = &state.inspected_face else {
+        return;
+    };
+    let Some(model) = state.objects.iter().find(|m| m.id == face.model_id) else {
+        return;
+    };
+    let mesh = &model.meshes[face.mesh];
+    let vec3 = |v: glm::Vec3| format!("{:.4}, {:.4}, {:.4}", v.x, v.y, v.z);
+
+    ui.tooltip(|| {
+        ui.text(format!("{} / {}", model.name, mesh.name));
+        ui.text_disabled(format!("{} {}", tr("inspect.triangle"), face.triangle));
+        ui.separator();
+
+        for (i, vertex) in face.vertices.iter().enumerate() {
+            ui.text(format!(
+                "{} {}",
+                tr("inspect.vertex"),
+                mesh.indices[face.triangle * 3 + i]
+            ));
+            ui.text(format!(
+                "  {}: {}",
+                tr("inspect.position"),
+                vec3(vertex.position)
+            ));
+            ui.text(format!(
+                "  {}: {}",
+                tr("inspect.normal"),
+                vec3(vertex.normal)
+            ));
+            // the vertex is packed, copy the field out before formatting it
+            let uv = vertex.tex_coords;
+            ui.text(format!("  UV: {:.4}, {:.4}", uv.x, uv.y));
+        }
+        ui.separator();
+
+        let material = mesh.material_at(face.triangle);
+        ui.text(format!("{}: {}", tr("inspect.material"), material.name));
+        ui.text(format!(
+            "  {}: {}",
+            tr("inspect.diffuse"),
+            vec3(material.diffuse_color)
+        ));
+        for texture in &material.textures {
+            ui.text(format!("  {:?}: {}", texture.typ, texture.name));
+        }
+    });
+}
+
 pub fn draw_ui(
     imgui: &mut imgui::Context,
     renderer: &imgui_opengl_renderer::Renderer,
@@ -630,6 +5594,14 @@ pub fn draw_ui(
         .prepare_frame(imgui.io_mut(), window)
         .expect("Failed to prepare imgui frame");
 
+    if state.theme_dirty {
+        state
+            .settings
+            .theme
+            .apply(imgui.style_mut(), state.settings.accent_color);
+        state.theme_dirty = false;
+    }
+
     let ui = imgui.new_frame();
     create_initial_docking(ui, state);
 
@@ -637,10 +5609,27 @@ pub fn draw_ui(
 
     draw_viewport(ui, state, scene_fb_texture);
     draw_objects_window(ui, state);
+    draw_annotations_window(ui, state);
     draw_console(ui, state);
+    draw_open_url_window(ui, state);
+    draw_heightmap_import_window(ui, state);
+    draw_import_options_window(ui, state);
+    draw_part_picker_window(ui, state);
+    draw_online_window(ui, state);
+    draw_camera_path_window(ui, state);
+    draw_note_editor(ui, state);
+    draw_library_window(ui, state);
+    draw_slicing_window(ui, state);
+    draw_materials_window(ui, state);
+    draw_measurements_window(ui, state);
+    draw_script_window(ui, state);
+    draw_status_bar(ui, state);
     draw_about_window(ui, state);
     draw_keybinds_window(ui, state);
     draw_settings_window(ui, state);
+    draw_crash_report_window(ui, state);
+    draw_markup_window(ui, state);
+    ui::notifications::draw(ui, state);
 
     ui.end_frame_early();
 
@@ -656,4 +5645,8 @@ pub fn draw_ui(
 
     renderer.render(imgui);
     state.first_frame_drawn = true;
+
+    if let Some(path) = state.window_capture_request.take() {
+        capture_window(state, window, &path);
+    }
 }
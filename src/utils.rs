@@ -1,59 +1,198 @@
-use std::path::PathBuf;
-use std::str::FromStr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use ::log::{error, info};
+use ::log::{error, info, warn};
 use anyhow::{Context, Result};
 use glad_gl::gl;
 use glm;
 
-use crate::{importer, model, ui};
+use crate::{
+    camera, crash, debug_draw, gl_state,
+    importer::{self, options::ImportOptions},
+    ipc, model, scale,
+    shader::Shader,
+    texture,
+    ui::{self, i18n::tr, notifications::Notification},
+};
 
-pub enum SupportedFileExtensions {
-    OBJ,
-    STL,
-}
-
-impl std::str::FromStr for SupportedFileExtensions {
-    type Err = String;
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_ascii_lowercase().as_str() {
-            "obj" => Ok(Self::OBJ),
-            "stl" => Ok(Self::STL),
-            _ => Err(format!("Unsupported file extension: {}", s)),
-        }
+/// Reads and decodes a texture image. Doesn't touch the GPU so it's safe
+/// to call from the importer threads.
+pub fn decode_texture(path: PathBuf) -> Result<image::DynamicImage> {
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ktx2"))
+    {
+        let bytes = std::fs::read(&path)
+            .with_context(|| format!("Failed to open texture file: {:?}", path))?;
+        return decode_ktx2(&bytes)
+            .with_context(|| format!("Failed to decode texture: {:?}", path));
     }
-}
 
-pub fn load_texture(path: PathBuf) -> Result<u32> {
     let tex = image::io::Reader::open(path.clone())
         .with_context(|| format!("Failed to open texture file: {:?}", path))?
         .decode()
         .with_context(|| format!("Failed to decode texture: {:?}", path))?;
 
+    Ok(tex)
+}
+
+/// Decodes a texture that's embedded in a model file. The format is guessed
+/// from the data itself.
+pub fn decode_texture_from_memory(bytes: &[u8]) -> Result<image::DynamicImage> {
+    if bytes.starts_with(&KTX2_IDENTIFIER) {
+        return decode_ktx2(bytes);
+    }
+
+    image::load_from_memory(bytes).context("Failed to decode embedded texture")
+}
+
+/// Decodes a texture from a base64 `data:` URI
+pub fn decode_data_uri(uri: &str) -> Result<image::DynamicImage> {
+    use base64::Engine;
+
+    let (header, data) = uri
+        .strip_prefix("data:")
+        .and_then(|uri| uri.split_once(','))
+        .context("Malformed data URI")?;
+    if !header.ends_with(";base64") {
+        anyhow::bail!("Only base64 encoded data URIs are supported");
+    }
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .context("Failed to decode base64 data URI")?;
+
+    decode_texture_from_memory(&bytes)
+}
+
+/// Decodes the base level of a KTX2 container. Only uncompressed 8-bit
+/// formats without supercompression are supported, Basis Universal and
+/// block compressed textures need a transcoder we don't have.
+fn decode_ktx2(bytes: &[u8]) -> Result<image::DynamicImage> {
+    // https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html
+    const VK_FORMAT_R8_UNORM: u32 = 9;
+    const VK_FORMAT_R8_SRGB: u32 = 15;
+    const VK_FORMAT_R8G8_UNORM: u32 = 16;
+    const VK_FORMAT_R8G8_SRGB: u32 = 22;
+    const VK_FORMAT_R8G8B8_UNORM: u32 = 23;
+    const VK_FORMAT_R8G8B8_SRGB: u32 = 29;
+    const VK_FORMAT_R8G8B8A8_UNORM: u32 = 37;
+    const VK_FORMAT_R8G8B8A8_SRGB: u32 = 43;
+
+    let read_u32 = |offset: usize| -> Result<u32> {
+        let end = offset.checked_add(4).context("Truncated KTX2 file")?;
+        let b = bytes.get(offset..end).context("Truncated KTX2 file")?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let read_u64 = |offset: usize| -> Result<usize> {
+        let value = read_u32(offset)? as u64 | (read_u32(offset + 4)? as u64) << 32;
+        usize::try_from(value).context("KTX2 level doesn't fit in memory")
+    };
+
+    if !bytes.starts_with(&KTX2_IDENTIFIER) {
+        anyhow::bail!("Not a KTX2 file");
+    }
+
+    let vk_format = read_u32(12)?;
+    let width = read_u32(20)?;
+    let height = read_u32(24)?;
+    let supercompression = read_u32(44)?;
+    if supercompression != 0 {
+        anyhow::bail!("Supercompressed KTX2 textures aren't supported");
+    }
+
+    // the level index starts right after the header, level 0 is the full size image
+    let offset = read_u64(80)?;
+    let length = read_u64(88)?;
+    let end = offset
+        .checked_add(length)
+        .context("KTX2 level is out of range")?;
+    let data = bytes
+        .get(offset..end)
+        .context("Truncated KTX2 file")?
+        .to_vec();
+
+    let image = match vk_format {
+        VK_FORMAT_R8_UNORM | VK_FORMAT_R8_SRGB => {
+            image::GrayImage::from_raw(width, height, data).map(image::DynamicImage::ImageLuma8)
+        }
+        VK_FORMAT_R8G8_UNORM | VK_FORMAT_R8G8_SRGB => {
+            image::GrayAlphaImage::from_raw(width, height, data)
+                .map(image::DynamicImage::ImageLumaA8)
+        }
+        VK_FORMAT_R8G8B8_UNORM | VK_FORMAT_R8G8B8_SRGB => {
+            image::RgbImage::from_raw(width, height, data).map(image::DynamicImage::ImageRgb8)
+        }
+        VK_FORMAT_R8G8B8A8_UNORM | VK_FORMAT_R8G8B8A8_SRGB => {
+            image::RgbaImage::from_raw(width, height, data).map(image::DynamicImage::ImageRgba8)
+        }
+        _ => anyhow::bail!("Unsupported KTX2 format: {}", vk_format),
+    };
+
+    image.context("KTX2 image data doesn't match its size")
+}
+
+pub fn upload_texture(
+    tex: &image::DynamicImage,
+    sampling: texture::Sampling,
+) -> crate::error::Result<u32> {
     let mut texture_id: u32 = 0;
-    let format = match tex.color().channel_count() {
-        1 => gl::RED,
-        2 => gl::RG,
-        3 => gl::RGB,
-        4 => gl::RGBA,
-        _ => panic!("Unknown image format"),
+    unsafe {
+        gl::GenTextures(1, &mut texture_id);
+    }
+    if let Err(e) = replace_texture_image(texture_id, tex, sampling) {
+        gl_state::delete_textures(&[texture_id]);
+        return Err(e);
+    }
+
+    Ok(texture_id)
+}
+
+/// Uploads a new image into an existing texture, the materials using it
+/// don't have to change. Images with more than 8 bits per channel are
+/// uploaded with 8.
+pub fn replace_texture_image(
+    texture_id: u32,
+    tex: &image::DynamicImage,
+    sampling: texture::Sampling,
+) -> crate::error::Result<()> {
+    use image::ColorType;
+
+    let converted;
+    let (format, tex) = match tex.color() {
+        ColorType::L8 => (gl::RED, tex),
+        ColorType::La8 => (gl::RG, tex),
+        ColorType::Rgb8 => (gl::RGB, tex),
+        ColorType::Rgba8 => (gl::RGBA, tex),
+        ColorType::L16
+        | ColorType::La16
+        | ColorType::Rgb16
+        | ColorType::Rgba16
+        | ColorType::Rgb32F
+        | ColorType::Rgba32F => {
+            converted = image::DynamicImage::ImageRgba8(tex.to_rgba8());
+            (gl::RGBA, &converted)
+        }
+        other => {
+            return Err(crate::error::Error::Invalid(format!(
+                "Unsupported texture format {:?}",
+                other
+            )))
+        }
     };
 
     unsafe {
         // set alignment to 1 since we use u8 for the pixel data type
         gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
 
-        gl::GenTextures(1, &mut texture_id);
-        gl::BindTexture(gl::TEXTURE_2D, texture_id);
-
-        gl::TexParameteri(
-            gl::TEXTURE_2D,
-            gl::TEXTURE_MIN_FILTER,
-            gl::LINEAR_MIPMAP_LINEAR as i32,
-        );
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        gl_state::bind_texture(0, texture_id);
 
+        // the mipmaps are generated from the base level
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_BASE_LEVEL, 0);
         gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
         gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
 
@@ -73,7 +212,192 @@ pub fn load_texture(path: PathBuf) -> Result<u32> {
         gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
     }
 
-    Ok(texture_id)
+    texture::apply_sampling(texture_id, sampling);
+    Ok(())
+}
+
+/// Sets up the scene's fixed lights on the mesh shader
+pub fn set_light_uniforms(shader: &Shader) {
+    let points_lights: [glm::Vec3; 4] = [
+        glm::vec3(0.7, 0.2, 2.0),
+        glm::vec3(2.3, -3.3, -4.0),
+        glm::vec3(-4.0, 2.0, -12.0),
+        glm::vec3(0.0, 0.0, -3.0),
+    ];
+
+    shader.use_shader();
+
+    for (i, point_light) in points_lights.iter().enumerate() {
+        shader.set_3fv(&format!("pointLights[{}].position", i), *point_light);
+
+        shader.set_float(&format!("pointLights[{}].constant", i), 1.0);
+        shader.set_float(&format!("pointLights[{}].linear", i), 0.09);
+        shader.set_float(&format!("pointLights[{}].quadratic", i), 0.032);
+
+        shader.set_3fv(
+            &format!("pointLights[{}].ambient", i),
+            glm::vec3(0.1, 0.1, 0.1),
+        );
+        shader.set_3fv(
+            &format!("pointLights[{}].diffuse", i),
+            glm::vec3(0.7, 0.7, 0.7),
+        );
+        shader.set_3fv(
+            &format!("pointLights[{}].specular", i),
+            glm::vec3(1.0, 1.0, 1.0),
+        );
+    }
+    shader.set_float("spotLight.cutOff", glm::cos(glm::radians(12.5)));
+    shader.set_float("spotLight.outerCutOff", glm::cos(glm::radians(15.0)));
+    shader.set_3fv("spotLight.ambient", glm::vec3(0.2, 0.2, 0.2));
+    shader.set_3fv("spotLight.diffuse", glm::vec3(0.5, 0.5, 0.5));
+    shader.set_3fv("spotLight.specular", glm::vec3(1.0, 1.0, 1.0));
+    shader.set_float("spotLight.constant", 1.0);
+    shader.set_float("spotLight.linear", 0.09);
+    shader.set_float("spotLight.quadratic", 0.032);
+
+    shader.set_3fv("dirLight.direction", glm::vec3(-0.2, -1.0, -0.3));
+    shader.set_3fv("dirLight.ambient", glm::vec3(0.3, 0.3, 0.3));
+    shader.set_3fv("dirLight.diffuse", glm::vec3(1.0, 1.0, 1.0));
+    shader.set_3fv("dirLight.specular", glm::vec3(1.0, 1.0, 1.0));
+}
+
+/// Opens the given path with the platform's default handler.
+/// Directories are opened in the file manager.
+pub fn open_path(path: &Path) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let program = "xdg-open";
+
+    std::process::Command::new(program)
+        .arg(path)
+        .spawn()
+        .with_context(|| format!("Failed to open {:?} with {}", path, program))?;
+
+    Ok(())
+}
+
+/// Puts the PNG image at `path` on the clipboard with the platform's own tools
+pub fn copy_image_to_clipboard(path: &Path) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = std::process::Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-STA",
+            "-Command",
+            &format!(
+                "Add-Type -AssemblyName System.Windows.Forms; \
+                 [System.Windows.Forms.Clipboard]::SetImage([System.Drawing.Image]::FromFile('{}'))",
+                path.display()
+            ),
+        ]);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = std::process::Command::new("osascript");
+        command.args([
+            "-e",
+            &format!(
+                "set the clipboard to (read (POSIX file \"{}\") as «class PNGf»)",
+                path.display()
+            ),
+        ]);
+        command
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut command = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = std::process::Command::new("wl-copy");
+        command
+            .args(["--type", "image/png"])
+            .stdin(std::fs::File::open(path)?);
+        command
+    } else {
+        let mut command = std::process::Command::new("xclip");
+        command
+            .args(["-selection", "clipboard", "-t", "image/png", "-i"])
+            .arg(path);
+        command
+    };
+
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run {:?}", command.get_program()))?;
+    anyhow::ensure!(
+        status.success(),
+        "{:?} exited with {}",
+        command.get_program(),
+        status
+    );
+
+    Ok(())
+}
+
+/// Path for a new capture of `size` pixels with the `extension`, in the
+/// capture folder of the settings and named after their template. A number is
+/// added to names that are taken.
+pub fn capture_path(state: &ui::ui::State, size: [f32; 2], extension: &str) -> PathBuf {
+    let dir = state.settings.capture_dir.clone().unwrap_or_default();
+    let model = state
+        .active_model()
+        .and_then(|id| state.objects.iter().find(|m| m.id == id))
+        .map_or("scene", |m| m.name.as_str());
+    let size = [size[0].max(0.0) as u32, size[1].max(0.0) as u32];
+    let name = expand_capture_template(&state.settings.capture_name, model, size);
+
+    let mut path = dir.join(format!("{}.{}", name, extension));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}-{}.{}", name, n, extension));
+        n += 1;
+    }
+    path
+}
+
+/// Fills in the tokens of a capture name template: {model}, {date}, {time},
+/// {timestamp}, {width} and {height}. Characters that can't be in file
+/// names are replaced.
+pub fn expand_capture_template(template: &str, model: &str, size: [u32; 2]) -> String {
+    let now = time::OffsetDateTime::now_local().unwrap_or_else(|_| time::OffsetDateTime::now_utc());
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Current time to not be before the UNIX epoch");
+
+    let name = template
+        .replace("{model}", model)
+        .replace(
+            "{date}",
+            &now.format(time::macros::format_description!("[year]-[month]-[day]"))
+                .unwrap_or_default(),
+        )
+        .replace(
+            "{time}",
+            &now.format(time::macros::format_description!(
+                "[hour]-[minute]-[second]"
+            ))
+            .unwrap_or_default(),
+        )
+        .replace("{timestamp}", &timestamp.as_secs().to_string())
+        .replace("{width}", &size[0].to_string())
+        .replace("{height}", &size[1].to_string());
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    if name.trim().is_empty() {
+        format!("capture-{}", timestamp.as_secs())
+    } else {
+        name
+    }
 }
 
 pub fn mat_ident() -> glm::Mat4 {
@@ -82,55 +406,525 @@ pub fn mat_ident() -> glm::Mat4 {
     )
 }
 
-pub fn import_models_from_paths(paths: &Vec<PathBuf>, state: &mut ui::ui::State) {
+/// Imports the supported files among `paths` with the options remembered
+/// for their extension, or the default ones
+pub fn import_models_from_paths(paths: &[PathBuf], state: &mut ui::ui::State) {
+    for model_path in supported_paths(paths) {
+        let options = state.settings.import_options_for(&model_path);
+        start_import(model_path, options, state);
+    }
+}
+
+/// Like `import_models_from_paths`, but asks for the import options of the
+/// files whose extension has none remembered
+pub fn ask_import_options(paths: &[PathBuf], state: &mut ui::ui::State) {
+    for model_path in supported_paths(paths) {
+        match ImportOptions::remembered(&state.settings.import_options, &model_path) {
+            Some(options) => start_import(model_path, options, state),
+            None => state.import_prompts.push(ui::ui::ImportPrompt {
+                options: state.settings.default_import_options(&model_path),
+                path: model_path,
+                remember: false,
+            }),
+        }
+    }
+}
+
+/// Imports a model, files with several objects are scanned first so the
+/// ones to import can be picked when that's turned on
+pub fn start_import(model_path: PathBuf, options: ImportOptions, state: &mut ui::ui::State) {
+    let has_parts = importer::registry::detect(&model_path)
+        .ok()
+        .flatten()
+        .is_some_and(|importer| importer.has_parts());
+    if has_parts && state.settings.pick_parts {
+        state.part_scans.push(importer::PartScan::spawn(
+            model_path,
+            !state.skip_textures,
+            options,
+        ));
+        return;
+    }
+    state.import_jobs.push(importer::ImportJob::spawn(
+        model_path,
+        !state.skip_textures,
+        options,
+    ));
+}
+
+/// Imports the files and downloads the models another instance passed on,
+/// with whether each one is being imported or why it isn't. Files that fail
+/// while importing show their error in this window like any other.
+pub fn import_forwarded(message: &ipc::Message, state: &mut ui::ui::State) -> ipc::Reply {
+    let mut files = vec![];
+    for path in &message.paths {
+        let error = skip_reason(path);
+        if error.is_none() {
+            let options = state.settings.import_options_for(path);
+            start_import(path.clone(), options, state);
+        }
+        files.push(ipc::FileResult {
+            name: path.display().to_string(),
+            error,
+        });
+    }
+    for url in &message.urls {
+        let error = url_skip_reason(url);
+        if error.is_none() {
+            import_model_from_url(url, state);
+        }
+        files.push(ipc::FileResult {
+            name: url.clone(),
+            error,
+        });
+    }
+
+    ipc::Reply { files }
+}
+
+/// Why a file can't be imported, None if an importer can read it. Files
+/// without a known extension are told apart by their contents.
+fn skip_reason(path: &Path) -> Option<String> {
+    if path.is_dir() {
+        return Some("it is a directory".to_string());
+    }
+    match importer::registry::detect(path) {
+        Ok(Some(_)) => None,
+        Ok(None) => Some(format!(
+            "it is not an {} file",
+            importer::registry::format_names()
+        )),
+        Err(e) => Some(format!("it can't be read: {}", e)),
+    }
+}
+
+fn url_skip_reason(url: &str) -> Option<String> {
+    let name = importer::download::file_name(url);
+    (!importer::registry::is_supported(Path::new(name))).then(|| {
+        format!(
+            "it doesn't point to an {} file",
+            importer::registry::format_names()
+        )
+    })
+}
+
+/// Leaves out directories and files that no importer can read
+fn supported_paths(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut supported = vec![];
     for model_path in paths {
-        let filename = model_path.file_name();
-        if model_path.is_dir() {
-            info!(
-                "Skipping directory \"{}\"",
-                filename.unwrap().to_str().unwrap()
+        if let Some(reason) = skip_reason(model_path) {
+            info!("Skipping {:?} because {}", model_path, reason);
+            continue;
+        }
+        supported.push(model_path.clone());
+    }
+    supported
+}
+
+/// Keeps the textures of models that aren't being drawn at a lower
+/// resolution when the setting is on, and brings them back once they are
+pub fn update_texture_residency(state: &mut ui::ui::State) {
+    for i in 0..state.objects.len() {
+        let model = &state.objects[i];
+        let inactive = !model.visible || !state.is_selected(model.id);
+        let low_res = state.settings.low_res_inactive_textures && inactive;
+
+        state.objects[i].set_low_res_textures(low_res);
+    }
+}
+
+/// Whether glClipControl is available, which reversed-Z depth needs. It's core
+/// in OpenGL 4.5 and an extension before that.
+pub fn supports_clip_control() -> bool {
+    gl_version() >= (4, 5) || has_gl_extension("GL_ARB_clip_control")
+}
+
+/// The version of the current context
+pub fn gl_version() -> (i32, i32) {
+    let (mut major, mut minor) = (0, 0);
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    }
+    (major, minor)
+}
+
+/// Whether the current context has the extension called `name`
+pub fn has_gl_extension(name: &str) -> bool {
+    unsafe {
+        let mut count = 0;
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+        (0..count as u32).any(|i| {
+            let extension = gl::GetStringi(gl::EXTENSIONS, i);
+            !extension.is_null()
+                && std::ffi::CStr::from_ptr(extension as *const std::ffi::c_char).to_bytes()
+                    == name.as_bytes()
+        })
+    }
+}
+
+/// Switches depth testing between the regular -1 to 1 depth range and
+/// reversed-Z, where the near plane is at 1 and the far plane at 0. Only call
+/// this if `supports_clip_control` is true.
+pub fn set_reversed_z(enabled: bool) {
+    unsafe {
+        if enabled {
+            gl::ClipControl(gl::LOWER_LEFT, gl::ZERO_TO_ONE);
+            gl::DepthFunc(gl::GREATER);
+            gl::ClearDepth(0.0);
+        } else {
+            gl::ClipControl(gl::LOWER_LEFT, gl::NEGATIVE_ONE_TO_ONE);
+            gl::DepthFunc(gl::LESS);
+            gl::ClearDepth(1.0);
+        }
+    }
+}
+
+/// The world space bounds of the displayed models, `None` if there are none
+pub fn displayed_bounds(state: &ui::ui::State) -> Option<(glm::Vec3, glm::Vec3)> {
+    let mut min = glm::vec3(f32::MAX, f32::MAX, f32::MAX);
+    let mut max = glm::vec3(f32::MIN, f32::MIN, f32::MIN);
+    for model in state
+        .objects
+        .iter()
+        .filter(|m| m.visible && !m.evicted && state.selection.contains(&m.id))
+    {
+        let aabb = model.world_aabb();
+        min = glm::min(min, aabb.min);
+        max = glm::max(max, aabb.max);
+    }
+
+    (min.x <= max.x).then_some((min, max))
+}
+
+/// Derives the camera's clip planes from the bounds of the displayed models
+/// if automatic clip planes are enabled
+pub fn update_clip_planes(state: &mut ui::ui::State) {
+    if !state.settings.auto_clip_planes {
+        state.camera.near = camera::NEAR_PLANE;
+        return;
+    }
+
+    // nothing to fit, keep the far plane where it is
+    let Some((min, max)) = displayed_bounds(state) else {
+        state.camera.near = camera::NEAR_PLANE;
+        return;
+    };
+
+    let reversed_z = state.reversed_z();
+    state
+        .camera
+        .fit_clip_planes((min + max) * 0.5, glm::length(max - min) / 2.0, reversed_z);
+}
+
+/// Adds the overlays of the displayed models to the debug draw for this frame
+pub fn queue_debug_geometry(state: &mut ui::ui::State) {
+    let bounds = displayed_bounds(state);
+    let grid_labels = if state.draw_grid && state.show_grid_labels {
+        scale::grid_labels(state)
+    } else {
+        vec![]
+    };
+    let debug = &mut state.debug_draw;
+    debug.labels.extend(grid_labels);
+
+    if let Some(bounds) = bounds {
+        state.slicing.draw_plane(bounds, debug);
+    }
+
+    for model in state
+        .objects
+        .iter()
+        .filter(|m| m.visible && state.selection.contains(&m.id))
+    {
+        if state.draw_aabb {
+            model.draw_aabb(debug);
+        }
+        if state.show_normals {
+            model.draw_normals(debug);
+        }
+        if let Some(analysis) = &model.topology {
+            analysis.draw(model, debug);
+        }
+        if let Some(bounds) = &model.bounds {
+            bounds.draw(model, debug);
+        }
+        if let Some(balance) = &model.balance {
+            balance.draw(model, debug);
+        }
+        for measurement in &model.measurements {
+            measurement.draw(model, debug);
+        }
+        if let Some(placement) = state.measuring.as_ref().filter(|p| p.model_id == model.id) {
+            placement.draw(model, debug);
+        }
+    }
+
+    // the path would be in the way while flying along it
+    let path = &state.camera_path;
+    if state.show_camera_path && !path.playing && path.export.is_none() {
+        let color = glm::vec3(1.0, 0.9, 0.3);
+        for pair in path.preview_points().windows(2) {
+            debug.line(pair[0], pair[1], color);
+        }
+        for keyframe in &path.keyframes {
+            debug.point(keyframe.position, 0.3, color);
+            debug.line(
+                keyframe.position,
+                keyframe.position + keyframe.front,
+                debug_draw::RED,
+            );
+        }
+    }
+
+    if let Some(face) = &state.inspected_face {
+        face.draw(debug);
+    }
+
+    let active = state
+        .selection
+        .last()
+        .and_then(|id| state.objects.iter().find(|m| m.id == *id));
+    if let Some(model) = active.filter(|m| m.visible) {
+        if let Some(pivot) = model.custom_pivot {
+            let size = model.world_size();
+            debug.axes(
+                model.local_to_world(pivot),
+                size.x.max(size.y).max(size.z) * 0.15,
             );
+        }
+    }
+}
+
+/// Loads evicted models back in once they're selected, and evicts the least
+/// recently used models while the loaded ones take up more than the memory budget
+pub fn update_model_residency(state: &mut ui::ui::State) {
+    let now = std::time::Instant::now();
+    for i in 0..state.objects.len() {
+        let id = state.objects[i].id;
+        if !state.is_selected(id) {
             continue;
         }
-        match model_path.extension() {
-            Some(ext) => {
-                if SupportedFileExtensions::from_str(ext.to_str().unwrap()).is_err() {
-                    info!(
-                        "Skipping file \"{}\" because it is not an OBJ or STL file",
-                        filename.unwrap().to_str().unwrap()
-                    );
-                    continue;
-                }
+        state.objects[i].last_used = now;
+
+        let model = &state.objects[i];
+        let reloading = state.import_jobs.iter().any(|j| j.reload == Some(id));
+        if model.evicted && !reloading {
+            if let Some(path) = &model.source_path {
+                info!("Loading \"{}\" back in", model.name);
+                state.import_jobs.push(importer::ImportJob::spawn_reload(
+                    path.clone(),
+                    id,
+                    model.heightmap,
+                    model.skipped_textures.is_none(),
+                    model.parts.clone(),
+                    model.import_options,
+                ));
+            }
+        }
+    }
+
+    let Some(budget) = state.settings.memory_budget else {
+        return;
+    };
+    let budget = budget as usize * 1024 * 1024;
+    let mut used: usize = state.objects.iter().map(|m| m.mem_usage).sum();
+    if used <= budget {
+        return;
+    }
+
+    // models without a file can't be loaded back in
+    let mut candidates: Vec<usize> = (0..state.objects.len())
+        .filter(|&i| {
+            let model = &state.objects[i];
+            !model.evicted
+                && model.source_path.is_some()
+                && !state.is_selected(model.id)
+                && !state.import_jobs.iter().any(|j| j.reload == Some(model.id))
+        })
+        .collect();
+    candidates.sort_by_key(|&i| state.objects[i].last_used);
+
+    for i in candidates {
+        if used <= budget {
+            break;
+        }
+
+        let model = &mut state.objects[i];
+        used -= model.mem_usage;
+        model.evict();
+        used += model.mem_usage;
+        info!(
+            "Unloaded \"{}\" to stay within the memory budget",
+            model.name
+        );
+    }
+}
+
+/// Downloads a model and imports it once it's done
+pub fn import_model_from_url(url: &str, state: &mut ui::ui::State) {
+    if let Some(reason) = url_skip_reason(url) {
+        warn!("Skipping URL \"{}\" because {}", url, reason);
+        return;
+    }
+
+    info!("Downloading {}", url);
+    // the options of the format the file is saved as
+    let options = state
+        .settings
+        .import_options_for(&importer::download::cache_path(url));
+    state.import_jobs.push(importer::ImportJob::spawn_url(
+        url.to_string(),
+        !state.skip_textures,
+        options,
+    ));
+}
+
+/// Turns the models of finished import jobs into `Model`s and uploads the
+/// textures that finished decoding. Must be called from the main thread
+/// since it uploads the meshes to the GPU.
+pub fn poll_import_jobs(state: &mut ui::ui::State) {
+    let sampling = state.settings.texture_sampling();
+    let mut missing_textures = vec![];
+    for model in &mut state.objects {
+        for name in model.poll_textures(sampling) {
+            missing_textures.push((model.name.clone(), name));
+        }
+        model.poll_mass_properties();
+        model.poll_bounds();
+    }
+    for (model, texture) in missing_textures {
+        let file = Path::new(&texture)
+            .file_name()
+            .map_or(texture.clone(), |f| f.to_string_lossy().into_owned());
+        state.notify(
+            Notification::warning(format!("{} {}", tr("notification.texture_missing"), file))
+                .with_text(model),
+        );
+    }
+
+    let mut i = 0;
+    while i < state.part_scans.len() {
+        let Some(result) = state.part_scans[i].poll() else {
+            i += 1;
+            continue;
+        };
+        let scan = state.part_scans.remove(i);
+        match result {
+            // nothing to pick from, the file is imported like any other
+            Ok(parts) if parts.len() <= 1 => {
+                state.import_jobs.push(importer::ImportJob::spawn(
+                    scan.path,
+                    scan.load_textures,
+                    scan.options,
+                ));
             }
+            Ok(parts) => state.part_picks.push(ui::ui::PartPick {
+                path: scan.path,
+                load_textures: scan.load_textures,
+                options: scan.options,
+                selected: vec![true; parts.len()],
+                parts,
+            }),
+            Err(e) => {
+                error!("Error reading the objects of {:?}: {}", scan.path, e);
+                state.notify_import_error(&scan.path, e);
+            }
+        }
+    }
+
+    let mut i = 0;
+    while i < state.import_jobs.len() {
+        if state.import_jobs[i].is_cancelled() {
+            let job = state.import_jobs.remove(i);
+            info!("Cancelled import of \"{}\"", job.name);
+            continue;
+        }
+
+        let obj_result = match state.import_jobs[i].poll() {
+            Some(result) => result,
             None => {
-                info!(
-                    "Skipping file \"{}\" because it has no extension",
-                    filename.unwrap().to_str().unwrap()
-                );
+                i += 1;
                 continue;
             }
-        }
-        let obj_result = importer::load_from_file(model_path);
+        };
+        let job = state.import_jobs.remove(i);
+
         match obj_result {
             Ok(obj) => {
+                let material_libs = obj.material_libs.clone();
                 let mut m = model::Model::new(obj, state);
+                if !job.load_textures && !material_libs.is_empty() {
+                    m.skipped_textures = Some(material_libs);
+                }
 
-                state.active_model = Some(m.id);
-                if let Some(model_name) = filename {
-                    info!("Loaded model \"{}\"", model_name.to_str().unwrap());
-                    m.name = model_name.to_str().unwrap().to_string();
+                if let Some(id) = job.reload {
+                    match state.objects.iter_mut().find(|m| m.id == id) {
+                        Some(model) => {
+                            model.replace_geometry(m);
+                            info!(
+                                "Reloaded model \"{}\" in {}ms",
+                                model.name,
+                                job.started.elapsed().as_millis()
+                            );
+                        }
+                        None => warn!(
+                            "Model \"{}\" was removed before it finished reloading",
+                            job.name
+                        ),
+                    }
+                    continue;
+                }
+
+                state.selection = vec![m.id];
+                info!(
+                    "Loaded model \"{}\" in {}ms",
+                    job.name,
+                    job.started.elapsed().as_millis()
+                );
+                state.notify(Notification::info(format!(
+                    "{} {} ({:.1}s)",
+                    tr("notification.loaded"),
+                    job.name,
+                    job.started.elapsed().as_secs_f32()
+                )));
+                m.name = job.name;
+                m.source_path = Some(job.path);
+                m.heightmap = job.heightmap;
+                m.parts = job.parts;
+                m.import_options = job.options;
+                let is_stl = m
+                    .source_path
+                    .as_ref()
+                    .and_then(|p| p.extension())
+                    .is_some_and(|e| e.eq_ignore_ascii_case("stl"));
+                if is_stl && m.units.is_none() {
+                    m.units = state.settings.stl_units;
                 }
                 state.objects.push(m);
-                state
-                    .camera
-                    .focus_on_selected_model(state.active_model, &state.objects);
+                crash::set_loaded_models(state.objects.iter().map(|m| m.name.as_str()));
+                state.camera.focus_on_selected_model(
+                    state.active_model(),
+                    &state.objects,
+                    state.viewport_aspect(),
+                );
             }
             Err(e) => {
                 error!(
                     "Error loading model \"{}\": {}",
-                    model_path.to_str().unwrap(),
+                    job.path.to_str().unwrap(),
                     e
                 );
+                state.notify_import_error(&job.path, e);
+                // an evicted model would keep trying to load back in otherwise
+                if let Some(model) = job
+                    .reload
+                    .and_then(|id| state.objects.iter_mut().find(|m| m.id == id))
+                {
+                    if model.evicted {
+                        model.source_path = None;
+                    }
+                }
             }
         }
     }
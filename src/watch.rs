@@ -0,0 +1,98 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use log::{error, info};
+
+use crate::{importer, importer::cache::Stamp, thumbnail};
+
+/// How often the files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const DEFAULT_SIZE: u32 = 512;
+
+/// Handles `watch <model> --output <image> [--size <size>] [--no-textures]`,
+/// rendering the model again every time it or its materials change. It runs
+/// until it's killed.
+pub fn run(args: &[String]) -> Result<()> {
+    let mut input = None;
+    let mut output = None;
+    let mut size = DEFAULT_SIZE;
+    let mut load_textures = true;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" | "-o" => output = args.next().map(PathBuf::from),
+            "--size" => {
+                let value = args.next().context("--size needs a value")?;
+                size = value
+                    .parse()
+                    .with_context(|| format!("Invalid thumbnail size \"{}\"", value))?;
+            }
+            "--no-textures" => load_textures = false,
+            _ if input.is_none() => input = Some(PathBuf::from(arg)),
+            _ => bail!("Unexpected argument \"{}\"", arg),
+        }
+    }
+    let (Some(input), Some(output)) = (input, output) else {
+        bail!("Usage: watch <model> --output <image.png> [--size <size>] [--no-textures]");
+    };
+    if size == 0 {
+        bail!("The thumbnail size must be bigger than 0");
+    }
+
+    let _context = thumbnail::offscreen_context("3dobs watch")?;
+    info!("Watching {:?}, renders go to {:?}", input, output);
+
+    // the files the last render read, along with how they looked then
+    let mut watched: Vec<(PathBuf, Option<Stamp>)> = vec![];
+    loop {
+        let changed = watched.is_empty()
+            || watched
+                .iter()
+                .any(|(path, stamp)| *stamp != Stamp::of(path));
+        if changed && settled(&input) {
+            watched = render(&input, &output, size, load_textures);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Whether the file exists and stayed the same for a poll interval, exporters
+/// often write their files in several steps
+fn settled(path: &Path) -> bool {
+    let before = Stamp::of(path);
+    std::thread::sleep(POLL_INTERVAL);
+    before.is_some() && before == Stamp::of(path)
+}
+
+/// Renders the model and returns the files to watch for the next render. A
+/// failed render still watches the model, so fixing it renders again.
+fn render(
+    input: &Path,
+    output: &Path,
+    size: u32,
+    load_textures: bool,
+) -> Vec<(PathBuf, Option<Stamp>)> {
+    let mut files = vec![input.to_path_buf()];
+    let result = importer::load_from_file(input, load_textures)
+        .map_err(|e| anyhow::anyhow!("Failed to load {:?}: {}", input, e))
+        .and_then(|obj| {
+            files.extend(obj.material_libs.iter().cloned());
+            files.extend(obj.texture_files.values().cloned());
+            thumbnail::render_object(obj, output, size)
+        });
+    match result {
+        Ok(()) => info!("Rendered {:?} to {:?}", input, output),
+        Err(e) => error!("{:#}", e),
+    }
+
+    files
+        .into_iter()
+        .map(|path| {
+            let stamp = Stamp::of(&path);
+            (path, stamp)
+        })
+        .collect()
+}